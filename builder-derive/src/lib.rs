@@ -22,6 +22,15 @@ pub fn derive(input: TokenStream) -> TokenStream {
         unimplemented!()
     };
 
+    // a struct level `#[builder(default)]` fills any unset field from the struct's `Default` implementation.
+    // `#[builder(consuming)]` also generates `build_owned(self)`, which moves the fields out of the builder rather
+    // than cloning them. Both can be given together as `#[builder(default, consuming)]`.
+    let options = match struct_options(&ast.attrs) {
+        std::result::Result::Ok(options) => options,
+        std::result::Result::Err(e) => return e.into_compile_error().into(),
+    };
+    let use_default = options.default;
+
     let builder_fields = fields.iter().map(|f| -> proc_macro2::TokenStream {
         let name = &f.ident;
         let ty = &f.ty;
@@ -77,17 +86,55 @@ pub fn derive(input: TokenStream) -> TokenStream {
         }
     });
 
-    // for when you call Builder::build()
-    let build_fields = fields.iter().map(|f| {
-        let name = &f.ident;
-        if inner_type("Option", &f.ty).is_some() || builder_of(f).is_some() {
-            quote! { #name: self.#name.clone() }
-        } else {
-            quote! {
-              #name: self.#name.clone().ok_or(concat!(stringify!(#name), " is not set"))?
+    // for when you call Builder::build(), cloning each field, or Builder::build_owned(), moving it.
+    let build_fields = |clone: bool| {
+        fields.iter().map(move |f| {
+            let name = &f.ident;
+            let value = if clone {
+                quote! { self.#name.clone() }
+            } else {
+                quote! { self.#name }
+            };
+            if builder_of(f).is_some() {
+                quote! { #name: #value }
+            } else if inner_type("Option", &f.ty).is_some() {
+                if use_default {
+                    quote! { #name: #value.or(default.#name) }
+                } else {
+                    quote! { #name: #value }
+                }
+            } else if use_default {
+                quote! { #name: #value.unwrap_or(default.#name) }
+            } else {
+                quote! {
+                  #name: #value.ok_or(concat!(stringify!(#name), " is not set"))?
+                }
+            }
+        })
+    };
+    let borrowed_fields = build_fields(true);
+
+    let build_default = if use_default {
+        quote! { let default = <#name as std::default::Default>::default(); }
+    } else {
+        quote! {}
+    };
+
+    // build can't take self as chained setters only leave a `&mut` to the builder, so the consuming build has its
+    // own name.
+    let build_owned = if options.consuming {
+        let owned_fields = build_fields(false);
+        quote! {
+            pub fn build_owned(self) -> std::result::Result<#name, std::boxed::Box<dyn std::error::Error>> {
+                #build_default
+                std::result::Result::Ok(#name {
+                    #(#owned_fields,)*
+                })
             }
         }
-    });
+    } else {
+        quote! {}
+    };
 
     let build_empty = fields.iter().map(|f| {
         let name = &f.ident;
@@ -118,11 +165,14 @@ pub fn derive(input: TokenStream) -> TokenStream {
                 #(#methods)*
 
                pub fn build(&self) -> std::result::Result<#name, std::boxed::Box<dyn std::error::Error>> {
+                   #build_default
                    std::result::Result::Ok(#name {
-                       #(#build_fields,)*
+                       #(#borrowed_fields,)*
                     })
                 }
 
+                #build_owned
+
             }
 
             impl #name {
@@ -156,6 +206,54 @@ fn inner_type<'a>(wrapper: &'a str, ty: &'a syn::Type) -> Option<&'a syn::Type>
     std::option::Option::None
 }
 
+/// StructOptions are the flags given in a struct level `#[builder(...)]`.
+#[derive(Default)]
+struct StructOptions {
+    default: bool,
+    consuming: bool,
+}
+
+fn struct_options(attrs: &[syn::Attribute]) -> syn::Result<StructOptions> {
+    let mut options = StructOptions::default();
+    for attr in attrs {
+        let seg = &attr.path.segments;
+        if seg.len() != 1 || seg[0].ident != "builder" {
+            continue;
+        }
+        match attr.parse_meta()? {
+            syn::Meta::List(nvs) if !nvs.nested.is_empty() => {
+                for nested in &nvs.nested {
+                    match nested {
+                        syn::NestedMeta::Meta(syn::Meta::Path(path))
+                            if path.is_ident("default") =>
+                        {
+                            options.default = true
+                        }
+                        syn::NestedMeta::Meta(syn::Meta::Path(path))
+                            if path.is_ident("consuming") =>
+                        {
+                            options.consuming = true
+                        }
+                        _ => {
+                            return std::result::Result::Err(syn::Error::new_spanned(
+                                nested,
+                                "expected `builder(default)` or `builder(consuming)`",
+                            ))
+                        }
+                    }
+                }
+            }
+            meta => {
+                return std::result::Result::Err(syn::Error::new_spanned(
+                    meta,
+                    "expected `builder(default)` or `builder(consuming)`",
+                ))
+            }
+        }
+    }
+    std::result::Result::Ok(options)
+}
+
 fn builder_of(f: &syn::Field) -> Option<&syn::Attribute> {
     for attr in &f.attrs {
         let seg = &attr.path.segments;
@@ -213,7 +311,7 @@ fn extend_method(f: &syn::Field) -> Option<(bool, proc_macro2::TokenStream)> {
                         self
                 }
             };
-            return std::option::Option::Some((*name.as_ref().unwrap() == arg, method));
+            std::option::Option::Some((*name.as_ref().unwrap() == arg, method))
         }
         lit => panic!("expected identifier, found {:?}", lit),
     }
@@ -0,0 +1,60 @@
+use builder_derive::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(consuming)]
+pub struct Layer {
+    name: String,
+    #[builder(each = "pattern")]
+    patterns: Vec<String>,
+    blend: Option<f64>,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(default, consuming)]
+pub struct Material {
+    shininess: f64,
+    label: Option<String>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            shininess: 200.,
+            label: None,
+        }
+    }
+}
+
+fn main() {
+    {
+        let mut builder = Layer::builder();
+        builder
+            .name("top".to_string())
+            .pattern("stripes".to_string())
+            .pattern("rings".to_string());
+        // the borrowing build is still there and leaves the builder to be used again.
+        let borrowed = builder.build().unwrap();
+        let owned = builder.build_owned().unwrap();
+        let want = Layer {
+            name: "top".to_string(),
+            patterns: vec!["stripes".to_string(), "rings".to_string()],
+            blend: None,
+        };
+        assert_eq!(want, borrowed);
+        assert_eq!(want, owned);
+    }
+    {
+        let mut builder = Layer::builder();
+        builder.blend(0.5);
+        assert!(builder.build_owned().is_err());
+    }
+    {
+        let mut builder = Material::builder();
+        builder.label("gold".to_string());
+        let want = Material {
+            shininess: 200.,
+            label: Some("gold".to_string()),
+        };
+        assert_eq!(want, builder.build_owned().unwrap());
+    }
+}
@@ -0,0 +1,47 @@
+use builder_derive::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(default)]
+pub struct Settings {
+    depth: usize,
+    scale: f64,
+    label: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            depth: 5,
+            scale: 1.0,
+            label: Some("default".to_string()),
+        }
+    }
+}
+
+fn main() {
+    {
+        // unset fields fall back to the default implementation.
+        let got = Settings::builder().depth(2).build().unwrap();
+        let want = Settings {
+            depth: 2,
+            scale: 1.0,
+            label: Some("default".to_string()),
+        };
+        assert_eq!(want, got);
+    }
+    {
+        // set fields override the defaults.
+        let got = Settings::builder()
+            .depth(1)
+            .scale(0.5)
+            .label("set".to_string())
+            .build()
+            .unwrap();
+        let want = Settings {
+            depth: 1,
+            scale: 0.5,
+            label: Some("set".to_string()),
+        };
+        assert_eq!(want, got);
+    }
+}
@@ -2,5 +2,7 @@
 fn builder() {
     let t = trybuild::TestCases::new();
     t.pass("tests/builder/happy_path.rs");
+    t.pass("tests/builder/struct-default.rs");
+    t.pass("tests/builder/consuming.rs");
     t.compile_fail("tests/builder/unrecognized-attribute.rs");
 }
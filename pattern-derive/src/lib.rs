@@ -0,0 +1,159 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput};
+
+/// PatternDerive implements `PatternBase` for a pattern in the ray tracer: `set_transformation`,
+/// `inverse_transformation`, `box_clone`, `box_eq` and `as_any`, which are the same for every pattern. The
+/// transformation is kept in the pattern's `transform` and `inverse_transform` fields, so only `local_color_at`
+/// is left to `Pattern`.
+///
+/// A struct level `#[pattern(new)]` also generates
+/// `new(a: impl Into<BoxedPattern>, b: impl Into<BoxedPattern>, transform: Option<Matrix>)` for a pattern of two
+/// colors held in its `a` and `b` fields, filling every other field from its `Default` implementation.
+///
+/// The pattern must be `Clone` and `PartialEq`, and have `transform` and `inverse_transform` fields of type `Matrix`.
+#[proc_macro_derive(PatternDerive, attributes(pattern))]
+pub fn derive(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    let name = &ast.ident;
+
+    let fields = if let syn::Data::Struct(syn::DataStruct {
+        fields: syn::Fields::Named(syn::FieldsNamed { ref named, .. }),
+        ..
+    }) = ast.data
+    {
+        named
+    } else {
+        return syn::Error::new_spanned(
+            &ast.ident,
+            "PatternDerive only works on structs with named fields",
+        )
+        .into_compile_error()
+        .into();
+    };
+
+    for required in ["transform", "inverse_transform"] {
+        if !has_field(fields, required) {
+            return syn::Error::new_spanned(
+                &ast.ident,
+                format!("a pattern needs a `{}` field of type `Matrix`", required),
+            )
+            .into_compile_error()
+            .into();
+        }
+    }
+
+    let constructor = match struct_new(&ast.attrs) {
+        std::result::Result::Ok(false) => quote! {},
+        std::result::Result::Ok(true) => {
+            if !has_field(fields, "a") || !has_field(fields, "b") {
+                return syn::Error::new_spanned(
+                    &ast.ident,
+                    "`pattern(new)` needs `a` and `b` fields for the pattern's two colors",
+                )
+                .into_compile_error()
+                .into();
+            }
+            let others = fields
+                .iter()
+                .filter(|f| {
+                    !f.ident.as_ref().is_some_and(|i| {
+                        i == "a" || i == "b" || i == "transform" || i == "inverse_transform"
+                    })
+                })
+                .map(|f| {
+                    let name = &f.ident;
+                    quote! { #name: std::default::Default::default() }
+                });
+            quote! {
+                impl #name {
+                    pub fn new(
+                        a: impl std::convert::Into<crate::shapes::patterns::BoxedPattern>,
+                        b: impl std::convert::Into<crate::shapes::patterns::BoxedPattern>,
+                        transform: std::option::Option<crate::primatives::matrix::Matrix>,
+                    ) -> Self {
+                        let transform = transform.unwrap_or_default();
+                        Self {
+                            a: a.into(),
+                            b: b.into(),
+                            inverse_transform: transform
+                                .inverse()
+                                .expect("trying to invert a matrix that cannot be inverted"),
+                            transform,
+                            #(#others,)*
+                        }
+                    }
+                }
+            }
+        }
+        std::result::Result::Err(e) => return e.into_compile_error().into(),
+    };
+
+    let expanded = quote! {
+        impl crate::shapes::patterns::PatternBase for #name {
+            fn set_transformation(&mut self, transform: crate::primatives::matrix::Matrix) {
+                self.inverse_transform = transform
+                    .inverse()
+                    .expect("trying to invert a matrix that cannot be inverted");
+                self.transform = transform;
+            }
+
+            fn inverse_transformation(&self) -> &crate::primatives::matrix::Matrix {
+                &self.inverse_transform
+            }
+
+            fn box_clone(&self) -> crate::shapes::patterns::BoxedPattern {
+                std::boxed::Box::new(std::clone::Clone::clone(self))
+            }
+
+            fn box_eq(&self, other: &dyn std::any::Any) -> bool {
+                other.downcast_ref::<Self>().is_some_and(|a| self == a)
+            }
+
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+        }
+
+        #constructor
+    };
+
+    expanded.into()
+}
+
+fn has_field(fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>, name: &str) -> bool {
+    fields
+        .iter()
+        .any(|f| f.ident.as_ref().is_some_and(|i| i == name))
+}
+
+fn struct_new(attrs: &[syn::Attribute]) -> syn::Result<bool> {
+    for attr in attrs {
+        let seg = &attr.path.segments;
+        if seg.len() != 1 || seg[0].ident != "pattern" {
+            continue;
+        }
+        match attr.parse_meta()? {
+            syn::Meta::List(nvs) if nvs.nested.len() == 1 => {
+                if let syn::NestedMeta::Meta(syn::Meta::Path(ref path)) = nvs.nested[0] {
+                    if path.is_ident("new") {
+                        return std::result::Result::Ok(true);
+                    }
+                }
+                return std::result::Result::Err(syn::Error::new_spanned(
+                    nvs,
+                    "expected `pattern(new)`",
+                ));
+            }
+            meta => {
+                return std::result::Result::Err(syn::Error::new_spanned(
+                    meta,
+                    "expected `pattern(new)`",
+                ))
+            }
+        }
+    }
+    std::result::Result::Ok(false)
+}
@@ -0,0 +1,73 @@
+//! Times the canvas walks a render makes: writing every pixel in the order a render fills them, reading them back in
+//! the same order and saving the image. Run with `cargo bench --bench canvas`.
+//! It uses std timing with its own main so it needs no benchmarking crates.
+
+use std::{
+    hint::black_box,
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use ray_tracer::{primatives::color::Color, world::canvas::Canvas};
+
+const WIDTH: usize = 4000;
+const HEIGHT: usize = 3000;
+/// RUNS is how many times each walk is timed, the fastest of which is reported.
+const RUNS: usize = 5;
+
+/// Sink counts the bytes written to it and throws them away, so saving times the canvas rather than a disk.
+struct Sink(usize);
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// fastest runs f RUNS times and prints the shortest time it took.
+fn fastest(name: &str, mut f: impl FnMut()) {
+    let best = (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or(Duration::ZERO);
+    println!("{:<8} {:>10.1?}", name, best);
+}
+
+fn main() {
+    let mut canvas = Canvas::new(WIDTH, HEIGHT);
+    println!("canvas {}x{}, fastest of {} runs", WIDTH, HEIGHT, RUNS);
+
+    fastest("write", || {
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                let color = Color::new(x as f64 / WIDTH as f64, y as f64 / HEIGHT as f64, 0.5);
+                canvas.write_pixel(x, y, color);
+            }
+        }
+    });
+
+    fastest("read", || {
+        let mut total = Color::BLACK;
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                total = total + canvas.pixel_at(x, y).unwrap_or(Color::BLACK);
+            }
+        }
+        black_box(total);
+    });
+
+    fastest("save", || {
+        let mut sink = Sink(0);
+        canvas.save(&mut sink);
+        black_box(sink.0);
+    });
+}
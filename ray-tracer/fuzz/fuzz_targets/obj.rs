@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ray_tracer::importers::obj::ObjImport;
+
+// any text should either import or give back an error, never panic. parse reads no files, so a mtllib in the
+// input can't make the target read something huge or blocking such as /dev/zero.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let _ = ObjImport::parse(input);
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use ray_tracer::scene_file;
+
+// any text should either build a scene or give back a SceneError, never panic. parse_without_files reads no files,
+// so a file named in the input can't make the target read something huge or blocking such as /dev/zero.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let _ = scene_file::parse_without_files(input);
+    }
+});
@@ -0,0 +1,695 @@
+use std::{collections::HashMap, fs, path::Path, sync::Arc};
+
+use ndarray::arr2;
+use serde_json::Value;
+
+use super::{mesh_builder::MeshBuilder, ImportError};
+use crate::{
+    comparison::EPSILON,
+    primatives::color::Color,
+    primatives::matrix::Matrix,
+    primatives::point::Point,
+    primatives::transformation::{scaling, translation},
+    primatives::tuple::Tuple,
+    primatives::vector::{cross, Vector},
+    shapes::{group::Group, material::Material, mesh::Mesh, ShapeBase},
+};
+
+/// GLB_MAGIC starts every binary glTF file.
+const GLB_MAGIC: &[u8] = b"glTF";
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+
+/// the primitive modes that hold triangles.
+const TRIANGLES: u64 = 4;
+const TRIANGLE_STRIP: u64 = 5;
+const TRIANGLE_FAN: u64 = 6;
+
+/// GltfImport is the result of reading a glTF 2.0 file, either the JSON `.gltf` form or the binary `.glb` form.
+///
+/// Every node in the scene becomes a group holding the node's transformation, its mesh and a group for each of its
+/// children. A glTF mesh becomes a Mesh, or a flat and a smooth one if only some of its primitives have normals,
+/// whose faces each keep the material of their primitive. Nodes using the same mesh share its buffers.
+/// The base color of a primitive's PBR material becomes the material's color, the rest of the material is left as the default.
+/// Primitives that aren't triangles are skipped and counted, as are animations, skins and cameras,
+/// and triangles with no area are left out.
+#[derive(Debug, Clone)]
+pub struct GltfImport {
+    group: Group,
+    ignored: usize,
+    degenerate: usize,
+}
+
+impl GltfImport {
+    /// load reads and parses the glTF file at the given path. Buffers stored in other files are found relative to it.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ImportError> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or(Path::new("."));
+        Self::parse_from(&fs::read(path)?, dir)
+    }
+
+    /// parse reads a glTF file from bytes, working out from the first bytes whether it is binary.
+    /// Buffers stored in other files are found relative to the working directory.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ImportError> {
+        Self::parse_from(bytes, Path::new("."))
+    }
+
+    pub fn group(&self) -> &Group {
+        &self.group
+    }
+
+    pub fn into_group(self) -> Group {
+        self.group
+    }
+
+    /// ignored is how many primitives and other parts of the file were skipped because they are not supported.
+    pub fn ignored(&self) -> usize {
+        self.ignored
+    }
+
+    /// degenerate is how many triangles were skipped because their corners are in a line.
+    pub fn degenerate(&self) -> usize {
+        self.degenerate
+    }
+
+    fn parse_from(bytes: &[u8], dir: &Path) -> Result<Self, ImportError> {
+        let (json, bin) = if bytes.starts_with(GLB_MAGIC) {
+            read_glb(bytes)?
+        } else {
+            (bytes, None)
+        };
+        let document: Value = serde_json::from_slice(json).map_err(|err| ImportError::Parse {
+            line: err.line(),
+            message: err.to_string(),
+        })?;
+
+        let version = document["asset"]["version"].as_str().unwrap_or_default();
+        if !version.starts_with('2') {
+            return Err(format_error(format!(
+                "only glTF 2.0 is supported but the file is version '{}'",
+                version
+            )));
+        }
+
+        let mut buffers = vec![];
+        for buffer in array(&document, "buffers") {
+            buffers.push(match buffer["uri"].as_str() {
+                None => bin
+                    .ok_or_else(|| {
+                        format_error("a buffer has no uri and there is no binary chunk")
+                    })?
+                    .to_vec(),
+                Some(uri) if uri.starts_with("data:") => decode_data_uri(uri)?,
+                Some(uri) => fs::read(dir.join(uri))?,
+            });
+        }
+
+        let mut reader = Reader {
+            document: &document,
+            buffers,
+            meshes: HashMap::new(),
+            ignored: 0,
+            degenerate: 0,
+        };
+        for key in ["animations", "skins", "cameras"] {
+            reader.ignored += array(&document, key).len();
+        }
+
+        let mut group = Group::default();
+        for root in reader.roots()? {
+            group.add_child(reader.node(root, &mut vec![])?.box_clone());
+        }
+
+        Ok(Self {
+            group,
+            ignored: reader.ignored,
+            degenerate: reader.degenerate,
+        })
+    }
+}
+
+fn format_error(message: impl Into<String>) -> ImportError {
+    ImportError::Format(message.into())
+}
+
+/// array is the list at the key, which is empty if the key isn't there.
+fn array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value[key].as_array().map_or(&[], |a| a)
+}
+
+/// index reads a non-negative integer, such as the index of another part of the file.
+fn index(value: &Value, what: &str) -> Result<usize, ImportError> {
+    value
+        .as_u64()
+        .map(|i| i as usize)
+        .ok_or_else(|| format_error(format!("{} should be a whole number", what)))
+}
+
+fn floats<const N: usize>(value: &Value, what: &str) -> Result<[f64; N], ImportError> {
+    let values = value
+        .as_array()
+        .map(|a| a.iter().filter_map(Value::as_f64).collect::<Vec<_>>())
+        .unwrap_or_default();
+    values
+        .try_into()
+        .map_err(|_| format_error(format!("{} should be a list of {} numbers", what, N)))
+}
+
+/// read_glb splits a binary glTF file into its JSON and binary chunks.
+fn read_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), ImportError> {
+    let u32_at = |offset: usize| {
+        bytes
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    if u32_at(4) != Some(2) {
+        return Err(format_error(
+            "only version 2 binary glTF files are supported",
+        ));
+    }
+
+    let mut json = None;
+    let mut bin = None;
+    let mut offset = 12;
+    while offset < bytes.len() {
+        let (Some(length), Some(kind)) = (u32_at(offset), u32_at(offset + 4)) else {
+            return Err(format_error("binary glTF chunk header is cut short"));
+        };
+        let start = offset + 8;
+        let Some(data) = bytes.get(start..start + length as usize) else {
+            return Err(format_error("binary glTF chunk is cut short"));
+        };
+        match kind {
+            GLB_JSON_CHUNK if json.is_none() => json = Some(data),
+            GLB_BIN_CHUNK if bin.is_none() => bin = Some(data),
+            _ => {}
+        }
+        offset = start + length as usize;
+    }
+
+    let json = json.ok_or_else(|| format_error("binary glTF file has no JSON chunk"))?;
+    Ok((json, bin))
+}
+
+/// decode_data_uri reads the base64 data embedded in a `data:` uri.
+fn decode_data_uri(uri: &str) -> Result<Vec<u8>, ImportError> {
+    let (_, data) = uri
+        .split_once(";base64,")
+        .ok_or_else(|| format_error("only base64 data uris are supported"))?;
+    decode_base64(data)
+}
+
+fn decode_base64(data: &str) -> Result<Vec<u8>, ImportError> {
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut bit_count = 0;
+    for c in data.bytes().take_while(|c| *c != b'=') {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(format_error(format!("'{}' is not base64", c as char))),
+        };
+        bits = (bits << 6) | value as u32;
+        bit_count += 6;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            bytes.push((bits >> bit_count) as u8);
+            bits &= (1 << bit_count) - 1;
+        }
+    }
+    Ok(bytes)
+}
+
+/// Reader turns the parts of a glTF document into shapes.
+struct Reader<'a> {
+    document: &'a Value,
+    buffers: Vec<Vec<u8>>,
+    /// meshes are the meshes already read, by their index in the document.
+    meshes: HashMap<usize, Vec<Mesh>>,
+    ignored: usize,
+    degenerate: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// part finds the item at the index in one of the document's top level lists.
+    fn part(&self, key: &str, i: usize) -> Result<&'a Value, ImportError> {
+        array(self.document, key)
+            .get(i)
+            .ok_or_else(|| format_error(format!("there is no {} {}", key, i)))
+    }
+
+    /// roots are the nodes at the top of the scene that is shown, or every node that isn't a child if there is no scene.
+    fn roots(&self) -> Result<Vec<usize>, ImportError> {
+        let scenes = array(self.document, "scenes");
+        if !scenes.is_empty() {
+            let scene = match &self.document["scene"] {
+                Value::Null => 0,
+                i => index(i, "scene")?,
+            };
+            let scene = self.part("scenes", scene)?;
+            return array(scene, "nodes")
+                .iter()
+                .map(|n| index(n, "a scene's node"))
+                .collect();
+        }
+
+        let nodes = array(self.document, "nodes");
+        let children: Vec<u64> = nodes
+            .iter()
+            .flat_map(|n| array(n, "children"))
+            .filter_map(Value::as_u64)
+            .collect();
+        Ok((0..nodes.len())
+            .filter(|i| !children.contains(&(*i as u64)))
+            .collect())
+    }
+
+    /// node builds the group for a node and its children. `path` holds the nodes above it to catch nodes that contain themselves.
+    fn node(&mut self, i: usize, path: &mut Vec<usize>) -> Result<Group, ImportError> {
+        if path.contains(&i) {
+            return Err(format_error(format!("node {} is its own ancestor", i)));
+        }
+        let node = self.part("nodes", i)?;
+        let transform = node_transform(node)?;
+        if transform.inverse().is_err() {
+            return Err(format_error(format!(
+                "node {} has a transformation that cannot be inverted",
+                i
+            )));
+        }
+
+        let mut group = Group::new(Some(transform), None);
+        if !node["mesh"].is_null() {
+            let mesh = index(&node["mesh"], "a node's mesh")?;
+            for mesh in self.mesh(mesh)? {
+                group.add_child(mesh.box_clone());
+            }
+        }
+        path.push(i);
+        for child in array(node, "children") {
+            let child = self.node(index(child, "a node's child")?, path)?;
+            group.add_child(child.box_clone());
+        }
+        path.pop();
+        Ok(group)
+    }
+
+    /// mesh reads the mesh at the index the first time it is used, and shares it with every later use.
+    fn mesh(&mut self, i: usize) -> Result<Vec<Mesh>, ImportError> {
+        if let Some(meshes) = self.meshes.get(&i) {
+            return Ok(meshes.clone());
+        }
+        let mesh = self.part("meshes", i)?;
+        let mut builder = MeshBuilder::default();
+        for primitive in array(mesh, "primitives") {
+            let mode = primitive["mode"].as_u64().unwrap_or(TRIANGLES);
+            if ![TRIANGLES, TRIANGLE_STRIP, TRIANGLE_FAN].contains(&mode) {
+                self.ignored += 1;
+                continue;
+            }
+
+            let attributes = &primitive["attributes"];
+            let positions = self.accessor(index(&attributes["POSITION"], "POSITION")?, 3)?;
+            let positions: Vec<Point> = positions
+                .chunks_exact(3)
+                .map(|p| Point::new(p[0], p[1], p[2]))
+                .collect();
+            let normals: Option<Vec<Vector>> = match &attributes["NORMAL"] {
+                Value::Null => None,
+                n => Some(
+                    self.accessor(index(n, "NORMAL")?, 3)?
+                        .chunks_exact(3)
+                        .map(|n| Vector::new(n[0], n[1], n[2]))
+                        .collect(),
+                ),
+            };
+            let indices: Vec<usize> = match &primitive["indices"] {
+                Value::Null => (0..positions.len()).collect(),
+                i => self
+                    .accessor(index(i, "indices")?, 1)?
+                    .into_iter()
+                    .map(|i| i as usize)
+                    .collect(),
+            };
+            let material = match &primitive["material"] {
+                Value::Null => None,
+                m => Some(Arc::new(
+                    self.material(index(m, "a primitive's material")?)?,
+                )),
+            };
+
+            for [a, b, c] in triangles(&indices, mode) {
+                let corner = |i: usize| {
+                    positions
+                        .get(i)
+                        .copied()
+                        .ok_or_else(|| format_error(format!("{} is not the index of a vertex", i)))
+                };
+                let [p1, p2, p3] = [corner(a)?, corner(b)?, corner(c)?];
+                if cross(p2 - p1, p3 - p1).magnitude() < EPSILON {
+                    self.degenerate += 1;
+                    continue;
+                }
+                let normals = match &normals {
+                    Some(normals) if [a, b, c].iter().all(|i| *i < normals.len()) => {
+                        Some([a, b, c].map(|i| normals[i]))
+                    }
+                    _ => None,
+                };
+                builder.add_triangle([p1, p2, p3], normals, material.as_ref());
+            }
+        }
+        let meshes = builder.build();
+        self.meshes.insert(i, meshes.clone());
+        Ok(meshes)
+    }
+
+    /// material makes a material from the base color of a glTF material.
+    fn material(&self, i: usize) -> Result<Material, ImportError> {
+        let material = self.part("materials", i)?;
+        let factor = &material["pbrMetallicRoughness"]["baseColorFactor"];
+        let mut builder = Material::builder();
+        if !factor.is_null() {
+            let [r, g, b, _] = floats::<4>(factor, "baseColorFactor")?;
+            builder.color(Color::new(r, g, b));
+        }
+        Ok(builder
+            .build_owned()
+            .expect("every material field has a default"))
+    }
+
+    /// accessor reads the values an accessor points at, checking it has the expected number of components.
+    fn accessor(&self, i: usize, components: usize) -> Result<Vec<f64>, ImportError> {
+        let accessor = self.part("accessors", i)?;
+        if !accessor["sparse"].is_null() {
+            return Err(format_error("sparse accessors are not supported"));
+        }
+        let expected = match components {
+            1 => "SCALAR",
+            3 => "VEC3",
+            _ => unreachable!("only scalars and 3 component vectors are read"),
+        };
+        if accessor["type"].as_str() != Some(expected) {
+            return Err(format_error(format!(
+                "accessor {} should be {}",
+                i, expected
+            )));
+        }
+        let count = index(&accessor["count"], "an accessor's count")?;
+        let component_type = accessor["componentType"].as_u64().unwrap_or_default();
+        let size = match component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            _ => {
+                return Err(format_error(format!(
+                    "accessor {} has an unknown component type {}",
+                    i, component_type
+                )))
+            }
+        };
+
+        let view = self.part(
+            "bufferViews",
+            index(&accessor["bufferView"], "an accessor's bufferView")?,
+        )?;
+        let buffer = self
+            .buffers
+            .get(index(&view["buffer"], "a buffer view's buffer")?)
+            .ok_or_else(|| format_error("a buffer view points at a buffer that doesn't exist"))?;
+        let view_offset = view["byteOffset"].as_u64().unwrap_or_default() as usize;
+        let view_length = index(&view["byteLength"], "a buffer view's byteLength")?;
+        let view_data = buffer
+            .get(view_offset..view_offset.saturating_add(view_length))
+            .ok_or_else(|| format_error("a buffer view is longer than its buffer"))?;
+        let offset = accessor["byteOffset"].as_u64().unwrap_or_default() as usize;
+        let stride = match view["byteStride"].as_u64() {
+            Some(stride) => stride as usize,
+            None => size * components,
+        };
+
+        let mut values = vec![];
+        for element in 0..count {
+            for component in 0..components {
+                let start = offset + element * stride + component * size;
+                let Some(b) = view_data.get(start..start + size) else {
+                    return Err(format_error(format!(
+                        "accessor {} reads past the end of its buffer view",
+                        i
+                    )));
+                };
+                values.push(match component_type {
+                    5120 => b[0] as i8 as f64,
+                    5121 => b[0] as f64,
+                    5122 => i16::from_le_bytes([b[0], b[1]]) as f64,
+                    5123 => u16::from_le_bytes([b[0], b[1]]) as f64,
+                    5125 => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                    _ => f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64,
+                });
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// triangles lists the corners of each triangle in a primitive.
+fn triangles(indices: &[usize], mode: u64) -> Vec<[usize; 3]> {
+    match mode {
+        TRIANGLE_STRIP => indices
+            .windows(3)
+            .enumerate()
+            // every other triangle in a strip is flipped to keep them all facing the same way.
+            .map(|(i, w)| {
+                if i % 2 == 0 {
+                    [w[0], w[1], w[2]]
+                } else {
+                    [w[1], w[0], w[2]]
+                }
+            })
+            .collect(),
+        TRIANGLE_FAN => indices
+            .windows(2)
+            .skip(1)
+            .map(|w| [indices[0], w[0], w[1]])
+            .collect(),
+        _ => indices
+            .chunks_exact(3)
+            .map(|c| [c[0], c[1], c[2]])
+            .collect(),
+    }
+}
+
+/// node_transform reads a node's transformation, either a whole matrix or a translation, rotation and scale.
+fn node_transform(node: &Value) -> Result<Matrix, ImportError> {
+    if !node["matrix"].is_null() {
+        // glTF matrices are listed a column at a time.
+        let m = floats::<16>(&node["matrix"], "a node's matrix")?;
+        let row = |r: usize| [m[r], m[4 + r], m[8 + r], m[12 + r]];
+        return Ok(Matrix::new(arr2(&[row(0), row(1), row(2), row(3)])));
+    }
+
+    let mut m = Matrix::default();
+    if !node["scale"].is_null() {
+        let [x, y, z] = floats::<3>(&node["scale"], "a node's scale")?;
+        m = scaling(x, y, z) * m;
+    }
+    if !node["rotation"].is_null() {
+        let q = floats::<4>(&node["rotation"], "a node's rotation")?;
+        m = quaternion_rotation(q) * m;
+    }
+    if !node["translation"].is_null() {
+        let [x, y, z] = floats::<3>(&node["translation"], "a node's translation")?;
+        m = translation(x, y, z) * m;
+    }
+    Ok(m)
+}
+
+/// quaternion_rotation turns a unit quaternion, written `[x, y, z, w]`, into a rotation matrix.
+fn quaternion_rotation([x, y, z, w]: [f64; 4]) -> Matrix {
+    Matrix::new(arr2(&[
+        [
+            1. - 2. * (y * y + z * z),
+            2. * (x * y - z * w),
+            2. * (x * z + y * w),
+            0.,
+        ],
+        [
+            2. * (x * y + z * w),
+            1. - 2. * (x * x + z * z),
+            2. * (y * z - x * w),
+            0.,
+        ],
+        [
+            2. * (x * z - y * w),
+            2. * (y * z + x * w),
+            1. - 2. * (x * x + y * y),
+            0.,
+        ],
+        [0., 0., 0., 1.],
+    ]))
+}
+
+#[cfg(test)]
+mod test_gltf {
+    use std::f64::consts::PI;
+
+    use crate::{primatives::transformation::rotation_y, shapes::Shape, C, P};
+
+    use super::*;
+
+    /// TRIANGLE_BUFFER is the corners (0, 1, 0), (-1, 0, 0) and (1, 0, 0) as floats followed by the indices 0, 1 and 2 as shorts.
+    const TRIANGLE_BUFFER: &str = "AAAAAAAAgD8AAAAAAACAvwAAAAAAAAAAAACAPwAAAAAAAAAAAAABAAIA";
+
+    fn document(buffer_uri: Option<&str>, extra: &str) -> String {
+        let uri = buffer_uri
+            .map(|uri| format!(r#""uri": "{}", "#, uri))
+            .unwrap_or_default();
+        format!(
+            r#"{{
+  "asset": {{ "version": "2.0" }},
+  "buffers": [ {{ {}"byteLength": 42 }} ],
+  "bufferViews": [
+    {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+    {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }}
+  ],
+  "accessors": [
+    {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3" }},
+    {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+  ],
+  "materials": [ {{ "pbrMetallicRoughness": {{ "baseColorFactor": [ 1, 0.5, 0, 1 ] }} }} ],
+  "meshes": [ {{ "primitives": [
+    {{ "attributes": {{ "POSITION": 0 }}, "indices": 1, "material": 0 }},
+    {{ "attributes": {{ "POSITION": 0 }}, "mode": 1 }}
+  ] }} ],
+  {}
+}}"#,
+            uri, extra
+        )
+    }
+
+    fn glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        while !json.len().is_multiple_of(4) {
+            json.push(b' ');
+        }
+        let mut bytes = GLB_MAGIC.to_vec();
+        bytes.extend(2u32.to_le_bytes());
+        bytes.extend(((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+        bytes.extend((json.len() as u32).to_le_bytes());
+        bytes.extend(GLB_JSON_CHUNK.to_le_bytes());
+        bytes.extend(json);
+        bytes.extend((bin.len() as u32).to_le_bytes());
+        bytes.extend(GLB_BIN_CHUNK.to_le_bytes());
+        bytes.extend(bin);
+        bytes
+    }
+
+    fn only_mesh(group: &Group) -> &Mesh {
+        let mut shape = group.children()[0].as_any();
+        while let Some(group) = shape.downcast_ref::<Group>() {
+            shape = group.children()[0].as_any();
+        }
+        shape.downcast_ref::<Mesh>().unwrap()
+    }
+
+    #[test]
+    fn test_embedded_buffer() {
+        let input = document(
+            Some(&format!(
+                "data:application/octet-stream;base64,{}",
+                TRIANGLE_BUFFER
+            )),
+            r#""nodes": [ { "mesh": 0 } ]"#,
+        );
+        let import = GltfImport::parse(input.as_bytes()).unwrap();
+
+        // the line primitive is skipped.
+        assert_eq!(1, import.ignored());
+        let mesh = only_mesh(import.group());
+        assert_eq!(1, mesh.faces().len());
+        assert_eq!(P![0., 1., 0.], mesh.corners(0)[0]);
+        assert_eq!(P![1., 0., 0.], mesh.corners(0)[2]);
+        assert_eq!(C![1., 0.5, 0.], mesh.face_material(0).color());
+    }
+
+    #[test]
+    fn test_binary_with_node_hierarchy() {
+        let json = document(
+            None,
+            r#""scene": 0,
+  "scenes": [ { "nodes": [ 0 ] } ],
+  "nodes": [
+    { "translation": [ 0, 0, 5 ], "children": [ 1 ] },
+    { "rotation": [ 0, 0.7071068, 0, 0.7071068 ], "scale": [ 2, 2, 2 ], "mesh": 0 },
+    { "mesh": 0 }
+  ]"#,
+        );
+        let bin = decode_base64(TRIANGLE_BUFFER).unwrap();
+        let import = GltfImport::parse(&glb(&json, &bin)).unwrap();
+
+        // only the nodes in the scene are read.
+        let root = import.group().children()[0]
+            .as_any()
+            .downcast_ref::<Group>()
+            .unwrap();
+        assert_eq!(1, root.children().len());
+
+        let expected = translation(0., 0., 5.) * rotation_y(PI / 2.) * scaling(2., 2., 2.);
+        let mesh = only_mesh(import.group());
+        assert_eq!(&expected, mesh.transformation());
+    }
+
+    #[test]
+    fn test_node_matrix() {
+        let node = serde_json::json!({
+            "matrix": [ 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 1, 2, 3, 1 ]
+        });
+        assert_eq!(translation(1., 2., 3.), node_transform(&node).unwrap());
+    }
+
+    #[test]
+    fn test_strips_and_fans() {
+        assert_eq!(
+            vec![[0, 1, 2], [2, 1, 3], [2, 3, 4]],
+            triangles(&[0, 1, 2, 3, 4], TRIANGLE_STRIP)
+        );
+        assert_eq!(
+            vec![[0, 1, 2], [0, 2, 3]],
+            triangles(&[0, 1, 2, 3], TRIANGLE_FAN)
+        );
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(b"hello".to_vec(), decode_base64("aGVsbG8=").unwrap());
+        assert!(decode_base64("a!").is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = GltfImport::parse(b"{\n  \"asset\": ").unwrap_err();
+        assert!(matches!(err, ImportError::Parse { line: 2, .. }));
+
+        let err = GltfImport::parse(br#"{ "asset": { "version": "1.0" } }"#).unwrap_err();
+        assert!(matches!(err, ImportError::Format(_)));
+
+        // the buffer is too short for the accessors.
+        let input = document(
+            Some("data:application/octet-stream;base64,AAAA"),
+            r#""nodes": [ { "mesh": 0 } ]"#,
+        );
+        let err = GltfImport::parse(input.as_bytes()).unwrap_err();
+        assert!(matches!(err, ImportError::Format(_)));
+
+        // a node that contains itself.
+        let input = document(
+            Some(&format!("data:;base64,{}", TRIANGLE_BUFFER)),
+            r#""scenes": [ { "nodes": [ 0 ] } ], "nodes": [ { "children": [ 0 ] } ]"#,
+        );
+        let err = GltfImport::parse(input.as_bytes()).unwrap_err();
+        assert!(matches!(err, ImportError::Format(_)));
+    }
+}
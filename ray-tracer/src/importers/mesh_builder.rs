@@ -0,0 +1,111 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    primatives::point::Point,
+    primatives::tuple::Tuple,
+    primatives::vector::Vector,
+    shapes::{material::Material, mesh::Mesh},
+};
+
+/// MeshBuilder gathers the triangles an importer reads into meshes, so a model holds each corner once instead of a
+/// shape for every face. Corners in the same place with the same normal are shared by every face that uses them.
+/// A mesh is either flat or smooth throughout, so flat and smooth faces are gathered into a mesh each.
+#[derive(Debug, Default)]
+pub(super) struct MeshBuilder {
+    flat: Part,
+    smooth: Part,
+}
+
+/// Part is the buffers of one of the meshes being built.
+#[derive(Debug, Default)]
+struct Part {
+    vertices: Vec<Point>,
+    normals: Vec<Vector>,
+    faces: Vec<[usize; 3]>,
+    /// corners finds the vertex already made for a position and normal, by the bits of their coordinates.
+    corners: HashMap<[u64; 6], usize>,
+    materials: Vec<Arc<Material>>,
+    /// face_materials is the index into materials for each face, or None for a face with the default material.
+    face_materials: Vec<Option<usize>>,
+}
+
+impl MeshBuilder {
+    /// add_triangle adds a face with the corners in order, which is smooth if it has a normal for each corner.
+    pub(super) fn add_triangle(
+        &mut self,
+        corners: [Point; 3],
+        normals: Option<[Vector; 3]>,
+        material: Option<&Arc<Material>>,
+    ) {
+        let part = if normals.is_some() {
+            &mut self.smooth
+        } else {
+            &mut self.flat
+        };
+        let face = [0, 1, 2].map(|i| part.corner(corners[i], normals.map(|n| n[i])));
+        part.faces.push(face);
+        let material = material.map(|material| {
+            part.materials
+                .iter()
+                .position(|m| Arc::ptr_eq(m, material))
+                .unwrap_or_else(|| {
+                    part.materials.push(material.clone());
+                    part.materials.len() - 1
+                })
+        });
+        part.face_materials.push(material);
+    }
+
+    /// build makes the flat mesh and then the smooth one, leaving out either if it has no faces.
+    pub(super) fn build(self) -> Vec<Mesh> {
+        [self.flat, self.smooth]
+            .into_iter()
+            .filter(|part| !part.faces.is_empty())
+            .map(Part::build)
+            .collect()
+    }
+}
+
+impl Part {
+    /// corner is the index of the vertex at the position with the normal, adding it if it is new.
+    fn corner(&mut self, position: Point, normal: Option<Vector>) -> usize {
+        let n = normal.unwrap_or(Vector::new(0., 0., 0.));
+        let key = [
+            position.x(),
+            position.y(),
+            position.z(),
+            n.x(),
+            n.y(),
+            n.z(),
+        ]
+        .map(f64::to_bits);
+        *self.corners.entry(key).or_insert_with(|| {
+            self.vertices.push(position);
+            self.normals.extend(normal);
+            self.vertices.len() - 1
+        })
+    }
+
+    fn build(self) -> Mesh {
+        let mut mesh = Mesh::new(self.vertices, self.faces, None, None);
+        if !self.normals.is_empty() {
+            mesh.set_normals(self.normals);
+        }
+        // faces without a material of their own keep the mesh's material, unless other faces have one, in which case
+        // they share a default one.
+        if self.face_materials.iter().any(Option::is_some) {
+            let mut materials = self.materials;
+            let default = materials.len();
+            if self.face_materials.iter().any(Option::is_none) {
+                materials.push(Arc::new(Material::default()));
+            }
+            let face_materials = self
+                .face_materials
+                .into_iter()
+                .map(|material| material.unwrap_or(default))
+                .collect();
+            mesh.set_face_materials(materials, face_materials);
+        }
+        mesh
+    }
+}
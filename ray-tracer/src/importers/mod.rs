@@ -0,0 +1,38 @@
+pub mod gltf;
+mod mesh_builder;
+pub mod mtl;
+mod netpbm;
+pub mod obj;
+pub mod pgm;
+pub mod ply;
+pub mod ppm;
+pub mod stl;
+
+use std::fmt::Display;
+
+/// ImportError is returned when a model file cannot be read or doesn't make sense.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportError {
+    /// Io is any problem opening or reading the file.
+    Io(String),
+    /// Parse is a statement that could not be understood, with the line it was found on.
+    Parse { line: usize, message: String },
+    /// Format is a file that doesn't have the layout its format requires, such as a truncated binary file.
+    Format(String),
+}
+
+impl Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::Io(message) => write!(f, "could not read model: {}", message),
+            ImportError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+            ImportError::Format(message) => write!(f, "badly formed model: {}", message),
+        }
+    }
+}
+
+impl From<std::io::Error> for ImportError {
+    fn from(err: std::io::Error) -> Self {
+        ImportError::Io(err.to_string())
+    }
+}
@@ -0,0 +1,243 @@
+use std::{fs, path::Path, sync::Arc};
+
+use super::ImportError;
+use crate::{
+    primatives::color::Color,
+    shapes::material::{Material, MaterialBuilder},
+};
+
+/// MtlLibrary holds the named materials read from a Wavefront MTL file.
+///
+/// MTL describes colors for each kind of light where this renderer uses one color and a strength for each,
+/// so the values are translated:
+/// - `Kd` becomes the color, with a diffuse strength of 1 as the color already holds how much light is reflected.
+/// - `Ka` and `Ks` become the ambient and specular strengths, taking the average of their channels.
+/// - `Ns` is the shininess.
+/// - `d` (dissolve) becomes a transparency of `1 - d`, and `Tr` is used as the transparency directly.
+/// - `Ni` is the refractive index.
+///
+/// Anything a material doesn't set keeps its default value. Each material is held behind an Arc so that every face
+/// using it shares the one copy.
+#[derive(Debug, Clone, Default)]
+pub struct MtlLibrary {
+    materials: Vec<(String, Arc<Material>)>,
+    ignored: usize,
+}
+
+impl MtlLibrary {
+    /// load reads and parses the MTL file at the given path.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ImportError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// parse reads MTL statements from a string. Statements it doesn't recognise are skipped and counted.
+    pub fn parse(input: &str) -> Result<Self, ImportError> {
+        let mut library = Self::default();
+        let mut current: Option<(String, MaterialBuilder)> = None;
+
+        for (i, line) in input.lines().enumerate() {
+            let line_number = i + 1;
+            let mut parts = line.split_whitespace();
+            let Some(keyword) = parts.next() else {
+                continue;
+            };
+            let args: Vec<&str> = parts.collect();
+
+            if keyword.starts_with('#') {
+                continue;
+            }
+            if keyword == "newmtl" {
+                library.finish(current.take());
+                let name = args.join(" ");
+                if name.is_empty() {
+                    return Err(parse_error(line_number, "newmtl needs a name"));
+                }
+                current = Some((name, Material::builder()));
+                continue;
+            }
+
+            let Some((_, builder)) = current.as_mut() else {
+                return Err(parse_error(
+                    line_number,
+                    format!("'{}' appears before any newmtl", keyword),
+                ));
+            };
+            match keyword {
+                "Kd" => {
+                    builder.color(parse_color(&args, line_number)?).diffuse(1.);
+                }
+                "Ka" => {
+                    builder.ambient(average(parse_color(&args, line_number)?));
+                }
+                "Ks" => {
+                    builder.specular(average(parse_color(&args, line_number)?));
+                }
+                "Ns" => {
+                    builder.shininess(parse_float(&args, line_number)?);
+                }
+                "d" => {
+                    builder.transparency(1. - parse_float(&args, line_number)?);
+                }
+                "Tr" => {
+                    builder.transparency(parse_float(&args, line_number)?);
+                }
+                "Ni" => {
+                    builder.refractive_index(parse_float(&args, line_number)?);
+                }
+                _ => library.ignored += 1,
+            }
+        }
+        library.finish(current);
+
+        Ok(library)
+    }
+
+    /// get finds the material with the given name.
+    pub fn get(&self, name: &str) -> Option<&Arc<Material>> {
+        self.materials
+            .iter()
+            .find(|(material_name, _)| material_name == name)
+            .map(|(_, material)| material)
+    }
+
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.materials.is_empty()
+    }
+
+    /// ignored is how many statements were skipped because they are not supported.
+    pub fn ignored(&self) -> usize {
+        self.ignored
+    }
+
+    /// extend adds the materials from another library. Later materials replace earlier ones with the same name.
+    pub fn extend(&mut self, other: MtlLibrary) {
+        for (name, material) in other.materials {
+            self.insert(name, material);
+        }
+        self.ignored += other.ignored;
+    }
+
+    fn insert(&mut self, name: String, material: Arc<Material>) {
+        match self.materials.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, existing)) => *existing = material,
+            None => self.materials.push((name, material)),
+        }
+    }
+
+    fn finish(&mut self, material: Option<(String, MaterialBuilder)>) {
+        if let Some((name, builder)) = material {
+            let material = builder.build().expect("every material field has a default");
+            self.insert(name, Arc::new(material));
+        }
+    }
+}
+
+fn parse_error(line: usize, message: impl Into<String>) -> ImportError {
+    ImportError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+fn parse_float(args: &[&str], line: usize) -> Result<f64, ImportError> {
+    let arg = args
+        .first()
+        .ok_or_else(|| parse_error(line, "expected a number"))?;
+    arg.parse()
+        .map_err(|_| parse_error(line, format!("'{}' is not a number", arg)))
+}
+
+/// parse_color reads an `r g b` color. A single value is used for all three channels.
+fn parse_color(args: &[&str], line: usize) -> Result<Color, ImportError> {
+    let values = args
+        .iter()
+        .take(3)
+        .map(|arg| {
+            arg.parse::<f64>()
+                .map_err(|_| parse_error(line, format!("'{}' is not a number", arg)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match values[..] {
+        [v] => Ok(Color::new(v, v, v)),
+        [r, g, b] => Ok(Color::new(r, g, b)),
+        _ => Err(parse_error(line, "expected 1 or 3 numbers for a color")),
+    }
+}
+
+fn average(c: Color) -> f64 {
+    (c.red() + c.green() + c.blue()) / 3.
+}
+
+#[cfg(test)]
+mod test_mtl {
+    use crate::{comparison::approx_eq, C};
+
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let input = "# two materials
+newmtl red
+Ka 0.1 0.1 0.1
+Kd 0.8 0.1 0.1
+Ks 0.3 0.6 0.9
+Ns 50
+illum 2
+
+newmtl glass
+Kd 1 1 1
+d 0.1
+Ni 1.5";
+        let library = MtlLibrary::parse(input).unwrap();
+        assert_eq!(2, library.len());
+        assert_eq!(1, library.ignored());
+
+        let red = library.get("red").unwrap();
+        assert_eq!(C![0.8, 0.1, 0.1], red.color());
+        assert!(approx_eq(0.1, red.ambient()));
+        assert!(approx_eq(1., red.diffuse()));
+        assert!(approx_eq(0.6, red.specular()));
+        assert!(approx_eq(50., red.shininess()));
+        assert!(approx_eq(0., red.transparency()));
+
+        let glass = library.get("glass").unwrap();
+        assert!(approx_eq(0.9, glass.transparency()));
+        assert!(approx_eq(1.5, glass.refractive_index()));
+        // unset values keep their defaults.
+        assert!(approx_eq(Material::default().ambient(), glass.ambient()));
+
+        assert!(library.get("blue").is_none());
+    }
+
+    #[test]
+    fn test_single_value_color() {
+        let library = MtlLibrary::parse("newmtl grey\nKd 0.5").unwrap();
+        assert_eq!(C![0.5, 0.5, 0.5], library.get("grey").unwrap().color());
+    }
+
+    #[test]
+    fn test_extend() {
+        let mut library = MtlLibrary::parse("newmtl a\nNs 10\nnewmtl b\nNs 20").unwrap();
+        library.extend(MtlLibrary::parse("newmtl b\nNs 30").unwrap());
+
+        assert_eq!(2, library.len());
+        assert!(approx_eq(30., library.get("b").unwrap().shininess()));
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = MtlLibrary::parse("Kd 1 1 1").unwrap_err();
+        assert!(matches!(err, ImportError::Parse { line: 1, .. }));
+
+        let err = MtlLibrary::parse("newmtl a\nKd 1 1").unwrap_err();
+        assert!(matches!(err, ImportError::Parse { line: 2, .. }));
+
+        let err = MtlLibrary::parse("newmtl a\nNs shiny").unwrap_err();
+        assert!(matches!(err, ImportError::Parse { line: 2, .. }));
+    }
+}
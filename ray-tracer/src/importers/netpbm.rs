@@ -0,0 +1,126 @@
+use super::ImportError;
+
+/// Netpbm is the header and pixels shared by the PGM and PPM image formats.
+pub(super) struct Netpbm {
+    pub width: usize,
+    pub height: usize,
+    /// samples are every channel of every pixel, a row at a time from the top, scaled so the file's maximum
+    /// value is 1.
+    pub samples: Vec<f64>,
+}
+
+/// parse reads a netpbm file from bytes, where plain and binary are the magic numbers of the two forms of the format
+/// and channels is how many values each pixel has. The header is the same for both forms, after which plain files
+/// list every value as text and binary files pack them into one byte each, or two when the maximum is over 255.
+pub(super) fn parse(
+    bytes: &[u8],
+    format: &str,
+    plain: &str,
+    binary: &str,
+    channels: usize,
+) -> Result<Netpbm, ImportError> {
+    let mut pos = 0;
+    let magic =
+        token(bytes, &mut pos).ok_or_else(|| ImportError::Format("file is empty".to_string()))?;
+    let is_binary = if magic == plain {
+        false
+    } else if magic == binary {
+        true
+    } else {
+        return Err(ImportError::Format(format!(
+            "expected a {} file starting with {} or {} but found '{}'",
+            format, plain, binary, magic
+        )));
+    };
+    let width = header_value(bytes, &mut pos, "width")?;
+    let height = header_value(bytes, &mut pos, "height")?;
+    let max_value = header_value(bytes, &mut pos, "maximum value")?;
+    if width == 0 || height == 0 {
+        return Err(ImportError::Format("image has no pixels".to_string()));
+    }
+    if max_value == 0 || max_value > u16::MAX as usize {
+        return Err(ImportError::Format(format!(
+            "maximum value {} is not between 1 and 65535",
+            max_value
+        )));
+    }
+
+    let pixels = width * height;
+    let count = pixels * channels;
+    let raw: Vec<usize> = if is_binary {
+        // a single whitespace character separates the header from the pixels.
+        let body = bytes.get(pos + 1..).unwrap_or_default();
+        let size = if max_value > 255 { 2 } else { 1 };
+        if body.len() < count * size {
+            return Err(ImportError::Format(format!(
+                "expected {} pixels but the file ends after {}",
+                pixels,
+                body.len() / (size * channels)
+            )));
+        }
+        body.chunks(size)
+            .take(count)
+            .map(|b| {
+                b.iter()
+                    .fold(0, |value, &byte| (value << 8) | byte as usize)
+            })
+            .collect()
+    } else {
+        (0..count)
+            .map(|i| {
+                let value = token(bytes, &mut pos).ok_or_else(|| {
+                    ImportError::Format(format!(
+                        "expected {} pixels but the file ends after {}",
+                        pixels,
+                        i / channels
+                    ))
+                })?;
+                value.parse().map_err(|_| {
+                    ImportError::Format(format!("pixel '{}' is not a whole number", value))
+                })
+            })
+            .collect::<Result<_, _>>()?
+    };
+
+    Ok(Netpbm {
+        width,
+        height,
+        // values over the maximum are treated as the maximum.
+        samples: raw
+            .into_iter()
+            .map(|value| value.min(max_value) as f64 / max_value as f64)
+            .collect(),
+    })
+}
+
+/// token is the next run of non whitespace in the header or a plain body, skipping comments which run from a # to
+/// the end of the line. pos is left on the character after the token.
+fn token<'a>(bytes: &'a [u8], pos: &mut usize) -> Option<&'a str> {
+    loop {
+        match bytes.get(*pos)? {
+            b'#' => {
+                while bytes.get(*pos).is_some_and(|&b| b != b'\n') {
+                    *pos += 1;
+                }
+            }
+            b if b.is_ascii_whitespace() => *pos += 1,
+            _ => break,
+        }
+    }
+    let start = *pos;
+    while bytes
+        .get(*pos)
+        .is_some_and(|b| !b.is_ascii_whitespace() && *b != b'#')
+    {
+        *pos += 1;
+    }
+    std::str::from_utf8(&bytes[start..*pos]).ok()
+}
+
+fn header_value(bytes: &[u8], pos: &mut usize, name: &str) -> Result<usize, ImportError> {
+    let value = token(bytes, pos)
+        .ok_or_else(|| ImportError::Format(format!("header is missing the {}", name)))?;
+    value
+        .parse()
+        .map_err(|_| ImportError::Format(format!("{} '{}' is not a whole number", name, value)))
+}
@@ -0,0 +1,616 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use super::{mesh_builder::MeshBuilder, mtl::MtlLibrary, ImportError};
+use crate::{
+    primatives::point::Point,
+    primatives::tuple::Tuple,
+    primatives::vector::Vector,
+    shapes::{group::Group, material::Material, subdivision::PolygonMesh, ShapeBase},
+};
+
+/// DEFAULT_GROUP is the name given to faces that appear before any `g` statement.
+pub const DEFAULT_GROUP: &str = "default";
+
+/// ObjImport is the result of reading a Wavefront OBJ file. Faces are turned into triangles, split into
+/// fans if they have more than three corners, and collected into a group for every `g` or `o` statement.
+/// Each group holds its triangles as a mesh, or two if it has both flat faces and faces with normals, which are smooth.
+/// Materials are read from the files named by `mtllib` and given to the faces that follow a `usemtl`.
+/// A material library that can't be read is skipped with a warning and its faces keep the default material.
+#[derive(Debug, Clone)]
+pub struct ObjImport {
+    vertices: Vec<Point>,
+    normals: Vec<Vector>,
+    groups: Vec<(String, Group)>,
+    /// faces are the vertex indices of every face as it was written, before being split into triangles.
+    faces: Vec<Vec<usize>>,
+    materials: MtlLibrary,
+    /// material_libraries are the paths of the files named by `mtllib`, in the order they were read.
+    material_libraries: Vec<PathBuf>,
+    ignored: usize,
+    /// warnings describe anything that was left out of the import, such as a material library that couldn't be read.
+    warnings: Vec<String>,
+}
+
+/// Corner is one corner of a face, the vertex and optionally the normal it uses.
+#[derive(Debug, Clone, Copy)]
+struct Corner {
+    index: usize,
+    vertex: Point,
+    normal: Option<Vector>,
+}
+
+impl ObjImport {
+    /// load reads and parses the OBJ file at the given path. Material libraries are found relative to the file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ImportError> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or(Path::new("."));
+        Self::parse_from(&fs::read_to_string(path)?, Some(dir), MtlLibrary::default())
+    }
+
+    /// parse reads OBJ statements from a string. Statements it doesn't recognise are skipped and counted.
+    /// It never reads any files, so a `mtllib` is skipped with a warning, which makes it safe to give untrusted input.
+    pub fn parse(input: &str) -> Result<Self, ImportError> {
+        Self::parse_from(input, None, MtlLibrary::default())
+    }
+
+    /// parse_with_materials reads OBJ statements from a string, starting with materials that have already been loaded.
+    /// Like parse it never reads any files.
+    pub fn parse_with_materials(input: &str, materials: MtlLibrary) -> Result<Self, ImportError> {
+        Self::parse_from(input, None, materials)
+    }
+
+    /// parse_from reads OBJ statements, finding material libraries in dir, or skipping them if there is no dir.
+    fn parse_from(
+        input: &str,
+        dir: Option<&Path>,
+        materials: MtlLibrary,
+    ) -> Result<Self, ImportError> {
+        let mut import = Self {
+            vertices: vec![],
+            normals: vec![],
+            groups: vec![],
+            faces: vec![],
+            materials,
+            material_libraries: vec![],
+            ignored: 0,
+            warnings: vec![],
+        };
+        let mut current = DEFAULT_GROUP.to_string();
+        let mut material: Option<Arc<Material>> = None;
+        let mut builders: Vec<(String, MeshBuilder)> = vec![];
+
+        for (i, line) in input.lines().enumerate() {
+            let line_number = i + 1;
+            let mut parts = line.split_whitespace();
+            let Some(keyword) = parts.next() else {
+                continue;
+            };
+            let args: Vec<&str> = parts.collect();
+
+            match keyword {
+                _ if keyword.starts_with('#') => {}
+                "v" => {
+                    let [x, y, z] = parse_floats(&args, line_number)?;
+                    import.vertices.push(Point::new(x, y, z));
+                }
+                "vn" => {
+                    let [x, y, z] = parse_floats(&args, line_number)?;
+                    import.normals.push(Vector::new(x, y, z));
+                }
+                "f" => {
+                    let corners = args
+                        .iter()
+                        .map(|arg| import.corner(arg, line_number))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if corners.len() < 3 {
+                        return Err(parse_error(line_number, "a face needs at least 3 vertices"));
+                    }
+                    import.faces.push(corners.iter().map(|c| c.index).collect());
+                    let builder = match builders.iter().position(|(name, _)| *name == current) {
+                        Some(i) => &mut builders[i].1,
+                        None => {
+                            builders.push((current.clone(), MeshBuilder::default()));
+                            &mut builders.last_mut().unwrap().1
+                        }
+                    };
+                    add_face(builder, &corners, material.as_ref());
+                }
+                "g" | "o" => {
+                    current = if args.is_empty() {
+                        DEFAULT_GROUP.to_string()
+                    } else {
+                        args.join(" ")
+                    };
+                }
+                "mtllib" => {
+                    // the geometry is still worth having without its materials, so a library that can't be read
+                    // leaves its faces with the default material.
+                    for file in args {
+                        let Some(dir) = dir else {
+                            import.warnings.push(format!(
+                                "line {}: skipped material library '{}' as files are only read when loading a model",
+                                line_number, file
+                            ));
+                            continue;
+                        };
+                        let path = dir.join(file);
+                        match MtlLibrary::load(&path) {
+                            Ok(library) => {
+                                import.materials.extend(library);
+                                import.material_libraries.push(path);
+                            }
+                            Err(err) => import.warnings.push(format!(
+                                "line {}: skipped material library '{}': {}",
+                                line_number,
+                                path.display(),
+                                err
+                            )),
+                        }
+                    }
+                }
+                "usemtl" => {
+                    // faces that ask for a material that doesn't exist keep the default one.
+                    material = import.materials.get(&args.join(" ")).cloned();
+                    if material.is_none() {
+                        import.ignored += 1;
+                    }
+                }
+                _ => import.ignored += 1,
+            }
+        }
+
+        import.groups = builders
+            .into_iter()
+            .map(|(name, builder)| {
+                let mut group = Group::default();
+                for mesh in builder.build() {
+                    group.add_child(mesh.box_clone());
+                }
+                (name, group)
+            })
+            .collect();
+        Ok(import)
+    }
+
+    pub fn vertices(&self) -> &Vec<Point> {
+        &self.vertices
+    }
+
+    pub fn normals(&self) -> &Vec<Vector> {
+        &self.normals
+    }
+
+    /// materials are every material that was loaded for the file.
+    pub fn materials(&self) -> &MtlLibrary {
+        &self.materials
+    }
+
+    /// material_libraries are the paths of the material files that were read for the model.
+    pub fn material_libraries(&self) -> &Vec<PathBuf> {
+        &self.material_libraries
+    }
+
+    /// ignored is how many statements were skipped because they are not supported.
+    /// A `usemtl` naming a material that was never loaded is counted too.
+    pub fn ignored(&self) -> usize {
+        self.ignored
+    }
+
+    /// warnings describe anything that was left out of the import, such as a material library that couldn't be read.
+    pub fn warnings(&self) -> &Vec<String> {
+        &self.warnings
+    }
+
+    /// group finds the faces that were given the name with a `g` or `o` statement.
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.groups
+            .iter()
+            .find(|(group_name, _)| group_name == name)
+            .map(|(_, group)| group)
+    }
+
+    pub fn group_names(&self) -> Vec<&str> {
+        self.groups.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// to_group puts every named group into a single group that can be added to a world.
+    pub fn to_group(&self) -> Group {
+        let mut group = Group::default();
+        for (_, child) in &self.groups {
+            group.add_child(child.box_clone());
+        }
+        group
+    }
+
+    /// to_polygon_mesh keeps every face with all its corners and the vertices they share, ignoring groups,
+    /// normals and materials. It is used to subdivide low poly models into smooth ones before rendering.
+    pub fn to_polygon_mesh(&self) -> PolygonMesh {
+        PolygonMesh::new(self.vertices.clone(), self.faces.clone())
+    }
+
+    /// corner resolves a face argument in the form `v`, `v/vt`, `v//vn` or `v/vt/vn`.
+    fn corner(&self, arg: &str, line: usize) -> Result<Corner, ImportError> {
+        let mut indices = arg.split('/');
+        let vertex = indices.next().unwrap_or_default();
+        let index = resolve_index(vertex, self.vertices.len(), line)?;
+        let vertex = self.vertices[index];
+
+        // texture coordinates are not used.
+        let _ = indices.next();
+
+        let normal = match indices.next() {
+            Some(normal) if !normal.is_empty() => {
+                Some(self.normals[resolve_index(normal, self.normals.len(), line)?])
+            }
+            _ => None,
+        };
+
+        Ok(Corner {
+            index,
+            vertex,
+            normal,
+        })
+    }
+}
+
+/// add_face splits the face into a fan of triangles around its first corner and adds them to the builder.
+/// Triangles are smooth if every corner of the face has a normal.
+fn add_face(builder: &mut MeshBuilder, corners: &[Corner], material: Option<&Arc<Material>>) {
+    let normals: Option<Vec<Vector>> = corners.iter().map(|c| c.normal).collect();
+    for i in 1..corners.len() - 1 {
+        let fan = [0, i, i + 1];
+        builder.add_triangle(
+            fan.map(|i| corners[i].vertex),
+            normals.as_ref().map(|normals| fan.map(|i| normals[i])),
+            material,
+        );
+    }
+}
+
+fn parse_error(line: usize, message: impl Into<String>) -> ImportError {
+    ImportError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+/// parse_floats reads the first three numbers of a statement. Any extra values, such as a `w` weight, are ignored.
+fn parse_floats(args: &[&str], line: usize) -> Result<[f64; 3], ImportError> {
+    if args.len() < 3 {
+        return Err(parse_error(line, "expected 3 numbers"));
+    }
+    let mut values = [0.; 3];
+    for (value, arg) in values.iter_mut().zip(args) {
+        *value = arg
+            .parse()
+            .map_err(|_| parse_error(line, format!("'{}' is not a number", arg)))?;
+    }
+    Ok(values)
+}
+
+/// resolve_index turns a 1 based OBJ index into a 0 based one. Negative indices count back from the most recent item.
+fn resolve_index(arg: &str, len: usize, line: usize) -> Result<usize, ImportError> {
+    let index: i64 = arg
+        .parse()
+        .map_err(|_| parse_error(line, format!("'{}' is not an index", arg)))?;
+
+    let resolved = if index < 0 {
+        len as i64 + index
+    } else {
+        index - 1
+    };
+
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(parse_error(
+            line,
+            format!("index {} is out of range, there are {} items", index, len),
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod test_obj {
+    use crate::{primatives::color::Color, shapes::mesh::Mesh, C, P, V};
+
+    use super::*;
+
+    /// mesh is the only mesh in the group.
+    fn mesh(group: &Group) -> &Mesh {
+        assert_eq!(1, group.children().len());
+        group.children()[0].as_any().downcast_ref::<Mesh>().unwrap()
+    }
+
+    #[test]
+    fn test_ignores_unrecognised_lines() {
+        let input = "There was a young lady named Bright
+who traveled much faster than light.
+She set out one day
+in a relative way,
+and came back the previous night.";
+        let import = ObjImport::parse(input).unwrap();
+        assert_eq!(5, import.ignored());
+    }
+
+    #[test]
+    fn test_comments_and_blank_lines_are_not_ignored_statements() {
+        let import = ObjImport::parse("# a comment\n\n   \nv 1 2 3").unwrap();
+        assert_eq!(0, import.ignored());
+        assert_eq!(1, import.vertices().len());
+    }
+
+    #[test]
+    fn test_vertices() {
+        let input = "v -1 1 0
+v -1.0000 0.5000 0.0000
+v 1 0 0
+v 1 1 0";
+        let import = ObjImport::parse(input).unwrap();
+        assert_eq!(
+            &vec![
+                P![-1., 1., 0.],
+                P![-1., 0.5, 0.],
+                P![1., 0., 0.],
+                P![1., 1., 0.]
+            ],
+            import.vertices()
+        );
+    }
+
+    #[test]
+    fn test_triangle_faces() {
+        let input = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+f 1 2 3
+f 1 3 4";
+        let import = ObjImport::parse(input).unwrap();
+        let m = mesh(import.group(DEFAULT_GROUP).unwrap());
+        let v = import.vertices();
+
+        assert_eq!(2, m.faces().len());
+        assert_eq!([v[0], v[1], v[2]], m.corners(0));
+        assert_eq!([v[0], v[2], v[3]], m.corners(1));
+        // the faces share the corners they have in common.
+        assert_eq!(4, m.vertices().len());
+    }
+
+    #[test]
+    fn test_polygons() {
+        let input = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+v 0 2 0
+
+f 1 2 3 4 5";
+        let import = ObjImport::parse(input).unwrap();
+        let m = mesh(import.group(DEFAULT_GROUP).unwrap());
+        assert_eq!(3, m.faces().len());
+
+        let v = import.vertices();
+        assert_eq!([v[0], v[3], v[4]], m.corners(2));
+    }
+
+    #[test]
+    fn test_polygon_mesh() {
+        let input = "v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 2 0 0
+g quad
+f 1 2 3 4
+g triangle
+f -4 -1 3";
+        let mesh = ObjImport::parse(input).unwrap().to_polygon_mesh();
+        assert_eq!(5, mesh.vertices().len());
+        assert_eq!(&[vec![0, 1, 2, 3], vec![1, 4, 2]], mesh.faces());
+
+        // the faces share an edge, so subdividing them gives one surface.
+        let smooth = mesh.subdivide(1).to_mesh(None, None);
+        assert_eq!(2 * (4 + 3), smooth.faces().len());
+    }
+
+    #[test]
+    fn test_named_groups() {
+        let input = "v -1 1 0
+v -1 0 0
+v 1 0 0
+v 1 1 0
+
+g FirstGroup
+f 1 2 3
+g SecondGroup
+f 1 3 4";
+        let import = ObjImport::parse(input).unwrap();
+        assert_eq!(vec!["FirstGroup", "SecondGroup"], import.group_names());
+
+        let t1 = mesh(import.group("FirstGroup").unwrap()).corners(0);
+        let t2 = mesh(import.group("SecondGroup").unwrap()).corners(0);
+        assert_eq!(import.vertices()[1], t1[1]);
+        assert_eq!(import.vertices()[3], t2[2]);
+
+        let g = import.to_group();
+        assert_eq!(2, g.children().len());
+    }
+
+    #[test]
+    fn test_normals() {
+        let input = "vn 0 0 1
+vn 0.707 0 -0.707
+vn 1 2 3";
+        let import = ObjImport::parse(input).unwrap();
+        assert_eq!(
+            &vec![V![0., 0., 1.], V![0.707, 0., -0.707], V![1., 2., 3.]],
+            import.normals()
+        );
+    }
+
+    #[test]
+    fn test_faces_with_normals() {
+        let input = "v 0 1 0
+v -1 0 0
+v 1 0 0
+
+vn -1 0 0
+vn 1 0 0
+vn 0 1 0
+
+f 1//3 2//1 3//2
+f 1/0/3 2/102/1 3/14/2
+f -3//-1 -2//-3 -1//-2";
+        let import = ObjImport::parse(input).unwrap();
+        let m = mesh(import.group(DEFAULT_GROUP).unwrap());
+        assert_eq!(3, m.faces().len());
+
+        let (v, n) = (import.vertices(), import.normals());
+        for (face, corners) in m.faces().iter().enumerate() {
+            assert_eq!([v[0], v[1], v[2]], m.corners(face));
+            assert_eq!([n[2], n[0], n[1]], corners.map(|i| m.normals()[i]));
+        }
+        // every face uses the same three corners with the same normals.
+        assert_eq!(3, m.vertices().len());
+    }
+
+    #[test]
+    fn test_flat_and_smooth_faces() {
+        let input = "v 0 1 0
+v -1 0 0
+v 1 0 0
+vn 0 0 -1
+
+f 1 2 3
+f 1//1 2//1 3//1
+f 1 3 2";
+        let import = ObjImport::parse(input).unwrap();
+        let g = import.group(DEFAULT_GROUP).unwrap();
+        let meshes: Vec<&Mesh> = g
+            .children()
+            .iter()
+            .map(|child| child.as_any().downcast_ref::<Mesh>().unwrap())
+            .collect();
+
+        // a mesh is either flat or smooth, so the group has one of each.
+        assert_eq!(2, meshes.len());
+        assert_eq!(2, meshes[0].faces().len());
+        assert!(meshes[0].normals().is_empty());
+        assert_eq!(1, meshes[1].faces().len());
+        assert_eq!(3, meshes[1].normals().len());
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = ObjImport::parse("v 1 2").unwrap_err();
+        assert_eq!(
+            ImportError::Parse {
+                line: 1,
+                message: "expected 3 numbers".to_string()
+            },
+            err
+        );
+
+        let err = ObjImport::parse("v 1 2 3\nv 1 2 3\nv 1 2 3\nf 1 2 4").unwrap_err();
+        assert!(matches!(err, ImportError::Parse { line: 4, .. }));
+
+        let err = ObjImport::parse("v 1 2 3\nf 1 1").unwrap_err();
+        assert!(matches!(err, ImportError::Parse { line: 2, .. }));
+
+        assert!(matches!(
+            ObjImport::load("does/not/exist.obj"),
+            Err(ImportError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_usemtl() {
+        let materials = MtlLibrary::parse("newmtl red\nKd 1 0 0").unwrap();
+        let input = "v 0 1 0
+v -1 0 0
+v 1 0 0
+
+f 1 2 3
+usemtl red
+f 1 2 3
+f 1 3 2
+usemtl missing
+f 1 2 3";
+        let import = ObjImport::parse_with_materials(input, materials).unwrap();
+        let m = mesh(import.group(DEFAULT_GROUP).unwrap());
+
+        assert_eq!(&Material::default(), m.face_material(0));
+        assert_eq!(C![1., 0., 0.], m.face_material(1).color());
+        assert_eq!(&Material::default(), m.face_material(3));
+        assert_eq!(1, import.ignored());
+        // faces using the same material share it rather than each holding a copy.
+        assert!(std::ptr::eq(m.face_material(1), m.face_material(2)));
+    }
+
+    #[test]
+    fn test_mtllib() {
+        let dir = std::env::temp_dir().join(format!("obj_mtllib_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("colors.mtl"), "newmtl green\nKd 0 1 0\nNi 1.5").unwrap();
+        fs::write(
+            dir.join("model.obj"),
+            "mtllib colors.mtl\nv 0 1 0\nv -1 0 0\nv 1 0 0\nusemtl green\nf 1 2 3",
+        )
+        .unwrap();
+
+        let import = ObjImport::load(dir.join("model.obj"));
+        fs::remove_dir_all(&dir).unwrap();
+        let import = import.unwrap();
+
+        assert_eq!(1, import.materials().len());
+        assert_eq!(&vec![dir.join("colors.mtl")], import.material_libraries());
+        let m = mesh(import.group(DEFAULT_GROUP).unwrap()).face_material(0);
+        assert_eq!(C![0., 1., 0.], m.color());
+        assert_eq!(1.5, m.refractive_index());
+    }
+
+    #[test]
+    fn test_missing_mtllib() {
+        let dir = std::env::temp_dir().join(format!("obj_missing_mtllib_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("model.obj"),
+            "mtllib does/not/exist.mtl\nv 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 3",
+        )
+        .unwrap();
+        let import = ObjImport::load(dir.join("model.obj"));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let import = import.unwrap();
+        assert_eq!(1, mesh(import.group(DEFAULT_GROUP).unwrap()).faces().len());
+        assert!(import.materials().is_empty());
+        assert!(import.material_libraries().is_empty());
+        assert_eq!(1, import.warnings().len());
+        let skipped = format!(
+            "line 1: skipped material library '{}'",
+            dir.join("does/not/exist.mtl").display()
+        );
+        assert!(import.warnings()[0].starts_with(&skipped));
+    }
+
+    #[test]
+    fn test_parse_reads_no_files() {
+        // a string has no directory to find libraries in, so even one that exists isn't read.
+        let import =
+            ObjImport::parse("mtllib /dev/zero\nv 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 3").unwrap();
+        assert_eq!(1, mesh(import.group(DEFAULT_GROUP).unwrap()).faces().len());
+        assert!(import.material_libraries().is_empty());
+        assert_eq!(
+            &vec![
+                "line 1: skipped material library '/dev/zero' as files are only read when loading a model"
+                    .to_string()
+            ],
+            import.warnings()
+        );
+    }
+}
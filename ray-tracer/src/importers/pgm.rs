@@ -0,0 +1,97 @@
+use std::{fs, path::Path};
+
+use ndarray::Array2;
+
+use super::{netpbm, ImportError};
+
+/// PgmImport is a greyscale image read from a PGM file in the plain (P2) or binary (P5) form,
+/// such as a height map exported from a terrain editor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgmImport {
+    /// values are indexed by [x, y] with y running down the image, scaled so the file's maximum value is 1.
+    values: Array2<f64>,
+}
+
+impl PgmImport {
+    /// load reads and parses the PGM file at the given path.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ImportError> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// parse reads a PGM file from bytes.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ImportError> {
+        let image = netpbm::parse(bytes, "PGM", "P2", "P5", 1)?;
+        let values = Array2::from_shape_fn((image.width, image.height), |(x, y)| {
+            image.samples[y * image.width + x]
+        });
+        Ok(Self { values })
+    }
+
+    pub fn width(&self) -> usize {
+        self.values.shape()[0]
+    }
+
+    pub fn height(&self) -> usize {
+        self.values.shape()[1]
+    }
+
+    /// values are the brightness of each pixel from 0 to 1, indexed by [x, y].
+    pub fn values(&self) -> &Array2<f64> {
+        &self.values
+    }
+
+    pub fn into_values(self) -> Array2<f64> {
+        self.values
+    }
+}
+
+#[cfg(test)]
+mod test_pgm {
+    use super::*;
+
+    #[test]
+    fn test_parse_plain() {
+        let input = b"P2\n# a comment\n3 2\n4\n0 1 2\n3 4 9\n";
+        let import = PgmImport::parse(input).unwrap();
+        assert_eq!((3, 2), (import.width(), import.height()));
+        assert_eq!(0., import.values()[[0, 0]]);
+        assert_eq!(0.5, import.values()[[2, 0]]);
+        assert_eq!(0.75, import.values()[[0, 1]]);
+        // values over the maximum are treated as the maximum.
+        assert_eq!(1., import.values()[[2, 1]]);
+    }
+
+    #[test]
+    fn test_parse_binary() {
+        let mut input = b"P5 2 2 255\n".to_vec();
+        input.extend([0, 51, 255, 102]);
+        let import = PgmImport::parse(&input).unwrap();
+        assert_eq!(0.2, import.values()[[1, 0]]);
+        assert_eq!(1., import.values()[[0, 1]]);
+
+        // two bytes a pixel, most significant first, when the maximum is over 255.
+        let mut input = b"P5 2 1 1000\n".to_vec();
+        input.extend([0, 250, 3, 232]);
+        let import = PgmImport::parse(&input).unwrap();
+        assert_eq!(0.25, import.values()[[0, 0]]);
+        assert_eq!(1., import.values()[[1, 0]]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases: [&[u8]; 5] = [
+            b"",
+            b"P3 1 1 255 0 0 0",
+            b"P2 2 2 255 0 1 2",
+            b"P2 2 x 255",
+            b"P5 2 2 255\n\x00\x01",
+        ];
+        for input in cases {
+            assert!(
+                matches!(PgmImport::parse(input), Err(ImportError::Format(_))),
+                "{:?}",
+                String::from_utf8_lossy(input)
+            );
+        }
+    }
+}
@@ -0,0 +1,619 @@
+use std::{fs, path::Path};
+
+use super::{mesh_builder::MeshBuilder, ImportError};
+use crate::{
+    comparison::EPSILON,
+    primatives::point::Point,
+    primatives::tuple::Tuple,
+    primatives::vector::{cross, Vector},
+    shapes::{group::Group, ShapeBase},
+};
+
+/// PlyImport is the result of reading a Stanford PLY file in the ASCII or either binary form.
+/// The `vertex` element gives the points, and normals too if it has `nx`, `ny` and `nz` properties,
+/// in which case the faces are smooth. The faces are gathered into a mesh, with faces of more than three corners
+/// split into fans. Triangles with no area are skipped and any other elements are read past and ignored.
+#[derive(Debug, Clone)]
+pub struct PlyImport {
+    vertices: Vec<Point>,
+    normals: Vec<Vector>,
+    group: Group,
+    degenerate: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// Type is the type of a value in the file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Type {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Property {
+    Scalar(String, Type),
+    /// List is a count of the first type followed by that many values of the second type.
+    List(String, Type, Type),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Value is a property read from the file, kept as a float as every type fits in one.
+#[derive(Debug, Clone)]
+enum Value {
+    Scalar(f64),
+    List(Vec<f64>),
+}
+
+impl PlyImport {
+    /// load reads and parses the PLY file at the given path.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ImportError> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// parse reads a PLY file from bytes. The header says whether the rest of the file is ASCII or binary.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ImportError> {
+        let (format, elements, body) = parse_header(bytes)?;
+        let header_lines = bytes[..bytes.len() - body.len()]
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count();
+
+        let mut reader: Box<dyn Reader> = match format {
+            Format::Ascii => {
+                let text = std::str::from_utf8(body).map_err(|_| {
+                    ImportError::Format("ASCII PLY file is not valid UTF-8".to_string())
+                })?;
+                Box::new(AsciiReader::new(text, header_lines))
+            }
+            Format::BinaryLittleEndian => Box::new(BinaryReader {
+                bytes: body,
+                pos: 0,
+                big_endian: false,
+            }),
+            Format::BinaryBigEndian => Box::new(BinaryReader {
+                bytes: body,
+                pos: 0,
+                big_endian: true,
+            }),
+        };
+
+        let mut import = Self {
+            vertices: vec![],
+            normals: vec![],
+            group: Group::default(),
+            degenerate: 0,
+        };
+        let mut mesh = MeshBuilder::default();
+        for element in &elements {
+            for _ in 0..element.count {
+                let values = reader.element(element)?;
+                match element.name.as_str() {
+                    "vertex" => import.add_vertex(element, &values, reader.line())?,
+                    "face" => import.add_face(&mut mesh, element, &values, reader.line())?,
+                    _ => {}
+                }
+            }
+        }
+
+        for mesh in mesh.build() {
+            import.group.add_child(mesh.box_clone());
+        }
+        Ok(import)
+    }
+
+    pub fn vertices(&self) -> &Vec<Point> {
+        &self.vertices
+    }
+
+    /// normals are the vertex normals, which is empty if the file doesn't have them.
+    pub fn normals(&self) -> &Vec<Vector> {
+        &self.normals
+    }
+
+    pub fn group(&self) -> &Group {
+        &self.group
+    }
+
+    pub fn into_group(self) -> Group {
+        self.group
+    }
+
+    /// degenerate is how many triangles were skipped because their corners are in a line.
+    pub fn degenerate(&self) -> usize {
+        self.degenerate
+    }
+
+    fn add_vertex(
+        &mut self,
+        element: &Element,
+        values: &[Value],
+        line: usize,
+    ) -> Result<(), ImportError> {
+        let scalar = |name: &str| {
+            element
+                .properties
+                .iter()
+                .position(|p| matches!(p, Property::Scalar(n, _) if n == name))
+                .and_then(|i| match values[i] {
+                    Value::Scalar(v) => Some(v),
+                    Value::List(_) => None,
+                })
+        };
+
+        let (Some(x), Some(y), Some(z)) = (scalar("x"), scalar("y"), scalar("z")) else {
+            return Err(parse_error(line, "a vertex needs x, y and z properties"));
+        };
+        self.vertices.push(Point::new(x, y, z));
+        if let (Some(nx), Some(ny), Some(nz)) = (scalar("nx"), scalar("ny"), scalar("nz")) {
+            self.normals.push(Vector::new(nx, ny, nz));
+        }
+        Ok(())
+    }
+
+    fn add_face(
+        &mut self,
+        mesh: &mut MeshBuilder,
+        element: &Element,
+        values: &[Value],
+        line: usize,
+    ) -> Result<(), ImportError> {
+        let indices = element
+            .properties
+            .iter()
+            .zip(values)
+            .find_map(|(p, v)| match (p, v) {
+                (Property::List(name, _, _), Value::List(indices))
+                    if name == "vertex_indices" || name == "vertex_index" =>
+                {
+                    Some(indices)
+                }
+                _ => None,
+            })
+            .ok_or_else(|| parse_error(line, "a face needs a vertex_indices list"))?;
+
+        if indices.len() < 3 {
+            return Err(parse_error(line, "a face needs at least 3 vertices"));
+        }
+        let indices = indices
+            .iter()
+            .map(|&i| {
+                if i < 0. || i as usize >= self.vertices.len() || i.fract() != 0. {
+                    Err(parse_error(
+                        line,
+                        format!("{} is not the index of a vertex", i),
+                    ))
+                } else {
+                    Ok(i as usize)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let smooth = self.normals.len() == self.vertices.len();
+        for i in 1..indices.len() - 1 {
+            let [a, b, c] = [indices[0], indices[i], indices[i + 1]];
+            let [p1, p2, p3] = [self.vertices[a], self.vertices[b], self.vertices[c]];
+            if cross(p2 - p1, p3 - p1).magnitude() < EPSILON {
+                self.degenerate += 1;
+                continue;
+            }
+            let normals = smooth.then(|| [a, b, c].map(|i| self.normals[i]));
+            mesh.add_triangle([p1, p2, p3], normals, None);
+        }
+        Ok(())
+    }
+}
+
+fn parse_error(line: usize, message: impl Into<String>) -> ImportError {
+    ImportError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+/// parse_header reads the header at the start of the file, returning the format, the elements and the rest of the file.
+fn parse_header(bytes: &[u8]) -> Result<(Format, Vec<Element>, &[u8]), ImportError> {
+    const END: &[u8] = b"end_header";
+    let end = bytes
+        .windows(END.len())
+        .position(|w| w == END)
+        .ok_or_else(|| ImportError::Format("PLY file has no end_header".to_string()))?;
+    let body_start = bytes[end..]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map_or(bytes.len(), |i| end + i + 1);
+    let header = std::str::from_utf8(&bytes[..end])
+        .map_err(|_| ImportError::Format("PLY header is not valid UTF-8".to_string()))?;
+
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+    for (i, line) in header.lines().enumerate() {
+        let line_number = i + 1;
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        match parts[..] {
+            [] => {}
+            ["ply"] if line_number == 1 => {}
+            _ if line_number == 1 => {
+                return Err(ImportError::Format(
+                    "PLY file should start with 'ply'".to_string(),
+                ))
+            }
+            ["comment", ..] | ["obj_info", ..] => {}
+            ["format", kind, _version] => {
+                format = Some(match kind {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => {
+                        return Err(parse_error(
+                            line_number,
+                            format!("unknown format '{}'", kind),
+                        ))
+                    }
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|_| parse_error(line_number, format!("'{}' is not a count", count)))?,
+                properties: vec![],
+            }),
+            ["property", "list", count, item, name] => {
+                let property = Property::List(
+                    name.to_string(),
+                    parse_type(count, line_number)?,
+                    parse_type(item, line_number)?,
+                );
+                add_property(&mut elements, property, line_number)?;
+            }
+            ["property", kind, name] => {
+                let property = Property::Scalar(name.to_string(), parse_type(kind, line_number)?);
+                add_property(&mut elements, property, line_number)?;
+            }
+            _ => return Err(parse_error(line_number, format!("unexpected '{}'", line))),
+        }
+    }
+
+    let format = format.ok_or_else(|| ImportError::Format("PLY file has no format".to_string()))?;
+    Ok((format, elements, &bytes[body_start..]))
+}
+
+fn add_property(
+    elements: &mut [Element],
+    property: Property,
+    line: usize,
+) -> Result<(), ImportError> {
+    elements
+        .last_mut()
+        .ok_or_else(|| parse_error(line, "a property appears before any element"))?
+        .properties
+        .push(property);
+    Ok(())
+}
+
+fn parse_type(name: &str, line: usize) -> Result<Type, ImportError> {
+    Ok(match name {
+        "char" | "int8" => Type::I8,
+        "uchar" | "uint8" => Type::U8,
+        "short" | "int16" => Type::I16,
+        "ushort" | "uint16" => Type::U16,
+        "int" | "int32" => Type::I32,
+        "uint" | "uint32" => Type::U32,
+        "float" | "float32" => Type::F32,
+        "double" | "float64" => Type::F64,
+        _ => return Err(parse_error(line, format!("unknown type '{}'", name))),
+    })
+}
+
+/// Reader reads the values of elements from the body of the file.
+trait Reader {
+    fn element(&mut self, element: &Element) -> Result<Vec<Value>, ImportError>;
+    /// line is the line of the last element read, or 0 for binary files.
+    fn line(&self) -> usize;
+}
+
+/// AsciiReader reads one element from each line.
+struct AsciiReader<'a> {
+    lines: std::iter::Enumerate<std::str::Lines<'a>>,
+    first_line: usize,
+    line: usize,
+}
+
+impl<'a> AsciiReader<'a> {
+    fn new(text: &'a str, header_lines: usize) -> Self {
+        Self {
+            lines: text.lines().enumerate(),
+            first_line: header_lines + 1,
+            line: header_lines,
+        }
+    }
+}
+
+impl Reader for AsciiReader<'_> {
+    fn element(&mut self, element: &Element) -> Result<Vec<Value>, ImportError> {
+        let (line, words) = loop {
+            let Some((i, text)) = self.lines.next() else {
+                return Err(ImportError::Format(format!(
+                    "PLY file ends before all of its {} elements",
+                    element.name
+                )));
+            };
+            self.line = self.first_line + i;
+            let words: Vec<&str> = text.split_whitespace().collect();
+            if !words.is_empty() {
+                break (self.line, words);
+            }
+        };
+
+        let mut words = words.into_iter();
+        let mut next = || -> Result<f64, ImportError> {
+            let word = words.next().ok_or_else(|| {
+                parse_error(line, format!("too few values for a {}", element.name))
+            })?;
+            word.parse()
+                .map_err(|_| parse_error(line, format!("'{}' is not a number", word)))
+        };
+
+        let mut values = vec![];
+        for property in &element.properties {
+            values.push(match property {
+                Property::Scalar(..) => Value::Scalar(next()?),
+                Property::List(..) => {
+                    let count = next()?;
+                    Value::List(
+                        (0..count as usize)
+                            .map(|_| next())
+                            .collect::<Result<_, _>>()?,
+                    )
+                }
+            });
+        }
+        Ok(values)
+    }
+
+    fn line(&self) -> usize {
+        self.line
+    }
+}
+
+struct BinaryReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl BinaryReader<'_> {
+    fn value(&mut self, kind: Type) -> Result<f64, ImportError> {
+        let size = match kind {
+            Type::I8 | Type::U8 => 1,
+            Type::I16 | Type::U16 => 2,
+            Type::I32 | Type::U32 | Type::F32 => 4,
+            Type::F64 => 8,
+        };
+        let Some(b) = self.bytes.get(self.pos..self.pos + size) else {
+            return Err(ImportError::Format(
+                "binary PLY file ends before all of its elements".to_string(),
+            ));
+        };
+        self.pos += size;
+
+        let mut raw = [0; 8];
+        raw[..size].copy_from_slice(b);
+        if self.big_endian {
+            raw[..size].reverse();
+        }
+        Ok(match kind {
+            Type::I8 => raw[0] as i8 as f64,
+            Type::U8 => raw[0] as f64,
+            Type::I16 => i16::from_le_bytes([raw[0], raw[1]]) as f64,
+            Type::U16 => u16::from_le_bytes([raw[0], raw[1]]) as f64,
+            Type::I32 => i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+            Type::U32 => u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+            Type::F32 => f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+            Type::F64 => f64::from_le_bytes(raw),
+        })
+    }
+}
+
+impl Reader for BinaryReader<'_> {
+    fn element(&mut self, element: &Element) -> Result<Vec<Value>, ImportError> {
+        let mut values = vec![];
+        for property in &element.properties {
+            values.push(match property {
+                Property::Scalar(_, kind) => Value::Scalar(self.value(*kind)?),
+                Property::List(_, count, item) => {
+                    let count = self.value(*count)?;
+                    Value::List(
+                        (0..count as usize)
+                            .map(|_| self.value(*item))
+                            .collect::<Result<_, _>>()?,
+                    )
+                }
+            });
+        }
+        Ok(values)
+    }
+
+    fn line(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod test_ply {
+    use crate::{shapes::mesh::Mesh, P, V};
+
+    use super::*;
+
+    /// mesh is the mesh holding every face in the file.
+    fn mesh(import: &PlyImport) -> &Mesh {
+        assert_eq!(1, import.group().children().len());
+        import.group().children()[0]
+            .as_any()
+            .downcast_ref::<Mesh>()
+            .unwrap()
+    }
+
+    const HEADER: &str = "ply
+format ascii 1.0
+comment a square and a triangle
+element vertex 5
+property float x
+property float y
+property float z
+element face 2
+property list uchar int vertex_indices
+end_header
+";
+
+    #[test]
+    fn test_ascii() {
+        let input = format!(
+            "{}0 0 0\n1 0 0\n1 1 0\n0 1 0\n2 2 2\n4 0 1 2 3\n3 4 0 1\n",
+            HEADER
+        );
+        let import = PlyImport::parse(input.as_bytes()).unwrap();
+
+        assert_eq!(5, import.vertices().len());
+        assert_eq!(P![1., 1., 0.], import.vertices()[2]);
+        assert!(import.normals().is_empty());
+        // the square is split into two triangles.
+        assert_eq!(3, mesh(&import).faces().len());
+        assert_eq!(
+            [P![0., 0., 0.], P![1., 1., 0.], P![0., 1., 0.]],
+            mesh(&import).corners(1)
+        );
+    }
+
+    #[test]
+    fn test_normals_make_smooth_triangles() {
+        let input = "ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property uchar red
+element face 1
+property list uchar uint vertex_index
+element edge 1
+property int vertex1
+property int vertex2
+end_header
+0 1 0 0 1 0 255
+-1 0 0 -1 0 0 255
+1 0 0 1 0 0 255
+3 0 1 2
+0 1
+";
+        let import = PlyImport::parse(input.as_bytes()).unwrap();
+        assert_eq!(V![-1., 0., 0.], import.normals()[1]);
+        assert_eq!(import.normals(), mesh(&import).normals());
+    }
+
+    #[test]
+    fn test_binary() {
+        for (format, big_endian) in [("binary_little_endian", false), ("binary_big_endian", true)] {
+            let mut bytes = format!(
+                "ply\nformat {} 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty double z\nelement face 1\nproperty list uchar ushort vertex_indices\nend_header\n",
+                format
+            )
+            .into_bytes();
+            let f32_bytes = |v: f32| {
+                if big_endian {
+                    v.to_be_bytes()
+                } else {
+                    v.to_le_bytes()
+                }
+            };
+            let f64_bytes = |v: f64| {
+                if big_endian {
+                    v.to_be_bytes()
+                } else {
+                    v.to_le_bytes()
+                }
+            };
+            for [x, y, z] in [[0., 1., 0.], [-1., 0., 0.], [1., 0., 0.5]] {
+                bytes.extend(f32_bytes(x));
+                bytes.extend(f32_bytes(y));
+                bytes.extend(f64_bytes(z as f64));
+            }
+            bytes.push(3);
+            for i in [0u16, 1, 2] {
+                bytes.extend(if big_endian {
+                    i.to_be_bytes()
+                } else {
+                    i.to_le_bytes()
+                });
+            }
+
+            let import = PlyImport::parse(&bytes).unwrap();
+            assert_eq!(P![1., 0., 0.5], import.vertices()[2]);
+            assert_eq!(1, mesh(&import).faces().len());
+        }
+    }
+
+    #[test]
+    fn test_degenerate_faces_are_skipped() {
+        let input = format!(
+            "{}0 0 0\n1 0 0\n2 0 0\n0 1 0\n0 0 1\n3 0 1 2\n3 0 3 4\n",
+            HEADER
+        );
+        let import = PlyImport::parse(input.as_bytes()).unwrap();
+        assert_eq!(1, mesh(&import).faces().len());
+        assert_eq!(1, import.degenerate());
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = PlyImport::parse(b"not a ply file\nend_header\n").unwrap_err();
+        assert!(matches!(err, ImportError::Format(_)));
+
+        let err =
+            PlyImport::parse(b"ply\nformat ascii 1.0\nproperty float x\nend_header\n").unwrap_err();
+        assert!(matches!(err, ImportError::Parse { line: 3, .. }));
+
+        // the first body line is line 11.
+        let input = format!("{}0 0 0\n1 0 zero\n", HEADER);
+        assert_eq!(
+            ImportError::Parse {
+                line: 12,
+                message: "'zero' is not a number".to_string()
+            },
+            PlyImport::parse(input.as_bytes()).unwrap_err()
+        );
+
+        let input = format!("{}0 0 0\n1 0 0\n1 1 0\n0 1 0\n2 2 2\n3 0 1 9\n", HEADER);
+        assert!(matches!(
+            PlyImport::parse(input.as_bytes()).unwrap_err(),
+            ImportError::Parse { line: 16, .. }
+        ));
+
+        let input = format!("{}0 0 0\n", HEADER);
+        assert!(matches!(
+            PlyImport::parse(input.as_bytes()).unwrap_err(),
+            ImportError::Format(_)
+        ));
+    }
+}
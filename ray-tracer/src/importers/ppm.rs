@@ -0,0 +1,95 @@
+use std::{fs, path::Path};
+
+use crate::{primatives::color::Color, world::canvas::Canvas};
+
+use super::{netpbm, ImportError};
+
+/// PpmImport is a color image read from a PPM file in the plain (P3) or binary (P6) form, such as a texture or a
+/// normal map. The values are read as they are, without undoing any gamma the image was saved with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PpmImport {
+    canvas: Canvas,
+}
+
+impl PpmImport {
+    /// load reads and parses the PPM file at the given path.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ImportError> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// parse reads a PPM file from bytes, which is laid out like a PGM file with three values for each pixel.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ImportError> {
+        let image = netpbm::parse(bytes, "PPM", "P3", "P6", 3)?;
+        let mut canvas = Canvas::new(image.width, image.height);
+        for (i, rgb) in image.samples.chunks(3).enumerate() {
+            canvas.write_pixel(
+                i % image.width,
+                i / image.width,
+                Color::new(rgb[0], rgb[1], rgb[2]),
+            );
+        }
+        Ok(Self { canvas })
+    }
+
+    pub fn canvas(&self) -> &Canvas {
+        &self.canvas
+    }
+
+    pub fn into_canvas(self) -> Canvas {
+        self.canvas
+    }
+}
+
+#[cfg(test)]
+mod test_ppm {
+    use crate::C;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_plain() {
+        let input = b"P3\n# a comment\n2 2\n4\n4 0 0  0 4 0\n0 0 4  2 2 9\n";
+        let canvas = PpmImport::parse(input).unwrap().into_canvas();
+        assert_eq!((2, 2), (canvas.width(), canvas.height()));
+        assert_eq!(Some(C![1., 0., 0.]), canvas.pixel_at(0, 0));
+        assert_eq!(Some(C![0., 1., 0.]), canvas.pixel_at(1, 0));
+        assert_eq!(Some(C![0., 0., 1.]), canvas.pixel_at(0, 1));
+        assert_eq!(Some(C![0.5, 0.5, 1.]), canvas.pixel_at(1, 1));
+    }
+
+    #[test]
+    fn test_parse_binary() {
+        let mut input = b"P6 2 1 255\n".to_vec();
+        input.extend([255, 0, 51, 0, 255, 102]);
+        let canvas = PpmImport::parse(&input).unwrap().into_canvas();
+        assert_eq!(Some(C![1., 0., 0.2]), canvas.pixel_at(0, 0));
+        assert_eq!(Some(C![0., 1., 0.4]), canvas.pixel_at(1, 0));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        // a saved canvas reads back to the nearest of the 256 levels of each channel.
+        let mut canvas = Canvas::new(3, 2);
+        canvas.write_pixel(2, 1, C![1., 0.2, 0.]);
+        let mut ppm = Vec::new();
+        canvas.save(&mut ppm);
+        let read = PpmImport::parse(&ppm).unwrap().into_canvas();
+        assert_eq!(canvas, read);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let cases: [&[u8]; 3] = [
+            b"P2 1 1 255 0",
+            b"P3 2 1 255 0 0 0 0 0",
+            b"P6 1 1 255\n\x00\x01",
+        ];
+        for input in cases {
+            assert!(
+                matches!(PpmImport::parse(input), Err(ImportError::Format(_))),
+                "{:?}",
+                String::from_utf8_lossy(input)
+            );
+        }
+    }
+}
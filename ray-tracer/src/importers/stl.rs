@@ -0,0 +1,326 @@
+use std::{fs, path::Path};
+
+use super::{mesh_builder::MeshBuilder, ImportError};
+use crate::{
+    comparison::EPSILON,
+    primatives::point::Point,
+    primatives::tuple::Tuple,
+    primatives::vector::cross,
+    shapes::{group::Group, ShapeBase},
+};
+
+/// HEADER_SIZE is the 80 byte header and 4 byte triangle count at the start of a binary STL file.
+const HEADER_SIZE: usize = 84;
+/// FACET_SIZE is a normal, three vertices and a 2 byte attribute count for every triangle in a binary STL file.
+const FACET_SIZE: usize = 50;
+
+/// StlImport is the result of reading an STL file, either the ASCII or the binary form.
+/// The triangles are gathered into a mesh, with each corner held once however many triangles share it.
+/// The normals stored in the file are ignored as faces work out their own from the vertices,
+/// and triangles with no area are skipped.
+#[derive(Debug, Clone)]
+pub struct StlImport {
+    name: Option<String>,
+    group: Group,
+    degenerate: usize,
+}
+
+impl StlImport {
+    /// load reads and parses the STL file at the given path.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ImportError> {
+        Self::parse(&fs::read(path)?)
+    }
+
+    /// parse reads an STL file from bytes, working out whether it is ASCII or binary.
+    /// Binary files may also start with `solid`, so a file is only treated as binary if its size matches the triangle count in the header.
+    pub fn parse(bytes: &[u8]) -> Result<Self, ImportError> {
+        if binary_size(bytes) == Some(bytes.len())
+            || !bytes.trim_ascii_start().starts_with(b"solid")
+        {
+            Self::parse_binary(bytes)
+        } else {
+            let text = std::str::from_utf8(bytes).map_err(|_| {
+                ImportError::Format("ASCII STL file is not valid UTF-8".to_string())
+            })?;
+            Self::parse_ascii(text)
+        }
+    }
+
+    /// name is the name given after `solid` in an ASCII file.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn group(&self) -> &Group {
+        &self.group
+    }
+
+    pub fn into_group(self) -> Group {
+        self.group
+    }
+
+    /// degenerate is how many triangles were skipped because their corners are in a line.
+    pub fn degenerate(&self) -> usize {
+        self.degenerate
+    }
+
+    fn empty(name: Option<String>) -> Self {
+        Self {
+            name,
+            group: Group::default(),
+            degenerate: 0,
+        }
+    }
+
+    fn add_triangle(&mut self, mesh: &mut MeshBuilder, [p1, p2, p3]: [Point; 3]) {
+        if cross(p2 - p1, p3 - p1).magnitude() < EPSILON {
+            self.degenerate += 1;
+            return;
+        }
+        mesh.add_triangle([p1, p2, p3], None, None);
+    }
+
+    /// finish puts the mesh of every triangle read into the group.
+    fn finish(mut self, mesh: MeshBuilder) -> Self {
+        for mesh in mesh.build() {
+            self.group.add_child(mesh.box_clone());
+        }
+        self
+    }
+
+    fn parse_binary(bytes: &[u8]) -> Result<Self, ImportError> {
+        let Some(size) = binary_size(bytes) else {
+            return Err(ImportError::Format(
+                "binary STL file is too short to hold a header".to_string(),
+            ));
+        };
+        if bytes.len() < size {
+            return Err(ImportError::Format(format!(
+                "binary STL file should be {} bytes but is {}",
+                size,
+                bytes.len()
+            )));
+        }
+
+        let mut import = Self::empty(None);
+        let mut mesh = MeshBuilder::default();
+        for facet in bytes[HEADER_SIZE..size].chunks_exact(FACET_SIZE) {
+            // skip the 12 byte normal at the start of the facet.
+            let vertex = |i: usize| {
+                let start = 12 + i * 12;
+                let f = |offset: usize| {
+                    let b = &facet[start + offset..start + offset + 4];
+                    f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64
+                };
+                Point::new(f(0), f(4), f(8))
+            };
+            import.add_triangle(&mut mesh, [vertex(0), vertex(1), vertex(2)]);
+        }
+        Ok(import.finish(mesh))
+    }
+
+    fn parse_ascii(input: &str) -> Result<Self, ImportError> {
+        let mut import: Option<Self> = None;
+        let mut mesh = MeshBuilder::default();
+        let mut vertices: Vec<Point> = vec![];
+
+        for (i, line) in input.lines().enumerate() {
+            let line_number = i + 1;
+            let mut parts = line.split_whitespace();
+            let Some(keyword) = parts.next() else {
+                continue;
+            };
+            let args: Vec<&str> = parts.collect();
+
+            match (keyword, import.as_mut()) {
+                ("solid", None) => {
+                    let name = args.join(" ");
+                    import = Some(Self::empty((!name.is_empty()).then_some(name)));
+                }
+                (_, None) => return Err(parse_error(line_number, "expected 'solid'")),
+                ("vertex", Some(_)) => vertices.push(parse_vertex(&args, line_number)?),
+                ("endloop", Some(import)) => {
+                    let [p1, p2, p3] = vertices[..] else {
+                        return Err(parse_error(
+                            line_number,
+                            format!("a facet needs 3 vertices but has {}", vertices.len()),
+                        ));
+                    };
+                    import.add_triangle(&mut mesh, [p1, p2, p3]);
+                    vertices.clear();
+                }
+                ("facet" | "outer" | "endfacet", Some(_)) => {}
+                ("endsolid", Some(_)) => break,
+                (_, Some(_)) => {
+                    return Err(parse_error(
+                        line_number,
+                        format!("unexpected '{}'", keyword),
+                    ))
+                }
+            }
+        }
+
+        import
+            .map(|import| import.finish(mesh))
+            .ok_or_else(|| ImportError::Format("STL file is empty".to_string()))
+    }
+}
+
+/// binary_size is how long a binary STL file should be given the triangle count in its header.
+fn binary_size(bytes: &[u8]) -> Option<usize> {
+    let count = bytes.get(80..HEADER_SIZE)?;
+    let count = u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize;
+    Some(HEADER_SIZE + count * FACET_SIZE)
+}
+
+fn parse_error(line: usize, message: impl Into<String>) -> ImportError {
+    ImportError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+fn parse_vertex(args: &[&str], line: usize) -> Result<Point, ImportError> {
+    let values = args
+        .iter()
+        .map(|arg| {
+            arg.parse::<f64>()
+                .map_err(|_| parse_error(line, format!("'{}' is not a number", arg)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match values[..] {
+        [x, y, z] => Ok(Point::new(x, y, z)),
+        _ => Err(parse_error(line, "expected 3 numbers")),
+    }
+}
+
+#[cfg(test)]
+mod test_stl {
+    use crate::{shapes::mesh::Mesh, P};
+
+    use super::*;
+
+    /// mesh is the mesh holding every triangle in the file.
+    fn mesh(import: &StlImport) -> &Mesh {
+        assert_eq!(1, import.group().children().len());
+        import.group().children()[0]
+            .as_any()
+            .downcast_ref::<Mesh>()
+            .unwrap()
+    }
+
+    fn binary(header: &[u8], triangles: &[[[f32; 3]; 3]]) -> Vec<u8> {
+        let mut bytes = vec![0; 80];
+        bytes[..header.len()].copy_from_slice(header);
+        bytes.extend((triangles.len() as u32).to_le_bytes());
+        for t in triangles {
+            // the normal is left as zero.
+            bytes.extend([0; 12]);
+            for v in t.iter().flatten() {
+                bytes.extend(v.to_le_bytes());
+            }
+            bytes.extend([0; 2]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_ascii() {
+        let input = "solid cube corner
+  facet normal 0 0 -1
+    outer loop
+      vertex 0 1 0
+      vertex -1 0 0
+      vertex 1 0 0
+    endloop
+  endfacet
+  facet normal 0 0 -1
+    outer loop
+      vertex 0 0 0
+      vertex 1 1 0
+      vertex 2 2 0
+    endloop
+  endfacet
+endsolid cube corner";
+        let import = StlImport::parse(input.as_bytes()).unwrap();
+
+        assert_eq!(Some("cube corner"), import.name());
+        assert_eq!(1, mesh(&import).faces().len());
+        assert_eq!(1, import.degenerate());
+
+        assert_eq!(
+            [P![0., 1., 0.], P![-1., 0., 0.], P![1., 0., 0.]],
+            mesh(&import).corners(0)
+        );
+    }
+
+    #[test]
+    fn test_binary() {
+        let bytes = binary(
+            b"made by a test",
+            &[
+                [[0., 1., 0.], [-1., 0., 0.], [1., 0., 0.]],
+                [[0., 0., 1.], [0., 1., 1.], [1., 0., 1.]],
+            ],
+        );
+        let import = StlImport::parse(&bytes).unwrap();
+
+        assert_eq!(None, import.name());
+        assert_eq!(2, mesh(&import).faces().len());
+        assert_eq!(P![1., 0., 1.], mesh(&import).corners(1)[2]);
+    }
+
+    #[test]
+    fn test_binary_starting_with_solid() {
+        // many exporters write "solid" at the start of the binary header.
+        let bytes = binary(
+            b"solid but binary",
+            &[[[0., 1., 0.], [-1., 0., 0.], [1., 0., 0.]]],
+        );
+        let import = StlImport::parse(&bytes).unwrap();
+        assert_eq!(1, mesh(&import).faces().len());
+    }
+
+    #[test]
+    fn test_shared_corners() {
+        // two triangles making a square share the corners on their diagonal.
+        let bytes = binary(
+            b"",
+            &[
+                [[0., 0., 0.], [0., 1., 0.], [1., 1., 0.]],
+                [[0., 0., 0.], [1., 1., 0.], [1., 0., 0.]],
+            ],
+        );
+        let import = StlImport::parse(&bytes).unwrap();
+        assert_eq!(2, mesh(&import).faces().len());
+        assert_eq!(4, mesh(&import).vertices().len());
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = StlImport::parse(b"not an stl").unwrap_err();
+        assert!(matches!(err, ImportError::Format(_)));
+
+        let mut bytes = binary(b"", &[[[0., 1., 0.], [-1., 0., 0.], [1., 0., 0.]]]);
+        bytes.truncate(100);
+        assert!(matches!(
+            StlImport::parse(&bytes).unwrap_err(),
+            ImportError::Format(_)
+        ));
+
+        let input = "solid a\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 1\n";
+        assert_eq!(
+            ImportError::Parse {
+                line: 5,
+                message: "expected 3 numbers".to_string()
+            },
+            StlImport::parse(input.as_bytes()).unwrap_err()
+        );
+
+        let input = "solid a\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nendloop\n";
+        assert!(matches!(
+            StlImport::parse(input.as_bytes()).unwrap_err(),
+            ImportError::Parse { line: 5, .. }
+        ));
+    }
+}
@@ -0,0 +1,13 @@
+#![allow(dead_code)]
+
+// lets the paths scene! writes, which start with ray_tracer, work inside the crate too.
+extern crate self as ray_tracer;
+
+pub use scene_macro::scene;
+
+pub mod comparison;
+pub mod importers;
+pub mod primatives;
+pub mod scene_file;
+pub mod shapes;
+pub mod world;
@@ -1,39 +1,179 @@
-#![allow(dead_code)]
-
-use primatives::{
+use ray_tracer::primatives::{
     color::Color,
     transformation::{rotation_z, scaling, translation, view_transformation},
     tuple::Tuple,
 };
-use shapes::{
+use ray_tracer::scene_file::{self, Reload, WatchedScene};
+use ray_tracer::shapes::{
     material::Material,
-    patterns::{checkered::CheckeredPattern, perlin::PerlinPattern, ring::RingPattern, Pattern},
+    patterns::{
+        perlin::{PerlinPattern, Turbulence},
+        ring::RingPattern,
+        wood::WoodPattern,
+        PatternBase,
+    },
     plane::Plane,
     sphere::Sphere,
-    Shape,
+    Shape, ShapeBase,
+};
+use ray_tracer::world::{
+    camera::{parse_duration, Camera},
+    canvas::{parse_tone_map, parse_white_balance, SaveOptions},
+    depth::{parse_depth_range, DepthRange},
+    light::{LightBase, PointLight},
+    scene::Scene,
+    World,
+};
+use ray_tracer::{C, P, V};
+use std::{
+    env,
+    f64::consts::PI,
+    fs::File,
+    io::BufWriter,
+    process, thread,
+    time::{Duration, Instant},
 };
-use std::{f64::consts::PI, fs::File};
-use world::{camera::Camera, light::PointLight, World};
 
-mod comparison;
-mod primatives;
-mod shapes;
-mod world;
+/// WATCH_INTERVAL is how often --watch checks whether the scene file has been saved.
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Output is how the command line asked for each image to be rendered and saved.
+struct Output {
+    budget: Option<Duration>,
+    adaptive: Option<f64>,
+    depth: Option<DepthRange>,
+    options: SaveOptions,
+}
 
 fn main() {
-    let circle_name = "images/circle.ppm";
-    first_sphere(circle_name);
+    // --time-budget 60s renders the best image it can in that time instead of the whole image.
+    // --adaptive 0.1 takes extra samples in pixels that differ from a neighbour by more than 0.1, smoothing edges.
+    // --depth auto or --depth 1:20 also writes the depth of the scene as a png and a pfm for compositing.
+    // --scene scene.yml renders a scene file, or a scene packed into a .rtscene.zip, instead of the built in scene.
+    // --scene scene.yml --pack scene.rtscene.zip packs the scene and the files it uses into one archive to share.
+    // --scene scene.yml --watch renders the scene again every time the file is saved, until stopped.
+    // --white-balance tungsten or --white-balance 4000:0.3 makes light of that temperature and tint look white.
+    // --exposure 1.5 brightens the image by that many stops, or darkens it if negative.
+    // --tone-map reinhard or --tone-map aces brings highlights brighter than white back into range instead of clipping.
+    let mut budget = None;
+    let mut adaptive = None;
+    let mut options = SaveOptions::builder();
+    let mut depth = None;
+    let mut scene = None;
+    let mut pack = None;
+    let mut watch = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--time-budget" => {
+                let value = args.next().unwrap_or_default();
+                budget = Some(parse_duration(&value).unwrap_or_else(|err| {
+                    eprintln!("error: --time-budget {}", err);
+                    process::exit(2);
+                }));
+            }
+            "--adaptive" => {
+                let value = args.next().unwrap_or_default();
+                adaptive = Some(value.parse::<f64>().unwrap_or_else(|_| {
+                    eprintln!("error: --adaptive needs a threshold such as 0.1");
+                    process::exit(2);
+                }));
+            }
+            "--depth" => {
+                let value = args.next().unwrap_or_default();
+                depth = Some(parse_depth_range(&value).unwrap_or_else(|err| {
+                    eprintln!("error: --depth {}", err);
+                    process::exit(2);
+                }));
+            }
+            "--white-balance" => {
+                let value = args.next().unwrap_or_default();
+                let white_balance = parse_white_balance(&value).unwrap_or_else(|err| {
+                    eprintln!("error: --white-balance {}", err);
+                    process::exit(2);
+                });
+                options.white_balance(white_balance);
+            }
+            "--exposure" => {
+                let value = args.next().unwrap_or_default();
+                options.exposure(value.parse::<f64>().unwrap_or_else(|_| {
+                    eprintln!("error: --exposure needs a number of stops such as 1.5");
+                    process::exit(2);
+                }));
+            }
+            "--tone-map" => {
+                let value = args.next().unwrap_or_default();
+                options.tone_map(parse_tone_map(&value).unwrap_or_else(|err| {
+                    eprintln!("error: --tone-map {}", err);
+                    process::exit(2);
+                }));
+            }
+            "--scene" => scene = Some(args.next().unwrap_or_default()),
+            "--pack" => pack = Some(args.next().unwrap_or_default()),
+            "--watch" => watch = true,
+            other => {
+                eprintln!("error: unknown argument '{}'", other);
+                process::exit(2);
+            }
+        }
+    }
+
+    let output = Output {
+        budget,
+        adaptive,
+        depth,
+        options: options.build().unwrap(),
+    };
+
+    if let Some(archive) = pack {
+        let Some(scene) = scene else {
+            eprintln!("error: --pack needs a --scene to pack");
+            process::exit(2);
+        };
+        scene_file::pack(&scene, &archive).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        });
+        return;
+    }
 
     let scene_name = "images/scene.ppm";
-    first_scene(scene_name);
+    if watch {
+        let Some(scene) = scene else {
+            eprintln!("error: --watch needs a --scene to watch");
+            process::exit(2);
+        };
+        watch_scene(&scene, scene_name, &output);
+    }
+    if let Some(scene) = scene {
+        let scene = scene_file::load(&scene).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        });
+        render(scene_name, &scene, &output);
+        return;
+    }
+
+    let circle_name = "images/circle.ppm";
+    first_sphere(circle_name);
+    first_scene(scene_name, &output);
 }
 
-fn first_scene(file_name: &str) {
+fn first_scene(file_name: &str, output: &Output) {
     let floor = Plane::new(
         None,
         Some(
             Material::builder()
-                .pattern(CheckeredPattern::new(Color::WHITE, Color::BLACK, None).box_clone())
+                // the grain runs along x, like boards laid across the view.
+                .pattern(
+                    WoodPattern::new(
+                        C![0.76, 0.55, 0.33],
+                        C![0.45, 0.27, 0.12],
+                        0.3,
+                        Some(rotation_z(PI / 2.)),
+                    )
+                    .box_clone(),
+                )
                 .color(C![0.1, 1., 0.5])
                 .diffuse(0.7)
                 .ambient(0.1)
@@ -57,6 +197,7 @@ fn first_scene(file_name: &str) {
                             Some(rotation_z(PI / 3.0) * translation(-0.1, 0.1, 0.4)),
                         )
                         .box_clone(),
+                        Turbulence::default(),
                         None,
                         None,
                     )
@@ -120,7 +261,7 @@ fn first_scene(file_name: &str) {
             left.box_clone(),
             right.box_clone(),
         ],
-        Some(light),
+        Some(light.box_clone()),
     );
 
     let mut camera = Camera::new(1000, 500, PI / 3.);
@@ -131,9 +272,92 @@ fn first_scene(file_name: &str) {
         V![0., 1., 0.],
     ));
 
-    let canvas = camera.render(world);
-    let mut image_file = File::create(file_name).expect("unable to create file");
-    canvas.save(&mut image_file)
+    render(file_name, &Scene::new(world, camera), output);
+}
+
+/// watch_scene renders the scene file and then renders it again each time it is saved, never returning.
+/// An edit that only changes materials or lights is applied to the scene already loaded, which is much quicker
+/// than loading it again for scenes with large models. A file saved with a mistake is reported and the last
+/// scene that loaded is kept until the mistake is fixed.
+fn watch_scene(path: &str, file_name: &str, output: &Output) -> ! {
+    let mut watched = WatchedScene::load(path).unwrap_or_else(|err| {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    });
+    loop {
+        render(file_name, watched.scene(), output);
+        eprintln!("wrote {}, watching {} for changes", file_name, path);
+        loop {
+            thread::sleep(WATCH_INTERVAL);
+            if !watched.changed() {
+                continue;
+            }
+            let start = Instant::now();
+            match watched.reload() {
+                Ok(Reload::Unchanged) => {}
+                Ok(Reload::Look) => {
+                    eprintln!("reloaded materials and lights in {:?}", start.elapsed());
+                    break;
+                }
+                Ok(Reload::Full) => {
+                    eprintln!("reloaded the scene in {:?}", start.elapsed());
+                    break;
+                }
+                Err(err) => eprintln!("error: {}", err),
+            }
+        }
+    }
+}
+
+/// render writes the image the scene's first camera sees, within the budget or sampled adaptively if either is given,
+/// and its depth if a range is given, next to file_name. Settings of the scene that look like mistakes are warned
+/// about first.
+fn render(file_name: &str, scene: &Scene, output: &Output) {
+    for warning in scene.settings().warnings() {
+        eprintln!("warning: {}", warning);
+    }
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut image_file = BufWriter::new(File::create(file_name).expect("unable to create file"));
+    let options = &output.options;
+    let image = match (output.budget, output.adaptive) {
+        (Some(budget), _) => Some(scene.render_progressive(0, threads, budget)),
+        (None, Some(threshold)) => Some(scene.render_adaptive(0, threads, threshold)),
+        (None, None) => {
+            if let Err(err) = scene.render_to_with_options(0, threads, &mut image_file, options) {
+                eprintln!("error: unable to write image: {}", err);
+                process::exit(1);
+            }
+            None
+        }
+    };
+    match image {
+        Some(Ok(image)) => image.save_with_options(&mut image_file, options),
+        Some(Err(err)) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+        None => {}
+    }
+
+    if let Some(range) = output.depth {
+        let depths = scene.render_depth(0).unwrap_or_else(|err| {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        });
+        let base = file_name.trim_end_matches(".ppm");
+        let mut png = BufWriter::new(
+            File::create(format!("{}-depth.png", base)).expect("unable to create file"),
+        );
+        depths
+            .save_png(&mut png, range)
+            .expect("unable to write depth image");
+        let mut pfm = BufWriter::new(
+            File::create(format!("{}-depth.pfm", base)).expect("unable to create file"),
+        );
+        depths
+            .save_pfm(&mut pfm)
+            .expect("unable to write depth image");
+    }
 }
 
 fn first_sphere(file_name: &str) {
@@ -153,7 +377,7 @@ fn first_sphere(file_name: &str) {
 
     let light = PointLight::new(P![-10., 10., -10.], Color::WHITE);
 
-    let world = World::new(vec![s.box_clone()], Some(light));
+    let world = World::new(vec![s.box_clone()], Some(light.box_clone()));
 
     let mut camera = Camera::new(300, 300, PI / 3.);
     camera.set_transform(view_transformation(
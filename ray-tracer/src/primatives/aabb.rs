@@ -0,0 +1,293 @@
+use super::{matrix::Matrix, point::Point, ray::Ray, tuple::Tuple};
+use crate::comparison::{approx_eq, EPSILON};
+
+/// Aabb is an axis aligned bounding box, the smallest box lined up with the axes that a shape fits inside.
+/// Sides can be infinite for shapes such as planes that go on forever.
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    min: Point,
+    max: Point,
+}
+
+impl Aabb {
+    pub fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    /// empty is a box with nothing in it. Adding a point to it gives a box around just that point.
+    pub fn empty() -> Self {
+        Self::new(
+            Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        )
+    }
+
+    /// infinite is a box around everything, for shapes whose size isn't known.
+    pub fn infinite() -> Self {
+        Self::new(
+            Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        )
+    }
+
+    /// around is the smallest box holding all of the points.
+    pub fn around(points: impl IntoIterator<Item = Point>) -> Self {
+        points
+            .into_iter()
+            .fold(Self::empty(), |bounds, point| bounds.add_point(point))
+    }
+
+    pub fn min(&self) -> Point {
+        self.min
+    }
+
+    pub fn max(&self) -> Point {
+        self.max
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x() > self.max.x() || self.min.y() > self.max.y() || self.min.z() > self.max.z()
+    }
+
+    /// is_finite checks that none of the box's sides are infinite.
+    pub fn is_finite(&self) -> bool {
+        [
+            self.min.x(),
+            self.min.y(),
+            self.min.z(),
+            self.max.x(),
+            self.max.y(),
+            self.max.z(),
+        ]
+        .iter()
+        .all(|n| n.is_finite())
+    }
+
+    /// surface_area is the total area of the six sides of the box.
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() {
+            return 0.;
+        }
+        let size = self.max - self.min;
+        2. * (size.x() * size.y() + size.y() * size.z() + size.z() * size.x())
+    }
+
+    /// add_point grows the box just enough to hold the point.
+    pub fn add_point(&self, point: Point) -> Self {
+        Self::new(
+            Point::new(
+                self.min.x().min(point.x()),
+                self.min.y().min(point.y()),
+                self.min.z().min(point.z()),
+            ),
+            Point::new(
+                self.max.x().max(point.x()),
+                self.max.y().max(point.y()),
+                self.max.z().max(point.z()),
+            ),
+        )
+    }
+
+    /// merge is the smallest box holding both boxes.
+    pub fn merge(&self, other: &Aabb) -> Self {
+        if other.is_empty() {
+            return *self;
+        }
+        self.add_point(other.min).add_point(other.max)
+    }
+
+    pub fn contains_point(&self, point: Point) -> bool {
+        (self.min.x()..=self.max.x()).contains(&point.x())
+            && (self.min.y()..=self.max.y()).contains(&point.y())
+            && (self.min.z()..=self.max.z()).contains(&point.z())
+    }
+
+    /// transform is the box around this box after it has been transformed. Rather than transforming the eight corners
+    /// it follows Arvo's method of taking the smaller and larger of each matrix entry times each side,
+    /// which skips entries that are 0 so that infinite sides stay infinite without turning into NaN.
+    pub fn transform(&self, transform: &Matrix) -> Self {
+        if self.is_empty() {
+            return *self;
+        }
+        let entry = |row, col| *transform.get(row, col).unwrap();
+        let min = [self.min.x(), self.min.y(), self.min.z()];
+        let max = [self.max.x(), self.max.y(), self.max.z()];
+        let mut new_min = [0.; 3];
+        let mut new_max = [0.; 3];
+        for row in 0..3 {
+            new_min[row] = entry(row, 3);
+            new_max[row] = entry(row, 3);
+            for col in 0..3 {
+                let m = entry(row, col);
+                if m == 0. {
+                    continue;
+                }
+                let (a, b) = (m * min[col], m * max[col]);
+                new_min[row] += a.min(b);
+                new_max[row] += a.max(b);
+            }
+        }
+        Self::new(
+            Point::new(new_min[0], new_min[1], new_min[2]),
+            Point::new(new_max[0], new_max[1], new_max[2]),
+        )
+    }
+
+    /// intersects checks whether the ray passes through the box anywhere in front of its origin.
+    pub fn intersects(&self, r: Ray) -> bool {
+        self.span(r).is_some()
+    }
+
+    /// span is how far along the ray it enters and leaves the box, found by working out where it crosses each pair
+    /// of sides and taking where those spans overlap. Boxes that are missed or are behind the ray have no span.
+    pub fn span(&self, r: Ray) -> Option<(f64, f64)> {
+        if self.is_empty() {
+            return None;
+        }
+        let (o, d) = (r.origin(), r.direction());
+        let axes = [
+            (o.x(), d.x(), self.min.x(), self.max.x()),
+            (o.y(), d.y(), self.min.y(), self.max.y()),
+            (o.z(), d.z(), self.min.z(), self.max.z()),
+        ];
+        let mut t_min = f64::NEG_INFINITY;
+        let mut t_max = f64::INFINITY;
+        for (origin, direction, min, max) in axes {
+            if direction.abs() < EPSILON {
+                // the ray runs alongside these sides so it is either always between them or never.
+                if origin < min - EPSILON || origin > max + EPSILON {
+                    return None;
+                }
+                continue;
+            }
+            let (a, b) = ((min - origin) / direction, (max - origin) / direction);
+            t_min = t_min.max(a.min(b));
+            t_max = t_max.min(a.max(b));
+        }
+        (t_min <= t_max + EPSILON && t_max >= -EPSILON).then_some((t_min, t_max))
+    }
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl PartialEq for Aabb {
+    fn eq(&self, other: &Self) -> bool {
+        let same = |a: f64, b: f64| a == b || approx_eq(a, b);
+        same(self.min.x(), other.min.x())
+            && same(self.min.y(), other.min.y())
+            && same(self.min.z(), other.min.z())
+            && same(self.max.x(), other.max.x())
+            && same(self.max.y(), other.max.y())
+            && same(self.max.z(), other.max.z())
+    }
+}
+
+#[cfg(test)]
+mod test_aabb {
+    use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_4, SQRT_2};
+
+    use crate::{
+        primatives::transformation::{rotation_x, rotation_y, scaling, translation},
+        P, V,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_add_point() {
+        let b = Aabb::empty();
+        assert!(b.is_empty());
+
+        let b = b.add_point(P![-5., 2., 0.]).add_point(P![7., 0., -3.]);
+        assert!(!b.is_empty());
+        assert_eq!(P![-5., 0., -3.], b.min());
+        assert_eq!(P![7., 2., 0.], b.max());
+        assert_eq!(b, Aabb::around([P![-5., 2., 0.], P![7., 0., -3.]]));
+    }
+
+    #[test]
+    fn test_merge() {
+        let a = Aabb::new(P![-5., -2., 0.], P![7., 4., 4.]);
+        let b = Aabb::new(P![8., -7., -2.], P![14., 2., 8.]);
+        assert_eq!(Aabb::new(P![-5., -7., -2.], P![14., 4., 8.]), a.merge(&b));
+        assert_eq!(a, a.merge(&Aabb::empty()));
+    }
+
+    #[test]
+    fn test_surface_area() {
+        assert_eq!(0., Aabb::empty().surface_area());
+        let b = Aabb::new(P![-1., 0., 2.], P![1., 3., 6.]);
+        assert_eq!(2. * (2. * 3. + 3. * 4. + 4. * 2.), b.surface_area());
+        // a flat box only has its top and bottom.
+        let flat = Aabb::new(P![0., 0., 0.], P![2., 0., 3.]);
+        assert_eq!(12., flat.surface_area());
+    }
+
+    #[test]
+    fn test_contains_point() {
+        let b = Aabb::new(P![5., -2., 0.], P![11., 4., 7.]);
+        assert!(b.contains_point(P![5., -2., 0.]));
+        assert!(b.contains_point(P![11., 4., 7.]));
+        assert!(b.contains_point(P![8., 1., 3.]));
+        assert!(!b.contains_point(P![3., 0., 3.]));
+        assert!(!b.contains_point(P![8., -4., 3.]));
+        assert!(!b.contains_point(P![8., 1., 8.]));
+    }
+
+    #[test]
+    fn test_transform() {
+        let b = Aabb::new(P![-1., -1., -1.], P![1., 1., 1.]);
+        let t = b.transform(&(rotation_x(FRAC_PI_4) * rotation_y(FRAC_PI_4)));
+        let (x, yz) = (SQRT_2, 1. + FRAC_1_SQRT_2);
+        assert_eq!(P![-x, -yz, -yz], t.min());
+        assert_eq!(P![x, yz, yz], t.max());
+
+        let t = b.transform(&(translation(1., 2., 3.) * scaling(2., 2., 2.)));
+        assert_eq!(Aabb::new(P![-1., 0., 1.], P![3., 4., 5.]), t);
+
+        // infinite sides stay infinite rather than becoming NaN.
+        let plane = Aabb::new(
+            P![f64::NEG_INFINITY, 0., f64::NEG_INFINITY],
+            P![f64::INFINITY, 0., f64::INFINITY],
+        );
+        let t = plane.transform(&translation(0., 1., 0.));
+        assert_eq!(
+            Aabb::new(
+                P![f64::NEG_INFINITY, 1., f64::NEG_INFINITY],
+                P![f64::INFINITY, 1., f64::INFINITY]
+            ),
+            t
+        );
+    }
+
+    #[test]
+    fn test_intersects() {
+        let b = Aabb::new(P![-1., -1., -1.], P![1., 1., 1.]);
+        let cases = [
+            (P![5., 0.5, 0.], V![-1., 0., 0.], true),
+            (P![-5., 0.5, 0.], V![1., 0., 0.], true),
+            (P![0.5, 5., 0.], V![0., -1., 0.], true),
+            (P![0., 0.5, 0.], V![0., 0., 1.], true),
+            (P![-2., 0., 0.], V![2., 4., 6.].norm(), false),
+            (P![0., -2., 0.], V![6., 2., 4.].norm(), false),
+            (P![2., 0., 2.], V![0., 0., -1.], false),
+            (P![2., 2., 0.], V![-1., 0., 0.], false),
+            // the box is behind the ray.
+            (P![5., 0.5, 0.], V![1., 0., 0.], false),
+        ];
+        for (origin, direction, expected) in cases {
+            let r = Ray::new(origin, direction);
+            assert_eq!(expected, b.intersects(r), "{:?}", r);
+        }
+
+        // flat boxes can still be hit.
+        let flat = Aabb::new(P![-1., 0., -1.], P![1., 0., 1.]);
+        assert!(flat.intersects(Ray::new(P![0., 1., 0.], V![0., -1., 0.])));
+        assert!(!Aabb::empty().intersects(Ray::new(P![0., 1., 0.], V![0., -1., 0.])));
+        assert!(Aabb::infinite().intersects(Ray::new(P![0., 1., 0.], V![0., -1., 0.])));
+    }
+}
@@ -0,0 +1,239 @@
+use core::fmt;
+use std::any::Any;
+
+use super::{aabb::Aabb, bvh::Bvh, grid::Grid, kdtree::KdTree, ray::Ray};
+
+/// Accelerator finds which of a list of items a ray might hit, so that only those need to be intersected.
+/// Items are only known by their index and the box around them, so the items themselves stay wherever they are kept.
+pub trait Accelerator: Any + fmt::Debug + Send + Sync {
+    fn box_clone(&self) -> BoxedAccelerator;
+    fn box_eq(&self, other: &dyn Any) -> bool;
+    fn as_any(&self) -> &dyn Any;
+    /// len is how many items the accelerator was built over.
+    fn len(&self) -> usize;
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// visit calls f once with the index of every item whose box the ray passes through.
+    fn visit(&self, r: Ray, f: &mut dyn FnMut(usize));
+}
+
+pub type BoxedAccelerator = Box<dyn Accelerator>;
+
+impl Clone for BoxedAccelerator {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+impl PartialEq for BoxedAccelerator {
+    fn eq(&self, other: &Self) -> bool {
+        self.box_eq(other.as_any())
+    }
+}
+
+/// Acceleration picks which accelerator to build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Acceleration {
+    /// Naive tests every item against every ray. It is only worth it for a handful of items.
+    Naive,
+    /// Bvh is a tree of boxes, which is quick to trace for most scenes.
+    #[default]
+    Bvh,
+    /// Grid splits the scene into equal cells. It is much quicker to build than the trees, which suits scenes
+    /// that change every frame.
+    Grid,
+    /// KdTree splits space where it is cheapest to trace. It is the slowest to build but the quickest to trace
+    /// for scenes that don't change.
+    KdTree,
+}
+
+impl Acceleration {
+    pub fn build(&self, bounds: Vec<Aabb>) -> BoxedAccelerator {
+        match self {
+            Acceleration::Naive => Box::new(Naive::build(bounds)),
+            Acceleration::Bvh => Box::new(Bvh::build(bounds)),
+            Acceleration::Grid => Box::new(Grid::build(bounds)),
+            Acceleration::KdTree => Box::new(KdTree::build(bounds)),
+        }
+    }
+}
+
+/// Naive visits every item the ray could hit by checking the box of each one in turn.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Naive {
+    bounds: Vec<Aabb>,
+}
+
+impl Naive {
+    pub fn build(bounds: Vec<Aabb>) -> Self {
+        Self { bounds }
+    }
+}
+
+impl Accelerator for Naive {
+    fn box_clone(&self) -> BoxedAccelerator {
+        Box::new(self.clone())
+    }
+
+    fn box_eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<Self>().is_some_and(|a| self == a)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    fn visit(&self, r: Ray, f: &mut dyn FnMut(usize)) {
+        for (item, bounds) in self.bounds.iter().enumerate() {
+            if bounds.intersects(r) {
+                f(item);
+            }
+        }
+    }
+}
+
+/// STACK_SIZE is how many nodes a TraversalStack holds before it has to allocate.
+const STACK_SIZE: usize = 64;
+
+/// TraversalStack is the stack of nodes still to visit while walking a tree. It holds its first STACK_SIZE nodes
+/// inline, so walking a tree of any sensible depth never allocates; only very deep trees spill onto the heap.
+pub(crate) struct TraversalStack<T> {
+    inline: [T; STACK_SIZE],
+    len: usize,
+    spill: Vec<T>,
+}
+
+impl<T: Copy + Default> TraversalStack<T> {
+    pub(crate) fn new(first: T) -> Self {
+        let mut stack = Self {
+            inline: [T::default(); STACK_SIZE],
+            len: 0,
+            spill: Vec::new(),
+        };
+        stack.push(first);
+        stack
+    }
+
+    pub(crate) fn push(&mut self, node: T) {
+        if self.len < STACK_SIZE {
+            self.inline[self.len] = node;
+            self.len += 1;
+        } else {
+            self.spill.push(node);
+        }
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        if let Some(node) = self.spill.pop() {
+            return Some(node);
+        }
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        Some(self.inline[self.len])
+    }
+}
+
+/// fixtures are the boxes and helpers shared by the tests of each accelerator.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use crate::{primatives::tuple::Tuple, P, V};
+
+    use super::*;
+
+    /// row is n unit boxes along the x axis, one every 3 units.
+    pub(crate) fn row(n: usize) -> Vec<Aabb> {
+        (0..n)
+            .map(|i| {
+                let x = 3. * i as f64;
+                Aabb::new(P![x, 0., 0.], P![x + 1., 1., 1.])
+            })
+            .collect()
+    }
+
+    /// scattered is a jumble of boxes of different sizes, some overlapping, along with a plane.
+    pub(crate) fn scattered() -> Vec<Aabb> {
+        let mut bounds: Vec<Aabb> = (0..60)
+            .map(|i| {
+                let i = i as f64;
+                let min = P![(i * 7.3) % 11., (i * 3.7) % 5., (i * 5.1) % 13.];
+                let size = 0.2 + (i * 1.9) % 1.5;
+                Aabb::new(min, min + V![size, size * 0.5, size * 2.])
+            })
+            .collect();
+        bounds.push(Aabb::new(
+            P![f64::NEG_INFINITY, -1., f64::NEG_INFINITY],
+            P![f64::INFINITY, -1., f64::INFINITY],
+        ));
+        bounds
+    }
+
+    /// visited is the index of every item the accelerator visits along the ray, in order.
+    pub(crate) fn visited(accelerator: &dyn Accelerator, r: Ray) -> Vec<usize> {
+        let mut items = vec![];
+        accelerator.visit(r, &mut |i| items.push(i));
+        items.sort();
+        items
+    }
+}
+
+#[cfg(test)]
+mod test_accelerator {
+    use crate::{primatives::tuple::Tuple, P, V};
+
+    use super::{fixtures::*, *};
+
+    #[test]
+    fn test_traversal_stack() {
+        let mut stack = TraversalStack::new(0);
+        for i in 1..STACK_SIZE * 2 {
+            stack.push(i);
+        }
+        // nodes come back last in first out, across the inline part and the spill.
+        for i in (0..STACK_SIZE * 2).rev() {
+            assert_eq!(Some(i), stack.pop());
+        }
+        assert_eq!(None, stack.pop());
+    }
+
+    #[test]
+    fn test_accelerations_agree() {
+        let bounds = scattered();
+        let naive = Acceleration::Naive.build(bounds.clone());
+        let rays = [
+            Ray::new(P![-5., 2., -5.], V![1., 0.1, 1.].norm()),
+            Ray::new(P![5., 20., 6.], V![0.01, -1., 0.02].norm()),
+            Ray::new(P![20., 1., 3.], V![-1., 0.05, 0.2].norm()),
+            Ray::new(P![5., 2., 6.], V![-0.3, 0.4, -0.8].norm()),
+            Ray::new(P![0., 2.5, -10.], V![0., 0., 1.]),
+            Ray::new(P![3., 2., 4.], V![0., 1., 0.]),
+        ];
+        for acceleration in [Acceleration::Bvh, Acceleration::Grid, Acceleration::KdTree] {
+            let accelerator = acceleration.build(bounds.clone());
+            assert_eq!(bounds.len(), accelerator.len());
+            for r in rays {
+                assert_eq!(
+                    visited(naive.as_ref(), r),
+                    visited(accelerator.as_ref(), r),
+                    "{:?} {:?}",
+                    acceleration,
+                    r
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_box_eq() {
+        let bounds = scattered();
+        let tree = Acceleration::KdTree.build(bounds.clone());
+        assert!(tree.box_eq(Acceleration::KdTree.build(bounds.clone()).as_any()));
+        assert!(!tree.box_eq(Acceleration::Bvh.build(bounds).as_any()));
+    }
+}
@@ -0,0 +1,236 @@
+use std::any::Any;
+
+use super::{
+    aabb::Aabb,
+    accelerator::{Accelerator, BoxedAccelerator, TraversalStack},
+    point::Point,
+    ray::Ray,
+    tuple::Tuple,
+};
+
+/// LEAF_SIZE is the most items a node holds before it is split in two.
+const LEAF_SIZE: usize = 4;
+
+/// Bvh is a bounding volume hierarchy, a tree of boxes over a list of items where every node's box holds
+/// everything beneath it. A ray only has to look at the items under the boxes it passes through,
+/// rather than at every item. The tree only holds indices, so the items themselves stay wherever they are kept.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Bvh {
+    /// bounds is the box around each item, in the same order as the items.
+    bounds: Vec<Aabb>,
+    nodes: Vec<Node>,
+    /// order holds the item indices arranged so that every leaf covers a run of it.
+    order: Vec<usize>,
+    /// unbounded are the items with infinite sides, such as planes. They can't be split sensibly
+    /// so they are kept out of the tree and checked for every ray.
+    unbounded: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    bounds: Aabb,
+    kind: NodeKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum NodeKind {
+    /// Leaf covers order[start..start + count].
+    Leaf { start: usize, count: usize },
+    /// Branch has the index of its two children in nodes.
+    Branch { left: usize, right: usize },
+}
+
+impl Bvh {
+    /// build makes the tree over items with the given boxes. Each split is made on the longest side of the box around
+    /// the centres of the items, putting half of them on each side.
+    pub fn build(bounds: Vec<Aabb>) -> Self {
+        let (mut order, unbounded): (Vec<usize>, Vec<usize>) =
+            (0..bounds.len()).partition(|&i| bounds[i].is_finite());
+        let mut nodes = vec![];
+        if !order.is_empty() {
+            let count = order.len();
+            split(&bounds, &mut order, 0, count, &mut nodes);
+        }
+        Self {
+            bounds,
+            nodes,
+            order,
+            unbounded,
+        }
+    }
+
+    /// bounds is the box around every item.
+    pub fn bounds(&self) -> Aabb {
+        self.bounds
+            .iter()
+            .fold(Aabb::empty(), |bounds, item| bounds.merge(item))
+    }
+}
+
+impl Accelerator for Bvh {
+    fn box_clone(&self) -> BoxedAccelerator {
+        Box::new(self.clone())
+    }
+
+    fn box_eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<Self>().is_some_and(|a| self == a)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    /// visit calls f with the index of every item whose box the ray passes through.
+    fn visit(&self, r: Ray, f: &mut dyn FnMut(usize)) {
+        for &item in &self.unbounded {
+            if self.bounds[item].intersects(r) {
+                f(item);
+            }
+        }
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut stack = TraversalStack::new(0);
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !node.bounds.intersects(r) {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf { start, count } => {
+                    for &item in &self.order[start..start + count] {
+                        if self.bounds[item].intersects(r) {
+                            f(item);
+                        }
+                    }
+                }
+                NodeKind::Branch { left, right } => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+    }
+}
+
+/// centre is the middle of the box along the axis, 0 for x, 1 for y and 2 for z.
+fn centre(bounds: &Aabb, axis: usize) -> f64 {
+    let (min, max) = (bounds.min(), bounds.max());
+    match axis {
+        0 => (min.x() + max.x()) / 2.,
+        1 => (min.y() + max.y()) / 2.,
+        _ => (min.z() + max.z()) / 2.,
+    }
+}
+
+/// split adds the node covering order[start..start + count] and everything beneath it, returning its index.
+fn split(
+    bounds: &[Aabb],
+    order: &mut [usize],
+    start: usize,
+    count: usize,
+    nodes: &mut Vec<Node>,
+) -> usize {
+    let items = &mut order[start..start + count];
+    let node_bounds = items
+        .iter()
+        .fold(Aabb::empty(), |b, &i| b.merge(&bounds[i]));
+    let index = nodes.len();
+    nodes.push(Node {
+        bounds: node_bounds,
+        kind: NodeKind::Leaf { start, count },
+    });
+    if count <= LEAF_SIZE {
+        return index;
+    }
+
+    let centres = items.iter().fold(Aabb::empty(), |b, &i| {
+        let item = &bounds[i];
+        b.add_point(Point::new(
+            centre(item, 0),
+            centre(item, 1),
+            centre(item, 2),
+        ))
+    });
+    let extent = centres.max() - centres.min();
+    let axis = if extent.x() >= extent.y() && extent.x() >= extent.z() {
+        0
+    } else if extent.y() >= extent.z() {
+        1
+    } else {
+        2
+    };
+    items.sort_by(|&a, &b| centre(&bounds[a], axis).total_cmp(&centre(&bounds[b], axis)));
+
+    let half = count / 2;
+    let left = split(bounds, order, start, half, nodes);
+    let right = split(bounds, order, start + half, count - half, nodes);
+    nodes[index].kind = NodeKind::Branch { left, right };
+    index
+}
+
+#[cfg(test)]
+mod test_bvh {
+    use crate::{P, V};
+
+    use super::*;
+    use crate::primatives::accelerator::fixtures::{row, visited};
+
+    #[test]
+    fn test_build() {
+        let bvh = Bvh::build(row(20));
+        assert_eq!(20, bvh.len());
+        assert_eq!(Aabb::new(P![0., 0., 0.], P![58., 1., 1.]), bvh.bounds());
+        // every item appears once in the tree.
+        let mut order = bvh.order.clone();
+        order.sort();
+        assert_eq!((0..20).collect::<Vec<_>>(), order);
+        // and is under the box of the root.
+        assert_eq!(bvh.bounds(), bvh.nodes[0].bounds);
+    }
+
+    #[test]
+    fn test_visit() {
+        let bvh = Bvh::build(row(20));
+
+        // straight down onto one box.
+        let r = Ray::new(P![30.5, 5., 0.5], V![0., -1., 0.]);
+        assert_eq!(vec![10], visited(&bvh, r));
+
+        // along the row through all of them.
+        let r = Ray::new(P![-5., 0.5, 0.5], V![1., 0., 0.]);
+        assert_eq!((0..20).collect::<Vec<_>>(), visited(&bvh, r));
+
+        // between two boxes.
+        let r = Ray::new(P![2., 5., 0.5], V![0., -1., 0.]);
+        assert!(visited(&bvh, r).is_empty());
+    }
+
+    #[test]
+    fn test_unbounded() {
+        let mut bounds = row(6);
+        bounds.push(Aabb::new(
+            P![f64::NEG_INFINITY, -1., f64::NEG_INFINITY],
+            P![f64::INFINITY, -1., f64::INFINITY],
+        ));
+        let bvh = Bvh::build(bounds);
+
+        let r = Ray::new(P![100., 5., 0.5], V![0., -1., 0.]);
+        assert_eq!(vec![6], visited(&bvh, r));
+
+        let r = Ray::new(P![3.5, 5., 0.5], V![0., -1., 0.]);
+        assert_eq!(vec![1, 6], visited(&bvh, r));
+    }
+
+    #[test]
+    fn test_empty() {
+        let bvh = Bvh::build(vec![]);
+        assert!(bvh.is_empty());
+        assert!(visited(&bvh, Ray::new(P![0., 0., 0.], V![0., 0., 1.])).is_empty());
+    }
+}
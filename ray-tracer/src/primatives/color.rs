@@ -13,7 +13,7 @@ pub struct Color {
 }
 
 impl Color {
-    pub fn new(red: f64, green: f64, blue: f64) -> Self {
+    pub const fn new(red: f64, green: f64, blue: f64) -> Self {
         Self { red, green, blue }
     }
 
@@ -27,6 +27,11 @@ impl Color {
         self.blue
     }
 
+    /// luminance is how bright the color looks, weighting each channel by how sensitive the eye is to it (Rec. 709).
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.red + 0.7152 * self.green + 0.0722 * self.blue
+    }
+
     pub const BLACK: Color = Color {
         red: 0.0,
         green: 0.0,
@@ -170,4 +175,12 @@ mod test_color {
 
         assert_eq!(res, Color::new(1.0, 4.0, 9.0))
     }
+
+    #[test]
+    fn test_luminance() {
+        assert!(approx_eq(1., Color::WHITE.luminance()));
+        assert!(approx_eq(0., Color::BLACK.luminance()));
+        // green looks much brighter than blue of the same strength.
+        assert!(Color::new(0., 1., 0.).luminance() > Color::new(0., 0., 1.).luminance());
+    }
 }
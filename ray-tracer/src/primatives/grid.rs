@@ -0,0 +1,312 @@
+use std::{any::Any, collections::HashSet};
+
+use super::{
+    aabb::Aabb,
+    accelerator::{Accelerator, BoxedAccelerator},
+    point::Point,
+    ray::Ray,
+    tuple::Tuple,
+};
+
+/// CELLS_PER_ITEM is roughly how many cells the grid has for every item in it.
+const CELLS_PER_ITEM: f64 = 3.;
+
+/// MAX_CELLS is the most cells along any one side of the grid.
+const MAX_CELLS: usize = 128;
+
+/// Grid splits the space around a list of items into equal sized cells and lists the items overlapping each cell.
+/// A ray only has to look at the items in the cells it passes through, which are found by stepping from cell to cell
+/// along the ray. It is slower to trace than a Bvh for scenes with very uneven detail but much quicker to build,
+/// as every item is simply dropped into the cells its box covers.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Grid {
+    /// bounds is the box around each item, in the same order as the items.
+    bounds: Vec<Aabb>,
+    /// extent is the box the cells fill.
+    extent: Aabb,
+    /// resolution is how many cells there are along x, y and z.
+    resolution: [usize; 3],
+    cells: Vec<Vec<usize>>,
+    /// unbounded are the items with infinite sides, such as planes, which can't be put in cells
+    /// so are checked for every ray.
+    unbounded: Vec<usize>,
+}
+
+impl Grid {
+    /// build drops every item into the cells its box covers. The number of cells grows with the number of items,
+    /// and the cells are made as close to cubes as the shape of the grid allows.
+    pub fn build(bounds: Vec<Aabb>) -> Self {
+        let (bounded, unbounded): (Vec<usize>, Vec<usize>) =
+            (0..bounds.len()).partition(|&i| bounds[i].is_finite());
+        let extent = bounded
+            .iter()
+            .fold(Aabb::empty(), |extent, &i| extent.merge(&bounds[i]));
+        if bounded.is_empty() {
+            return Self {
+                bounds,
+                extent,
+                resolution: [0; 3],
+                cells: vec![],
+                unbounded,
+            };
+        }
+
+        let resolution = resolution(&extent, bounded.len());
+        let mut grid = Self {
+            bounds,
+            extent,
+            resolution,
+            cells: vec![vec![]; resolution.iter().product()],
+            unbounded,
+        };
+        for item in bounded {
+            let (from, to) = (
+                grid.cell_of(grid.bounds[item].min()),
+                grid.cell_of(grid.bounds[item].max()),
+            );
+            for z in from[2]..=to[2] {
+                for y in from[1]..=to[1] {
+                    for x in from[0]..=to[0] {
+                        let index = grid.index([x, y, z]);
+                        grid.cells[index].push(item);
+                    }
+                }
+            }
+        }
+        grid
+    }
+
+    pub fn resolution(&self) -> [usize; 3] {
+        self.resolution
+    }
+
+    fn min(&self) -> [f64; 3] {
+        let min = self.extent.min();
+        [min.x(), min.y(), min.z()]
+    }
+
+    fn cell_size(&self) -> [f64; 3] {
+        let size = self.extent.max() - self.extent.min();
+        let size = [size.x(), size.y(), size.z()];
+        [0, 1, 2].map(|axis| size[axis] / self.resolution[axis] as f64)
+    }
+
+    /// cell_of is the cell holding the point, or the nearest cell if the point is outside of the grid.
+    fn cell_of(&self, point: Point) -> [usize; 3] {
+        let (min, size) = (self.min(), self.cell_size());
+        let point = [point.x(), point.y(), point.z()];
+        [0, 1, 2].map(|axis| {
+            if size[axis] == 0. {
+                return 0;
+            }
+            let cell = ((point[axis] - min[axis]) / size[axis]).floor().max(0.) as usize;
+            cell.min(self.resolution[axis] - 1)
+        })
+    }
+
+    fn index(&self, cell: [usize; 3]) -> usize {
+        (cell[2] * self.resolution[1] + cell[1]) * self.resolution[0] + cell[0]
+    }
+}
+
+impl Accelerator for Grid {
+    fn box_clone(&self) -> BoxedAccelerator {
+        Box::new(self.clone())
+    }
+
+    fn box_eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<Self>().is_some_and(|a| self == a)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    /// visit calls f with the index of every item whose box the ray passes through. Items are only visited once
+    /// even if they cover several of the cells the ray passes through.
+    fn visit(&self, r: Ray, f: &mut dyn FnMut(usize)) {
+        for &item in &self.unbounded {
+            if self.bounds[item].intersects(r) {
+                f(item);
+            }
+        }
+        let Some((t_enter, t_exit)) = self.extent.span(r) else {
+            return;
+        };
+
+        // step through the cells using the 3D DDA of Amanatides and Woo. For each axis t_next is how far along the ray
+        // it crosses into the next cell on that axis and t_delta how far it travels between crossings.
+        let t_start = t_enter.max(0.);
+        let mut cell = self.cell_of(r.at(t_start));
+        let (o, d) = (r.origin(), r.direction());
+        let (origin, direction) = ([o.x(), o.y(), o.z()], [d.x(), d.y(), d.z()]);
+        let (min, size) = (self.min(), self.cell_size());
+        let mut step = [0isize; 3];
+        let mut t_next = [f64::INFINITY; 3];
+        let mut t_delta = [f64::INFINITY; 3];
+        for axis in 0..3 {
+            if direction[axis] == 0. || size[axis] == 0. {
+                continue;
+            }
+            let forwards = direction[axis] > 0.;
+            let boundary = min[axis] + (cell[axis] + usize::from(forwards)) as f64 * size[axis];
+            step[axis] = if forwards { 1 } else { -1 };
+            t_next[axis] = (boundary - origin[axis]) / direction[axis];
+            t_delta[axis] = size[axis] / direction[axis].abs();
+        }
+
+        let mut seen = HashSet::new();
+        loop {
+            for &item in &self.cells[self.index(cell)] {
+                // only remember items the ray does hit so that misses allocate nothing.
+                if self.bounds[item].intersects(r) && seen.insert(item) {
+                    f(item);
+                }
+            }
+
+            let axis = (0..3)
+                .min_by(|&a, &b| t_next[a].total_cmp(&t_next[b]))
+                .unwrap();
+            if t_next[axis] > t_exit {
+                return;
+            }
+            match cell[axis].checked_add_signed(step[axis]) {
+                Some(next) if next < self.resolution[axis] => cell[axis] = next,
+                _ => return,
+            }
+            t_next[axis] += t_delta[axis];
+        }
+    }
+}
+
+/// resolution picks how many cells go along each side of the extent, aiming for about CELLS_PER_ITEM cells per item
+/// with each cell about as long as it is wide and deep. Sides with no length get a single cell.
+fn resolution(extent: &Aabb, items: usize) -> [usize; 3] {
+    let size = extent.max() - extent.min();
+    let size = [size.x(), size.y(), size.z()];
+    let sides: Vec<f64> = size.iter().copied().filter(|&s| s > 0.).collect();
+    if sides.is_empty() {
+        return [1; 3];
+    }
+    // the cell side that would give the wanted number of cells over the sides that have a length.
+    let volume: f64 = sides.iter().product();
+    let cells = CELLS_PER_ITEM * items as f64;
+    let cell_side = (volume / cells).powf(1. / sides.len() as f64);
+    size.map(|s| {
+        if s > 0. {
+            ((s / cell_side).round() as usize).clamp(1, MAX_CELLS)
+        } else {
+            1
+        }
+    })
+}
+
+#[cfg(test)]
+mod test_grid {
+    use crate::{P, V};
+
+    use super::*;
+    use crate::primatives::accelerator::fixtures::{row, visited};
+
+    /// cube is n * n * n unit boxes, one every 2 units in each direction.
+    fn cube(n: usize) -> Vec<Aabb> {
+        let mut boxes = vec![];
+        for z in 0..n {
+            for y in 0..n {
+                for x in 0..n {
+                    let min = P![2. * x as f64, 2. * y as f64, 2. * z as f64];
+                    boxes.push(Aabb::new(min, min + V![1., 1., 1.]));
+                }
+            }
+        }
+        boxes
+    }
+
+    /// brute_force is every item whose box the ray hits, which the grid should always agree with.
+    fn brute_force(bounds: &[Aabb], r: Ray) -> Vec<usize> {
+        (0..bounds.len())
+            .filter(|&i| bounds[i].intersects(r))
+            .collect()
+    }
+
+    #[test]
+    fn test_build() {
+        let grid = Grid::build(row(20));
+        assert_eq!(20, grid.len());
+        // a long thin row gets cells along its length.
+        let [x, y, z] = grid.resolution();
+        assert!(x > 1);
+        assert_eq!((1, 1), (y, z));
+        // every item is in at least one cell.
+        for item in 0..20 {
+            assert!(grid.cells.iter().any(|cell| cell.contains(&item)));
+        }
+    }
+
+    #[test]
+    fn test_visit() {
+        let grid = Grid::build(row(20));
+
+        // straight down onto one box.
+        let r = Ray::new(P![30.5, 5., 0.5], V![0., -1., 0.]);
+        assert_eq!(vec![10], visited(&grid, r));
+
+        // along the row through all of them, in both directions, visiting each once.
+        let r = Ray::new(P![-5., 0.5, 0.5], V![1., 0., 0.]);
+        assert_eq!((0..20).collect::<Vec<_>>(), visited(&grid, r));
+        let r = Ray::new(P![100., 0.5, 0.5], V![-1., 0., 0.]);
+        assert_eq!((0..20).collect::<Vec<_>>(), visited(&grid, r));
+
+        // between two boxes.
+        let r = Ray::new(P![2., 5., 0.5], V![0., -1., 0.]);
+        assert!(visited(&grid, r).is_empty());
+
+        // starting inside the grid only finds what is in front of the ray.
+        let r = Ray::new(P![31.5, 0.5, 0.5], V![1., 0., 0.]);
+        assert_eq!((11..20).collect::<Vec<_>>(), visited(&grid, r));
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let bounds = cube(6);
+        let grid = Grid::build(bounds.clone());
+        let rays = [
+            Ray::new(P![-1., -1., -1.], V![1., 1., 1.].norm()),
+            Ray::new(P![-3., 0.5, 2.5], V![1., 0.1, 0.2].norm()),
+            Ray::new(P![20., 4.5, 6.5], V![-1., -0.3, 0.05].norm()),
+            Ray::new(P![5.5, 20., 5.5], V![0.01, -1., -0.02].norm()),
+            Ray::new(P![5.5, 5.5, 5.5], V![-0.4, 0.7, -0.3].norm()),
+            Ray::new(P![0.5, 0.5, -10.], V![0., 0., 1.]),
+        ];
+        for r in rays {
+            assert_eq!(brute_force(&bounds, r), visited(&grid, r), "{:?}", r);
+        }
+    }
+
+    #[test]
+    fn test_unbounded() {
+        let mut bounds = row(6);
+        bounds.push(Aabb::new(
+            P![f64::NEG_INFINITY, -1., f64::NEG_INFINITY],
+            P![f64::INFINITY, -1., f64::INFINITY],
+        ));
+        let grid = Grid::build(bounds);
+
+        let r = Ray::new(P![100., 5., 0.5], V![0., -1., 0.]);
+        assert_eq!(vec![6], visited(&grid, r));
+
+        let r = Ray::new(P![3.5, 5., 0.5], V![0., -1., 0.]);
+        assert_eq!(vec![1, 6], visited(&grid, r));
+    }
+
+    #[test]
+    fn test_empty() {
+        let grid = Grid::build(vec![]);
+        assert!(grid.is_empty());
+        assert!(visited(&grid, Ray::new(P![0., 0., 0.], V![0., 0., 1.])).is_empty());
+    }
+}
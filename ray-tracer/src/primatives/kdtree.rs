@@ -0,0 +1,361 @@
+use std::{any::Any, collections::HashSet};
+
+use super::{
+    aabb::Aabb,
+    accelerator::{Accelerator, BoxedAccelerator, TraversalStack},
+    point::Point,
+    ray::Ray,
+    tuple::Tuple,
+};
+
+/// TRAVERSAL_COST is how expensive stepping through a split is compared with INTERSECTION_COST, the cost of
+/// testing a ray against one item.
+const TRAVERSAL_COST: f64 = 1.;
+const INTERSECTION_COST: f64 = 80.;
+
+/// EMPTY_BONUS makes splits that leave one side empty look cheaper, as rays through the empty side are free.
+const EMPTY_BONUS: f64 = 0.5;
+
+/// LEAF_SIZE is the number of items a node can hold without ever being split.
+const LEAF_SIZE: usize = 1;
+
+/// KdTree splits the space around a list of items in two again and again with planes lined up with the axes.
+/// Each plane is placed where the surface area heuristic expects rays to be cheapest to trace, so empty space is cut
+/// away and crowded space is split finely. Items crossing a plane go on both sides of it. It is slow to build
+/// compared with a Bvh or Grid so suits scenes that don't change.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct KdTree {
+    /// bounds is the box around each item, in the same order as the items.
+    bounds: Vec<Aabb>,
+    /// extent is the box around every bounded item, which the root node covers.
+    extent: Aabb,
+    nodes: Vec<Node>,
+    /// unbounded are the items with infinite sides, such as planes, which would end up in every node
+    /// so are kept out of the tree and checked for every ray.
+    unbounded: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Leaf {
+        items: Vec<usize>,
+    },
+    /// Split has the index of the nodes below and above the plane at position along the axis,
+    /// 0 for x, 1 for y and 2 for z.
+    Split {
+        axis: usize,
+        position: f64,
+        below: usize,
+        above: usize,
+    },
+}
+
+impl KdTree {
+    /// build makes the tree over items with the given boxes, stopping splitting when a split would cost more to
+    /// trace than testing every item in the node, or when the tree gets too deep.
+    pub fn build(bounds: Vec<Aabb>) -> Self {
+        let (bounded, unbounded): (Vec<usize>, Vec<usize>) =
+            (0..bounds.len()).partition(|&i| bounds[i].is_finite());
+        let extent = bounded
+            .iter()
+            .fold(Aabb::empty(), |extent, &i| extent.merge(&bounds[i]));
+        let mut tree = Self {
+            bounds,
+            extent,
+            nodes: vec![],
+            unbounded,
+        };
+        if !bounded.is_empty() {
+            let max_depth = (8. + 1.3 * (bounded.len() as f64).log2()).round() as usize;
+            tree.split(bounded, extent, max_depth);
+        }
+        tree
+    }
+
+    pub fn depth(&self) -> usize {
+        fn depth(nodes: &[Node], index: usize) -> usize {
+            match nodes[index] {
+                Node::Leaf { .. } => 1,
+                Node::Split { below, above, .. } => {
+                    1 + depth(nodes, below).max(depth(nodes, above))
+                }
+            }
+        }
+        if self.nodes.is_empty() {
+            0
+        } else {
+            depth(&self.nodes, 0)
+        }
+    }
+
+    /// split adds the node holding items inside node_bounds and everything beneath it, returning its index.
+    fn split(&mut self, items: Vec<usize>, node_bounds: Aabb, depth: usize) -> usize {
+        let index = self.nodes.len();
+        let best = if depth > 0 && items.len() > LEAF_SIZE {
+            self.best_split(&items, &node_bounds)
+        } else {
+            None
+        };
+        let Some((axis, position)) = best else {
+            self.nodes.push(Node::Leaf { items });
+            return index;
+        };
+
+        self.nodes.push(Node::Split {
+            axis,
+            position,
+            below: 0,
+            above: 0,
+        });
+        let below_items = items
+            .iter()
+            .copied()
+            .filter(|&i| component(self.bounds[i].min(), axis) <= position)
+            .collect();
+        let above_items = items
+            .iter()
+            .copied()
+            .filter(|&i| component(self.bounds[i].max(), axis) >= position)
+            .collect();
+        let (below_bounds, above_bounds) = cut(&node_bounds, axis, position);
+        let below_node = self.split(below_items, below_bounds, depth - 1);
+        let above_node = self.split(above_items, above_bounds, depth - 1);
+        if let Node::Split { below, above, .. } = &mut self.nodes[index] {
+            *below = below_node;
+            *above = above_node;
+        }
+        index
+    }
+
+    /// best_split tries a plane at the edge of every item's box on every axis and picks the one expected to be
+    /// cheapest, or none if tracing the items as they are is cheaper. The expected cost of a split weights the
+    /// number of items on each side by the chance of a ray that hits the node also hitting that side, which is
+    /// the ratio of their surface areas.
+    fn best_split(&self, items: &[usize], node_bounds: &Aabb) -> Option<(usize, f64)> {
+        let area = node_bounds.surface_area();
+        let leaf_cost = INTERSECTION_COST * items.len() as f64;
+        if area <= 0. {
+            return None;
+        }
+
+        let mut best: Option<(f64, usize, f64)> = None;
+        for axis in 0..3 {
+            let (low, high) = (
+                component(node_bounds.min(), axis),
+                component(node_bounds.max(), axis),
+            );
+            let mut mins: Vec<f64> = items
+                .iter()
+                .map(|&i| component(self.bounds[i].min(), axis))
+                .collect();
+            let mut maxes: Vec<f64> = items
+                .iter()
+                .map(|&i| component(self.bounds[i].max(), axis))
+                .collect();
+            mins.sort_by(f64::total_cmp);
+            maxes.sort_by(f64::total_cmp);
+
+            for &position in mins.iter().chain(maxes.iter()) {
+                if position <= low || position >= high {
+                    continue;
+                }
+                let below = mins.partition_point(|&m| m <= position);
+                let above = maxes.len() - maxes.partition_point(|&m| m < position);
+                let (below_bounds, above_bounds) = cut(node_bounds, axis, position);
+                let bonus = if below == 0 || above == 0 {
+                    EMPTY_BONUS
+                } else {
+                    0.
+                };
+                let cost = TRAVERSAL_COST
+                    + (1. - bonus)
+                        * INTERSECTION_COST
+                        * (below_bounds.surface_area() / area * below as f64
+                            + above_bounds.surface_area() / area * above as f64);
+                if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, axis, position));
+                }
+            }
+        }
+        best.filter(|&(cost, _, _)| cost < leaf_cost)
+            .map(|(_, axis, position)| (axis, position))
+    }
+}
+
+impl Accelerator for KdTree {
+    fn box_clone(&self) -> BoxedAccelerator {
+        Box::new(self.clone())
+    }
+
+    fn box_eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<Self>().is_some_and(|a| self == a)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    /// visit walks down the tree keeping track of the stretch of the ray inside each node, so only the sides of a
+    /// split that the stretch reaches are visited. Items on both sides of a split are only visited once.
+    fn visit(&self, r: Ray, f: &mut dyn FnMut(usize)) {
+        for &item in &self.unbounded {
+            if self.bounds[item].intersects(r) {
+                f(item);
+            }
+        }
+        if self.nodes.is_empty() {
+            return;
+        }
+        let Some((t_enter, t_exit)) = self.extent.span(r) else {
+            return;
+        };
+
+        let (origin, direction) = (r.origin(), r.direction());
+        let mut seen = HashSet::new();
+        let mut stack = TraversalStack::new((0, t_enter.max(0.), t_exit));
+        while let Some((index, t_min, t_max)) = stack.pop() {
+            match &self.nodes[index] {
+                Node::Leaf { items } => {
+                    for &item in items {
+                        // only remember items the ray does hit so that misses allocate nothing.
+                        if self.bounds[item].intersects(r) && seen.insert(item) {
+                            f(item);
+                        }
+                    }
+                }
+                &Node::Split {
+                    axis,
+                    position,
+                    below,
+                    above,
+                } => {
+                    let (o, d) = (component(origin, axis), component(direction, axis));
+                    if d == 0. {
+                        // the ray runs alongside the plane so it stays on the side it starts on.
+                        if o <= position {
+                            stack.push((below, t_min, t_max));
+                        }
+                        if o >= position {
+                            stack.push((above, t_min, t_max));
+                        }
+                        continue;
+                    }
+                    let (near, far) = if o < position || (o == position && d < 0.) {
+                        (below, above)
+                    } else {
+                        (above, below)
+                    };
+                    let t_plane = (position - o) / d;
+                    if t_plane > t_max || t_plane <= 0. {
+                        stack.push((near, t_min, t_max));
+                    } else if t_plane < t_min {
+                        stack.push((far, t_min, t_max));
+                    } else {
+                        stack.push((far, t_plane, t_max));
+                        stack.push((near, t_min, t_plane));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// component is the x, y or z of the tuple for axis 0, 1 or 2.
+fn component(tuple: impl Tuple, axis: usize) -> f64 {
+    match axis {
+        0 => tuple.x(),
+        1 => tuple.y(),
+        _ => tuple.z(),
+    }
+}
+
+/// cut splits the box in two with a plane at position along the axis.
+fn cut(bounds: &Aabb, axis: usize, position: f64) -> (Aabb, Aabb) {
+    let (min, max) = (bounds.min(), bounds.max());
+    let with = |point: Point, value: f64| match axis {
+        0 => Point::new(value, point.y(), point.z()),
+        1 => Point::new(point.x(), value, point.z()),
+        _ => Point::new(point.x(), point.y(), value),
+    };
+    (
+        Aabb::new(min, with(max, position)),
+        Aabb::new(with(min, position), max),
+    )
+}
+
+#[cfg(test)]
+mod test_kdtree {
+    use crate::{P, V};
+
+    use super::*;
+    use crate::primatives::accelerator::fixtures::{row, visited};
+
+    #[test]
+    fn test_build() {
+        let tree = KdTree::build(row(20));
+        assert_eq!(20, tree.len());
+        assert!(tree.depth() > 1);
+        // the boxes are far apart so the first plane is put between two of them rather than through one.
+        let Node::Split { axis, position, .. } = tree.nodes[0] else {
+            panic!("expected the root to be split");
+        };
+        assert_eq!(0, axis);
+        assert!(row(20)
+            .iter()
+            .all(|b| position <= b.min().x() || position >= b.max().x()));
+
+        // a single item is never split.
+        assert_eq!(1, KdTree::build(row(1)).depth());
+    }
+
+    #[test]
+    fn test_visit() {
+        let tree = KdTree::build(row(20));
+
+        // straight down onto one box.
+        let r = Ray::new(P![30.5, 5., 0.5], V![0., -1., 0.]);
+        assert_eq!(vec![10], visited(&tree, r));
+
+        // along the row through all of them, in both directions.
+        let r = Ray::new(P![-5., 0.5, 0.5], V![1., 0., 0.]);
+        assert_eq!((0..20).collect::<Vec<_>>(), visited(&tree, r));
+        let r = Ray::new(P![100., 0.5, 0.5], V![-1., 0., 0.]);
+        assert_eq!((0..20).collect::<Vec<_>>(), visited(&tree, r));
+
+        // between two boxes.
+        let r = Ray::new(P![2., 5., 0.5], V![0., -1., 0.]);
+        assert!(visited(&tree, r).is_empty());
+
+        // starting inside the tree only finds what is in front of the ray.
+        let r = Ray::new(P![31.5, 0.5, 0.5], V![1., 0., 0.]);
+        assert_eq!((11..20).collect::<Vec<_>>(), visited(&tree, r));
+    }
+
+    #[test]
+    fn test_unbounded() {
+        let mut bounds = row(6);
+        bounds.push(Aabb::new(
+            P![f64::NEG_INFINITY, -1., f64::NEG_INFINITY],
+            P![f64::INFINITY, -1., f64::INFINITY],
+        ));
+        let tree = KdTree::build(bounds);
+
+        let r = Ray::new(P![100., 5., 0.5], V![0., -1., 0.]);
+        assert_eq!(vec![6], visited(&tree, r));
+
+        let r = Ray::new(P![3.5, 5., 0.5], V![0., -1., 0.]);
+        assert_eq!(vec![1, 6], visited(&tree, r));
+    }
+
+    #[test]
+    fn test_empty() {
+        let tree = KdTree::build(vec![]);
+        assert!(tree.is_empty());
+        assert_eq!(0, tree.depth());
+        assert!(visited(&tree, Ray::new(P![0., 0., 0.], V![0., 0., 1.])).is_empty());
+    }
+}
@@ -80,7 +80,7 @@ impl Matrix {
     /// cofactor finds the minor of a matrix at a given position but flips the sign if the row index + column index is an odd number.
     pub fn cofactor(&self, row: usize, column: usize) -> f64 {
         let minor = self.minor(row, column);
-        if (row + column) % 2 == 0 {
+        if (row + column).is_multiple_of(2) {
             minor
         } else {
             -minor
@@ -121,6 +121,15 @@ impl Mul for Matrix {
 impl Mul<Point> for Matrix {
     type Output = Point;
 
+    fn mul(self, rhs: Point) -> Self::Output {
+        &self * rhs
+    }
+}
+
+/// multiplying by a reference saves cloning a matrix that is kept, such as a shape's inverse transformation.
+impl Mul<Point> for &Matrix {
+    type Output = Point;
+
     fn mul(self, rhs: Point) -> Self::Output {
         let vec = self
             .elements
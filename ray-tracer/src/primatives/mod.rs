@@ -1,7 +1,15 @@
+pub mod aabb;
+pub mod accelerator;
+pub mod bvh;
 pub mod color;
+pub mod grid;
+pub mod kdtree;
 pub mod matrix;
 pub mod point;
 pub mod ray;
+pub mod rng;
+pub mod sampling;
+pub mod solvers;
 pub mod transformation;
 pub mod tuple;
 pub mod vector;
@@ -102,7 +102,7 @@ impl PartialEq for Point {
 #[macro_export]
 macro_rules! P {
     ($x: expr, $y: expr, $z: expr) => {
-        crate::primatives::point::Point::new($x, $y, $z)
+        $crate::primatives::point::Point::new($x, $y, $z)
     };
 }
 #[cfg(test)]
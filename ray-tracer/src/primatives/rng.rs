@@ -0,0 +1,70 @@
+use super::{point::Point, tuple::Tuple};
+
+/// Rng is a small seedable pseudorandom number generator (splitmix64).
+/// It is quick and good enough for choosing samples, and the same seed always gives the same numbers
+/// so renders that sample can be reproduced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// for_point seeds a generator from a point, so a point always gets the same samples
+    /// whichever thread happens to shade it.
+    pub fn for_point(point: Point) -> Self {
+        Self::new(
+            point.x().to_bits()
+                ^ point.y().to_bits().rotate_left(21)
+                ^ point.z().to_bits().rotate_left(42),
+        )
+    }
+
+    /// next_u64 moves the generator's state on and returns the next of its numbers.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// next_f64 is a number from 0 up to but not including 1.
+    pub fn next_f64(&mut self) -> f64 {
+        // the top 53 bits fill an f64's mantissa exactly.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod test_rng {
+    use crate::P;
+
+    use super::*;
+
+    #[test]
+    fn test_rng_is_reproducible() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        let mut c = Rng::new(8);
+        let first: Vec<u64> = (0..4).map(|_| a.next_u64()).collect();
+        assert_eq!(first, (0..4).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(first, (0..4).map(|_| c.next_u64()).collect::<Vec<_>>());
+
+        let p = P![1., 2., 3.];
+        assert_eq!(Rng::for_point(p), Rng::for_point(p));
+        assert_ne!(Rng::for_point(p), Rng::for_point(P![1., 2., 3.5]));
+    }
+
+    #[test]
+    fn test_rng_floats_are_in_unit_range() {
+        let mut rng = Rng::new(1);
+        let samples: Vec<f64> = (0..1000).map(|_| rng.next_f64()).collect();
+        assert!(samples.iter().all(|&x| (0. ..1.).contains(&x)));
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        assert!((mean - 0.5).abs() < 0.05);
+    }
+}
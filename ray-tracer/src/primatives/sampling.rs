@@ -0,0 +1,315 @@
+use super::rng::Rng;
+
+/// Sampler is a sequence of points in the unit square for spreading samples over something, such as a pixel or the
+/// area of a light. The sequences here are spread more evenly than points picked at random, so averages over them
+/// settle down with fewer samples.
+pub trait Sampler {
+    /// point is the sample at the index, with both coordinates from 0 up to but not including 1.
+    fn point(&self, index: usize) -> (f64, f64);
+
+    /// count is how many different points the sampler gives before repeating itself, or None if it never does.
+    fn count(&self) -> Option<usize>;
+}
+
+/// Jittered splits the square into a grid of strata by strata cells and puts one point at a random place in each,
+/// so no part of the square is missed however the points fall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Jittered {
+    strata: usize,
+    seed: u64,
+}
+
+impl Jittered {
+    pub fn new(strata: usize, seed: u64) -> Self {
+        assert!(strata > 0, "a jittered sampler needs at least one cell");
+        Self { strata, seed }
+    }
+
+    pub fn strata(&self) -> usize {
+        self.strata
+    }
+}
+
+impl Sampler for Jittered {
+    fn point(&self, index: usize) -> (f64, f64) {
+        let index = index % (self.strata * self.strata);
+        let (column, row) = (index % self.strata, index / self.strata);
+        let mut rng = Rng::new(self.seed ^ (index as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        let cell = 1. / self.strata as f64;
+        (
+            (column as f64 + rng.next_f64()) * cell,
+            (row as f64 + rng.next_f64()) * cell,
+        )
+    }
+
+    fn count(&self) -> Option<usize> {
+        Some(self.strata * self.strata)
+    }
+}
+
+/// Halton is the Halton sequence in bases 2 and 3, where each point falls in the biggest gap left by the ones before
+/// it, so it can be stopped after any number of points. The whole sequence can be shifted, wrapping around the
+/// edges, so that neighbouring pixels or points don't all use the same points.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Halton {
+    shift: (f64, f64),
+}
+
+impl Halton {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// shifted is the sequence moved by (dx, dy), wrapping around the edges of the square.
+    pub fn shifted(dx: f64, dy: f64) -> Self {
+        Self {
+            shift: (dx.rem_euclid(1.), dy.rem_euclid(1.)),
+        }
+    }
+
+    /// scrambled is the sequence moved by a random amount taken from rng.
+    pub fn scrambled(rng: &mut Rng) -> Self {
+        Self::shifted(rng.next_f64(), rng.next_f64())
+    }
+}
+
+impl Sampler for Halton {
+    fn point(&self, index: usize) -> (f64, f64) {
+        (
+            (radical_inverse(index, 2) + self.shift.0).fract(),
+            (radical_inverse(index, 3) + self.shift.1).fract(),
+        )
+    }
+
+    fn count(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// radical_inverse mirrors the digits of the index in the base around the decimal point, so 6 in base 2 (110) is
+/// 0.011, which is 0.375.
+fn radical_inverse(mut index: usize, base: usize) -> f64 {
+    let (mut inverse, mut digit) = (0., 1. / base as f64);
+    while index > 0 {
+        inverse += (index % base) as f64 * digit;
+        index /= base;
+        digit /= base as f64;
+    }
+    inverse
+}
+
+/// BlueNoise is a fixed set of points that are random but never close together, which leaves noise without the
+/// clumps and gaps of points picked at random. The points are found once, when the sampler is made, by trying
+/// several random candidates for each one and keeping the candidate furthest from the points already chosen.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlueNoise {
+    points: Vec<(f64, f64)>,
+}
+
+/// CANDIDATES is how many random candidates are tried for each point of blue noise.
+const CANDIDATES: usize = 16;
+
+impl BlueNoise {
+    pub fn new(count: usize, seed: u64) -> Self {
+        assert!(count > 0, "blue noise needs at least one point");
+        let mut rng = Rng::new(seed);
+        let mut points: Vec<(f64, f64)> = Vec::with_capacity(count);
+        while points.len() < count {
+            let best = (0..CANDIDATES)
+                .map(|_| (rng.next_f64(), rng.next_f64()))
+                .map(|candidate| {
+                    let nearest = points
+                        .iter()
+                        .map(|&point| wrapped_distance2(candidate, point))
+                        .fold(f64::INFINITY, f64::min);
+                    (candidate, nearest)
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(candidate, _)| candidate)
+                .expect("there is at least one candidate");
+            points.push(best);
+        }
+        Self { points }
+    }
+}
+
+impl Sampler for BlueNoise {
+    fn point(&self, index: usize) -> (f64, f64) {
+        self.points[index % self.points.len()]
+    }
+
+    fn count(&self) -> Option<usize> {
+        Some(self.points.len())
+    }
+}
+
+/// wrapped_distance2 is the squared distance between two points of the square with its edges joined up, so points
+/// near opposite edges count as close and the noise tiles without seams.
+fn wrapped_distance2(a: (f64, f64), b: (f64, f64)) -> f64 {
+    let wrap = |d: f64| {
+        let d = d.abs();
+        d.min(1. - d)
+    };
+    wrap(a.0 - b.0).powi(2) + wrap(a.1 - b.1).powi(2)
+}
+
+/// sample_bilinear warps a point of the unit square so points land in proportion to the bilinear function with the
+/// weights at the corners (0, 0), (1, 0), (0, 1) and (1, 1). It picks the second coordinate from the weights of the
+/// two edges and then the first along that line.
+pub(crate) fn sample_bilinear(u: f64, v: f64, weights: [f64; 4]) -> (f64, f64) {
+    let [w00, w10, w01, w11] = weights;
+    let t = sample_linear(v, w00 + w10, w01 + w11);
+    let s = sample_linear(u, lerp(t, w00, w01), lerp(t, w10, w11));
+    (s, t)
+}
+
+/// bilinear_pdf is the density of sample_bilinear at the point (s, t), which averages 1 over the square.
+pub(crate) fn bilinear_pdf(s: f64, t: f64, weights: [f64; 4]) -> f64 {
+    let [w00, w10, w01, w11] = weights;
+    let total = w00 + w10 + w01 + w11;
+    if total == 0. {
+        return 1.;
+    }
+    4. * lerp(t, lerp(s, w00, w10), lerp(s, w01, w11)) / total
+}
+
+/// sample_linear is the point from 0 to 1 with u of the area under the line from a at 0 to b at 1 before it, written
+/// in a way that stays accurate when a and b are close.
+fn sample_linear(u: f64, a: f64, b: f64) -> f64 {
+    if a + b == 0. {
+        return u;
+    }
+    let x = u * (a + b) / (a + lerp(u, a * a, b * b).sqrt());
+    x.min(1. - f64::EPSILON)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    (1. - t) * a + t * b
+}
+
+#[cfg(test)]
+mod test_sampling {
+    use super::*;
+
+    /// discrepancy is how far the share of points inside the worst box from the corner is from the box's area,
+    /// checked on a grid of boxes. Evenly spread points have a low discrepancy.
+    fn discrepancy(sampler: &impl Sampler, n: usize) -> f64 {
+        let points: Vec<_> = (0..n).map(|i| sampler.point(i)).collect();
+        let mut worst: f64 = 0.;
+        for i in 1..=16 {
+            for j in 1..=16 {
+                let (w, h) = (i as f64 / 16., j as f64 / 16.);
+                let inside = points.iter().filter(|(x, y)| *x < w && *y < h).count();
+                worst = worst.max((inside as f64 / n as f64 - w * h).abs());
+            }
+        }
+        worst
+    }
+
+    fn in_square(sampler: &impl Sampler, n: usize) -> bool {
+        (0..n)
+            .map(|i| sampler.point(i))
+            .all(|(x, y)| (0. ..1.).contains(&x) && (0. ..1.).contains(&y))
+    }
+
+    #[test]
+    fn test_sample_bilinear() {
+        let weights = [0.1, 1., 0.5, 2.];
+        // equal weights leave the square as it is.
+        assert_eq!((0.25, 0.75), sample_bilinear(0.25, 0.75, [1.; 4]));
+        assert_eq!(1., bilinear_pdf(0.3, 0.6, [1.; 4]));
+        // the corners stay where they are.
+        assert_eq!((0., 0.), sample_bilinear(0., 0., weights));
+
+        // the density averages 1 over the square.
+        let n = 100;
+        let mut total = 0.;
+        for i in 0..n {
+            for j in 0..n {
+                let (s, t) = ((i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64);
+                total += bilinear_pdf(s, t, weights);
+            }
+        }
+        assert!((total / (n * n) as f64 - 1.).abs() < 1e-9);
+
+        // the share of samples left of and below a point is the density added up over that part of the square.
+        let (s, t) = sample_bilinear(0.4, 0.7, weights);
+        let below = |t: f64| {
+            let w0 = weights[0] + weights[1];
+            let w1 = weights[2] + weights[3];
+            (w0 * t + (w1 - w0) * t * t / 2.) / ((w0 + w1) / 2.)
+        };
+        assert!((below(t) - 0.7).abs() < 1e-9);
+        let (a, b) = (
+            lerp(t, weights[0], weights[2]),
+            lerp(t, weights[1], weights[3]),
+        );
+        assert!(((a * s + (b - a) * s * s / 2.) / ((a + b) / 2.) - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_radical_inverse() {
+        assert_eq!(0., radical_inverse(0, 2));
+        assert_eq!(0.5, radical_inverse(1, 2));
+        assert_eq!(0.375, radical_inverse(6, 2));
+        assert_eq!(1. / 3. + 1. / 9., radical_inverse(4, 3));
+    }
+
+    #[test]
+    fn test_halton() {
+        let halton = Halton::new();
+        assert_eq!((0., 0.), halton.point(0));
+        assert_eq!((0.5, 1. / 3.), halton.point(1));
+        assert_eq!(None, halton.count());
+        assert_eq!((0.75, 1. / 3. + 0.25), Halton::shifted(0.25, 1.25).point(1));
+        assert!(in_square(&Halton::scrambled(&mut Rng::new(3)), 256));
+    }
+
+    #[test]
+    fn test_jittered() {
+        let jittered = Jittered::new(4, 9);
+        assert_eq!(Some(16), jittered.count());
+        assert!(in_square(&jittered, 16));
+        // each point stays in its own cell.
+        for index in 0..16 {
+            let (x, y) = jittered.point(index);
+            assert_eq!(index % 4, (x * 4.) as usize);
+            assert_eq!(index / 4, (y * 4.) as usize);
+        }
+        assert_eq!(jittered.point(3), jittered.point(19));
+    }
+
+    #[test]
+    fn test_blue_noise() {
+        let noise = BlueNoise::new(64, 1);
+        assert_eq!(Some(64), noise.count());
+        assert!(in_square(&noise, 64));
+        assert_eq!(noise, BlueNoise::new(64, 1));
+
+        // no two points are as close as random points usually get.
+        let closest = (0..64)
+            .flat_map(|i| (0..i).map(move |j| (i, j)))
+            .map(|(i, j)| wrapped_distance2(noise.point(i), noise.point(j)))
+            .fold(f64::INFINITY, f64::min);
+        assert!(closest.sqrt() > 0.04);
+    }
+
+    #[test]
+    fn test_more_even_than_random() {
+        let mut rng = Rng::new(11);
+        let random: Vec<_> = (0..256).map(|_| (rng.next_f64(), rng.next_f64())).collect();
+        struct Random(Vec<(f64, f64)>);
+        impl Sampler for Random {
+            fn point(&self, index: usize) -> (f64, f64) {
+                self.0[index]
+            }
+            fn count(&self) -> Option<usize> {
+                Some(self.0.len())
+            }
+        }
+        let random = discrepancy(&Random(random), 256);
+        assert!(discrepancy(&Halton::new(), 256) < random);
+        assert!(discrepancy(&Jittered::new(16, 2), 256) < random);
+        assert!(discrepancy(&BlueNoise::new(256, 2), 256) < random);
+    }
+}
@@ -0,0 +1,191 @@
+use std::f64::consts::PI;
+
+/// NEWTON_STEPS is how many rounds of Newton's method are used to tidy up each root of a quartic.
+const NEWTON_STEPS: usize = 2;
+
+/// quadratic finds the real roots of ax² + bx + c = 0 in ascending order.
+/// A repeated root is returned twice so that a ray grazing a surface still gives an entry and an exit.
+///
+/// The textbook formula loses most of its precision when b² is much larger than 4ac, as -b and the square root
+/// nearly cancel. Instead the larger root is found without any cancellation and the smaller one from the product of the roots.
+pub fn quadratic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a == 0. {
+        // the equation is linear.
+        return if b == 0. { vec![] } else { vec![-c / b] };
+    }
+
+    let discriminant = b * b - 4. * a * c;
+    if discriminant < 0. {
+        return vec![];
+    }
+
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    if q == 0. {
+        // b and c are both zero so the only root is zero.
+        return vec![0., 0.];
+    }
+
+    let (t1, t2) = (q / a, c / q);
+    if t1 < t2 {
+        vec![t1, t2]
+    } else {
+        vec![t2, t1]
+    }
+}
+
+/// cubic finds the real roots of ax³ + bx² + cx + d = 0 in ascending order.
+/// Three real roots are found with the trigonometric method and a single real root with Cardano's formula.
+pub fn cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if a == 0. {
+        return quadratic(b, c, d);
+    }
+    let (a, b, c) = (b / a, c / a, d / a);
+
+    let q = (a * a - 3. * b) / 9.;
+    let r = (2. * a.powi(3) - 9. * a * b + 27. * c) / 54.;
+    let shift = a / 3.;
+
+    if r * r < q.powi(3) {
+        let theta = (r / q.powi(3).sqrt()).acos();
+        let scale = -2. * q.sqrt();
+        let mut roots = vec![
+            scale * (theta / 3.).cos() - shift,
+            scale * ((theta + 2. * PI) / 3.).cos() - shift,
+            scale * ((theta - 2. * PI) / 3.).cos() - shift,
+        ];
+        roots.retain(|root| root.is_finite());
+        roots.sort_by(f64::total_cmp);
+        roots
+    } else {
+        let big = -r.signum() * (r.abs() + (r * r - q.powi(3)).sqrt()).cbrt();
+        let small = if big == 0. { 0. } else { q / big };
+        let mut roots = vec![big + small - shift];
+        roots.retain(|root| root.is_finite());
+        roots
+    }
+}
+
+/// quartic finds the real roots of ax⁴ + bx³ + cx² + dx + e = 0 in ascending order using Ferrari's method.
+/// The quartic is split into two quadratics with the help of a root of its resolvent cubic,
+/// and each root found is then polished with Newton's method against the original equation.
+pub fn quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if a == 0. {
+        return cubic(b, c, d, e);
+    }
+    let (a, b, c, d) = (b / a, c / a, d / a, e / a);
+
+    // substitute x = y - a/4 to remove the cubic term, leaving y⁴ + py² + qy + r = 0.
+    let shift = a / 4.;
+    let p = b - 3. * a * a / 8.;
+    let q = c - a * b / 2. + a.powi(3) / 8.;
+    let r = d - a * c / 4. + a * a * b / 16. - 3. * a.powi(4) / 256.;
+
+    let mut roots = if q.abs() < f64::EPSILON {
+        // the quartic is a quadratic in y².
+        quadratic(1., p, r)
+            .into_iter()
+            .filter(|z| *z >= 0.)
+            .flat_map(|z| [-z.sqrt(), z.sqrt()])
+            .collect::<Vec<_>>()
+    } else {
+        // the resolvent always has a positive root when q is not zero.
+        let m = cubic(1., p, p * p / 4. - r, -q * q / 8.)
+            .into_iter()
+            .fold(f64::MIN, f64::max);
+        let s = (2. * m).sqrt();
+        let mut roots = quadratic(1., -s, p / 2. + m + q / (2. * s));
+        roots.extend(quadratic(1., s, p / 2. + m - q / (2. * s)));
+        roots
+    };
+
+    let f = |x: f64| (((x + a) * x + b) * x + c) * x + d;
+    let df = |x: f64| ((4. * x + 3. * a) * x + 2. * b) * x + c;
+    for root in roots.iter_mut() {
+        *root -= shift;
+        for _ in 0..NEWTON_STEPS {
+            let slope = df(*root);
+            if slope == 0. {
+                break;
+            }
+            *root -= f(*root) / slope;
+        }
+    }
+
+    // coefficients too large to square, or a Newton step off a nearly flat slope, give roots that aren't numbers.
+    roots.retain(|root| root.is_finite());
+    roots.sort_by(f64::total_cmp);
+    roots
+}
+
+#[cfg(test)]
+mod test_solvers {
+    use crate::comparison::approx_eq;
+
+    use super::*;
+
+    fn assert_roots(expected: &[f64], roots: Vec<f64>) {
+        assert_eq!(expected.len(), roots.len(), "roots were {:?}", roots);
+        for (e, r) in expected.iter().zip(roots) {
+            assert!(approx_eq(*e, r), "expected {} but got {}", e, r);
+        }
+    }
+
+    #[test]
+    fn test_quadratic() {
+        assert_roots(&[-3., 2.], quadratic(1., 1., -6.));
+        assert_roots(&[-1., -1.], quadratic(1., 2., 1.));
+        assert_roots(&[], quadratic(1., 0., 1.));
+        assert_roots(&[0., 0.], quadratic(2., 0., 0.));
+
+        // a is zero so the equation is linear.
+        assert_roots(&[2.], quadratic(0., 2., -4.));
+        assert_roots(&[], quadratic(0., 0., 1.));
+    }
+
+    #[test]
+    fn test_quadratic_cancellation() {
+        // the small root is -1e-8. The textbook formula gets this badly wrong as -b and the square root almost cancel.
+        let roots = quadratic(1., 1e8, 1.);
+        assert_eq!(2, roots.len());
+        assert!((roots[1] + 1e-8).abs() / 1e-8 < 1e-10);
+        assert!((roots[0] + 1e8).abs() / 1e8 < 1e-10);
+    }
+
+    #[test]
+    fn test_cubic() {
+        // (x - 1)(x - 2)(x - 3)
+        assert_roots(&[1., 2., 3.], cubic(1., -6., 11., -6.));
+        // x³ - 1 only crosses zero once.
+        assert_roots(&[1.], cubic(1., 0., 0., -1.));
+        // scaling every coefficient doesn't change the roots.
+        assert_roots(&[1., 2., 3.], cubic(-2., 12., -22., 12.));
+        // a is zero so the equation is quadratic.
+        assert_roots(&[-3., 2.], cubic(0., 1., 1., -6.));
+    }
+
+    #[test]
+    fn test_quartic() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(&[1., 2., 3., 4.], quartic(1., -10., 35., -50., 24.));
+        // (x² - 1)(x² - 4) has no odd terms.
+        assert_roots(&[-2., -1., 1., 2.], quartic(1., 0., -5., 0., 4.));
+        // (x + 1)²(x - 1)² touches zero twice.
+        assert_roots(&[-1., -1., 1., 1.], quartic(1., 0., -2., 0., 1.));
+        // x⁴ + 1 is never zero.
+        assert_roots(&[], quartic(1., 0., 0., 0., 1.));
+        // (x - 0.5)(x + 2)(x² + 1) has two real and two complex roots.
+        assert_roots(&[-2., 0.5], quartic(1., 1.5, 0., 1.5, -1.));
+    }
+
+    #[test]
+    fn test_roots_that_are_not_numbers() {
+        assert_roots(&[], cubic(1., f64::NAN, 0., 0.));
+        assert_roots(&[], quartic(1., f64::NAN, 0., 0., 0.));
+
+        // the coefficients overflow when they are squared.
+        let roots = quartic(1e-300, 1., 1e300, 1e300, 1e300);
+        assert!(roots.iter().all(|root| root.is_finite()), "{:?}", roots);
+        let roots = cubic(1e-300, 1e300, 1., 1e300);
+        assert!(roots.iter().all(|root| root.is_finite()), "{:?}", roots);
+    }
+}
@@ -84,11 +84,59 @@ pub fn view_transformation(from: Point, to: Point, up: Vector) -> Matrix {
     orientation * translation(-from.x(), -from.y(), -from.z())
 }
 
+/// Transform builds up a transformation from steps that are applied in the order they are added,
+/// so `Transform::new().scale(2., 2., 2.).translate(0., 1., 0.)` scales first and then translates.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Transform {
+    matrix: Matrix,
+}
+
+impl Transform {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// then applies the given transformation after the steps so far.
+    pub fn then(self, m: Matrix) -> Self {
+        Self {
+            matrix: m * self.matrix,
+        }
+    }
+
+    pub fn translate(self, x: f64, y: f64, z: f64) -> Self {
+        self.then(translation(x, y, z))
+    }
+
+    pub fn scale(self, x: f64, y: f64, z: f64) -> Self {
+        self.then(scaling(x, y, z))
+    }
+
+    pub fn rotate_x(self, angle: f64) -> Self {
+        self.then(rotation_x(angle))
+    }
+
+    pub fn rotate_y(self, angle: f64) -> Self {
+        self.then(rotation_y(angle))
+    }
+
+    pub fn rotate_z(self, angle: f64) -> Self {
+        self.then(rotation_z(angle))
+    }
+
+    pub fn shear(self, x_y: f64, x_z: f64, y_x: f64, y_z: f64, z_x: f64, z_y: f64) -> Self {
+        self.then(shearing(x_y, x_z, y_x, y_z, z_x, z_y))
+    }
+
+    pub fn build(self) -> Matrix {
+        self.matrix
+    }
+}
+
 #[cfg(test)]
 mod test_transformation {
     use std::f64::consts::PI;
 
-    use crate::Tuple;
+    use crate::primatives::tuple::Tuple;
     use crate::P;
     use crate::V;
 
@@ -222,6 +270,14 @@ mod test_transformation {
         let c = translation(10.0, 5.0, 7.0);
         let got = point.transform(&[a, b, c]);
         assert_eq!(P![15.0, 0.0, 7.0], got);
+
+        // or with the builder.
+        let m = Transform::new()
+            .rotate_x(PI / 2.0)
+            .scale(5.0, 5.0, 5.0)
+            .translate(10.0, 5.0, 7.0)
+            .build();
+        assert_eq!(P![15.0, 0.0, 7.0], m * point);
     }
 
     #[test]
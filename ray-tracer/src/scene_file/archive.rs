@@ -0,0 +1,326 @@
+use std::{
+    env, fs,
+    path::{Component, Path},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use super::SceneError;
+use crate::world::scene::Scene;
+
+/// ARCHIVE_EXTENSION ends the name of a scene that has been packed into one file with everything it uses.
+pub const ARCHIVE_EXTENSION: &str = ".rtscene.zip";
+
+/// SCENE_ENTRY is the name the scene file is stored under in an archive.
+const SCENE_ENTRY: &str = "scene.yml";
+
+/// UNPACKED counts the archives unpacked by this process so each one gets its own folder.
+static UNPACKED: AtomicUsize = AtomicUsize::new(0);
+
+pub(super) fn is_archive(path: &Path) -> bool {
+    path.to_string_lossy().ends_with(ARCHIVE_EXTENSION)
+}
+
+/// pack writes the scene file and every file it uses, such as models and their materials, into a single zip
+/// archive so a scene can be shared or kept with a bug report. The files keep their paths relative to the scene,
+/// so they have to be inside the scene's folder. The scene is built first, so a scene that can't be loaded is
+/// never packed.
+pub fn pack(scene: impl AsRef<Path>, archive: impl AsRef<Path>) -> Result<(), SceneError> {
+    let scene = scene.as_ref();
+    let input = fs::read_to_string(scene)?;
+    let dir = super::directory_of(scene);
+    let (_, assets) = super::read(&input, Some(dir))?;
+
+    let mut entries = vec![(SCENE_ENTRY.to_string(), input.into_bytes())];
+    for asset in assets {
+        let name = entry_name(&asset, dir)?;
+        if entries.iter().all(|(existing, _)| *existing != name) {
+            entries.push((name, fs::read(&asset)?));
+        }
+    }
+    fs::write(archive, write_zip(&entries))?;
+    Ok(())
+}
+
+/// load unpacks an archive made by pack into a folder of its own and loads the scene from there.
+pub(super) fn load(path: &Path) -> Result<Scene, SceneError> {
+    let entries = read_zip(&fs::read(path)?)?;
+    if entries.iter().all(|(name, _)| name != SCENE_ENTRY) {
+        return Err(archive_error(format!("there is no {}", SCENE_ENTRY)));
+    }
+
+    let dir = env::temp_dir().join(format!(
+        "rtscene_{}_{}",
+        process::id(),
+        UNPACKED.fetch_add(1, Ordering::Relaxed)
+    ));
+    let scene = unpack(&entries, &dir).and_then(|_| super::load(dir.join(SCENE_ENTRY)));
+    // the scene holds everything it read, so the unpacked files aren't needed whether it loaded or not.
+    let _ = fs::remove_dir_all(&dir);
+    scene
+}
+
+fn unpack(entries: &[(String, Vec<u8>)], dir: &Path) -> Result<(), SceneError> {
+    for (name, data) in entries {
+        let path = Path::new(name);
+        if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(archive_error(format!(
+                "'{}' would be unpacked outside the archive's folder",
+                name
+            )));
+        }
+        let path = dir.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, data)?;
+    }
+    Ok(())
+}
+
+/// entry_name is the name a file is stored under in an archive, its path from the scene's folder.
+fn entry_name(asset: &Path, dir: &Path) -> Result<String, SceneError> {
+    let outside = || {
+        SceneError::Format(format!(
+            "'{}' is outside the scene's folder so it can't be packed",
+            asset.display()
+        ))
+    };
+    let mut parts = vec![];
+    for component in asset.strip_prefix(dir).map_err(|_| outside())?.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            Component::CurDir => {}
+            _ => return Err(outside()),
+        }
+    }
+    Ok(parts.join("/"))
+}
+
+fn archive_error(message: impl Into<String>) -> SceneError {
+    SceneError::Format(format!("bad scene archive, {}", message.into()))
+}
+
+/// write_zip stores the files in a zip archive without compressing them, which every zip tool can read.
+fn write_zip(entries: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut out = vec![];
+    let mut central = vec![];
+    for (name, data) in entries {
+        let offset = out.len() as u32;
+        // version 2.0, no flags, stored, and a date of 1980-01-01 as the files' times aren't kept.
+        let fields = |header: &mut Vec<u8>| {
+            put_u16(header, 20);
+            put_u16(header, 0);
+            put_u16(header, 0);
+            put_u16(header, 0);
+            put_u16(header, 0x21);
+            put_u32(header, crc32(data));
+            put_u32(header, data.len() as u32);
+            put_u32(header, data.len() as u32);
+            put_u16(header, name.len() as u16);
+            put_u16(header, 0);
+        };
+
+        put_u32(&mut out, 0x0403_4b50);
+        fields(&mut out);
+        out.extend(name.as_bytes());
+        out.extend(data);
+
+        put_u32(&mut central, 0x0201_4b50);
+        put_u16(&mut central, 20);
+        fields(&mut central);
+        // no comment, disk 0, no attributes and then where the file's own header is.
+        central.extend([0; 10]);
+        put_u32(&mut central, offset);
+        central.extend(name.as_bytes());
+    }
+
+    let central_offset = out.len() as u32;
+    out.extend(&central);
+    put_u32(&mut out, 0x0605_4b50);
+    put_u32(&mut out, 0);
+    put_u16(&mut out, entries.len() as u16);
+    put_u16(&mut out, entries.len() as u16);
+    put_u32(&mut out, central.len() as u32);
+    put_u32(&mut out, central_offset);
+    put_u16(&mut out, 0);
+    out
+}
+
+/// read_zip reads the files out of a zip archive. Only stored files can be read, so archives made with other
+/// tools need to be made without compression.
+fn read_zip(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>, SceneError> {
+    // the end of the central directory is 22 bytes followed by a comment of up to 64k.
+    let end = (0..=bytes.len().saturating_sub(22))
+        .rev()
+        .take(22 + u16::MAX as usize)
+        .find(|&i| get_u32(bytes, i).ok() == Some(0x0605_4b50))
+        .ok_or_else(|| archive_error("it is not a zip file"))?;
+    let count = get_u16(bytes, end + 10)?;
+    let mut at = get_u32(bytes, end + 16)? as usize;
+
+    let mut entries = vec![];
+    for _ in 0..count {
+        if get_u32(bytes, at)? != 0x0201_4b50 {
+            return Err(archive_error("its list of files is damaged"));
+        }
+        let method = get_u16(bytes, at + 10)?;
+        let crc = get_u32(bytes, at + 16)?;
+        let size = get_u32(bytes, at + 20)? as usize;
+        let name_length = get_u16(bytes, at + 28)? as usize;
+        let extra_length = get_u16(bytes, at + 30)? as usize;
+        let comment_length = get_u16(bytes, at + 32)? as usize;
+        let offset = get_u32(bytes, at + 42)? as usize;
+        let name = String::from_utf8_lossy(get(bytes, at + 46, name_length)?).into_owned();
+        at += 46 + name_length + extra_length + comment_length;
+
+        if name.ends_with('/') {
+            continue;
+        }
+        if method != 0 {
+            return Err(archive_error(format!(
+                "'{}' is compressed and only stored files can be read",
+                name
+            )));
+        }
+        if get_u32(bytes, offset)? != 0x0403_4b50 {
+            return Err(archive_error(format!("'{}' is damaged", name)));
+        }
+        let start = offset + 30 + get_u16(bytes, offset + 26)? as usize;
+        let data = get(bytes, start + get_u16(bytes, offset + 28)? as usize, size)?;
+        if crc32(data) != crc {
+            return Err(archive_error(format!("'{}' is damaged", name)));
+        }
+        entries.push((name, data.to_vec()));
+    }
+    Ok(entries)
+}
+
+/// crc32 is the checksum zip files use to check each file is unchanged.
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            (crc >> 1) ^ (0xedb8_8320 & (crc & 1).wrapping_neg())
+        })
+    })
+}
+
+fn put_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend(value.to_le_bytes());
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend(value.to_le_bytes());
+}
+
+fn get(bytes: &[u8], at: usize, length: usize) -> Result<&[u8], SceneError> {
+    bytes
+        .get(at..at + length)
+        .ok_or_else(|| archive_error("it ends too soon"))
+}
+
+fn get_u16(bytes: &[u8], at: usize) -> Result<u16, SceneError> {
+    Ok(u16::from_le_bytes(get(bytes, at, 2)?.try_into().unwrap()))
+}
+
+fn get_u32(bytes: &[u8], at: usize) -> Result<u32, SceneError> {
+    Ok(u32::from_le_bytes(get(bytes, at, 4)?.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod test_archive {
+    use std::path::PathBuf;
+
+    use crate::{
+        primatives::color::Color,
+        shapes::{group::Group, mesh::Mesh},
+        C,
+    };
+
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("{}_{}", name, process::id()));
+        fs::create_dir_all(dir.join("models")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_zip() {
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+
+        let entries = vec![
+            ("scene.yml".to_string(), b"- add: camera".to_vec()),
+            ("models/empty.obj".to_string(), vec![]),
+        ];
+        let bytes = write_zip(&entries);
+        assert_eq!(entries, read_zip(&bytes).unwrap());
+
+        let mut damaged = bytes.clone();
+        damaged[30 + "scene.yml".len()] = b'!';
+        assert!(read_zip(&damaged).is_err());
+        assert!(read_zip(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_pack_and_load() {
+        let dir = test_dir("rtscene_pack");
+        fs::write(dir.join("models/colors.mtl"), "newmtl green\nKd 0 1 0").unwrap();
+        fs::write(
+            dir.join("models/triangle.obj"),
+            "mtllib colors.mtl\nv 0 1 0\nv -1 0 0\nv 1 0 0\nusemtl green\nf 1 2 3",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("scene.yml"),
+            "- add: camera\n  width: 10\n  height: 10\n  field-of-view: 1\n  from: [ 0, 0, -5 ]\n  to: [ 0, 0, 0 ]\n  up: [ 0, 1, 0 ]\n- add: obj\n  file: models/triangle.obj\n",
+        )
+        .unwrap();
+
+        let archive = env::temp_dir().join(format!("pack_{}{}", process::id(), ARCHIVE_EXTENSION));
+        let packed = pack(dir.join("scene.yml"), &archive);
+        fs::remove_dir_all(&dir).unwrap();
+        packed.unwrap();
+
+        // the scene loads from the archive alone now its folder is gone.
+        let entries = read_zip(&fs::read(&archive).unwrap()).unwrap();
+        let scene = super::super::load(&archive);
+        fs::remove_file(&archive).unwrap();
+
+        let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            vec!["scene.yml", "models/triangle.obj", "models/colors.mtl"],
+            names
+        );
+        // the model is a group holding a group of meshes for each of its own groups.
+        let scene = scene.unwrap();
+        let as_group = |shape: &dyn crate::shapes::Shape| -> Group {
+            shape.as_any().downcast_ref::<Group>().unwrap().clone()
+        };
+        let model = as_group(scene.world().objects()[0].as_ref());
+        let faces = as_group(model.children()[0].as_ref());
+        let mesh = faces.children()[0].as_any().downcast_ref::<Mesh>().unwrap();
+        assert_eq!(C![0., 1., 0.], mesh.face_material(0).color());
+    }
+
+    #[test]
+    fn test_pack_outside_folder() {
+        let dir = test_dir("rtscene_outside");
+        fs::write(
+            dir.join("triangle.obj"),
+            "v 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 3",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("models/scene.yml"),
+            "- add: camera\n  width: 10\n  height: 10\n  field-of-view: 1\n  from: [ 0, 0, -5 ]\n  to: [ 0, 0, 0 ]\n  up: [ 0, 1, 0 ]\n- add: obj\n  file: ../triangle.obj\n",
+        )
+        .unwrap();
+
+        let packed = pack(dir.join("models/scene.yml"), dir.join("out.rtscene.zip"));
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            matches!(packed, Err(SceneError::Format(m)) if m.contains("outside the scene's folder"))
+        );
+    }
+}
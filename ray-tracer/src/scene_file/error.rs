@@ -0,0 +1,218 @@
+use std::fmt::Display;
+
+use super::yaml::{Key, Node, YamlError};
+
+/// SceneError is returned when a scene file cannot be read or describes a scene that can't be built.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SceneError {
+    /// Io is any problem opening or reading the file.
+    Io(String),
+    /// Parse is a value that is badly written or doesn't make sense. It points at where the value starts,
+    /// names the key that was wrong if there was one and suggests what might have been meant.
+    Parse {
+        line: usize,
+        column: usize,
+        key: Option<String>,
+        message: String,
+        suggestion: Option<String>,
+    },
+    /// Format is a problem with the scene as a whole, such as it having no camera.
+    Format(String),
+}
+
+impl SceneError {
+    /// at makes an error pointing at the start of the value.
+    pub(super) fn at(node: &Node, message: impl Into<String>) -> Self {
+        SceneError::Parse {
+            line: node.line,
+            column: node.column,
+            key: None,
+            message: message.into(),
+            suggestion: None,
+        }
+    }
+
+    /// at_key makes an error about a key, pointing at where the key is written.
+    pub(super) fn at_key(key: &Key, message: impl Into<String>) -> Self {
+        SceneError::Parse {
+            line: key.line,
+            column: key.column,
+            key: Some(key.name.clone()),
+            message: message.into(),
+            suggestion: None,
+        }
+    }
+
+    /// with_key names the key the error is about.
+    pub(super) fn with_key(self, name: &str) -> Self {
+        match self {
+            SceneError::Parse {
+                line,
+                column,
+                message,
+                suggestion,
+                ..
+            } => SceneError::Parse {
+                line,
+                column,
+                key: Some(name.to_string()),
+                message,
+                suggestion,
+            },
+            other => other,
+        }
+    }
+
+    pub(super) fn has_suggestion(&self) -> bool {
+        matches!(
+            self,
+            SceneError::Parse {
+                suggestion: Some(_),
+                ..
+            }
+        )
+    }
+
+    /// suggest adds the closest of the options to the error if there is one close enough to what was written.
+    pub(super) fn suggest<'a>(
+        self,
+        written: &str,
+        options: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        match self {
+            SceneError::Parse {
+                line,
+                column,
+                key,
+                message,
+                ..
+            } => SceneError::Parse {
+                line,
+                column,
+                key,
+                message,
+                suggestion: did_you_mean(written, options),
+            },
+            other => other,
+        }
+    }
+}
+
+impl Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneError::Io(message) => write!(f, "could not read scene: {}", message),
+            SceneError::Parse {
+                line,
+                column,
+                message,
+                suggestion,
+                ..
+            } => {
+                write!(f, "line {}, column {}: {}", line, column, message)?;
+                match suggestion {
+                    Some(suggestion) => write!(f, ", did you mean '{}'?", suggestion),
+                    None => Ok(()),
+                }
+            }
+            SceneError::Format(message) => write!(f, "badly formed scene: {}", message),
+        }
+    }
+}
+
+impl From<std::io::Error> for SceneError {
+    fn from(err: std::io::Error) -> Self {
+        SceneError::Io(err.to_string())
+    }
+}
+
+impl From<YamlError> for SceneError {
+    fn from(err: YamlError) -> Self {
+        SceneError::Parse {
+            line: err.line,
+            column: err.column,
+            key: None,
+            message: err.message,
+            suggestion: None,
+        }
+    }
+}
+
+/// did_you_mean finds the option closest to what was written, allowing about one mistake for every three letters.
+fn did_you_mean<'a>(written: &str, options: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let allowed = (written.chars().count() / 3).max(1);
+    options
+        .into_iter()
+        .map(|option| (edit_distance(written, option), option))
+        .filter(|(distance, _)| *distance <= allowed)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, option)| option.to_string())
+}
+
+/// edit_distance is the number of letters that have to be added, removed, changed or swapped to turn a into b.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // rows[i][j] is the distance between the first i letters of a and the first j letters of b.
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod test_error {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(0, edit_distance("diffuse", "diffuse"));
+        assert_eq!(1, edit_distance("difuse", "diffuse"));
+        assert_eq!(1, edit_distance("spehre", "sphere"));
+        assert_eq!(3, edit_distance("kitten", "sitting"));
+        assert_eq!(5, edit_distance("", "plane"));
+    }
+
+    #[test]
+    fn test_did_you_mean() {
+        let keys = ["color", "diffuse", "specular", "shininess"];
+        assert_eq!(Some("diffuse".to_string()), did_you_mean("difuse", keys));
+        assert_eq!(
+            Some("shininess".to_string()),
+            did_you_mean("shinyness", keys)
+        );
+        assert_eq!(Some("color".to_string()), did_you_mean("colour", keys));
+        assert_eq!(None, did_you_mean("transparency", keys));
+    }
+
+    #[test]
+    fn test_display() {
+        let err = SceneError::Parse {
+            line: 3,
+            column: 5,
+            key: Some("difuse".to_string()),
+            message: "unknown key 'difuse'".to_string(),
+            suggestion: Some("diffuse".to_string()),
+        };
+        assert_eq!(
+            "line 3, column 5: unknown key 'difuse', did you mean 'diffuse'?",
+            err.to_string()
+        );
+    }
+}
@@ -0,0 +1,2094 @@
+mod archive;
+mod error;
+mod watch;
+pub mod yaml;
+
+pub use archive::{pack, ARCHIVE_EXTENSION};
+pub use error::SceneError;
+pub use watch::{Reload, WatchedScene};
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    importers::{obj::ObjImport, ppm::PpmImport},
+    primatives::{
+        color::Color,
+        matrix::Matrix,
+        point::Point,
+        transformation::{view_transformation, Transform},
+        tuple::Tuple,
+        vector::Vector,
+    },
+    shapes::{
+        cone::Cone,
+        cylinder::Cylinder,
+        disc::Disc,
+        group::Group,
+        make_mut,
+        material::Material,
+        patterns::{
+            blend::{BlendMode, BlendPattern},
+            checkered::CheckeredPattern,
+            dots::DotsPattern,
+            gradient::{Easing, GraidentPattern},
+            marble::MarblePattern,
+            ring::RingPattern,
+            striped::StripePattern,
+            texture_map::{TextureMap, UvImage, UvMapping},
+            wood::WoodPattern,
+            AddressMode, BoxedPattern, PatternBase,
+        },
+        plane::Plane,
+        quad::Quad,
+        sphere::Sphere,
+        torus::Torus,
+        triangle::Triangle,
+        BoxedShape, ShapeBase,
+    },
+    world::{
+        camera::{Bokeh, BokehMask, Camera, Projection},
+        canvas::MAX_PIXELS,
+        environment::Environment,
+        light::{BoxedLight, LightBase, PointLight},
+        scene::Scene,
+        World,
+    },
+};
+use yaml::{Key, Node};
+
+/// load reads and builds the scene file at the given path. Files the scene uses, such as models,
+/// are found relative to the scene file. A scene packed into a `.rtscene.zip` archive is loaded from the archive.
+pub fn load(path: impl AsRef<Path>) -> Result<Scene, SceneError> {
+    let path = path.as_ref();
+    if archive::is_archive(path) {
+        return archive::load(path);
+    }
+    read(&fs::read_to_string(path)?, Some(directory_of(path))).map(|(scene, _)| scene)
+}
+
+/// parse builds a scene from the YAML format described in the appendix of The Ray Tracer Challenge.
+///
+/// A scene file is a list of items. Each item either adds something to the scene with `add`
+/// (a camera, a light, an environment or a shape) or names a material or transform with `define` so it can be reused.
+/// A definition can `extend` another: a material takes the other material's values and replaces
+/// the ones it sets again, and a transform applies the other transform's steps before its own.
+///
+/// A transform is a list of steps such as `[ translate, 1, 2, 3 ]`, applied in the order they are listed.
+/// The list can also hold the names of defined transforms and nested lists, which are expanded in place,
+/// so `[ standard-transform, [ scale, 0.5 ], [ translate, 1, 0, 0 ] ]` adds two steps to a defined transform.
+///
+/// Definitions can be used anywhere in the file, each name can only be defined once and a definition
+/// can't refer back to itself.
+///
+/// An `obj` shape loads the model named by its `file`, and an `environment` wraps the PPM image named by its `file`
+/// around the scene, with an `intensity` to scale it by, a `yaw` in radians to turn it around the y axis and the
+/// number of `samples` to light each point with. A scene has at most one environment. An `image` pattern wraps the
+/// PPM image named by its `file` onto a shape with a `mapping` and fills the space around it by its `address` mode.
+/// Files are found relative to the working directory
+/// when parsing a string and relative to the scene file when it is loaded from a path.
+pub fn parse(input: &str) -> Result<Scene, SceneError> {
+    read(input, Some(Path::new(""))).map(|(scene, _)| scene)
+}
+
+/// parse_without_files builds a scene like parse but never reads a file, so a scene from somewhere that isn't trusted
+/// can't make it read something huge or that never ends, such as /dev/zero. An item that names a file, such as an
+/// `obj` shape, an `environment`, an `image` pattern or a bokeh `mask`, is an error.
+pub fn parse_without_files(input: &str) -> Result<Scene, SceneError> {
+    read(input, None).map(|(scene, _)| scene)
+}
+
+/// read builds a scene whose files are found relative to dir, along with the paths of every file it read.
+/// Without a dir it reads no files.
+fn read(input: &str, dir: Option<&Path>) -> Result<(Scene, Vec<PathBuf>), SceneError> {
+    let document = yaml::parse(input)?;
+    let items = items(&document)?;
+
+    let mut loader = Loader {
+        dir: dir.map(Path::to_path_buf),
+        ..Loader::default()
+    };
+    for item in items {
+        loader.collect_definition(item)?;
+    }
+    loader.resolve_definitions()?;
+    for item in items {
+        loader.item(item)?;
+    }
+    let files = loader.files.take();
+    Ok((loader.finish()?, files))
+}
+
+/// items are the items listed in a scene file, of which an empty file has none.
+fn items(document: &Node) -> Result<&[Node], SceneError> {
+    match &document.value {
+        yaml::Value::Null => Ok(&[]),
+        _ => document
+            .as_sequence()
+            .map(Vec::as_slice)
+            .ok_or_else(|| error(document, "a scene file should be a list of items")),
+    }
+}
+
+/// directory_of is the folder a file is in, which is empty for a file in the working directory.
+fn directory_of(path: &Path) -> &Path {
+    path.parent().unwrap_or(Path::new(""))
+}
+
+fn error(node: &Node, message: impl Into<String>) -> SceneError {
+    SceneError::at(node, message)
+}
+
+/// TRANSFORM_STEPS are the names of the steps a transform can be built from.
+const TRANSFORM_STEPS: [&str; 6] = [
+    "translate",
+    "scale",
+    "rotate-x",
+    "rotate-y",
+    "rotate-z",
+    "shear",
+];
+
+/// SHAPES are the kinds of shape that can be added.
+const SHAPES: [&str; 10] = [
+    "sphere", "plane", "cylinder", "cone", "quad", "torus", "disc", "triangle", "group", "obj",
+];
+
+/// PATTERNS are the kinds of pattern a material can have.
+const PATTERNS: [&str; 9] = [
+    "stripes", "gradient", "rings", "checkers", "blend", "marble", "wood", "dots", "image",
+];
+
+/// UV_MAPPINGS are the ways an image pattern can be wrapped onto a shape.
+const UV_MAPPINGS: [&str; 4] = ["planar", "spherical", "cylindrical", "conical"];
+
+/// ADDRESS_MODES are the ways an image pattern can fill the space outside of the image.
+const ADDRESS_MODES: [&str; 3] = ["clamp", "repeat", "mirror"];
+
+/// EASINGS are the ways a gradient can move between its colors.
+const EASINGS: [&str; 3] = ["linear", "smoothstep", "cosine"];
+
+/// BLEND_MODES are the ways a blend pattern can mix its colors, other than by a weight.
+const BLEND_MODES: [&str; 3] = ["average", "multiply", "screen"];
+
+/// PROJECTIONS are the ways a camera can spread its pixels over the directions it looks in.
+const PROJECTIONS: [&str; 3] = ["perspective", "fisheye", "equirectangular"];
+
+/// Definition is a reusable value named by a `define` item.
+#[derive(Debug, Clone)]
+enum Definition {
+    /// Material keeps the entries of the material mapping so that extending it can replace some of them,
+    /// along with the material they make, which is shared by every shape using it.
+    Material(Vec<(Key, Node)>, Arc<Material>),
+    /// Transform keeps its steps with every named transform and nested list expanded.
+    Transform(Vec<Node>),
+}
+
+impl Definition {
+    fn kind(&self) -> &'static str {
+        match self {
+            Definition::Material(..) => "a material",
+            Definition::Transform(_) => "a transform",
+        }
+    }
+}
+
+/// RawDefinition is a `define` item as it was written, before the definitions it refers to have been looked up.
+#[derive(Debug, Clone)]
+struct RawDefinition {
+    name: Node,
+    extend: Option<Node>,
+    value: Node,
+}
+
+#[derive(Default)]
+struct Loader {
+    raw_definitions: Vec<RawDefinition>,
+    definitions: HashMap<String, Definition>,
+    objects: Vec<BoxedShape>,
+    lights: Vec<BoxedLight>,
+    cameras: Vec<Camera>,
+    environment: Option<Environment>,
+    /// dir is the folder files named in the scene are found relative to, or None if no files may be read.
+    dir: Option<PathBuf>,
+    /// files are the paths of every file read while building the scene, such as models and their materials.
+    files: RefCell<Vec<PathBuf>>,
+}
+
+impl Loader {
+    /// file_path is where to find the file the node names, which is an error when no files may be read.
+    fn file_path(&self, node: &Node) -> Result<PathBuf, SceneError> {
+        let name = string(node)?;
+        match &self.dir {
+            Some(dir) => Ok(dir.join(name)),
+            None => Err(error(
+                node,
+                format!(
+                    "'{}' can't be read as this scene is parsed without files",
+                    name
+                ),
+            )),
+        }
+    }
+
+    /// collect_definition remembers the item if it is a definition, so definitions can be used before they are written.
+    fn collect_definition(&mut self, item: &Node) -> Result<(), SceneError> {
+        let entries = Entries::new(item, "an item")?;
+        let Some(name) = entries.get("define") else {
+            return Ok(());
+        };
+        entries.allow(&["define", "extend", "value"])?;
+        let name_str = string(name)?;
+        if let Some(existing) = self.raw_definition(name_str) {
+            return Err(error(
+                name,
+                format!(
+                    "'{}' is already defined on line {}",
+                    name_str, existing.name.line
+                ),
+            ));
+        }
+        self.raw_definitions.push(RawDefinition {
+            name: name.clone(),
+            extend: entries.get("extend").cloned(),
+            value: entries.require("value")?.clone(),
+        });
+        Ok(())
+    }
+
+    fn raw_definition(&self, name: &str) -> Option<&RawDefinition> {
+        self.raw_definitions
+            .iter()
+            .find(|raw| raw.name.as_str() == Some(name))
+    }
+
+    /// resolve_definitions looks up what every definition refers to, so mistakes are reported even in definitions
+    /// that are never used. Transforms are resolved first as the patterns in materials can use them.
+    fn resolve_definitions(&mut self) -> Result<(), SceneError> {
+        let (transforms, materials): (Vec<_>, Vec<_>) = self
+            .raw_definitions
+            .clone()
+            .into_iter()
+            .partition(|raw| raw.value.as_sequence().is_some());
+        for raw in transforms.iter().chain(&materials) {
+            self.resolve(&raw.name, &mut vec![])?;
+        }
+        Ok(())
+    }
+
+    /// resolve works out the definition named by the node, resolving the definitions it refers to first.
+    /// `stack` holds the names being resolved, which is how a definition that refers back to itself is caught.
+    fn resolve(&mut self, name: &Node, stack: &mut Vec<String>) -> Result<Definition, SceneError> {
+        let name_str = string(name)?;
+        if let Some(definition) = self.definitions.get(name_str) {
+            return Ok(definition.clone());
+        }
+        if let Some(start) = stack.iter().position(|n| n == name_str) {
+            let mut cycle = stack[start..].to_vec();
+            cycle.push(name_str.to_string());
+            return Err(error(
+                name,
+                format!("'{}' refers to itself: {}", name_str, cycle.join(" -> ")),
+            ));
+        }
+        let Some(raw) = self.raw_definition(name_str).cloned() else {
+            let names = self
+                .raw_definitions
+                .iter()
+                .filter_map(|raw| raw.name.as_str());
+            return Err(error(name, format!("'{}' has not been defined", name_str))
+                .suggest(name_str, names));
+        };
+
+        stack.push(name_str.to_string());
+        let parent = match &raw.extend {
+            Some(parent) => Some(self.resolve(parent, stack)?),
+            None => None,
+        };
+        let value = &raw.value;
+        let definition = match (&value.value, parent) {
+            (yaml::Value::Mapping(own), parent) => {
+                let mut merged = match parent {
+                    Some(Definition::Material(entries, _)) => entries,
+                    Some(other) => {
+                        return Err(error(
+                            value,
+                            format!("a material can't extend {}", other.kind()),
+                        ))
+                    }
+                    None => vec![],
+                };
+                for (key, node) in own {
+                    match merged.iter_mut().find(|(k, _)| k.name == key.name) {
+                        Some(existing) => *existing = (key.clone(), node.clone()),
+                        None => merged.push((key.clone(), node.clone())),
+                    }
+                }
+                // build the material now so mistakes are reported where they are defined.
+                let material = self.material_entries(&Entries::from_entries(&merged, value))?;
+                Definition::Material(merged, Arc::new(material))
+            }
+            (yaml::Value::Sequence(_), parent) => {
+                let mut steps = match parent {
+                    Some(Definition::Transform(steps)) => steps,
+                    Some(other) => {
+                        return Err(error(
+                            value,
+                            format!("a transform can't extend {}", other.kind()),
+                        ))
+                    }
+                    None => vec![],
+                };
+                steps.extend(expand_transform(value, &mut |n| self.resolve(n, stack))?);
+                transform_steps(&steps)?;
+                Definition::Transform(steps)
+            }
+            _ => {
+                return Err(error(
+                    value,
+                    format!(
+                        "a definition's value should be a material mapping or a list of transforms but is {}",
+                        value.kind()
+                    ),
+                ))
+            }
+        };
+        stack.pop();
+
+        self.definitions
+            .insert(name_str.to_string(), definition.clone());
+        Ok(definition)
+    }
+
+    /// definition looks up the definition named by the node, once every definition has been resolved.
+    fn definition(&self, name: &Node) -> Result<Definition, SceneError> {
+        let name_str = string(name)?;
+        self.definitions.get(name_str).cloned().ok_or_else(|| {
+            error(name, format!("'{}' has not been defined", name_str))
+                .suggest(name_str, self.definitions.keys().map(String::as_str))
+        })
+    }
+
+    fn item(&mut self, item: &Node) -> Result<(), SceneError> {
+        let entries = Entries::new(item, "an item")?;
+        if entries.get("define").is_some() {
+            return Ok(());
+        }
+        let Some(kind) = entries.get("add") else {
+            return Err(error(
+                item,
+                "expected an item starting with 'add' or 'define'",
+            ));
+        };
+
+        match string(kind)? {
+            "camera" => {
+                entries.allow(&[
+                    "add",
+                    "width",
+                    "height",
+                    "field-of-view",
+                    "from",
+                    "to",
+                    "up",
+                    "pixel-aspect",
+                    "aperture",
+                    "focal-distance",
+                    "bokeh",
+                    "projection",
+                ])?;
+                let mut camera = camera(&entries)?;
+                if let Some(node) = entries.get("bokeh") {
+                    camera.set_bokeh(self.bokeh(node).map_err(|err| err.with_key("bokeh"))?);
+                }
+                self.cameras.push(camera);
+            }
+            "light" => self.lights.push(light(&entries)?),
+            "environment" => {
+                if self.environment.is_some() {
+                    return Err(error(kind, "a scene can only have one environment"));
+                }
+                self.environment = Some(self.environment(&entries)?);
+            }
+            _ => {
+                let shape = self.shape(item)?;
+                self.objects.push(shape);
+            }
+        }
+        Ok(())
+    }
+
+    fn shape(&self, item: &Node) -> Result<BoxedShape, SceneError> {
+        let entries = Entries::new(item, "a shape")?;
+        let kind = entries.require("add")?;
+        let extra: &[&str] = match string(kind)? {
+            "cylinder" | "cone" => &["minimum", "maximum", "closed"],
+            "torus" => &["major-radius", "minor-radius"],
+            "disc" => &["inner-radius"],
+            "triangle" => &["p1", "p2", "p3"],
+            "group" => &["children"],
+            "obj" => &["file"],
+            _ => &[],
+        };
+        let mut allowed = vec!["add", "name", "material", "transform"];
+        allowed.extend(extra);
+        entries.allow(&allowed)?;
+
+        let transform = match entries.get("transform") {
+            Some(node) => Some(self.transform(node)?),
+            None => None,
+        };
+        let material = match entries.get("material") {
+            Some(node) => Some(self.material(node)?),
+            None => None,
+        };
+
+        let mut shape = match string(kind)? {
+            "sphere" => Sphere::new(transform, None).box_clone(),
+            "plane" => Plane::new(transform, None).box_clone(),
+            "cylinder" => {
+                let mut cylinder = Cylinder::new(transform, None);
+                let (minimum, maximum, closed) = self.limits(&entries)?;
+                cylinder.set_limits(minimum, maximum);
+                cylinder.set_closed(closed);
+                cylinder.box_clone()
+            }
+            "cone" => {
+                let mut cone = Cone::new(transform, None);
+                let (minimum, maximum, closed) = self.limits(&entries)?;
+                cone.set_limits(minimum, maximum);
+                cone.set_closed(closed);
+                cone.box_clone()
+            }
+            "quad" => Quad::new(transform, None).box_clone(),
+            "torus" => {
+                let mut torus = Torus::new(transform, None);
+                let major = entries.number_or("major-radius", torus.major_radius())?;
+                let minor = entries.number_or("minor-radius", torus.minor_radius())?;
+                if major <= 0. || minor <= 0. {
+                    return Err(error(item, "a torus's radii must be greater than 0"));
+                }
+                torus.set_radii(major, minor);
+                torus.box_clone()
+            }
+            "disc" => {
+                let mut disc = Disc::new(transform, None);
+                let inner = entries.number_or("inner-radius", 0.)?;
+                if !(0. ..1.).contains(&inner) {
+                    return Err(error(
+                        entries.require("inner-radius")?,
+                        "a disc's inner radius must be at least 0 and less than 1",
+                    ));
+                }
+                disc.set_inner_radius(inner);
+                disc.box_clone()
+            }
+            "triangle" => Triangle::new(
+                point(entries.require("p1")?)?,
+                point(entries.require("p2")?)?,
+                point(entries.require("p3")?)?,
+                transform,
+                None,
+            )
+            .box_clone(),
+            "group" => {
+                let mut group = Group::new(transform, None);
+                if let Some(children) = entries.get("children") {
+                    let children = children.as_sequence().ok_or_else(|| {
+                        error(
+                            children,
+                            format!("children should be a list but is {}", children.kind()),
+                        )
+                    })?;
+                    for child in children {
+                        group.add_child(self.shape(child)?);
+                    }
+                }
+                group.box_clone()
+            }
+            "obj" => {
+                let file = entries.require("file")?;
+                let path = self.file_path(file)?;
+                let import = ObjImport::load(&path).map_err(|err| {
+                    error(
+                        file,
+                        format!("could not load '{}': {}", path.display(), err),
+                    )
+                })?;
+                let mut files = self.files.borrow_mut();
+                files.push(path);
+                files.extend(import.material_libraries().iter().cloned());
+
+                let mut model = import.to_group();
+                if let Some(transform) = transform {
+                    model.set_transform(transform);
+                }
+                model.box_clone()
+            }
+            "camera" | "light" | "environment" => {
+                return Err(error(
+                    kind,
+                    format!("a {} can't be a group's child", string(kind)?),
+                ))
+            }
+            other => {
+                return Err(error(
+                    kind,
+                    format!("'{}' is not something that can be added", other),
+                )
+                .suggest(other, SHAPES.into_iter().chain(["camera", "light"])))
+            }
+        };
+        if let Some(material) = material {
+            make_mut(&mut shape).set_shared_material(material);
+        }
+        if let Some(name) = entries.get("name") {
+            make_mut(&mut shape).set_name(string(name)?);
+        }
+        Ok(shape)
+    }
+
+    /// material reads either a material mapping or the name of a defined material.
+    /// Every shape using the same defined material shares it.
+    fn material(&self, node: &Node) -> Result<Arc<Material>, SceneError> {
+        match &node.value {
+            yaml::Value::String(_) => match self.definition(node)? {
+                Definition::Material(_, material) => Ok(material),
+                other => Err(error(
+                    node,
+                    format!("'{}' is {}, not a material", string(node)?, other.kind()),
+                )),
+            },
+            _ => Ok(Arc::new(
+                self.material_entries(&Entries::new(node, "a material")?)?,
+            )),
+        }
+    }
+
+    /// limits reads the heights a cylinder or cone is truncated between and whether its ends are capped.
+    fn limits(&self, entries: &Entries) -> Result<(f64, f64, bool), SceneError> {
+        let minimum = entries.number_or("minimum", f64::NEG_INFINITY)?;
+        let maximum = entries.number_or("maximum", f64::INFINITY)?;
+        if minimum >= maximum {
+            return Err(error(
+                entries.require("maximum")?,
+                "the maximum must be above the minimum",
+            ));
+        }
+        let closed = entries.bool_or("closed", false)?;
+        Ok((minimum, maximum, closed))
+    }
+
+    fn material_entries(&self, entries: &Entries) -> Result<Material, SceneError> {
+        entries.allow(&[
+            "color",
+            "ambient",
+            "diffuse",
+            "specular",
+            "shininess",
+            "reflective",
+            "reflection-blur",
+            "transparency",
+            "refractive-index",
+            "casts-shadow",
+            "pattern",
+            "bump",
+            "bump-strength",
+            "emissive",
+        ])?;
+        let defaults = Material::default();
+        let mut builder = Material::builder();
+        builder
+            .color(match entries.get("color") {
+                Some(node) => color(node)?,
+                None => defaults.color(),
+            })
+            .ambient(entries.number_or("ambient", defaults.ambient())?)
+            .diffuse(entries.number_or("diffuse", defaults.diffuse())?)
+            .specular(entries.number_or("specular", defaults.specular())?)
+            .shininess(entries.number_or("shininess", defaults.shininess())?)
+            .reflective(entries.number_or("reflective", defaults.reflective())?)
+            .reflection_blur(entries.number_or("reflection-blur", defaults.reflection_blur())?)
+            .transparency(entries.number_or("transparency", defaults.transparency())?)
+            .refractive_index(entries.number_or("refractive-index", defaults.refractive_index())?)
+            .casts_shadow(entries.bool_or("casts-shadow", defaults.casts_shadow())?)
+            .bump_strength(entries.number_or("bump-strength", defaults.bump_strength())?)
+            .emissive(match entries.get("emissive") {
+                Some(node) => color(node)?,
+                None => defaults.emissive(),
+            });
+        if let Some(node) = entries.get("pattern") {
+            builder.pattern(self.pattern(node)?);
+        }
+        if let Some(node) = entries.get("bump") {
+            builder.bump_map(self.pattern(node)?);
+        }
+        Ok(builder
+            .build_owned()
+            .expect("every material field has a default"))
+    }
+
+    fn pattern(&self, node: &Node) -> Result<BoxedPattern, SceneError> {
+        let entries = Entries::new(node, "a pattern")?;
+        entries.allow(&[
+            "type",
+            "colors",
+            "transform",
+            "mode",
+            "weight",
+            "spacing",
+            "radius",
+            "width",
+            "axis",
+            "stops",
+            "easing",
+            "file",
+            "mapping",
+            "address",
+        ])?;
+        // either color can be another pattern, filling the stripes or squares it is given.
+        let slot = |node: &Node| -> Result<BoxedPattern, SceneError> {
+            match node.as_mapping() {
+                Some(_) => self.pattern(node),
+                None => Ok(color(node)?.into()),
+            }
+        };
+        let transform = match entries.get("transform") {
+            Some(node) => Some(self.transform(node)?),
+            None => None,
+        };
+        let kind = entries.require("type")?;
+
+        if string(kind)? == "image" {
+            return self.image_pattern(&entries, transform);
+        }
+
+        if let Some(stops) = entries.get("stops") {
+            if string(kind)? != "gradient" {
+                return Err(error(stops, "only a gradient has stops"));
+            }
+            if entries.get("colors").is_some() {
+                return Err(error(
+                    stops,
+                    "a gradient has either colors or stops, not both",
+                ));
+            }
+            let stops = stops
+                .as_sequence()
+                .filter(|stops| !stops.is_empty())
+                .ok_or_else(|| error(stops, "a gradient's stops should be a list of stops"))?
+                .iter()
+                .map(|stop| match stop.as_sequence().map(|s| &s[..]) {
+                    Some([position, color]) => match number(position)? {
+                        p if (0. ..=1.).contains(&p) => Ok((p, slot(color)?)),
+                        _ => Err(error(
+                            position,
+                            "a gradient stop's position must be between 0 and 1",
+                        )),
+                    },
+                    _ => Err(error(
+                        stop,
+                        "a gradient stop should be a position and a color",
+                    )),
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut gradient = GraidentPattern::with_stops(stops, transform);
+            gradient.set_easing(easing(&entries)?);
+            return Ok(gradient.box_clone());
+        }
+
+        let colors = entries.require("colors")?;
+        let [a, b] = colors.as_sequence().map(|c| &c[..]).unwrap_or_default() else {
+            return Err(error(colors, "a pattern needs a list of two colors"));
+        };
+        let (a, b) = (slot(a)?, slot(b)?);
+        Ok(match string(kind)? {
+            "stripes" => {
+                let mut stripes = StripePattern::new(a, b, transform);
+                if let Some(width) = entries.get("width") {
+                    match number(width)? {
+                        w if w > 0. => stripes.set_width(w),
+                        _ => return Err(error(width, "stripes must be wider than 0")),
+                    }
+                }
+                if let Some(axis) = entries.get("axis") {
+                    stripes.set_axis(stripe_axis(axis)?);
+                }
+                stripes.box_clone()
+            }
+            "gradient" => {
+                let mut gradient = GraidentPattern::new(a, b, transform);
+                gradient.set_easing(easing(&entries)?);
+                gradient.box_clone()
+            }
+            "rings" => RingPattern::new(a, b, transform).box_clone(),
+            "checkers" => CheckeredPattern::new(a, b, transform).box_clone(),
+            "blend" => BlendPattern::new(a, b, blend_mode(&entries)?, transform).box_clone(),
+            "marble" => MarblePattern::new(a, b, transform).box_clone(),
+            "wood" => {
+                let spacing = entries.number_or("spacing", 1.)?;
+                if spacing <= 0. {
+                    return Err(error(
+                        entries.require("spacing")?,
+                        "the rings of wood must be more than 0 apart",
+                    ));
+                }
+                WoodPattern::new(a, b, spacing, transform).box_clone()
+            }
+            "dots" => {
+                let radius = entries.number_or("radius", 0.25)?;
+                if radius <= 0. {
+                    return Err(error(
+                        entries.require("radius")?,
+                        "the radius of a dot must be greater than 0",
+                    ));
+                }
+                DotsPattern::new(a, b, radius, transform).box_clone()
+            }
+            other => {
+                return Err(error(
+                    kind,
+                    format!(
+                        "'{}' is not a pattern, expected one of {}",
+                        other,
+                        PATTERNS.join(", ")
+                    ),
+                )
+                .suggest(other, PATTERNS))
+            }
+        })
+    }
+
+    /// transform reads a transform, either a list of steps or the name of a defined transform.
+    fn transform(&self, node: &Node) -> Result<Matrix, SceneError> {
+        let steps = expand_transform(node, &mut |n| self.definition(n))?;
+        invertible(transform_steps(&steps)?, node)
+    }
+
+    fn finish(self) -> Result<Scene, SceneError> {
+        let mut cameras = self.cameras.into_iter();
+        let camera = cameras
+            .next()
+            .ok_or_else(|| SceneError::Format("the scene has no camera".to_string()))?;
+        let mut lights = self.lights.into_iter();
+        let mut world = World::new(self.objects, lights.next());
+        lights.for_each(|light| {
+            world.add_light(light);
+        });
+        world.set_environment(self.environment);
+        let mut scene = Scene::new(world, camera);
+        cameras.for_each(|camera| scene.add_camera(camera));
+        Ok(scene)
+    }
+}
+
+impl Loader {
+    /// image_pattern loads the PPM image an image pattern's `file` names and wraps it onto shapes with its `mapping`,
+    /// planar unless it says otherwise. Its `address` is how the space outside the image is filled, by clamping
+    /// to the edge pixels unless it says otherwise.
+    fn image_pattern(
+        &self,
+        entries: &Entries,
+        transform: Option<Matrix>,
+    ) -> Result<BoxedPattern, SceneError> {
+        let file = entries.require("file")?;
+        let path = self.file_path(file)?;
+        let import = PpmImport::load(&path).map_err(|err| {
+            error(
+                file,
+                format!("could not load '{}': {}", path.display(), err),
+            )
+        })?;
+        self.files.borrow_mut().push(path);
+
+        let mut image = UvImage::new(import.into_canvas());
+        if let Some(node) = entries.get("address") {
+            image.set_address_mode(match string(node)? {
+                "clamp" => AddressMode::Clamp,
+                "repeat" => AddressMode::Repeat,
+                "mirror" => AddressMode::Mirror,
+                other => {
+                    return Err(error(
+                        node,
+                        format!(
+                            "'{}' is not an address mode, expected one of {}",
+                            other,
+                            ADDRESS_MODES.join(", ")
+                        ),
+                    )
+                    .suggest(other, ADDRESS_MODES))
+                }
+            });
+        }
+        let mapping = match entries.get("mapping") {
+            None => UvMapping::Planar,
+            Some(node) => match string(node)? {
+                "planar" => UvMapping::Planar,
+                "spherical" => UvMapping::Spherical,
+                "cylindrical" => UvMapping::Cylindrical,
+                "conical" => UvMapping::Conical,
+                other => {
+                    return Err(error(
+                        node,
+                        format!(
+                            "'{}' is not a mapping, expected one of {}",
+                            other,
+                            UV_MAPPINGS.join(", ")
+                        ),
+                    )
+                    .suggest(other, UV_MAPPINGS))
+                }
+            },
+        };
+        Ok(TextureMap::new(image, mapping, transform).box_clone())
+    }
+
+    /// bokeh reads the shape of a camera's lens, either `sides` and an optional `rotation` for a polygon or `mask`
+    /// naming an image whose bright pixels make the shape.
+    fn bokeh(&self, node: &Node) -> Result<Bokeh, SceneError> {
+        let entries = Entries::new(node, "a bokeh")?;
+        entries.allow(&["sides", "rotation", "mask"])?;
+        match (entries.get("sides"), entries.get("mask")) {
+            (Some(sides), None) => {
+                let count = number(sides)?;
+                if count < 3. || count.fract() != 0. {
+                    return Err(error(
+                        sides,
+                        "the sides of a bokeh should be a whole number of at least 3",
+                    ));
+                }
+                Ok(Bokeh::Polygon {
+                    sides: count as usize,
+                    rotation: entries.number_or("rotation", 0.)?,
+                })
+            }
+            (None, Some(mask)) => {
+                if let Some(rotation) = entries.get("rotation") {
+                    return Err(error(rotation, "only a bokeh with sides can be rotated"));
+                }
+                let path = self.file_path(mask)?;
+                let image = PpmImport::load(&path)
+                    .map_err(|err| {
+                        error(
+                            mask,
+                            format!("could not load '{}': {}", path.display(), err),
+                        )
+                    })?
+                    .into_canvas();
+                self.files.borrow_mut().push(path);
+                BokehMask::new(&image).map(Bokeh::Mask).ok_or_else(|| {
+                    error(
+                        mask,
+                        "a bokeh mask needs at least one pixel brighter than half white",
+                    )
+                })
+            }
+            _ => Err(error(node, "a bokeh should have either sides or a mask")),
+        }
+    }
+
+    /// environment loads the image an environment item names and sets it up as the item says.
+    fn environment(&self, entries: &Entries) -> Result<Environment, SceneError> {
+        entries.allow(&["add", "file", "intensity", "yaw", "samples"])?;
+        let file = entries.require("file")?;
+        let path = self.file_path(file)?;
+        let import = PpmImport::load(&path).map_err(|err| {
+            error(
+                file,
+                format!("could not load '{}': {}", path.display(), err),
+            )
+        })?;
+        self.files.borrow_mut().push(path);
+
+        let mut environment = Environment::new(UvImage::new(import.into_canvas()));
+        environment.set_intensity(entries.number_or("intensity", environment.intensity())?);
+        environment.set_yaw(entries.number_or("yaw", environment.yaw())?);
+        let samples = entries.number_or("samples", environment.samples() as f64)?;
+        if samples < 0. || samples.fract() != 0. {
+            return Err(error(
+                entries.require("samples")?,
+                "the samples of an environment should be a whole number",
+            ));
+        }
+        environment.set_samples(samples as usize);
+        Ok(environment)
+    }
+}
+
+fn light(entries: &Entries) -> Result<BoxedLight, SceneError> {
+    entries.allow(&["add", "at", "intensity"])?;
+    Ok(PointLight::new(
+        point(entries.require("at")?)?,
+        color(entries.require("intensity")?)?,
+    )
+    .box_clone())
+}
+
+/// stripe_axis reads the direction stripes alternate in, either `x`, `y` or `z` or a vector pointing any way.
+fn stripe_axis(node: &Node) -> Result<Vector, SceneError> {
+    let axis = match node.as_str() {
+        Some("x") => return Ok(Vector::new(1., 0., 0.)),
+        Some("y") => return Ok(Vector::new(0., 1., 0.)),
+        Some("z") => return Ok(Vector::new(0., 0., 1.)),
+        Some(other) => {
+            return Err(error(
+                node,
+                format!("'{}' is not an axis, expected x, y, z or a vector", other),
+            )
+            .suggest(other, ["x", "y", "z"]))
+        }
+        None => vector(node)?,
+    };
+    if axis.magnitude() == 0. {
+        return Err(error(node, "the axis of stripes can't be a zero vector"));
+    }
+    Ok(axis)
+}
+
+/// easing reads how a gradient moves between its colors, which is linear unless it says otherwise.
+fn easing(entries: &Entries) -> Result<Easing, SceneError> {
+    let Some(node) = entries.get("easing") else {
+        return Ok(Easing::default());
+    };
+    match string(node)? {
+        "linear" => Ok(Easing::Linear),
+        "smoothstep" => Ok(Easing::Smoothstep),
+        "cosine" => Ok(Easing::Cosine),
+        other => Err(error(
+            node,
+            format!(
+                "'{}' is not an easing, expected one of {}",
+                other,
+                EASINGS.join(", ")
+            ),
+        )
+        .suggest(other, EASINGS)),
+    }
+}
+
+/// blend_mode reads how a blend pattern mixes its colors, either a named mode or a weight towards the second color.
+fn blend_mode(entries: &Entries) -> Result<BlendMode, SceneError> {
+    match (entries.get("mode"), entries.get("weight")) {
+        (Some(_), Some(weight)) => Err(error(
+            weight,
+            "a blend has either a mode or a weight, not both",
+        )),
+        (None, Some(weight)) => match number(weight)? {
+            w if (0. ..=1.).contains(&w) => Ok(BlendMode::Weighted(w)),
+            _ => Err(error(weight, "a blend weight must be between 0 and 1")),
+        },
+        (Some(mode), None) => match string(mode)? {
+            "average" => Ok(BlendMode::Average),
+            "multiply" => Ok(BlendMode::Multiply),
+            "screen" => Ok(BlendMode::Screen),
+            other => Err(error(
+                mode,
+                format!(
+                    "'{}' is not a blend mode, expected one of {} or a weight",
+                    other,
+                    BLEND_MODES.join(", ")
+                ),
+            )
+            .suggest(other, BLEND_MODES)),
+        },
+        (None, None) => Ok(BlendMode::default()),
+    }
+}
+
+fn invertible(m: Matrix, node: &Node) -> Result<Matrix, SceneError> {
+    match m.inverse() {
+        Ok(_) => Ok(m),
+        Err(_) => Err(error(node, "the transform cannot be inverted")),
+    }
+}
+
+/// expand_transform turns a transform into the list of steps it is made from,
+/// replacing the names of defined transforms with their steps and flattening nested lists.
+fn expand_transform(
+    node: &Node,
+    lookup: &mut dyn FnMut(&Node) -> Result<Definition, SceneError>,
+) -> Result<Vec<Node>, SceneError> {
+    match &node.value {
+        yaml::Value::String(name) if !is_step(node) => match lookup(node) {
+            Ok(Definition::Transform(steps)) => Ok(steps),
+            Ok(other) => Err(error(
+                node,
+                format!("'{}' is {}, not a transform", name, other.kind()),
+            )),
+            // the name may be a misspelt step rather than a misspelt definition.
+            Err(err) if !err.has_suggestion() => Err(err.suggest(name, TRANSFORM_STEPS)),
+            Err(err) => Err(err),
+        },
+        yaml::Value::Sequence(_) if is_step(node) => Ok(vec![node.clone()]),
+        yaml::Value::Sequence(items) => {
+            let mut steps = vec![];
+            for item in items {
+                steps.extend(expand_transform(item, lookup)?);
+            }
+            Ok(steps)
+        }
+        _ => Err(error(
+            node,
+            format!(
+                "a transform should be a list of steps or the name of a defined transform but is {}",
+                node.kind()
+            ),
+        )),
+    }
+}
+
+/// is_step is whether the node is a single step such as `[ translate, 1, 2, 3 ]` or `[ scale 0.5 ]`
+/// rather than a list of steps or a name.
+fn is_step(node: &Node) -> bool {
+    let first = match &node.value {
+        yaml::Value::Sequence(items) => items.first().and_then(Node::as_str),
+        _ => None,
+    };
+    first
+        .and_then(|s| s.split_whitespace().next())
+        .is_some_and(|name| TRANSFORM_STEPS.contains(&name))
+}
+
+/// transform_steps combines a list of steps, applying them in the order they are listed.
+fn transform_steps(steps: &[Node]) -> Result<Matrix, SceneError> {
+    steps
+        .iter()
+        .try_fold(Transform::new(), transform_step)
+        .map(Transform::build)
+}
+
+/// transform_step adds a step to the transform. The step's numbers can be separate items
+/// or written after the name in the same string, so `[ scale, 2, 2, 2 ]` and `[ scale 2 2 2 ]` are the same.
+fn transform_step(transform: Transform, step: &Node) -> Result<Transform, SceneError> {
+    let parts = step.as_sequence().ok_or_else(|| {
+        error(
+            step,
+            format!(
+                "a transform step should be a list like [ translate, 1, 2, 3 ] but is {}",
+                step.kind()
+            ),
+        )
+    })?;
+    let Some((first, rest)) = parts.split_first() else {
+        return Err(error(step, "a transform step can't be empty"));
+    };
+    let mut words = string(first)?.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    let mut args = vec![];
+    for word in words {
+        args.push(
+            word.parse::<f64>()
+                .map_err(|_| error(first, format!("'{}' is not a number", word)))?,
+        );
+    }
+    for arg in rest {
+        args.push(number(arg)?);
+    }
+
+    Ok(match (name, &args[..]) {
+        ("translate", &[x, y, z]) => transform.translate(x, y, z),
+        ("scale", &[s]) => transform.scale(s, s, s),
+        ("scale", &[x, y, z]) => transform.scale(x, y, z),
+        ("rotate-x", &[r]) => transform.rotate_x(r),
+        ("rotate-y", &[r]) => transform.rotate_y(r),
+        ("rotate-z", &[r]) => transform.rotate_z(r),
+        ("shear", &[xy, xz, yx, yz, zx, zy]) => transform.shear(xy, xz, yx, yz, zx, zy),
+        ("translate", _) => return Err(error(step, "translate needs 3 numbers")),
+        ("scale", _) => return Err(error(step, "scale needs 1 or 3 numbers")),
+        ("rotate-x" | "rotate-y" | "rotate-z", _) => {
+            return Err(error(step, format!("{} needs 1 number", name)))
+        }
+        ("shear", _) => return Err(error(step, "shear needs 6 numbers")),
+        _ => {
+            return Err(error(
+                step,
+                format!(
+                    "'{}' is not a transform step, expected one of {}",
+                    name,
+                    TRANSFORM_STEPS.join(", ")
+                ),
+            )
+            .suggest(name, TRANSFORM_STEPS))
+        }
+    })
+}
+
+fn camera(entries: &Entries) -> Result<Camera, SceneError> {
+    let size = |key: &str| -> Result<usize, SceneError> {
+        let node = entries.require(key)?;
+        match node.as_f64() {
+            Some(n) if n >= 1. && n.fract() == 0. => Ok(n as usize),
+            _ => Err(error(
+                node,
+                format!("{} should be a whole number greater than 0", key),
+            )),
+        }
+    };
+    let width = size("width")?;
+    // images are rendered a row at a time at the least, so a row has to fit in a canvas.
+    if width > MAX_PIXELS {
+        return Err(error(
+            entries.require("width")?,
+            format!("width can't be more than {} pixels", MAX_PIXELS),
+        ));
+    }
+    let mut camera = Camera::new(
+        width,
+        size("height")?,
+        number(entries.require("field-of-view")?)?,
+    );
+    let transform = view_transformation(
+        point(entries.require("from")?)?,
+        point(entries.require("to")?)?,
+        vector(entries.require("up")?)?,
+    );
+    camera.set_transform(invertible(transform, entries.node)?);
+    if let Some(node) = entries.get("pixel-aspect") {
+        let pixel_aspect = number(node).map_err(|err| err.with_key("pixel-aspect"))?;
+        if pixel_aspect <= 0. {
+            return Err(error(node, "pixel-aspect must be greater than 0").with_key("pixel-aspect"));
+        }
+        camera.set_pixel_aspect(pixel_aspect);
+    }
+    if let Some(node) = entries.get("aperture") {
+        let aperture = number(node).map_err(|err| err.with_key("aperture"))?;
+        if aperture < 0. {
+            return Err(error(node, "aperture can't be negative").with_key("aperture"));
+        }
+        camera.set_aperture(aperture);
+    }
+    if let Some(node) = entries.get("focal-distance") {
+        let focal_distance = number(node).map_err(|err| err.with_key("focal-distance"))?;
+        if focal_distance <= 0. {
+            return Err(
+                error(node, "focal-distance must be greater than 0").with_key("focal-distance")
+            );
+        }
+        camera.set_focal_distance(focal_distance);
+    }
+    if let Some(node) = entries.get("projection") {
+        let projection = match string(node).map_err(|err| err.with_key("projection"))? {
+            "perspective" => Projection::Perspective,
+            "fisheye" => Projection::Fisheye,
+            "equirectangular" => Projection::Equirectangular,
+            other => {
+                return Err(error(node, format!("'{}' is not a projection", other))
+                    .with_key("projection")
+                    .suggest(other, PROJECTIONS))
+            }
+        };
+        camera.set_projection(projection);
+    }
+    Ok(camera)
+}
+
+/// Entries are the entries of a mapping, looked up by key.
+struct Entries<'a> {
+    entries: &'a [(Key, Node)],
+    node: &'a Node,
+}
+
+impl<'a> Entries<'a> {
+    fn new(node: &'a Node, what: &str) -> Result<Self, SceneError> {
+        let entries = node.as_mapping().ok_or_else(|| {
+            error(
+                node,
+                format!("{} should be a mapping but is {}", what, node.kind()),
+            )
+        })?;
+        Ok(Self::from_entries(entries, node))
+    }
+
+    fn from_entries(entries: &'a [(Key, Node)], node: &'a Node) -> Self {
+        Self { entries, node }
+    }
+
+    fn get(&self, key: &str) -> Option<&'a Node> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k.name == key)
+            .map(|(_, node)| node)
+    }
+
+    fn require(&self, key: &str) -> Result<&'a Node, SceneError> {
+        self.get(key)
+            .ok_or_else(|| error(self.node, format!("'{}' is missing", key)).with_key(key))
+    }
+
+    fn number_or(&self, key: &str, default: f64) -> Result<f64, SceneError> {
+        self.get(key).map_or(Ok(default), |node| {
+            number(node).map_err(|err| err.with_key(key))
+        })
+    }
+
+    fn bool_or(&self, key: &str, default: bool) -> Result<bool, SceneError> {
+        self.get(key).map_or(Ok(default), |node| {
+            boolean(node).map_err(|err| err.with_key(key))
+        })
+    }
+
+    /// allow makes sure every key is one of the given keys, so that typing mistakes are not silently ignored.
+    fn allow(&self, keys: &[&str]) -> Result<(), SceneError> {
+        match self
+            .entries
+            .iter()
+            .find(|(k, _)| !keys.contains(&&k.name[..]))
+        {
+            Some((key, _)) => Err(SceneError::at_key(
+                key,
+                format!(
+                    "unknown key '{}', expected one of {}",
+                    key.name,
+                    keys.join(", ")
+                ),
+            )
+            .suggest(&key.name, keys.iter().copied())),
+            None => Ok(()),
+        }
+    }
+}
+
+fn string(node: &Node) -> Result<&str, SceneError> {
+    node.as_str()
+        .ok_or_else(|| error(node, format!("expected a name but found {}", node.kind())))
+}
+
+fn boolean(node: &Node) -> Result<bool, SceneError> {
+    node.as_bool().ok_or_else(|| {
+        error(
+            node,
+            format!("expected true or false but found {}", node.kind()),
+        )
+    })
+}
+
+fn number(node: &Node) -> Result<f64, SceneError> {
+    node.as_f64()
+        .ok_or_else(|| error(node, format!("expected a number but found {}", node.kind())))
+}
+
+fn triple(node: &Node) -> Result<(f64, f64, f64), SceneError> {
+    let values = match node.as_sequence() {
+        Some(items) => items.iter().map(number).collect::<Result<Vec<_>, _>>()?,
+        None => vec![],
+    };
+    match values[..] {
+        [x, y, z] => Ok((x, y, z)),
+        _ => Err(error(node, "expected a list of 3 numbers")),
+    }
+}
+
+fn point(node: &Node) -> Result<Point, SceneError> {
+    triple(node).map(|(x, y, z)| Point::new(x, y, z))
+}
+
+fn vector(node: &Node) -> Result<Vector, SceneError> {
+    triple(node).map(|(x, y, z)| Vector::new(x, y, z))
+}
+
+fn color(node: &Node) -> Result<Color, SceneError> {
+    triple(node).map(|(r, g, b)| Color::new(r, g, b))
+}
+
+#[cfg(test)]
+mod test_scene_file {
+    use crate::{
+        comparison::approx_eq,
+        primatives::transformation::{scaling, translation},
+        primatives::tuple::Tuple,
+        shapes::patterns::Pattern,
+        C, P, V,
+    };
+
+    use super::*;
+
+    const CAMERA: &str = "
+- add: camera
+  width: 20
+  height: 10
+  field-of-view: 1.0
+  from: [ 0, 1, -5 ]
+  to: [ 0, 0, 0 ]
+  up: [ 0, 1, 0 ]
+";
+
+    fn parse_with_camera(input: &str) -> Result<Scene, SceneError> {
+        parse(&format!("{}{}", CAMERA, input))
+    }
+
+    fn line_of(err: SceneError) -> usize {
+        match err {
+            SceneError::Parse { line, .. } => line,
+            other => panic!("expected a parse error but got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_basic_scene() {
+        let scene = parse_with_camera(
+            "
+- add: light
+  at: [ -10, 10, -10 ]
+  intensity: [ 1, 1, 1 ]
+
+- add: sphere
+  transform:
+    - [ scale, 2, 2, 2 ]
+    - [ translate, 0, 1, 0 ]
+  material:
+    color: [ 1, 0.2, 1 ]
+    diffuse: 0.7
+    pattern:
+      type: stripes
+      colors:
+        - [ 1, 1, 1 ]
+        - [ 0, 0, 0 ]
+
+- add: plane
+
+- add: light
+  at: [ 10, 10, -10 ]
+  intensity: [ 0.5, 0.5, 0.5 ]
+",
+        )
+        .unwrap();
+
+        let camera = scene.camera(0).unwrap();
+        assert_eq!((20, 10), (camera.hsize(), camera.vsize()));
+        assert!(approx_eq(1., camera.fov()));
+        assert!(approx_eq(1., camera.pixel_aspect()));
+
+        let world = scene.world();
+        assert_eq!(C![1., 1., 1.], world.light().unwrap().intensity());
+        assert_eq!(2, world.lights().count());
+        assert_eq!(2, world.objects().len());
+
+        let sphere = &world.objects()[0];
+        assert_eq!(
+            &(translation(0., 1., 0.) * scaling(2., 2., 2.)),
+            sphere.transformation()
+        );
+        assert_eq!(C![1., 0.2, 1.], sphere.material().color());
+        assert!(approx_eq(0.7, sphere.material().diffuse()));
+        assert!(sphere.material().pattern().is_some());
+        // values that aren't set keep the material's defaults.
+        assert!(approx_eq(
+            Material::default().ambient(),
+            sphere.material().ambient()
+        ));
+    }
+
+    #[test]
+    fn test_nested_patterns() {
+        let scene = parse_with_camera(
+            "
+- add: plane
+  material:
+    pattern:
+      type: checkers
+      colors:
+        - [ 1, 1, 1 ]
+        - type: stripes
+          colors: [ [ 1, 0, 0 ], [ 0, 0, 1 ] ]
+          transform:
+            - [ scale, 0.25, 1, 1 ]
+",
+        )
+        .unwrap();
+
+        let plane = &scene.world().objects()[0];
+        let pattern = plane.material().pattern().unwrap();
+        assert_eq!(C![1., 1., 1.], pattern.local_color_at(P![0.5, 0., 0.5]));
+        // the other squares are striped four times as finely as the checkers.
+        assert_eq!(C![1., 0., 0.], pattern.local_color_at(P![1.1, 0., 0.5]));
+        assert_eq!(C![0., 0., 1.], pattern.local_color_at(P![1.3, 0., 0.5]));
+    }
+
+    #[test]
+    fn test_blend_pattern() {
+        let scene = parse_with_camera(
+            "
+- add: plane
+  material:
+    pattern:
+      type: blend
+      mode: multiply
+      colors:
+        - type: checkers
+          colors: [ [ 1, 1, 1 ], [ 0.5, 0.5, 0.5 ] ]
+        - [ 1, 0, 0 ]
+",
+        )
+        .unwrap();
+        let pattern = scene.world().objects()[0].material().pattern().unwrap();
+        assert_eq!(C![1., 0., 0.], pattern.local_color_at(P![0.5, 0., 0.5]));
+        assert_eq!(C![0.5, 0., 0.], pattern.local_color_at(P![1.5, 0., 0.5]));
+
+        let weighted = "- add: plane\n  material:\n    pattern: { type: blend, weight: 0.25, colors: [ [ 0, 0, 0 ], [ 1, 1, 1 ] ] }\n";
+        let scene = parse_with_camera(weighted).unwrap();
+        let pattern = scene.world().objects()[0].material().pattern().unwrap();
+        assert_eq!(C![0.25, 0.25, 0.25], pattern.local_color_at(P![0., 0., 0.]));
+
+        let bad = weighted.replace("weight: 0.25", "mode: multipy");
+        let err = parse_with_camera(&bad).unwrap_err();
+        assert!(
+            err.to_string().contains("did you mean 'multiply'"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_wood_pattern() {
+        let wood = "- add: plane\n  material:\n    pattern: { type: wood, spacing: 0.5, colors: [ [ 0, 0, 0 ], [ 1, 1, 1 ] ] }\n";
+        let scene = parse_with_camera(wood).unwrap();
+        let pattern = scene.world().objects()[0].material().pattern().unwrap();
+        let pattern = pattern.as_any().downcast_ref::<WoodPattern>().unwrap();
+        assert_eq!(0.5, pattern.spacing());
+
+        let err = parse_with_camera(&wood.replace("spacing: 0.5", "spacing: 0")).unwrap_err();
+        assert_eq!(
+            "line 11, column 37: the rings of wood must be more than 0 apart",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_stripes_pattern() {
+        let stripes = "- add: plane\n  material:\n    pattern: { type: stripes, width: 0.5, axis: z, colors: [ [ 1, 1, 1 ], [ 0, 0, 0 ] ] }\n";
+        let scene = parse_with_camera(stripes).unwrap();
+        let pattern = scene.world().objects()[0].material().pattern().unwrap();
+        let pattern = pattern.as_any().downcast_ref::<StripePattern>().unwrap();
+        assert_eq!(0.5, pattern.width());
+        assert_eq!(V![0., 0., 1.], pattern.axis());
+
+        let scene = parse_with_camera(&stripes.replace("axis: z", "axis: [ 1, 1, 0 ]")).unwrap();
+        let pattern = scene.world().objects()[0].material().pattern().unwrap();
+        let pattern = pattern.as_any().downcast_ref::<StripePattern>().unwrap();
+        assert_eq!(V![1., 1., 0.].norm(), pattern.axis());
+
+        let err = parse_with_camera(&stripes.replace("axis: z", "axis: w")).unwrap_err();
+        assert!(err.to_string().contains("'w' is not an axis"), "{}", err);
+        let err = parse_with_camera(&stripes.replace("axis: z", "axis: [ 0, 0, 0 ]")).unwrap_err();
+        assert!(err.to_string().contains("zero vector"), "{}", err);
+        let err = parse_with_camera(&stripes.replace("width: 0.5", "width: -1")).unwrap_err();
+        assert!(err.to_string().contains("wider than 0"), "{}", err);
+    }
+
+    #[test]
+    fn test_gradient_stops() {
+        let sunset = "- add: plane\n  material:\n    pattern:\n      type: gradient\n      easing: smoothstep\n      stops:\n        - [ 0, [ 1, 0.5, 0 ] ]\n        - [ 0.5, [ 1, 0, 0 ] ]\n        - [ 1, [ 0, 0, 0.5 ] ]\n";
+        let scene = parse_with_camera(sunset).unwrap();
+        let pattern = scene.world().objects()[0].material().pattern().unwrap();
+        let pattern = pattern.as_any().downcast_ref::<GraidentPattern>().unwrap();
+        assert_eq!(3, pattern.stops().len());
+        assert_eq!(Easing::Smoothstep, pattern.easing());
+        assert_eq!(C![1., 0., 0.], pattern.local_color_at(P![0.5, 0., 0.]));
+
+        let err = parse_with_camera(&sunset.replace("[ 0.5, [", "[ 1.5, [")).unwrap_err();
+        assert!(err.to_string().contains("between 0 and 1"), "{}", err);
+        let err = parse_with_camera(&sunset.replace("smoothstep", "smoothstop")).unwrap_err();
+        assert!(
+            err.to_string().contains("did you mean 'smoothstep'"),
+            "{}",
+            err
+        );
+        let err = parse_with_camera(&sunset.replace("type: gradient", "type: rings")).unwrap_err();
+        assert!(
+            err.to_string().contains("only a gradient has stops"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_dots_pattern() {
+        let dots = "- add: sphere\n  material:\n    pattern: { type: dots, colors: [ [ 1, 0, 0 ], [ 1, 1, 1 ] ] }\n";
+        let scene = parse_with_camera(dots).unwrap();
+        let pattern = scene.world().objects()[0].material().pattern().unwrap();
+        let pattern = pattern.as_any().downcast_ref::<DotsPattern>().unwrap();
+        assert_eq!(0.25, pattern.radius());
+
+        let scene =
+            parse_with_camera(&dots.replace("type: dots", "type: dots, radius: 0.4")).unwrap();
+        let pattern = scene.world().objects()[0].material().pattern().unwrap();
+        assert_eq!(C![1., 0., 0.], pattern.local_color_at(P![0.3, 0., 0.2]));
+    }
+
+    #[test]
+    fn test_image_pattern() {
+        let dir = std::env::temp_dir().join(format!("scene_image_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // black on the left and white on the right.
+        fs::write(dir.join("half.ppm"), "P3\n2 1\n255\n0 0 0 255 255 255\n").unwrap();
+        let input = format!(
+            "{}- add: sphere\n  material:\n    pattern: {{ type: image, file: half.ppm }}\n",
+            CAMERA
+        );
+        let clamped = read(&input, Some(&dir));
+        let repeated = read(
+            &input.replace("half.ppm }", "half.ppm, address: repeat }"),
+            Some(&dir),
+        );
+        let wrong = read(
+            &input.replace("half.ppm }", "half.ppm, address: mirrored }"),
+            Some(&dir),
+        );
+        fs::remove_dir_all(&dir).unwrap();
+
+        let (scene, files) = clamped.unwrap();
+        assert_eq!(vec![dir.join("half.ppm")], files);
+        let pattern = scene.world().objects()[0].material().pattern().unwrap();
+        // planar mapping lays the image along x, and past its right edge the right edge pixel carries on.
+        assert_eq!(Color::BLACK, pattern.local_color_at(P![0.2, 0., 0.]));
+        assert_eq!(Color::WHITE, pattern.local_color_at(P![0.8, 0., 0.]));
+        assert_eq!(Color::WHITE, pattern.local_color_at(P![1.2, 0., 0.]));
+
+        let (scene, _) = repeated.unwrap();
+        let pattern = scene.world().objects()[0].material().pattern().unwrap();
+        assert_eq!(Color::BLACK, pattern.local_color_at(P![1.2, 0., 0.]));
+
+        assert_eq!(
+            "line 11, column 54: 'mirrored' is not an address mode, expected one of clamp, repeat, mirror, did you mean 'mirror'?",
+            wrong.unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_parse_without_files() {
+        let scene = parse_without_files(&format!("{}- add: sphere\n", CAMERA)).unwrap();
+        assert_eq!(1, scene.world().objects().len());
+
+        let inputs = [
+            "- add: obj\n  file: /dev/zero\n",
+            "- add: environment\n  file: /dev/zero\n",
+            "- add: sphere\n  material:\n    pattern: { type: image, file: /dev/zero }\n",
+            "  bokeh:\n    mask: /dev/zero\n",
+        ];
+        for input in inputs {
+            let err = parse_without_files(&format!("{}{}", CAMERA, input)).unwrap_err();
+            assert!(
+                err.to_string()
+                    .ends_with("'/dev/zero' can't be read as this scene is parsed without files"),
+                "{}",
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_emissive() {
+        let scene = parse_with_camera(
+            "- add: quad\n  material:\n    emissive: [ 1, 0.5, 0 ]\n- add: sphere\n",
+        )
+        .unwrap();
+        let objects = scene.world().objects();
+        assert_eq!(C![1., 0.5, 0.], objects[0].material().emissive());
+        assert_eq!(Color::BLACK, objects[1].material().emissive());
+    }
+
+    #[test]
+    fn test_bump_map() {
+        let scene = parse_with_camera(
+            "- add: sphere\n  material:\n    bump: { type: marble, colors: [ [ 0, 0, 0 ], [ 1, 1, 1 ] ] }\n    bump-strength: 0.2\n",
+        )
+        .unwrap();
+        let material = scene.world().objects()[0].material();
+        assert!(material.bump_map().is_some());
+        assert!(approx_eq(0.2, material.bump_strength()));
+        assert!(material.pattern().is_none());
+    }
+
+    #[test]
+    fn test_pixel_aspect() {
+        let scene = parse(&format!("{}  pixel-aspect: 2\n", CAMERA)).unwrap();
+        let camera = scene.camera(0).unwrap();
+        assert!(approx_eq(2., camera.pixel_aspect()));
+        assert!(approx_eq(2. * camera.pixel_height(), camera.pixel_width()));
+    }
+
+    #[test]
+    fn test_depth_of_field() {
+        let scene = parse(&format!("{}  aperture: 0.2\n  focal-distance: 5\n", CAMERA)).unwrap();
+        let camera = scene.camera(0).unwrap();
+        assert_eq!(0.2, camera.aperture());
+        assert_eq!(5., camera.focal_distance());
+
+        let err = parse(&format!("{}  aperture: -1\n", CAMERA)).unwrap_err();
+        assert_eq!(
+            "line 9, column 13: aperture can't be negative",
+            err.to_string()
+        );
+        let err = parse(&format!("{}  focal-distance: 0\n", CAMERA)).unwrap_err();
+        assert_eq!(
+            "line 9, column 19: focal-distance must be greater than 0",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_camera_too_wide() {
+        // tall images are streamed to their file, but even one row of this is too many pixels.
+        let err = parse(&CAMERA.replace("width: 20", "width: 100000001")).unwrap_err();
+        assert_eq!(
+            "line 3, column 10: width can't be more than 100000000 pixels",
+            err.to_string()
+        );
+        assert!(parse(&CAMERA.replace("height: 10", "height: 100000001")).is_ok());
+    }
+
+    #[test]
+    fn test_projection() {
+        let scene = parse(&format!("{}  projection: fisheye\n", CAMERA)).unwrap();
+        assert_eq!(Projection::Fisheye, scene.camera(0).unwrap().projection());
+        let scene = parse(CAMERA).unwrap();
+        assert_eq!(
+            Projection::Perspective,
+            scene.camera(0).unwrap().projection()
+        );
+
+        let err = parse(&format!("{}  projection: fishey\n", CAMERA)).unwrap_err();
+        assert_eq!(
+            "line 9, column 15: 'fishey' is not a projection, did you mean 'fisheye'?",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_bokeh() {
+        let scene = parse(&format!(
+            "{}  bokeh:\n    sides: 6\n    rotation: 0.5\n",
+            CAMERA
+        ))
+        .unwrap();
+        assert_eq!(
+            &Bokeh::Polygon {
+                sides: 6,
+                rotation: 0.5
+            },
+            scene.camera(0).unwrap().bokeh()
+        );
+
+        let dir = std::env::temp_dir().join(format!("scene_bokeh_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("star.ppm"), "P3\n2 1\n255\n255 255 255 0 0 0\n").unwrap();
+        fs::write(dir.join("dark.ppm"), "P3\n1 1\n255\n0 0 0\n").unwrap();
+        let (scene, files) = read(
+            &format!("{}  bokeh:\n    mask: star.ppm\n", CAMERA),
+            Some(&dir),
+        )
+        .unwrap();
+        let dark = read(
+            &format!("{}  bokeh:\n    mask: dark.ppm\n", CAMERA),
+            Some(&dir),
+        );
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(scene.camera(0).unwrap().bokeh(), Bokeh::Mask(_)));
+        assert_eq!(vec![dir.join("star.ppm")], files);
+        assert_eq!(
+            "line 10, column 11: a bokeh mask needs at least one pixel brighter than half white",
+            dark.unwrap_err().to_string()
+        );
+
+        let err = parse(&format!("{}  bokeh:\n    sides: 2\n", CAMERA)).unwrap_err();
+        assert_eq!(
+            "line 10, column 12: the sides of a bokeh should be a whole number of at least 3",
+            err.to_string()
+        );
+        let err = parse(&format!("{}  bokeh:\n    rotation: 1\n", CAMERA)).unwrap_err();
+        assert_eq!(
+            "line 10, column 5: a bokeh should have either sides or a mask",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_define_extend_material() {
+        let scene = parse_with_camera(
+            "
+- define: white-material
+  value:
+    color: [ 1, 1, 1 ]
+    diffuse: 0.7
+    reflective: 0.1
+    reflection-blur: 0.05
+    casts-shadow: false
+
+- define: blue-material
+  extend: white-material
+  value:
+    color: [ 0.5, 0.8, 0.9 ]
+
+- add: sphere
+  material: blue-material
+
+- add: plane
+  material: blue-material
+",
+        )
+        .unwrap();
+
+        let objects = scene.world().objects();
+        let material = objects[0].material();
+        assert_eq!(C![0.5, 0.8, 0.9], material.color());
+        assert!(approx_eq(0.7, material.diffuse()));
+        assert!(approx_eq(0.1, material.reflective()));
+        assert!(approx_eq(0.05, material.reflection_blur()));
+        assert!(!material.casts_shadow());
+        // shapes using the same defined material share it.
+        assert!(Arc::ptr_eq(
+            objects[0].shared_material(),
+            objects[1].shared_material()
+        ));
+    }
+
+    #[test]
+    fn test_define_extend_transform() {
+        let scene = parse_with_camera(
+            "
+- define: standard-transform
+  value:
+    - [ translate, 1, -1, 1 ]
+    - [ scale, 0.5, 0.5, 0.5 ]
+
+- define: large-object
+  extend: standard-transform
+  value:
+    - [ scale, 4, 4, 4 ]
+
+- add: sphere
+  transform: large-object
+",
+        )
+        .unwrap();
+
+        let expected = scaling(4., 4., 4.) * scaling(0.5, 0.5, 0.5) * translation(1., -1., 1.);
+        assert_eq!(&expected, scene.world().objects()[0].transformation());
+    }
+
+    #[test]
+    fn test_transform_stacks() {
+        let scene = parse_with_camera(
+            "
+- add: sphere
+  transform: [ large-object, [ scale 0.5 ], [ translate 1 0 0 ] ]
+
+- define: large-object
+  value:
+    - standard-transform
+    - [ scale, 3.5, 3.5, 3.5 ]
+
+- define: standard-transform
+  value:
+    - [ translate, 1, -1, 1 ]
+    - [ [ scale, 0.5, 0.5, 0.5 ] ]
+",
+        )
+        .unwrap();
+
+        let expected = Transform::new()
+            .translate(1., -1., 1.)
+            .scale(0.5, 0.5, 0.5)
+            .scale(3.5, 3.5, 3.5)
+            .scale(0.5, 0.5, 0.5)
+            .translate(1., 0., 0.)
+            .build();
+        assert_eq!(&expected, scene.world().objects()[0].transformation());
+    }
+
+    #[test]
+    fn test_definitions_can_be_used_before_they_are_written() {
+        let scene = parse_with_camera(
+            "
+- add: sphere
+  material: blue
+- define: blue
+  extend: base
+  value: { color: [ 0, 0, 1 ] }
+- define: base
+  value: { ambient: 0.5 }
+",
+        )
+        .unwrap();
+        let material = scene.world().objects()[0].material();
+        assert_eq!(C![0., 0., 1.], material.color());
+        assert!(approx_eq(0.5, material.ambient()));
+    }
+
+    #[test]
+    fn test_cycles() {
+        let err = parse_with_camera(
+            "
+- define: a
+  value: [ b, [ scale, 2 ] ]
+- define: b
+  value: [ [ translate, 1, 0, 0 ], c ]
+- define: c
+  value: [ a ]
+",
+        )
+        .unwrap_err();
+        assert_eq!(
+            "line 15, column 12: 'a' refers to itself: a -> b -> c -> a",
+            err.to_string()
+        );
+
+        let err =
+            parse_with_camera("- define: m\n  extend: m\n  value: { diffuse: 1 }\n").unwrap_err();
+        assert_eq!(10, line_of(err));
+    }
+
+    #[test]
+    fn test_group() {
+        let scene = parse_with_camera(
+            "
+- add: group
+  transform:
+    - [ translate, 0, 2, 0 ]
+  children:
+    - add: sphere
+    - add: disc
+      inner-radius: 0.5
+",
+        )
+        .unwrap();
+
+        let group = scene.world().objects()[0]
+            .as_any()
+            .downcast_ref::<Group>()
+            .unwrap();
+        assert_eq!(2, group.children().len());
+        assert_eq!(
+            &translation(0., 2., 0.),
+            group.children()[0].transformation()
+        );
+    }
+
+    #[test]
+    fn test_named_shapes() {
+        let scene = parse_with_camera("- add: sphere\n  name: ball\n\n- add: plane\n").unwrap();
+        let objects = scene.world().objects();
+        assert_eq!(Some("ball"), objects[0].name());
+        assert_eq!(None, objects[1].name());
+    }
+
+    #[test]
+    fn test_truncated_shapes() {
+        let scene = parse_with_camera(
+            "
+- add: cylinder
+  minimum: 0
+  maximum: 2
+  closed: true
+
+- add: cone
+  maximum: 0
+",
+        )
+        .unwrap();
+
+        let objects = scene.world().objects();
+        let cylinder = objects[0].as_any().downcast_ref::<Cylinder>().unwrap();
+        assert_eq!(
+            (0., 2., true),
+            (cylinder.minimum(), cylinder.maximum(), cylinder.closed())
+        );
+        let cone = objects[1].as_any().downcast_ref::<Cone>().unwrap();
+        assert_eq!(f64::NEG_INFINITY, cone.minimum());
+        assert!(!cone.closed());
+
+        let err = parse_with_camera("- add: cylinder\n  minimum: 2\n  maximum: 1\n").unwrap_err();
+        assert_eq!(11, line_of(err));
+        let err = parse_with_camera("- add: cone\n  closed: yes\n").unwrap_err();
+        assert_eq!(10, line_of(err));
+    }
+
+    #[test]
+    fn test_obj_found_next_to_scene() {
+        let dir = std::env::temp_dir().join(format!("scene_obj_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("triangle.obj"),
+            "v 0 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 3",
+        )
+        .unwrap();
+        let input = format!(
+            "{}- add: obj\n  file: triangle.obj\n  transform:\n    - [ translate, 0, 1, 0 ]\n",
+            CAMERA
+        );
+        fs::write(dir.join("scene.yml"), &input).unwrap();
+
+        let scene = load(dir.join("scene.yml"));
+        let (_, files) = read(&input, Some(&dir)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let scene = scene.unwrap();
+        let model = &scene.world().objects()[0];
+        assert_eq!(&translation(0., 1., 0.), model.transformation());
+        assert_eq!(vec![dir.join("triangle.obj")], files);
+
+        // parsing a string looks in the working directory, where there is no triangle.obj.
+        let err = parse(&input).unwrap_err();
+        assert_eq!(
+            "line 10, column 9: could not load 'triangle.obj': could not read model: No such file or directory (os error 2)",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_environment() {
+        let dir = std::env::temp_dir().join(format!("scene_environment_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("sky.ppm"), "P3\n1 1\n255\n255 0 0\n").unwrap();
+        let input = format!(
+            "{}- add: environment\n  file: sky.ppm\n  intensity: 2\n  yaw: 1.5\n  samples: 4\n",
+            CAMERA
+        );
+        let (scene, files) = read(&input, Some(&dir)).unwrap();
+        let twice = read(
+            &format!("{}- add: environment\n  file: sky.ppm\n", input),
+            Some(&dir),
+        );
+        let fractional = read(&input.replace("samples: 4", "samples: 2.5"), Some(&dir));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let environment = scene.world().environment().unwrap();
+        assert_eq!(2., environment.intensity());
+        assert_eq!(1.5, environment.yaw());
+        assert_eq!(4, environment.samples());
+        assert_eq!(C![2., 0., 0.], environment.color_in(V![0., 0., 1.]));
+        assert_eq!(vec![dir.join("sky.ppm")], files);
+
+        assert_eq!(
+            "line 14, column 8: a scene can only have one environment",
+            twice.unwrap_err().to_string()
+        );
+        assert_eq!(
+            "line 13, column 12: the samples of an environment should be a whole number",
+            fractional.unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn test_reference_errors() {
+        let err = parse_with_camera("- add: sphere\n  material: missing\n").unwrap_err();
+        assert_eq!(
+            "line 10, column 13: 'missing' has not been defined",
+            err.to_string()
+        );
+
+        let err = parse_with_camera(
+            "- define: a\n  value: { diffuse: 1 }\n- define: a\n  value: { ambient: 1 }\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            "line 11, column 11: 'a' is already defined on line 9",
+            err.to_string()
+        );
+
+        // the wrong kind of definition.
+        let err = parse_with_camera(
+            "- define: t\n  value:\n    - [ scale, 2, 2, 2 ]\n- add: sphere\n  material: t\n",
+        )
+        .unwrap_err();
+        assert_eq!(13, line_of(err));
+
+        let err = parse_with_camera(
+            "- define: m\n  value: { diffuse: 1 }\n- define: t\n  extend: m\n  value:\n    - [ scale, 2, 2, 2 ]\n",
+        )
+        .unwrap_err();
+        assert_eq!(14, line_of(err));
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let err = parse_with_camera(
+            "
+- add: sphere
+  material:
+    color: [ 1, 0, 0 ]
+    difuse: 0.5
+",
+        )
+        .unwrap_err();
+        assert_eq!(
+            SceneError::Parse {
+                line: 13,
+                column: 5,
+                key: Some("difuse".to_string()),
+                message: "unknown key 'difuse', expected one of color, ambient, diffuse, specular, shininess, reflective, reflection-blur, transparency, refractive-index, casts-shadow, pattern, bump, bump-strength, emissive".to_string(),
+                suggestion: Some("diffuse".to_string()),
+            },
+            err
+        );
+
+        let suggestion = |input: &str| match parse_with_camera(input).unwrap_err() {
+            SceneError::Parse { suggestion, .. } => suggestion,
+            other => panic!("expected a parse error but got {:?}", other),
+        };
+        assert_eq!(Some("sphere".to_string()), suggestion("- add: spehre\n"));
+        assert_eq!(
+            Some("stripes".to_string()),
+            suggestion("- add: plane\n  material:\n    pattern: { type: stripe, colors: [ [1, 1, 1], [0, 0, 0] ] }\n")
+        );
+        assert_eq!(
+            Some("translate".to_string()),
+            suggestion("- add: plane\n  transform: [ [ translat, 1, 2, 3 ] ]\n")
+        );
+        assert_eq!(
+            Some("white-material".to_string()),
+            suggestion("- define: white-material\n  value: { ambient: 1 }\n- add: plane\n  material: white-materal\n")
+        );
+        assert_eq!(None, suggestion("- add: cube\n"));
+    }
+
+    #[test]
+    fn test_missing_and_mistyped_values_name_their_key() {
+        let key = |input: &str| match parse_with_camera(input).unwrap_err() {
+            SceneError::Parse { key, .. } => key,
+            other => panic!("expected a parse error but got {:?}", other),
+        };
+        assert_eq!(
+            Some("p3".to_string()),
+            key("- add: triangle\n  p1: [ 0, 0, 0 ]\n  p2: [ 1, 0, 0 ]\n")
+        );
+        assert_eq!(
+            Some("diffuse".to_string()),
+            key("- add: plane\n  material: { diffuse: lots }\n")
+        );
+    }
+
+    #[test]
+    fn test_value_errors() {
+        // mistakes inside a definition are reported where the definition is.
+        let err = parse_with_camera("- define: m\n  value:\n    shiny: 1\n").unwrap_err();
+        assert_eq!(11, line_of(err));
+
+        let err =
+            parse_with_camera("- add: sphere\n  transform:\n    - [ scale, 1, 2 ]\n").unwrap_err();
+        assert_eq!(11, line_of(err));
+
+        let err = parse_with_camera("- add: sphere\n  transform:\n    - [ scale, 0, 1, 1 ]\n")
+            .unwrap_err();
+        assert_eq!(11, line_of(err));
+
+        let err = parse_with_camera("- add: cube\n").unwrap_err();
+        assert_eq!(9, line_of(err));
+
+        let err = parse(&format!("{}  pixel-aspect: 0\n", CAMERA)).unwrap_err();
+        assert_eq!(
+            "line 9, column 17: pixel-aspect must be greater than 0",
+            err.to_string()
+        );
+
+        assert_eq!(
+            SceneError::Format("the scene has no camera".to_string()),
+            parse("- add: sphere").unwrap_err()
+        );
+    }
+}
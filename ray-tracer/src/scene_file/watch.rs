@@ -0,0 +1,371 @@
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use crate::{
+    shapes::{group::Group, make_mut, BoxedShape},
+    world::scene::Scene,
+};
+
+use super::{
+    archive, directory_of, items, light, read, string,
+    yaml::{self, Node, Value},
+    Entries, Loader, SceneError,
+};
+
+/// Reload is what a watched scene did when its file was read again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reload {
+    /// Unchanged means nothing the scene is built from has changed.
+    Unchanged,
+    /// Look means only materials or lights changed, which were applied to the shapes already built.
+    Look,
+    /// Full means the scene was built again from the start.
+    Full,
+}
+
+/// WatchedScene is a scene file kept loaded so that it can be brought up to date whenever the file is saved,
+/// such as by a preview that renders again after every change.
+///
+/// When an edit only changes materials, material definitions or lights, the new materials are put onto the shapes
+/// already built and the world keeps its accelerator, so trying out a look on a scene with large models doesn't
+/// wait for the models to be read and the accelerator to be built again. Any other edit loads the scene in full.
+pub struct WatchedScene {
+    path: PathBuf,
+    /// text is the scene file the scene was last brought up to date with, which is empty for an archive.
+    text: String,
+    scene: Scene,
+    /// files are the other files the scene was built from, such as models.
+    files: Vec<PathBuf>,
+    /// stamps are when the scene file and each of the files were last modified as of the last reload.
+    stamps: Vec<Option<SystemTime>>,
+}
+
+impl WatchedScene {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        let path = path.as_ref().to_path_buf();
+        let (text, scene, files) = if archive::is_archive(&path) {
+            (String::new(), archive::load(&path)?, vec![])
+        } else {
+            let text = fs::read_to_string(&path)?;
+            let (scene, files) = read(&text, Some(directory_of(&path)))?;
+            (text, scene, files)
+        };
+        let mut watched = Self {
+            path,
+            text,
+            scene,
+            files,
+            stamps: vec![],
+        };
+        watched.stamps = watched.stamp();
+        Ok(watched)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    /// changed is whether the scene file, or a file it uses, has been modified since the last reload.
+    pub fn changed(&self) -> bool {
+        self.stamp() != self.stamps
+    }
+
+    /// reload reads the scene file again and brings the scene up to date with it, doing as little work as the edit allows.
+    /// If the file now has a mistake the error is returned and the scene is left as it was.
+    pub fn reload(&mut self) -> Result<Reload, SceneError> {
+        let stamps = self.stamp();
+        let files_changed = stamps[1..] != self.stamps[1..];
+        // remember the stamps even if the file has a mistake, so it's only reported once per save.
+        self.stamps = stamps;
+
+        if archive::is_archive(&self.path) {
+            self.scene = archive::load(&self.path)?;
+            return Ok(Reload::Full);
+        }
+        let text = fs::read_to_string(&self.path)?;
+        if text == self.text && !files_changed {
+            return Ok(Reload::Unchanged);
+        }
+        let dir = directory_of(&self.path);
+        let reload = if !files_changed && restyle(&mut self.scene, &self.text, &text, dir)? {
+            Reload::Look
+        } else {
+            let (scene, files) = read(&text, Some(dir))?;
+            self.scene = scene;
+            self.files = files;
+            self.stamps = self.stamp();
+            Reload::Full
+        };
+        self.text = text;
+        Ok(reload)
+    }
+
+    fn stamp(&self) -> Vec<Option<SystemTime>> {
+        std::iter::once(&self.path)
+            .chain(&self.files)
+            .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+}
+
+/// restyle gives the scene, built from the old scene file, the materials and lights of the new one.
+/// It returns false without changing the scene if the files differ in anything else.
+fn restyle(scene: &mut Scene, old: &str, new: &str, dir: &Path) -> Result<bool, SceneError> {
+    let (old, new) = (yaml::parse(old)?, yaml::parse(new)?);
+    if layout(&old) != layout(&new) {
+        return Ok(false);
+    }
+
+    let items = items(&new)?;
+    let mut loader = Loader {
+        dir: Some(dir.to_path_buf()),
+        ..Loader::default()
+    };
+    for item in items {
+        loader.collect_definition(item)?;
+    }
+    loader.resolve_definitions()?;
+
+    let objects = scene.world().objects();
+    let (mut restyled, mut lights) = (vec![], vec![]);
+    for item in items {
+        let entries = Entries::new(item, "an item")?;
+        match entries.get("add").map(string).transpose()? {
+            Some("light") => lights.push(light(&entries)?),
+            Some("camera") | None => {}
+            Some(_) => restyled.push(loader.restyled(item, &objects[restyled.len()])?),
+        }
+    }
+
+    let world = scene.world_mut();
+    world.restyle(restyled);
+    world.set_lights(lights);
+    Ok(true)
+}
+
+impl Loader {
+    /// restyled is a copy of a shape built from the item, with the material the item now gives it and its children.
+    fn restyled(&self, item: &Node, shape: &BoxedShape) -> Result<BoxedShape, SceneError> {
+        let entries = Entries::new(item, "a shape")?;
+        let mut shape = shape.clone();
+        if let Some(material) = entries.get("material") {
+            make_mut(&mut shape).set_shared_material(self.material(material)?);
+        }
+        if let (Some(children), Some(group)) = (
+            entries.get("children").and_then(Node::as_sequence),
+            shape.as_any().downcast_ref::<Group>(),
+        ) {
+            let mut group = group.clone();
+            for (index, child) in children.iter().enumerate() {
+                let restyled = self.restyled(child, &group.children()[index])?;
+                group.replace_child(index, restyled);
+            }
+            shape = Arc::new(group);
+        }
+        Ok(shape)
+    }
+}
+
+/// layout writes out everything in a scene file except its lights, material definitions and the materials given to
+/// shapes, leaving out where anything was written. Files with the same layout build the same shapes and cameras.
+/// Whether a shape has a material is kept, as a shape without one is given the default material.
+fn layout(document: &Node) -> String {
+    let mut out = String::new();
+    match document.as_sequence() {
+        Some(items) => {
+            for item in items {
+                let entries = item.as_mapping().map(Vec::as_slice).unwrap_or_default();
+                let value = |name| entries.iter().find(|(key, _)| key.name == name);
+                let is_light = value("add").is_some_and(|(_, add)| add.as_str() == Some("light"));
+                let is_material = value("define").is_some()
+                    && value("value").is_some_and(|(_, value)| value.as_mapping().is_some());
+                if !is_light && !is_material {
+                    write_shape(item, &mut out);
+                    out.push('\n');
+                }
+            }
+        }
+        None => write_value(document, &mut out),
+    }
+    out
+}
+
+/// write_shape writes an item with its material, and the materials of its children, replaced by a placeholder.
+fn write_shape(item: &Node, out: &mut String) {
+    let Some(entries) = item.as_mapping() else {
+        return write_value(item, out);
+    };
+    out.push('{');
+    for (key, node) in entries {
+        let _ = write!(out, "{:?}: ", key.name);
+        match (key.name.as_str(), node.as_sequence()) {
+            ("material", _) => out.push('*'),
+            ("children", Some(children)) => {
+                out.push('[');
+                for child in children {
+                    write_shape(child, out);
+                    out.push(',');
+                }
+                out.push(']');
+            }
+            _ => write_value(node, out),
+        }
+        out.push(',');
+    }
+    out.push('}');
+}
+
+fn write_value(node: &Node, out: &mut String) {
+    match &node.value {
+        Value::Null => out.push('~'),
+        Value::Bool(b) => {
+            let _ = write!(out, "{}", b);
+        }
+        Value::Number(n) => {
+            let _ = write!(out, "{}", n);
+        }
+        Value::String(s) => {
+            let _ = write!(out, "{:?}", s);
+        }
+        Value::Sequence(items) => {
+            out.push('[');
+            for item in items {
+                write_value(item, out);
+                out.push(',');
+            }
+            out.push(']');
+        }
+        Value::Mapping(entries) => {
+            out.push('{');
+            for (key, node) in entries {
+                let _ = write!(out, "{:?}: ", key.name);
+                write_value(node, out);
+                out.push(',');
+            }
+            out.push('}');
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_watch {
+    use std::{env, process};
+
+    use crate::{
+        primatives::{color::Color, tuple::Tuple},
+        shapes::group::Group,
+        C,
+    };
+
+    use super::*;
+
+    const SCENE: &str = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 1
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+- define: red
+  value:
+    color: [1, 0, 0]
+- add: sphere
+  material: red
+- add: group
+  transform:
+    - [translate, 2, 0, 0]
+  children:
+    - add: sphere
+      material:
+        color: [0, 1, 0]
+";
+
+    fn scene_file(name: &str, text: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("watch_{}_{}.yml", name, process::id()));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_layout_ignores_looks() {
+        let layout_of = |text: &str| layout(&yaml::parse(text).unwrap());
+        let restyled = SCENE
+            .replace("color: [1, 0, 0]", "color: [0, 0, 1]\n    reflective: 0.5")
+            .replace("at: [-10, 10, -10]", "at: [5, 5, 5]")
+            .replace("material: red", "material:\n    color: [1, 1, 0]");
+        assert_eq!(layout_of(SCENE), layout_of(&restyled));
+
+        let moved = SCENE.replace("[translate, 2, 0, 0]", "[translate, 3, 0, 0]");
+        assert_ne!(layout_of(SCENE), layout_of(&moved));
+        let unstyled = SCENE.replace("- add: sphere\n  material: red", "- add: sphere");
+        assert_ne!(layout_of(SCENE), layout_of(&unstyled));
+    }
+
+    #[test]
+    fn test_reload_materials_and_lights() {
+        let path = scene_file("look", SCENE);
+        let mut watched = WatchedScene::load(&path).unwrap();
+        assert_eq!(Reload::Unchanged, watched.reload().unwrap());
+
+        let edited = SCENE
+            .replace("color: [1, 0, 0]", "color: [0, 0, 1]")
+            .replace("color: [0, 1, 0]", "color: [1, 1, 1]")
+            .replace("at: [-10, 10, -10]", "at: [5, 5, 5]");
+        fs::write(&path, &edited).unwrap();
+        assert_eq!(Reload::Look, watched.reload().unwrap());
+
+        let world = watched.scene().world();
+        assert_eq!(C![0., 0., 1.], world.objects()[0].material().color());
+        let group = world.objects()[1].as_any().downcast_ref::<Group>().unwrap();
+        assert_eq!(C![1., 1., 1.], group.children()[0].material().color());
+        assert_eq!(C![1., 1., 1.], world.primitives()[1].material().color());
+        let lights: Vec<_> = world.lights().collect();
+        assert_eq!(1, lights.len());
+        assert_eq!(
+            Some(crate::primatives::point::Point::new(5., 5., 5.)),
+            lights[0].position()
+        );
+        assert_eq!(
+            watched.scene().world().primitives()[1].transformation(),
+            WatchedScene::load(&path)
+                .unwrap()
+                .scene()
+                .world()
+                .primitives()[1]
+                .transformation()
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_reload_full() {
+        let path = scene_file("full", SCENE);
+        let mut watched = WatchedScene::load(&path).unwrap();
+
+        fs::write(&path, format!("{}- add: plane\n", SCENE)).unwrap();
+        assert_eq!(Reload::Full, watched.reload().unwrap());
+        assert_eq!(3, watched.scene().world().objects().len());
+
+        fs::write(
+            &path,
+            SCENE.replace("color: [1, 0, 0]", "colour: [1, 0, 0]"),
+        )
+        .unwrap();
+        assert!(watched.reload().is_err());
+        assert_eq!(3, watched.scene().world().objects().len());
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,626 @@
+use std::fmt::Display;
+
+/// Node is a value read from a YAML document, along with the line and column it starts on so errors can point back into the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub value: Value,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Value is the subset of YAML that scene files use: block and flow lists and mappings holding plain or quoted scalars.
+/// Anchors, tags, multi-line strings and multiple documents are not supported.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Sequence(Vec<Node>),
+    Mapping(Vec<(Key, Node)>),
+}
+
+/// Key is the name of a mapping entry and where it was written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Key {
+    pub name: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// YamlError is text that could not be read as YAML, with the line and column it was found on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct YamlError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Display for YamlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Node {
+    fn new(value: Value, line: usize, column: usize) -> Self {
+        Self {
+            value,
+            line,
+            column,
+        }
+    }
+
+    /// kind describes the type of the value for error messages.
+    pub fn kind(&self) -> &'static str {
+        match self.value {
+            Value::Null => "nothing",
+            Value::Bool(_) => "a boolean",
+            Value::Number(_) => "a number",
+            Value::String(_) => "a string",
+            Value::Sequence(_) => "a list",
+            Value::Mapping(_) => "a mapping",
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.value {
+            Value::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self.value {
+            Value::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.value {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_sequence(&self) -> Option<&Vec<Node>> {
+        match &self.value {
+            Value::Sequence(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_mapping(&self) -> Option<&Vec<(Key, Node)>> {
+        match &self.value {
+            Value::Mapping(entries) => Some(entries),
+            _ => None,
+        }
+    }
+}
+
+/// parse reads a YAML document.
+pub fn parse(input: &str) -> Result<Node, YamlError> {
+    let mut lines = vec![];
+    for (i, raw) in input.lines().enumerate() {
+        let number = i + 1;
+        let text = strip_comment(raw).trim_end();
+        let content = text.trim_start_matches(' ');
+        if content.is_empty() || content == "---" {
+            continue;
+        }
+        if content.starts_with('\t') {
+            return Err(YamlError {
+                line: number,
+                column: text.len() - content.len() + 1,
+                message: "tabs can't be used for indentation".to_string(),
+            });
+        }
+        lines.push(Line {
+            number,
+            indent: text.len() - content.len(),
+            text: content.to_string(),
+        });
+    }
+
+    let Some(first) = lines.first() else {
+        return Ok(Node::new(Value::Null, 1, 1));
+    };
+    let indent = first.indent;
+    let mut parser = Parser { lines, pos: 0 };
+    let node = parser.block(indent)?;
+    match parser.lines.get(parser.pos) {
+        Some(line) => Err(line.error(0, "unexpected indentation")),
+        None => Ok(node),
+    }
+}
+
+/// Line is a line of the document with its comment and indentation removed.
+#[derive(Debug, Clone)]
+struct Line {
+    number: usize,
+    indent: usize,
+    text: String,
+}
+
+impl Line {
+    fn error(&self, offset: usize, message: impl Into<String>) -> YamlError {
+        YamlError {
+            line: self.number,
+            column: self.indent + offset + 1,
+            message: message.into(),
+        }
+    }
+}
+
+struct Parser {
+    lines: Vec<Line>,
+    pos: usize,
+}
+
+impl Parser {
+    /// block reads the value starting at the current line, which is indented by `indent`.
+    fn block(&mut self, indent: usize) -> Result<Node, YamlError> {
+        let line = &self.lines[self.pos];
+        if is_sequence_item(&line.text) {
+            self.sequence(indent)
+        } else if split_key(&line.text).is_some() {
+            self.mapping(indent)
+        } else {
+            let line = line.clone();
+            self.pos += 1;
+            self.check_no_deeper(indent)?;
+            inline(&line.text, line.number, line.indent + 1)
+        }
+    }
+
+    fn sequence(&mut self, indent: usize) -> Result<Node, YamlError> {
+        let start = self.lines[self.pos].clone();
+        let mut items = vec![];
+
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent != indent || !is_sequence_item(&line.text) {
+                break;
+            }
+            let line = line.clone();
+            let rest = line.text[1..].trim_start();
+            let offset = line.text.len() - rest.len();
+
+            if rest.is_empty() {
+                self.pos += 1;
+                match self.lines.get(self.pos) {
+                    Some(next) if next.indent > indent => {
+                        let deeper = next.indent;
+                        items.push(self.block(deeper)?);
+                    }
+                    _ => items.push(Node::new(Value::Null, line.number, indent + 1)),
+                }
+            } else if is_sequence_item(rest) || split_key(rest).is_some() {
+                // a block value that starts on the same line as its dash is read as if it were on its own line,
+                // indented to where it starts, which is how the entries that follow it line up.
+                self.lines[self.pos] = Line {
+                    number: line.number,
+                    indent: indent + offset,
+                    text: rest.to_string(),
+                };
+                items.push(self.block(indent + offset)?);
+            } else {
+                self.pos += 1;
+                self.check_no_deeper(indent)?;
+                items.push(inline(rest, line.number, indent + offset + 1)?);
+            }
+        }
+
+        Ok(Node::new(
+            Value::Sequence(items),
+            start.number,
+            start.indent + 1,
+        ))
+    }
+
+    fn mapping(&mut self, indent: usize) -> Result<Node, YamlError> {
+        let start = self.lines[self.pos].clone();
+        let mut entries: Vec<(Key, Node)> = vec![];
+
+        while let Some(line) = self.lines.get(self.pos) {
+            if line.indent != indent {
+                break;
+            }
+            let line = line.clone();
+            let Some((name, value_offset)) = split_key(&line.text) else {
+                return Err(line.error(0, "expected 'key: value'"));
+            };
+            if entries.iter().any(|(key, _)| key.name == name) {
+                return Err(line.error(0, format!("'{}' appears more than once", name)));
+            }
+            let key = Key {
+                name,
+                line: line.number,
+                column: indent + 1,
+            };
+            let rest = &line.text[value_offset..];
+
+            let value = if rest.is_empty() {
+                self.pos += 1;
+                match self.lines.get(self.pos) {
+                    Some(next) if next.indent > indent => {
+                        let deeper = next.indent;
+                        self.block(deeper)?
+                    }
+                    // lists are allowed to line up with the key they belong to.
+                    Some(next) if next.indent == indent && is_sequence_item(&next.text) => {
+                        self.sequence(indent)?
+                    }
+                    _ => Node::new(Value::Null, line.number, indent + value_offset + 1),
+                }
+            } else {
+                self.pos += 1;
+                self.check_no_deeper(indent)?;
+                inline(rest, line.number, indent + value_offset + 1)?
+            };
+            entries.push((key, value));
+        }
+
+        Ok(Node::new(
+            Value::Mapping(entries),
+            start.number,
+            start.indent + 1,
+        ))
+    }
+
+    /// check_no_deeper makes sure a value that ended on its own line isn't followed by more indented lines.
+    fn check_no_deeper(&self, indent: usize) -> Result<(), YamlError> {
+        match self.lines.get(self.pos) {
+            Some(next) if next.indent > indent => Err(next.error(0, "unexpected indentation")),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_sequence_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// split_key finds a plain `key:` at the start of the text, returning the key and where its value starts.
+fn split_key(text: &str) -> Option<(String, usize)> {
+    if text.starts_with(['[', '{', '"', '\'']) {
+        return None;
+    }
+    let bytes = text.as_bytes();
+    let i = (0..bytes.len())
+        .find(|&i| bytes[i] == b':' && bytes.get(i + 1).is_none_or(|b| b.is_ascii_whitespace()))?;
+    let key = text[..i].trim();
+    if key.is_empty() {
+        return None;
+    }
+    let rest = &text[i + 1..];
+    Some((
+        key.to_string(),
+        i + 1 + rest.len() - rest.trim_start().len(),
+    ))
+}
+
+/// strip_comment removes everything after a `#` that starts a comment, leaving any inside quotes alone.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '#') if previous.is_whitespace() => return &line[..i],
+            _ => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+/// inline reads a value written on a single line, which may be a flow list or mapping such as `[1, 2, 3]`.
+fn inline(text: &str, line: usize, column: usize) -> Result<Node, YamlError> {
+    let mut flow = Flow {
+        chars: text.chars().collect(),
+        pos: 0,
+        line,
+        column,
+    };
+    let node = flow.value(false)?;
+    flow.skip_whitespace();
+    match flow.peek() {
+        Some(c) => Err(flow.error(format!("unexpected '{}' after the value", c))),
+        None => Ok(node),
+    }
+}
+
+struct Flow {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Flow {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, message: impl Into<String>) -> YamlError {
+        YamlError {
+            line: self.line,
+            column: self.column + self.pos,
+            message: message.into(),
+        }
+    }
+
+    fn node(&self, value: Value, start: usize) -> Node {
+        Node::new(value, self.line, self.column + start)
+    }
+
+    /// value reads the next value. Inside a flow collection plain scalars stop at the collection's punctuation.
+    fn value(&mut self, in_flow: bool) -> Result<Node, YamlError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        match self.peek() {
+            Some('[') => {
+                self.pos += 1;
+                let items = self.collection(']', |flow| flow.value(true))?;
+                Ok(self.node(Value::Sequence(items), start))
+            }
+            Some('{') => {
+                self.pos += 1;
+                let entries = self.collection('}', |flow| flow.entry())?;
+                Ok(self.node(Value::Mapping(entries), start))
+            }
+            Some(q @ ('"' | '\'')) => {
+                let s = self.quoted(q)?;
+                Ok(self.node(Value::String(s), start))
+            }
+            _ => {
+                let s = self.plain(if in_flow { ",]}" } else { "" });
+                if s.is_empty() {
+                    return Err(self.error("expected a value"));
+                }
+                Ok(self.node(scalar(&s), start))
+            }
+        }
+    }
+
+    fn collection<T>(
+        &mut self,
+        close: char,
+        mut item: impl FnMut(&mut Self) -> Result<T, YamlError>,
+    ) -> Result<Vec<T>, YamlError> {
+        let mut items = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(close) {
+                self.pos += 1;
+                return Ok(items);
+            }
+            items.push(item(self)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(c) if c == close => {}
+                Some(c) => {
+                    return Err(self.error(format!("expected ',' or '{}' but found '{}'", close, c)))
+                }
+                None => return Err(self.error(format!("missing '{}'", close))),
+            }
+        }
+    }
+
+    fn entry(&mut self) -> Result<(Key, Node), YamlError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let name = match self.peek() {
+            Some(q @ ('"' | '\'')) => self.quoted(q)?,
+            _ => self.plain(":,}"),
+        };
+        if name.is_empty() {
+            return Err(self.error("expected a key"));
+        }
+        let key = Key {
+            name,
+            line: self.line,
+            column: self.column + start,
+        };
+        self.skip_whitespace();
+        if self.peek() != Some(':') {
+            return Err(self.error(format!("expected ':' after '{}'", key.name)));
+        }
+        self.pos += 1;
+        Ok((key, self.value(true)?))
+    }
+
+    fn quoted(&mut self, quote: char) -> Result<String, YamlError> {
+        self.pos += 1;
+        let mut s = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                c if c == quote => return Ok(s),
+                '\\' if quote == '"' => {
+                    let escaped = self.peek().ok_or_else(|| self.error("unfinished escape"))?;
+                    self.pos += 1;
+                    s.push(match escaped {
+                        'n' => '\n',
+                        't' => '\t',
+                        c => c,
+                    });
+                }
+                c => s.push(c),
+            }
+        }
+        Err(self.error(format!("missing closing {}", quote)))
+    }
+
+    fn plain(&mut self, stop: &str) -> String {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| !stop.contains(c)) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .trim()
+            .to_string()
+    }
+}
+
+/// scalar works out what a plain, unquoted scalar holds.
+fn scalar(s: &str) -> Value {
+    match s {
+        "~" | "null" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        // only look for numbers when the text starts like one, so words such as "inf" stay strings,
+        // and keep anything that isn't finite, like "-nan" or "1e999", as a string too.
+        _ if s.starts_with(|c: char| c.is_ascii_digit() || "+-.".contains(c)) => s
+            .parse::<f64>()
+            .ok()
+            .filter(|n| n.is_finite())
+            .map_or_else(|| Value::String(s.to_string()), Value::Number),
+        _ => Value::String(s.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test_yaml {
+    use super::*;
+
+    fn number(n: f64) -> Value {
+        Value::Number(n)
+    }
+
+    fn values(node: &Node) -> Vec<Value> {
+        node.as_sequence()
+            .unwrap()
+            .iter()
+            .map(|n| n.value.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_scalars() {
+        assert_eq!(number(1.5), parse("1.5").unwrap().value);
+        assert_eq!(number(-2.), parse("-2").unwrap().value);
+        assert_eq!(Value::Bool(true), parse("true").unwrap().value);
+        assert_eq!(Value::Null, parse("~").unwrap().value);
+        assert_eq!(Value::Null, parse("# only a comment").unwrap().value);
+        assert_eq!(
+            Value::String("red-material".to_string()),
+            parse("red-material").unwrap().value
+        );
+        assert_eq!(
+            Value::String("a # b".to_string()),
+            parse("\"a # b\" # comment").unwrap().value
+        );
+        // numbers that aren't finite would slip past checks such as "must be greater than 0".
+        assert_eq!(
+            Value::String("-nan".to_string()),
+            parse("-nan").unwrap().value
+        );
+        assert_eq!(
+            Value::String("1e999".to_string()),
+            parse("1e999").unwrap().value
+        );
+    }
+
+    #[test]
+    fn test_flow_sequence() {
+        let node = parse("[ translate, 1, -0.5, [2, 3] ]").unwrap();
+        let items = node.as_sequence().unwrap();
+        assert_eq!(4, items.len());
+        assert_eq!(Some("translate"), items[0].as_str());
+        assert_eq!(Some(-0.5), items[2].as_f64());
+        assert_eq!(vec![number(2.), number(3.)], values(&items[3]));
+        // columns count from 1.
+        assert_eq!(3, items[0].column);
+    }
+
+    #[test]
+    fn test_flow_mapping() {
+        let node = parse("{ color: [1, 0, 0], diffuse: 0.5 }").unwrap();
+        let entries = node.as_mapping().unwrap();
+        assert_eq!("color", entries[0].0.name);
+        assert_eq!(Some(0.5), entries[1].1.as_f64());
+    }
+
+    #[test]
+    fn test_block_document() {
+        let input = "
+# a camera and a sphere
+- add: camera
+  width: 100
+  from: [ 0, 1.5, -5 ]
+
+- add: sphere
+  transform:
+    - [ scale, 0.5, 0.5, 0.5 ]
+    - [ translate, 0, 1, 0 ]
+  material:
+    color: [1, 0.2, 1]
+- plain item
+-
+  nested: true
+";
+        let node = parse(input).unwrap();
+        let items = node.as_sequence().unwrap();
+        assert_eq!(4, items.len());
+
+        let camera = items[0].as_mapping().unwrap();
+        assert_eq!(3, camera.len());
+        assert_eq!("width", camera[1].0.name);
+        assert_eq!(4, camera[1].0.line);
+        assert_eq!(3, camera[1].0.column);
+        assert_eq!(Some(100.), camera[1].1.as_f64());
+
+        let sphere = items[1].as_mapping().unwrap();
+        let transform = sphere[1].1.as_sequence().unwrap();
+        assert_eq!(2, transform.len());
+        assert_eq!(10, transform[1].line);
+        let material = sphere[2].1.as_mapping().unwrap();
+        assert_eq!("color", material[0].0.name);
+
+        assert_eq!(Some("plain item"), items[2].as_str());
+        assert!(items[3].as_mapping().is_some());
+    }
+
+    #[test]
+    fn test_list_in_line_with_key() {
+        let node = parse("transform:\n- [ scale, 2, 2, 2 ]\n- [ rotate-x, 1 ]\nafter: 1").unwrap();
+        let entries = node.as_mapping().unwrap();
+        assert_eq!(2, entries.len());
+        assert_eq!(2, entries[0].1.as_sequence().unwrap().len());
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = parse("a: 1\n   b: 2").unwrap_err();
+        assert_eq!((2, 4), (err.line, err.column));
+
+        let err = parse("a: 1\na: 2").unwrap_err();
+        assert_eq!(2, err.line);
+
+        let err = parse("- [1, 2").unwrap_err();
+        assert_eq!(1, err.line);
+        assert!(err.message.contains("missing ']'"));
+
+        let err = parse("x: [1, 2] 3").unwrap_err();
+        assert_eq!((1, 11), (err.line, err.column));
+
+        let err = parse("a:\n\t- 1").unwrap_err();
+        assert_eq!(2, err.line);
+    }
+}
@@ -0,0 +1,104 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use super::{
+    cylinder::Cylinder, group::Group, material::Material, sphere::Sphere, Shape, ShapeBase,
+};
+use crate::primatives::{
+    matrix::Matrix,
+    transformation::{rotation_y, rotation_z, scaling, translation},
+};
+
+/// hexagon_corner is a small sphere sitting on the z axis one unit from the origin.
+pub fn hexagon_corner() -> Sphere {
+    Sphere::new(
+        Some(translation(0., 0., -1.) * scaling(0.25, 0.25, 0.25)),
+        None,
+    )
+}
+
+/// hexagon_edge is a thin cylinder one unit long, running from the corner on the z axis to the next corner round.
+pub fn hexagon_edge() -> Cylinder {
+    let mut edge = Cylinder::new(
+        Some(
+            translation(0., 0., -1.)
+                * rotation_y(-PI / 6.)
+                * rotation_z(-PI / 2.)
+                * scaling(0.25, 1., 0.25),
+        ),
+        None,
+    );
+    edge.set_limits(0., 1.);
+    edge
+}
+
+/// hexagon_side is one corner and the edge leading from it, turned a sixth of the way round for each side.
+pub fn hexagon_side(side: usize, material: &Arc<Material>) -> Group {
+    let mut corner = hexagon_corner();
+    corner.set_shared_material(material.clone());
+    let mut edge = hexagon_edge();
+    edge.set_shared_material(material.clone());
+
+    let mut group = Group::new(Some(rotation_y(side as f64 * PI / 3.)), None);
+    group.add_child(corner.box_clone());
+    group.add_child(edge.box_clone());
+    group
+}
+
+/// hexagon is the ring of six corners and edges from the chapter on groups, lying flat around the origin with
+/// corners one unit out. Every part shares the one material.
+pub fn hexagon(transform: Option<Matrix>, material: Option<Material>) -> Group {
+    let material = Arc::new(material.unwrap_or_default());
+    let mut hexagon = Group::new(transform, None);
+    for side in 0..6 {
+        hexagon.add_child(hexagon_side(side, &material).box_clone());
+    }
+    hexagon.set_shared_material(material);
+    hexagon
+}
+
+#[cfg(test)]
+mod test_compound {
+    use crate::{
+        comparison::approx_eq,
+        primatives::{ray::Ray, tuple::Tuple},
+        P, V,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_hexagon() {
+        let h = hexagon(None, None);
+        assert_eq!(6, h.children().len());
+        assert!(h.children().iter().all(|side| side
+            .as_any()
+            .downcast_ref::<Group>()
+            .unwrap()
+            .children()
+            .len()
+            == 2));
+
+        // the first corner is on the z axis nearest the ray.
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+        let mut xs = h.intersect(r);
+        assert!(approx_eq(3.75, xs.hit().unwrap().t()));
+
+        // the hexagon lies flat, no thicker than its corners.
+        let bounds = h.world_bounds();
+        assert!(approx_eq(-0.25, bounds.min().y()) && approx_eq(0.25, bounds.max().y()));
+    }
+
+    #[test]
+    fn test_transformed_hexagon() {
+        let m = Material::builder().reflective(0.5).build().unwrap();
+        let h = hexagon(Some(translation(0., 2., 0.)), Some(m.clone()));
+
+        // the group's transform is passed down to every corner and edge.
+        let r = Ray::new(P![0., 2., -5.], V![0., 0., 1.]);
+        let hit = h.intersect(r).hit().unwrap().clone();
+        assert!(approx_eq(3.75, hit.t()));
+        assert_eq!(&m, hit.object().material());
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+        assert!(h.intersect(r).hit().is_none());
+    }
+}
@@ -0,0 +1,241 @@
+use super::{material::Material, Shape, ShapeBase, ShapeProps};
+use crate::{
+    comparison::EPSILON,
+    primatives::aabb::Aabb,
+    primatives::matrix::Matrix,
+    primatives::point::Point,
+    primatives::ray::Ray,
+    primatives::solvers,
+    primatives::tuple::Tuple,
+    primatives::vector::Vector,
+    world::intersection::{Intersection, Intersections},
+    P,
+};
+use shape_derive::ShapeDerive;
+
+/// a cone is a double-napped cone around the y axis. Its two halves meet at the origin
+/// and the radius at any point is equal to the distance along y from the origin.
+/// Like a cylinder it can be truncated between a minimum and maximum y and closed with caps at the ends.
+#[derive(Clone, Debug, PartialEq, ShapeDerive)]
+pub struct Cone {
+    minimum: f64,
+    maximum: f64,
+    closed: bool,
+    props: ShapeProps,
+}
+
+impl Cone {
+    pub fn new(transform: Option<Matrix>, material: Option<Material>) -> Self {
+        Self {
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+            props: ShapeProps::new(transform, material),
+        }
+    }
+
+    pub fn minimum(&self) -> f64 {
+        self.minimum
+    }
+
+    pub fn maximum(&self) -> f64 {
+        self.maximum
+    }
+
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    /// set_limits truncates the cone to the part between the two heights, which are not included.
+    pub fn set_limits(&mut self, minimum: f64, maximum: f64) {
+        assert!(
+            minimum < maximum,
+            "a cone's minimum must be below its maximum"
+        );
+        self.minimum = minimum;
+        self.maximum = maximum;
+    }
+
+    /// set_closed puts caps on the ends of a truncated cone, making it solid.
+    pub fn set_closed(&mut self, closed: bool) {
+        self.closed = closed;
+    }
+
+    /// cap_hits are where the ray crosses the caps at each end, whose radius is the distance of the cap from the origin.
+    fn cap_hits(&self, r: Ray) -> Vec<f64> {
+        if !self.closed || r.direction().y().abs() < EPSILON {
+            return vec![];
+        }
+        [self.minimum, self.maximum]
+            .into_iter()
+            .filter(|y| y.is_finite())
+            .map(|y| (y - r.origin().y()) / r.direction().y())
+            .filter(|&t| {
+                let p = r.at(t);
+                p.x().powi(2) + p.z().powi(2) <= p.y().powi(2)
+            })
+            .collect()
+    }
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+impl Shape for Cone {
+    fn local_intersect(&self, r: Ray) -> Intersections {
+        let (o, d) = (r.origin(), r.direction());
+        let mut hits = self.cap_hits(r);
+
+        let a = d.x().powi(2) - d.y().powi(2) + d.z().powi(2);
+        let b = 2. * o.x() * d.x() - 2. * o.y() * d.y() + 2. * o.z() * d.z();
+        let c = o.x().powi(2) - o.y().powi(2) + o.z().powi(2);
+
+        let walls = if a.abs() < EPSILON {
+            // the ray is parallel to one of the cone's halves so it can only cross the other half once.
+            if b.abs() < EPSILON {
+                vec![]
+            } else {
+                vec![-c / (2. * b)]
+            }
+        } else {
+            solvers::quadratic(a, b, c)
+        };
+        hits.extend(walls.into_iter().filter(|&t| {
+            let y = r.at(t).y();
+            self.minimum < y && y < self.maximum
+        }));
+
+        hits.sort_by(f64::total_cmp);
+        Intersections::new(
+            hits.into_iter()
+                .map(|t| Intersection::new(t, self.box_clone()))
+                .collect(),
+        )
+    }
+
+    fn local_normal(&self, point: Point) -> Vector {
+        let distance = point.x().powi(2) + point.z().powi(2);
+        if distance < point.y().powi(2) && point.y() >= self.maximum - EPSILON {
+            return Vector::new(0., 1., 0.);
+        }
+        if distance < point.y().powi(2) && point.y() <= self.minimum + EPSILON {
+            return Vector::new(0., -1., 0.);
+        }
+        let y = distance.sqrt();
+        // the normal points down on the upper half and up on the lower half.
+        let y = if point.y() > 0. { -y } else { y };
+        Vector::new(point.x(), y, point.z())
+    }
+
+    fn bounds(&self) -> Aabb {
+        // the widest part of the cone is at whichever end is furthest from the origin.
+        let radius = self.minimum.abs().max(self.maximum.abs());
+        Aabb::new(
+            P![-radius, self.minimum, -radius],
+            P![radius, self.maximum, radius],
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_cone {
+    use crate::{comparison::approx_eq, P, V};
+
+    use super::*;
+
+    #[test]
+    fn test_ray_hits() {
+        let c = Cone::default();
+        let cases = [
+            (P![0., 0., -5.], V![0., 0., 1.], 5., 5.),
+            (P![0., 0., -5.], V![1., 1., 1.], 8.66025, 8.66025),
+            (P![1., 1., -5.], V![-0.5, -1., 1.], 4.55006, 49.44994),
+        ];
+
+        for (origin, direction, t0, t1) in cases {
+            let r = Ray::new(origin, direction.norm());
+            let xs = c.local_intersect(r);
+            assert_eq!(2, xs.len());
+            assert!(approx_eq(t0, xs[0].t()));
+            assert!(approx_eq(t1, xs[1].t()));
+        }
+    }
+
+    #[test]
+    fn test_ray_parallel_to_one_half() {
+        let c = Cone::default();
+        let r = Ray::new(P![0., 0., -1.], V![0., 1., 1.].norm());
+        let xs = c.local_intersect(r);
+
+        assert_eq!(1, xs.len());
+        assert!(approx_eq(0.35355, xs[0].t()));
+    }
+
+    #[test]
+    fn test_normal() {
+        let c = Cone::default();
+        let cases = [
+            (P![0., 0., 0.], V![0., 0., 0.]),
+            (P![1., 1., 1.], V![1., -(2.0_f64.sqrt()), 1.]),
+            (P![-1., -1., 0.], V![-1., 1., 0.]),
+        ];
+
+        for (point, normal) in cases {
+            assert_eq!(normal, c.local_normal(point));
+        }
+    }
+
+    #[test]
+    fn test_in_world() {
+        let c = Cone::default().box_clone();
+        let r = Ray::new(P![0., 0.5, -5.], V![0., 0., 1.]);
+        let xs = c.intersect(r);
+
+        assert_eq!(2, xs.len());
+        assert!(approx_eq(4.5, xs[0].t()));
+        assert!(approx_eq(5.5, xs[1].t()));
+    }
+
+    #[test]
+    fn test_closed() {
+        let mut c = Cone::default();
+        c.set_limits(-0.5, 0.5);
+        c.set_closed(true);
+
+        let cases = [
+            (P![0., 0., -5.], V![0., 1., 0.], 0),
+            (P![0., 0., -0.25], V![0., 1., 1.], 2),
+            (P![0., 0., -0.25], V![0., 1., 0.], 4),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.norm());
+            assert_eq!(count, c.local_intersect(r).len());
+        }
+
+        // an open cone is only hit on its sides.
+        c.set_closed(false);
+        let r = Ray::new(P![0., 0., -0.25], V![0., 1., 0.]);
+        assert_eq!(2, c.local_intersect(r).len());
+
+        assert_eq!(
+            Aabb::new(P![-0.5, -0.5, -0.5], P![0.5, 0.5, 0.5]),
+            c.bounds()
+        );
+    }
+
+    #[test]
+    fn test_cap_normals() {
+        let mut c = Cone::default();
+        c.set_limits(-1., 2.);
+        c.set_closed(true);
+        assert_eq!(V![0., 1., 0.], c.local_normal(P![0.5, 2., 0.]));
+        assert_eq!(V![0., -1., 0.], c.local_normal(P![0., -1., 0.5]));
+        assert_eq!(
+            V![1., -(2.0_f64.sqrt()), 1.],
+            c.local_normal(P![1., 1., 1.])
+        );
+    }
+}
@@ -0,0 +1,254 @@
+use super::{material::Material, Shape, ShapeBase, ShapeProps};
+use crate::{
+    comparison::EPSILON,
+    primatives::aabb::Aabb,
+    primatives::matrix::Matrix,
+    primatives::point::Point,
+    primatives::ray::Ray,
+    primatives::solvers,
+    primatives::tuple::Tuple,
+    primatives::vector::Vector,
+    world::intersection::{Intersection, Intersections},
+    P,
+};
+use shape_derive::ShapeDerive;
+
+/// a cylinder is a tube of radius 1 centred on the y axis. It goes on forever unless it has been truncated
+/// between a minimum and maximum y, and truncated cylinders are open tubes unless they are closed with end caps.
+#[derive(Clone, Debug, PartialEq, ShapeDerive)]
+pub struct Cylinder {
+    minimum: f64,
+    maximum: f64,
+    closed: bool,
+    props: ShapeProps,
+}
+
+impl Cylinder {
+    pub fn new(transform: Option<Matrix>, material: Option<Material>) -> Self {
+        Self {
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+            props: ShapeProps::new(transform, material),
+        }
+    }
+
+    pub fn minimum(&self) -> f64 {
+        self.minimum
+    }
+
+    pub fn maximum(&self) -> f64 {
+        self.maximum
+    }
+
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    /// set_limits truncates the cylinder to the part between the two heights, which are not included.
+    pub fn set_limits(&mut self, minimum: f64, maximum: f64) {
+        assert!(
+            minimum < maximum,
+            "a cylinder's minimum must be below its maximum"
+        );
+        self.minimum = minimum;
+        self.maximum = maximum;
+    }
+
+    /// set_closed puts caps on the ends of a truncated cylinder, making it solid.
+    pub fn set_closed(&mut self, closed: bool) {
+        self.closed = closed;
+    }
+
+    /// cap_hits are where the ray crosses the caps at each end, which are discs of radius 1.
+    fn cap_hits(&self, r: Ray) -> Vec<f64> {
+        if !self.closed || r.direction().y().abs() < EPSILON {
+            return vec![];
+        }
+        [self.minimum, self.maximum]
+            .into_iter()
+            .filter(|y| y.is_finite())
+            .map(|y| (y - r.origin().y()) / r.direction().y())
+            .filter(|&t| {
+                let p = r.at(t);
+                p.x().powi(2) + p.z().powi(2) <= 1.
+            })
+            .collect()
+    }
+}
+
+impl Default for Cylinder {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+impl Shape for Cylinder {
+    fn local_intersect(&self, r: Ray) -> Intersections {
+        let mut hits = self.cap_hits(r);
+
+        // the cylinder is a circle in the xz plane so only the x and z parts of the ray matter.
+        let a = r.direction().x().powi(2) + r.direction().z().powi(2);
+
+        // a ray parallel to the y axis can never hit the walls.
+        if a.abs() >= EPSILON {
+            let b =
+                2. * r.origin().x() * r.direction().x() + 2. * r.origin().z() * r.direction().z();
+            let c = r.origin().x().powi(2) + r.origin().z().powi(2) - 1.;
+
+            hits.extend(solvers::quadratic(a, b, c).into_iter().filter(|&t| {
+                let y = r.at(t).y();
+                self.minimum < y && y < self.maximum
+            }));
+        }
+
+        hits.sort_by(f64::total_cmp);
+        Intersections::new(
+            hits.into_iter()
+                .map(|t| Intersection::new(t, self.box_clone()))
+                .collect(),
+        )
+    }
+
+    fn local_normal(&self, point: Point) -> Vector {
+        let distance = point.x().powi(2) + point.z().powi(2);
+        if distance < 1. && point.y() >= self.maximum - EPSILON {
+            return Vector::new(0., 1., 0.);
+        }
+        if distance < 1. && point.y() <= self.minimum + EPSILON {
+            return Vector::new(0., -1., 0.);
+        }
+        Vector::new(point.x(), 0., point.z())
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::new(P![-1., self.minimum, -1.], P![1., self.maximum, 1.])
+    }
+}
+
+#[cfg(test)]
+mod test_cylinder {
+    use crate::{comparison::approx_eq, P, V};
+
+    use super::*;
+
+    #[test]
+    fn test_ray_misses() {
+        let c = Cylinder::default();
+        let cases = [
+            (P![1., 0., 0.], V![0., 1., 0.]),
+            (P![0., 0., 0.], V![0., 1., 0.]),
+            (P![0., 0., -5.], V![1., 1., 1.]),
+        ];
+
+        for (origin, direction) in cases {
+            let r = Ray::new(origin, direction.norm());
+            let xs = c.local_intersect(r);
+            assert_eq!(0, xs.len());
+        }
+    }
+
+    #[test]
+    fn test_ray_hits() {
+        let c = Cylinder::default();
+        let cases = [
+            (P![1., 0., -5.], V![0., 0., 1.], 5., 5.),
+            (P![0., 0., -5.], V![0., 0., 1.], 4., 6.),
+            (P![0.5, 0., -5.], V![0.1, 1., 1.], 6.80798, 7.08872),
+        ];
+
+        for (origin, direction, t0, t1) in cases {
+            let r = Ray::new(origin, direction.norm());
+            let xs = c.local_intersect(r);
+            assert_eq!(2, xs.len());
+            assert!(approx_eq(t0, xs[0].t()));
+            assert!(approx_eq(t1, xs[1].t()));
+        }
+    }
+
+    #[test]
+    fn test_normal() {
+        let c = Cylinder::default();
+        let cases = [
+            (P![1., 0., 0.], V![1., 0., 0.]),
+            (P![0., 5., -1.], V![0., 0., -1.]),
+            (P![0., -2., 1.], V![0., 0., 1.]),
+            (P![-1., 1., 0.], V![-1., 0., 0.]),
+        ];
+
+        for (point, normal) in cases {
+            assert_eq!(normal, c.local_normal(point));
+        }
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut c = Cylinder::default();
+        c.set_limits(1., 2.);
+        assert_eq!(1., c.minimum());
+        assert_eq!(2., c.maximum());
+        assert!(!c.closed());
+
+        let cases = [
+            (P![0., 1.5, 0.], V![0.1, 1., 0.], 0),
+            (P![0., 3., -5.], V![0., 0., 1.], 0),
+            (P![0., 0., -5.], V![0., 0., 1.], 0),
+            (P![0., 2., -5.], V![0., 0., 1.], 0),
+            (P![0., 1., -5.], V![0., 0., 1.], 0),
+            (P![0., 1.5, -2.], V![0., 0., 1.], 2),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.norm());
+            assert_eq!(count, c.local_intersect(r).len());
+        }
+        assert_eq!(Aabb::new(P![-1., 1., -1.], P![1., 2., 1.]), c.bounds());
+    }
+
+    #[test]
+    fn test_closed() {
+        let mut c = Cylinder::default();
+        c.set_limits(1., 2.);
+        c.set_closed(true);
+
+        let cases = [
+            (P![0., 3., 0.], V![0., -1., 0.], 2),
+            (P![0., 3., -2.], V![0., -1., 2.], 2),
+            (P![0., 4., -2.], V![0., -1., 1.], 2),
+            (P![0., 0., -2.], V![0., 1., 2.], 2),
+            (P![0., -1., -2.], V![0., 1., 1.], 2),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.norm());
+            assert_eq!(count, c.local_intersect(r).len());
+        }
+
+        // straight down through both caps, nearest first.
+        let xs = c.local_intersect(Ray::new(P![0., 3., 0.], V![0., -1., 0.]));
+        assert!(approx_eq(1., xs[0].t()));
+        assert!(approx_eq(2., xs[1].t()));
+    }
+
+    #[test]
+    fn test_cap_normals() {
+        let mut c = Cylinder::default();
+        c.set_limits(1., 2.);
+        c.set_closed(true);
+        let cases = [
+            (P![0., 1., 0.], V![0., -1., 0.]),
+            (P![0.5, 1., 0.], V![0., -1., 0.]),
+            (P![0., 1., 0.5], V![0., -1., 0.]),
+            (P![0., 2., 0.], V![0., 1., 0.]),
+            (P![0.5, 2., 0.], V![0., 1., 0.]),
+            (P![0., 2., 0.5], V![0., 1., 0.]),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(normal, c.local_normal(point));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_limits_must_be_in_order() {
+        Cylinder::default().set_limits(2., 1.);
+    }
+}
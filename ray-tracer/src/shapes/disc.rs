@@ -0,0 +1,133 @@
+use super::{Shape, ShapeBase, ShapeProps};
+use crate::{
+    comparison::EPSILON,
+    primatives::aabb::Aabb,
+    primatives::point::Point,
+    primatives::ray::Ray,
+    primatives::tuple::Tuple,
+    primatives::vector::Vector,
+    world::intersection::{Intersection, Intersections},
+    P,
+};
+use shape_derive::ShapeDerive;
+
+/// a disc is a flat circle of radius 1 in the xz plane, centred on the origin.
+/// Giving it an inner radius cuts a hole out of the middle, turning it into an annulus.
+#[derive(Clone, Debug, Default, PartialEq, ShapeDerive)]
+#[shape(new)]
+pub struct Disc {
+    inner_radius: f64,
+    props: ShapeProps,
+}
+
+impl Disc {
+    pub fn inner_radius(&self) -> f64 {
+        self.inner_radius
+    }
+
+    pub fn set_inner_radius(&mut self, inner_radius: f64) {
+        assert!(
+            (0. ..1.).contains(&inner_radius),
+            "a disc's inner radius must be at least 0 and less than 1"
+        );
+        self.inner_radius = inner_radius;
+    }
+}
+
+impl Shape for Disc {
+    /// local_intersect finds where the ray crosses the xz plane and keeps the hit if it lands between the two radii.
+    fn local_intersect(&self, r: Ray) -> Intersections {
+        // if the ray is parallel then there are no intersections
+        if r.direction().y().abs() < EPSILON {
+            return Intersections::EMPTY;
+        }
+
+        let t = -r.origin().y() / r.direction().y();
+        let point = r.at(t);
+        let distance_sq = point.x().powi(2) + point.z().powi(2);
+        if distance_sq > 1. || distance_sq < self.inner_radius.powi(2) {
+            return Intersections::EMPTY;
+        }
+
+        Intersections::new(vec![Intersection::new(t, self.box_clone())])
+    }
+
+    fn local_normal(&self, _point: Point) -> Vector {
+        Vector::new(0., 1., 0.)
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::new(P![-1., 0., -1.], P![1., 0., 1.])
+    }
+
+    fn is_planar(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test_disc {
+    use crate::{comparison::approx_eq, P, V};
+
+    use super::*;
+
+    #[test]
+    fn test_ray_hits() {
+        let d = Disc::default();
+        let cases = [
+            (P![0., 1., 0.], V![0., -1., 0.], 1.),
+            (P![0.5, -2., -0.5], V![0., 1., 0.], 2.),
+            (P![1., 1., 0.], V![0., -1., 0.], 1.),
+        ];
+
+        for (origin, direction, t) in cases {
+            let xs = d.local_intersect(Ray::new(origin, direction));
+            assert_eq!(1, xs.len());
+            assert!(approx_eq(t, xs[0].t()));
+        }
+    }
+
+    #[test]
+    fn test_ray_misses() {
+        let d = Disc::default();
+        let cases = [
+            // parallel to the disc.
+            (P![0., 1., 0.], V![0., 0., 1.]),
+            // outside the edge.
+            (P![1., 1., 1.], V![0., -1., 0.]),
+            (P![0., 1., -1.5], V![0., -1., 0.]),
+        ];
+
+        for (origin, direction) in cases {
+            assert_eq!(0, d.local_intersect(Ray::new(origin, direction)).len());
+        }
+    }
+
+    #[test]
+    fn test_annulus() {
+        let mut d = Disc::default();
+        d.set_inner_radius(0.5);
+        assert!(approx_eq(0.5, d.inner_radius()));
+
+        // through the hole.
+        let r = Ray::new(P![0.2, 1., 0.2], V![0., -1., 0.]);
+        assert_eq!(0, d.local_intersect(r).len());
+
+        // through the ring.
+        let r = Ray::new(P![0., 1., 0.75], V![0., -1., 0.]);
+        assert_eq!(1, d.local_intersect(r).len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_inner_radius_too_large() {
+        Disc::default().set_inner_radius(1.);
+    }
+
+    #[test]
+    fn test_normal() {
+        let d = Disc::default();
+        assert_eq!(V![0., 1., 0.], d.local_normal(P![0., 0., 0.]));
+        assert_eq!(V![0., 1., 0.], d.local_normal(P![0.5, 0., -0.5]));
+    }
+}
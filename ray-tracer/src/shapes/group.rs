@@ -0,0 +1,257 @@
+use std::sync::Arc;
+
+use super::{
+    make_mut, material::Material, triangle::TriangleIntersection, BoxedShape, Shape, ShapeProps,
+};
+use crate::{
+    primatives::aabb::Aabb, primatives::matrix::Matrix, primatives::point::Point,
+    primatives::ray::Ray, primatives::vector::Vector, world::intersection::Intersections,
+};
+use shape_derive::ShapeDerive;
+
+/// a group is a collection of shapes that can be transformed together.
+/// The group's transformation is passed down into its children when they are added,
+/// so every child always holds its full transformation from object space to world space.
+#[derive(Clone, Debug, Default, PartialEq, ShapeDerive)]
+pub struct Group {
+    children: Vec<BoxedShape>,
+    /// bounds is the box around all of the children in world space, kept up to date as they are added or moved.
+    bounds: Aabb,
+    props: ShapeProps,
+}
+
+impl Group {
+    pub fn new(transform: Option<Matrix>, material: Option<Material>) -> Self {
+        Self {
+            children: vec![],
+            bounds: Aabb::empty(),
+            props: ShapeProps::new(transform, material),
+        }
+    }
+
+    /// add_child puts the shape into the group, applying the group's transformation on top of the shape's own.
+    pub fn add_child(&mut self, mut child: BoxedShape) {
+        make_mut(&mut child).transform_by(self.transformation());
+        self.bounds = self.bounds.merge(&child.world_bounds());
+        self.children.push(child);
+    }
+
+    pub fn children(&self) -> &Vec<BoxedShape> {
+        &self.children
+    }
+
+    /// replace_child swaps the child at the index for another that already holds the group's transformation,
+    /// such as a changed copy of the child it replaces. It panics if there is no such child.
+    pub fn replace_child(&mut self, index: usize, child: BoxedShape) {
+        self.children[index] = child;
+        self.bounds = self.children.iter().fold(Aabb::empty(), |bounds, child| {
+            bounds.merge(&child.world_bounds())
+        });
+    }
+
+    /// set_transform replaces the group's transformation, undoing the old one on every child before applying the new one.
+    pub fn set_transform(&mut self, transform: Matrix) {
+        let change = transform.clone() * self.inverse_transformation().clone();
+        self.children
+            .iter_mut()
+            .for_each(|child| make_mut(child).transform_by(&change));
+        self.bounds = self.children.iter().fold(Aabb::empty(), |bounds, child| {
+            bounds.merge(&child.world_bounds())
+        });
+
+        self.props.set_transform(transform);
+    }
+}
+
+impl Shape for Group {
+    /// intersect passes the world space ray straight to the children as they already hold the group's transformation.
+    /// Rays that miss the box around the children don't need to test any of them.
+    fn intersect(&self, r: Ray) -> Intersections {
+        if !self.bounds.intersects(r) {
+            return Intersections::EMPTY;
+        }
+        let mut intersections = Intersections::EMPTY;
+        self.children
+            .iter()
+            .for_each(|child| intersections.extend(child.intersect(r)));
+        intersections
+    }
+
+    fn local_intersect(&self, r: Ray) -> Intersections {
+        self.intersect(r.transform(self.transformation()))
+    }
+
+    fn local_normal(&self, _point: Point) -> Vector {
+        unreachable!("a group has no surface of its own, normals come from the child that was hit")
+    }
+
+    fn replace_material(&mut self, old: &Arc<Material>, new: &Arc<Material>) {
+        if Arc::ptr_eq(self.shared_material(), old) {
+            self.set_shared_material(new.clone());
+        }
+        self.children
+            .iter_mut()
+            .for_each(|child| make_mut(child).replace_material(old, new));
+    }
+
+    fn set_triangle_intersection(&mut self, intersection: TriangleIntersection) {
+        self.children
+            .iter_mut()
+            .for_each(|child| make_mut(child).set_triangle_intersection(intersection));
+    }
+
+    fn set_transformation(&mut self, transform: Matrix) {
+        self.set_transform(transform)
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.bounds.transform(self.inverse_transformation())
+    }
+
+    fn world_bounds(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+#[cfg(test)]
+mod test_group {
+    use std::f64::consts::PI;
+
+    use crate::{
+        comparison::approx_eq,
+        primatives::transformation::{rotation_y, scaling, translation},
+        primatives::tuple::Tuple,
+        shapes::{sphere::Sphere, ShapeBase},
+        P, V,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let g = Group::default();
+        assert_eq!(&Matrix::identity_matrix(), g.transformation());
+        assert!(g.children().is_empty());
+    }
+
+    #[test]
+    fn test_add_child() {
+        let mut g = Group::new(Some(translation(1., 0., 0.)), None);
+        g.add_child(Sphere::new(Some(scaling(2., 2., 2.)), None).box_clone());
+
+        assert_eq!(1, g.children().len());
+        assert_eq!(
+            &(translation(1., 0., 0.) * scaling(2., 2., 2.)),
+            g.children()[0].transformation()
+        );
+    }
+
+    #[test]
+    fn test_set_transform_after_adding() {
+        let mut g = Group::default();
+        g.add_child(Sphere::new(Some(translation(5., 0., 0.)), None).box_clone());
+        g.set_transform(scaling(2., 2., 2.));
+        g.set_transform(rotation_y(PI / 2.));
+
+        assert_eq!(
+            &(rotation_y(PI / 2.) * translation(5., 0., 0.)),
+            g.children()[0].transformation()
+        );
+    }
+
+    #[test]
+    fn test_intersect_empty() {
+        let g = Group::default();
+        let r = Ray::new(P![0., 0., 0.], V![0., 0., 1.]);
+        assert_eq!(0, g.local_intersect(r).len());
+    }
+
+    #[test]
+    fn test_intersect() {
+        let s1 = Sphere::default().box_clone();
+        let s2 = Sphere::new(Some(translation(0., 0., -3.)), None).box_clone();
+        let s3 = Sphere::new(Some(translation(5., 0., 0.)), None).box_clone();
+        let mut g = Group::default();
+        g.add_child(s1.clone());
+        g.add_child(s2.clone());
+        g.add_child(s3);
+
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+        let xs = g.local_intersect(r);
+
+        assert_eq!(4, xs.len());
+        assert_eq!(&s2, &xs[0].clone().object());
+        assert_eq!(&s2, &xs[1].clone().object());
+        assert_eq!(&s1, &xs[2].clone().object());
+        assert_eq!(&s1, &xs[3].clone().object());
+    }
+
+    #[test]
+    fn test_intersect_transformed() {
+        let mut g = Group::new(Some(scaling(2., 2., 2.)), None);
+        g.add_child(Sphere::new(Some(translation(5., 0., 0.)), None).box_clone());
+
+        let r = Ray::new(P![10., 0., -10.], V![0., 0., 1.]);
+        let xs = g.box_clone().intersect(r);
+
+        assert_eq!(2, xs.len());
+        assert!(approx_eq(8., xs[0].t()));
+        assert!(approx_eq(12., xs[1].t()));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut g = Group::default();
+        assert!(g.world_bounds().is_empty());
+
+        g.add_child(Sphere::new(Some(translation(5., 0., 0.)), None).box_clone());
+        g.add_child(Sphere::new(Some(scaling(2., 2., 2.)), None).box_clone());
+        assert_eq!(
+            Aabb::new(P![-2., -2., -2.], P![6., 2., 2.]),
+            g.world_bounds()
+        );
+
+        // moving the group moves the box around its children.
+        g.set_transform(translation(0., 10., 0.));
+        assert_eq!(
+            Aabb::new(P![-2., 8., -2.], P![6., 12., 2.]),
+            g.world_bounds()
+        );
+        assert_eq!(Aabb::new(P![-2., -2., -2.], P![6., 2., 2.]), g.bounds());
+
+        // a ray missing the box doesn't reach the children.
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+        assert_eq!(0, g.intersect(r).len());
+    }
+
+    /// nested builds a sphere inside two groups, the inner one scaled by the given amount.
+    fn nested(inner: Matrix) -> BoxedShape {
+        let mut g2 = Group::new(Some(inner), None);
+        g2.add_child(Sphere::new(Some(translation(5., 0., 0.)), None).box_clone());
+        let mut g1 = Group::new(Some(rotation_y(PI / 2.)), None);
+        g1.add_child(g2.box_clone());
+
+        let g2 = g1.children()[0]
+            .as_any()
+            .downcast_ref::<Group>()
+            .unwrap()
+            .clone();
+        g2.children()[0].clone()
+    }
+
+    #[test]
+    fn test_world_to_object() {
+        let s = nested(scaling(2., 2., 2.));
+        assert_eq!(P![0., 0., -1.], s.world_to_object(P![-2., 0., -10.]));
+    }
+
+    #[test]
+    fn test_normal_to_world() {
+        let s = nested(scaling(1., 2., 3.));
+        let r = 3.0_f64.sqrt() / 3.;
+
+        let expected = V![0.28571, 0.42857, -0.85714];
+        assert_eq!(expected, s.normal_to_world(V![r, r, r]));
+        assert_eq!(expected, s.normal(P![3. * r, 2. * r, -(r + 5.)]));
+    }
+}
@@ -0,0 +1,308 @@
+use std::sync::Arc;
+
+use ndarray::Array2;
+
+use super::{material::Material, Shape, ShapeBase, ShapeProps};
+use crate::{
+    comparison::EPSILON,
+    primatives::aabb::Aabb,
+    primatives::matrix::Matrix,
+    primatives::point::Point,
+    primatives::ray::Ray,
+    primatives::solvers::quadratic,
+    primatives::tuple::Tuple,
+    primatives::vector::Vector,
+    world::intersection::{Intersection, Intersections},
+    P, V,
+};
+use shape_derive::ShapeDerive;
+
+/// Heightfield is a terrain made from a grid of heights spread evenly over the square from -1 to 1 in x and z,
+/// with y as the height. Between the samples the surface is a bilinear patch, which bends smoothly between
+/// the heights of the four corners of its cell.
+#[derive(Clone, Debug, ShapeDerive)]
+pub struct Heightfield {
+    /// heights are indexed by [x, z]. They are shared between copies of the shape as they can be large.
+    heights: Arc<Array2<f64>>,
+    bounds: Aabb,
+    props: ShapeProps,
+}
+
+impl Heightfield {
+    /// new makes a heightfield from heights indexed by [x, z], which needs at least two samples in each direction.
+    /// A greyscale image such as a PgmImport can be used directly, giving heights from 0 to 1 with
+    /// the top of the image at -z.
+    pub fn new(
+        heights: Array2<f64>,
+        transform: Option<Matrix>,
+        material: Option<Material>,
+    ) -> Self {
+        assert!(
+            heights.shape()[0] >= 2 && heights.shape()[1] >= 2,
+            "a heightfield needs at least 2x2 heights but was given {}x{}",
+            heights.shape()[0],
+            heights.shape()[1]
+        );
+        let (low, high) = heights
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), &h| {
+                (low.min(h), high.max(h))
+            });
+        Self {
+            heights: Arc::new(heights),
+            bounds: Aabb::new(P![-1., low, -1.], P![1., high, 1.]),
+            props: ShapeProps::new(transform, material),
+        }
+    }
+
+    pub fn heights(&self) -> &Array2<f64> {
+        &self.heights
+    }
+
+    /// height_at is the height of the surface above the point (x, z), which must be inside the square.
+    pub fn height_at(&self, x: f64, z: f64) -> f64 {
+        let (gx, gz) = self.to_grid(x, z);
+        let (i, j) = self.cell_of(gx, gz);
+        let [h00, a, b, c] = self.patch(i, j);
+        let (u, v) = (gx - i as f64, gz - j as f64);
+        h00 + a * u + b * v + c * u * v
+    }
+
+    /// cells is how many cells there are along x and z, one fewer than the number of heights.
+    fn cells(&self) -> (usize, usize) {
+        (self.heights.shape()[0] - 1, self.heights.shape()[1] - 1)
+    }
+
+    /// to_grid turns x and z into grid coordinates, which run from 0 to the number of cells.
+    fn to_grid(&self, x: f64, z: f64) -> (f64, f64) {
+        let (nx, nz) = self.cells();
+        ((x + 1.) / 2. * nx as f64, (z + 1.) / 2. * nz as f64)
+    }
+
+    fn cell_of(&self, gx: f64, gz: f64) -> (usize, usize) {
+        let (nx, nz) = self.cells();
+        (
+            (gx.floor().max(0.) as usize).min(nx - 1),
+            (gz.floor().max(0.) as usize).min(nz - 1),
+        )
+    }
+
+    /// patch is the bilinear patch over the cell as [h00, a, b, c], where the height at (u, v) across the cell is
+    /// h00 + a·u + b·v + c·u·v.
+    fn patch(&self, i: usize, j: usize) -> [f64; 4] {
+        let h = &self.heights;
+        let (h00, h10, h01, h11) = (h[[i, j]], h[[i + 1, j]], h[[i, j + 1]], h[[i + 1, j + 1]]);
+        [h00, h10 - h00, h01 - h00, h00 - h10 - h01 + h11]
+    }
+}
+
+impl Shape for Heightfield {
+    /// local_intersect steps the ray across the cells it passes over, like drawing a line across a grid of pixels,
+    /// and intersects the patch of each cell along the stretch of the ray above it. The height of a patch along a
+    /// straight line is a quadratic, so each cell can be solved exactly.
+    fn local_intersect(&self, r: Ray) -> Intersections {
+        let Some((t_enter, t_exit)) = self.bounds.span(r) else {
+            return Intersections::EMPTY;
+        };
+        let (nx, nz) = self.cells();
+        let (o, d) = (r.origin(), r.direction());
+        let (ox, oz) = self.to_grid(o.x(), o.z());
+        // how far the ray moves across the grid for every unit of t.
+        let (dx, dz) = (d.x() * nx as f64 / 2., d.z() * nz as f64 / 2.);
+
+        let start = r.at(t_enter);
+        let (gx, gz) = self.to_grid(start.x(), start.z());
+        let (mut i, mut j) = self.cell_of(gx, gz);
+        // t_next is how far along the ray it crosses into the next cell along each axis.
+        let next = |cell: usize, origin: f64, direction: f64| {
+            if direction > 0. {
+                (cell as f64 + 1. - origin) / direction
+            } else if direction < 0. {
+                (cell as f64 - origin) / direction
+            } else {
+                f64::INFINITY
+            }
+        };
+        let (mut next_x, mut next_z) = (next(i, ox, dx), next(j, oz, dz));
+
+        let mut xs: Vec<Intersection> = vec![];
+        let mut t_start = t_enter;
+        loop {
+            let t_end = next_x.min(next_z).min(t_exit);
+            let [h00, a, b, c] = self.patch(i, j);
+            let (u0, v0) = (ox - i as f64, oz - j as f64);
+            // the ray's height minus the patch's height along the ray, as a quadratic in t.
+            let roots = quadratic(
+                -c * dx * dz,
+                d.y() - a * dx - b * dz - c * (u0 * dz + v0 * dx),
+                o.y() - h00 - a * u0 - b * v0 - c * u0 * v0,
+            );
+            for t in roots {
+                let inside = t >= t_start - EPSILON && t <= t_end + EPSILON;
+                // a hit right on the edge of a cell is found from both sides.
+                let repeated = xs.last().is_some_and(|x| (x.t() - t).abs() < EPSILON);
+                if inside && !repeated {
+                    xs.push(Intersection::new(t, self.box_clone()));
+                }
+            }
+
+            if t_end >= t_exit {
+                break;
+            }
+            if next_x <= next_z {
+                match i.checked_add_signed(dx.signum() as isize) {
+                    Some(cell) if cell < nx => i = cell,
+                    _ => break,
+                }
+                next_x = next(i, ox, dx);
+            } else {
+                match j.checked_add_signed(dz.signum() as isize) {
+                    Some(cell) if cell < nz => j = cell,
+                    _ => break,
+                }
+                next_z = next(j, oz, dz);
+            }
+            t_start = t_end;
+        }
+        Intersections::new(xs)
+    }
+
+    /// local_normal is the slope of the patch under the point, found from how fast its height changes along x and z.
+    fn local_normal(&self, point: Point) -> Vector {
+        let (nx, nz) = self.cells();
+        let (gx, gz) = self.to_grid(point.x(), point.z());
+        let (i, j) = self.cell_of(gx, gz);
+        let [_, a, b, c] = self.patch(i, j);
+        let (u, v) = (gx - i as f64, gz - j as f64);
+        let dh_dx = (a + c * v) * nx as f64 / 2.;
+        let dh_dz = (b + c * u) * nz as f64 / 2.;
+        V![-dh_dx, 1., -dh_dz].norm()
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+impl PartialEq for Heightfield {
+    fn eq(&self, other: &Self) -> bool {
+        (Arc::ptr_eq(&self.heights, &other.heights) || self.heights == other.heights)
+            && self.props == other.props
+    }
+}
+
+#[cfg(test)]
+mod test_heightfield {
+    use ndarray::array;
+
+    use crate::{comparison::approx_eq, importers::pgm::PgmImport};
+
+    use super::*;
+
+    /// ramp rises from 0 at x = -1 to 1 at x = 1, with four cells along x and two along z.
+    fn ramp() -> Heightfield {
+        let heights = Array2::from_shape_fn((5, 3), |(x, _)| x as f64 / 4.);
+        Heightfield::new(heights, None, None)
+    }
+
+    /// bump is flat at 0 apart from a single peak of 1 in the middle of a 3x3 grid.
+    fn bump() -> Heightfield {
+        let heights = array![[0., 0., 0.], [0., 1., 0.], [0., 0., 0.]];
+        Heightfield::new(heights, None, None)
+    }
+
+    #[test]
+    fn test_height_at() {
+        let h = ramp();
+        assert!(approx_eq(0., h.height_at(-1., 0.)));
+        assert!(approx_eq(0.5, h.height_at(0., 0.7)));
+        assert!(approx_eq(0.625, h.height_at(0.25, -0.3)));
+        assert!(approx_eq(1., h.height_at(1., 1.)));
+
+        let h = bump();
+        assert!(approx_eq(1., h.height_at(0., 0.)));
+        // bilinear across the cell, so a quarter of the way along both sides is (3/4)².
+        assert!(approx_eq(0.5625, h.height_at(0.25, 0.25)));
+        assert!(approx_eq(0., h.height_at(-1., 0.5)));
+    }
+
+    #[test]
+    fn test_bounds() {
+        assert_eq!(Aabb::new(P![-1., 0., -1.], P![1., 1., 1.]), bump().bounds());
+    }
+
+    #[test]
+    fn test_ray_hits_from_above() {
+        for h in [ramp(), bump()] {
+            for (x, z) in [(-0.9, -0.9), (0., 0.), (0.3, -0.6), (0.75, 0.1), (1., 1.)] {
+                let xs = h.local_intersect(Ray::new(P![x, 5., z], V![0., -1., 0.]));
+                assert_eq!(1, xs.len());
+                assert!(approx_eq(5. - h.height_at(x, z), xs[0].t()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ray_crosses_cells() {
+        // a shallow ray skimming across the ramp passes over several cells before hitting it.
+        let h = ramp();
+        let r = Ray::new(P![-3., 1.5, 0.1], V![1., -0.2, 0.].norm());
+        let xs = h.local_intersect(r);
+        assert_eq!(1, xs.len());
+        let hit = r.at(xs[0].t());
+        assert!(approx_eq(h.height_at(hit.x(), hit.z()), hit.y()));
+        assert!(approx_eq(4. / 7., hit.x()));
+
+        // a ray along the peak of the bump goes in and out of the surface.
+        let h = bump();
+        let r = Ray::new(P![-2., 0.5, 0.], V![1., 0., 0.]);
+        let xs = h.local_intersect(r);
+        assert_eq!(2, xs.len());
+        assert!(approx_eq(1.5, xs[0].t()));
+        assert!(approx_eq(2.5, xs[1].t()));
+    }
+
+    #[test]
+    fn test_ray_misses() {
+        let h = bump();
+        let cases = [
+            // above the highest point.
+            (P![-2., 1.5, 0.], V![1., 0., 0.]),
+            // beside the square.
+            (P![1.5, 5., 0.], V![0., -1., 0.]),
+            // under the surface pointing away.
+            (P![0., -1., 0.], V![0., -1., 0.]),
+            // over a corner where the ground is flat.
+            (P![-2., 0.1, -0.95], V![1., 0., 0.]),
+        ];
+        for (origin, direction) in cases {
+            assert_eq!(0, h.local_intersect(Ray::new(origin, direction)).len());
+        }
+    }
+
+    #[test]
+    fn test_normal() {
+        // the ramp rises 1 over 2 units of x so its normal leans back towards -x.
+        let n = ramp().local_normal(P![0.2, 0.6, 0.4]);
+        assert_eq!(V![-0.5, 1., 0.].norm(), n);
+
+        let h = bump();
+        assert_eq!(V![0., 1., 0.], h.local_normal(P![-1., 0., -1.]));
+        // on the slope towards -x from the peak the surface faces -x.
+        assert!(h.local_normal(P![-0.5, 0.5, 0.]).x() < 0.);
+    }
+
+    #[test]
+    fn test_from_image() {
+        let image = PgmImport::parse(b"P2 2 2 10 0 10 0 10").unwrap();
+        let h = Heightfield::new(image.into_values(), None, None);
+        assert!(approx_eq(0.5, h.height_at(0., 0.)));
+        assert!(approx_eq(1., h.height_at(1., -1.)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_too_few_heights() {
+        Heightfield::new(array![[0., 1., 2.]], None, None);
+    }
+}
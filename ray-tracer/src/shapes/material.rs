@@ -1,10 +1,18 @@
-use crate::primatives::color::Color;
+use crate::primatives::{color::Color, point::Point, vector::Vector};
 
-use super::patterns::BoxedPattern;
+use super::{
+    patterns::{
+        bump::{filtered_shininess, perturb_normal},
+        normal_map::NormalMap,
+        BoxedPattern,
+    },
+    BoxedShape,
+};
 
 use builder_derive::Builder;
 
 #[derive(Clone, Debug, PartialEq, Builder)]
+#[builder(default, consuming)]
 pub struct Material {
     color: Color,
     ambient: f64,
@@ -12,6 +20,28 @@ pub struct Material {
     specular: f64,
     shininess: f64,
     pattern: Option<BoxedPattern>,
+    /// reflective is how much of the reflected scene is added to the surface color, 0 is not reflective and 1 is a perfect mirror.
+    reflective: f64,
+    /// reflection_blur is the radius of the disc around the mirror direction that reflections off floor-style planar
+    /// shapes are scattered over, 0 gives a sharp mirror and around 0.1 a polished floor.
+    reflection_blur: f64,
+    /// transparency is how much light passes through the surface, 0 is opaque and 1 is completely clear.
+    transparency: f64,
+    /// refractive_index is how much light bends when it enters the material, 1 is a vacuum and 1.5 is glass.
+    refractive_index: f64,
+    /// casts_shadow is whether the surface blocks light from reaching the shapes behind it. Turning it off stops
+    /// water and windows throwing solid black shadows.
+    casts_shadow: bool,
+    /// bump_map tilts the surface normal as if the surface were raised by the pattern's brightness, so a smooth
+    /// shape can be shaded like orange peel or hammered metal without changing its geometry.
+    bump_map: Option<BoxedPattern>,
+    /// bump_strength is how tall the bumps are for each unit of the bump map's brightness.
+    bump_strength: f64,
+    /// normal_map tilts the surface normal by the normals stored in an image, for detail baked from a finer model.
+    normal_map: Option<NormalMap>,
+    /// emissive is light the surface gives off itself, added whatever lights the scene, for glowing panels and neon.
+    /// It only lights other shapes if the shape is also made into a light, see `World::add_emissive_lights`.
+    emissive: Color,
 }
 
 impl Material {
@@ -30,6 +60,7 @@ impl Material {
             specular,
             shininess,
             pattern,
+            ..Default::default()
         }
     }
     pub fn color(&self) -> Color {
@@ -50,6 +81,117 @@ impl Material {
     pub fn pattern(&self) -> Option<&BoxedPattern> {
         self.pattern.as_ref()
     }
+
+    /// color_at is the color of the surface at a point on the object in world space, taken from the pattern if
+    /// the material has one.
+    pub fn color_at(&self, object: BoxedShape, point: Point) -> Color {
+        let Some(pattern) = &self.pattern else {
+            return self.color;
+        };
+        // the object's pattern transformation is only for its own material.
+        match object
+            .pattern_transformation()
+            .filter(|_| std::ptr::eq(object.material(), self))
+        {
+            Some(transform) => pattern.local_color_at(transform * point),
+            None => pattern.at_shape(object, point),
+        }
+    }
+    pub fn reflective(&self) -> f64 {
+        self.reflective
+    }
+    pub fn reflection_blur(&self) -> f64 {
+        self.reflection_blur
+    }
+    pub fn transparency(&self) -> f64 {
+        self.transparency
+    }
+    pub fn refractive_index(&self) -> f64 {
+        self.refractive_index
+    }
+    pub fn casts_shadow(&self) -> bool {
+        self.casts_shadow
+    }
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+    pub fn set_ambient(&mut self, ambient: f64) {
+        self.ambient = ambient;
+    }
+    pub fn set_diffuse(&mut self, diffuse: f64) {
+        self.diffuse = diffuse;
+    }
+    pub fn set_specular(&mut self, specular: f64) {
+        self.specular = specular;
+    }
+    pub fn set_shininess(&mut self, shininess: f64) {
+        self.shininess = shininess;
+    }
+    pub fn set_pattern(&mut self, pattern: Option<BoxedPattern>) {
+        self.pattern = pattern;
+    }
+    pub fn set_reflective(&mut self, reflective: f64) {
+        self.reflective = reflective;
+    }
+    pub fn set_reflection_blur(&mut self, reflection_blur: f64) {
+        self.reflection_blur = reflection_blur;
+    }
+    pub fn set_transparency(&mut self, transparency: f64) {
+        self.transparency = transparency;
+    }
+    pub fn set_refractive_index(&mut self, refractive_index: f64) {
+        self.refractive_index = refractive_index;
+    }
+    pub fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        self.casts_shadow = casts_shadow;
+    }
+    pub fn bump_map(&self) -> Option<&BoxedPattern> {
+        self.bump_map.as_ref()
+    }
+    pub fn bump_strength(&self) -> f64 {
+        self.bump_strength
+    }
+    pub fn set_bump_map(&mut self, bump_map: Option<BoxedPattern>) {
+        self.bump_map = bump_map;
+    }
+    pub fn set_bump_strength(&mut self, bump_strength: f64) {
+        self.bump_strength = bump_strength;
+    }
+    pub fn normal_map(&self) -> Option<&NormalMap> {
+        self.normal_map.as_ref()
+    }
+    pub fn set_normal_map(&mut self, normal_map: Option<NormalMap>) {
+        self.normal_map = normal_map;
+    }
+    pub fn emissive(&self) -> Color {
+        self.emissive
+    }
+    pub fn set_emissive(&mut self, emissive: Color) {
+        self.emissive = emissive;
+    }
+
+    /// normal_at is the normal to shade the object with at a world point, which is its surface normal tilted by the
+    /// normal map and then the bump map, if the material has them.
+    pub fn normal_at(&self, object: &BoxedShape, point: Point, normal: Vector) -> Vector {
+        let normal = match &self.normal_map {
+            Some(normal_map) => normal_map.perturb(object, point, normal),
+            None => normal,
+        };
+        match &self.bump_map {
+            Some(bump_map) => {
+                perturb_normal(&**bump_map, object, point, normal, self.bump_strength)
+            }
+            None => normal,
+        }
+    }
+
+    /// with_bump_roughness is the material for shading bumps of the given roughness from far away, such as found
+    /// by bump_roughness, with its highlight spread out so that it doesn't sparkle.
+    pub fn with_bump_roughness(&self, roughness: f64) -> Self {
+        let mut material = self.clone();
+        material.shininess = filtered_shininess(self.shininess, roughness);
+        material
+    }
 }
 
 impl Default for Material {
@@ -61,13 +203,41 @@ impl Default for Material {
             specular: 0.9,
             shininess: 200.0,
             pattern: None,
+            reflective: 0.0,
+            reflection_blur: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+            casts_shadow: true,
+            bump_map: None,
+            bump_strength: 1.0,
+            normal_map: None,
+            emissive: Color::BLACK,
         }
     }
 }
 
 #[cfg(test)]
 mod test_materials {
-    use crate::C;
+    use crate::{
+        primatives::{
+            transformation::{scaling, translation},
+            tuple::Tuple,
+        },
+        shapes::{
+            make_mut,
+            patterns::{
+                solid::SolidPattern,
+                striped::StripePattern,
+                texture_map::{UvImage, UvMapping},
+                Pattern, PatternBase,
+            },
+            plane::Plane,
+            sphere::Sphere,
+            Shape, ShapeBase,
+        },
+        world::canvas::Canvas,
+        C, P, V,
+    };
 
     use super::*;
 
@@ -80,6 +250,13 @@ mod test_materials {
         assert_eq!(m.diffuse, 0.9);
         assert_eq!(m.specular, 0.9);
         assert_eq!(m.shininess, 200.0);
+        assert_eq!(m.reflective, 0.0);
+        assert_eq!(m.reflection_blur, 0.0);
+        assert_eq!(m.transparency, 0.0);
+        assert_eq!(m.refractive_index, 1.0);
+        assert_eq!(m.bump_map, None);
+        assert_eq!(m.bump_strength, 1.0);
+        assert_eq!(m.normal_map, None);
     }
 
     #[test]
@@ -93,7 +270,84 @@ mod test_materials {
             .build()
             .unwrap();
 
-        assert_eq!(m, Material::new(C![1., 1., 1.], 0.5, 1.0, 0.5, 200.0, None))
+        assert_eq!(m, Material::new(C![1., 1., 1.], 0.5, 1.0, 0.5, 200.0, None));
+
         // should apply defaults for unset values
+        let m = Material::builder().reflective(0.5).build().unwrap();
+        assert_eq!(m.reflective(), 0.5);
+        assert_eq!(m.color(), Material::default().color());
+        assert_eq!(m.shininess(), Material::default().shininess());
+
+        // an owned builder can move its pattern into the material instead of cloning it.
+        let mut builder = Material::builder();
+        builder.pattern(SolidPattern::new(C![1., 0., 0.]).box_clone());
+        let m = builder.build().unwrap();
+        assert_eq!(m, builder.build_owned().unwrap());
+    }
+    #[test]
+    fn test_color_at_pattern_transform() {
+        let mut stripes = StripePattern::new(Color::WHITE, Color::BLACK, None);
+        stripes.set_transformation(translation(0.5, 0., 0.));
+        let mut sphere = Sphere::default();
+        sphere.set_transform(scaling(2., 2., 2.));
+        sphere.set_material(
+            Material::builder()
+                .pattern(stripes.box_clone())
+                .build()
+                .unwrap(),
+        );
+        let sphere = sphere.box_clone();
+
+        assert_eq!(
+            &(translation(-0.5, 0., 0.) * scaling(0.5, 0.5, 0.5)),
+            sphere.pattern_transformation().unwrap()
+        );
+        for x in [0.5, 1.5, 2.5, 3.5] {
+            let point = P![x, 0., 0.];
+            assert_eq!(
+                stripes.at_shape(sphere.clone(), point),
+                sphere.material().color_at(sphere.clone(), point)
+            );
+        }
+
+        // editing the material in place drops the combined transformation rather than leaving it stale.
+        let mut edited = sphere.clone();
+        make_mut(&mut edited).material_mut().set_pattern(Some(
+            StripePattern::new(Color::WHITE, Color::BLACK, None).box_clone(),
+        ));
+        assert!(edited.pattern_transformation().is_none());
+        assert_eq!(
+            Color::BLACK,
+            edited.material().color_at(edited.clone(), P![2.5, 0., 0.])
+        );
+    }
+
+    #[test]
+    fn test_normal_at() {
+        let plane = Plane::default().box_clone();
+        let up = V![0., 1., 0.];
+        let point = P![0.5, 0., 0.5];
+        assert_eq!(up, Material::default().normal_at(&plane, point, up));
+
+        // a normal map that leans every normal along u.
+        let mut image = Canvas::new(1, 1);
+        image.write_pixel(0, 0, C![1., 0.5, 0.5]);
+        let m = Material::builder()
+            .normal_map(NormalMap::new(UvImage::new(image), UvMapping::Planar, None))
+            .build()
+            .unwrap();
+        let normal = m.normal_at(&plane, point, up);
+        assert!(normal.x() > 0.99);
+    }
+
+    #[test]
+    fn test_with_bump_roughness() {
+        let m = Material::default();
+        assert_eq!(m, m.with_bump_roughness(0.));
+
+        // rough bumps soften the highlight but leave everything else alone.
+        let rough = m.with_bump_roughness(0.1);
+        assert!(rough.shininess() < m.shininess());
+        assert_eq!(m.specular(), rough.specular());
     }
 }
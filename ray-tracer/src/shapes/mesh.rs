@@ -0,0 +1,383 @@
+use std::sync::Arc;
+
+use super::{
+    material::Material,
+    triangle::{barycentric, TriangleIntersection},
+    BoxedShape, Shape, ShapeBase, ShapeProps,
+};
+use crate::{
+    primatives::aabb::Aabb,
+    primatives::accelerator::Accelerator,
+    primatives::bvh::Bvh,
+    primatives::matrix::Matrix,
+    primatives::point::Point,
+    primatives::ray::Ray,
+    primatives::vector::{cross, Vector},
+    world::intersection::{Intersection, Intersections},
+};
+use shape_derive::ShapeDerive;
+
+/// MeshData is everything about a mesh that doesn't change when it is moved or cloned.
+/// It is shared between every copy of the mesh and every face that is hit.
+#[derive(Clone, Debug, PartialEq)]
+struct MeshData {
+    vertices: Vec<Point>,
+    /// normals has one normal for each vertex. When it is empty the faces are flat.
+    normals: Vec<Vector>,
+    faces: Vec<[usize; 3]>,
+    materials: Vec<Arc<Material>>,
+    /// face_materials is the index into materials for each face. When it is empty every face uses the mesh's material.
+    face_materials: Vec<usize>,
+    bounds: Aabb,
+    /// bvh is the tree of boxes over the faces, so a ray only tests the faces near it.
+    bvh: Bvh,
+}
+
+/// a mesh is a lot of triangles that share their corners. The vertex, normal and face buffers are held once
+/// behind an Arc, so cloning the mesh or hitting one of its faces doesn't copy them.
+#[derive(Clone, Debug, ShapeDerive)]
+pub struct Mesh {
+    data: Arc<MeshData>,
+    intersection: TriangleIntersection,
+    props: ShapeProps,
+}
+
+impl Mesh {
+    /// new makes a mesh from the vertices and the faces between them, each face being the indices of three vertices.
+    pub fn new(
+        vertices: Vec<Point>,
+        faces: Vec<[usize; 3]>,
+        transform: Option<Matrix>,
+        material: Option<Material>,
+    ) -> Self {
+        if let Some(index) = faces.iter().flatten().find(|&&i| i >= vertices.len()) {
+            panic!(
+                "mesh face refers to vertex {} but there are only {}",
+                index,
+                vertices.len()
+            );
+        }
+        Self {
+            data: Arc::new(MeshData {
+                bounds: Aabb::around(vertices.iter().copied()),
+                bvh: Bvh::build(
+                    faces
+                        .iter()
+                        .map(|face| Aabb::around(face.map(|i| vertices[i])))
+                        .collect(),
+                ),
+                vertices,
+                normals: vec![],
+                faces,
+                materials: vec![],
+                face_materials: vec![],
+            }),
+            intersection: TriangleIntersection::default(),
+            props: ShapeProps::new(transform, material),
+        }
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.data.vertices
+    }
+
+    pub fn normals(&self) -> &[Vector] {
+        &self.data.normals
+    }
+
+    pub fn faces(&self) -> &[[usize; 3]] {
+        &self.data.faces
+    }
+
+    /// set_normals gives every vertex a normal, which are blended across each face like a smooth triangle.
+    pub fn set_normals(&mut self, normals: Vec<Vector>) {
+        assert_eq!(
+            self.data.vertices.len(),
+            normals.len(),
+            "a mesh needs one normal for each vertex"
+        );
+        Arc::make_mut(&mut self.data).normals = normals;
+    }
+
+    /// set_face_materials gives each face its own material, face_materials being the index into materials for each face.
+    /// Materials can be given as they are or already shared with other shapes.
+    pub fn set_face_materials(
+        &mut self,
+        materials: Vec<impl Into<Arc<Material>>>,
+        face_materials: Vec<usize>,
+    ) {
+        assert_eq!(
+            self.data.faces.len(),
+            face_materials.len(),
+            "a mesh needs one material index for each face"
+        );
+        if let Some(index) = face_materials.iter().find(|&&i| i >= materials.len()) {
+            panic!(
+                "mesh face refers to material {} but there are only {}",
+                index,
+                materials.len()
+            );
+        }
+        let data = Arc::make_mut(&mut self.data);
+        data.materials = materials.into_iter().map(Into::into).collect();
+        data.face_materials = face_materials;
+    }
+
+    /// face_material is the material the face is drawn with.
+    pub fn face_material(&self, face: usize) -> &Material {
+        self.face_shared_material(face)
+    }
+
+    fn face_shared_material(&self, face: usize) -> &Arc<Material> {
+        match self.data.face_materials.get(face) {
+            Some(&index) => &self.data.materials[index],
+            None => self.shared_material(),
+        }
+    }
+
+    /// corners are the three vertices of the face.
+    pub fn corners(&self, face: usize) -> [Point; 3] {
+        self.data.faces[face].map(|i| self.data.vertices[i])
+    }
+
+    /// face_normal is the normal of the face at a point on it in object space.
+    fn face_normal(&self, face: usize, point: Point) -> Vector {
+        let [p1, p2, p3] = self.corners(face);
+        let (e1, e2) = (p2 - p1, p3 - p1);
+        if self.data.normals.is_empty() {
+            return cross(e2, e1).norm();
+        }
+        let [n1, n2, n3] = self.data.faces[face].map(|i| self.data.normals[i]);
+        let (u, v) = barycentric(p1, e1, e2, point);
+        n1 * (1. - u - v) + n2 * u + n3 * v
+    }
+}
+
+impl Shape for Mesh {
+    /// local_intersect tests the faces whose boxes the ray passes through, recording which one was hit
+    /// rather than making a triangle for it.
+    fn local_intersect(&self, r: Ray) -> Intersections {
+        let mut hits = vec![];
+        self.data.bvh.visit(r, &mut |face| {
+            if let Some(t) = self.intersection.intersect(self.corners(face), r) {
+                let hit = MeshFace {
+                    mesh: self.clone(),
+                    face,
+                };
+                hits.push(Intersection::new(t, Arc::new(hit)));
+            }
+        });
+        Intersections::new(hits)
+    }
+
+    fn local_normal(&self, _point: Point) -> Vector {
+        unreachable!("a mesh's hits are its faces, which work out their own normals")
+    }
+
+    /// replace_material swaps the face materials as well as the mesh's own.
+    fn replace_material(&mut self, old: &Arc<Material>, new: &Arc<Material>) {
+        if Arc::ptr_eq(self.shared_material(), old) {
+            self.set_shared_material(new.clone());
+        }
+        if self.data.materials.iter().any(|m| Arc::ptr_eq(m, old)) {
+            Arc::make_mut(&mut self.data)
+                .materials
+                .iter_mut()
+                .filter(|m| Arc::ptr_eq(m, old))
+                .for_each(|m| *m = new.clone());
+        }
+    }
+
+    fn set_triangle_intersection(&mut self, intersection: TriangleIntersection) {
+        self.intersection = intersection;
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.data.bounds
+    }
+}
+
+impl PartialEq for Mesh {
+    fn eq(&self, other: &Self) -> bool {
+        (Arc::ptr_eq(&self.data, &other.data) || self.data == other.data)
+            && self.props == other.props
+    }
+}
+
+/// MeshFace is what a ray hits on a mesh: the mesh, sharing its buffers, and the index of the face.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshFace {
+    mesh: Mesh,
+    face: usize,
+}
+
+impl MeshFace {
+    pub fn mesh(&self) -> &Mesh {
+        &self.mesh
+    }
+
+    pub fn face(&self) -> usize {
+        self.face
+    }
+
+    /// barycentric finds (u, v) for a point on the face in world space, so that the point is
+    /// p1 + u * (p2 - p1) + v * (p3 - p1) for the face's corners in order.
+    pub fn barycentric(&self, point: Point) -> (f64, f64) {
+        let [p1, p2, p3] = self.mesh.corners(self.face);
+        barycentric(p1, p2 - p1, p3 - p1, self.world_to_object(point))
+    }
+}
+
+impl ShapeBase for MeshFace {
+    fn box_clone(&self) -> BoxedShape {
+        Arc::new(self.clone())
+    }
+
+    fn box_eq(&self, other: &dyn std::any::Any) -> bool {
+        other.downcast_ref::<Self>().is_some_and(|a| self == a)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    /// a face has the props of the whole mesh, so setting its material or transformation changes the whole mesh.
+    /// Faces share the mesh's tag too, so a ray entering and leaving a mesh through different faces is entering and
+    /// leaving the same object.
+    fn props(&self) -> &ShapeProps {
+        self.mesh.props()
+    }
+
+    fn props_mut(&mut self) -> &mut ShapeProps {
+        self.mesh.props_mut()
+    }
+}
+
+impl Shape for MeshFace {
+    fn local_intersect(&self, r: Ray) -> Intersections {
+        match self
+            .mesh
+            .intersection
+            .intersect(self.mesh.corners(self.face), r)
+        {
+            Some(t) => Intersections::new(vec![Intersection::new(t, self.box_clone())]),
+            None => Intersections::EMPTY,
+        }
+    }
+
+    fn local_normal(&self, point: Point) -> Vector {
+        self.mesh.face_normal(self.face, point)
+    }
+
+    fn shared_material(&self) -> &Arc<Material> {
+        self.mesh.face_shared_material(self.face)
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::around(self.mesh.corners(self.face))
+    }
+}
+
+#[cfg(test)]
+mod test_mesh {
+    use crate::{
+        comparison::approx_eq,
+        primatives::{transformation::translation, tuple::Tuple},
+        P, V,
+    };
+
+    use super::*;
+
+    /// square is two triangles making the unit square in the xy plane facing -z.
+    fn square() -> Mesh {
+        Mesh::new(
+            vec![
+                P![0., 0., 0.],
+                P![0., 1., 0.],
+                P![1., 1., 0.],
+                P![1., 0., 0.],
+            ],
+            vec![[0, 2, 1], [0, 3, 2]],
+            None,
+            None,
+        )
+    }
+
+    fn hit_face(xs: &Intersections) -> usize {
+        xs[0]
+            .clone()
+            .object()
+            .as_any()
+            .downcast_ref::<MeshFace>()
+            .unwrap()
+            .face()
+    }
+
+    #[test]
+    fn test_intersect() {
+        let m = square();
+
+        let r = Ray::new(P![0.25, 0.75, -2.], V![0., 0., 1.]);
+        let xs = m.local_intersect(r);
+        assert_eq!(1, xs.len());
+        assert!(approx_eq(2., xs[0].t()));
+        assert_eq!(0, hit_face(&xs));
+
+        let r = Ray::new(P![0.75, 0.25, -2.], V![0., 0., 1.]);
+        assert_eq!(1, hit_face(&m.local_intersect(r)));
+
+        let r = Ray::new(P![1.5, 0.5, -2.], V![0., 0., 1.]);
+        assert_eq!(0, m.local_intersect(r).len());
+    }
+
+    #[test]
+    fn test_hits_share_buffers() {
+        let m = square();
+        let r = Ray::new(P![0.25, 0.75, -2.], V![0., 0., 1.]);
+        let hit = m.local_intersect(r)[0].clone().object();
+        let face = hit.as_any().downcast_ref::<MeshFace>().unwrap();
+        assert!(Arc::ptr_eq(&m.data, &face.mesh().data));
+    }
+
+    #[test]
+    fn test_normal() {
+        let mut m = square();
+        m.set_transform(translation(0., 0., 5.));
+        let r = Ray::new(P![0.25, 0.75, 0.], V![0., 0., 1.]);
+        let xs = m.intersect(r);
+        let hit = xs[0].clone().object();
+        assert_eq!(V![0., 0., -1.], hit.normal(P![0.25, 0.75, 5.]));
+
+        // with vertex normals the normal is blended across the face.
+        m.set_normals(vec![
+            V![0., 0., -1.],
+            V![0., 1., -1.].norm(),
+            V![1., 1., -1.].norm(),
+            V![1., 0., -1.].norm(),
+        ]);
+        let hit = m.intersect(r)[0].clone().object();
+        let n = hit.normal(P![0.25, 0.75, 5.]);
+        assert!(n.x() > 0. && n.y() > 0. && n.z() < 0.);
+    }
+
+    #[test]
+    fn test_face_materials() {
+        let mut m = square();
+        let red = Material::builder().ambient(1.).build().unwrap();
+        let blue = Material::builder().diffuse(0.1).build().unwrap();
+        m.set_face_materials(vec![red.clone(), blue.clone()], vec![1, 0]);
+
+        assert_eq!(&blue, m.face_material(0));
+        assert_eq!(&red, m.face_material(1));
+
+        let r = Ray::new(P![0.75, 0.25, -2.], V![0., 0., 1.]);
+        let hit = m.local_intersect(r)[0].clone().object();
+        assert_eq!(&red, hit.material());
+    }
+
+    #[test]
+    #[should_panic(expected = "mesh face refers to vertex 4 but there are only 4")]
+    fn test_bad_index() {
+        Mesh::new(vec![P![0., 0., 0.]; 4], vec![[0, 1, 4]], None, None);
+    }
+}
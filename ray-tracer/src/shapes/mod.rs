@@ -1,55 +1,312 @@
+pub mod compound;
+pub mod cone;
+pub mod cylinder;
+pub mod disc;
+pub mod group;
+pub mod heightfield;
 pub mod material;
+pub mod mesh;
 pub mod patterns;
 pub mod plane;
+pub mod quad;
+pub mod quadric;
+pub mod sdf;
+pub mod smooth_triangle;
 pub mod sphere;
+pub mod subdivision;
+pub mod torus;
+pub mod triangle;
 
 use core::fmt;
-use std::any::Any;
+use std::{
+    any::Any,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
 
 use crate::{
+    primatives::aabb::Aabb,
     primatives::point::Point,
     primatives::ray::Ray,
     primatives::{matrix::Matrix, vector::Vector},
-    shapes::material::Material,
+    shapes::{material::Material, triangle::TriangleIntersection},
     world::intersection::Intersections,
 };
 
-pub trait Shape: Any + fmt::Debug {
+/// NEXT_ID is the id the next tag made will be given.
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Tag identifies a shape. Every shape is given an id no other shape has when it is made, and keeps it when it is
+/// moved or cloned, so the copy of a shape held by an intersection has the id of the shape in the world.
+/// Shapes can also be given a name to make debugging easier. Tags are ignored when comparing shapes.
+#[derive(Clone, Debug)]
+pub struct Tag {
+    id: u64,
+    name: Option<Arc<str>>,
+}
+
+impl Tag {
+    pub fn new() -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            name: None,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        self.name = Some(name.into());
+    }
+
+    /// renew gives the tag a new id, keeping its name.
+    pub fn renew(&mut self) {
+        self.id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Default for Tag {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// ShapeProps are the parts every shape has: its transformation and the inverse of it, its material and its tag.
+/// Shapes hold them in one field so that a new shape only has to say where rays hit it and which way it faces.
+/// Tags are ignored when comparing them, like the shapes they belong to.
+#[derive(Clone, Debug, Default)]
+pub struct ShapeProps {
+    transform: Arc<Matrix>,
+    inverse_transform: Arc<Matrix>,
+    material: Arc<Material>,
+    /// pattern_transform is the inverse of the material pattern's transformation times the inverse of the
+    /// shape's, which takes a point in world space straight into pattern space. It is None when the material has
+    /// no pattern, or when the material was edited in place and it hasn't been worked out again.
+    pattern_transform: Option<Arc<Matrix>>,
+    tag: Tag,
+}
+
+impl ShapeProps {
+    pub fn new(transform: Option<Matrix>, material: Option<Material>) -> Self {
+        let transform = transform.unwrap_or_default();
+        let mut props = Self {
+            inverse_transform: Arc::new(
+                transform
+                    .inverse()
+                    .expect("trying to invert a matrix that cannot be inverted"),
+            ),
+            transform: Arc::new(transform),
+            material: Arc::new(material.unwrap_or_default()),
+            pattern_transform: None,
+            tag: Tag::default(),
+        };
+        props.combine_pattern_transform();
+        props
+    }
+
+    pub fn set_transform(&mut self, transform: Matrix) {
+        self.inverse_transform = Arc::new(
+            transform
+                .inverse()
+                .expect("trying to invert a matrix that cannot be inverted"),
+        );
+        self.transform = Arc::new(transform);
+        self.combine_pattern_transform();
+    }
+
+    /// combine_pattern_transform works out the pattern transform again, after the transformation or material changed.
+    pub(crate) fn combine_pattern_transform(&mut self) {
+        self.pattern_transform = self.material.pattern().map(|pattern| {
+            Arc::new(pattern.inverse_transformation().clone() * (*self.inverse_transform).clone())
+        });
+    }
+}
+
+impl PartialEq for ShapeProps {
+    fn eq(&self, other: &Self) -> bool {
+        self.transform == other.transform && self.material == other.material
+    }
+}
+
+/// ShapeBase is the part of a shape that is the same for every kind of shape. Shapes derive it with
+/// `#[derive(ShapeDerive)]` rather than writing it out.
+pub trait ShapeBase {
     fn box_clone(&self) -> BoxedShape;
     fn box_eq(&self, other: &dyn Any) -> bool;
     fn as_any(&self) -> &dyn Any;
+    fn props(&self) -> &ShapeProps;
+    fn props_mut(&mut self) -> &mut ShapeProps;
+}
+
+pub trait Shape: ShapeBase + Any + fmt::Debug + Send + Sync {
     fn local_intersect(&self, r: Ray) -> Intersections;
     fn local_normal(&self, point: Point) -> Vector;
-    fn material(&self) -> &Material;
-    fn transformation(&self) -> &Matrix;
-    fn inverse_transformation(&self) -> &Matrix;
+    /// bounds is the box around the shape in object space. Shapes that go on forever have infinite sides.
+    fn bounds(&self) -> Aabb;
+
+    /// shared_material is the shape's material behind an Arc, so that shapes given the same material and
+    /// every hit on them share one copy of it.
+    fn shared_material(&self) -> &Arc<Material> {
+        &self.props().material
+    }
+
+    fn set_shared_material(&mut self, material: Arc<Material>) {
+        let props = self.props_mut();
+        props.material = material;
+        props.combine_pattern_transform();
+    }
+
+    /// shared_material_mut lets the material be replaced or edited. The pattern transform can't follow an edit
+    /// made through it, so it is dropped until the shape is next put into a world.
+    fn shared_material_mut(&mut self) -> &mut Arc<Material> {
+        let props = self.props_mut();
+        props.pattern_transform = None;
+        &mut props.material
+    }
+
+    fn transformation(&self) -> &Matrix {
+        &self.props().transform
+    }
+
+    fn inverse_transformation(&self) -> &Matrix {
+        &self.props().inverse_transform
+    }
+
+    fn set_transformation(&mut self, transform: Matrix) {
+        self.props_mut().set_transform(transform);
+    }
+
+    /// pattern_transformation takes a point in world space straight into the space of the material's pattern,
+    /// so shading a patterned surface is one multiplication rather than one for the shape and one for the pattern.
+    fn pattern_transformation(&self) -> Option<&Matrix> {
+        self.props().pattern_transform.as_deref()
+    }
+
+    fn tag(&self) -> &Tag {
+        &self.props().tag
+    }
+
+    fn tag_mut(&mut self) -> &mut Tag {
+        &mut self.props_mut().tag
+    }
+
+    /// id tells shapes apart, even ones that are otherwise the same.
+    fn id(&self) -> u64 {
+        self.tag().id()
+    }
+
+    fn name(&self) -> Option<&str> {
+        self.tag().name()
+    }
+
+    fn set_name(&mut self, name: &str) {
+        self.tag_mut().set_name(name);
+    }
+
+    fn material(&self) -> &Material {
+        self.shared_material()
+    }
+
+    /// set_material gives the shape a material of its own, so boxed shapes can be changed after they are made.
+    fn set_material(&mut self, material: Material) {
+        self.set_shared_material(Arc::new(material));
+    }
+
+    /// material_mut lets the shape's material be edited in place. A material shared with other shapes is copied
+    /// first, so only this shape changes.
+    fn material_mut(&mut self) -> &mut Material {
+        Arc::make_mut(self.shared_material_mut())
+    }
+
+    /// set_transform replaces the shape's transformation, recomputing its inverse.
+    fn set_transform(&mut self, transform: Matrix) {
+        self.set_transformation(transform);
+    }
+
+    /// replace_material gives the shape the new material if it shares the old one. Groups pass this on to their
+    /// children, so a material used all over a scene can be changed everywhere at once.
+    fn replace_material(&mut self, old: &Arc<Material>, new: &Arc<Material>) {
+        if Arc::ptr_eq(self.shared_material(), old) {
+            self.set_shared_material(new.clone());
+        }
+    }
+
+    /// set_triangle_intersection chooses how the shape tests rays against its triangles. Only triangles and meshes
+    /// have any, and groups pass it on to their children.
+    fn set_triangle_intersection(&mut self, _intersection: TriangleIntersection) {}
+
+    /// is_planar is true for flat shapes such as floors, the only shapes whose reflections are blurred by a
+    /// material's reflection_blur.
+    fn is_planar(&self) -> bool {
+        false
+    }
+
+    /// world_bounds is the box around the shape once its transformation has been applied.
+    fn world_bounds(&self) -> Aabb {
+        self.bounds().transform(self.transformation())
+    }
+
+    /// transform_by applies another transformation on top of the one the shape already has.
+    /// Groups use this to pass their own transformation down to their children.
+    fn transform_by(&mut self, transform: &Matrix) {
+        self.set_transformation(transform.clone() * self.transformation().clone());
+    }
+
+    /// world_to_object converts a point in world space into the shape's own object space,
+    /// including any transformations inherited from the groups the shape is in.
+    fn world_to_object(&self, point: Point) -> Point {
+        self.inverse_transformation() * point
+    }
+
+    /// normal_to_world converts a normal in object space back into world space and normalises it.
+    fn normal_to_world(&self, normal: Vector) -> Vector {
+        (self.inverse_transformation().transpose() * normal).norm()
+    }
 
     /// intersect transforms the ray by the shapes held transformation parameter
     /// and then calls a local intersection function.
     fn intersect(&self, r: Ray) -> Intersections {
         self.local_intersect(r.transform(self.inverse_transformation()))
     }
+    /// shading_point is where lighting and shadow rays should start from for a hit at the given world point.
+    /// For most shapes this is the hit itself, but faceted shapes can move it to where a smooth surface would be.
+    fn shading_point(&self, point: Point) -> Point {
+        point
+    }
+
     /// normal transforms the given point by the shapes transformation matrix and calls the normal function for the shape with this transformed value.
     /// Then re-transforms the returned normal and normalises it
     fn normal(&self, point: Point) -> Vector {
-        let object_normal = self.local_normal(self.inverse_transformation().clone() * point);
-        let world_normal = self.inverse_transformation().transpose() * object_normal;
-        world_normal.norm()
+        let object_normal = self.local_normal(self.world_to_object(point));
+        self.normal_to_world(object_normal)
     }
 }
 
-pub type BoxedShape = Box<dyn Shape>;
+/// BoxedShape is a handle to a shape that can be shared. Cloning it copies a pointer rather than the shape, so
+/// intersections and shading can hold on to the shape that was hit cheaply.
+pub type BoxedShape = Arc<dyn Shape>;
 
-impl Clone for BoxedShape {
-    fn clone(&self) -> Self {
-        self.box_clone()
+impl PartialEq for dyn Shape {
+    fn eq(&self, other: &dyn Shape) -> bool {
+        self.box_eq(other.as_any())
     }
 }
 
-impl PartialEq for BoxedShape {
-    fn eq(&self, other: &BoxedShape) -> bool {
-        self.box_eq(other.as_any())
+/// make_mut lets a shape behind a handle be changed. A shape shared with other handles is copied first, so only
+/// this handle sees the change.
+pub fn make_mut(shape: &mut BoxedShape) -> &mut dyn Shape {
+    if Arc::get_mut(shape).is_none() {
+        *shape = shape.box_clone();
     }
+    Arc::get_mut(shape).expect("a copied shape is not shared")
 }
 
 #[cfg(test)]
@@ -57,63 +314,34 @@ mod test_shapes {
     use std::f64::consts::FRAC_1_SQRT_2;
 
     use crate::{
+        primatives::tuple::Tuple,
         primatives::{
             point::ORIGIN,
             transformation::{scaling, translation},
             vector::ZERO,
         },
         shapes::material::Material,
-        Tuple, P, V,
+        P, V,
     };
+    use shape_derive::ShapeDerive;
 
     use super::*;
 
     /// TestShape is an example implementation of the Shape trait.
-    #[derive(Debug, Clone)]
+    #[derive(Debug, Clone, PartialEq, ShapeDerive)]
+    #[shape(new)]
     struct TestShape {
-        pub transformation: Matrix,
-        pub material: Material,
-        pub inverse_transformation: Matrix,
+        props: ShapeProps,
     }
     static mut SAVED_RAY: Ray = Ray {
         origin: ORIGIN,
         direction: ZERO,
     };
-    impl TestShape {
-        fn new(transform: Option<Matrix>, material: Option<Material>) -> Self {
-            Self {
-                transformation: transform.clone().unwrap_or_default(),
-                material: material.unwrap_or_default(),
-                inverse_transformation: transform
-                    .unwrap_or_default()
-                    .inverse()
-                    .expect("trying to invert a matrix that cannot be inverted"),
-            }
-        }
-    }
-
-    impl PartialEq for TestShape {
-        fn eq(&self, other: &Self) -> bool {
-            self.transformation == other.transformation && self.material == other.material
-        }
-    }
 
     impl Shape for TestShape {
-        fn box_clone(&self) -> BoxedShape {
-            Box::new(self.clone())
-        }
-
-        fn box_eq(&self, other: &dyn Any) -> bool {
-            other.downcast_ref::<Self>().map_or(false, |a| self == a)
-        }
-
-        fn as_any(&self) -> &dyn Any {
-            self
-        }
-
         fn intersect(&self, r: Ray) -> Intersections {
             unsafe {
-                SAVED_RAY = r.transform(&self.inverse_transformation);
+                SAVED_RAY = r.transform(self.inverse_transformation());
                 self.local_intersect(SAVED_RAY)
             }
         }
@@ -126,27 +354,19 @@ mod test_shapes {
             Vector::new(point.x(), point.y(), point.z())
         }
 
-        fn material(&self) -> &Material {
-            &self.material
-        }
-
-        fn transformation(&self) -> &Matrix {
-            &self.transformation
-        }
-
-        fn inverse_transformation(&self) -> &Matrix {
-            &self.inverse_transformation
+        fn bounds(&self) -> Aabb {
+            Aabb::new(P![-1., -1., -1.], P![1., 1., 1.])
         }
     }
 
     #[test]
     fn test_transformation() {
         let s = TestShape::new(None, None);
-        assert_eq!(s.transformation, Matrix::identity_matrix());
+        assert_eq!(s.transformation(), &Matrix::identity_matrix());
 
         let transform = translation(2., 3., 4.);
         let s = TestShape::new(Some(transform.clone()), None);
-        assert_eq!(s.transformation, transform);
+        assert_eq!(s.transformation(), &transform);
     }
 
     fn test_material() {
@@ -166,10 +386,56 @@ mod test_shapes {
 
         let _xs = s.intersect(r);
 
-        unsafe {
-            assert_eq!(SAVED_RAY.origin(), P![0., 0., -2.5]);
-            assert_eq!(SAVED_RAY.direction(), V![0., 0., 1.]);
-        }
+        let saved_ray = unsafe { SAVED_RAY };
+        assert_eq!(saved_ray.origin(), P![0., 0., -2.5]);
+        assert_eq!(saved_ray.direction(), V![0., 0., 1.]);
+    }
+
+    #[test]
+    fn test_tags() {
+        let a = TestShape::new(None, None);
+        let b = TestShape::new(None, None);
+        assert_ne!(a.id(), b.id());
+        // tags don't stop otherwise identical shapes being equal.
+        assert_eq!(a, b);
+
+        let mut boxed = a.box_clone();
+        assert_eq!(a.id(), boxed.id());
+        assert_eq!(None, boxed.name());
+        make_mut(&mut boxed).set_name("floor");
+        make_mut(&mut boxed).set_transform(translation(1., 0., 0.));
+        assert_eq!(Some("floor"), boxed.name());
+        assert_eq!(a.id(), boxed.id());
+
+        make_mut(&mut boxed).tag_mut().renew();
+        assert_ne!(a.id(), boxed.id());
+        assert_eq!(Some("floor"), boxed.name());
+    }
+
+    #[test]
+    fn test_edit_boxed_shape() {
+        let mut handle: BoxedShape = Arc::new(TestShape::new(None, None));
+        // changing a shape through a handle that is shared copies it first, leaving the other handle alone.
+        let copy = handle.clone();
+        let s = make_mut(&mut handle);
+        s.set_transform(translation(1., 2., 3.));
+        assert_eq!(&translation(1., 2., 3.), s.transformation());
+        assert_eq!(&translation(-1., -2., -3.), s.inverse_transformation());
+
+        let m = Material::builder().ambient(1.).build().unwrap();
+        s.set_material(m.clone());
+        assert_eq!(&m, s.material());
+
+        // editing a shared material in place leaves the other shapes sharing it alone.
+        let shared = Arc::new(Material::default());
+        let mut other = TestShape::new(None, None);
+        other.set_shared_material(shared.clone());
+        s.set_shared_material(shared.clone());
+        s.material_mut().set_ambient(0.5);
+        assert_eq!(0.5, s.material().ambient());
+        assert_eq!(&Material::default(), other.material());
+        assert_eq!(&Matrix::identity_matrix(), copy.transformation());
+        assert_eq!(copy.id(), handle.id());
     }
 
     #[test]
@@ -0,0 +1,130 @@
+use crate::primatives::{color::Color, matrix::Matrix, point::Point};
+
+use super::{BoxedPattern, Pattern};
+use pattern_derive::PatternDerive;
+
+/// BlendMode is how the colors of the two patterns in a blend are combined.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum BlendMode {
+    /// Average is halfway between the two colors.
+    #[default]
+    Average,
+    /// Multiply darkens one pattern by the other, like laying one slide on top of another.
+    Multiply,
+    /// Screen lightens one pattern by the other, the opposite of multiply.
+    Screen,
+    /// Weighted moves from the first color towards the second by the weight, from 0 to 1.
+    Weighted(f64),
+}
+
+impl BlendMode {
+    pub fn blend(&self, a: Color, b: Color) -> Color {
+        match *self {
+            BlendMode::Average => (a + b) * 0.5,
+            BlendMode::Multiply => a * b,
+            BlendMode::Screen => Color::WHITE - (Color::WHITE - a) * (Color::WHITE - b),
+            BlendMode::Weighted(weight) => a + (b - a) * weight,
+        }
+    }
+}
+
+/// BlendPattern layers two patterns over each other everywhere, mixing their colors with a blend mode,
+/// for example to put noise over checkers.
+#[derive(Debug, Clone, PartialEq, PatternDerive)]
+pub struct BlendPattern {
+    a: BoxedPattern,
+    b: BoxedPattern,
+    mode: BlendMode,
+    transform: Matrix,
+    inverse_transform: Matrix,
+}
+
+impl BlendPattern {
+    pub fn new(
+        a: impl Into<BoxedPattern>,
+        b: impl Into<BoxedPattern>,
+        mode: BlendMode,
+        transform: Option<Matrix>,
+    ) -> Self {
+        let mut pattern = Self {
+            a: a.into(),
+            b: b.into(),
+            mode: BlendMode::default(),
+            transform: transform.clone().unwrap_or_default(),
+            inverse_transform: transform
+                .unwrap_or_default()
+                .inverse()
+                .expect("trying to invert a matrix that cannot be inverted"),
+        };
+        pattern.set_blend_mode(mode);
+        pattern
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.mode
+    }
+
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        if let BlendMode::Weighted(weight) = mode {
+            assert!(
+                (0. ..=1.).contains(&weight),
+                "a blend weight must be between 0 and 1"
+            );
+        }
+        self.mode = mode;
+    }
+}
+
+impl Pattern for BlendPattern {
+    fn local_color_at(&self, pattern_point: Point) -> Color {
+        self.mode.blend(
+            self.a.nested_color_at(pattern_point),
+            self.b.nested_color_at(pattern_point),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_blend {
+    use crate::{
+        primatives::tuple::Tuple,
+        shapes::patterns::{checkered::CheckeredPattern, striped::StripePattern, PatternBase},
+        C, P,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_blend_modes() {
+        let (a, b) = (C![0.2, 0.5, 1.], C![0.5, 0.5, 0.]);
+        assert_eq!(C![0.35, 0.5, 0.5], BlendMode::Average.blend(a, b));
+        assert_eq!(C![0.1, 0.25, 0.], BlendMode::Multiply.blend(a, b));
+        assert_eq!(C![0.6, 0.75, 1.], BlendMode::Screen.blend(a, b));
+        assert_eq!(C![0.275, 0.5, 0.75], BlendMode::Weighted(0.25).blend(a, b));
+        assert_eq!(a, BlendMode::Weighted(0.).blend(a, b));
+        assert_eq!(b, BlendMode::Weighted(1.).blend(a, b));
+    }
+
+    #[test]
+    fn test_blend_patterns() {
+        // stripes multiplied over checkers black out every other unit along x.
+        let checkers = CheckeredPattern::new(Color::WHITE, C![0.5, 0.5, 0.5], None);
+        let stripes = StripePattern::new(Color::WHITE, Color::BLACK, None);
+        let p = BlendPattern::new(
+            checkers.box_clone(),
+            stripes.box_clone(),
+            BlendMode::Multiply,
+            None,
+        );
+
+        assert_eq!(Color::WHITE, p.local_color_at(P![0.5, 0., 0.5]));
+        assert_eq!(Color::BLACK, p.local_color_at(P![1.5, 0., 0.5]));
+        assert_eq!(C![0.5, 0.5, 0.5], p.local_color_at(P![0.5, 0., 1.5]));
+    }
+
+    #[test]
+    #[should_panic(expected = "a blend weight must be between 0 and 1")]
+    fn test_bad_weight() {
+        BlendPattern::new(Color::WHITE, Color::BLACK, BlendMode::Weighted(1.5), None);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{
+    primatives::{
+        point::Point,
+        tuple::Tuple,
+        vector::{dot, Vector},
+    },
+    shapes::BoxedShape,
+    V,
+};
+
+use super::Pattern;
+
+/// SAMPLES is how many points along each side of the footprint the bumps are sampled at.
+const SAMPLES: usize = 8;
+
+/// SLOPE_STEP is how far apart, in pattern space, the heights are taken to find the slope of the bumps at a point.
+const SLOPE_STEP: f64 = 1e-4;
+
+/// NORMAL_STEP is how far apart, in world space, the heights of a bump map are taken to find its slope at a point.
+const NORMAL_STEP: f64 = 1e-4;
+
+/// perturb_normal tilts the normal of the object at a world point as if the surface were raised by the brightness of
+/// the bump map times strength. Only the slope of the bumps across the surface counts, so they change how the
+/// surface is shaded without moving it.
+pub fn perturb_normal(
+    bump_map: &dyn Pattern,
+    object: &BoxedShape,
+    point: Point,
+    normal: Vector,
+    strength: f64,
+) -> Vector {
+    let height = |p: Point| strength * bump_map.at_shape(object.clone(), p).luminance();
+    let slope = |axis: Vector| {
+        (height(point + axis * NORMAL_STEP) - height(point - axis * NORMAL_STEP))
+            / (2. * NORMAL_STEP)
+    };
+    let gradient = V![
+        slope(V![1., 0., 0.]),
+        slope(V![0., 1., 0.]),
+        slope(V![0., 0., 1.])
+    ];
+
+    // the part of the slope along the normal would only move the surface, not turn it.
+    let across = gradient - normal * dot(gradient, normal);
+    (normal - across).norm()
+}
+
+/// bump_roughness estimates how rough a bump pattern looks once a single pixel covers a square of pattern space
+/// footprint units wide around the centre, treating the brightness of the pattern times strength as the height of
+/// the surface. Up close the bumps under a pixel all face the same way and the roughness is 0. Further away they
+/// face many ways and their average normal gets shorter, which is turned into the spread of the slopes under the
+/// pixel (Toksvig's approximation). Shading distant bumps as this much rougher stops their highlights sparkling.
+pub fn bump_roughness(pattern: &dyn Pattern, centre: Point, footprint: f64, strength: f64) -> f64 {
+    let height = |x: f64, z: f64| {
+        strength
+            * pattern
+                .local_color_at(Point::new(x, centre.y(), z))
+                .luminance()
+    };
+
+    let cell = footprint / SAMPLES as f64;
+    let start = |c: f64| c - footprint / 2. + cell / 2.;
+    let mut sum = Vector::new(0., 0., 0.);
+    for i in 0..SAMPLES {
+        for j in 0..SAMPLES {
+            let x = start(centre.x()) + i as f64 * cell;
+            let z = start(centre.z()) + j as f64 * cell;
+            let dx = (height(x + SLOPE_STEP, z) - height(x - SLOPE_STEP, z)) / (2. * SLOPE_STEP);
+            let dz = (height(x, z + SLOPE_STEP) - height(x, z - SLOPE_STEP)) / (2. * SLOPE_STEP);
+            sum = sum + Vector::new(-dx, 1., -dz).norm();
+        }
+    }
+
+    let length = sum.magnitude() / (SAMPLES * SAMPLES) as f64;
+    ((1. - length) / length).max(0.).sqrt()
+}
+
+/// filtered_shininess lowers a material's shininess to spread its highlight over bumps of the given roughness,
+/// as if the bumps under a pixel were one rougher surface.
+pub fn filtered_shininess(shininess: f64, roughness: f64) -> f64 {
+    shininess / (1. + shininess * roughness * roughness)
+}
+
+#[cfg(test)]
+mod test_bump {
+    use crate::{
+        comparison::approx_eq,
+        primatives::{color::Color, point::ORIGIN},
+        shapes::{
+            patterns::{gradient::GraidentPattern, AddressMode},
+            plane::Plane,
+            ShapeBase,
+        },
+        P,
+    };
+
+    use super::*;
+
+    /// ridges rise and fall by one unit every unit along x.
+    fn ridges() -> GraidentPattern {
+        let mut p = GraidentPattern::new(Color::BLACK, Color::WHITE, None);
+        p.set_address_mode(AddressMode::Mirror);
+        p
+    }
+
+    #[test]
+    fn test_flat_pattern() {
+        let flat = GraidentPattern::new(Color::WHITE, Color::WHITE, None);
+        assert!(approx_eq(0., bump_roughness(&flat, ORIGIN, 4., 1.)));
+    }
+
+    #[test]
+    fn test_bump_roughness() {
+        // under a small pixel the slope is the same everywhere.
+        let near = bump_roughness(&ridges(), P![0.5, 0., 0.5], 0.1, 1.);
+        assert!(approx_eq(0., near));
+
+        // a wide pixel sees as many slopes going up as down, averaging to a normal 1/√2 long.
+        let far = bump_roughness(&ridges(), ORIGIN, 2., 1.);
+        assert!(approx_eq((2_f64.sqrt() - 1.).sqrt(), far));
+
+        // taller bumps are rougher.
+        assert!(bump_roughness(&ridges(), ORIGIN, 2., 2.) > far);
+    }
+
+    #[test]
+    fn test_perturb_normal() {
+        let plane = Plane::default().box_clone();
+        let up = V![0., 1., 0.];
+
+        // a flat bump map leaves the normal alone.
+        let flat = GraidentPattern::new(Color::WHITE, Color::WHITE, None);
+        assert_eq!(up, perturb_normal(&flat, &plane, P![0.3, 0., 0.3], up, 1.));
+
+        // on the rising side of a ridge the normal leans back down the slope, and the other way on the falling side.
+        let rising = perturb_normal(&ridges(), &plane, P![0.5, 0., 0.5], up, 0.5);
+        assert!(approx_eq(1., rising.magnitude()));
+        assert!(rising.x() < 0. && approx_eq(0., rising.z()));
+        let falling = perturb_normal(&ridges(), &plane, P![1.5, 0., 0.5], up, 0.5);
+        assert!(approx_eq(-rising.x(), falling.x()));
+
+        // stronger bumps tilt the normal further.
+        let strong = perturb_normal(&ridges(), &plane, P![0.5, 0., 0.5], up, 2.);
+        assert!(strong.x() < rising.x());
+    }
+
+    #[test]
+    fn test_filtered_shininess() {
+        assert_eq!(200., filtered_shininess(200., 0.));
+        assert!(approx_eq(200. / 3., filtered_shininess(200., 0.1)));
+    }
+}
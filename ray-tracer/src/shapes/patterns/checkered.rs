@@ -1,21 +1,28 @@
 use crate::primatives::{color::Color, matrix::Matrix, tuple::Tuple};
 
-use super::Pattern;
+use super::{BoxedPattern, Pattern};
+use pattern_derive::PatternDerive;
 
-/// CheckeredPattern is a 3D chess board pattern.
-#[derive(Debug, Clone, PartialEq)]
+/// CheckeredPattern is a 3D chess board pattern. Each cell is a cube of `cell_size`, one unit by default.
+#[derive(Debug, Clone, PartialEq, PatternDerive)]
 pub struct CheckeredPattern {
-    a: Color,
-    b: Color,
+    a: BoxedPattern,
+    b: BoxedPattern,
+    cell_size: f64,
     transform: Matrix,
     inverse_transform: Matrix,
 }
 
 impl CheckeredPattern {
-    pub fn new(a: Color, b: Color, transform: Option<Matrix>) -> Self {
+    pub fn new(
+        a: impl Into<BoxedPattern>,
+        b: impl Into<BoxedPattern>,
+        transform: Option<Matrix>,
+    ) -> Self {
         Self {
-            a,
-            b,
+            a: a.into(),
+            b: b.into(),
+            cell_size: 1.,
             transform: transform.clone().unwrap_or_default(),
             inverse_transform: transform
                 .unwrap_or_default()
@@ -23,49 +30,58 @@ impl CheckeredPattern {
                 .expect("trying to invert a matrix that cannot be inverted"),
         }
     }
+
+    pub fn set_cell_size(&mut self, cell_size: f64) {
+        assert!(
+            cell_size > 0.,
+            "checker cell size must be greater than zero"
+        );
+        self.cell_size = cell_size;
+    }
+
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
 }
 
 impl Pattern for CheckeredPattern {
     fn local_color_at(&self, pattern_point: crate::primatives::point::Point) -> Color {
-        if (pattern_point.x().floor() + pattern_point.y().floor() + pattern_point.z().floor()) % 2.0
-            == 0.0
-        {
-            self.a
+        let cell = pattern_point / self.cell_size;
+        if (cell.x().floor() + cell.y().floor() + cell.z().floor()) % 2.0 == 0.0 {
+            self.a.nested_color_at(pattern_point)
         } else {
-            self.b
+            self.b.nested_color_at(pattern_point)
         }
     }
-
-    fn set_transformation(&mut self, transform: crate::primatives::matrix::Matrix) {
-        self.transform = transform.clone();
-        self.inverse_transform = transform
-            .inverse()
-            .expect("trying to invert a matrix that cannot be inverted");
-    }
-
-    fn inverse_transformation(&self) -> &crate::primatives::matrix::Matrix {
-        &self.inverse_transform
-    }
-
-    fn box_clone(&self) -> super::BoxedPattern {
-        Box::new(self.clone())
-    }
-
-    fn box_eq(&self, other: &dyn std::any::Any) -> bool {
-        other.downcast_ref::<Self>().map_or(false, |a| self == a)
-    }
-
-    fn as_any(&self) -> &dyn std::any::Any {
-        self
-    }
 }
 
 #[cfg(test)]
 mod test_checkered_pattern {
-    use crate::{primatives::color::Color, shapes::patterns::Pattern, Tuple, P};
+    use crate::{
+        primatives::color::Color,
+        primatives::{transformation::scaling, tuple::Tuple},
+        shapes::patterns::{
+            gradient::GraidentPattern, striped::StripePattern, Pattern, PatternBase,
+        },
+        C, P,
+    };
 
     use super::*;
 
+    #[test]
+    fn test_nested_patterns() {
+        // every other square is striped, with stripes a quarter of a square wide.
+        let stripes = StripePattern::new(Color::WHITE, Color::BLACK, Some(scaling(0.25, 1., 1.)));
+        let p = CheckeredPattern::new(C![1., 0., 0.], stripes.box_clone(), None);
+        assert_eq!(C![1., 0., 0.], p.local_color_at(P![0.5, 0.5, 0.5]));
+        assert_eq!(Color::WHITE, p.local_color_at(P![1.1, 0.5, 0.5]));
+        assert_eq!(Color::BLACK, p.local_color_at(P![1.3, 0.5, 0.5]));
+
+        // gradients blend between whatever their ends are.
+        let g = GraidentPattern::new(Color::BLACK, p.box_clone(), None);
+        assert_eq!(C![0.5, 0., 0.], g.local_color_at(P![0.5, 0.5, 0.5]));
+    }
+
     #[test]
     fn test_checkered() {
         let p = CheckeredPattern::new(Color::WHITE, Color::BLACK, None);
@@ -83,4 +99,15 @@ mod test_checkered_pattern {
         assert_eq!(Color::WHITE, p.local_color_at(P![0., 0., 0.99]));
         assert_eq!(Color::BLACK, p.local_color_at(P![0., 0., 1.01]));
     }
+
+    #[test]
+    fn test_cell_size() {
+        let mut p = CheckeredPattern::new(Color::WHITE, Color::BLACK, None);
+        p.set_cell_size(2.);
+
+        assert_eq!(Color::WHITE, p.local_color_at(P![1.5, 0., 0.]));
+        assert_eq!(Color::BLACK, p.local_color_at(P![2.5, 0., 0.]));
+        assert_eq!(Color::BLACK, p.local_color_at(P![0., 0., 2.01]));
+        assert_eq!(Color::WHITE, p.local_color_at(P![2.5, 2.5, 0.]));
+    }
 }
@@ -0,0 +1,96 @@
+use crate::primatives::{color::Color, matrix::Matrix, point::Point, tuple::Tuple};
+
+use super::{BoxedPattern, Pattern};
+use pattern_derive::PatternDerive;
+
+/// DotsPattern is balls of a on a background of b, one centred on every whole numbered point, so a plane shows a
+/// grid of polka dots and a scaled sphere is covered in spots. The radius is how big each ball is, with balls wider
+/// than 0.5 running into their neighbours.
+#[derive(Debug, Clone, PartialEq, PatternDerive)]
+pub struct DotsPattern {
+    a: BoxedPattern,
+    b: BoxedPattern,
+    radius: f64,
+    transform: Matrix,
+    inverse_transform: Matrix,
+}
+
+impl DotsPattern {
+    pub fn new(
+        a: impl Into<BoxedPattern>,
+        b: impl Into<BoxedPattern>,
+        radius: f64,
+        transform: Option<Matrix>,
+    ) -> Self {
+        let mut pattern = Self {
+            a: a.into(),
+            b: b.into(),
+            radius: 0.,
+            transform: transform.clone().unwrap_or_default(),
+            inverse_transform: transform
+                .unwrap_or_default()
+                .inverse()
+                .expect("trying to invert a matrix that cannot be inverted"),
+        };
+        pattern.set_radius(radius);
+        pattern
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn set_radius(&mut self, radius: f64) {
+        assert!(radius > 0., "the radius of a dot must be greater than 0");
+        self.radius = radius;
+    }
+}
+
+impl Pattern for DotsPattern {
+    fn local_color_at(&self, pattern_point: Point) -> Color {
+        let offset = |v: f64| v - v.round();
+        let distance = (offset(pattern_point.x()).powi(2)
+            + offset(pattern_point.y()).powi(2)
+            + offset(pattern_point.z()).powi(2))
+        .sqrt();
+        if distance < self.radius {
+            self.a.nested_color_at(pattern_point)
+        } else {
+            self.b.nested_color_at(pattern_point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_dots {
+    use crate::P;
+
+    use super::*;
+
+    #[test]
+    fn test_dots() {
+        let p = DotsPattern::new(Color::WHITE, Color::BLACK, 0.25, None);
+
+        // inside a ball around every whole numbered point.
+        assert_eq!(Color::WHITE, p.local_color_at(P![0., 0., 0.]));
+        assert_eq!(Color::WHITE, p.local_color_at(P![2.1, 0., -3.1]));
+        assert_eq!(Color::WHITE, p.local_color_at(P![0.9, -1.1, 0.1]));
+        // between the balls.
+        assert_eq!(Color::BLACK, p.local_color_at(P![0.5, 0., 0.]));
+        assert_eq!(Color::BLACK, p.local_color_at(P![0.2, 0.2, 0.]));
+        assert_eq!(Color::BLACK, p.local_color_at(P![-1.5, 0., 4.5]));
+    }
+
+    #[test]
+    fn test_radius() {
+        let mut p = DotsPattern::new(Color::WHITE, Color::BLACK, 0.25, None);
+        p.set_radius(0.3);
+        assert_eq!(Color::WHITE, p.local_color_at(P![0.2, 0.2, 0.]));
+    }
+
+    #[test]
+    #[should_panic(expected = "the radius of a dot must be greater than 0")]
+    fn test_bad_radius() {
+        DotsPattern::new(Color::WHITE, Color::BLACK, 0., None);
+    }
+}
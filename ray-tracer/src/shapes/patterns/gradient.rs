@@ -1,21 +1,63 @@
+use std::f64::consts::PI;
+
 use crate::primatives::{color::Color, matrix::Matrix, tuple::Tuple};
 
-use super::Pattern;
+use super::{AddressMode, BoxedPattern, Pattern};
+use pattern_derive::PatternDerive;
 
-/// Gradient Pattern linearly interpolates between two colors.
-#[derive(Debug, Clone, PartialEq)]
+/// Gradient Pattern interpolates between colors placed at stops along x, such as the two given to `new` at 0 and 1.
+/// The address mode decides what happens outside of `0 <= x < 1`, by default the gradient repeats every unit.
+/// Before the first stop and after the last the gradient holds that stop's color.
+#[derive(Debug, Clone, PartialEq, PatternDerive)]
 pub struct GraidentPattern {
-    a: Color,
-    b: Color,
+    stops: Vec<(f64, BoxedPattern)>,
+    easing: Easing,
+    mode: AddressMode,
     transform: Matrix,
     inverse_transform: Matrix,
 }
 
+/// Easing shapes how a gradient moves from one stop's color to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Easing {
+    /// Linear changes at the same rate all the way between stops.
+    #[default]
+    Linear,
+    /// Smoothstep starts and finishes slowly, so stops don't show as creases.
+    Smoothstep,
+    /// Cosine follows half a cosine wave, a little softer than smoothstep.
+    Cosine,
+}
+
+impl Easing {
+    /// apply maps how far along a segment a point is, from 0 to 1, to how much of the next color it takes.
+    pub fn apply(&self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::Smoothstep => t * t * (3. - 2. * t),
+            Easing::Cosine => (1. - (t * PI).cos()) / 2.,
+        }
+    }
+}
+
 impl GraidentPattern {
-    pub fn new(a: Color, b: Color, transform: Option<Matrix>) -> Self {
+    pub fn new(
+        a: impl Into<BoxedPattern>,
+        b: impl Into<BoxedPattern>,
+        transform: Option<Matrix>,
+    ) -> Self {
+        Self::with_stops(vec![(0., a.into()), (1., b.into())], transform)
+    }
+
+    /// with_stops makes a gradient through any number of colors, each given with its position between 0 and 1.
+    /// Two stops at the same position make a hard edge. It panics if there are no stops.
+    pub fn with_stops(mut stops: Vec<(f64, BoxedPattern)>, transform: Option<Matrix>) -> Self {
+        assert!(!stops.is_empty(), "a gradient needs at least one stop");
+        stops.sort_by(|(a, _), (b, _)| a.total_cmp(b));
         Self {
-            a,
-            b,
+            stops,
+            easing: Easing::default(),
+            mode: AddressMode::default(),
             transform: transform.clone().unwrap_or_default(),
             inverse_transform: transform
                 .unwrap_or_default()
@@ -23,42 +65,48 @@ impl GraidentPattern {
                 .expect("trying to invert a matrix that cannot be inverted"),
         }
     }
-}
 
-impl Pattern for GraidentPattern {
-    fn local_color_at(&self, pattern_point: crate::primatives::point::Point) -> Color {
-        let distance = self.b - self.a;
-        let fraction = pattern_point.x() - pattern_point.x().floor();
-        self.a + distance * fraction
+    pub fn stops(&self) -> &[(f64, BoxedPattern)] {
+        &self.stops
     }
 
-    fn set_transformation(&mut self, transform: crate::primatives::matrix::Matrix) {
-        self.transform = transform.clone();
-        self.inverse_transform = transform
-            .inverse()
-            .expect("trying to invert a matrix that cannot be inverted");
+    pub fn easing(&self) -> Easing {
+        self.easing
     }
 
-    fn inverse_transformation(&self) -> &crate::primatives::matrix::Matrix {
-        &self.inverse_transform
+    pub fn set_easing(&mut self, easing: Easing) {
+        self.easing = easing;
     }
 
-    fn box_clone(&self) -> super::BoxedPattern {
-        Box::new(self.clone())
+    pub fn address_mode(&self) -> AddressMode {
+        self.mode
     }
 
-    fn box_eq(&self, other: &dyn std::any::Any) -> bool {
-        other.downcast_ref::<Self>().map_or(false, |a| self == a)
+    pub fn set_address_mode(&mut self, mode: AddressMode) {
+        self.mode = mode;
     }
+}
 
-    fn as_any(&self) -> &dyn std::any::Any {
-        self
+impl Pattern for GraidentPattern {
+    fn local_color_at(&self, pattern_point: crate::primatives::point::Point) -> Color {
+        let t = self.mode.apply(pattern_point.x());
+        let next = self.stops.partition_point(|(position, _)| *position <= t);
+        if next == 0 || next == self.stops.len() {
+            let (_, stop) = &self.stops[next.saturating_sub(1)];
+            return stop.nested_color_at(pattern_point);
+        }
+        let ((start, a), (end, b)) = (&self.stops[next - 1], &self.stops[next]);
+        let (a, b) = (
+            a.nested_color_at(pattern_point),
+            b.nested_color_at(pattern_point),
+        );
+        a + (b - a) * self.easing.apply((t - start) / (end - start))
     }
 }
 
 #[cfg(test)]
 mod test_gradient {
-    use crate::{Tuple, C, P};
+    use crate::{comparison::approx_eq, primatives::tuple::Tuple, C, P};
 
     use super::*;
 
@@ -71,4 +119,68 @@ mod test_gradient {
         assert_eq!(C![0.5, 0.5, 0.5], p.local_color_at(P![0.5, 0., 0.]));
         assert_eq!(C![0.25, 0.25, 0.25], p.local_color_at(P![0.75, 0., 0.]));
     }
+
+    #[test]
+    fn test_address_modes() {
+        let mut p = GraidentPattern::new(Color::WHITE, Color::BLACK, None);
+        assert_eq!(AddressMode::Repeat, p.address_mode());
+        assert_eq!(C![0.75, 0.75, 0.75], p.local_color_at(P![1.25, 0., 0.]));
+
+        p.set_address_mode(AddressMode::Clamp);
+        assert_eq!(Color::BLACK, p.local_color_at(P![1.25, 0., 0.]));
+        assert_eq!(Color::WHITE, p.local_color_at(P![-3., 0., 0.]));
+
+        p.set_address_mode(AddressMode::Mirror);
+        assert_eq!(C![0.25, 0.25, 0.25], p.local_color_at(P![1.25, 0., 0.]));
+        assert_eq!(C![0.75, 0.75, 0.75], p.local_color_at(P![-0.25, 0., 0.]));
+    }
+
+    #[test]
+    fn test_stops() {
+        let red = Color::new(1., 0., 0.);
+        let p = GraidentPattern::with_stops(
+            vec![
+                (1., Color::BLACK.into()),
+                (0.2, Color::WHITE.into()),
+                (0.6, red.into()),
+            ],
+            None,
+        );
+        assert_eq!(0.2, p.stops()[0].0);
+
+        assert_eq!(Color::WHITE, p.local_color_at(P![0.1, 0., 0.]));
+        assert_eq!(C![1., 0.5, 0.5], p.local_color_at(P![0.4, 0., 0.]));
+        assert_eq!(red, p.local_color_at(P![0.6, 0., 0.]));
+        assert_eq!(C![0.5, 0., 0.], p.local_color_at(P![0.8, 0., 0.]));
+
+        // stops at the same position make a hard edge.
+        let p = GraidentPattern::with_stops(
+            vec![
+                (0.5, Color::WHITE.into()),
+                (0.5, Color::BLACK.into()),
+                (1., Color::BLACK.into()),
+            ],
+            None,
+        );
+        assert_eq!(Color::WHITE, p.local_color_at(P![0.49, 0., 0.]));
+        assert_eq!(Color::BLACK, p.local_color_at(P![0.5, 0., 0.]));
+    }
+
+    #[test]
+    fn test_easing() {
+        for easing in [Easing::Linear, Easing::Smoothstep, Easing::Cosine] {
+            assert_eq!(0., easing.apply(0.));
+            assert!(approx_eq(0.5, easing.apply(0.5)));
+            assert!(approx_eq(1., easing.apply(1.)));
+        }
+        assert!(approx_eq(0.15625, Easing::Smoothstep.apply(0.25)));
+        assert!(Easing::Cosine.apply(0.25) < 0.25);
+
+        let mut p = GraidentPattern::new(Color::WHITE, Color::BLACK, None);
+        p.set_easing(Easing::Smoothstep);
+        assert_eq!(
+            C![0.84375, 0.84375, 0.84375],
+            p.local_color_at(P![0.25, 0., 0.])
+        );
+    }
 }
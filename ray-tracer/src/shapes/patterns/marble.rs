@@ -0,0 +1,116 @@
+use std::f64::consts::PI;
+
+use crate::primatives::{color::Color, matrix::Matrix, point::Point, tuple::Tuple};
+
+use super::{perlin::turbulence, BoxedPattern, Pattern};
+use pattern_derive::PatternDerive;
+
+/// DEFAULT_TURBULENCE is how far, in units along x, the veins of a marble pattern are pushed about by the noise.
+pub const DEFAULT_TURBULENCE: f64 = 1.5;
+
+/// DEFAULT_OCTAVES is how many octaves of noise make up the turbulence of a marble pattern.
+pub const DEFAULT_OCTAVES: usize = 4;
+
+/// MarblePattern is bands running across x that fade from a to b and back every two units, like a gradient
+/// following a sine wave. The bands are pushed back and forth by turbulence so that they wander and crease like
+/// the veins in marble. With no turbulence the bands are straight.
+#[derive(Debug, Clone, PartialEq, PatternDerive)]
+pub struct MarblePattern {
+    a: BoxedPattern,
+    b: BoxedPattern,
+    turbulence: f64,
+    octaves: usize,
+    transform: Matrix,
+    inverse_transform: Matrix,
+}
+
+impl MarblePattern {
+    pub fn new(
+        a: impl Into<BoxedPattern>,
+        b: impl Into<BoxedPattern>,
+        transform: Option<Matrix>,
+    ) -> Self {
+        Self {
+            a: a.into(),
+            b: b.into(),
+            turbulence: DEFAULT_TURBULENCE,
+            octaves: DEFAULT_OCTAVES,
+            transform: transform.clone().unwrap_or_default(),
+            inverse_transform: transform
+                .unwrap_or_default()
+                .inverse()
+                .expect("trying to invert a matrix that cannot be inverted"),
+        }
+    }
+
+    pub fn turbulence(&self) -> f64 {
+        self.turbulence
+    }
+
+    pub fn set_turbulence(&mut self, turbulence: f64) {
+        assert!(turbulence >= 0., "marble turbulence cannot be negative");
+        self.turbulence = turbulence;
+    }
+
+    pub fn octaves(&self) -> usize {
+        self.octaves
+    }
+
+    pub fn set_octaves(&mut self, octaves: usize) {
+        assert!(octaves > 0, "marble needs at least one octave of noise");
+        self.octaves = octaves;
+    }
+}
+
+impl Pattern for MarblePattern {
+    fn local_color_at(&self, pattern_point: Point) -> Color {
+        let (a, b) = (
+            self.a.nested_color_at(pattern_point),
+            self.b.nested_color_at(pattern_point),
+        );
+        let x = pattern_point.x() + self.turbulence * turbulence(pattern_point, self.octaves);
+        let fraction = (1. + (PI * x).sin()) / 2.;
+        a + (b - a) * fraction
+    }
+}
+
+#[cfg(test)]
+mod test_marble {
+    use crate::{C, P};
+
+    use super::*;
+
+    #[test]
+    fn test_straight_bands() {
+        let mut p = MarblePattern::new(Color::BLACK, Color::WHITE, None);
+        p.set_turbulence(0.);
+
+        assert_eq!(C![0.5, 0.5, 0.5], p.local_color_at(P![0., 0.3, 0.7]));
+        assert_eq!(Color::WHITE, p.local_color_at(P![0.5, 0., 0.]));
+        assert_eq!(Color::BLACK, p.local_color_at(P![1.5, 2., 0.]));
+    }
+
+    #[test]
+    fn test_veins() {
+        let p = MarblePattern::new(Color::BLACK, Color::WHITE, None);
+        let mut straight = p.clone();
+        straight.set_turbulence(0.);
+
+        // the noise moves the bands, but the same point always has the same color.
+        let points = (0..20).map(|i| P![0.13 * i as f64, 0.71 * i as f64, 0.37 * i as f64]);
+        assert!(points
+            .clone()
+            .any(|point| p.local_color_at(point) != straight.local_color_at(point)));
+        for point in points {
+            assert_eq!(p.local_color_at(point), p.local_color_at(point));
+            let t = turbulence(point, DEFAULT_OCTAVES);
+            assert!((0. ..=1.).contains(&t), "{}", t);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "marble needs at least one octave of noise")]
+    fn test_no_octaves() {
+        MarblePattern::new(Color::BLACK, Color::WHITE, None).set_octaves(0);
+    }
+}
@@ -1,8 +1,16 @@
+pub mod blend;
+pub mod bump;
 pub mod checkered;
+pub mod dots;
 pub mod gradient;
+pub mod marble;
+pub mod normal_map;
 pub mod perlin;
 pub mod ring;
+pub mod solid;
 pub mod striped;
+pub mod texture_map;
+pub mod wood;
 
 use core::fmt;
 use std::any::Any;
@@ -11,18 +19,30 @@ use crate::primatives::{color::Color, matrix::Matrix, point::Point};
 
 use super::BoxedShape;
 
-pub trait Pattern: Any + fmt::Debug {
-    fn local_color_at(&self, pattern_point: Point) -> Color;
+/// PatternBase is the part of a pattern that is the same for every kind of pattern. Patterns derive it with
+/// `#[derive(PatternDerive)]` rather than writing it out.
+pub trait PatternBase {
     fn set_transformation(&mut self, transform: Matrix);
     fn inverse_transformation(&self) -> &Matrix;
     fn box_clone(&self) -> BoxedPattern;
     fn box_eq(&self, other: &dyn Any) -> bool;
     fn as_any(&self) -> &dyn Any;
+}
+
+pub trait Pattern: PatternBase + Any + fmt::Debug + Send + Sync {
+    fn local_color_at(&self, pattern_point: Point) -> Color;
+
+    /// nested_color_at is the color of the pattern where it fills part of another pattern. The point is in the outer
+    /// pattern's space and is moved into this pattern's space by its own transformation, so a striped square of a
+    /// checkered pattern can be turned or scaled on its own.
+    fn nested_color_at(&self, outer_point: Point) -> Color {
+        self.local_color_at(self.inverse_transformation().clone() * outer_point)
+    }
 
     /// at_shape returns the color for a pattern for the given object and point.
     fn at_shape(&self, object: BoxedShape, world_point: Point) -> Color {
-        let object_point = object.inverse_transformation().clone() * world_point;
-        let pattern_point = self.inverse_transformation().clone() * object_point;
+        let object_point = object.world_to_object(world_point);
+        let pattern_point = self.inverse_transformation() * object_point;
 
         self.local_color_at(pattern_point)
     }
@@ -30,6 +50,35 @@ pub trait Pattern: Any + fmt::Debug {
 
 pub type BoxedPattern = Box<dyn Pattern>;
 
+/// AddressMode decides how a pattern coordinate outside of `0..1` is mapped back into that range.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AddressMode {
+    /// Repeat starts again from 0 every unit, leaving a hard seam at each whole number.
+    #[default]
+    Repeat,
+    /// Clamp holds the value at 0 or 1 once the coordinate leaves the range.
+    Clamp,
+    /// Mirror runs back and forth between 0 and 1 so there is no seam.
+    Mirror,
+}
+
+impl AddressMode {
+    pub fn apply(&self, x: f64) -> f64 {
+        match self {
+            AddressMode::Repeat => x - x.floor(),
+            AddressMode::Clamp => x.clamp(0., 1.),
+            AddressMode::Mirror => {
+                let t = x.rem_euclid(2.);
+                if t > 1. {
+                    2. - t
+                } else {
+                    t
+                }
+            }
+        }
+    }
+}
+
 impl Clone for BoxedPattern {
     fn clone(&self) -> Self {
         self.box_clone()
@@ -42,6 +91,14 @@ impl PartialEq for BoxedPattern {
     }
 }
 
+/// comparing with a reference lets patterns holding other patterns derive PartialEq, which would otherwise try to
+/// move the boxed pattern out of the field (rust-lang/rust#31740).
+impl PartialEq<&Self> for BoxedPattern {
+    fn eq(&self, other: &&Self) -> bool {
+        self.box_eq(other.as_any())
+    }
+}
+
 #[cfg(test)]
 mod test_patterns {
     use crate::{
@@ -49,13 +106,15 @@ mod test_patterns {
             transformation::{scaling, translation},
             tuple::Tuple,
         },
-        shapes::{sphere::Sphere, Shape},
+        shapes::{sphere::Sphere, Shape, ShapeBase},
         C, P,
     };
 
+    use pattern_derive::PatternDerive;
+
     use super::*;
 
-    #[derive(Debug, Clone, PartialEq)]
+    #[derive(Debug, Clone, PartialEq, PatternDerive)]
     pub struct TestPattern {
         pub transform: Matrix,
         pub inverse_transform: Matrix,
@@ -74,27 +133,20 @@ mod test_patterns {
         fn local_color_at(&self, pattern_point: Point) -> Color {
             Color::new(pattern_point.x(), pattern_point.y(), pattern_point.z())
         }
+    }
 
-        fn set_transformation(&mut self, transform: Matrix) {
-            self.transform = transform.clone();
-            self.inverse_transform = transform.inverse().unwrap()
-        }
-
-        fn inverse_transformation(&self) -> &Matrix {
-            &self.inverse_transform
-        }
-
-        fn box_clone(&self) -> BoxedPattern {
-            Box::new(self.clone())
-        }
+    #[test]
+    fn test_address_modes() {
+        assert_eq!(0.25, AddressMode::Repeat.apply(1.25));
+        assert_eq!(0.75, AddressMode::Repeat.apply(-0.25));
 
-        fn box_eq(&self, other: &dyn Any) -> bool {
-            other.downcast_ref::<Self>().map_or(false, |a| self == a)
-        }
+        assert_eq!(1., AddressMode::Clamp.apply(1.25));
+        assert_eq!(0., AddressMode::Clamp.apply(-0.25));
+        assert_eq!(0.5, AddressMode::Clamp.apply(0.5));
 
-        fn as_any(&self) -> &dyn Any {
-            self
-        }
+        assert_eq!(0.75, AddressMode::Mirror.apply(1.25));
+        assert_eq!(0.25, AddressMode::Mirror.apply(-0.25));
+        assert_eq!(0.25, AddressMode::Mirror.apply(2.25));
     }
 
     #[test]
@@ -0,0 +1,224 @@
+use crate::{
+    primatives::{
+        matrix::Matrix,
+        point::Point,
+        vector::{dot, Vector},
+    },
+    shapes::BoxedShape,
+    V,
+};
+
+use super::texture_map::{UvImage, UvMapping};
+
+/// UV_STEP is how far apart, in world space, the texture coordinates are taken to find which way they run across
+/// the surface.
+const UV_STEP: f64 = 1e-4;
+
+/// NormalMap tilts a shape's shading normals with an RGB normal map, such as one baked from a detailed model onto
+/// a simpler one. Each pixel holds a normal in tangent space with red along u, green along v and blue straight out
+/// of the surface, each scaled from -1..1 into 0..1, so the flat blue of (0.5, 0.5, 1) leaves the surface alone.
+/// The image is laid over the shape with mapping like a TextureMap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalMap {
+    image: UvImage,
+    mapping: UvMapping,
+    transform: Matrix,
+    inverse_transform: Matrix,
+}
+
+impl NormalMap {
+    pub fn new(image: UvImage, mapping: UvMapping, transform: Option<Matrix>) -> Self {
+        Self {
+            image,
+            mapping,
+            transform: transform.clone().unwrap_or_default(),
+            inverse_transform: transform
+                .unwrap_or_default()
+                .inverse()
+                .expect("trying to invert a matrix that cannot be inverted"),
+        }
+    }
+
+    pub fn mapping(&self) -> UvMapping {
+        self.mapping
+    }
+
+    pub fn transformation(&self) -> &Matrix {
+        &self.transform
+    }
+
+    /// uv is the texture coordinates of a world point on the object.
+    fn uv(&self, object: &BoxedShape, point: Point) -> (f64, f64) {
+        let object_point = object.world_to_object(point);
+        self.mapping
+            .map(self.inverse_transform.clone() * object_point)
+    }
+
+    /// tangent_frame is the tangent and bitangent at a world point on the object, the directions across the surface
+    /// that u and v grow in. They are found from how the texture coordinates change around the point, so they follow
+    /// the object's and the map's transformations and work for any mapping. None where u or v doesn't change across
+    /// the surface, such as at the poles of a spherical map.
+    pub fn tangent_frame(
+        &self,
+        object: &BoxedShape,
+        point: Point,
+        normal: Vector,
+    ) -> Option<(Vector, Vector)> {
+        // the coordinates wrap around from 1 to 0 at seams, which a small step never really crosses.
+        let wrap = |d: f64| d - d.round();
+        let slopes = |axis: Vector| {
+            let (u1, v1) = self.uv(object, point + axis * UV_STEP);
+            let (u0, v0) = self.uv(object, point - axis * UV_STEP);
+            (
+                wrap(u1 - u0) / (2. * UV_STEP),
+                wrap(v1 - v0) / (2. * UV_STEP),
+            )
+        };
+        let (x, y, z) = (
+            slopes(V![1., 0., 0.]),
+            slopes(V![0., 1., 0.]),
+            slopes(V![0., 0., 1.]),
+        );
+
+        // only the part of each gradient across the surface says which way the texture runs along it.
+        let across = |gradient: Vector| gradient - normal * dot(gradient, normal);
+        let tangent = across(V![x.0, y.0, z.0]);
+        let bitangent = across(V![x.1, y.1, z.1]);
+        if tangent.magnitude() > 0. && bitangent.magnitude() > 0. {
+            Some((tangent.norm(), bitangent.norm()))
+        } else {
+            None
+        }
+    }
+
+    /// perturb is the normal of the object at a world point after tilting it by the map. normal is the object's own
+    /// normal there, facing out of the object.
+    pub fn perturb(&self, object: &BoxedShape, point: Point, normal: Vector) -> Vector {
+        let Some((tangent, bitangent)) = self.tangent_frame(object, point, normal) else {
+            return normal;
+        };
+        let (u, v) = self.uv(object, point);
+        let texel = self.image.uv_color_at(u, v);
+        let along = |channel: f64| 2. * channel - 1.;
+        (tangent * along(texel.red())
+            + bitangent * along(texel.green())
+            + normal * along(texel.blue()))
+        .norm()
+    }
+}
+
+#[cfg(test)]
+mod test_normal_map {
+    use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_2};
+
+    use crate::{
+        comparison::approx_eq,
+        primatives::{
+            color::Color,
+            transformation::{rotation_z, scaling},
+            tuple::Tuple,
+        },
+        shapes::{plane::Plane, sphere::Sphere, Shape, ShapeBase},
+        world::canvas::Canvas,
+        P,
+    };
+
+    use super::*;
+
+    /// uniform is a normal map that tilts every normal to the same direction in tangent space.
+    fn uniform(x: f64, y: f64, z: f64, mapping: UvMapping) -> NormalMap {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.write_pixel(
+            0,
+            0,
+            Color::new((x + 1.) / 2., (y + 1.) / 2., (z + 1.) / 2.),
+        );
+        NormalMap::new(UvImage::new(canvas), mapping, None)
+    }
+
+    fn assert_vector(expected: Vector, actual: Vector) {
+        assert!(
+            approx_eq(expected.x(), actual.x())
+                && approx_eq(expected.y(), actual.y())
+                && approx_eq(expected.z(), actual.z()),
+            "expected {:?} but got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_plane_tangent_frame() {
+        let plane = Plane::default().box_clone();
+        let map = uniform(0., 0., 1., UvMapping::Planar);
+        let (t, b) = map
+            .tangent_frame(&plane, P![0.3, 0., 0.7], V![0., 1., 0.])
+            .unwrap();
+        assert_vector(V![1., 0., 0.], t);
+        assert_vector(V![0., 0., 1.], b);
+
+        // the frame follows the map around seams where the coordinates wrap back to 0.
+        let (t, _) = map
+            .tangent_frame(&plane, P![1., 0., 0.5], V![0., 1., 0.])
+            .unwrap();
+        assert_vector(V![1., 0., 0.], t);
+    }
+
+    #[test]
+    fn test_sphere_tangent_frame() {
+        let sphere = Sphere::default_boxed();
+        let map = uniform(0., 0., 1., UvMapping::Spherical);
+
+        // u runs around the equator from -z towards +x, and v runs up towards the pole. -z is on the seam.
+        let (t, b) = map
+            .tangent_frame(&sphere, P![0., 0., -1.], V![0., 0., -1.])
+            .unwrap();
+        assert_vector(V![1., 0., 0.], t);
+        assert_vector(V![0., 1., 0.], b);
+
+        // the frame turns with the object.
+        let mut turned = Sphere::default();
+        turned.set_transform(rotation_z(FRAC_PI_2));
+        let turned = turned.box_clone();
+        let point = P![0., 0., -1.];
+        let (t, b) = map
+            .tangent_frame(&turned, point, turned.normal(point))
+            .unwrap();
+        assert_vector(V![0., 1., 0.], t);
+        assert_vector(V![-1., 0., 0.], b);
+    }
+
+    #[test]
+    fn test_perturb() {
+        let plane = Plane::default().box_clone();
+        let up = V![0., 1., 0.];
+        let point = P![0.2, 0., 0.4];
+
+        // flat blue leaves the normal alone.
+        let flat = uniform(0., 0., 1., UvMapping::Planar);
+        assert_vector(up, flat.perturb(&plane, point, up));
+
+        // red tilts the normal towards u and green towards v.
+        let towards_u = uniform(FRAC_1_SQRT_2, 0., FRAC_1_SQRT_2, UvMapping::Planar);
+        assert_vector(
+            V![FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.],
+            towards_u.perturb(&plane, point, up),
+        );
+        let towards_v = uniform(0., FRAC_1_SQRT_2, FRAC_1_SQRT_2, UvMapping::Planar);
+        assert_vector(
+            V![0., FRAC_1_SQRT_2, FRAC_1_SQRT_2],
+            towards_v.perturb(&plane, point, up),
+        );
+
+        // stretching the map doesn't change which way the normals lean.
+        let stretched = NormalMap::new(
+            towards_u.image.clone(),
+            UvMapping::Planar,
+            Some(scaling(3., 1., 0.5)),
+        );
+        assert_vector(
+            V![FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.],
+            stretched.perturb(&plane, point, up),
+        );
+    }
+}
@@ -1,69 +1,111 @@
+use std::sync::Arc;
+
 use crate::{
-    primatives::{matrix::Matrix, point::Point, tuple::Tuple},
+    primatives::{matrix::Matrix, point::Point, rng::Rng, tuple::Tuple},
     P,
 };
 
 use super::{BoxedPattern, Pattern};
+use pattern_derive::PatternDerive;
+
+/// Turbulence is how far a perlin pattern jitters the pattern it wraps. scale is how far points are moved, octaves
+/// is how many layers of finer noise are added together and persistence is how much less each layer counts than
+/// the one before it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Turbulence {
+    scale: f64,
+    octaves: usize,
+    persistence: f64,
+}
+
+impl Turbulence {
+    pub fn new(scale: f64, octaves: usize, persistence: f64) -> Self {
+        assert!(octaves > 0, "turbulence needs at least one octave of noise");
+        Self {
+            scale,
+            octaves,
+            persistence,
+        }
+    }
+
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub fn octaves(&self) -> usize {
+        self.octaves
+    }
+
+    pub fn persistence(&self) -> f64 {
+        self.persistence
+    }
+}
+
+impl Default for Turbulence {
+    fn default() -> Self {
+        Self::new(0.01, 3, 0.8)
+    }
+}
 
 /// PerlinPattern applies a perlin noise jitter to the given pattern
-#[derive(Debug)]
+#[derive(Debug, PatternDerive)]
 pub struct PerlinPattern {
     pattern: BoxedPattern,
     repeat: Option<usize>,
+    turbulence: Turbulence,
+    noise: Noise,
     transform: Matrix,
     inverse_transform: Matrix,
 }
 
 impl PerlinPattern {
-    pub fn new(pattern: BoxedPattern, repeat: Option<usize>, transform: Option<Matrix>) -> Self {
+    pub fn new(
+        pattern: BoxedPattern,
+        turbulence: Turbulence,
+        repeat: Option<usize>,
+        transform: Option<Matrix>,
+    ) -> Self {
         Self {
             pattern,
             repeat,
+            turbulence,
+            noise: Noise::default(),
             transform: transform.clone().unwrap_or_default(),
             inverse_transform: transform.unwrap_or_default().inverse().unwrap(),
         }
     }
-}
-
-impl Pattern for PerlinPattern {
-    fn local_color_at(&self, pattern_point: Point) -> crate::primatives::color::Color {
-        let scale_value = 0.01;
-        let octaves = 3;
-        let persistance = 0.8;
-
-        let jitter_x =
-            pattern_point.x() + octave_perlin(pattern_point, octaves, persistance) * scale_value;
-        let jitter_y = pattern_point.y()
-            + octave_perlin(pattern_point + P![0., 0., 1.], octaves, persistance) * scale_value;
-        let jitter_z = pattern_point.z()
-            + octave_perlin(pattern_point + P![0., 0., 2.], octaves, persistance) * scale_value;
 
-        let point = Point::new(jitter_x, jitter_y, jitter_z);
-
-        self.pattern.local_color_at(point)
+    pub fn turbulence(&self) -> Turbulence {
+        self.turbulence
     }
 
-    fn set_transformation(&mut self, transform: crate::primatives::matrix::Matrix) {
-        self.transform = transform.clone();
-        self.inverse_transform = transform
-            .inverse()
-            .expect("trying to invert a matrix that cannot be inverted");
+    pub fn noise(&self) -> &Noise {
+        &self.noise
     }
 
-    fn inverse_transformation(&self) -> &crate::primatives::matrix::Matrix {
-        &self.inverse_transform
+    /// set_noise changes the noise the pattern is jittered with, such as to a seeded noise so that two objects
+    /// with the same pattern don't look exactly alike.
+    pub fn set_noise(&mut self, noise: Noise) {
+        self.noise = noise;
     }
+}
 
-    fn box_clone(&self) -> super::BoxedPattern {
-        Box::new(self.clone())
-    }
+impl Pattern for PerlinPattern {
+    fn local_color_at(&self, pattern_point: Point) -> crate::primatives::color::Color {
+        let Turbulence {
+            scale,
+            octaves,
+            persistence,
+        } = self.turbulence;
+        let jitter = |offset: Point| self.noise.fbm(offset, octaves, persistence) * scale;
 
-    fn box_eq(&self, other: &dyn std::any::Any) -> bool {
-        other.downcast_ref::<Self>().map_or(false, |a| self == a)
-    }
+        let jitter_x = pattern_point.x() + jitter(pattern_point);
+        let jitter_y = pattern_point.y() + jitter(pattern_point + P![0., 0., 1.]);
+        let jitter_z = pattern_point.z() + jitter(pattern_point + P![0., 0., 2.]);
 
-    fn as_any(&self) -> &dyn std::any::Any {
-        self
+        let point = Point::new(jitter_x, jitter_y, jitter_z);
+
+        self.pattern.local_color_at(point)
     }
 }
 
@@ -72,6 +114,8 @@ impl Clone for PerlinPattern {
         Self {
             pattern: self.pattern.box_clone(),
             repeat: self.repeat,
+            turbulence: self.turbulence,
+            noise: self.noise.clone(),
             transform: self.transform.clone(),
             inverse_transform: self.inverse_transform.clone(),
         }
@@ -82,6 +126,8 @@ impl PartialEq for PerlinPattern {
     fn eq(&self, other: &Self) -> bool {
         self.pattern.box_eq(&other.pattern)
             && self.repeat == other.repeat
+            && self.turbulence == other.turbulence
+            && self.noise == other.noise
             && self.transform == other.transform
             && self.inverse_transform == other.inverse_transform
     }
@@ -117,7 +163,58 @@ const PERMUTATION: [usize; 512] = [
     222, 114, 67, 29, 24, 72, 243, 141, 128, 195, 78, 66, 215, 61, 156, 180,
 ];
 
+/// Noise is perlin noise made from a table of the numbers 0 to 255 in a random order. The default is Ken Perlin's own
+/// table, which is what the free noise functions use, and seeded makes a different table for every seed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Noise {
+    permutation: Arc<[usize]>,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self {
+            permutation: Arc::from(PERMUTATION.as_slice()),
+        }
+    }
+}
+
+impl Noise {
+    /// seeded shuffles the numbers 0 to 255 with a generator started from the seed, so the same seed always
+    /// makes the same noise.
+    pub fn seeded(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut table: Vec<usize> = (0..256).collect();
+        for i in (1..table.len()).rev() {
+            let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+            table.swap(i, j);
+        }
+        table.extend_from_within(..);
+        Self {
+            permutation: table.into(),
+        }
+    }
+
+    /// noise is perlin noise at the point, between 0 and 1.
+    pub fn noise(&self, point: Point, repeat: Option<usize>) -> f64 {
+        noise_from(&self.permutation, point, repeat)
+    }
+
+    /// fbm is fractal brownian motion, see the free function of the same name.
+    pub fn fbm(&self, point: Point, octaves: usize, persistence: f64) -> f64 {
+        fbm_from(&self.permutation, point, octaves, persistence)
+    }
+
+    /// turbulence is folded octaves of noise, see the free function of the same name.
+    pub fn turbulence(&self, point: Point, octaves: usize) -> f64 {
+        turbulence_from(&self.permutation, point, octaves)
+    }
+}
+
 pub fn perlin_noise(point: Point, repeat: Option<usize>) -> f64 {
+    noise_from(&PERMUTATION, point, repeat)
+}
+
+fn noise_from(p: &[usize], point: Point, repeat: Option<usize>) -> f64 {
     let x_i: usize = (point.x() as usize) & 255;
     let y_i: usize = (point.y() as usize) & 255;
     let z_i: usize = (point.z() as usize) & 255;
@@ -129,7 +226,6 @@ pub fn perlin_noise(point: Point, repeat: Option<usize>) -> f64 {
     let v = fade(y_f);
     let w = fade(z_f);
 
-    let p = PERMUTATION;
     let aaa = p[p[p[x_i] + y_i] + z_i];
     let aba = p[p[p[x_i] + inc(y_i, repeat)] + z_i];
     let aab = p[p[p[x_i] + y_i] + inc(z_i, repeat)];
@@ -212,21 +308,121 @@ fn lerp(a: f64, b: f64, x: f64) -> f64 {
     a + x * (b - a)
 }
 
-/// octave_perlin applies a series of "octaves" of perlin noise to a point.
-/// each perlin ocatve has a lower effect on the overall shape.
-fn octave_perlin(point: Point, octaves: usize, persistance: f64) -> f64 {
+/// fbm, fractal brownian motion, applies a series of "octaves" of perlin noise to a point.
+/// each perlin ocatve is twice as fine as the one before and counts persistence times as much,
+/// and the total is scaled back to between 0 and 1.
+pub fn fbm(point: Point, octaves: usize, persistence: f64) -> f64 {
+    fbm_from(&PERMUTATION, point, octaves, persistence)
+}
+
+fn fbm_from(p: &[usize], point: Point, octaves: usize, persistence: f64) -> f64 {
     let mut total = 0.0;
     let mut frequency = 1.0;
     let mut amplitude = 1.0;
     let mut max_value = 0.0; // Used for normalizing result to 0.0 - 1.0
-    for _ in [0..octaves] {
-        total += perlin_noise(point * frequency, None) * amplitude;
+    for _ in 0..octaves {
+        total += noise_from(p, point * frequency, None) * amplitude;
 
         max_value += amplitude;
 
-        amplitude *= persistance;
+        amplitude *= persistence;
+        frequency *= 2.0;
+    }
+
+    total / max_value
+}
+
+/// turbulence adds up octaves of perlin noise folded about their middle, so each octave makes sharp creases where
+/// the noise crosses it rather than smooth hills. Octaves twice as fine count half as much, and the total is
+/// scaled back to between 0 and 1.
+pub fn turbulence(point: Point, octaves: usize) -> f64 {
+    turbulence_from(&PERMUTATION, point, octaves)
+}
+
+fn turbulence_from(p: &[usize], point: Point, octaves: usize) -> f64 {
+    let mut total = 0.0;
+    let mut frequency = 1.0;
+    let mut max_value = 0.0;
+    for _ in 0..octaves {
+        total += (noise_from(p, point * frequency, None) * 2. - 1.).abs() / frequency;
+        max_value += 1. / frequency;
         frequency *= 2.0;
     }
 
     total / max_value
 }
+
+#[cfg(test)]
+mod test_perlin {
+    use crate::comparison::approx_eq;
+    use crate::primatives::color::Color;
+    use crate::shapes::patterns::{striped::StripePattern, PatternBase};
+
+    use super::*;
+
+    fn points() -> impl Iterator<Item = Point> {
+        (0..50).map(|i| P![0.37 * i as f64, 0.11 * i as f64, 0.73 * i as f64])
+    }
+
+    #[test]
+    fn test_default_noise() {
+        let noise = Noise::default();
+        for point in points() {
+            assert_eq!(perlin_noise(point, None), noise.noise(point, None));
+            assert_eq!(fbm(point, 3, 0.8), noise.fbm(point, 3, 0.8));
+            assert_eq!(turbulence(point, 4), noise.turbulence(point, 4));
+            assert!((0. ..=1.).contains(&fbm(point, 3, 0.8)));
+        }
+    }
+
+    #[test]
+    fn test_fbm() {
+        let point = P![1.3, 2.7, 0.4];
+        let (coarse, fine) = (perlin_noise(point, None), perlin_noise(point * 2., None));
+        assert!(approx_eq(coarse, fbm(point, 1, 0.5)));
+        // each octave is twice as fine and counts for persistence times as much as the one before.
+        assert!(approx_eq((coarse + 0.5 * fine) / 1.5, fbm(point, 2, 0.5)));
+    }
+
+    #[test]
+    fn test_seeded_noise() {
+        let (a, b) = (Noise::seeded(7), Noise::seeded(8));
+        assert_eq!(a, Noise::seeded(7));
+        assert_ne!(a, b);
+
+        // every number appears once in each half of the table.
+        let mut half = a.permutation[..256].to_vec();
+        half.sort();
+        assert_eq!((0..256).collect::<Vec<_>>(), half);
+        assert_eq!(a.permutation[..256], a.permutation[256..]);
+
+        assert!(points().any(|point| a.noise(point, None) != b.noise(point, None)));
+        for point in points() {
+            assert!((0. ..=1.).contains(&a.noise(point, None)));
+        }
+    }
+
+    #[test]
+    fn test_turbulence_settings() {
+        let stripes = StripePattern::new(Color::WHITE, Color::BLACK, None).box_clone();
+        let still =
+            PerlinPattern::new(stripes.box_clone(), Turbulence::new(0., 3, 0.8), None, None);
+        let jittered = PerlinPattern::new(
+            stripes.box_clone(),
+            Turbulence::new(0.5, 3, 0.8),
+            None,
+            None,
+        );
+
+        // with no scale the pattern is left as it is.
+        let near_edge = P![0.99, 0., 0.];
+        assert_eq!(Color::WHITE, still.local_color_at(near_edge));
+        assert!(points()
+            .map(|point| point + P![0.99, 0., 0.])
+            .any(|point| jittered.local_color_at(point) != stripes.local_color_at(point)));
+
+        let mut seeded = jittered.clone();
+        seeded.set_noise(Noise::seeded(1));
+        assert_ne!(jittered, seeded);
+    }
+}
@@ -1,69 +1,37 @@
 use crate::primatives::{color::Color, matrix::Matrix, tuple::Tuple};
 
-use super::Pattern;
+use super::{BoxedPattern, Pattern};
+use pattern_derive::PatternDerive;
 
 // RingPattern draws concentric circles on a object.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, PatternDerive)]
+#[pattern(new)]
 pub struct RingPattern {
-    a: Color,
-    b: Color,
+    a: BoxedPattern,
+    b: BoxedPattern,
     transform: Matrix,
     inverse_transform: Matrix,
 }
 
-impl RingPattern {
-    pub fn new(a: Color, b: Color, transform: Option<Matrix>) -> Self {
-        Self {
-            a,
-            b,
-            transform: transform.clone().unwrap_or_default(),
-            inverse_transform: transform
-                .unwrap_or_default()
-                .inverse()
-                .expect("trying to invert a matrix that cannot be inverted"),
-        }
-    }
-}
-
 impl Pattern for RingPattern {
     fn local_color_at(&self, pattern_point: crate::primatives::point::Point) -> Color {
-        if (pattern_point.x().powf(2.) + pattern_point.z().powf(2.)).sqrt() as usize % 2 == 0 {
-            self.a
+        if ((pattern_point.x().powf(2.) + pattern_point.z().powf(2.)).sqrt() as usize)
+            .is_multiple_of(2)
+        {
+            self.a.nested_color_at(pattern_point)
         } else {
-            self.b
+            self.b.nested_color_at(pattern_point)
         }
     }
-
-    fn set_transformation(&mut self, transform: Matrix) {
-        self.transform = transform.clone();
-        self.inverse_transform = transform
-            .inverse()
-            .expect("trying to invert a matrix that cannot be inverted");
-    }
-
-    fn inverse_transformation(&self) -> &Matrix {
-        &self.inverse_transform
-    }
-
-    fn box_clone(&self) -> super::BoxedPattern {
-        Box::new(self.clone())
-    }
-
-    fn box_eq(&self, other: &dyn std::any::Any) -> bool {
-        other.downcast_ref::<Self>().map_or(false, |a| self == a)
-    }
-
-    fn as_any(&self) -> &dyn std::any::Any {
-        self
-    }
 }
 
 #[cfg(test)]
 mod test_ring_pattern {
     use crate::{
         primatives::color::Color,
+        primatives::tuple::Tuple,
         shapes::patterns::{ring::RingPattern, Pattern},
-        Tuple, P,
+        P,
     };
 
     #[test]
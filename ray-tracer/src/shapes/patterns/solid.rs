@@ -0,0 +1,39 @@
+use crate::primatives::{color::Color, matrix::Matrix, point::Point};
+
+use super::{BoxedPattern, Pattern};
+use pattern_derive::PatternDerive;
+
+/// SolidPattern is the same color everywhere. It lets a plain color fill a slot of another pattern, such as one of
+/// the squares of a checkered pattern.
+#[derive(Debug, Clone, PartialEq, PatternDerive)]
+pub struct SolidPattern {
+    color: Color,
+    transform: Matrix,
+    inverse_transform: Matrix,
+}
+
+impl SolidPattern {
+    pub fn new(color: Color) -> Self {
+        Self {
+            color,
+            transform: Matrix::identity_matrix(),
+            inverse_transform: Matrix::identity_matrix(),
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        self.color
+    }
+}
+
+impl Pattern for SolidPattern {
+    fn local_color_at(&self, _pattern_point: Point) -> Color {
+        self.color
+    }
+}
+
+impl From<Color> for BoxedPattern {
+    fn from(color: Color) -> Self {
+        Box::new(SolidPattern::new(color))
+    }
+}
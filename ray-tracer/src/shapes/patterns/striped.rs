@@ -1,54 +1,49 @@
-use crate::primatives::{color::Color, matrix::Matrix, point::Point, tuple::Tuple};
-
-use super::Pattern;
+use crate::{
+    primatives::{
+        matrix::Matrix,
+        point::{Point, ORIGIN},
+        vector::{dot, Vector},
+    },
+    V,
+};
+
+use super::{BoxedPattern, Pattern};
+use pattern_derive::PatternDerive;
 
 /// StripePattern alternates between two given colors over a set inverval.
-#[derive(Debug, Clone, PartialEq)]
+/// By default the stripes are one unit wide and alternate along the x axis.
+#[derive(Debug, Clone, PartialEq, PatternDerive)]
 pub struct StripePattern {
-    a: Color,
-    b: Color,
+    a: BoxedPattern,
+    b: BoxedPattern,
+    axis: Vector,
+    width: f64,
     transform: Matrix,
     inverse_transform: Matrix,
 }
 
 impl Pattern for StripePattern {
     fn local_color_at(&self, pattern_point: Point) -> crate::primatives::color::Color {
-        if pattern_point.x().floor() % 2.0 == 0.0 {
-            self.a
+        let distance = dot(pattern_point - ORIGIN, self.axis) / self.width;
+        if distance.floor() % 2.0 == 0.0 {
+            self.a.nested_color_at(pattern_point)
         } else {
-            self.b
+            self.b.nested_color_at(pattern_point)
         }
     }
-
-    fn set_transformation(&mut self, transform: crate::primatives::matrix::Matrix) {
-        self.transform = transform.clone();
-        self.inverse_transform = transform
-            .inverse()
-            .expect("trying to invert a matrix that cannot be inverted");
-    }
-
-    fn inverse_transformation(&self) -> &crate::primatives::matrix::Matrix {
-        &self.inverse_transform
-    }
-
-    fn box_clone(&self) -> super::BoxedPattern {
-        Box::new(self.clone())
-    }
-
-    fn box_eq(&self, other: &dyn std::any::Any) -> bool {
-        other.downcast_ref::<Self>().map_or(false, |a| self == a)
-    }
-
-    fn as_any(&self) -> &dyn std::any::Any {
-        self
-    }
 }
 
 impl StripePattern {
-    pub fn new(a: Color, b: Color, transform: Option<Matrix>) -> Self {
+    pub fn new(
+        a: impl Into<BoxedPattern>,
+        b: impl Into<BoxedPattern>,
+        transform: Option<Matrix>,
+    ) -> Self {
         Self {
-            a,
-            b,
+            a: a.into(),
+            b: b.into(),
+            axis: V![1., 0., 0.],
+            width: 1.,
             transform: transform.clone().unwrap_or_default(),
             inverse_transform: transform
                 .unwrap_or_default()
@@ -56,17 +51,36 @@ impl StripePattern {
                 .expect("trying to invert a matrix that cannot be inverted"),
         }
     }
+
+    /// set_axis changes the direction the stripes alternate in, e.g. `V![0., 1., 0.]` for horizontal bands.
+    pub fn set_axis(&mut self, axis: Vector) {
+        self.axis = axis.norm();
+    }
+
+    pub fn axis(&self) -> Vector {
+        self.axis
+    }
+
+    pub fn set_width(&mut self, width: f64) {
+        assert!(width > 0., "stripe width must be greater than zero");
+        self.width = width;
+    }
+
+    pub fn width(&self) -> f64 {
+        self.width
+    }
 }
 
 #[cfg(test)]
 mod test_striped_pattern {
     use crate::{
+        primatives::tuple::Tuple,
         primatives::{
             color::Color,
             transformation::{scaling, translation},
         },
-        shapes::{sphere::Sphere, Shape},
-        Tuple, P,
+        shapes::{patterns::PatternBase, sphere::Sphere, Shape, ShapeBase},
+        P, V,
     };
 
     use super::*;
@@ -75,8 +89,30 @@ mod test_striped_pattern {
     fn test_stripe_new() {
         let pattern = StripePattern::new(Color::WHITE, Color::BLACK, None);
 
-        assert_eq!(pattern.a, Color::new(1., 1., 1.));
-        assert_eq!(pattern.b, Color::new(0., 0., 0.));
+        assert_eq!(pattern.a, BoxedPattern::from(Color::new(1., 1., 1.)));
+        assert_eq!(pattern.b, BoxedPattern::from(Color::new(0., 0., 0.)));
+    }
+
+    #[test]
+    fn test_stripe_axis_and_width() {
+        let mut pattern = StripePattern::new(Color::WHITE, Color::BLACK, None);
+        pattern.set_width(0.5);
+
+        assert_eq!(pattern.local_color_at(P![0.4, 0., 0.]), Color::WHITE);
+        assert_eq!(pattern.local_color_at(P![0.6, 0., 0.]), Color::BLACK);
+        assert_eq!(pattern.local_color_at(P![1.1, 0., 0.]), Color::WHITE);
+
+        // stripes along y are constant in x.
+        pattern.set_axis(V![0., 2., 0.]);
+        assert_eq!(V![0., 1., 0.], pattern.axis());
+        assert_eq!(pattern.local_color_at(P![0.6, 0., 0.]), Color::WHITE);
+        assert_eq!(pattern.local_color_at(P![0., 0.6, 0.]), Color::BLACK);
+
+        // an arbitrary direction.
+        pattern.set_width(1.);
+        pattern.set_axis(V![1., 1., 0.]);
+        assert_eq!(pattern.local_color_at(P![0.5, 0.5, 0.]), Color::WHITE);
+        assert_eq!(pattern.local_color_at(P![0.8, 0.8, 0.]), Color::BLACK);
     }
 
     fn test_stripe_color_at() {
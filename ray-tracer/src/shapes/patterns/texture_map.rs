@@ -0,0 +1,660 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+    primatives::{color::Color, matrix::Matrix, point::Point, tuple::Tuple},
+    world::canvas::Canvas,
+};
+
+use super::{AddressMode, BoxedPattern, Pattern};
+use pattern_derive::PatternDerive;
+
+/// UvMapping flattens a point on a shape into (u, v) texture coordinates, both between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UvMapping {
+    /// Planar repeats the texture across the xz plane every unit.
+    Planar,
+    /// Spherical wraps the texture around a unit sphere like lines of latitude and longitude.
+    Spherical,
+    /// Cylindrical wraps the texture around the y axis once and repeats it every unit of height.
+    Cylindrical,
+    /// Conical wraps the texture around the y axis like Cylindrical, but measures v along the slope of a cone
+    /// from its tip so the texture isn't stretched as the cone widens.
+    Conical,
+    /// Cube puts the whole texture on each face of a cube from -1 to 1, for faces that should all look the same.
+    /// Use CubeMap to give each face its own texture.
+    Cube,
+}
+
+impl UvMapping {
+    pub fn map(&self, point: Point) -> (f64, f64) {
+        match self {
+            UvMapping::Planar => planar_map(point),
+            UvMapping::Spherical => spherical_map(point),
+            UvMapping::Cylindrical => cylindrical_map(point),
+            UvMapping::Conical => conical_map(point),
+            UvMapping::Cube => cube_map(point).1,
+        }
+    }
+
+    /// map_unwrapped is map without wrapping the repeating planar, cylindrical and conical coordinates back into
+    /// 0 to 1, so a texture can choose how to fill the space outside itself.
+    pub fn map_unwrapped(&self, point: Point) -> (f64, f64) {
+        match self {
+            UvMapping::Planar => (point.x(), point.z()),
+            UvMapping::Cylindrical => (around_y(point), point.y()),
+            UvMapping::Conical => (around_y(point), slope_distance(point)),
+            UvMapping::Spherical | UvMapping::Cube => self.map(point),
+        }
+    }
+}
+
+/// around_y is how far around the y axis the point is, from 0 at -z through 0.25 at +x to 1 back at -z.
+fn around_y(point: Point) -> f64 {
+    let theta = point.x().atan2(point.z());
+    1. - (theta / (2. * PI) + 0.5)
+}
+
+pub fn planar_map(point: Point) -> (f64, f64) {
+    (point.x().rem_euclid(1.), point.z().rem_euclid(1.))
+}
+
+pub fn spherical_map(point: Point) -> (f64, f64) {
+    let radius = (point.x().powi(2) + point.y().powi(2) + point.z().powi(2)).sqrt();
+    let phi = (point.y() / radius).acos();
+    (around_y(point), 1. - phi / PI)
+}
+
+pub fn cylindrical_map(point: Point) -> (f64, f64) {
+    (around_y(point), point.y().rem_euclid(1.))
+}
+
+/// conical_map measures v as the distance along the surface of a cone from its tip, negative below the tip.
+pub fn conical_map(point: Point) -> (f64, f64) {
+    (around_y(point), slope_distance(point).rem_euclid(1.))
+}
+
+fn slope_distance(point: Point) -> f64 {
+    let slope = (point.x().powi(2) + point.y().powi(2) + point.z().powi(2)).sqrt();
+    point.y().signum() * slope
+}
+
+/// CubeFace is a face of a cube from -1 to 1, named as seen from in front of the cube looking towards +z.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    Left,
+    Front,
+    Right,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    /// from_point is the face of the cube the point is on, which is the axis it is furthest along.
+    pub fn from_point(point: Point) -> Self {
+        let (x, y, z) = (point.x(), point.y(), point.z());
+        let coord = x.abs().max(y.abs()).max(z.abs());
+        if coord == x {
+            CubeFace::Right
+        } else if coord == -x {
+            CubeFace::Left
+        } else if coord == y {
+            CubeFace::Up
+        } else if coord == -y {
+            CubeFace::Down
+        } else if coord == z {
+            CubeFace::Front
+        } else {
+            CubeFace::Back
+        }
+    }
+}
+
+/// cube_map finds the face of a cube from -1 to 1 the point is on and the (u, v) of the point on that face, with
+/// each face unfolded so that v runs upwards and the four sides join up around the cube.
+/// Points off the cube are first moved onto it towards or away from the centre, so only their direction matters
+/// and any shape around the origin, such as a sphere enclosing a scene as its sky, can be mapped like a cube.
+pub fn cube_map(point: Point) -> (CubeFace, (f64, f64)) {
+    let along = |c: f64| (c + 1.).rem_euclid(2.) / 2.;
+    let against = |c: f64| (1. - c).rem_euclid(2.) / 2.;
+    let coord = point.x().abs().max(point.y().abs()).max(point.z().abs());
+    let (x, y, z) = (point.x() / coord, point.y() / coord, point.z() / coord);
+    let face = CubeFace::from_point(point);
+    let uv = match face {
+        CubeFace::Front => (along(x), along(y)),
+        CubeFace::Back => (against(x), along(y)),
+        CubeFace::Left => (along(z), along(y)),
+        CubeFace::Right => (against(z), along(y)),
+        CubeFace::Up => (along(x), against(z)),
+        CubeFace::Down => (along(x), along(z)),
+    };
+    (face, uv)
+}
+
+/// UvCheckers is a checker board in texture space with width cells across u and height cells across v.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UvCheckers {
+    width: f64,
+    height: f64,
+    a: Color,
+    b: Color,
+}
+
+impl UvCheckers {
+    pub fn new(width: f64, height: f64, a: Color, b: Color) -> Self {
+        Self {
+            width,
+            height,
+            a,
+            b,
+        }
+    }
+
+    pub fn uv_color_at(&self, u: f64, v: f64) -> Color {
+        let cell = (u * self.width).floor() + (v * self.height).floor();
+        if cell % 2. == 0. {
+            self.a
+        } else {
+            self.b
+        }
+    }
+}
+
+/// AlignCheck is one color with a square of another in each corner, for checking how the faces of a cube map line
+/// up with each other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlignCheck {
+    main: Color,
+    upper_left: Color,
+    upper_right: Color,
+    bottom_left: Color,
+    bottom_right: Color,
+}
+
+impl AlignCheck {
+    pub fn new(
+        main: Color,
+        upper_left: Color,
+        upper_right: Color,
+        bottom_left: Color,
+        bottom_right: Color,
+    ) -> Self {
+        Self {
+            main,
+            upper_left,
+            upper_right,
+            bottom_left,
+            bottom_right,
+        }
+    }
+
+    pub fn uv_color_at(&self, u: f64, v: f64) -> Color {
+        match (u < 0.2, u > 0.8, v < 0.2, v > 0.8) {
+            (true, _, _, true) => self.upper_left,
+            (_, true, _, true) => self.upper_right,
+            (true, _, true, _) => self.bottom_left,
+            (_, true, true, _) => self.bottom_right,
+            _ => self.main,
+        }
+    }
+}
+
+/// UvImage is a picture in texture space, stretched so u runs across it from left to right and v runs up it from
+/// the bottom. The canvas is shared so copies of the pattern don't copy the picture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UvImage {
+    canvas: Arc<Canvas>,
+    /// address_mode is how u and v outside of 0 to 1 find a pixel, clamping to the edge pixels unless it's changed.
+    address_mode: AddressMode,
+}
+
+impl UvImage {
+    pub fn new(canvas: impl Into<Arc<Canvas>>) -> Self {
+        let canvas = canvas.into();
+        assert!(
+            canvas.width() > 0 && canvas.height() > 0,
+            "an image texture needs at least one pixel"
+        );
+        Self {
+            canvas,
+            address_mode: AddressMode::Clamp,
+        }
+    }
+
+    pub fn address_mode(&self) -> AddressMode {
+        self.address_mode
+    }
+
+    /// set_address_mode changes whether the image is repeated, mirrored or has its edges stretched outside of 0 to 1.
+    pub fn set_address_mode(&mut self, mode: AddressMode) {
+        self.address_mode = mode;
+    }
+
+    /// uv_color_at is the color of the nearest pixel, after u and v are brought back between 0 and 1 by the address
+    /// mode.
+    pub fn uv_color_at(&self, u: f64, v: f64) -> Color {
+        let (u, v) = (self.address_mode.apply(u), self.address_mode.apply(v));
+        let x = u * (self.canvas.width() - 1) as f64;
+        let y = (1. - v) * (self.canvas.height() - 1) as f64;
+        self.canvas
+            .pixel_at(x.round() as usize, y.round() as usize)
+            .expect("the pixel is inside the image")
+    }
+}
+
+/// UvTexture is a pattern made in texture space, taking (u, v) rather than a point.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UvTexture {
+    Checkers(UvCheckers),
+    AlignCheck(AlignCheck),
+    Image(UvImage),
+    /// Pattern lays any other pattern over the texture, with u along its x axis and v along its z axis.
+    Pattern(BoxedPattern),
+}
+
+impl UvTexture {
+    pub fn uv_color_at(&self, u: f64, v: f64) -> Color {
+        match self {
+            UvTexture::Checkers(checkers) => checkers.uv_color_at(u, v),
+            UvTexture::AlignCheck(check) => check.uv_color_at(u, v),
+            UvTexture::Image(image) => image.uv_color_at(u, v),
+            UvTexture::Pattern(pattern) => pattern.nested_color_at(Point::new(u, 0., v)),
+        }
+    }
+}
+
+impl From<AlignCheck> for UvTexture {
+    fn from(check: AlignCheck) -> Self {
+        UvTexture::AlignCheck(check)
+    }
+}
+
+impl From<BoxedPattern> for UvTexture {
+    fn from(pattern: BoxedPattern) -> Self {
+        UvTexture::Pattern(pattern)
+    }
+}
+
+impl From<UvCheckers> for UvTexture {
+    fn from(checkers: UvCheckers) -> Self {
+        UvTexture::Checkers(checkers)
+    }
+}
+
+impl From<UvImage> for UvTexture {
+    fn from(image: UvImage) -> Self {
+        UvTexture::Image(image)
+    }
+}
+
+/// TextureMap paints a pattern made in texture space onto a shape, using mapping to find the (u, v) of each point.
+#[derive(Debug, Clone, PartialEq, PatternDerive)]
+pub struct TextureMap {
+    texture: UvTexture,
+    mapping: UvMapping,
+    transform: Matrix,
+    inverse_transform: Matrix,
+}
+
+impl TextureMap {
+    pub fn new(
+        texture: impl Into<UvTexture>,
+        mapping: UvMapping,
+        transform: Option<Matrix>,
+    ) -> Self {
+        Self {
+            texture: texture.into(),
+            mapping,
+            transform: transform.clone().unwrap_or_default(),
+            inverse_transform: transform
+                .unwrap_or_default()
+                .inverse()
+                .expect("trying to invert a matrix that cannot be inverted"),
+        }
+    }
+
+    pub fn mapping(&self) -> UvMapping {
+        self.mapping
+    }
+}
+
+impl Pattern for TextureMap {
+    fn local_color_at(&self, pattern_point: Point) -> Color {
+        // images fill the space past their edges by their own address mode, so they see the coordinates unwrapped
+        let (u, v) = match self.texture {
+            UvTexture::Image(_) => self.mapping.map_unwrapped(pattern_point),
+            _ => self.mapping.map(pattern_point),
+        };
+        self.texture.uv_color_at(u, v)
+    }
+}
+
+/// CubeMap paints a different texture on each face of a cube from -1 to 1, such as the faces of a die. As cube_map
+/// only looks at the direction of a point, a cube map on a large sphere around a scene makes a skybox, with each
+/// face of the sky seen in the direction of that face. The sky's material wants an ambient of 1 with no diffuse or
+/// specular, so it isn't shaded by the lights, and casts_shadow turned off so it doesn't hide them.
+#[derive(Debug, Clone, PartialEq, PatternDerive)]
+pub struct CubeMap {
+    /// faces holds the texture for each face in the order of CubeFace: left, front, right, back, up and down.
+    faces: [UvTexture; 6],
+    transform: Matrix,
+    inverse_transform: Matrix,
+}
+
+impl CubeMap {
+    /// new makes a cube map from the textures for the left, front, right, back, up and down faces.
+    pub fn new(faces: [UvTexture; 6], transform: Option<Matrix>) -> Self {
+        Self {
+            faces,
+            transform: transform.clone().unwrap_or_default(),
+            inverse_transform: transform
+                .unwrap_or_default()
+                .inverse()
+                .expect("trying to invert a matrix that cannot be inverted"),
+        }
+    }
+
+    pub fn face(&self, face: CubeFace) -> &UvTexture {
+        &self.faces[face as usize]
+    }
+}
+
+impl Pattern for CubeMap {
+    fn local_color_at(&self, pattern_point: Point) -> Color {
+        let (face, (u, v)) = cube_map(pattern_point);
+        self.face(face).uv_color_at(u, v)
+    }
+}
+
+#[cfg(test)]
+mod test_texture_map {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use crate::{
+        comparison::approx_eq,
+        primatives::transformation::scaling,
+        shapes::patterns::{striped::StripePattern, PatternBase},
+        C, P,
+    };
+
+    use super::*;
+
+    fn assert_uv(expected: (f64, f64), actual: (f64, f64)) {
+        assert!(
+            approx_eq(expected.0, actual.0) && approx_eq(expected.1, actual.1),
+            "expected {:?} but got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_uv_checkers() {
+        let checkers = UvCheckers::new(2., 2., Color::BLACK, Color::WHITE);
+        assert_eq!(Color::BLACK, checkers.uv_color_at(0., 0.));
+        assert_eq!(Color::WHITE, checkers.uv_color_at(0.5, 0.));
+        assert_eq!(Color::WHITE, checkers.uv_color_at(0., 0.5));
+        assert_eq!(Color::BLACK, checkers.uv_color_at(0.5, 0.5));
+        assert_eq!(Color::BLACK, checkers.uv_color_at(1., 1.));
+    }
+
+    #[test]
+    fn test_uv_image() {
+        // a 10x10 image with each pixel colored by its position.
+        let mut canvas = Canvas::new(10, 10);
+        for x in 0..10 {
+            for y in 0..10 {
+                canvas.write_pixel(x, y, Color::new(x as f64 / 10., y as f64 / 10., 0.));
+            }
+        }
+        let image = UvImage::new(canvas);
+        let cases = [
+            (0., 0., Color::new(0., 0.9, 0.)),
+            (0.3, 0., Color::new(0.3, 0.9, 0.)),
+            (0.6, 0.3, Color::new(0.5, 0.6, 0.)),
+            (1., 1., Color::new(0.9, 0., 0.)),
+            // outside of the image takes the nearest edge.
+            (-0.5, 1.5, Color::new(0., 0., 0.)),
+        ];
+        for (u, v, color) in cases {
+            assert_eq!(color, image.uv_color_at(u, v), "({}, {})", u, v);
+        }
+    }
+
+    #[test]
+    fn test_uv_image_address_modes() {
+        // a 5x1 image getting brighter from left to right.
+        let mut canvas = Canvas::new(5, 1);
+        for x in 0..5 {
+            let c = x as f64 / 4.;
+            canvas.write_pixel(x, 0, Color::new(c, c, c));
+        }
+        let mut image = UvImage::new(canvas);
+        assert_eq!(AddressMode::Clamp, image.address_mode());
+        assert_eq!(C![1., 1., 1.], image.uv_color_at(1.25, 0.));
+        assert_eq!(C![0., 0., 0.], image.uv_color_at(-0.75, 0.));
+
+        image.set_address_mode(AddressMode::Repeat);
+        assert_eq!(C![0.25, 0.25, 0.25], image.uv_color_at(1.25, 0.));
+        assert_eq!(C![0.25, 0.25, 0.25], image.uv_color_at(-0.75, 0.));
+
+        image.set_address_mode(AddressMode::Mirror);
+        assert_eq!(C![0.75, 0.75, 0.75], image.uv_color_at(1.25, 0.));
+        assert_eq!(C![0.75, 0.75, 0.75], image.uv_color_at(-0.75, 0.));
+    }
+
+    #[test]
+    fn test_cylindrical_map() {
+        let h = FRAC_1_SQRT_2;
+        let cases = [
+            (P![0., 0., -1.], (0., 0.)),
+            (P![0., 0.5, -1.], (0., 0.5)),
+            (P![0., 1., -1.], (0., 0.)),
+            (P![h, 0.5, -h], (0.125, 0.5)),
+            (P![1., 0.5, 0.], (0.25, 0.5)),
+            (P![h, 0.5, h], (0.375, 0.5)),
+            (P![0., -0.25, 1.], (0.5, 0.75)),
+            (P![-h, 0.75, h], (0.625, 0.75)),
+            (P![-1., 1.25, 0.], (0.75, 0.25)),
+            (P![-h, 0.5, -h], (0.875, 0.5)),
+        ];
+        for (point, uv) in cases {
+            assert_uv(uv, cylindrical_map(point));
+        }
+    }
+
+    #[test]
+    fn test_conical_map() {
+        // u goes around the cone just like a cylinder.
+        assert_uv((0.25, FRAC_1_SQRT_2), conical_map(P![0.5, 0.5, 0.]));
+        assert_uv((0.75, FRAC_1_SQRT_2), conical_map(P![-0.5, 0.5, 0.]));
+        // v is the distance along the slope, which is √2 times the height on a cone with sides at 45°.
+        assert_uv(
+            (0., 0.5),
+            conical_map(P![0., 0.5 * FRAC_1_SQRT_2, -0.5 * FRAC_1_SQRT_2]),
+        );
+        assert_uv(
+            (0., (2f64.sqrt()).rem_euclid(1.)),
+            conical_map(P![0., 1., -1.]),
+        );
+        // below the tip the distance is negative.
+        assert_uv(
+            (0., 0.5),
+            conical_map(P![0., -0.5 * FRAC_1_SQRT_2, -0.5 * FRAC_1_SQRT_2]),
+        );
+    }
+
+    #[test]
+    fn test_spherical_map() {
+        let h = FRAC_1_SQRT_2;
+        let cases = [
+            (P![0., 0., -1.], (0., 0.5)),
+            (P![1., 0., 0.], (0.25, 0.5)),
+            (P![0., 0., 1.], (0.5, 0.5)),
+            (P![-1., 0., 0.], (0.75, 0.5)),
+            (P![0., 1., 0.], (0.5, 1.)),
+            (P![0., -1., 0.], (0.5, 0.)),
+            (P![h, h, 0.], (0.25, 0.75)),
+        ];
+        for (point, uv) in cases {
+            assert_uv(uv, spherical_map(point));
+        }
+    }
+
+    #[test]
+    fn test_cube_face() {
+        let cases = [
+            (P![-1., 0.5, -0.25], CubeFace::Left),
+            (P![1.1, -0.75, 0.8], CubeFace::Right),
+            (P![0.1, 0.6, 0.9], CubeFace::Front),
+            (P![-0.7, 0., -2.], CubeFace::Back),
+            (P![0.5, 1., 0.9], CubeFace::Up),
+            (P![-0.2, -1.3, 1.1], CubeFace::Down),
+        ];
+        for (point, face) in cases {
+            assert_eq!(face, CubeFace::from_point(point), "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn test_cube_map() {
+        // on every face the first point is in the top left quarter and the second in the bottom right.
+        let cases = [
+            (CubeFace::Front, P![-0.5, 0.5, 1.], P![0.5, -0.5, 1.]),
+            (CubeFace::Back, P![0.5, 0.5, -1.], P![-0.5, -0.5, -1.]),
+            (CubeFace::Left, P![-1., 0.5, -0.5], P![-1., -0.5, 0.5]),
+            (CubeFace::Right, P![1., 0.5, 0.5], P![1., -0.5, -0.5]),
+            (CubeFace::Up, P![-0.5, 1., -0.5], P![0.5, 1., 0.5]),
+            (CubeFace::Down, P![-0.5, -1., 0.5], P![0.5, -1., -0.5]),
+        ];
+        for (face, top_left, bottom_right) in cases {
+            assert_eq!(face, cube_map(top_left).0);
+            assert_uv((0.25, 0.75), cube_map(top_left).1);
+            assert_uv((0.75, 0.25), cube_map(bottom_right).1);
+        }
+    }
+
+    #[test]
+    fn test_cube_texture_map() {
+        // a die, with a different color on each face.
+        let colors = [
+            Color::new(1., 0., 0.),
+            Color::new(0., 1., 0.),
+            Color::new(0., 0., 1.),
+            Color::new(1., 1., 0.),
+            Color::new(0., 1., 1.),
+            Color::new(1., 0., 1.),
+        ];
+        let die = CubeMap::new(colors.map(|c| UvCheckers::new(1., 1., c, c).into()), None);
+        assert_eq!(colors[0], die.local_color_at(P![-1., 0.2, 0.3]));
+        assert_eq!(colors[1], die.local_color_at(P![0.2, 0.3, 1.]));
+        assert_eq!(colors[2], die.local_color_at(P![1., -0.2, 0.3]));
+        assert_eq!(colors[3], die.local_color_at(P![0.2, 0.3, -1.]));
+        assert_eq!(colors[4], die.local_color_at(P![0.2, 1., 0.3]));
+        assert_eq!(colors[5], die.local_color_at(P![0.2, -1., 0.3]));
+
+        // the cube mapping repeats one texture on every face.
+        let checkers = UvCheckers::new(2., 2., Color::BLACK, Color::WHITE);
+        let pattern = TextureMap::new(checkers, UvMapping::Cube, None);
+        for point in [P![-0.5, 0.5, 1.], P![1., 0.5, 0.5], P![-0.5, 1., -0.5]] {
+            assert_eq!(Color::WHITE, pattern.local_color_at(point));
+        }
+    }
+
+    #[test]
+    fn test_align_check() {
+        let check = AlignCheck::new(
+            Color::WHITE,
+            C![1., 0., 0.],
+            C![1., 1., 0.],
+            C![0., 1., 0.],
+            C![0., 1., 1.],
+        );
+        assert_eq!(Color::WHITE, check.uv_color_at(0.5, 0.5));
+        assert_eq!(C![1., 0., 0.], check.uv_color_at(0.1, 0.9));
+        assert_eq!(C![1., 1., 0.], check.uv_color_at(0.9, 0.9));
+        assert_eq!(C![0., 1., 0.], check.uv_color_at(0.1, 0.1));
+        assert_eq!(C![0., 1., 1.], check.uv_color_at(0.9, 0.1));
+    }
+
+    #[test]
+    fn test_skybox() {
+        // every face has a red upper left and a yellow upper right corner, so along the edges between the four
+        // sides a red corner always meets a yellow one.
+        let (red, yellow) = (C![1., 0., 0.], C![1., 1., 0.]);
+        let face = || AlignCheck::new(Color::WHITE, red, yellow, Color::BLACK, Color::BLACK).into();
+        let sky = CubeMap::new([face(), face(), face(), face(), face(), face()], None);
+        for (side, other) in [
+            (P![-0.9, 0.9, 1.], P![-1., 0.9, 0.9]),
+            (P![0.9, 0.9, 1.], P![1., 0.9, 0.9]),
+            (P![0.9, 0.9, -1.], P![1., 0.9, -0.9]),
+            (P![-0.9, 0.9, -1.], P![-1., 0.9, -0.9]),
+        ] {
+            let colors = [sky.local_color_at(side), sky.local_color_at(other)];
+            assert!(
+                colors.contains(&red) && colors.contains(&yellow),
+                "{:?}",
+                side
+            );
+        }
+
+        // only the direction from the centre matters, so the sky can be painted on a sphere of any size.
+        assert_eq!(red, sky.local_color_at(P![-90., 90., 100.]));
+        assert_eq!(Color::WHITE, sky.local_color_at(P![0., 0.5, 0.5]));
+
+        // faces can be any pattern, here stripes across the sky above.
+        let stripes = StripePattern::new(Color::WHITE, Color::BLACK, Some(scaling(0.25, 1., 1.)));
+        let mut faces = [face(), face(), face(), face(), face(), face()];
+        faces[CubeFace::Up as usize] = stripes.box_clone().into();
+        let sky = CubeMap::new(faces, None);
+        assert_eq!(Color::WHITE, sky.local_color_at(P![-0.9, 1., 0.]));
+        assert_eq!(Color::BLACK, sky.local_color_at(P![-0.4, 1., 0.]));
+    }
+
+    #[test]
+    fn test_planar_map() {
+        assert_uv((0.25, 0.75), planar_map(P![0.25, 0., -0.25]));
+        assert_uv((0.25, 0.75), planar_map(P![1.25, 0., 0.75]));
+        assert_uv((0.25, 0.25), planar_map(P![0.25, 1., -1.75]));
+    }
+
+    #[test]
+    fn test_texture_map() {
+        // a label wrapped around a cylinder, 4 checks around and 2 up every unit of height.
+        let checkers = UvCheckers::new(4., 2., Color::BLACK, Color::WHITE);
+        let pattern = TextureMap::new(checkers, UvMapping::Cylindrical, None);
+        assert_eq!(Color::BLACK, pattern.local_color_at(P![0., 0., -1.]));
+        assert_eq!(Color::WHITE, pattern.local_color_at(P![0., 0.6, -1.]));
+        assert_eq!(Color::WHITE, pattern.local_color_at(P![1., 0., 0.]));
+    }
+
+    #[test]
+    fn test_spherical_texture_map() {
+        // checks on a sphere keep their shape near the poles, 16 around and 8 from pole to pole.
+        let checkers = UvCheckers::new(16., 8., Color::BLACK, Color::WHITE);
+        let pattern = TextureMap::new(checkers, UvMapping::Spherical, None);
+        let cases = [
+            (P![0.4315, 0.4670, 0.7719], Color::WHITE),
+            (P![-0.9654, 0.2552, -0.0534], Color::BLACK),
+            (P![0.1039, 0.7090, 0.6975], Color::WHITE),
+            (P![-0.4986, -0.7856, -0.3663], Color::BLACK),
+            (P![-0.0317, -0.9395, 0.3411], Color::BLACK),
+            (P![0.4809, -0.7721, 0.4154], Color::BLACK),
+            (P![0.0285, -0.9612, -0.2745], Color::BLACK),
+            (P![-0.5734, -0.2162, -0.7903], Color::WHITE),
+            (P![0.7688, -0.1470, 0.6223], Color::BLACK),
+            (P![-0.7652, 0.2175, 0.6060], Color::BLACK),
+        ];
+        for (point, color) in cases {
+            assert_eq!(color, pattern.local_color_at(point), "{:?}", point);
+        }
+
+        // an image works the same way, here with white at the north pole.
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(0, 0, Color::WHITE);
+        canvas.write_pixel(1, 0, Color::WHITE);
+        let pattern = TextureMap::new(UvImage::new(canvas), UvMapping::Spherical, None);
+        assert_eq!(Color::WHITE, pattern.local_color_at(P![0., 1., 0.]));
+        assert_eq!(Color::BLACK, pattern.local_color_at(P![0., -1., 0.]));
+    }
+}
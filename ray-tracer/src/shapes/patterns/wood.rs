@@ -0,0 +1,114 @@
+use crate::primatives::{color::Color, matrix::Matrix, point::Point, tuple::Tuple};
+
+use super::{marble::DEFAULT_OCTAVES, perlin::turbulence, BoxedPattern, Pattern};
+use pattern_derive::PatternDerive;
+
+/// DEFAULT_DISTORTION is how far, in units, the rings of a wood pattern are pushed in and out by the noise.
+pub const DEFAULT_DISTORTION: f64 = 0.2;
+
+/// WoodPattern is the grain of wood cut along the trunk, rings around the y axis like a ring pattern but fading
+/// from a to b across each ring, so each ring has a hard edge on the outside like a tree's growth rings.
+/// The distance from the axis is pushed in and out by turbulence so the rings wobble like real grain.
+#[derive(Debug, Clone, PartialEq, PatternDerive)]
+pub struct WoodPattern {
+    a: BoxedPattern,
+    b: BoxedPattern,
+    spacing: f64,
+    distortion: f64,
+    transform: Matrix,
+    inverse_transform: Matrix,
+}
+
+impl WoodPattern {
+    /// new makes a wood pattern with a ring every spacing units out from the y axis.
+    pub fn new(
+        a: impl Into<BoxedPattern>,
+        b: impl Into<BoxedPattern>,
+        spacing: f64,
+        transform: Option<Matrix>,
+    ) -> Self {
+        let mut pattern = Self {
+            a: a.into(),
+            b: b.into(),
+            spacing: 1.,
+            distortion: DEFAULT_DISTORTION,
+            transform: transform.clone().unwrap_or_default(),
+            inverse_transform: transform
+                .unwrap_or_default()
+                .inverse()
+                .expect("trying to invert a matrix that cannot be inverted"),
+        };
+        pattern.set_spacing(spacing);
+        pattern
+    }
+
+    pub fn spacing(&self) -> f64 {
+        self.spacing
+    }
+
+    pub fn set_spacing(&mut self, spacing: f64) {
+        assert!(spacing > 0., "the rings of wood must be more than 0 apart");
+        self.spacing = spacing;
+    }
+
+    pub fn distortion(&self) -> f64 {
+        self.distortion
+    }
+
+    pub fn set_distortion(&mut self, distortion: f64) {
+        assert!(distortion >= 0., "wood distortion cannot be negative");
+        self.distortion = distortion;
+    }
+}
+
+impl Pattern for WoodPattern {
+    fn local_color_at(&self, pattern_point: Point) -> Color {
+        let (a, b) = (
+            self.a.nested_color_at(pattern_point),
+            self.b.nested_color_at(pattern_point),
+        );
+        let radius = (pattern_point.x().powi(2) + pattern_point.z().powi(2)).sqrt()
+            + self.distortion * turbulence(pattern_point, DEFAULT_OCTAVES);
+        let fraction = (radius / self.spacing).fract();
+        a + (b - a) * fraction
+    }
+}
+
+#[cfg(test)]
+mod test_wood {
+    use crate::{C, P};
+
+    use super::*;
+
+    #[test]
+    fn test_rings() {
+        let mut p = WoodPattern::new(Color::BLACK, Color::WHITE, 0.5, None);
+        p.set_distortion(0.);
+
+        // each ring fades from a to b and starts again at a, half a unit further out.
+        assert_eq!(Color::BLACK, p.local_color_at(P![0., 0., 0.]));
+        assert_eq!(C![0.5, 0.5, 0.5], p.local_color_at(P![0.25, 3., 0.]));
+        assert_eq!(C![0.5, 0.5, 0.5], p.local_color_at(P![0., -1., 0.75]));
+        assert_eq!(C![0.2, 0.2, 0.2], p.local_color_at(P![0.06, 0., 0.08]));
+    }
+
+    #[test]
+    fn test_grain() {
+        let p = WoodPattern::new(Color::BLACK, Color::WHITE, 0.5, None);
+        let mut straight = p.clone();
+        straight.set_distortion(0.);
+
+        let points: Vec<_> = (1..20)
+            .map(|i| P![0.13 * i as f64, 0.29 * i as f64, 0.07 * i as f64])
+            .collect();
+        assert!(points
+            .iter()
+            .any(|&point| p.local_color_at(point) != straight.local_color_at(point)));
+    }
+
+    #[test]
+    #[should_panic(expected = "the rings of wood must be more than 0 apart")]
+    fn test_bad_spacing() {
+        WoodPattern::new(Color::BLACK, Color::WHITE, 0., None);
+    }
+}
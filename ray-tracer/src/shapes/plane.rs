@@ -1,45 +1,21 @@
 use crate::{
     comparison::EPSILON,
-    primatives::{matrix::Matrix, tuple::Tuple, vector::Vector},
+    primatives::{aabb::Aabb, tuple::Tuple, vector::Vector},
     world::intersection::{Intersection, Intersections},
+    P,
 };
 
-use super::{material::Material, Shape};
+use super::{Shape, ShapeBase, ShapeProps};
+use shape_derive::ShapeDerive;
 
 /// a plane is a flat surface the extends infinitely in two dimensions. The plane travels in the `xz` direction.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default, PartialEq, ShapeDerive)]
+#[shape(new)]
 pub struct Plane {
-    transform: Matrix,
-    inverse_transform: Matrix,
-    material: Material,
-}
-
-impl Plane {
-    pub fn new(transform: Option<Matrix>, material: Option<Material>) -> Self {
-        Self {
-            transform: transform.clone().unwrap_or_default(),
-            inverse_transform: transform
-                .unwrap_or_default()
-                .inverse()
-                .expect("trying to invert a matrix that cannot be inverted"),
-            material: material.unwrap_or_default(),
-        }
-    }
+    props: ShapeProps,
 }
 
 impl Shape for Plane {
-    fn box_clone(&self) -> super::BoxedShape {
-        Box::new(self.clone())
-    }
-
-    fn box_eq(&self, other: &dyn std::any::Any) -> bool {
-        other.downcast_ref::<Self>().map_or(false, |a| self == a)
-    }
-
-    fn as_any(&self) -> &dyn std::any::Any {
-        self
-    }
-
     fn local_intersect(
         &self,
         r: crate::primatives::ray::Ray,
@@ -60,49 +36,21 @@ impl Shape for Plane {
         Vector::new(0., 1., 0.)
     }
 
-    fn material(&self) -> &super::material::Material {
-        &self.material
-    }
-
-    fn transformation(&self) -> &crate::primatives::matrix::Matrix {
-        &self.transform
-    }
-
-    fn inverse_transformation(&self) -> &crate::primatives::matrix::Matrix {
-        &self.inverse_transform
-    }
-}
-
-impl Plane {
-    pub fn set_transform(&mut self, transform: Matrix) {
-        self.transform = transform.clone();
-        self.inverse_transform = transform
-            .inverse()
-            .expect("trying to invert a matrix that cannot be inverted")
+    fn bounds(&self) -> Aabb {
+        Aabb::new(
+            P![f64::NEG_INFINITY, 0., f64::NEG_INFINITY],
+            P![f64::INFINITY, 0., f64::INFINITY],
+        )
     }
-}
-
-impl Default for Plane {
-    fn default() -> Self {
-        Self {
-            transform: Matrix::identity_matrix(),
-            inverse_transform: Matrix::identity_matrix(),
-            material: Material::default(),
-        }
-    }
-}
 
-impl PartialEq for Plane {
-    fn eq(&self, other: &Self) -> bool {
-        self.transform == other.transform
-            && self.inverse_transform == other.inverse_transform
-            && self.material == other.material
+    fn is_planar(&self) -> bool {
+        true
     }
 }
 
 #[cfg(test)]
 mod test_planes {
-    use crate::{primatives::ray::Ray, Tuple, P, V};
+    use crate::{primatives::ray::Ray, primatives::tuple::Tuple, P, V};
 
     use super::*;
 
@@ -127,12 +75,12 @@ mod test_planes {
         // ray is parallel to the plane
         let r = Ray::new(P![0., 10., 0.], V![0., 0., 1.]);
         let xs = p.local_intersect(r);
-        assert!(xs.len() == 0);
+        assert!(xs.is_empty());
 
         // ray is coplanar (every point in ray is on the plane)
         let r = Ray::new(P![0., 0., 0.], V![0., 0., 1.]);
         let xs = p.local_intersect(r);
-        assert!(xs.len() == 0);
+        assert!(xs.is_empty());
 
         // ray is above plane
         let r = Ray::new(P![0., 1., 0.], V![0., -1., 0.]);
@@ -0,0 +1,108 @@
+use super::{Shape, ShapeBase, ShapeProps};
+use crate::{
+    comparison::EPSILON,
+    primatives::aabb::Aabb,
+    primatives::point::Point,
+    primatives::ray::Ray,
+    primatives::tuple::Tuple,
+    primatives::vector::Vector,
+    world::intersection::{Intersection, Intersections},
+    P,
+};
+use shape_derive::ShapeDerive;
+
+/// a quad is a flat square in the xz plane running from -1 to 1 in both x and z.
+/// Unlike a plane it stops at its edges, so it can be scaled and moved into place as a wall or a floor.
+#[derive(Clone, Debug, Default, PartialEq, ShapeDerive)]
+#[shape(new)]
+pub struct Quad {
+    props: ShapeProps,
+}
+
+impl Shape for Quad {
+    /// local_intersect finds where the ray crosses the xz plane and keeps the hit if it lands inside the square.
+    fn local_intersect(&self, r: Ray) -> Intersections {
+        // if the ray is parallel then there are no intersections
+        if r.direction().y().abs() < EPSILON {
+            return Intersections::EMPTY;
+        }
+
+        let t = -r.origin().y() / r.direction().y();
+        let point = r.at(t);
+        if point.x().abs() > 1. || point.z().abs() > 1. {
+            return Intersections::EMPTY;
+        }
+
+        Intersections::new(vec![Intersection::new(t, self.box_clone())])
+    }
+
+    fn local_normal(&self, _point: Point) -> Vector {
+        Vector::new(0., 1., 0.)
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::new(P![-1., 0., -1.], P![1., 0., 1.])
+    }
+
+    fn is_planar(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test_quad {
+    use crate::{comparison::approx_eq, primatives::transformation::scaling, P, V};
+
+    use super::*;
+
+    #[test]
+    fn test_ray_hits() {
+        let q = Quad::default();
+        let cases = [
+            (P![0., 1., 0.], V![0., -1., 0.], 1.),
+            (P![0.9, -2., -0.9], V![0., 1., 0.], 2.),
+            (P![1., 1., -1.], V![0., -1., 0.], 1.),
+        ];
+
+        for (origin, direction, t) in cases {
+            let xs = q.local_intersect(Ray::new(origin, direction));
+            assert_eq!(1, xs.len());
+            assert!(approx_eq(t, xs[0].t()));
+        }
+    }
+
+    #[test]
+    fn test_ray_misses() {
+        let q = Quad::default();
+        let cases = [
+            // parallel to the quad.
+            (P![0., 1., 0.], V![0., 0., 1.]),
+            // past each edge.
+            (P![1.1, 1., 0.], V![0., -1., 0.]),
+            (P![-1.1, 1., 0.], V![0., -1., 0.]),
+            (P![0., 1., 1.1], V![0., -1., 0.]),
+            (P![0., 1., -1.1], V![0., -1., 0.]),
+        ];
+
+        for (origin, direction) in cases {
+            assert_eq!(0, q.local_intersect(Ray::new(origin, direction)).len());
+        }
+    }
+
+    #[test]
+    fn test_normal() {
+        let q = Quad::default();
+        assert_eq!(V![0., 1., 0.], q.local_normal(P![0., 0., 0.]));
+        assert_eq!(V![0., 1., 0.], q.local_normal(P![0.5, 0., -0.5]));
+    }
+
+    #[test]
+    fn test_scaled() {
+        let q = Quad::new(Some(scaling(3., 1., 0.5)), None).box_clone();
+        let r = Ray::new(P![2.5, 1., 0.], V![0., -1., 0.]);
+        assert_eq!(1, q.intersect(r).len());
+
+        let r = Ray::new(P![0., 1., 0.75], V![0., -1., 0.]);
+        assert_eq!(0, q.intersect(r).len());
+    }
+}
@@ -0,0 +1,383 @@
+use ndarray::arr2;
+
+use super::{material::Material, Shape, ShapeBase, ShapeProps};
+use crate::{
+    primatives::aabb::Aabb,
+    primatives::matrix::Matrix,
+    primatives::point::Point,
+    primatives::ray::Ray,
+    primatives::solvers,
+    primatives::tuple::Tuple,
+    primatives::vector::Vector,
+    world::intersection::{Intersection, Intersections},
+    P,
+};
+use shape_derive::ShapeDerive;
+
+/// a quadric is the surface where
+/// ax² + by² + cz² + dxy + exz + fyz + gx + hy + iz + j = 0,
+/// which covers ellipsoids, paraboloids, hyperboloids, cones and cylinders along any axis.
+/// The coefficients are held in that order. Many quadrics go on forever, so they can be clipped to an extent.
+#[derive(Clone, Debug, PartialEq, ShapeDerive)]
+pub struct Quadric {
+    coefficients: [f64; 10],
+    /// extent is the box hits must be inside, which is infinite unless the quadric has been clipped.
+    extent: Aabb,
+    props: ShapeProps,
+}
+
+impl Quadric {
+    pub fn new(
+        coefficients: [f64; 10],
+        transform: Option<Matrix>,
+        material: Option<Material>,
+    ) -> Self {
+        assert!(
+            coefficients[..9].iter().any(|&c| c != 0.),
+            "a quadric needs at least one coefficient that depends on the position"
+        );
+        Self {
+            coefficients,
+            extent: Aabb::infinite(),
+            props: ShapeProps::new(transform, material),
+        }
+    }
+
+    /// from_matrix makes the quadric pᵀQp = 0 from its 4x4 coefficient matrix Q, where p is the point (x, y, z, 1).
+    /// The matrix doesn't need to be symmetric, each pair of opposite elements is added together.
+    pub fn from_matrix(q: &Matrix, transform: Option<Matrix>, material: Option<Material>) -> Self {
+        let element = |row: usize, column: usize| {
+            *q.get(row, column)
+                .expect("a quadric's coefficient matrix is 4x4")
+        };
+        let pair = |row: usize, column: usize| element(row, column) + element(column, row);
+        Self::new(
+            [
+                element(0, 0),
+                element(1, 1),
+                element(2, 2),
+                pair(0, 1),
+                pair(0, 2),
+                pair(1, 2),
+                pair(0, 3),
+                pair(1, 3),
+                pair(2, 3),
+                element(3, 3),
+            ],
+            transform,
+            material,
+        )
+    }
+
+    /// matrix is the symmetric 4x4 coefficient matrix of the quadric, the inverse of from_matrix.
+    pub fn matrix(&self) -> Matrix {
+        let [a, b, c, d, e, f, g, h, i, j] = self.coefficients;
+        Matrix::new(arr2(&[
+            [a, d / 2., e / 2., g / 2.],
+            [d / 2., b, f / 2., h / 2.],
+            [e / 2., f / 2., c, i / 2.],
+            [g / 2., h / 2., i / 2., j],
+        ]))
+    }
+
+    /// ellipsoid is centred on the origin with the given radii along x, y and z.
+    pub fn ellipsoid(
+        radii: (f64, f64, f64),
+        transform: Option<Matrix>,
+        material: Option<Material>,
+    ) -> Self {
+        let (a, b, c) = radii;
+        let mut coefficients = [0.; 10];
+        coefficients[..3].copy_from_slice(&[1. / (a * a), 1. / (b * b), 1. / (c * c)]);
+        coefficients[9] = -1.;
+        Self::new(coefficients, transform, material)
+    }
+
+    /// paraboloid is the bowl y = x² + z² opening upwards from the origin.
+    pub fn paraboloid(transform: Option<Matrix>, material: Option<Material>) -> Self {
+        Self::new(
+            [1., 0., 1., 0., 0., 0., 0., -1., 0., 0.],
+            transform,
+            material,
+        )
+    }
+
+    /// hyperboloid is x² - y² + z² = waist², a single sheet around the y axis that is narrowest at y = 0.
+    /// A waist of 0 is a double cone and a negative waist gives two separate sheets above and below the origin.
+    pub fn hyperboloid(waist: f64, transform: Option<Matrix>, material: Option<Material>) -> Self {
+        Self::new(
+            [1., -1., 1., 0., 0., 0., 0., 0., 0., -waist * waist.abs()],
+            transform,
+            material,
+        )
+    }
+
+    pub fn coefficients(&self) -> &[f64; 10] {
+        &self.coefficients
+    }
+
+    pub fn extent(&self) -> Aabb {
+        self.extent
+    }
+
+    /// set_extent clips the quadric to the parts inside the box, leaving the surface open where it is cut.
+    pub fn set_extent(&mut self, extent: Aabb) {
+        self.extent = extent;
+    }
+
+    /// value is the left hand side of the quadric's equation at a point, 0 on the surface.
+    pub fn value(&self, p: Point) -> f64 {
+        let [a, b, c, d, e, f, g, h, i, j] = self.coefficients;
+        let (x, y, z) = (p.x(), p.y(), p.z());
+        a * x * x
+            + b * y * y
+            + c * z * z
+            + d * x * y
+            + e * x * z
+            + f * y * z
+            + g * x
+            + h * y
+            + i * z
+            + j
+    }
+}
+
+impl Shape for Quadric {
+    /// local_intersect substitutes the ray into the quadric's equation, which gives a quadratic in t.
+    /// It becomes linear when the ray runs parallel to an open side, such as along the axis of a paraboloid.
+    fn local_intersect(&self, r: Ray) -> Intersections {
+        let [a, b, c, d, e, f, g, h, i, _] = self.coefficients;
+        let (o, v) = (r.origin(), r.direction());
+
+        let t_sq = a * v.x() * v.x()
+            + b * v.y() * v.y()
+            + c * v.z() * v.z()
+            + d * v.x() * v.y()
+            + e * v.x() * v.z()
+            + f * v.y() * v.z();
+        let t_linear = 2. * (a * o.x() * v.x() + b * o.y() * v.y() + c * o.z() * v.z())
+            + d * (o.x() * v.y() + o.y() * v.x())
+            + e * (o.x() * v.z() + o.z() * v.x())
+            + f * (o.y() * v.z() + o.z() * v.y())
+            + g * v.x()
+            + h * v.y()
+            + i * v.z();
+
+        let hits = solvers::quadratic(t_sq, t_linear, self.value(o))
+            .into_iter()
+            .filter(|&t| self.extent.contains_point(r.at(t)))
+            .map(|t| Intersection::new(t, self.box_clone()))
+            .collect();
+
+        Intersections::new(hits)
+    }
+
+    /// local_normal is the gradient of the quadric's equation, which points towards where it is positive.
+    fn local_normal(&self, p: Point) -> Vector {
+        let [a, b, c, d, e, f, g, h, i, _] = self.coefficients;
+        let (x, y, z) = (p.x(), p.y(), p.z());
+        Vector::new(
+            2. * a * x + d * y + e * z + g,
+            2. * b * y + d * x + f * z + h,
+            2. * c * z + e * x + f * y + i,
+        )
+    }
+
+    fn bounds(&self) -> Aabb {
+        let [a, b, c, d, e, f, g, h, i, j] = self.coefficients;
+        let is_ellipsoid =
+            [d, e, f, g, h, i].iter().all(|&k| k == 0.) && a > 0. && b > 0. && c > 0. && j < 0.;
+        if !is_ellipsoid {
+            return self.extent;
+        }
+        let (x, y, z) = ((-j / a).sqrt(), (-j / b).sqrt(), (-j / c).sqrt());
+        let ellipsoid = Aabb::new(P![-x, -y, -z], P![x, y, z]);
+        Aabb::new(
+            P![
+                ellipsoid.min().x().max(self.extent.min().x()),
+                ellipsoid.min().y().max(self.extent.min().y()),
+                ellipsoid.min().z().max(self.extent.min().z())
+            ],
+            P![
+                ellipsoid.max().x().min(self.extent.max().x()),
+                ellipsoid.max().y().min(self.extent.max().y()),
+                ellipsoid.max().z().min(self.extent.max().z())
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_quadric {
+    use crate::{
+        comparison::approx_eq,
+        primatives::{transformation::translation, vector::dot},
+        shapes::sphere::Sphere,
+        V,
+    };
+
+    use super::*;
+
+    fn assert_hits(expected: &[f64], xs: Intersections) {
+        assert_eq!(expected.len(), xs.len());
+        for (i, t) in expected.iter().enumerate() {
+            assert!(
+                approx_eq(*t, xs[i].t()),
+                "expected {} but got {}",
+                t,
+                xs[i].t()
+            );
+        }
+    }
+
+    #[test]
+    fn test_unit_sphere_matches_sphere() {
+        let q = Quadric::ellipsoid((1., 1., 1.), None, None);
+        let s = Sphere::default();
+        let rays = [
+            Ray::new(P![0., 0., -5.], V![0., 0., 1.]),
+            Ray::new(P![0., 0.5, -5.], V![0.1, 0., 1.]),
+            Ray::new(P![0., 0., 0.], V![1., 1., 0.]),
+            Ray::new(P![0., 2., -5.], V![0., 0., 1.]),
+        ];
+        for r in rays {
+            let expected: Vec<f64> = s.local_intersect(r).iter().map(|i| i.t()).collect();
+            assert_hits(&expected, q.local_intersect(r));
+        }
+        assert_eq!(V![0., 0., 2.], q.local_normal(P![0., 0., 1.]));
+        assert_eq!(s.bounds(), q.bounds());
+    }
+
+    #[test]
+    fn test_from_matrix() {
+        // the unit sphere x² + y² + z² - 1 = 0.
+        let q = Quadric::from_matrix(
+            &Matrix::new(arr2(&[
+                [1., 0., 0., 0.],
+                [0., 1., 0., 0.],
+                [0., 0., 1., 0.],
+                [0., 0., 0., -1.],
+            ])),
+            None,
+            None,
+        );
+        assert_eq!(Quadric::ellipsoid((1., 1., 1.), None, None), q);
+
+        // opposite elements are added so lopsided matrices give the same surface as symmetric ones.
+        let lopsided = Matrix::new(arr2(&[
+            [1., 2., 0., 0.],
+            [0., 0., 0., -1.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 0.],
+        ]));
+        let q = Quadric::from_matrix(&lopsided, None, None);
+        assert_eq!(&[1., 0., 1., 2., 0., 0., 0., -1., 0., 0.], q.coefficients());
+        assert_eq!(q, Quadric::from_matrix(&q.matrix(), None, None));
+        assert_eq!(q.matrix(), q.matrix().transpose());
+    }
+
+    #[test]
+    fn test_ellipsoid() {
+        let q = Quadric::ellipsoid((2., 1., 0.5), None, None);
+        assert_hits(
+            &[3., 7.],
+            q.local_intersect(Ray::new(P![-5., 0., 0.], V![1., 0., 0.])),
+        );
+        assert_hits(
+            &[4.5, 5.5],
+            q.local_intersect(Ray::new(P![0., 0., -5.], V![0., 0., 1.])),
+        );
+        assert_eq!(Aabb::new(P![-2., -1., -0.5], P![2., 1., 0.5]), q.bounds());
+    }
+
+    #[test]
+    fn test_paraboloid() {
+        let q = Quadric::paraboloid(None, None);
+
+        // straight down the axis the equation is linear and there is a single hit at the bottom of the bowl.
+        assert_hits(
+            &[5.],
+            q.local_intersect(Ray::new(P![0., 5., 0.], V![0., -1., 0.])),
+        );
+        // across the bowl at y = 4 it is two units wide either side.
+        assert_hits(
+            &[3., 7.],
+            q.local_intersect(Ray::new(P![-5., 4., 0.], V![1., 0., 0.])),
+        );
+        // the normal points out of the bowl, away from the inside where the equation is negative.
+        let n = q.normal(P![1., 1., 0.]);
+        assert!(dot(n, V![1., 0., 0.]) > 0.);
+        assert!(dot(n, V![0., 1., 0.]) < 0.);
+        assert!(!q.bounds().is_finite());
+    }
+
+    #[test]
+    fn test_hyperboloid() {
+        let q = Quadric::hyperboloid(1., None, None);
+        // the waist is one unit wide at y = 0 and wider further up.
+        assert_hits(
+            &[4., 6.],
+            q.local_intersect(Ray::new(P![-5., 0., 0.], V![1., 0., 0.])),
+        );
+        let wide = 2.0_f64.sqrt();
+        assert_hits(
+            &[5. - wide, 5. + wide],
+            q.local_intersect(Ray::new(P![-5., 1., 0.], V![1., 0., 0.])),
+        );
+
+        // two sheets leave a gap around the origin.
+        let q = Quadric::hyperboloid(-1., None, None);
+        assert_eq!(
+            0,
+            q.local_intersect(Ray::new(P![-5., 0., 0.], V![1., 0., 0.]))
+                .len()
+        );
+        assert_hits(
+            &[4., 6.],
+            q.local_intersect(Ray::new(P![0., -5., 0.], V![0., 1., 0.])),
+        );
+    }
+
+    #[test]
+    fn test_extent() {
+        let mut q = Quadric::hyperboloid(1., None, None);
+        q.set_extent(Aabb::new(
+            P![f64::NEG_INFINITY, -1., f64::NEG_INFINITY],
+            P![f64::INFINITY, 1., f64::INFINITY],
+        ));
+        assert_eq!(q.extent(), q.bounds());
+
+        // across the waist both sides are inside the extent, but higher up they have been cut away.
+        let wide = 1.25_f64.sqrt();
+        assert_hits(
+            &[5. - wide, 5. + wide],
+            q.local_intersect(Ray::new(P![-5., 0.5, 0.], V![1., 0., 0.])),
+        );
+        assert_eq!(
+            0,
+            q.local_intersect(Ray::new(P![-5., 3., 0.], V![1., 0., 0.]))
+                .len()
+        );
+        // looking down the middle of the open end misses it.
+        assert_eq!(
+            0,
+            q.local_intersect(Ray::new(P![0., 5., 0.], V![0., -1., 0.]))
+                .len()
+        );
+    }
+
+    #[test]
+    fn test_in_world() {
+        let q = Quadric::ellipsoid((1., 1., 1.), Some(translation(0., 0., 2.)), None).box_clone();
+        assert_hits(
+            &[6., 8.],
+            q.intersect(Ray::new(P![0., 0., -5.], V![0., 0., 1.])),
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_needs_a_surface() {
+        Quadric::new([0., 0., 0., 0., 0., 0., 0., 0., 0., 1.], None, None);
+    }
+}
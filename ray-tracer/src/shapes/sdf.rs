@@ -0,0 +1,244 @@
+use std::{fmt, sync::Arc};
+
+use super::{material::Material, Shape, ShapeBase, ShapeProps};
+use crate::{
+    comparison::EPSILON,
+    primatives::aabb::Aabb,
+    primatives::matrix::Matrix,
+    primatives::point::Point,
+    primatives::ray::Ray,
+    primatives::vector::Vector,
+    world::intersection::{Intersection, Intersections},
+    V,
+};
+use shape_derive::ShapeDerive;
+
+/// MAX_STEPS is how many steps a ray may take through the bounds before the march gives up.
+const MAX_STEPS: usize = 512;
+
+/// HIT_DISTANCE is how close to the surface a step must land to count as a hit. It is well inside EPSILON so that
+/// points over and under the surface end up on the right sides of it.
+const HIT_DISTANCE: f64 = EPSILON / 10.;
+
+/// NORMAL_STEP is how far either side of a point the distance is sampled to estimate the normal.
+const NORMAL_STEP: f64 = 0.0001;
+
+/// DistanceFn returns the distance from a point in object space to the nearest surface of a shape,
+/// negative for points inside it.
+pub type DistanceFn = Arc<dyn Fn(Point) -> f64 + Send + Sync>;
+
+/// SdfShape is a surface described by a signed distance function rather than an equation, which lets shapes such as
+/// fractals and blobs be rendered. The function may underestimate the distance but must never overestimate it,
+/// otherwise rays can step straight through thin parts of the surface.
+#[derive(Clone, ShapeDerive)]
+pub struct SdfShape {
+    distance: DistanceFn,
+    /// bounds is the box the whole surface lies inside, rays are only marched where they cross it.
+    bounds: Aabb,
+    props: ShapeProps,
+}
+
+impl SdfShape {
+    pub fn new(
+        distance: impl Fn(Point) -> f64 + Send + Sync + 'static,
+        bounds: Aabb,
+        transform: Option<Matrix>,
+        material: Option<Material>,
+    ) -> Self {
+        assert!(
+            bounds.is_finite(),
+            "a distance function shape needs finite bounds to march through"
+        );
+        Self {
+            distance: Arc::new(distance),
+            bounds,
+            props: ShapeProps::new(transform, material),
+        }
+    }
+
+    /// distance is the value of the shape's distance function at a point in object space.
+    pub fn distance(&self, point: Point) -> f64 {
+        (self.distance)(point)
+    }
+}
+
+/// smooth_min joins two distances with a rounded seam of about the given size, so that shapes combined with it
+/// flow into each other like blobs. A size of 0 is the same as a plain union.
+pub fn smooth_min(a: f64, b: f64, size: f64) -> f64 {
+    if size <= 0. {
+        return a.min(b);
+    }
+    let h = (0.5 + 0.5 * (b - a) / size).clamp(0., 1.);
+    b + (a - b) * h - size * h * (1. - h)
+}
+
+impl Shape for SdfShape {
+    /// local_intersect sphere traces the ray through the bounds. Each step moves the ray on by the distance to the
+    /// nearest surface, which can never carry it past one, and a hit is found where that distance becomes tiny.
+    /// Rays start marching again just past each hit so that they also find where they leave the shape.
+    fn local_intersect(&self, r: Ray) -> Intersections {
+        let Some((start, end)) = self.bounds.span(r) else {
+            return Intersections::new(vec![]);
+        };
+        // the distance function measures object space, so steps are scaled to the length of the ray's direction.
+        let scale = r.direction().magnitude();
+        let mut hits = vec![];
+        let mut t = start;
+        let mut on_surface = false;
+        for _ in 0..MAX_STEPS {
+            if t > end {
+                break;
+            }
+            let distance = self.distance(r.at(t)).abs();
+            if distance < HIT_DISTANCE {
+                if !on_surface {
+                    hits.push(Intersection::new(t, self.box_clone()));
+                    on_surface = true;
+                }
+                t += 2. * HIT_DISTANCE / scale;
+            } else {
+                on_surface = false;
+                t += distance / scale;
+            }
+        }
+        Intersections::new(hits)
+    }
+
+    /// local_normal is the gradient of the distance function, estimated from central differences.
+    fn local_normal(&self, point: Point) -> Vector {
+        let difference =
+            |offset: Vector| self.distance(point + offset) - self.distance(point - offset);
+        Vector::new(
+            difference(V![NORMAL_STEP, 0., 0.]),
+            difference(V![0., NORMAL_STEP, 0.]),
+            difference(V![0., 0., NORMAL_STEP]),
+        )
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+}
+
+impl fmt::Debug for SdfShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SdfShape")
+            .field("bounds", &self.bounds)
+            .field("props", &self.props)
+            .finish_non_exhaustive()
+    }
+}
+
+/// shapes are only equal when they share the same distance function, as two functions can't be compared.
+impl PartialEq for SdfShape {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.distance, &other.distance)
+            && self.bounds == other.bounds
+            && self.props == other.props
+    }
+}
+
+#[cfg(test)]
+mod test_sdf {
+    use crate::{
+        comparison::approx_eq,
+        primatives::{point::ORIGIN, transformation::scaling, tuple::Tuple},
+        P,
+    };
+
+    use super::*;
+
+    fn sphere(radius: f64) -> SdfShape {
+        SdfShape::new(
+            move |p: Point| (p - ORIGIN).magnitude() - radius,
+            Aabb::new(P![-radius, -radius, -radius], P![radius, radius, radius]),
+            None,
+            None,
+        )
+    }
+
+    fn assert_hits(expected: &[f64], xs: Intersections) {
+        assert_eq!(expected.len(), xs.len());
+        for (i, t) in expected.iter().enumerate() {
+            assert!(
+                approx_eq(*t, xs[i].t()),
+                "expected {} but got {}",
+                t,
+                xs[i].t()
+            );
+        }
+    }
+
+    #[test]
+    fn test_sphere_trace() {
+        let s = sphere(1.);
+
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+        assert_hits(&[4., 6.], s.local_intersect(r));
+
+        // starting inside finds the hit behind the ray as well.
+        let r = Ray::new(ORIGIN, V![0., 0., 1.]);
+        assert_hits(&[-1., 1.], s.local_intersect(r));
+
+        // missing the bounds or passing by the surface inside them.
+        let r = Ray::new(P![0., 2., -5.], V![0., 0., 1.]);
+        assert_eq!(0, s.local_intersect(r).len());
+        let r = Ray::new(P![0.9, 0.9, -5.], V![0., 0., 1.]);
+        assert_eq!(0, s.local_intersect(r).len());
+    }
+
+    #[test]
+    fn test_transformed() {
+        let s = SdfShape::new(
+            |p: Point| (p - ORIGIN).magnitude() - 1.,
+            Aabb::new(P![-1., -1., -1.], P![1., 1., 1.]),
+            Some(scaling(2., 2., 2.)),
+            None,
+        );
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+        assert_hits(&[3., 7.], s.intersect(r));
+    }
+
+    #[test]
+    fn test_normal() {
+        let s = sphere(1.).box_clone();
+        let sqrt = 3.0_f64.sqrt() / 3.;
+        let cases = [
+            (P![1., 0., 0.], V![1., 0., 0.]),
+            (P![0., -1., 0.], V![0., -1., 0.]),
+            (P![sqrt, sqrt, sqrt], V![sqrt, sqrt, sqrt]),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(normal, s.normal(point));
+        }
+    }
+
+    #[test]
+    fn test_smooth_min() {
+        assert_eq!(1., smooth_min(1., 2., 0.));
+        // far apart distances are left alone and close ones are pulled below both.
+        assert!(approx_eq(1., smooth_min(1., 3., 0.5)));
+        assert!(smooth_min(1., 1., 0.5) < 1.);
+
+        // two blobs joined smoothly fill in the gap between them.
+        let blob = SdfShape::new(
+            |p: Point| {
+                let a = (p - P![-0.6, 0., 0.]).magnitude() - 0.5;
+                let b = (p - P![0.6, 0., 0.]).magnitude() - 0.5;
+                smooth_min(a, b, 0.5)
+            },
+            Aabb::new(P![-1.2, -0.7, -0.7], P![1.2, 0.7, 0.7]),
+            None,
+            None,
+        );
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+        assert_eq!(2, blob.local_intersect(r).len());
+    }
+
+    #[test]
+    fn test_equality() {
+        let s = sphere(1.);
+        assert_eq!(s, s.clone());
+        assert_ne!(s, sphere(1.));
+    }
+}
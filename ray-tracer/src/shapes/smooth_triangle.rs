@@ -0,0 +1,224 @@
+use super::{
+    material::Material,
+    triangle::{Triangle, TriangleIntersection},
+    Shape, ShapeBase, ShapeProps,
+};
+use crate::{
+    primatives::aabb::Aabb,
+    primatives::matrix::Matrix,
+    primatives::point::Point,
+    primatives::ray::Ray,
+    primatives::vector::{dot, Vector, ZERO},
+    world::intersection::{Intersection, Intersections},
+};
+use shape_derive::ShapeDerive;
+
+/// a smooth triangle is a triangle with a normal at each corner. The normal across the face is
+/// blended between them so that a mesh of them looks like a curved surface rather than flat facets.
+#[derive(Clone, Debug, PartialEq, ShapeDerive)]
+pub struct SmoothTriangle {
+    triangle: Triangle,
+    n1: Vector,
+    n2: Vector,
+    n3: Vector,
+    props: ShapeProps,
+}
+
+impl SmoothTriangle {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        p1: Point,
+        p2: Point,
+        p3: Point,
+        n1: Vector,
+        n2: Vector,
+        n3: Vector,
+        transform: Option<Matrix>,
+        material: Option<Material>,
+    ) -> Self {
+        Self {
+            triangle: Triangle::new(p1, p2, p3, None, None),
+            n1,
+            n2,
+            n3,
+            props: ShapeProps::new(transform, material),
+        }
+    }
+
+    pub fn p1(&self) -> Point {
+        self.triangle.p1()
+    }
+    pub fn p2(&self) -> Point {
+        self.triangle.p2()
+    }
+    pub fn p3(&self) -> Point {
+        self.triangle.p3()
+    }
+    pub fn n1(&self) -> Vector {
+        self.n1
+    }
+    pub fn n2(&self) -> Vector {
+        self.n2
+    }
+    pub fn n3(&self) -> Vector {
+        self.n3
+    }
+
+    /// corners pairs each corner with its normal and how much weight it has at the point.
+    fn corners(&self, point: Point) -> [(Point, Vector, f64); 3] {
+        let (u, v) = self.triangle.barycentric(point);
+        [
+            (self.p1(), self.n1, 1. - u - v),
+            (self.p2(), self.n2, u),
+            (self.p3(), self.n3, v),
+        ]
+    }
+}
+
+impl Shape for SmoothTriangle {
+    fn local_intersect(&self, r: Ray) -> Intersections {
+        match self.triangle.intersection_t(r) {
+            Some(t) => Intersections::new(vec![Intersection::new(t, self.box_clone())]),
+            None => Intersections::EMPTY,
+        }
+    }
+
+    fn local_normal(&self, point: Point) -> Vector {
+        self.corners(point)
+            .into_iter()
+            .fold(ZERO, |normal, (_, n, weight)| normal + n * weight)
+    }
+
+    /// shading_point fixes the shadow terminator. Smooth normals make a coarse mesh look round, but shadow rays
+    /// still leave from the flat face, which sits inside the curved surface and so is shadowed by its neighbours.
+    /// Following Hanika's "Hacking the Shadow Terminator", the point is lifted onto the tangent plane of each corner
+    /// it lies beneath and these are blended by the barycentric weights. Corners the point already lies above don't move it.
+    fn shading_point(&self, point: Point) -> Point {
+        let local = self.world_to_object(point);
+        let offset = self
+            .corners(local)
+            .into_iter()
+            .fold(ZERO, |offset, (corner, n, weight)| {
+                let below = dot(local - corner, n).min(0.);
+                offset - n * (below * weight)
+            });
+        self.transformation().clone() * (local + offset)
+    }
+
+    fn set_triangle_intersection(&mut self, intersection: TriangleIntersection) {
+        self.triangle.set_triangle_intersection(intersection);
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.triangle.bounds()
+    }
+}
+
+#[cfg(test)]
+mod test_smooth_triangle {
+    use crate::{
+        comparison::{approx_eq, EPSILON},
+        primatives::{transformation::translation, tuple::Tuple},
+        P, V,
+    };
+
+    use super::*;
+
+    fn test_triangle() -> SmoothTriangle {
+        SmoothTriangle::new(
+            P![0., 1., 0.],
+            P![-1., 0., 0.],
+            P![1., 0., 0.],
+            V![0., 1., 0.],
+            V![-1., 0., 0.],
+            V![1., 0., 0.],
+            None,
+            None,
+        )
+    }
+
+    /// bulging is a triangle facing -z whose corner normals lean outwards, like a patch of a sphere.
+    fn bulging() -> SmoothTriangle {
+        SmoothTriangle::new(
+            P![0., 1., 0.],
+            P![-1., 0., 0.],
+            P![1., 0., 0.],
+            V![0., 1., -1.].norm(),
+            V![-1., 0., -1.].norm(),
+            V![1., 0., -1.].norm(),
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_intersect() {
+        let t = test_triangle();
+        let r = Ray::new(P![-0.2, 0.3, -2.], V![0., 0., 1.]);
+        let xs = t.local_intersect(r);
+        assert_eq!(1, xs.len());
+        assert!(approx_eq(2., xs[0].t()));
+
+        let r = Ray::new(P![1., 1., -2.], V![0., 0., 1.]);
+        assert_eq!(0, t.local_intersect(r).len());
+    }
+
+    #[test]
+    fn test_normal() {
+        let t = test_triangle().box_clone();
+        assert_eq!(V![-0.5547, 0.83205, 0.], t.normal(P![-0.2, 0.3, 0.]));
+    }
+
+    #[test]
+    fn test_shading_point_flat() {
+        // when every corner normal matches the face the point doesn't move.
+        let face = V![0., 0., -1.];
+        let t = SmoothTriangle::new(
+            P![0., 1., 0.],
+            P![-1., 0., 0.],
+            P![1., 0., 0.],
+            face,
+            face,
+            face,
+            None,
+            None,
+        );
+        let p = P![-0.2, 0.3, 0.];
+        assert_eq!(p, t.shading_point(p));
+    }
+
+    #[test]
+    fn test_shading_point_bulging() {
+        let t = bulging();
+
+        // corners don't move.
+        assert_eq!(t.p1(), t.shading_point(t.p1()));
+
+        // the middle of the face is lifted out towards the curved surface.
+        let p = P![0., 1. / 3., 0.];
+        let shading = t.shading_point(p);
+        assert!(shading.z() < -EPSILON);
+        assert!(approx_eq(p.x(), shading.x()));
+    }
+
+    #[test]
+    fn test_shading_point_transformed() {
+        let mut t = bulging();
+        let local = t.shading_point(P![0., 1. / 3., 0.]);
+
+        t.set_transform(translation(0., 0., 5.));
+        let world = t.shading_point(P![0., 1. / 3., 5.]);
+        assert!(approx_eq(local.z() + 5., world.z()));
+    }
+
+    #[test]
+    fn test_prepare_computations() {
+        let t = bulging().box_clone();
+        let r = Ray::new(P![0., 1. / 3., -2.], V![0., 0., 1.]);
+        let i = Intersection::new(2., t.clone());
+        let comps = i.prepare_computations(r);
+
+        // shadow rays start from the lifted point rather than the flat face.
+        assert!(comps.over_point.z() < comps.point.z() - 10. * EPSILON);
+    }
+}
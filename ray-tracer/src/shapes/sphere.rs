@@ -1,57 +1,27 @@
-use super::{material::Material, BoxedShape, Shape};
+use std::sync::Arc;
+
+use super::{material::Material, BoxedShape, Shape, ShapeBase, ShapeProps};
 use crate::{
+    primatives::aabb::Aabb,
     primatives::matrix::Matrix,
     primatives::point::Point,
     primatives::ray::Ray,
+    primatives::solvers,
     primatives::tuple::Tuple,
     primatives::vector::{self, Vector},
     world::intersection::{Intersection, Intersections},
     P,
 };
+use shape_derive::ShapeDerive;
 
 // a sphere is a rounded three dimensional shape. For simplicity it is centred at (0,0,0) with radius 1.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default, PartialEq, ShapeDerive)]
+#[shape(new)]
 pub struct Sphere {
-    transform: Matrix,
-    inverse_transform: Matrix,
-    material: Material,
-}
-
-impl Sphere {
-    pub fn new(transform: Option<Matrix>, material: Option<Material>) -> Self {
-        Self {
-            transform: transform.clone().unwrap_or_default(),
-            inverse_transform: transform
-                .unwrap_or_default()
-                .inverse()
-                .expect("trying to invert a matrix that cannot be inverted"),
-            material: material.unwrap_or_default(),
-        }
-    }
-    pub fn set_transform(&mut self, transform: Matrix) {
-        self.transform = transform.clone();
-        self.inverse_transform = transform
-            .inverse()
-            .expect("trying to invert a matrix that cannot be inverted")
-    }
-
-    pub fn set_material(&mut self, material: Material) {
-        self.material = material;
-    }
+    props: ShapeProps,
 }
 
 impl Shape for Sphere {
-    fn box_clone(&self) -> BoxedShape {
-        Box::new(self.clone())
-    }
-
-    fn box_eq(&self, other: &dyn std::any::Any) -> bool {
-        other.downcast_ref::<Self>().map_or(false, |a| self == a)
-    }
-
-    fn as_any(&self) -> &dyn std::any::Any {
-        self
-    }
     fn local_intersect(&self, r: Ray) -> Intersections {
         // the vector from the sphere's center to the ray origin.
         // the sphere is centred at the origin (0,0,0)
@@ -61,26 +31,10 @@ impl Shape for Sphere {
         let b = 2.0 * vector::dot(r.direction(), sphere_to_ray);
         let c = vector::dot(sphere_to_ray, sphere_to_ray) - 1.0;
 
-        let discriminant = b * b - 4.0 * a * c;
-
-        if discriminant < 0.0 {
-            return Intersections::EMPTY;
-        }
-
-        let t1 = (-b - discriminant.sqrt()) / (2.0 * a);
-        let t2 = (-b + discriminant.sqrt()) / (2.0 * a);
-
-        let hits = if t1 < t2 {
-            vec![
-                Intersection::new(t1, Box::new(self.clone())),
-                Intersection::new(t2, Box::new(self.clone())),
-            ]
-        } else {
-            vec![
-                Intersection::new(t2, Box::new(self.clone())),
-                Intersection::new(t1, Box::new(self.clone())),
-            ]
-        };
+        let hits = solvers::quadratic(a, b, c)
+            .into_iter()
+            .map(|t| Intersection::new(t, self.box_clone()))
+            .collect();
 
         Intersections::new(hits)
     }
@@ -89,46 +43,30 @@ impl Shape for Sphere {
         point - Point::new(0., 0., 0.)
     }
 
-    fn material(&self) -> &Material {
-        &self.material
-    }
-
-    fn transformation(&self) -> &Matrix {
-        &self.transform
-    }
-
-    fn inverse_transformation(&self) -> &Matrix {
-        &self.inverse_transform
-    }
-}
-
-impl Default for Sphere {
-    fn default() -> Self {
-        Self {
-            transform: Matrix::identity_matrix(),
-            inverse_transform: Matrix::identity_matrix(),
-            material: Material::default(),
-        }
-    }
-}
-
-impl PartialEq for Sphere {
-    fn eq(&self, other: &Self) -> bool {
-        self.transform == other.transform
-            && self.inverse_transform == other.inverse_transform
-            && self.material == other.material
+    fn bounds(&self) -> Aabb {
+        Aabb::new(P![-1., -1., -1.], P![1., 1., 1.])
     }
 }
 
 impl Sphere {
     pub fn default_boxed() -> BoxedShape {
-        Box::new(Sphere::default())
+        Arc::new(Sphere::default())
+    }
+
+    /// glass is a completely clear sphere that bends light by the given refractive index.
+    pub fn glass(transform: Option<Matrix>, refractive_index: f64) -> Self {
+        let material = Material::builder()
+            .transparency(1.)
+            .refractive_index(refractive_index)
+            .build()
+            .expect("every material field has a default");
+        Self::new(transform, Some(material))
     }
 }
 
 #[cfg(test)]
 mod test_sphere {
-    use std::f64::consts::PI;
+    use std::{f64::consts::PI, sync::Arc};
 
     use crate::{
         comparison::approx_eq,
@@ -200,12 +138,12 @@ mod test_sphere {
     fn test_sphere_set_transform() {
         // default transform is identity
         let mut s = Sphere::default();
-        assert_eq!(Matrix::identity_matrix(), s.transform);
+        assert_eq!(&Matrix::identity_matrix(), s.transformation());
 
         // changing the transform
         let t = translation(2., 3., 4.);
         s.set_transform(t.clone());
-        assert_eq!(t, s.transform)
+        assert_eq!(&t, s.transformation())
     }
 
     #[test]
@@ -274,11 +212,19 @@ mod test_sphere {
     #[test]
     fn test_sphere_materials() {
         let s = Sphere::default();
-        let m = s.material;
-        assert_eq!(Material::default(), m);
+        assert_eq!(&Material::default(), s.material());
 
         let m = Material::default();
         let s = Sphere::new(None, Some(m.clone()));
-        assert_eq!(m, s.material)
+        assert_eq!(&m, s.material());
+
+        // shapes given the same shared material hold the one copy of it.
+        let shared = Arc::new(Material::builder().ambient(1.).build().unwrap());
+        let mut a = Sphere::default();
+        let mut b = Sphere::default();
+        a.set_shared_material(shared.clone());
+        b.set_shared_material(shared.clone());
+        assert!(Arc::ptr_eq(a.shared_material(), b.shared_material()));
+        assert_eq!(3, Arc::strong_count(&shared));
     }
 }
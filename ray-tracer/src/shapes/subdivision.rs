@@ -0,0 +1,451 @@
+use std::collections::HashMap;
+
+use super::{material::Material, mesh::Mesh};
+use crate::{
+    primatives::matrix::Matrix,
+    primatives::point::{Point, ORIGIN},
+    primatives::vector::{cross, Vector},
+    V,
+};
+
+/// PolygonMesh is a surface made of faces with any number of corners that share their vertices, such as the cage
+/// of a model read from an OBJ file. Keeping which faces share each vertex and edge lets the surface be subdivided,
+/// smoothing a coarse cage into a curved surface before it is turned into triangles for rendering.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolygonMesh {
+    vertices: Vec<Point>,
+    faces: Vec<Vec<usize>>,
+}
+
+/// Edge is what subdivision needs to know about an edge, keyed by its two vertices with the smaller first.
+#[derive(Debug, Default)]
+struct Edge {
+    /// faces are the faces on either side of the edge. Edges with only one face are on the boundary of an open surface.
+    faces: Vec<usize>,
+    /// point is the index of the new vertex placed on the edge.
+    point: usize,
+}
+
+impl PolygonMesh {
+    pub fn new(vertices: Vec<Point>, faces: Vec<Vec<usize>>) -> Self {
+        for face in &faces {
+            assert!(face.len() >= 3, "a face needs at least 3 vertices");
+            if let Some(index) = face.iter().find(|&&i| i >= vertices.len()) {
+                panic!(
+                    "face refers to vertex {} but there are only {}",
+                    index,
+                    vertices.len()
+                );
+            }
+        }
+        Self { vertices, faces }
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    pub fn faces(&self) -> &[Vec<usize>] {
+        &self.faces
+    }
+
+    /// subdivide refines the mesh the given number of times. Meshes made only of triangles use Loop subdivision,
+    /// which keeps them as triangles, and any others use Catmull-Clark, which turns every face into quads.
+    /// Each level multiplies the number of faces by about four.
+    pub fn subdivide(&self, levels: usize) -> PolygonMesh {
+        let mut mesh = self.clone();
+        for _ in 0..levels {
+            mesh = if mesh.faces.iter().all(|f| f.len() == 3) {
+                mesh.loop_step()
+            } else {
+                mesh.catmull_clark_step()
+            };
+        }
+        mesh
+    }
+
+    /// loop_step splits every triangle into four. A point is added on each edge, weighted towards the edge's own
+    /// vertices and away from the two opposite corners, and every old vertex is moved towards the average of its
+    /// neighbours, by less the more neighbours it has.
+    pub fn loop_step(&self) -> PolygonMesh {
+        assert!(
+            self.faces.iter().all(|f| f.len() == 3),
+            "Loop subdivision only works on triangles"
+        );
+        let edges = self.edges(self.vertices.len());
+        let neighbours = self.neighbours();
+
+        let mut vertices: Vec<Point> = self
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(v, &point)| {
+                let around = &neighbours[v];
+                if let Some(boundary) = self.boundary_rule(v, &edges) {
+                    return boundary;
+                }
+                let n = around.len() as f64;
+                let beta = if around.len() == 3 {
+                    3. / 16.
+                } else {
+                    3. / (8. * n)
+                };
+                ORIGIN
+                    + offset(point) * (1. - n * beta)
+                    + sum(around.iter().map(|&u| self.vertices[u])) * beta
+            })
+            .collect();
+
+        for (a, b, edge) in sorted(&edges) {
+            let ends = offset(self.vertices[a]) + offset(self.vertices[b]);
+            let point = match edge.faces[..] {
+                [f, g] => {
+                    let opposite = |face: usize| {
+                        self.faces[face]
+                            .iter()
+                            .copied()
+                            .find(|&v| v != a && v != b)
+                            .unwrap()
+                    };
+                    ends * (3. / 8.)
+                        + (offset(self.vertices[opposite(f)]) + offset(self.vertices[opposite(g)]))
+                            * (1. / 8.)
+                }
+                _ => ends * 0.5,
+            };
+            debug_assert_eq!(edge.point, vertices.len());
+            vertices.push(ORIGIN + point);
+        }
+
+        let faces = self
+            .faces
+            .iter()
+            .flat_map(|face| {
+                let [a, b, c] = [face[0], face[1], face[2]];
+                let (ab, bc, ca) = (
+                    edges[&key(a, b)].point,
+                    edges[&key(b, c)].point,
+                    edges[&key(c, a)].point,
+                );
+                [
+                    vec![a, ab, ca],
+                    vec![b, bc, ab],
+                    vec![c, ca, bc],
+                    vec![ab, bc, ca],
+                ]
+            })
+            .collect();
+
+        PolygonMesh::new(vertices, faces)
+    }
+
+    /// catmull_clark_step splits every face into quads around a point in its middle. Each edge gets a point between
+    /// its ends and the middles of the faces either side, and every old vertex is pulled towards the faces and
+    /// edges around it.
+    pub fn catmull_clark_step(&self) -> PolygonMesh {
+        let edges = self.edges(self.vertices.len() + self.faces.len());
+        let neighbours = self.neighbours();
+
+        let face_points: Vec<Vector> = self
+            .faces
+            .iter()
+            .map(|face| sum(face.iter().map(|&v| self.vertices[v])) * (1. / face.len() as f64))
+            .collect();
+        let mut vertex_faces = vec![vec![]; self.vertices.len()];
+        for (f, face) in self.faces.iter().enumerate() {
+            face.iter().for_each(|&v| vertex_faces[v].push(f));
+        }
+
+        let mut vertices: Vec<Point> = self
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(v, &point)| {
+                if let Some(boundary) = self.boundary_rule(v, &edges) {
+                    return boundary;
+                }
+                let n = neighbours[v].len() as f64;
+                let faces = vertex_faces[v]
+                    .iter()
+                    .fold(V![0., 0., 0.], |total, &f| total + face_points[f])
+                    * (1. / vertex_faces[v].len() as f64);
+                let midpoints = neighbours[v].iter().fold(V![0., 0., 0.], |total, &u| {
+                    total + (offset(point) + offset(self.vertices[u])) * 0.5
+                }) * (1. / n);
+                ORIGIN + (faces + midpoints * 2. + offset(point) * (n - 3.)) * (1. / n)
+            })
+            .collect();
+        vertices.extend(face_points.iter().map(|&p| ORIGIN + p));
+
+        for (a, b, edge) in sorted(&edges) {
+            let ends = offset(self.vertices[a]) + offset(self.vertices[b]);
+            let point = match edge.faces[..] {
+                [f, g] => (ends + face_points[f] + face_points[g]) * 0.25,
+                _ => ends * 0.5,
+            };
+            debug_assert_eq!(edge.point, vertices.len());
+            vertices.push(ORIGIN + point);
+        }
+
+        let first_face_point = self.vertices.len();
+        let faces = self
+            .faces
+            .iter()
+            .enumerate()
+            .flat_map(|(f, face)| {
+                let edges = &edges;
+                (0..face.len()).map(move |i| {
+                    let (previous, v, next) = (
+                        face[(i + face.len() - 1) % face.len()],
+                        face[i],
+                        face[(i + 1) % face.len()],
+                    );
+                    vec![
+                        v,
+                        edges[&key(v, next)].point,
+                        first_face_point + f,
+                        edges[&key(previous, v)].point,
+                    ]
+                })
+            })
+            .collect();
+
+        PolygonMesh::new(vertices, faces)
+    }
+
+    /// to_mesh splits every face into a fan of triangles and gives each vertex a normal averaged from the faces
+    /// around it, weighted by their area, so the subdivided surface is shaded smoothly.
+    pub fn to_mesh(&self, transform: Option<Matrix>, material: Option<Material>) -> Mesh {
+        let triangles: Vec<[usize; 3]> = self
+            .faces
+            .iter()
+            .flat_map(|face| face[1..].windows(2).map(|pair| [face[0], pair[0], pair[1]]))
+            .collect();
+
+        let mut normals = vec![V![0., 0., 0.]; self.vertices.len()];
+        for &[a, b, c] in &triangles {
+            let (p1, p2, p3) = (self.vertices[a], self.vertices[b], self.vertices[c]);
+            // the same winding as a flat mesh face, and twice the area of the triangle long.
+            let normal = cross(p3 - p1, p2 - p1);
+            for v in [a, b, c] {
+                normals[v] = normals[v] + normal;
+            }
+        }
+        let normals = normals
+            .into_iter()
+            .map(|n| {
+                if n.magnitude() > 0. {
+                    n.norm()
+                } else {
+                    V![0., 1., 0.]
+                }
+            })
+            .collect();
+
+        let mut mesh = Mesh::new(self.vertices.clone(), triangles, transform, material);
+        mesh.set_normals(normals);
+        mesh
+    }
+
+    /// edges finds every edge and the faces either side of it.
+    /// The points added on the edges are numbered from first_point, in the order of the edges' vertices.
+    fn edges(&self, first_point: usize) -> HashMap<(usize, usize), Edge> {
+        let mut edges: HashMap<(usize, usize), Edge> = HashMap::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            for i in 0..face.len() {
+                edges
+                    .entry(key(face[i], face[(i + 1) % face.len()]))
+                    .or_default()
+                    .faces
+                    .push(f);
+            }
+        }
+        let mut keys: Vec<_> = edges.keys().copied().collect();
+        keys.sort();
+        for (i, k) in keys.into_iter().enumerate() {
+            edges.get_mut(&k).unwrap().point = first_point + i;
+        }
+        edges
+    }
+
+    /// neighbours are the vertices joined to each vertex by an edge.
+    fn neighbours(&self) -> Vec<Vec<usize>> {
+        let mut neighbours = vec![vec![]; self.vertices.len()];
+        for face in &self.faces {
+            for i in 0..face.len() {
+                let (a, b) = (face[i], face[(i + 1) % face.len()]);
+                if !neighbours[a].contains(&b) {
+                    neighbours[a].push(b);
+                }
+                if !neighbours[b].contains(&a) {
+                    neighbours[b].push(a);
+                }
+            }
+        }
+        neighbours
+    }
+
+    /// boundary_rule is where a vertex on the edge of an open surface moves to, or None for vertices inside the
+    /// surface. Boundary vertices are only smoothed along the boundary so that open edges stay where they are.
+    /// Vertices where boundaries cross are corners and don't move at all.
+    fn boundary_rule(&self, v: usize, edges: &HashMap<(usize, usize), Edge>) -> Option<Point> {
+        let boundary: Vec<usize> = edges
+            .iter()
+            .filter(|((a, b), edge)| (*a == v || *b == v) && edge.faces.len() != 2)
+            .map(|(&(a, b), _)| if a == v { b } else { a })
+            .collect();
+        match boundary[..] {
+            [] => None,
+            [a, b] => Some(
+                ORIGIN
+                    + offset(self.vertices[v]) * 0.75
+                    + (offset(self.vertices[a]) + offset(self.vertices[b])) * 0.125,
+            ),
+            _ => Some(self.vertices[v]),
+        }
+    }
+}
+
+/// sorted lists the edges in the order their points are numbered.
+fn sorted(edges: &HashMap<(usize, usize), Edge>) -> Vec<(usize, usize, &Edge)> {
+    let mut sorted: Vec<_> = edges.iter().map(|(&(a, b), edge)| (a, b, edge)).collect();
+    sorted.sort_by_key(|&(a, b, _)| (a, b));
+    sorted
+}
+
+/// key is how an edge is found whichever way round its vertices are given.
+fn key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// offset is the point as a vector from the origin, so that points can be added together and averaged.
+fn offset(point: Point) -> Vector {
+    point - ORIGIN
+}
+
+fn sum(points: impl Iterator<Item = Point>) -> Vector {
+    points.fold(V![0., 0., 0.], |total, p| total + offset(p))
+}
+
+#[cfg(test)]
+mod test_subdivision {
+    use crate::{
+        primatives::{tuple::Tuple, vector::dot},
+        P,
+    };
+
+    use super::*;
+
+    /// tetrahedron is a regular tetrahedron centred on the origin.
+    fn tetrahedron() -> PolygonMesh {
+        PolygonMesh::new(
+            vec![
+                P![1., 1., 1.],
+                P![1., -1., -1.],
+                P![-1., 1., -1.],
+                P![-1., -1., 1.],
+            ],
+            vec![vec![0, 1, 2], vec![0, 3, 1], vec![0, 2, 3], vec![1, 3, 2]],
+        )
+    }
+
+    /// cube is the six square faces of the cube from -1 to 1, wound the same way as a mesh's faces so they face out.
+    fn cube() -> PolygonMesh {
+        let vertices = (0..8)
+            .map(|i| {
+                let corner = |bit: usize| if i & bit == 0 { -1. } else { 1. };
+                P![corner(1), corner(2), corner(4)]
+            })
+            .collect();
+        let faces = vec![
+            vec![0, 1, 3, 2],
+            vec![4, 6, 7, 5],
+            vec![0, 4, 5, 1],
+            vec![2, 3, 7, 6],
+            vec![0, 2, 6, 4],
+            vec![1, 5, 7, 3],
+        ];
+        PolygonMesh::new(vertices, faces)
+    }
+
+    #[test]
+    fn test_loop_closed() {
+        let mesh = tetrahedron().subdivide(1);
+        assert_eq!(4 + 6, mesh.vertices().len());
+        assert_eq!(16, mesh.faces().len());
+
+        // each corner has three neighbours, which sum to minus itself, so it moves to a quarter of the way out.
+        assert_eq!(P![0.25, 0.25, 0.25], mesh.vertices()[0]);
+
+        // an edge point is 3/8 of the edge's ends and 1/8 of the opposite corners, which for this edge is its midpoint
+        // pulled halfway towards the origin.
+        assert!(mesh.vertices().contains(&P![0.5, 0., 0.]));
+
+        let twice = tetrahedron().subdivide(2);
+        assert_eq!(64, twice.faces().len());
+    }
+
+    #[test]
+    fn test_loop_open() {
+        let triangle = PolygonMesh::new(
+            vec![P![0., 0., 0.], P![4., 0., 0.], P![0., 4., 0.]],
+            vec![vec![0, 1, 2]],
+        );
+        let mesh = triangle.subdivide(1);
+        assert_eq!(4, mesh.faces().len());
+        // every edge is on the boundary, so edge points are midpoints and corners are smoothed along the boundary.
+        assert!(mesh.vertices().contains(&P![2., 0., 0.]));
+        assert!(mesh.vertices().contains(&P![2., 2., 0.]));
+        assert_eq!(P![0.5, 0.5, 0.], mesh.vertices()[0]);
+    }
+
+    #[test]
+    fn test_catmull_clark() {
+        let mesh = cube().subdivide(1);
+        assert_eq!(8 + 6 + 12, mesh.vertices().len());
+        assert_eq!(24, mesh.faces().len());
+        assert!(mesh.faces().iter().all(|f| f.len() == 4));
+
+        // a corner is pulled in by its three faces and edges to 5/9 of the way out.
+        let corner = 5. / 9.;
+        assert_eq!(P![corner, corner, corner], mesh.vertices()[7]);
+        // face points stay in the middle of their faces and edge points are pulled in by the faces either side.
+        assert!(mesh.vertices().contains(&P![0., 0., -1.]));
+        assert!(mesh.vertices().contains(&P![0., -0.75, -0.75]));
+
+        // mixed meshes are turned into quads first.
+        let mixed = PolygonMesh::new(
+            vec![
+                P![0., 0., 0.],
+                P![1., 0., 0.],
+                P![1., 1., 0.],
+                P![0., 1., 0.],
+                P![2., 0., 0.],
+            ],
+            vec![vec![0, 1, 2, 3], vec![1, 4, 2]],
+        );
+        let mesh = mixed.subdivide(2);
+        assert!(mesh.faces().iter().all(|f| f.len() == 4));
+        assert_eq!(4 * (4 + 3), mesh.faces().len());
+    }
+
+    #[test]
+    fn test_to_mesh() {
+        let cage = cube();
+        assert_eq!(cage, cage.subdivide(0));
+
+        let mesh = cage.subdivide(1).to_mesh(None, None);
+        assert_eq!(48, mesh.faces().len());
+        assert_eq!(26, mesh.normals().len());
+        // normals point out of the surface.
+        for (point, normal) in mesh.vertices().iter().zip(mesh.normals()) {
+            assert!(dot(offset(*point), *normal) > 0.);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "face refers to vertex 3 but there are only 3")]
+    fn test_bad_index() {
+        PolygonMesh::new(vec![P![0., 0., 0.]; 3], vec![vec![0, 1, 3]]);
+    }
+}
@@ -0,0 +1,193 @@
+use super::{material::Material, Shape, ShapeBase, ShapeProps};
+use crate::{
+    primatives::aabb::Aabb,
+    primatives::matrix::Matrix,
+    primatives::point::Point,
+    primatives::ray::Ray,
+    primatives::solvers,
+    primatives::tuple::Tuple,
+    primatives::vector::{dot, Vector},
+    world::intersection::{Intersection, Intersections},
+    P,
+};
+use shape_derive::ShapeDerive;
+
+/// a torus is a ring lying in the xz plane around the y axis. The major radius is the distance from the
+/// origin to the middle of the tube and the minor radius is the radius of the tube itself.
+#[derive(Clone, Debug, PartialEq, ShapeDerive)]
+pub struct Torus {
+    major_radius: f64,
+    minor_radius: f64,
+    props: ShapeProps,
+}
+
+impl Torus {
+    pub fn new(transform: Option<Matrix>, material: Option<Material>) -> Self {
+        Self {
+            major_radius: 1.,
+            minor_radius: 0.25,
+            props: ShapeProps::new(transform, material),
+        }
+    }
+
+    pub fn major_radius(&self) -> f64 {
+        self.major_radius
+    }
+
+    pub fn minor_radius(&self) -> f64 {
+        self.minor_radius
+    }
+
+    pub fn set_radii(&mut self, major_radius: f64, minor_radius: f64) {
+        assert!(
+            major_radius > 0. && minor_radius > 0.,
+            "torus radii must be greater than 0"
+        );
+        self.major_radius = major_radius;
+        self.minor_radius = minor_radius;
+    }
+}
+
+impl Default for Torus {
+    fn default() -> Self {
+        Self::new(None, None)
+    }
+}
+
+impl Shape for Torus {
+    /// local_intersect substitutes the ray into the torus equation (x² + y² + z² + R² - r²)² = 4R²(x² + z²),
+    /// which gives a quartic in t with up to four real roots.
+    fn local_intersect(&self, r: Ray) -> Intersections {
+        let (o, d) = (r.origin(), r.direction());
+        let o_v = Vector::new(o.x(), o.y(), o.z());
+        let major_sq = self.major_radius.powi(2);
+        let minor_sq = self.minor_radius.powi(2);
+
+        let d_sq = dot(d, d);
+        let e = dot(o_v, o_v) - major_sq - minor_sq;
+        let f = dot(o_v, d);
+        let four_major_sq = 4. * major_sq;
+
+        let hits = solvers::quartic(
+            d_sq * d_sq,
+            4. * d_sq * f,
+            2. * d_sq * e + 4. * f * f + four_major_sq * d.y().powi(2),
+            4. * f * e + 2. * four_major_sq * o.y() * d.y(),
+            e * e - four_major_sq * (minor_sq - o.y().powi(2)),
+        )
+        .into_iter()
+        .map(|t| Intersection::new(t, self.box_clone()))
+        .collect();
+
+        Intersections::new(hits)
+    }
+
+    /// local_normal is the gradient of the torus equation at the point.
+    fn local_normal(&self, point: Point) -> Vector {
+        let major_sq = self.major_radius.powi(2);
+        let radii_sq = major_sq + self.minor_radius.powi(2);
+        let distance_sq = point.x().powi(2) + point.y().powi(2) + point.z().powi(2);
+
+        Vector::new(
+            point.x() * (distance_sq - radii_sq),
+            point.y() * (distance_sq - radii_sq + 2. * major_sq),
+            point.z() * (distance_sq - radii_sq),
+        )
+    }
+
+    fn bounds(&self) -> Aabb {
+        let outer = self.major_radius + self.minor_radius;
+        Aabb::new(
+            P![-outer, -self.minor_radius, -outer],
+            P![outer, self.minor_radius, outer],
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_torus {
+    use crate::{comparison::approx_eq, primatives::transformation::scaling, P, V};
+
+    use super::*;
+
+    fn assert_hits(expected: &[f64], xs: Intersections) {
+        assert_eq!(expected.len(), xs.len());
+        for (i, t) in expected.iter().enumerate() {
+            assert!(
+                approx_eq(*t, xs[i].t()),
+                "expected {} but got {}",
+                t,
+                xs[i].t()
+            );
+        }
+    }
+
+    #[test]
+    fn test_ray_hits() {
+        let t = Torus::default();
+
+        // straight through both sides of the ring.
+        let r = Ray::new(P![-5., 0., 0.], V![1., 0., 0.]);
+        assert_hits(&[3.75, 4.25, 5.75, 6.25], t.local_intersect(r));
+
+        // through one side of the tube only.
+        let r = Ray::new(P![1., 0., -5.], V![0., 0., 1.]);
+        assert_hits(&[4.25, 5.75], t.local_intersect(r));
+
+        // down through the top of the tube.
+        let r = Ray::new(P![1., 5., 0.], V![0., -1., 0.]);
+        assert_hits(&[4.75, 5.25], t.local_intersect(r));
+    }
+
+    #[test]
+    fn test_ray_misses() {
+        let t = Torus::default();
+
+        // through the hole in the middle.
+        let r = Ray::new(P![0., 5., 0.], V![0., -1., 0.]);
+        assert_eq!(0, t.local_intersect(r).len());
+
+        // passing above the ring.
+        let r = Ray::new(P![-5., 0.5, 0.], V![1., 0., 0.]);
+        assert_eq!(0, t.local_intersect(r).len());
+    }
+
+    #[test]
+    fn test_radii() {
+        let mut t = Torus::default();
+        t.set_radii(2., 0.5);
+        assert!(approx_eq(2., t.major_radius()));
+        assert!(approx_eq(0.5, t.minor_radius()));
+
+        let r = Ray::new(P![-5., 0., 0.], V![1., 0., 0.]);
+        assert_hits(&[2.5, 3.5, 6.5, 7.5], t.local_intersect(r));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_radii_must_be_positive() {
+        Torus::default().set_radii(1., 0.);
+    }
+
+    #[test]
+    fn test_normal() {
+        let t = Torus::default().box_clone();
+        let cases = [
+            (P![1.25, 0., 0.], V![1., 0., 0.]),
+            (P![0.75, 0., 0.], V![-1., 0., 0.]),
+            (P![1., 0.25, 0.], V![0., 1., 0.]),
+            (P![0., -0.25, -1.], V![0., -1., 0.]),
+        ];
+
+        for (point, normal) in cases {
+            assert_eq!(normal, t.normal(point));
+        }
+    }
+
+    #[test]
+    fn test_in_world() {
+        let t = Torus::new(Some(scaling(2., 2., 2.)), None).box_clone();
+        let r = Ray::new(P![-5., 0., 0.], V![1., 0., 0.]);
+        assert_hits(&[2.5, 3.5, 6.5, 7.5], t.intersect(r));
+    }
+}
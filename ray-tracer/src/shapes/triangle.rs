@@ -0,0 +1,329 @@
+use super::{material::Material, Shape, ShapeBase, ShapeProps};
+use crate::{
+    comparison::EPSILON,
+    primatives::aabb::Aabb,
+    primatives::matrix::Matrix,
+    primatives::point::Point,
+    primatives::ray::Ray,
+    primatives::tuple::Tuple,
+    primatives::vector::{cross, dot, Vector},
+    world::intersection::{Intersection, Intersections},
+};
+use shape_derive::ShapeDerive;
+
+/// a triangle is a flat shape between three points. The edges and normal are calculated
+/// once when the triangle is made as they are needed for every intersection.
+#[derive(Clone, Debug, ShapeDerive)]
+pub struct Triangle {
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    e1: Vector,
+    e2: Vector,
+    normal: Vector,
+    intersection: TriangleIntersection,
+    props: ShapeProps,
+}
+
+impl Triangle {
+    pub fn new(
+        p1: Point,
+        p2: Point,
+        p3: Point,
+        transform: Option<Matrix>,
+        material: Option<Material>,
+    ) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        Self {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: cross(e2, e1).norm(),
+            intersection: TriangleIntersection::default(),
+            props: ShapeProps::new(transform, material),
+        }
+    }
+
+    pub fn p1(&self) -> Point {
+        self.p1
+    }
+    pub fn p2(&self) -> Point {
+        self.p2
+    }
+    pub fn p3(&self) -> Point {
+        self.p3
+    }
+    pub fn e1(&self) -> Vector {
+        self.e1
+    }
+    pub fn e2(&self) -> Vector {
+        self.e2
+    }
+
+    /// intersection_t is how far along the ray it hits the triangle, if it does.
+    pub(super) fn intersection_t(&self, r: Ray) -> Option<f64> {
+        match self.intersection {
+            TriangleIntersection::MollerTrumbore => intersect_face(self.p1, self.e1, self.e2, r),
+            TriangleIntersection::Watertight => intersect_watertight(self.p1, self.p2, self.p3, r),
+        }
+    }
+
+    /// barycentric finds how far along e1 (u) and e2 (v) a point in the plane of the triangle is,
+    /// so that point = p1 + u * e1 + v * e2.
+    pub(super) fn barycentric(&self, point: Point) -> (f64, f64) {
+        barycentric(self.p1, self.e1, self.e2, point)
+    }
+}
+
+impl Shape for Triangle {
+    fn local_intersect(&self, r: Ray) -> Intersections {
+        match self.intersection_t(r) {
+            Some(t) => Intersections::new(vec![Intersection::new(t, self.box_clone())]),
+            None => Intersections::EMPTY,
+        }
+    }
+
+    fn local_normal(&self, _point: Point) -> Vector {
+        self.normal
+    }
+
+    fn set_triangle_intersection(&mut self, intersection: TriangleIntersection) {
+        self.intersection = intersection;
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::around([self.p1, self.p2, self.p3])
+    }
+}
+
+impl PartialEq for Triangle {
+    fn eq(&self, other: &Self) -> bool {
+        self.p1 == other.p1
+            && self.p2 == other.p2
+            && self.p3 == other.p3
+            && self.props == other.props
+    }
+}
+
+/// TriangleIntersection is how triangles and meshes test rays against their faces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TriangleIntersection {
+    /// MollerTrumbore is fast but rounds each face on its own, so a ray exactly along an edge shared by two faces
+    /// can miss both of them, leaving single pixel cracks in large meshes.
+    #[default]
+    MollerTrumbore,
+    /// Watertight shears the faces into the ray's space before testing them, so faces sharing an edge always agree
+    /// on which side of it the ray passes and no ray can slip between them.
+    Watertight,
+}
+
+impl TriangleIntersection {
+    /// intersect is how far along the ray it hits the triangle with corners p1, p2 and p3, if it does.
+    pub(super) fn intersect(self, [p1, p2, p3]: [Point; 3], r: Ray) -> Option<f64> {
+        match self {
+            Self::MollerTrumbore => intersect_face(p1, p2 - p1, p3 - p1, r),
+            Self::Watertight => intersect_watertight(p1, p2, p3, r),
+        }
+    }
+}
+
+/// intersect_face uses the Möller–Trumbore algorithm on the triangle starting at p1 with edges e1 and e2,
+/// finding the barycentric coordinates (u, v) of the hit and rejecting it if they fall outside of the triangle.
+pub(super) fn intersect_face(p1: Point, e1: Vector, e2: Vector, r: Ray) -> Option<f64> {
+    let dir_cross_e2 = cross(r.direction(), e2);
+    let det = dot(e1, dir_cross_e2);
+
+    // the ray is parallel to the triangle.
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1. / det;
+    let p1_to_origin = r.origin() - p1;
+    let u = f * dot(p1_to_origin, dir_cross_e2);
+    if !(0. ..=1.).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = cross(p1_to_origin, e1);
+    let v = f * dot(r.direction(), origin_cross_e1);
+    if v < 0. || u + v > 1. {
+        return None;
+    }
+
+    Some(f * dot(e2, origin_cross_e1))
+}
+
+/// intersect_watertight uses the watertight test of Woop, Benthin and Wald. The corners are moved so the ray starts at
+/// the origin and sheared so it points along its largest axis, leaving a 2D test of which side of each edge the ray
+/// is on. Both faces sharing an edge work that side out from the same two corners in the same way, so they always
+/// agree and a ray on the edge hits at least one of them.
+fn intersect_watertight(p1: Point, p2: Point, p3: Point, r: Ray) -> Option<f64> {
+    let d = r.direction();
+    let dir = [d.x(), d.y(), d.z()];
+
+    // kz is the axis the ray mostly points along, kx and ky are swapped if needed to keep the faces' winding.
+    let kz = (0..3)
+        .max_by(|&a, &b| dir[a].abs().total_cmp(&dir[b].abs()))
+        .unwrap();
+    let (mut kx, mut ky) = ((kz + 1) % 3, (kz + 2) % 3);
+    if dir[kz] < 0. {
+        std::mem::swap(&mut kx, &mut ky);
+    }
+    let (sx, sy, sz) = (dir[kx] / dir[kz], dir[ky] / dir[kz], 1. / dir[kz]);
+
+    let sheared = |p: Point| {
+        let v = p - r.origin();
+        let v = [v.x(), v.y(), v.z()];
+        (v[kx] - sx * v[kz], v[ky] - sy * v[kz], sz * v[kz])
+    };
+    let (ax, ay, az) = sheared(p1);
+    let (bx, by, bz) = sheared(p2);
+    let (cx, cy, cz) = sheared(p3);
+
+    // u, v and w are twice the areas of the triangles between the ray and each edge.
+    let u = cx * by - cy * bx;
+    let v = ax * cy - ay * cx;
+    let w = bx * ay - by * ax;
+    if (u < 0. || v < 0. || w < 0.) && (u > 0. || v > 0. || w > 0.) {
+        return None;
+    }
+
+    // the ray is parallel to the triangle.
+    let det = u + v + w;
+    if det == 0. {
+        return None;
+    }
+
+    Some((u * az + v * bz + w * cz) / det)
+}
+
+/// barycentric finds (u, v) for a point in the plane of the triangle starting at p1 with edges e1 and e2.
+pub(super) fn barycentric(p1: Point, e1: Vector, e2: Vector, point: Point) -> (f64, f64) {
+    let p1_to_point = point - p1;
+    let (d11, d12, d22) = (dot(e1, e1), dot(e1, e2), dot(e2, e2));
+    let (dp1, dp2) = (dot(p1_to_point, e1), dot(p1_to_point, e2));
+    let denom = d11 * d22 - d12 * d12;
+
+    let u = (d22 * dp1 - d12 * dp2) / denom;
+    let v = (d11 * dp2 - d12 * dp1) / denom;
+    (u, v)
+}
+
+#[cfg(test)]
+mod test_triangle {
+    use crate::{comparison::approx_eq, primatives::tuple::Tuple, P, V};
+
+    use super::*;
+
+    fn test_triangle() -> Triangle {
+        Triangle::new(P![0., 1., 0.], P![-1., 0., 0.], P![1., 0., 0.], None, None)
+    }
+
+    #[test]
+    fn test_new() {
+        let t = test_triangle();
+
+        assert_eq!(P![0., 1., 0.], t.p1());
+        assert_eq!(P![-1., 0., 0.], t.p2());
+        assert_eq!(P![1., 0., 0.], t.p3());
+        assert_eq!(V![-1., -1., 0.], t.e1());
+        assert_eq!(V![1., -1., 0.], t.e2());
+        assert_eq!(V![0., 0., -1.], t.normal);
+    }
+
+    #[test]
+    fn test_normal() {
+        let t = test_triangle();
+
+        assert_eq!(t.normal, t.local_normal(P![0., 0.5, 0.]));
+        assert_eq!(t.normal, t.local_normal(P![-0.5, 0.75, 0.]));
+        assert_eq!(t.normal, t.local_normal(P![0.5, 0.25, 0.]));
+    }
+
+    #[test]
+    fn test_intersect() {
+        let t = test_triangle();
+
+        // parallel to the triangle
+        let r = Ray::new(P![0., -1., -2.], V![0., 1., 0.]);
+        assert_eq!(0, t.local_intersect(r).len());
+
+        // misses the p1-p3 edge
+        let r = Ray::new(P![1., 1., -2.], V![0., 0., 1.]);
+        assert_eq!(0, t.local_intersect(r).len());
+
+        // misses the p1-p2 edge
+        let r = Ray::new(P![-1., 1., -2.], V![0., 0., 1.]);
+        assert_eq!(0, t.local_intersect(r).len());
+
+        // misses the p2-p3 edge
+        let r = Ray::new(P![0., -1., -2.], V![0., 0., 1.]);
+        assert_eq!(0, t.local_intersect(r).len());
+
+        // strikes the triangle
+        let r = Ray::new(P![0., 0.5, -2.], V![0., 0., 1.]);
+        let xs = t.local_intersect(r);
+        assert_eq!(1, xs.len());
+        assert!(approx_eq(2., xs[0].t()));
+    }
+
+    #[test]
+    fn test_intersect_watertight() {
+        let mut t = test_triangle();
+        t.set_triangle_intersection(TriangleIntersection::Watertight);
+
+        let r = Ray::new(P![0., -1., -2.], V![0., 1., 0.]);
+        assert_eq!(0, t.local_intersect(r).len());
+        let r = Ray::new(P![1., 1., -2.], V![0., 0., 1.]);
+        assert_eq!(0, t.local_intersect(r).len());
+        let r = Ray::new(P![0., -1., -2.], V![0., 0., 1.]);
+        assert_eq!(0, t.local_intersect(r).len());
+
+        let r = Ray::new(P![0., 0.5, -2.], V![0., 0., 1.]);
+        let xs = t.local_intersect(r);
+        assert_eq!(1, xs.len());
+        assert!(approx_eq(2., xs[0].t()));
+
+        // the same from behind and at an angle.
+        let r = Ray::new(P![0.5, 0.5, 2.], V![-0.25, 0., -1.]);
+        let xs = t.local_intersect(r);
+        assert_eq!(1, xs.len());
+        assert!(approx_eq(2., xs[0].t()));
+    }
+
+    #[test]
+    fn test_shared_edge() {
+        // two faces far from the origin sharing the edge a-b, and a ray aimed at a point on that edge.
+        let (a, b) = (P![1000.3, 20.7, 500.1], P![1003.9, 27.1, 499.3]);
+        let (c, d) = (P![1000., 29., 498.], P![1004., 19., 501.]);
+        let origin = P![995.1, 21.3, 480.7];
+        let r = Ray::new(origin, a + (b - a) * 0.00289 - origin);
+        let hits = |mode: TriangleIntersection| {
+            [[a, b, c], [b, a, d]]
+                .into_iter()
+                .filter(|&face| mode.intersect(face, r).is_some())
+                .count()
+        };
+
+        // Möller–Trumbore rounds each face differently and the ray slips between them.
+        assert_eq!(0, hits(TriangleIntersection::MollerTrumbore));
+        assert!(hits(TriangleIntersection::Watertight) > 0);
+    }
+
+    #[test]
+    fn test_barycentric() {
+        let t = test_triangle();
+
+        let (u, v) = t.barycentric(P![-0.2, 0.3, 0.]);
+        assert!(approx_eq(0.45, u));
+        assert!(approx_eq(0.25, v));
+
+        let (u, v) = t.barycentric(t.p1());
+        assert!(approx_eq(0., u));
+        assert!(approx_eq(0., v));
+    }
+}
@@ -0,0 +1,139 @@
+use super::{camera::Camera, canvas::Canvas, World};
+
+/// Animator changes a world and camera to how they should look on a frame of an animation.
+/// Closures taking the world, camera and frame are animators too.
+pub trait Animator {
+    fn apply(&self, world: &mut World, camera: &mut Camera, frame: usize);
+}
+
+impl<F: Fn(&mut World, &mut Camera, usize)> Animator for F {
+    fn apply(&self, world: &mut World, camera: &mut Camera, frame: usize) {
+        self(world, camera, frame)
+    }
+}
+
+/// Animation renders a number of frames of a world, running its animators on every frame in the order they were
+/// added. Each frame starts again from the world and camera the animation was made with, so a frame always looks
+/// the same however many frames were rendered before it and animators work from the frame number alone.
+pub struct Animation {
+    world: World,
+    camera: Camera,
+    frames: usize,
+    animators: Vec<Box<dyn Animator>>,
+}
+
+impl Animation {
+    pub fn new(world: World, camera: Camera, frames: usize) -> Self {
+        Self {
+            world,
+            camera,
+            frames,
+            animators: vec![],
+        }
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    pub fn add_animator(&mut self, animator: impl Animator + 'static) {
+        self.animators.push(Box::new(animator));
+    }
+
+    /// frame is the world and camera as they are on the given frame.
+    pub fn frame(&self, frame: usize) -> (World, Camera) {
+        let mut world = self.world.clone();
+        let mut camera = self.camera.clone();
+        self.animators
+            .iter()
+            .for_each(|animator| animator.apply(&mut world, &mut camera, frame));
+        (world, camera)
+    }
+
+    /// render_parallel renders every frame in turn using several threads, handing each image to on_frame with
+    /// its frame number as soon as it is finished so it can be saved without holding the whole animation.
+    pub fn render_parallel(&self, threads: usize, mut on_frame: impl FnMut(usize, Canvas)) {
+        for frame in 0..self.frames {
+            let (world, camera) = self.frame(frame);
+            on_frame(frame, camera.render_parallel(world, threads));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_animation {
+    use crate::{
+        primatives::{
+            color::Color,
+            transformation::{translation, view_transformation},
+            tuple::Tuple,
+        },
+        P, V,
+    };
+
+    use super::*;
+
+    fn test_camera() -> Camera {
+        let mut c = Camera::new(11, 11, 0.5);
+        c.set_transform(view_transformation(
+            P![0., 0., -5.],
+            P![0., 0., 0.],
+            V![0., 1., 0.],
+        ));
+        c
+    }
+
+    /// slide moves the first object one unit to the right every frame.
+    fn slide(world: &mut World, _camera: &mut Camera, frame: usize) {
+        world.update_object(0, |object| {
+            object.set_transform(translation(frame as f64, 0., 0.))
+        });
+    }
+
+    #[test]
+    fn test_frames_are_deterministic() {
+        let mut animation = Animation::new(World::default(), test_camera(), 5);
+        animation.add_animator(slide);
+
+        let (world, _) = animation.frame(3);
+        assert_eq!(
+            &translation(3., 0., 0.),
+            world.objects()[0].transformation()
+        );
+        assert_eq!(world, animation.frame(3).0);
+        // frame 0 is the world the animation started with.
+        assert_eq!(World::default(), animation.frame(0).0);
+    }
+
+    #[test]
+    fn test_animators_run_in_order() {
+        let mut animation = Animation::new(World::default(), test_camera(), 2);
+        animation.add_animator(slide);
+        animation.add_animator(|world: &mut World, camera: &mut Camera, _: usize| {
+            // the camera follows the sphere moved by the first animator.
+            camera.set_transform(world.objects()[0].transformation().clone());
+        });
+
+        let (_, camera) = animation.frame(1);
+        assert_eq!(&translation(1., 0., 0.), camera.transform());
+    }
+
+    #[test]
+    fn test_render_parallel() {
+        let mut animation = Animation::new(World::default(), test_camera(), 3);
+        animation.add_animator(|world: &mut World, _: &mut Camera, frame: usize| {
+            // both spheres leave the middle of the view after the first frame.
+            for i in 0..2 {
+                world.update_object(i, |object| {
+                    object.set_transform(translation(0., 10. * frame as f64, 0.))
+                });
+            }
+        });
+
+        let mut rendered = vec![];
+        animation.render_parallel(2, |frame, canvas| {
+            rendered.push((frame, canvas.pixel_at(5, 5) != Some(Color::BLACK)))
+        });
+        assert_eq!(vec![(0, true), (1, false), (2, false)], rendered);
+    }
+}
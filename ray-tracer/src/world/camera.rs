@@ -1,21 +1,91 @@
-use super::{canvas::Canvas, World};
-use crate::{primatives::matrix::Matrix, primatives::ray::Ray, Tuple, P};
+use std::{
+    f64::consts::{FRAC_PI_2, PI, TAU},
+    io::{self, Write},
+    ops::Range,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 
+use super::{
+    canvas::{pixel_count, write_ppm_header, Canvas, CanvasError, SaveOptions, STREAM_THRESHOLD},
+    depth::DepthMap,
+    World,
+};
+use crate::{
+    primatives::color::Color,
+    primatives::matrix::Matrix,
+    primatives::point::Point,
+    primatives::ray::Ray,
+    primatives::rng::Rng,
+    primatives::sampling::{BlueNoise, Halton, Jittered, Sampler},
+    primatives::tuple::Tuple,
+    P,
+};
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Camera {
     hsize: usize,
     vsize: usize,
     fov: f64, // field of view
     transform: Matrix,
     inverse_transform: Matrix,
-    pixel_size: f64,
+    /// pixel_aspect is the width of a pixel divided by its height, 1 for square pixels.
+    pixel_aspect: f64,
+    pixel_width: f64,
+    pixel_height: f64,
     half_width: f64,
     half_height: f64,
+    sampler: PixelSampler,
+    /// aperture is the width of the lens. Rays start from all over it and meet again at the focal distance, so
+    /// things nearer or further away are blurred. An aperture of 0 is a pinhole, with everything in focus.
+    aperture: f64,
+    /// focal_distance is how far in front of the camera things are in focus, measured along the way it looks.
+    focal_distance: f64,
+    /// bokeh is the shape of the lens, which out of focus highlights take on.
+    bokeh: Bokeh,
+    projection: Projection,
 }
 
 impl Camera {
     pub fn new(hsize: usize, vsize: usize, fov: f64) -> Self {
-        let half_view = (fov / 2.0).tan();
-        let aspect = hsize as f64 / vsize as f64;
+        let mut camera = Self {
+            hsize,
+            vsize,
+            fov,
+            transform: Matrix::identity_matrix(),
+            inverse_transform: Matrix::identity_matrix(),
+            pixel_aspect: 1.,
+            pixel_width: 0.,
+            pixel_height: 0.,
+            half_width: 0.,
+            half_height: 0.,
+            sampler: PixelSampler::default(),
+            aperture: 0.,
+            focal_distance: 1.,
+            bokeh: Bokeh::default(),
+            projection: Projection::default(),
+        };
+        camera.fit_view();
+        camera
+    }
+
+    /// fit_view works out the size of the view and of each pixel on it. The field of view covers the longer side
+    /// of the image as it will be shown, which is wider than the canvas when pixels are wider than they are tall.
+    fn fit_view(&mut self) {
+        let half_view = match self.projection {
+            Projection::Perspective => (self.fov / 2.0).tan(),
+            Projection::Fisheye => self.fov / 2.0,
+            Projection::Equirectangular => {
+                // every direction, with longitude across the canvas and latitude up it, whatever its shape.
+                self.half_width = PI;
+                self.half_height = FRAC_PI_2;
+                self.pixel_width = TAU / self.hsize as f64;
+                self.pixel_height = PI / self.vsize as f64;
+                return;
+            }
+        };
+        let aspect = self.hsize as f64 * self.pixel_aspect / self.vsize as f64;
 
         let (half_width, half_height) = if aspect >= 1.0 {
             (half_view, half_view / aspect)
@@ -23,17 +93,10 @@ impl Camera {
             (half_view * aspect, half_view)
         };
 
-        let pixel_size = (half_width * 2.0) / hsize as f64;
-        Self {
-            hsize,
-            vsize,
-            fov,
-            transform: Matrix::identity_matrix(),
-            inverse_transform: Matrix::identity_matrix(),
-            pixel_size,
-            half_width,
-            half_height,
-        }
+        self.half_width = half_width;
+        self.half_height = half_height;
+        self.pixel_width = (half_width * 2.0) / self.hsize as f64;
+        self.pixel_height = (half_height * 2.0) / self.vsize as f64;
     }
 
     pub fn hsize(&self) -> usize {
@@ -56,34 +119,489 @@ impl Camera {
         self.transform = transform;
     }
 
+    /// pixel_size is how wide each pixel is on the view. For square pixels it is also how tall they are.
     pub fn pixel_size(&self) -> f64 {
-        self.pixel_size
+        self.pixel_width
     }
 
+    /// footprint_at is how wide a pixel is on a surface facing the camera at the given distance, the footprint to
+    /// give bump_roughness for bumps that far away.
+    pub fn footprint_at(&self, distance: f64) -> f64 {
+        self.pixel_size() * distance
+    }
+
+    pub fn pixel_width(&self) -> f64 {
+        self.pixel_width
+    }
+
+    pub fn pixel_height(&self) -> f64 {
+        self.pixel_height
+    }
+
+    pub fn pixel_aspect(&self) -> f64 {
+        self.pixel_aspect
+    }
+
+    /// set_pixel_aspect makes each pixel pixel_aspect times as wide as it is tall. Anamorphic footage squeezed by 2
+    /// has a pixel aspect of 2, so the canvas holds a view twice as wide as its pixel count suggests.
+    pub fn set_pixel_aspect(&mut self, pixel_aspect: f64) {
+        assert!(
+            pixel_aspect > 0. && pixel_aspect.is_finite(),
+            "pixel aspect must be greater than 0"
+        );
+        self.pixel_aspect = pixel_aspect;
+        self.fit_view();
+    }
+
+    pub fn sampler(&self) -> &PixelSampler {
+        &self.sampler
+    }
+
+    /// set_sampler changes where in each pixel the samples of a multi sample render are taken.
+    pub fn set_sampler(&mut self, sampler: PixelSampler) {
+        if let PixelSampler::Fixed(offsets) = &sampler {
+            assert!(
+                !offsets.is_empty(),
+                "a fixed sampler needs at least one offset"
+            );
+        }
+        self.sampler = sampler;
+    }
+
+    pub fn aperture(&self) -> f64 {
+        self.aperture
+    }
+
+    /// set_aperture changes how wide the lens is, and so how blurred things away from the focal distance are.
+    pub fn set_aperture(&mut self, aperture: f64) {
+        assert!(
+            aperture >= 0. && aperture.is_finite(),
+            "a camera's aperture can't be negative"
+        );
+        self.aperture = aperture;
+    }
+
+    pub fn focal_distance(&self) -> f64 {
+        self.focal_distance
+    }
+
+    pub fn set_focal_distance(&mut self, focal_distance: f64) {
+        assert!(
+            focal_distance > 0. && focal_distance.is_finite(),
+            "a camera's focal distance must be greater than 0"
+        );
+        self.focal_distance = focal_distance;
+    }
+
+    pub fn bokeh(&self) -> &Bokeh {
+        &self.bokeh
+    }
+
+    /// set_bokeh changes the shape of the lens, which is as wide as the aperture whatever its shape.
+    pub fn set_bokeh(&mut self, bokeh: Bokeh) {
+        if let Bokeh::Polygon { sides, .. } = bokeh {
+            assert!(sides >= 3, "a polygon needs at least three sides");
+        }
+        self.bokeh = bokeh;
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    /// set_projection changes how the pixels of the canvas are spread over the directions the camera looks in.
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        self.fit_view();
+    }
+
+    /// focus_on sets the focal distance so the point is in focus. It panics if the point is behind the camera.
+    pub fn focus_on(&mut self, point: Point) {
+        // the camera looks down -z in its own space.
+        self.set_focal_distance(-(self.transform.clone() * point).z());
+    }
+
+    /// ray_for_sample is the ray for the given sample of a pixel, through the point the camera's sampler picks and
+    /// from the sample's point on the lens.
+    pub fn ray_for_sample(&self, x: usize, y: usize, sample: usize) -> Ray {
+        let (dx, dy) = self.sampler.point(sample);
+        self.ray_through_lens(x as f64 + dx, y as f64 + dy, self.lens_point(x, y, sample))
+    }
+
+    /// ray_for_pixel is the ray through the centre of a pixel, from the first of the pixel's points on the lens.
     pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
-        // the offset from the edge of the canvas to the pixel center
-        let x_offset = (x as f64 + 0.5) * self.pixel_size();
-        let y_offset = (y as f64 + 0.5) * self.pixel_size();
+        self.ray_through_lens(x as f64 + 0.5, y as f64 + 0.5, self.lens_point(x, y, 0))
+    }
+
+    /// lens_point is where on the lens the given sample of a pixel starts, measured from the middle of the lens.
+    /// Each pixel's samples are spread evenly over the lens with a Halton sequence, shifted by a different amount
+    /// for every pixel so that neighbouring pixels' blur doesn't line up into patterns.
+    fn lens_point(&self, x: usize, y: usize, sample: usize) -> (f64, f64) {
+        if self.aperture == 0. {
+            return (0., 0.);
+        }
+        let mut rng = Rng::new((y * self.hsize + x) as u64);
+        let (u, v) = Halton::scrambled(&mut rng).point(sample);
+        let (lx, ly) = self.bokeh.point(u, v);
+        let radius = self.aperture / 2.;
+        (lx * radius, ly * radius)
+    }
+
+    /// ray_through is the ray through a point on the canvas, in pixels from the top left corner, from the middle
+    /// of the lens, so that rays can be sent through anywhere inside a pixel and not just its centre.
+    pub fn ray_through(&self, x: f64, y: f64) -> Ray {
+        self.ray_through_lens(x, y, (0., 0.))
+    }
+
+    /// ray_through_lens is the ray from a point on the lens that meets the ray through the point on the canvas from
+    /// the middle of the lens at the focal distance, so everything at that distance is seen in the same place
+    /// whichever part of the lens it is seen through.
+    fn ray_through_lens(&self, x: f64, y: f64, lens: (f64, f64)) -> Ray {
+        // the offset from the edge of the canvas to the point
+        let x_offset = x * self.pixel_width;
+        let y_offset = y * self.pixel_height;
 
         // the untransformed world coordinates
         let world_x = self.half_width - x_offset;
         let world_y = self.half_height - y_offset;
 
+        let (dx, dy, dz) = self.projection.direction(world_x, world_y);
+        let (target, start) = if lens == (0., 0.) {
+            (P![dx, dy, dz], P![0., 0., 0.])
+        } else {
+            // where the ray through the middle of the lens reaches the focal distance.
+            let f = self.focal_distance;
+            (P![dx * f, dy * f, dz * f], P![lens.0, lens.1, 0.])
+        };
+
         // transform the canvas point and origin
-        let pixel = self.inverse_transform.clone() * P![world_x, world_y, -1.];
-        let origin = self.inverse_transform.clone() * P![0., 0., 0.];
+        let pixel = self.inverse_transform.clone() * target;
+        let origin = self.inverse_transform.clone() * start;
         let direction = (pixel - origin).norm();
 
         Ray::new(origin, direction)
     }
 
+    /// project finds where on the canvas a point in the world would be seen, in pixels from the top left corner,
+    /// so the centre of pixel (x, y) is at (x + 0.5, y + 0.5). Points behind a perspective camera can't be seen.
+    pub fn project(&self, point: Point) -> Option<(f64, f64)> {
+        let view = self.transform.clone() * point;
+        let (world_x, world_y) = self.projection.view_point(view.x(), view.y(), view.z())?;
+        Some((
+            (self.half_width - world_x) / self.pixel_width,
+            (self.half_height - world_y) / self.pixel_height,
+        ))
+    }
+
+    /// render_motion_vectors finds how far the surface seen through each pixel has moved on screen since the previous frame.
+    /// The point that was hit is taken back into the object space of the shape it is on, moved by that shape's transformation
+    /// in previous_world and projected through previous_camera. Shapes are matched between the worlds by their position in
+    /// World::primitives, so previous_world should hold the same shapes with last frame's transformations.
+    ///
+    /// The red channel of each pixel is how many pixels the point moved to the right and the green channel how many it moved
+    /// down. Pixels that see nothing, or see something that wasn't in view in the previous frame, haven't moved.
+    /// The values are not colors and are not limited to 0 to 1.
+    pub fn render_motion_vectors(
+        &self,
+        world: &World,
+        previous_world: &World,
+        previous_camera: &Camera,
+    ) -> Result<Canvas, CanvasError> {
+        let mut image = Canvas::try_new(self.hsize, self.vsize)?;
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let ray = self.ray_through(x as f64 + 0.5, y as f64 + 0.5);
+                let motion = world.primitive_hit(ray).and_then(|(index, hit)| {
+                    let object_point = world.primitives()[index].world_to_object(ray.at(hit.t()));
+                    let previous = previous_world.primitives().get(index)?;
+                    let previous_point = previous.transformation().clone() * object_point;
+                    let (px, py) = previous_camera.project(previous_point)?;
+                    Some(Color::new(x as f64 + 0.5 - px, y as f64 + 0.5 - py, 0.))
+                });
+                image.write_pixel(x, y, motion.unwrap_or(Color::BLACK));
+            }
+        }
+        Ok(image)
+    }
+
+    /// render_depth finds the depth of the surface seen through the centre of each pixel from the middle of the lens,
+    /// measured along the direction the camera looks, for compositing effects such as fog and depth of field.
+    /// It is an error for the map to have more pixels than a canvas can hold.
+    pub fn render_depth(&self, world: &World) -> Result<DepthMap, CanvasError> {
+        let mut depths = DepthMap::try_new(self.hsize, self.vsize)?;
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let ray = self.ray_through(x as f64 + 0.5, y as f64 + 0.5);
+                if let Some((_, hit)) = world.primitive_hit(ray) {
+                    // the camera looks down -z in its own space.
+                    let view = self.transform.clone() * ray.at(hit.t());
+                    depths.write_depth(x, y, -view.z());
+                }
+            }
+        }
+        Ok(depths)
+    }
+
     pub fn render(&self, world: World) -> Canvas {
-        let mut image = Canvas::new(self.hsize(), self.vsize());
+        self.render_rows(&world, 0..self.vsize)
+    }
+
+    /// render_parallel splits the image into bands of rows and renders each band on its own thread.
+    /// Every thread writes into its own canvas slab so nothing is shared while rendering,
+    /// and the slabs are merged into the final image once all the threads have finished.
+    pub fn render_parallel(&self, world: World, threads: usize) -> Canvas {
+        self.render_band(&world, threads, 0..self.vsize)
+    }
+
+    /// render_to renders the image and writes it to out as a ppm file. Images with more than STREAM_THRESHOLD pixels
+    /// are never held in memory all at once, instead they are rendered a band of rows at a time and each band is
+    /// written out before the next is started, so renders far larger than memory still finish. Only an image with a
+    /// single row of more than MAX_PIXELS pixels can't be rendered, which is an InvalidInput error.
+    pub fn render_to(&self, world: &World, threads: usize, out: &mut dyn Write) -> io::Result<()> {
+        self.render_to_with_options(world, threads, out, &SaveOptions::default())
+    }
+
+    /// render_to_with_options is render_to saving the image with the given options, such as a white balance.
+    pub fn render_to_with_options(
+        &self,
+        world: &World,
+        threads: usize,
+        out: &mut dyn Write,
+        options: &SaveOptions,
+    ) -> io::Result<()> {
+        self.render_streamed(world, threads, out, options, STREAM_THRESHOLD)
+    }
+
+    /// render_streamed is render_to with the number of pixels above which the image is streamed.
+    fn render_streamed(
+        &self,
+        world: &World,
+        threads: usize,
+        out: &mut dyn Write,
+        options: &SaveOptions,
+        threshold: usize,
+    ) -> io::Result<()> {
+        // even a band of one row has to fit in a canvas.
+        pixel_count(self.hsize, 1)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        let band = if self.hsize.saturating_mul(self.vsize) <= threshold {
+            self.vsize.max(1)
+        } else {
+            (threshold / self.hsize.max(1)).max(1)
+        };
+        write_ppm_header(out, self.hsize, self.vsize)?;
+        for start in (0..self.vsize).step_by(band) {
+            let rows = start..(start + band).min(self.vsize);
+            self.render_band(world, threads, rows)
+                .write_ppm_rows(out, options, start)?;
+        }
+        out.flush()
+    }
+
+    /// render_band renders the given rows of the image into a canvas just tall enough to hold them, splitting them
+    /// between the threads.
+    fn render_band(&self, world: &World, threads: usize, rows: Range<usize>) -> Canvas {
+        let height = rows.len();
+        let threads = threads.clamp(1, height.max(1));
+        let slab_height = height.div_ceil(threads).max(1);
+
+        let slabs = thread::scope(|scope| {
+            let workers: Vec<_> = rows
+                .clone()
+                .step_by(slab_height)
+                .map(|start| {
+                    let slab = start..(start + slab_height).min(rows.end);
+                    scope.spawn(move || (start - rows.start, self.render_rows(world, slab)))
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .map(|worker| worker.join().expect("a render thread panicked"))
+                .collect::<Vec<_>>()
+        });
+
+        let mut image = Canvas::new(self.hsize, height);
+        for (start, slab) in slabs {
+            image.merge(&slab, 0, start);
+        }
+        image
+    }
+
+    /// render_progressive renders the best image it can before the budget runs out, which is useful for previews.
+    /// The first passes trace every eighth, fourth and second pixel, filling the pixels in between with the nearest
+    /// traced color, and a pass over every pixel follows. Once the whole image has been traced, further passes add
+    /// samples from different places inside each pixel and average them, which smooths jagged edges, until the
+    /// camera's sampler has no new places left or MAX_PROGRESSIVE_SAMPLES have been taken. Rendering stops as soon as the budget runs out, keeping whatever the last pass had finished.
+    /// A budget too long to have an end is no limit at all. The whole image is held in memory, so it is an error for it
+    /// to have more than MAX_PIXELS pixels.
+    pub fn render_progressive(
+        &self,
+        world: &World,
+        threads: usize,
+        budget: Duration,
+    ) -> Result<Canvas, CanvasError> {
+        let deadline = Instant::now().checked_add(budget);
+        let mut image = Canvas::try_new(self.hsize, self.vsize)?;
+
+        for (pass, &stride) in PREVIEW_STRIDES.iter().enumerate() {
+            // pixels traced by a coarser pass already have their color.
+            let coarser = (pass > 0).then_some(stride * 2);
+            let rows: Vec<usize> = (0..self.vsize).step_by(stride).collect();
+            // the coarsest pass always finishes so that there is something to show.
+            let until = if pass > 0 { deadline } else { None };
+            let traced = self.trace_rows(world, threads, until, &rows, |x, y| {
+                let done = coarser.is_some_and(|c| x % c == 0 && y % c == 0);
+                (x % stride == 0 && !done).then(|| self.ray_for_sample(x, y, 0))
+            });
+            for (x, y, color) in traced {
+                for block_y in y..(y + stride).min(self.vsize) {
+                    for block_x in x..(x + stride).min(self.hsize) {
+                        image.write_pixel(block_x, block_y, color);
+                    }
+                }
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Ok(image);
+            }
+        }
+
+        // every pixel has had its first sample, so more can be averaged in.
+        let mut totals = vec![Color::BLACK; self.hsize * self.vsize];
+        let mut counts = vec![1_usize; self.hsize * self.vsize];
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                totals[y * self.hsize + x] = image.pixel_at(x, y).unwrap_or(Color::BLACK);
+            }
+        }
+        let rows: Vec<usize> = (0..self.vsize).collect();
+        for sample in 1..self.sample_limit() {
+            let traced = self.trace_rows(world, threads, deadline, &rows, |x, y| {
+                Some(self.ray_for_sample(x, y, sample))
+            });
+            for (x, y, color) in traced {
+                totals[y * self.hsize + x] = totals[y * self.hsize + x] + color;
+                counts[y * self.hsize + x] += 1;
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+        }
+
         for y in 0..self.vsize {
-            for x in 0..self.hsize() {
+            for x in 0..self.hsize {
+                let i = y * self.hsize + x;
+                image.write_pixel(x, y, totals[i] * (1. / counts[i] as f64));
+            }
+        }
+        Ok(image)
+    }
+
+    /// render_adaptive traces one sample through every pixel, then takes the rest of the camera's sampler's samples,
+    /// up to MAX_PROGRESSIVE_SAMPLES, only in pixels that differ from a pixel next to them by more than the
+    /// threshold in any channel. Those are the pixels on edges, where a single sample looks jagged, so the image
+    /// looks much like one with every pixel supersampled while flat areas, usually most of it, are traced once.
+    /// Like render_progressive, it is an error for the image to have more than MAX_PIXELS pixels.
+    pub fn render_adaptive(
+        &self,
+        world: &World,
+        threads: usize,
+        threshold: f64,
+    ) -> Result<Canvas, CanvasError> {
+        let mut image = Canvas::try_new(self.hsize, self.vsize)?;
+        let rows: Vec<usize> = (0..self.vsize).collect();
+        let traced = self.trace_rows(world, threads, None, &rows, |x, y| {
+            Some(self.ray_for_sample(x, y, 0))
+        });
+        for (x, y, color) in traced {
+            image.write_pixel(x, y, color);
+        }
+
+        let edges = edge_pixels(&image, threshold);
+        let rows: Vec<usize> = rows
+            .into_iter()
+            .filter(|&y| edges[y * self.hsize..(y + 1) * self.hsize].contains(&true))
+            .collect();
+        let mut totals = vec![Color::BLACK; self.hsize * self.vsize];
+        for sample in 1..self.sample_limit() {
+            let traced = self.trace_rows(world, threads, None, &rows, |x, y| {
+                edges[y * self.hsize + x].then(|| self.ray_for_sample(x, y, sample))
+            });
+            for (x, y, color) in traced {
+                totals[y * self.hsize + x] = totals[y * self.hsize + x] + color;
+            }
+        }
+
+        let samples = self.sample_limit() as f64;
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                if edges[y * self.hsize + x] {
+                    let first = image.pixel_at(x, y).unwrap_or(Color::BLACK);
+                    image.write_pixel(x, y, (first + totals[y * self.hsize + x]) * (1. / samples));
+                }
+            }
+        }
+        Ok(image)
+    }
+
+    /// sample_limit is how many samples a render that refines pixels takes of each, which is every sample the
+    /// camera's sampler has up to MAX_PROGRESSIVE_SAMPLES.
+    fn sample_limit(&self) -> usize {
+        self.sampler
+            .count()
+            .map_or(MAX_PROGRESSIVE_SAMPLES, |count| {
+                count.min(MAX_PROGRESSIVE_SAMPLES)
+            })
+    }
+
+    /// trace_rows finds the color for the ray given for each pixel along the rows, skipping pixels without one.
+    /// Rows are dealt out to the threads in turn so that a pass cut short by the deadline is missing rows spread
+    /// across the image rather than a band at the bottom. Rows not started by the deadline, if there is one, are left out.
+    fn trace_rows(
+        &self,
+        world: &World,
+        threads: usize,
+        deadline: Option<Instant>,
+        rows: &[usize],
+        ray: impl Fn(usize, usize) -> Option<Ray> + Sync,
+    ) -> Vec<(usize, usize, Color)> {
+        let threads = threads.clamp(1, rows.len().max(1));
+        let ray = &ray;
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads)
+                .map(|worker| {
+                    scope.spawn(move || {
+                        let mut traced = vec![];
+                        for &y in rows.iter().skip(worker).step_by(threads) {
+                            if deadline.is_some_and(|d| Instant::now() >= d) {
+                                break;
+                            }
+                            for x in 0..self.hsize {
+                                if let Some(r) = ray(x, y) {
+                                    traced.push((x, y, world.color_at(r)));
+                                }
+                            }
+                        }
+                        traced
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().expect("a render thread panicked"))
+                .collect()
+        })
+    }
+
+    /// render_rows renders the given rows of the image into a canvas just tall enough to hold them.
+    fn render_rows(&self, world: &World, rows: Range<usize>) -> Canvas {
+        let mut image = Canvas::new(self.hsize, rows.len());
+        for (slab_y, y) in rows.enumerate() {
+            for x in 0..self.hsize {
                 let ray = self.ray_for_pixel(x, y);
                 let color = world.color_at(ray);
-                image.write_pixel(x, y, color);
+                image.write_pixel(x, slab_y, color);
             }
         }
 
@@ -91,14 +609,283 @@ impl Camera {
     }
 }
 
+/// edge_pixels marks the pixels whose color differs from the pixel above, below, left or right of them by more than
+/// the threshold in any channel, in the order the pixels are stored in, row by row.
+fn edge_pixels(image: &Canvas, threshold: f64) -> Vec<bool> {
+    let (width, height) = (image.width(), image.height());
+    let mut edges = vec![false; width * height];
+    let color = |x, y| image.pixel_at(x, y).unwrap_or(Color::BLACK);
+    let differ = |a: Color, b: Color| {
+        (a.red() - b.red()).abs() > threshold
+            || (a.green() - b.green()).abs() > threshold
+            || (a.blue() - b.blue()).abs() > threshold
+    };
+    for y in 0..height {
+        for x in 0..width {
+            // comparing with the pixel to the right and below covers every pair of neighbours once.
+            for (nx, ny) in [(x + 1, y), (x, y + 1)] {
+                if nx < width && ny < height && differ(color(x, y), color(nx, ny)) {
+                    edges[y * width + x] = true;
+                    edges[ny * width + nx] = true;
+                }
+            }
+        }
+    }
+    edges
+}
+
+/// Projection is how a camera spreads the pixels of its canvas over the directions it looks in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Projection {
+    /// Perspective is an ordinary camera, where straight lines stay straight. Its field of view has to be less
+    /// than half a turn.
+    #[default]
+    Perspective,
+    /// Fisheye spreads angles evenly over the canvas, so the angle from the middle of the view grows in step with
+    /// the distance from the middle of the canvas. Its field of view can be half a turn or more.
+    Fisheye,
+    /// Equirectangular sees in every direction, with a full turn of longitude across the canvas and latitude from
+    /// straight down to straight up it, which is the layout of 360° panoramas. The field of view is ignored and
+    /// the canvas should be twice as wide as it is tall.
+    Equirectangular,
+}
+
+impl Projection {
+    /// direction is the way the camera looks, in its own space, through a point on the view, where (0, 0) is the
+    /// middle of the view. A perspective direction reaches one unit in front of the camera and the others are one
+    /// unit long, so either way a point on the direction's ray at the focal distance is the direction times it.
+    fn direction(self, x: f64, y: f64) -> (f64, f64, f64) {
+        match self {
+            Projection::Perspective => (x, y, -1.),
+            Projection::Fisheye => {
+                // the distance from the middle of the view is the angle from the middle of the view.
+                let theta = x.hypot(y);
+                if theta == 0. {
+                    return (0., 0., -1.);
+                }
+                let scale = theta.sin() / theta;
+                (x * scale, y * scale, -theta.cos())
+            }
+            Projection::Equirectangular => {
+                // x is the longitude and y the latitude.
+                (x.sin() * y.cos(), y.sin(), -x.cos() * y.cos())
+            }
+        }
+    }
+
+    /// view_point is the point on the view that sees a point in the camera's space, the opposite of direction.
+    fn view_point(self, x: f64, y: f64, z: f64) -> Option<(f64, f64)> {
+        match self {
+            Projection::Perspective => {
+                if z >= 0. {
+                    return None;
+                }
+                // scale the point back onto the canvas, which is one unit in front of the camera.
+                Some((x / -z, y / -z))
+            }
+            Projection::Fisheye => {
+                let side = x.hypot(y);
+                if side == 0. {
+                    return (z < 0.).then_some((0., 0.));
+                }
+                let theta = side.atan2(-z);
+                Some((x / side * theta, y / side * theta))
+            }
+            Projection::Equirectangular => {
+                let side = x.hypot(z);
+                (side != 0. || y != 0.).then(|| (x.atan2(-z), y.atan2(side)))
+            }
+        }
+    }
+}
+
+/// Bokeh is the shape of a camera's lens. Out of focus points of light are blurred into this shape, so a real
+/// lens's blades or a cut out mask show in the highlights.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Bokeh {
+    /// Disc is a round lens.
+    #[default]
+    Disc,
+    /// Polygon is a lens with straight sides, such as the six blades of many camera lenses, with a corner at the
+    /// top turned by rotation radians.
+    Polygon { sides: usize, rotation: f64 },
+    /// Mask is a lens with the shape of the bright parts of an image.
+    Mask(BokehMask),
+}
+
+impl Bokeh {
+    /// point spreads (u, v) from the unit square evenly over the shape, which fits inside a circle of radius 1.
+    pub fn point(&self, u: f64, v: f64) -> (f64, f64) {
+        match self {
+            Bokeh::Disc => {
+                let (r, theta) = (u.sqrt(), TAU * v);
+                (r * theta.cos(), r * theta.sin())
+            }
+            Bokeh::Polygon { sides, rotation } => {
+                // pick one of the triangles between the middle and each side, then a point in that triangle.
+                let scaled = u * *sides as f64;
+                let side = (scaled.floor() as usize).min(sides - 1);
+                let corner = |i: usize| {
+                    let angle = rotation + FRAC_PI_2 + TAU * i as f64 / *sides as f64;
+                    (angle.cos(), angle.sin())
+                };
+                let (a, b) = (corner(side), corner(side + 1));
+                let r = (scaled - side as f64).sqrt();
+                (r * (a.0 + v * (b.0 - a.0)), r * (a.1 + v * (b.1 - a.1)))
+            }
+            Bokeh::Mask(mask) => mask.point(u, v),
+        }
+    }
+}
+
+/// BokehMask is the shape of the pixels of an image that are more than half as bright as white, stretched over
+/// the square around the lens. The image is read once, keeping only where its bright pixels are.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BokehMask {
+    width: usize,
+    height: usize,
+    open: Arc<[(usize, usize)]>,
+}
+
+impl BokehMask {
+    /// new makes a mask from an image, or None if the image has no bright pixels to let light through.
+    pub fn new(image: &Canvas) -> Option<Self> {
+        let open: Arc<[(usize, usize)]> = (0..image.height())
+            .flat_map(|y| (0..image.width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| image.pixel_at(x, y).is_some_and(|c| c.luminance() > 0.5))
+            .collect();
+        (!open.is_empty()).then(|| Self {
+            width: image.width(),
+            height: image.height(),
+            open,
+        })
+    }
+
+    fn point(&self, u: f64, v: f64) -> (f64, f64) {
+        // u picks one of the bright pixels and what's left of it and v pick where in that pixel.
+        let scaled = u * self.open.len() as f64;
+        let index = (scaled.floor() as usize).min(self.open.len() - 1);
+        let (x, y) = self.open[index];
+        let (fx, fy) = (scaled - index as f64, v);
+        // the image covers -1 to 1 across the lens, with its top at +y. Corners outside the circle are fine as the
+        // aperture only says how wide the lens is.
+        (
+            (x as f64 + fx) / self.width as f64 * 2. - 1.,
+            1. - (y as f64 + fy) / self.height as f64 * 2.,
+        )
+    }
+}
+
+/// PREVIEW_STRIDES are the spacings between the pixels traced by each of the first passes of a progressive render.
+const PREVIEW_STRIDES: [usize; 4] = [8, 4, 2, 1];
+
+/// MAX_PROGRESSIVE_SAMPLES is the most samples a progressive render takes for each pixel, after which
+/// it stops even if there is time left.
+const MAX_PROGRESSIVE_SAMPLES: usize = 16;
+
+/// PixelSampler decides where inside a pixel each of its samples goes when a render takes more than one.
+/// Offsets are measured in pixels from the pixel's top left corner.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum PixelSampler {
+    /// Centre sends every sample through the middle of the pixel, so extra samples change nothing.
+    Centre,
+    /// Rgss takes four samples on a grid turned so that no two share a row or column, which smooths nearly
+    /// horizontal and vertical edges almost as well as many more samples. It is cheap and the same every render,
+    /// so previews use it unless another sampler is chosen.
+    #[default]
+    Rgss,
+    /// R2 spreads samples evenly over the pixel with the R2 sequence, starting at the centre. It never runs out,
+    /// so a progressive render keeps adding samples for as long as it has time.
+    R2,
+    /// Jittered puts one sample somewhere random in each cell of a grid over the pixel.
+    Jittered(Jittered),
+    /// Halton spreads samples over the pixel with the Halton sequence, which never runs out like R2.
+    Halton(Halton),
+    /// BlueNoise takes a fixed set of samples that are random but never bunched up.
+    BlueNoise(BlueNoise),
+    /// Fixed uses the given offsets in turn, going back to the first when they run out.
+    /// Tests use it to know exactly which rays a render sends.
+    Fixed(Vec<(f64, f64)>),
+}
+
+impl Sampler for PixelSampler {
+    /// point is where in the pixel the given sample goes.
+    fn point(&self, sample: usize) -> (f64, f64) {
+        match self {
+            PixelSampler::Centre => (0.5, 0.5),
+            PixelSampler::Rgss => RGSS_OFFSETS[sample % RGSS_OFFSETS.len()],
+            PixelSampler::R2 => {
+                // the plastic number, the real root of x³ = x + 1.
+                const G: f64 = 1.324_717_957_244_746;
+                let n = sample as f64;
+                ((0.5 + n / G).fract(), (0.5 + n / (G * G)).fract())
+            }
+            PixelSampler::Jittered(jittered) => jittered.point(sample),
+            PixelSampler::Halton(halton) => halton.point(sample),
+            PixelSampler::BlueNoise(noise) => noise.point(sample),
+            PixelSampler::Fixed(offsets) => offsets[sample % offsets.len()],
+        }
+    }
+
+    fn count(&self) -> Option<usize> {
+        match self {
+            PixelSampler::Centre => Some(1),
+            PixelSampler::Rgss => Some(RGSS_OFFSETS.len()),
+            PixelSampler::R2 => None,
+            PixelSampler::Jittered(jittered) => jittered.count(),
+            PixelSampler::Halton(halton) => halton.count(),
+            PixelSampler::BlueNoise(noise) => noise.count(),
+            PixelSampler::Fixed(offsets) => Some(offsets.len()),
+        }
+    }
+}
+
+/// RGSS_OFFSETS are the four samples of a rotated grid, a grid of 4 turned by about 27° so that each sample is in
+/// a row and column of the pixel of its own.
+const RGSS_OFFSETS: [(f64, f64); 4] = [
+    (0.625, 0.125),
+    (0.875, 0.625),
+    (0.375, 0.875),
+    (0.125, 0.375),
+];
+
+/// parse_duration reads a time budget such as 90s, 500ms or 2m. A number on its own is a number of seconds.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' does not start with a number", text))?;
+    let seconds = match unit.trim() {
+        "" | "s" => number,
+        "ms" => number / 1000.,
+        "m" => number * 60.,
+        "h" => number * 3600.,
+        other => {
+            return Err(format!(
+                "unknown unit '{}', expected one of ms, s, m, h",
+                other
+            ))
+        }
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("'{}' is too long", text))
+}
+
 #[cfg(test)]
 mod test_camera {
     use std::f64::consts::PI;
 
     use crate::{
-        comparison::approx_eq,
-        primatives::{color::Color, transformation::rotation_y, tuple::Tuple},
-        translation, view_transformation,
+        comparison::{approx_eq, EPSILON},
+        primatives::{
+            color::Color,
+            transformation::{rotation_y, translation, view_transformation},
+            tuple::Tuple,
+        },
+        shapes::make_mut,
         world::World,
         C, P, V,
     };
@@ -129,6 +916,26 @@ mod test_camera {
         // vertical canvas
         let c = Camera::new(125, 200, PI / 2.0);
         assert!(approx_eq(c.pixel_size(), 0.01));
+        assert!(approx_eq(c.pixel_height(), 0.01));
+    }
+
+    #[test]
+    fn test_pixel_aspect() {
+        // pixels twice as wide as they are tall make a 100x100 canvas show a view twice as wide as it is high.
+        let mut c = Camera::new(100, 100, PI / 2.0);
+        c.set_pixel_aspect(2.);
+        assert!(approx_eq(c.pixel_width(), 0.02));
+        assert!(approx_eq(c.pixel_height(), 0.01));
+        assert!(approx_eq(2. * c.pixel_height(), c.pixel_width()));
+
+        // the top left pixel is one wide pixel in from the left of a view 2 wide and 1 high.
+        let r = c.ray_for_pixel(0, 0);
+        assert_eq!(V![0.99, 0.495, -1.].norm(), r.direction());
+
+        // tall pixels squeeze the view the other way.
+        c.set_pixel_aspect(0.5);
+        assert!(approx_eq(c.pixel_width(), 0.01));
+        assert!(approx_eq(c.pixel_height(), 0.02));
     }
 
     #[test]
@@ -150,6 +957,118 @@ mod test_camera {
         assert_eq!(r.direction(), V![sqrt_2_2, 0., -sqrt_2_2]);
     }
 
+    #[test]
+    fn test_project() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.set_transform(rotation_y(PI / 4.0) * translation(0., -2., 5.));
+        c.set_pixel_aspect(1.5);
+
+        // projecting a point along a pixel's ray lands in the middle of that pixel.
+        for (x, y) in [(0, 0), (100, 50), (17, 90)] {
+            let (px, py) = c.project(c.ray_for_pixel(x, y).at(7.)).unwrap();
+            assert!(approx_eq(x as f64 + 0.5, px));
+            assert!(approx_eq(y as f64 + 0.5, py));
+        }
+
+        // behind the camera.
+        let behind = c.ray_for_pixel(100, 50).at(-1.);
+        assert_eq!(None, c.project(behind));
+    }
+
+    #[test]
+    fn test_fisheye() {
+        let mut c = Camera::new(11, 11, PI);
+        c.set_projection(Projection::Fisheye);
+        assert_eq!(V![0., 0., -1.], c.ray_for_pixel(5, 5).direction());
+        // the edge of a half turn fisheye looks straight out to the side.
+        assert_eq!(V![1., 0., 0.], c.ray_through(0., 5.5).direction());
+        assert_eq!(V![0., -1., 0.], c.ray_through(5.5, 11.).direction());
+        // angles are spread evenly, so a quarter of the way across is a quarter turn from the side.
+        let r = c.ray_through(2.75, 5.5).direction();
+        assert_eq!(V![(PI / 4.).sin(), 0., -(PI / 4.).cos()], r);
+
+        // the corners see further round than the sides.
+        assert!(c.ray_through(0., 0.).direction().z() > 0.);
+        c.set_transform(rotation_y(PI / 4.0) * translation(0., -2., 5.));
+        for (x, y) in [(0, 0), (5, 5), (2, 9)] {
+            let (px, py) = c.project(c.ray_for_pixel(x, y).at(7.)).unwrap();
+            assert!(approx_eq(x as f64 + 0.5, px));
+            assert!(approx_eq(y as f64 + 0.5, py));
+        }
+    }
+
+    #[test]
+    fn test_equirectangular() {
+        let mut c = Camera::new(20, 10, PI / 2.);
+        c.set_projection(Projection::Equirectangular);
+        assert_eq!(V![0., 0., -1.], c.ray_through(10., 5.).direction());
+        assert_eq!(V![0., 0., 1.], c.ray_through(0., 5.).direction());
+        assert_eq!(V![1., 0., 0.], c.ray_through(5., 5.).direction());
+        assert_eq!(V![-1., 0., 0.], c.ray_through(15., 5.).direction());
+        assert_eq!(V![0., 1., 0.], c.ray_through(3., 0.).direction());
+        assert_eq!(V![0., -1., 0.], c.ray_through(3., 10.).direction());
+
+        // every direction can be seen, even behind the camera.
+        c.set_transform(rotation_y(PI / 4.0) * translation(0., -2., 5.));
+        for (x, y) in [(1, 1), (10, 5), (17, 8)] {
+            let (px, py) = c.project(c.ray_for_pixel(x, y).at(7.)).unwrap();
+            assert!(approx_eq(x as f64 + 0.5, px));
+            assert!(approx_eq(y as f64 + 0.5, py));
+        }
+    }
+
+    #[test]
+    fn test_render_motion_vectors() {
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(view_transformation(
+            P![0., 0., -5.],
+            P![0., 0., 0.],
+            V![0., 1., 0.],
+        ));
+        let world = World::default();
+
+        // nothing moved.
+        let motion = c.render_motion_vectors(&world, &world, &c).unwrap();
+        assert_eq!(Some(Color::BLACK), motion.pixel_at(5, 5));
+        assert_eq!(Some(Color::BLACK), motion.pixel_at(3, 4));
+
+        // the camera has turned, so everything appears to move across the screen.
+        let mut previous_camera = c.clone();
+        previous_camera.set_transform(view_transformation(
+            P![0., 0., -5.],
+            P![1., 0., 0.],
+            V![0., 1., 0.],
+        ));
+        let motion = c
+            .render_motion_vectors(&world, &world, &previous_camera)
+            .unwrap();
+        let moved = motion.pixel_at(5, 5).unwrap();
+        assert!(moved.red().abs() > 1.);
+        assert!(approx_eq(0., moved.green()));
+
+        // the spheres moved up by a quarter, which is about half a pixel at that distance.
+        let previous_world = World::new(
+            world
+                .objects()
+                .iter()
+                .map(|o| {
+                    let mut o = o.clone();
+                    make_mut(&mut o).transform_by(&translation(0., -0.25, 0.));
+                    o
+                })
+                .collect(),
+            world.light(),
+        );
+        let motion = c
+            .render_motion_vectors(&world, &previous_world, &c)
+            .unwrap();
+        let moved = motion.pixel_at(5, 5).unwrap();
+        assert!(approx_eq(0., moved.red()));
+        assert!(moved.green() < -0.25 && moved.green() > -1.);
+        // the background hasn't moved.
+        assert_eq!(Some(Color::BLACK), motion.pixel_at(0, 0));
+    }
+
     #[test]
     fn test_render() {
         let w = World::default();
@@ -163,4 +1082,396 @@ mod test_camera {
         let image = c.render(w);
         assert_eq!(image.pixel_at(5, 5).unwrap(), C![0.38066, 0.47583, 0.2855])
     }
+
+    #[test]
+    fn test_render_parallel() {
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(view_transformation(
+            P![0., 0., -5.],
+            P![0., 0., 0.],
+            V![0., 1., 0.],
+        ));
+
+        let serial = c.render(World::default());
+        // 4 threads don't divide 11 rows evenly so the last band is shorter.
+        for threads in [1, 4, 11, 20] {
+            assert_eq!(serial, c.render_parallel(World::default(), threads));
+        }
+    }
+
+    #[test]
+    fn test_render_depth() {
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(view_transformation(
+            P![0., 0., -5.],
+            P![0., 0., 0.],
+            V![0., 1., 0.],
+        ));
+        let depths = c.render_depth(&World::default()).unwrap();
+
+        // the middle pixel looks straight at the front of the outer sphere, four units away.
+        assert!(approx_eq(4., depths.depth_at(5, 5).unwrap()));
+        // depth is measured along the view, so a point off to the side is less deep than its distance.
+        let ray = c.ray_for_pixel(4, 5);
+        let side = depths.depth_at(4, 5).unwrap();
+        let distance = World::default().primitive_hit(ray).unwrap().1.t();
+        assert!(side < distance);
+        assert!(approx_eq(side, distance * ray.direction().z()));
+        assert_eq!(None, depths.depth_at(0, 0));
+    }
+
+    #[test]
+    fn test_render_streamed() {
+        let mut c = Camera::new(11, 7, PI / 2.);
+        c.set_transform(view_transformation(
+            P![0., 0., -5.],
+            P![0., 0., 0.],
+            V![0., 1., 0.],
+        ));
+        let world = World::default();
+        let mut expected = vec![];
+        c.render(world.clone()).save(&mut expected);
+
+        let mut whole = vec![];
+        c.render_to(&world, 2, &mut whole).unwrap();
+        assert_eq!(expected, whole);
+
+        // a threshold of 30 pixels streams the image two rows at a time, leaving one row for the last band.
+        let mut streamed = vec![];
+        c.render_streamed(&world, 2, &mut streamed, &SaveOptions::default(), 30)
+            .unwrap();
+        assert_eq!(expected, streamed);
+    }
+
+    #[test]
+    fn test_render_too_large() {
+        use crate::world::canvas::MAX_PIXELS;
+
+        let world = World::default();
+        let too_large = CanvasError::TooLarge {
+            width: MAX_PIXELS,
+            height: 2,
+        };
+        // renders held in memory refuse an image with too many pixels before tracing anything.
+        let c = Camera::new(MAX_PIXELS, 2, PI / 2.);
+        assert_eq!(
+            Err(too_large.clone()),
+            c.render_progressive(&world, 2, Duration::ZERO)
+        );
+        assert_eq!(Err(too_large.clone()), c.render_adaptive(&world, 2, 0.1));
+        assert_eq!(Err(too_large), c.render_depth(&world));
+
+        // a streamed render only needs a row at a time, so only a row that is too long is refused.
+        let c = Camera::new(MAX_PIXELS + 1, 1, PI / 2.);
+        let err = c.render_to(&world, 2, &mut vec![]).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    }
+
+    #[test]
+    fn test_render_progressive() {
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(view_transformation(
+            P![0., 0., -5.],
+            P![0., 0., 0.],
+            V![0., 1., 0.],
+        ));
+        let w = World::default();
+        let full = c.render(w.clone());
+
+        // with no time at all only the coarsest pass is traced, filling blocks of eight pixels.
+        let preview = c.render_progressive(&w, 2, Duration::ZERO).unwrap();
+        assert_eq!(full.pixel_at(0, 0), preview.pixel_at(0, 0));
+        assert_eq!(preview.pixel_at(0, 0), preview.pixel_at(7, 7));
+        assert_eq!(
+            Some(w.color_at(c.ray_for_sample(8, 8, 0))),
+            preview.pixel_at(10, 10)
+        );
+
+        // with plenty of time every pixel is averaged over samples spread across it, which changes little away from edges.
+        let refined = c
+            .render_progressive(&w, 2, Duration::from_secs(60))
+            .unwrap();
+        let (a, b) = (
+            full.pixel_at(5, 5).unwrap(),
+            refined.pixel_at(5, 5).unwrap(),
+        );
+        assert!((a.luminance() - b.luminance()).abs() < 0.05);
+        assert!((full.mean_luminance() - refined.mean_luminance()).abs() < 0.05);
+
+        // when every sample goes through the centre the samples all agree with a plain render.
+        c.set_sampler(PixelSampler::Centre);
+        assert_eq!(
+            full,
+            c.render_progressive(&w, 2, Duration::from_secs(60))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_render_progressive_diff() {
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(view_transformation(
+            P![0., 0., -5.],
+            P![0., 0., 0.],
+            V![0., 1., 0.],
+        ));
+        let w = World::default();
+        let full = c.render(w.clone());
+
+        // a refined render keeps the structure of a plain one, only softening its edges.
+        let refined = c
+            .render_progressive(&w, 2, Duration::from_secs(60))
+            .unwrap();
+        assert!(full.diff(&refined).ssim() > 0.9);
+        // the coarsest preview loses most of it.
+        let preview = c.render_progressive(&w, 2, Duration::ZERO).unwrap();
+        assert!(full.diff(&preview).ssim() < full.diff(&refined).ssim());
+    }
+
+    #[test]
+    fn test_render_progressive_endless_budget() {
+        // a budget that ends too far in the future to be represented is treated as no limit.
+        let mut c = Camera::new(3, 3, PI / 2.);
+        c.set_sampler(PixelSampler::Centre);
+        let w = World::default();
+        let budget = Duration::from_secs(10_000_000_000_000_000_000);
+        assert_eq!(
+            c.render(w.clone()),
+            c.render_progressive(&w, 2, budget).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_depth_of_field() {
+        let mut c = Camera::new(20, 10, PI / 2.);
+        let pinhole = c.ray_for_pixel(3, 4);
+        c.set_focal_distance(4.);
+        assert_eq!(pinhole, c.ray_for_pixel(3, 4));
+
+        // rays start from all over the lens and meet where the ray from its middle reaches the focal distance.
+        c.set_aperture(0.5);
+        c.set_sampler(PixelSampler::Centre);
+        let focus = pinhole.at(4. / -pinhole.direction().z());
+        let rays: Vec<_> = (0..16)
+            .map(|sample| c.ray_for_sample(3, 4, sample))
+            .collect();
+        for ray in &rays {
+            assert!(approx_eq(0., ray.origin().z()));
+            assert!(V![ray.origin().x(), ray.origin().y(), 0.].magnitude() <= 0.25);
+            assert_eq!(
+                focus,
+                ray.at((-4. - ray.origin().z()) / ray.direction().z())
+            );
+        }
+        assert_ne!(rays[0].origin(), rays[1].origin());
+        // a pixel's own ray starts from the same place on the lens as its first sample.
+        assert_eq!(rays[0].origin(), c.ray_for_pixel(3, 4).origin());
+    }
+
+    #[test]
+    fn test_focus_on() {
+        let mut c = Camera::new(10, 10, PI / 2.);
+        c.set_transform(view_transformation(
+            P![0., 0., -5.],
+            P![0., 0., 0.],
+            V![0., 1., 0.],
+        ));
+        c.focus_on(P![1., 2., 0.]);
+        assert!(approx_eq(5., c.focal_distance()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_focus_behind() {
+        Camera::new(10, 10, PI / 2.).focus_on(P![0., 0., 1.]);
+    }
+
+    #[test]
+    fn test_polygon_bokeh() {
+        // a square turned an eighth of a turn has its sides square to the axes.
+        let square = Bokeh::Polygon {
+            sides: 4,
+            rotation: PI / 4.,
+        };
+        let halton = Halton::new();
+        let points: Vec<_> = (0..256)
+            .map(|i| {
+                let (u, v) = halton.point(i);
+                square.point(u, v)
+            })
+            .collect();
+        let half = 0.5_f64.sqrt() + EPSILON;
+        assert!(points
+            .iter()
+            .all(|(x, y)| x.abs() <= half && y.abs() <= half));
+        // the points are spread evenly, so each quarter of the square gets about a quarter of them.
+        let top_right = points.iter().filter(|(x, y)| *x > 0. && *y > 0.).count();
+        assert!((56..=72).contains(&top_right));
+        // the corner of a triangle points up.
+        let triangle = Bokeh::Polygon {
+            sides: 3,
+            rotation: 0.,
+        };
+        let (x, y) = triangle.point(1. / 3. - 1e-12, 0.);
+        assert!(approx_eq(0., x) && approx_eq(1., y));
+        // and its bottom side is halfway down.
+        assert!((0..256)
+            .map(|i| halton.point(i))
+            .map(|(u, v)| triangle.point(u, v))
+            .all(|(_, y)| y >= -0.5 - EPSILON));
+    }
+
+    #[test]
+    fn test_mask_bokeh() {
+        // only the top left pixel is bright, so the lens is the top left quarter of the square.
+        let mut image = Canvas::new(2, 2);
+        image.write_pixel(0, 0, C![1., 1., 1.]);
+        image.write_pixel(1, 0, C![0.4, 0.4, 0.4]);
+        let mask = Bokeh::Mask(BokehMask::new(&image).unwrap());
+        let halton = Halton::new();
+        for i in 0..64 {
+            let (u, v) = halton.point(i);
+            let (x, y) = mask.point(u, v);
+            assert!((-1. ..=0.).contains(&x) && (0. ..=1.).contains(&y));
+        }
+
+        let mut c = Camera::new(10, 10, PI / 2.);
+        c.set_aperture(0.5);
+        c.set_bokeh(mask);
+        let (x, y) = c.lens_point(2, 3, 5);
+        assert!((-0.25..=0.).contains(&x) && (0. ..=0.25).contains(&y));
+    }
+
+    #[test]
+    fn test_empty_mask_bokeh() {
+        assert_eq!(None, BokehMask::new(&Canvas::new(2, 2)));
+    }
+
+    #[test]
+    fn test_edge_pixels() {
+        let mut image = Canvas::new(4, 2);
+        for y in 0..2 {
+            image.write_pixel(2, y, C![0.5, 0.5, 0.5]);
+            image.write_pixel(3, y, C![0.55, 0.5, 0.5]);
+        }
+        // only the pixels either side of the jump from black to grey differ by more than the threshold.
+        assert_eq!(
+            vec![false, true, true, false, false, true, true, false],
+            edge_pixels(&image, 0.1)
+        );
+        assert!(edge_pixels(&image, 0.01)[3]);
+    }
+
+    #[test]
+    fn test_render_adaptive() {
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(view_transformation(
+            P![0., 0., -5.],
+            P![0., 0., 0.],
+            V![0., 1., 0.],
+        ));
+        c.set_sampler(PixelSampler::R2);
+        let w = World::default();
+        let full = c.render(w.clone());
+
+        // with a threshold nothing reaches, every pixel keeps its one sample through the middle.
+        assert_eq!(full, c.render_adaptive(&w, 2, 10.).unwrap());
+
+        // otherwise pixels on the sphere's edge are refined and the rest are left alone.
+        let adaptive = c.render_adaptive(&w, 2, 0.1).unwrap();
+        let edges = edge_pixels(&full, 0.1);
+        assert!(edges.contains(&true) && edges.contains(&false));
+        for y in 0..11 {
+            for x in 0..11 {
+                if !edges[y * 11 + x] {
+                    assert_eq!(full.pixel_at(x, y), adaptive.pixel_at(x, y));
+                }
+            }
+        }
+        assert_ne!(full, adaptive);
+        let refined = c
+            .render_progressive(&w, 2, Duration::from_secs(60))
+            .unwrap();
+        assert!(refined.diff(&adaptive).mse() < refined.diff(&full).mse());
+    }
+
+    #[test]
+    fn test_sampler() {
+        assert_eq!(PixelSampler::Rgss, PixelSampler::default());
+        assert_eq!((0.5, 0.5), PixelSampler::R2.point(0));
+        assert_eq!(None, PixelSampler::R2.count());
+
+        // the rotated grid has one sample in each quarter of the pixel, and in each row and column of a 4x4 grid.
+        let rgss: Vec<_> = (0..4)
+            .map(|sample| PixelSampler::Rgss.point(sample))
+            .collect();
+        assert_eq!(Some(4), PixelSampler::Rgss.count());
+        assert_eq!(rgss[0], PixelSampler::Rgss.point(4));
+        for (i, &(dx, dy)) in rgss.iter().enumerate() {
+            let quarter = |(x, y): (f64, f64)| ((x * 2.).floor(), (y * 2.).floor());
+            let (col, row) = ((dx * 4.).floor(), (dy * 4.).floor());
+            for &other in &rgss[i + 1..] {
+                assert_ne!(quarter((dx, dy)), quarter(other));
+                assert_ne!(col, (other.0 * 4.).floor());
+                assert_ne!(row, (other.1 * 4.).floor());
+            }
+        }
+
+        for sample in 0..MAX_PROGRESSIVE_SAMPLES {
+            let (dx, dy) = PixelSampler::R2.point(sample);
+            assert!((0. ..1.).contains(&dx) && (0. ..1.).contains(&dy));
+            assert_eq!((0.5, 0.5), PixelSampler::Centre.point(sample));
+        }
+
+        let halton = PixelSampler::Halton(Halton::shifted(0.5, 0.5));
+        assert_eq!((0.5, 0.5), halton.point(0));
+        assert_eq!(None, halton.count());
+        assert_eq!(Some(9), PixelSampler::Jittered(Jittered::new(3, 0)).count());
+        let noise = BlueNoise::new(8, 0);
+        assert_eq!(
+            noise.point(3),
+            PixelSampler::BlueNoise(noise.clone()).point(3)
+        );
+
+        let fixed = PixelSampler::Fixed(vec![(0., 0.), (1., 0.5)]);
+        assert_eq!((1., 0.5), fixed.point(1));
+        assert_eq!((0., 0.), fixed.point(2));
+    }
+
+    #[test]
+    fn test_ray_for_sample() {
+        let mut c = Camera::new(200, 100, PI / 2.);
+        // the first sample of the R2 sequence goes through the centre of the pixel.
+        c.set_sampler(PixelSampler::R2);
+        assert_eq!(c.ray_for_pixel(100, 50), c.ray_for_sample(100, 50, 0));
+
+        // a fixed sampler picks exactly where each ray goes.
+        c.set_sampler(PixelSampler::Fixed(vec![(0., 0.), (1., 1.)]));
+        assert_eq!(c.ray_through(100., 50.), c.ray_for_sample(100, 50, 0));
+        assert_eq!(c.ray_for_sample(100, 50, 1), c.ray_for_sample(100, 50, 3));
+        let r = c.ray_for_sample(0, 0, 0);
+        assert_eq!(P![0., 0., 0.], r.origin());
+        assert_eq!(V![1., 0.5, -1.].norm(), r.direction());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_empty_fixed_sampler() {
+        Camera::new(10, 10, PI / 2.).set_sampler(PixelSampler::Fixed(vec![]));
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(Ok(Duration::from_secs(60)), parse_duration("60s"));
+        assert_eq!(Ok(Duration::from_secs(60)), parse_duration("60"));
+        assert_eq!(Ok(Duration::from_millis(500)), parse_duration("500ms"));
+        assert_eq!(Ok(Duration::from_secs(90)), parse_duration("1.5m"));
+        assert_eq!(Ok(Duration::from_secs(7200)), parse_duration("2h"));
+        assert!(parse_duration("s").is_err());
+        assert!(parse_duration("10 days").is_err());
+        assert_eq!(
+            Err("'99999999999999999999999h' is too long".to_string()),
+            parse_duration("99999999999999999999999h")
+        );
+    }
 }
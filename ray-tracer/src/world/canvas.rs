@@ -1,31 +1,425 @@
-use std::{io::Write, ops::Deref, panic};
+use std::{fmt::Display, io, io::Write, panic};
 
-use ndarray::Array;
+use builder_derive::Builder;
+use ndarray::{s, Array};
 
 use crate::primatives::color::Color;
 
+/// Dither adds a small offset to each pixel before it is rounded to 8 bits.
+/// This breaks up the visible bands that smooth gradients get when quantized.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Dither {
+    #[default]
+    None,
+    /// Ordered uses a repeating 4x4 Bayer matrix.
+    Ordered,
+    /// BlueNoise uses interleaved gradient noise which has no visible repeating structure.
+    BlueNoise,
+}
+
+const BAYER_4X4: [[f64; 4]; 4] = [
+    [0., 8., 2., 10.],
+    [12., 4., 14., 6.],
+    [3., 11., 1., 9.],
+    [15., 7., 13., 5.],
+];
+
+impl Dither {
+    /// offset returns a value between -0.5 and 0.5 to add to a pixel before rounding.
+    pub fn offset(&self, x: usize, y: usize) -> f64 {
+        match self {
+            Dither::None => 0.,
+            Dither::Ordered => (BAYER_4X4[y % 4][x % 4] + 0.5) / 16. - 0.5,
+            Dither::BlueNoise => {
+                let f = 0.06711056 * x as f64 + 0.00583715 * y as f64;
+                let noise = 52.9829189 * f.fract();
+                noise.fract() - 0.5
+            }
+        }
+    }
+}
+
+/// NEUTRAL_TEMPERATURE is the color temperature in kelvin of the light that is already white in a render, daylight.
+pub const NEUTRAL_TEMPERATURE: f64 = 6500.;
+
+/// WhiteBalance makes light of a color temperature look white, so a render lit with warm or cool lights can be
+/// made neutral without rendering it again. temperature is the kelvin of the light to make white, lower for warmer
+/// light, and tint moves between green and magenta, from -1 to 1, with more tint taking out more green.
+/// Colors are scaled so that a gray keeps its brightness.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WhiteBalance {
+    temperature: f64,
+    tint: f64,
+}
+
+impl WhiteBalance {
+    pub const NEUTRAL: WhiteBalance = WhiteBalance {
+        temperature: NEUTRAL_TEMPERATURE,
+        tint: 0.,
+    };
+    pub const TUNGSTEN: WhiteBalance = WhiteBalance {
+        temperature: 3200.,
+        tint: 0.,
+    };
+    /// FLUORESCENT lights are a little green as well as warm.
+    pub const FLUORESCENT: WhiteBalance = WhiteBalance {
+        temperature: 4000.,
+        tint: 0.3,
+    };
+    pub const DAYLIGHT: WhiteBalance = WhiteBalance {
+        temperature: 5500.,
+        tint: 0.,
+    };
+    pub const CLOUDY: WhiteBalance = WhiteBalance {
+        temperature: 7500.,
+        tint: 0.,
+    };
+    pub const SHADE: WhiteBalance = WhiteBalance {
+        temperature: 9000.,
+        tint: 0.,
+    };
+
+    /// PRESETS are the names of the presets parse_white_balance understands.
+    pub const PRESETS: [(&'static str, WhiteBalance); 6] = [
+        ("neutral", WhiteBalance::NEUTRAL),
+        ("tungsten", WhiteBalance::TUNGSTEN),
+        ("fluorescent", WhiteBalance::FLUORESCENT),
+        ("daylight", WhiteBalance::DAYLIGHT),
+        ("cloudy", WhiteBalance::CLOUDY),
+        ("shade", WhiteBalance::SHADE),
+    ];
+
+    pub fn new(temperature: f64, tint: f64) -> Self {
+        assert!(
+            (2000. ..=15000.).contains(&temperature),
+            "a white balance temperature must be between 2000 and 15000 kelvin"
+        );
+        assert!(
+            (-1. ..=1.).contains(&tint),
+            "a white balance tint must be between -1 and 1"
+        );
+        Self { temperature, tint }
+    }
+
+    pub fn temperature(&self) -> f64 {
+        self.temperature
+    }
+
+    pub fn tint(&self) -> f64 {
+        self.tint
+    }
+
+    /// gains are what each channel is multiplied by.
+    pub fn gains(&self) -> Color {
+        let (white, light) = (blackbody(NEUTRAL_TEMPERATURE), blackbody(self.temperature));
+        let gains = Color::new(
+            white.red() / light.red(),
+            white.green() / light.green() * (1. - 0.25 * self.tint),
+            white.blue() / light.blue(),
+        );
+        gains * (1. / gains.luminance())
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        color * self.gains()
+    }
+}
+
+impl Default for WhiteBalance {
+    fn default() -> Self {
+        WhiteBalance::NEUTRAL
+    }
+}
+
+/// blackbody is roughly the color of light glowing at the temperature in kelvin, using Tanner Helland's fit.
+fn blackbody(temperature: f64) -> Color {
+    let t = temperature / 100.;
+    let red = if t <= 66. {
+        255.
+    } else {
+        329.698727446 * (t - 60.).powf(-0.1332047592)
+    };
+    let green = if t <= 66. {
+        99.4708025861 * t.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (t - 60.).powf(-0.0755148492)
+    };
+    let blue = if t >= 66. {
+        255.
+    } else {
+        138.5177312231 * (t - 10.).ln() - 305.0447927307
+    };
+    Color::new(red, green, blue) * (1. / 255.)
+}
+
+/// parse_white_balance reads a white balance given as a preset name, such as tungsten, or as a temperature in kelvin
+/// with an optional tint, such as 3200 or 4000:0.3.
+pub fn parse_white_balance(text: &str) -> Result<WhiteBalance, String> {
+    let text = text.trim();
+    if let Some((_, preset)) = WhiteBalance::PRESETS.iter().find(|(name, _)| *name == text) {
+        return Ok(*preset);
+    }
+    let (temperature, tint) = text.split_once(':').unwrap_or((text, "0"));
+    let number = |n: &str| {
+        n.trim().parse::<f64>().map_err(|_| {
+            let presets: Vec<&str> = WhiteBalance::PRESETS
+                .iter()
+                .map(|(name, _)| *name)
+                .collect();
+            format!(
+                "'{}' should be one of {} or a temperature such as 3200 or 4000:0.3",
+                text,
+                presets.join(", ")
+            )
+        })
+    };
+    let (temperature, tint) = (number(temperature)?, number(tint)?);
+    if !(2000. ..=15000.).contains(&temperature) {
+        return Err(format!(
+            "temperature {} must be between 2000 and 15000",
+            temperature
+        ));
+    }
+    if !(-1. ..=1.).contains(&tint) {
+        return Err(format!("tint {} must be between -1 and 1", tint));
+    }
+    Ok(WhiteBalance::new(temperature, tint))
+}
+
+/// ToneMap squeezes the brightness of a render, which has no upper limit, into the 0 to 1 an image can hold.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ToneMap {
+    /// Clamp cuts every channel off at 1, so anything brighter than white, such as a bright specular highlight,
+    /// clips to flat white.
+    #[default]
+    Clamp,
+    /// Reinhard scales each color by 1 / (1 + its luminance), which barely touches dark colors and brings any
+    /// brightness below white while keeping the color's hue.
+    Reinhard,
+    /// Aces is Krzysztof Narkowicz's fit of the ACES filmic curve, with a toe that deepens the shadows and a
+    /// shoulder that rolls highlights off towards white like film does.
+    Aces,
+}
+
+impl ToneMap {
+    /// NAMES are the names of the tone maps parse_tone_map understands.
+    pub const NAMES: [(&'static str, ToneMap); 3] = [
+        ("clamp", ToneMap::Clamp),
+        ("reinhard", ToneMap::Reinhard),
+        ("aces", ToneMap::Aces),
+    ];
+
+    /// apply maps a color to one that fits in 0 to 1, or would once clamped.
+    pub fn apply(&self, color: Color) -> Color {
+        match self {
+            ToneMap::Clamp => color,
+            ToneMap::Reinhard => color * (1. / (1. + color.luminance().max(0.))),
+            ToneMap::Aces => {
+                let curve = |x: f64| {
+                    let x = x.max(0.);
+                    (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+                };
+                Color::new(
+                    curve(color.red()),
+                    curve(color.green()),
+                    curve(color.blue()),
+                )
+            }
+        }
+    }
+}
+
+/// parse_tone_map reads a tone map given by name, such as aces.
+pub fn parse_tone_map(text: &str) -> Result<ToneMap, String> {
+    let text = text.trim();
+    ToneMap::NAMES
+        .iter()
+        .find(|(name, _)| *name == text)
+        .map(|(_, tone_map)| *tone_map)
+        .ok_or_else(|| {
+            let names: Vec<&str> = ToneMap::NAMES.iter().map(|(name, _)| *name).collect();
+            format!("'{}' should be one of {}", text, names.join(", "))
+        })
+}
+
+/// SaveOptions changes how a canvas is written to an image file.
+#[derive(Debug, Clone, PartialEq, Default, Builder)]
+#[builder(default)]
+pub struct SaveOptions {
+    dither: Dither,
+    white_balance: WhiteBalance,
+    /// exposure brightens the image by this many stops, doubling it for each one, or darkens it if negative.
+    exposure: f64,
+    tone_map: ToneMap,
+}
+
+impl SaveOptions {
+    pub fn dither(&self) -> Dither {
+        self.dither
+    }
+
+    /// white_balance is applied to every pixel before it is quantized.
+    pub fn white_balance(&self) -> WhiteBalance {
+        self.white_balance
+    }
+
+    pub fn exposure(&self) -> f64 {
+        self.exposure
+    }
+
+    pub fn tone_map(&self) -> ToneMap {
+        self.tone_map
+    }
+
+    /// develop turns a rendered color into the one to save, balancing its white, then exposing it and then tone
+    /// mapping it, leaving it ready to be quantized.
+    pub fn develop(&self, color: Color) -> Color {
+        let exposed = self.white_balance.apply(color) * 2_f64.powf(self.exposure);
+        self.tone_map.apply(exposed)
+    }
+}
+
+/// quantize clamps a color channel between 0 and 1 and scales it to a byte, adding the dither offset before rounding.
+fn quantize(value: f64, offset: f64) -> u8 {
+    let scaled = value.clamp(0.0, 1.0) * 255.0 + offset;
+    scaled.round().clamp(0.0, 255.0) as u8
+}
+
+/// DIFF_THRESHOLD is how far apart two channels of a pixel must be for the pixel to count as different,
+/// which is one step of an 8 bit image so that differences too small to be saved are ignored.
+pub const DIFF_THRESHOLD: f64 = 1. / 255.;
+
+/// SSIM_WINDOW is the width and height of the squares of pixels whose structure is compared.
+const SSIM_WINDOW: usize = 8;
+
+/// DiffReport describes how two canvases of the same size differ, from Canvas::diff.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffReport {
+    /// max_delta is the largest difference between the same channel of the same pixel.
+    max_delta: f64,
+    /// mse is the mean of the squared differences over every channel of every pixel.
+    mse: f64,
+    /// ssim is the structural similarity of the images' brightness, 1 when they are the same and lower as
+    /// their local contrast and structure drift apart. Unlike mse it is barely moved by noise spread thinly over
+    /// an image but falls quickly when an edge or a shadow moves, which is closer to what people notice.
+    ssim: f64,
+    /// differing is how many pixels have a channel that differs by more than DIFF_THRESHOLD.
+    differing: usize,
+    /// highlight is a dimmed grey copy of the first canvas with the differing pixels drawn in red,
+    /// brighter the more they differ.
+    highlight: Canvas,
+}
+
+impl DiffReport {
+    pub fn max_delta(&self) -> f64 {
+        self.max_delta
+    }
+
+    pub fn mse(&self) -> f64 {
+        self.mse
+    }
+
+    pub fn ssim(&self) -> f64 {
+        self.ssim
+    }
+
+    pub fn differing(&self) -> usize {
+        self.differing
+    }
+
+    pub fn highlight(&self) -> &Canvas {
+        &self.highlight
+    }
+
+    /// is_identical checks that no pixel differs by more than DIFF_THRESHOLD.
+    pub fn is_identical(&self) -> bool {
+        self.differing == 0
+    }
+}
+
+/// MAX_PIXELS is the largest canvas that can be made, 100 megapixels or about 2.4GB of colors.
+/// Anything bigger is almost certainly a typing mistake, and can still be rendered straight to a file.
+pub const MAX_PIXELS: usize = 100_000_000;
+
+/// STREAM_THRESHOLD is the number of pixels above which renders are written to their file a band of rows at a time
+/// rather than held in a canvas, 16 megapixels or about 400MB of colors.
+pub const STREAM_THRESHOLD: usize = 16_000_000;
+
+/// CanvasError is returned when a canvas can't be made.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CanvasError {
+    /// TooLarge is a canvas with more than MAX_PIXELS pixels.
+    TooLarge { width: usize, height: usize },
+    /// OutOfMemory is a canvas that is allowed but that there isn't enough memory for.
+    OutOfMemory { width: usize, height: usize },
+}
+
+impl Display for CanvasError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CanvasError::TooLarge { width, height } => write!(
+                f,
+                "a {}x{} canvas is larger than the limit of {} pixels",
+                width, height, MAX_PIXELS
+            ),
+            CanvasError::OutOfMemory { width, height } => {
+                write!(
+                    f,
+                    "there is not enough memory for a {}x{} canvas",
+                    width, height
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for CanvasError {}
+
+/// pixel_count is the number of pixels in an image of the given size, or an error if it has more than MAX_PIXELS.
+pub(crate) fn pixel_count(width: usize, height: usize) -> Result<usize, CanvasError> {
+    width
+        .checked_mul(height)
+        .filter(|&count| count <= MAX_PIXELS)
+        .ok_or(CanvasError::TooLarge { width, height })
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct Canvas {
+    /// pixels are stored a row at a time and indexed (y, x), the order images are rendered and saved in,
+    /// so walking along a row walks along memory.
     pixels: ndarray::Array2<Color>,
 }
 
 impl Canvas {
+    /// new makes a black canvas, panicking if it is too large. Use try_new for sizes that come from users.
     pub fn new(width: usize, height: usize) -> Self {
-        let pixels = Array::from_elem((width, height), Color::BLACK);
+        Self::try_new(width, height).unwrap_or_else(|err| panic!("{}", err))
+    }
 
-        Self { pixels }
+    /// try_new makes a black canvas, or an error if it has more than MAX_PIXELS pixels or can't be allocated.
+    pub fn try_new(width: usize, height: usize) -> Result<Self, CanvasError> {
+        let count = pixel_count(width, height)?;
+        let mut colors = Vec::new();
+        colors
+            .try_reserve_exact(count)
+            .map_err(|_| CanvasError::OutOfMemory { width, height })?;
+        colors.resize(count, Color::BLACK);
+        let pixels = Array::from_shape_vec((height, width), colors)
+            .expect("the canvas has one color for every pixel");
+
+        Ok(Self { pixels })
     }
 
     pub fn width(&self) -> usize {
-        self.pixels.shape()[0]
+        self.pixels.shape()[1]
     }
 
     pub fn height(&self) -> usize {
-        self.pixels.shape()[1]
+        self.pixels.shape()[0]
     }
 
     pub fn write_pixel(&mut self, x: usize, y: usize, color: Color) {
-        let pixel = self.pixels.get_mut((x, y));
+        let pixel = self.pixels.get_mut((y, x));
         match pixel {
             Some(pix_color) => *pix_color = color,
             None => panic!(
@@ -36,44 +430,209 @@ impl Canvas {
     }
 
     pub fn pixel_at(&self, x: usize, y: usize) -> Option<Color> {
-        if let Some(color) = self.get((x, y)) {
-            return Some(*color);
+        self.pixels.get((y, x)).copied()
+    }
+
+    /// pixels are every pixel of the canvas a row at a time, from the top left to the bottom right.
+    pub fn pixels(&self) -> impl Iterator<Item = Color> + '_ {
+        self.pixels.iter().copied()
+    }
+
+    /// row is the pixels of row y from left to right, or None if the canvas doesn't have that row.
+    pub fn row(&self, y: usize) -> Option<&[Color]> {
+        if y >= self.height() {
+            return None;
         }
-        None
+        self.pixels.row(y).to_slice()
     }
 
-    pub fn save(&self, out: &mut dyn Write) {
-        // write first 3 lines
-        write!(out, "P3\n{} {}\n255\n", self.width(), self.height())
-            .expect("failed to save canvas");
+    /// merge copies every pixel of a smaller canvas into this one with its top left corner at (x, y).
+    /// Renderers can fill separate slabs of the image on different threads and merge them once they are all done.
+    pub fn merge(&mut self, slab: &Canvas, x: usize, y: usize) {
+        assert!(
+            x + slab.width() <= self.width() && y + slab.height() <= self.height(),
+            "trying to merge a {}x{} canvas at {},{} into a {}x{} canvas",
+            slab.width(),
+            slab.height(),
+            x,
+            y,
+            self.width(),
+            self.height()
+        );
+        self.pixels
+            .slice_mut(s![y..y + slab.height(), x..x + slab.width()])
+            .assign(&slab.pixels);
+    }
 
-        // write each color
-        for row in self.columns() {
-            row.for_each(|pixel| writeln!(out, "{}", pixel).expect("could not write pixel"));
+    /// mean_luminance is the average brightness of every pixel.
+    pub fn mean_luminance(&self) -> f64 {
+        if self.pixels.is_empty() {
+            return 0.;
         }
+        self.pixels.iter().map(|c| c.luminance()).sum::<f64>() / self.pixels.len() as f64
+    }
+
+    /// luminance_centroid is the average position of the pixels weighted by how bright they are, measured from
+    /// the centre of the image. It points towards the brighter side of the image, so tests can check which way
+    /// lighting falls off without depending on the exact value of any pixel.
+    pub fn luminance_centroid(&self) -> (f64, f64) {
+        let (centre_x, centre_y) = (self.width() as f64 / 2., self.height() as f64 / 2.);
+        let (mut x, mut y, mut total) = (0., 0., 0.);
+        for ((py, px), color) in self.pixels.indexed_iter() {
+            let luminance = color.luminance();
+            x += (px as f64 + 0.5 - centre_x) * luminance;
+            y += (py as f64 + 0.5 - centre_y) * luminance;
+            total += luminance;
+        }
+        if total == 0. {
+            return (0., 0.);
+        }
+        (x / total, y / total)
+    }
+
+    /// diff compares this canvas with another of the same size, such as a render against a saved golden image.
+    pub fn diff(&self, other: &Canvas) -> DiffReport {
+        assert!(
+            self.width() == other.width() && self.height() == other.height(),
+            "trying to compare a {}x{} canvas with a {}x{} canvas",
+            self.width(),
+            self.height(),
+            other.width(),
+            other.height()
+        );
+        let mut highlight = Canvas::new(self.width(), self.height());
+        let (mut max_delta, mut squares, mut differing) = (0_f64, 0., 0);
+        for ((y, x), a) in self.pixels.indexed_iter() {
+            let b = other.pixels[(y, x)];
+            let deltas = [
+                a.red() - b.red(),
+                a.green() - b.green(),
+                a.blue() - b.blue(),
+            ];
+            let delta = deltas.iter().fold(0_f64, |max, d| max.max(d.abs()));
+            max_delta = max_delta.max(delta);
+            squares += deltas.iter().map(|d| d * d).sum::<f64>();
+
+            let grey = a.luminance().clamp(0., 1.) * 0.3;
+            let color = if delta > DIFF_THRESHOLD {
+                differing += 1;
+                Color::new(0.5 + delta.min(1.) * 0.5, 0., 0.)
+            } else {
+                Color::new(grey, grey, grey)
+            };
+            highlight.write_pixel(x, y, color);
+        }
+
+        DiffReport {
+            max_delta,
+            mse: if self.pixels.is_empty() {
+                0.
+            } else {
+                squares / (self.pixels.len() * 3) as f64
+            },
+            ssim: self.ssim(other),
+            differing,
+            highlight,
+        }
+    }
+
+    /// ssim is the mean structural similarity of the brightness of each window of the two canvases, using the
+    /// constants from Wang et al. for values between 0 and 1. Windows at the right and bottom edges can be smaller.
+    fn ssim(&self, other: &Canvas) -> f64 {
+        const C1: f64 = 0.01 * 0.01;
+        const C2: f64 = 0.03 * 0.03;
+        let mut total = 0.;
+        let mut windows = 0;
+        for wy in (0..self.height()).step_by(SSIM_WINDOW) {
+            for wx in (0..self.width()).step_by(SSIM_WINDOW) {
+                let region = s![
+                    wy..(wy + SSIM_WINDOW).min(self.height()),
+                    wx..(wx + SSIM_WINDOW).min(self.width())
+                ];
+                let a: Vec<f64> = self
+                    .pixels
+                    .slice(region)
+                    .iter()
+                    .map(|c| c.luminance().clamp(0., 1.))
+                    .collect();
+                let b: Vec<f64> = other
+                    .pixels
+                    .slice(region)
+                    .iter()
+                    .map(|c| c.luminance().clamp(0., 1.))
+                    .collect();
+                let n = a.len() as f64;
+                let (mean_a, mean_b) = (a.iter().sum::<f64>() / n, b.iter().sum::<f64>() / n);
+                let (mut var_a, mut var_b, mut covariance) = (0., 0., 0.);
+                for (a, b) in a.iter().zip(&b) {
+                    var_a += (a - mean_a).powi(2) / n;
+                    var_b += (b - mean_b).powi(2) / n;
+                    covariance += (a - mean_a) * (b - mean_b) / n;
+                }
+                total += ((2. * mean_a * mean_b + C1) * (2. * covariance + C2))
+                    / ((mean_a.powi(2) + mean_b.powi(2) + C1) * (var_a + var_b + C2));
+                windows += 1;
+            }
+        }
+        if windows == 0 {
+            return 1.;
+        }
+        total / windows as f64
+    }
+
+    pub fn save(&self, out: &mut dyn Write) {
+        self.save_with_options(out, &SaveOptions::default())
     }
-}
 
-impl Deref for Canvas {
-    type Target = ndarray::Array2<Color>;
+    /// save_with_options writes the canvas as a ppm file, quantizing each pixel with the given options.
+    pub fn save_with_options(&self, out: &mut dyn Write, options: &SaveOptions) {
+        write_ppm_header(out, self.width(), self.height()).expect("failed to save canvas");
+        self.write_ppm_rows(out, options, 0)
+            .expect("could not write pixel");
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.pixels
+    /// write_ppm_rows writes every row of the canvas as the body of a ppm file. first_row is where the canvas's top
+    /// row is in the whole image, so that a canvas holding one band of a larger image is dithered to match the rest.
+    pub fn write_ppm_rows(
+        &self,
+        out: &mut dyn Write,
+        options: &SaveOptions,
+        first_row: usize,
+    ) -> io::Result<()> {
+        for (y, row) in self.pixels.rows().into_iter().enumerate() {
+            for (x, &pixel) in row.iter().enumerate() {
+                let pixel = options.develop(pixel);
+                let offset = options.dither().offset(x, first_row + y);
+                writeln!(
+                    out,
+                    "{} {} {}",
+                    quantize(pixel.red(), offset),
+                    quantize(pixel.green(), offset),
+                    quantize(pixel.blue(), offset)
+                )?;
+            }
+        }
+        Ok(())
     }
 }
 
+/// write_ppm_header writes the first three lines of a ppm file of the given size.
+pub fn write_ppm_header(out: &mut dyn Write, width: usize, height: usize) -> io::Result<()> {
+    write!(out, "P3\n{} {}\n255\n", width, height)
+}
+
 #[cfg(test)]
 mod test_canvas {
     use super::*;
-    use crate::C;
+    use crate::{comparison::approx_eq, C};
 
     #[test]
     fn test_new() {
         let c = Canvas::new(10, 20);
         assert_eq!(c.width(), 10);
         assert_eq!(c.height(), 20);
-        for pixel in c.iter() {
-            assert_eq!(*pixel, Color::BLACK)
+        for pixel in c.pixels() {
+            assert_eq!(pixel, Color::BLACK)
         }
     }
 
@@ -84,7 +643,23 @@ mod test_canvas {
 
         c.write_pixel(2, 3, red);
 
-        assert_eq!(*c.pixels.get((2, 3)).unwrap(), red);
+        assert_eq!(*c.pixels.get((3, 2)).unwrap(), red);
+        assert_eq!(Some(red), c.pixel_at(2, 3));
+    }
+
+    #[test]
+    fn test_row_major_layout() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(2, 1, Color::WHITE);
+        assert_eq!((3, 2), (c.width(), c.height()));
+
+        // each row is one run of memory, and pixels run along the rows.
+        assert_eq!(
+            Some(&[Color::BLACK, Color::BLACK, Color::WHITE][..]),
+            c.row(1)
+        );
+        assert_eq!(None, c.row(2));
+        assert_eq!(Some(5), c.pixels().position(|pixel| pixel == Color::WHITE));
     }
 
     #[test]
@@ -168,4 +743,276 @@ mod test_canvas {
 
         assert_eq!(got, want);
     }
+
+    #[test]
+    fn test_dither_offsets() {
+        for dither in [Dither::None, Dither::Ordered, Dither::BlueNoise] {
+            for y in 0..8 {
+                for x in 0..8 {
+                    let offset = dither.offset(x, y);
+                    assert!((-0.5..0.5).contains(&offset));
+                }
+            }
+        }
+        assert_eq!(0., Dither::None.offset(3, 2));
+    }
+
+    #[test]
+    fn test_save_dithered() {
+        // colors that are exactly representable are not changed by dithering.
+        let mut c = Canvas::new(4, 4);
+        c.write_pixel(1, 1, Color::WHITE);
+        let mut plain = Vec::new();
+        let mut dithered = Vec::new();
+        c.save(&mut plain);
+        let options = SaveOptions::builder()
+            .dither(Dither::Ordered)
+            .build()
+            .unwrap();
+        c.save_with_options(&mut dithered, &options);
+        assert_eq!(plain, dithered);
+
+        // a value between two steps is spread over both of them.
+        let mut c = Canvas::new(4, 4);
+        let between = 127.5 / 255.;
+        c.pixels.fill(C!(between, between, between));
+        let mut out = Vec::new();
+        c.save_with_options(&mut out, &options);
+        let got = String::from_utf8(out).unwrap();
+        let values: Vec<u32> = got
+            .lines()
+            .skip(3)
+            .map(|l| l.split(' ').next().unwrap().parse().unwrap())
+            .collect();
+
+        assert!(values.contains(&127));
+        assert!(values.contains(&128));
+        let mean = values.iter().sum::<u32>() as f64 / values.len() as f64;
+        assert!((mean - 127.5).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_white_balance() {
+        let gray = C![0.5, 0.5, 0.5];
+        assert_eq!(gray, WhiteBalance::NEUTRAL.apply(gray));
+        assert_eq!(Color::WHITE, WhiteBalance::default().gains());
+
+        // a white surface lit by tungsten light comes out white again.
+        let (light, white) = (blackbody(3200.), blackbody(NEUTRAL_TEMPERATURE));
+        let warm = C![
+            light.red() / white.red(),
+            light.green() / white.green(),
+            light.blue() / white.blue()
+        ];
+        assert!(warm.red() > warm.blue());
+        let balanced = WhiteBalance::TUNGSTEN.apply(warm);
+        assert!((balanced.red() - balanced.green()).abs() < 1e-9);
+        assert!((balanced.red() - balanced.blue()).abs() < 1e-9);
+
+        // grays keep their brightness and tint takes out green.
+        for balance in WhiteBalance::PRESETS.map(|(_, balance)| balance) {
+            assert!((balance.apply(gray).luminance() - 0.5).abs() < 1e-9);
+        }
+        let tinted = WhiteBalance::new(NEUTRAL_TEMPERATURE, 0.5).apply(gray);
+        assert!(tinted.green() < tinted.red());
+        assert_eq!(tinted.red(), tinted.blue());
+    }
+
+    #[test]
+    fn test_parse_white_balance() {
+        assert_eq!(Ok(WhiteBalance::TUNGSTEN), parse_white_balance("tungsten"));
+        assert_eq!(
+            Ok(WhiteBalance::new(4200., 0.)),
+            parse_white_balance(" 4200 ")
+        );
+        assert_eq!(
+            Ok(WhiteBalance::new(4000., -0.2)),
+            parse_white_balance("4000:-0.2")
+        );
+        assert!(parse_white_balance("tungstn").is_err());
+        assert!(parse_white_balance("1000").is_err());
+        assert!(parse_white_balance("5000:2").is_err());
+    }
+
+    #[test]
+    fn test_save_white_balanced() {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, C![0.5, 0.5, 0.5]);
+        let options = SaveOptions::builder()
+            .white_balance(WhiteBalance::TUNGSTEN)
+            .build()
+            .unwrap();
+        let mut out = Vec::new();
+        c.save_with_options(&mut out, &options);
+
+        // a gray lit by white light turns blue when the warmth of tungsten light is taken out.
+        let got = String::from_utf8(out).unwrap();
+        let values: Vec<u8> = got
+            .lines()
+            .nth(3)
+            .unwrap()
+            .split(' ')
+            .map(|v| v.parse().unwrap())
+            .collect();
+        assert!(
+            values[0] < values[1] && values[1] < values[2],
+            "{:?}",
+            values
+        );
+    }
+
+    #[test]
+    fn test_tone_map() {
+        let bright = C![4., 2., 1.];
+        assert_eq!(bright, ToneMap::Clamp.apply(bright));
+
+        // reinhard brings the brightness below white but keeps the balance between the channels.
+        let reinhard = ToneMap::Reinhard.apply(bright);
+        assert!(reinhard.luminance() < 1.);
+        assert!(approx_eq(2., reinhard.red() / reinhard.green()));
+        assert_eq!(Color::BLACK, ToneMap::Reinhard.apply(Color::BLACK));
+
+        // aces rolls off towards white and passes through black.
+        let aces = ToneMap::Aces.apply(bright);
+        assert!(aces.red() < 1. && aces.red() > aces.green() && aces.green() > aces.blue());
+        assert!(ToneMap::Aces.apply(C![100., 100., 100.]).red() > 0.99);
+        assert_eq!(Color::BLACK, ToneMap::Aces.apply(Color::BLACK));
+
+        assert_eq!(Ok(ToneMap::Aces), parse_tone_map(" aces"));
+        assert_eq!(
+            Err("'filmic' should be one of clamp, reinhard, aces".to_string()),
+            parse_tone_map("filmic")
+        );
+    }
+
+    #[test]
+    fn test_save_exposed() {
+        // a highlight too bright to save and a mid gray.
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, C![3., 3., 3.]);
+        c.write_pixel(1, 0, C![0.25, 0.25, 0.25]);
+        let saved = |options: &SaveOptions| -> Vec<u8> {
+            let mut out = Vec::new();
+            c.save_with_options(&mut out, options);
+            String::from_utf8(out)
+                .unwrap()
+                .lines()
+                .skip(3)
+                .map(|line| line.split(' ').next().unwrap().parse().unwrap())
+                .collect()
+        };
+        assert_eq!(vec![255, 64], saved(&SaveOptions::default()));
+
+        // one stop up doubles the gray and the highlight still clips.
+        let brighter = SaveOptions::builder().exposure(1.).build().unwrap();
+        assert_eq!(vec![255, 128], saved(&brighter));
+
+        // tone mapping keeps the highlight below white.
+        let mapped = SaveOptions::builder()
+            .tone_map(ToneMap::Reinhard)
+            .build()
+            .unwrap();
+        assert_eq!(vec![191, 51], saved(&mapped));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut c = Canvas::new(4, 5);
+        let mut slab = Canvas::new(4, 2);
+        slab.write_pixel(0, 0, Color::WHITE);
+        slab.write_pixel(3, 1, C!(0.5, 0.5, 0.5));
+
+        c.merge(&slab, 0, 3);
+
+        assert_eq!(Some(Color::WHITE), c.pixel_at(0, 3));
+        assert_eq!(Some(C!(0.5, 0.5, 0.5)), c.pixel_at(3, 4));
+        assert_eq!(Some(Color::BLACK), c.pixel_at(0, 0));
+    }
+
+    #[test]
+    fn test_luminance_statistics() {
+        let mut c = Canvas::new(4, 4);
+        assert_eq!(0., c.mean_luminance());
+        assert_eq!((0., 0.), c.luminance_centroid());
+
+        // light up the right hand column.
+        (0..4).for_each(|y| c.write_pixel(3, y, Color::WHITE));
+        assert!(approx_eq(0.25, c.mean_luminance()));
+        let (x, y) = c.luminance_centroid();
+        assert!(approx_eq(1.5, x));
+        assert!(approx_eq(0., y));
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut a = Canvas::new(16, 16);
+        for (x, y) in (0..16).flat_map(|x| (0..16).map(move |y| (x, y))) {
+            let shade = (x + y) as f64 / 30.;
+            a.write_pixel(x, y, C!(shade, shade, shade));
+        }
+
+        let same = a.diff(&a.clone());
+        assert_eq!(0., same.max_delta());
+        assert_eq!(0., same.mse());
+        assert!(approx_eq(1., same.ssim()));
+        assert!(same.is_identical());
+
+        // a change smaller than an 8 bit step doesn't count as a difference.
+        let mut b = a.clone();
+        b.write_pixel(0, 0, C!(0.001, 0., 0.));
+        assert!(a.diff(&b).is_identical());
+
+        // a bright block in one corner.
+        (0..4).for_each(|x| (0..4).for_each(|y| b.write_pixel(x, y, Color::WHITE)));
+        let report = a.diff(&b);
+        assert_eq!(16, report.differing());
+        assert!(approx_eq(1., report.max_delta()));
+        assert!(report.mse() > 0.);
+        assert!(report.ssim() < 1.);
+        assert_eq!(Some(C!(1., 0., 0.)), report.highlight().pixel_at(0, 0));
+        let grey = report.highlight().pixel_at(8, 8).unwrap();
+        assert!(grey.red() == grey.green() && grey.red() < 0.3);
+
+        // thin noise over the whole image hurts the structure less than a block that stands out.
+        let mut noisy = a.clone();
+        for (x, y) in (0..16).flat_map(|x| (0..16).map(move |y| (x, y))) {
+            let pixel = noisy.pixel_at(x, y).unwrap();
+            let offset = if (x + y) % 2 == 0 { 0.02 } else { -0.02 };
+            noisy.write_pixel(x, y, pixel + C!(offset, offset, offset));
+        }
+        assert!(a.diff(&noisy).ssim() > report.ssim());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_diff_different_sizes() {
+        Canvas::new(4, 4).diff(&Canvas::new(4, 2));
+    }
+
+    #[test]
+    fn test_try_new() {
+        assert_eq!(Ok(Canvas::new(4, 2)), Canvas::try_new(4, 2));
+        assert_eq!(
+            Err(CanvasError::TooLarge {
+                width: 100_000,
+                height: 100_000
+            }),
+            Canvas::try_new(100_000, 100_000)
+        );
+        // sizes whose pixel count doesn't fit in a usize are too large too.
+        assert!(Canvas::try_new(usize::MAX, 2).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "larger than the limit")]
+    fn test_new_too_large() {
+        Canvas::new(MAX_PIXELS + 1, 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_merge_too_large() {
+        let mut c = Canvas::new(4, 4);
+        c.merge(&Canvas::new(4, 2), 0, 3);
+    }
 }
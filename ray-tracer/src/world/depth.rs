@@ -0,0 +1,288 @@
+use std::io::{self, Write};
+
+use ndarray::Array2;
+
+use super::canvas::{pixel_count, CanvasError};
+
+/// DepthRange is how depths are spread over the grey levels of a depth image.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DepthRange {
+    /// Auto runs from the nearest surface in the image to the furthest.
+    #[default]
+    Auto,
+    /// Fixed runs from near to far, with depths outside of them clamped. Fixed ranges keep the grey levels of
+    /// every frame of an animation the same.
+    Fixed { near: f64, far: f64 },
+}
+
+/// DepthMap is the depth of the surface seen through each pixel: how far in front of the camera it is, measured
+/// along the direction the camera looks rather than along the ray, which is what compositing tools expect.
+/// Pixels that see nothing are infinitely deep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthMap {
+    depths: Array2<f64>,
+}
+
+impl DepthMap {
+    /// new makes a map of the given size where every pixel sees nothing.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            depths: Array2::from_elem((width, height), f64::INFINITY),
+        }
+    }
+
+    /// try_new is new, or an error if the map would have more pixels than a canvas can.
+    pub fn try_new(width: usize, height: usize) -> Result<Self, CanvasError> {
+        pixel_count(width, height)?;
+        Ok(Self::new(width, height))
+    }
+
+    pub fn width(&self) -> usize {
+        self.depths.nrows()
+    }
+
+    pub fn height(&self) -> usize {
+        self.depths.ncols()
+    }
+
+    /// depth_at is the depth seen through the pixel, or None if it sees nothing or is outside the map.
+    pub fn depth_at(&self, x: usize, y: usize) -> Option<f64> {
+        self.depths.get((x, y)).copied().filter(|d| d.is_finite())
+    }
+
+    pub fn write_depth(&mut self, x: usize, y: usize, depth: f64) {
+        if let Some(d) = self.depths.get_mut((x, y)) {
+            *d = depth;
+        }
+    }
+
+    /// range is the nearest and furthest depths seen, or None when every pixel sees nothing.
+    pub fn range(&self) -> Option<(f64, f64)> {
+        self.depths
+            .iter()
+            .filter(|d| d.is_finite())
+            .fold(None, |range, &d| match range {
+                None => Some((d, d)),
+                Some((near, far)) => Some((d.min(near), d.max(far))),
+            })
+    }
+
+    /// save_pfm writes the raw depths as a greyscale pfm image of 32 bit floats, which keeps the real distances
+    /// for tools that do their own mapping. Pixels that see nothing are written as infinity.
+    pub fn save_pfm(&self, out: &mut dyn Write) -> io::Result<()> {
+        // a negative scale marks the floats as little endian.
+        write!(out, "Pf\n{} {}\n-1.0\n", self.width(), self.height())?;
+        // pfm rows run from the bottom of the image to the top.
+        for y in (0..self.height()).rev() {
+            for x in 0..self.width() {
+                out.write_all(&(self.depths[(x, y)] as f32).to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// save_png writes the depths as a 16 bit greyscale png, black at the near end of the range and white at the
+    /// far end. Pixels that see nothing are white.
+    pub fn save_png(&self, out: &mut dyn Write, range: DepthRange) -> io::Result<()> {
+        let (near, far) = match range {
+            DepthRange::Auto => self.range().unwrap_or((0., 1.)),
+            DepthRange::Fixed { near, far } => (near, far),
+        };
+        let span = far - near;
+
+        let mut rows = Vec::with_capacity(self.height() * (1 + 2 * self.width()));
+        for y in 0..self.height() {
+            // each row starts with the filter it uses, and these aren't filtered.
+            rows.push(0);
+            for x in 0..self.width() {
+                let depth = self.depths[(x, y)];
+                let level = if span > 0. {
+                    ((depth - near) / span).clamp(0., 1.)
+                } else if depth > near {
+                    1.
+                } else {
+                    0.
+                };
+                rows.extend_from_slice(&((level * u16::MAX as f64).round() as u16).to_be_bytes());
+            }
+        }
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width() as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height() as u32).to_be_bytes());
+        // 16 bits, greyscale, deflate compression, adaptive filtering and no interlacing.
+        header.extend_from_slice(&[16, 0, 0, 0, 0]);
+
+        out.write_all(&PNG_SIGNATURE)?;
+        write_png_chunk(out, b"IHDR", &header)?;
+        write_png_chunk(out, b"IDAT", &zlib_stored(&rows))?;
+        write_png_chunk(out, b"IEND", &[])
+    }
+}
+
+/// parse_depth_range reads a depth range given as auto or as near:far, such as 1:20.
+pub fn parse_depth_range(text: &str) -> Result<DepthRange, String> {
+    let text = text.trim();
+    if text == "auto" {
+        return Ok(DepthRange::Auto);
+    }
+    let (near, far) = text
+        .split_once(':')
+        .ok_or_else(|| format!("'{}' should be auto or near:far", text))?;
+    let number = |n: &str| {
+        n.trim()
+            .parse::<f64>()
+            .map_err(|_| format!("'{}' is not a number", n))
+    };
+    let (near, far) = (number(near)?, number(far)?);
+    if near >= far {
+        return Err(format!("near {} must be less than far {}", near, far));
+    }
+    Ok(DepthRange::Fixed { near, far })
+}
+
+/// PNG_SIGNATURE are the bytes every png file starts with.
+const PNG_SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// MAX_STORED_BLOCK is the most bytes an uncompressed deflate block can hold.
+const MAX_STORED_BLOCK: usize = u16::MAX as usize;
+
+/// write_png_chunk writes a chunk of a png file: its length, type and data followed by a checksum of the type and data.
+fn write_png_chunk(out: &mut dyn Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+/// zlib_stored wraps the data in a zlib stream without compressing it, which every png reader can inflate.
+/// Depth images are mostly smooth gradients that compress poorly without filtering anyway.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = Vec::with_capacity(data.len() + data.len() / MAX_STORED_BLOCK * 5 + 11);
+    // deflate with a 32K window and no preset dictionary.
+    stream.extend_from_slice(&[0x78, 0x01]);
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        stream.push(last as u8);
+        stream.extend_from_slice(&len.to_le_bytes());
+        stream.extend_from_slice(&(!len).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+/// crc32 is the checksum png chunks end with.
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    let mut crc = u32::MAX;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// adler32 is the checksum zlib streams end with.
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1_u32, 0_u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod test_depth {
+    use super::*;
+
+    fn gradient() -> DepthMap {
+        let mut map = DepthMap::new(3, 2);
+        map.write_depth(0, 0, 1.);
+        map.write_depth(1, 0, 2.);
+        map.write_depth(2, 0, 3.);
+        map.write_depth(0, 1, 5.);
+        map
+    }
+
+    #[test]
+    fn test_range() {
+        let map = gradient();
+        assert_eq!(Some((1., 5.)), map.range());
+        assert_eq!(Some(2.), map.depth_at(1, 0));
+        assert_eq!(None, map.depth_at(1, 1));
+        assert_eq!(None, map.depth_at(3, 0));
+        assert_eq!(None, DepthMap::new(2, 2).range());
+    }
+
+    #[test]
+    fn test_parse_depth_range() {
+        assert_eq!(Ok(DepthRange::Auto), parse_depth_range("auto"));
+        assert_eq!(
+            Ok(DepthRange::Fixed {
+                near: 0.5,
+                far: 20.
+            }),
+            parse_depth_range("0.5:20")
+        );
+        assert!(parse_depth_range("20").is_err());
+        assert!(parse_depth_range("a:2").is_err());
+        assert!(parse_depth_range("3:2").is_err());
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+        assert_eq!(0x091e_01de, adler32(b"123456789"));
+    }
+
+    #[test]
+    fn test_save_pfm() {
+        let mut out = vec![];
+        gradient().save_pfm(&mut out).unwrap();
+        let header = b"Pf\n3 2\n-1.0\n";
+        assert_eq!(header, &out[..header.len()]);
+        let floats: Vec<f32> = out[header.len()..]
+            .chunks(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect();
+        // the bottom row comes first.
+        assert_eq!(vec![5., f32::INFINITY, f32::INFINITY, 1., 2., 3.], floats);
+    }
+
+    #[test]
+    fn test_save_png() {
+        let mut out = vec![];
+        gradient()
+            .save_png(&mut out, DepthRange::Fixed { near: 1., far: 3. })
+            .unwrap();
+        assert_eq!(PNG_SIGNATURE, out[..8]);
+        assert_eq!(b"IHDR", &out[12..16]);
+        assert_eq!([0, 0, 0, 3, 0, 0, 0, 2, 16, 0], out[16..26]);
+
+        // the image data is one stored deflate block after the zlib header.
+        let idat = 8 + 25;
+        assert_eq!(b"IDAT", &out[idat + 4..idat + 8]);
+        let block = &out[idat + 8 + 2..];
+        assert_eq!(1, block[0]);
+        let rows = &block[5..5 + 14];
+        // the middle of the range is half way up the grey levels and deeper pixels are clamped to white.
+        assert_eq!(
+            [0, 0, 0, 0x80, 0x00, 0xff, 0xff, 0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            rows
+        );
+        assert!(out.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+    }
+}
@@ -0,0 +1,148 @@
+use std::f64::consts::{PI, TAU};
+
+use crate::{
+    primatives::{
+        color::Color,
+        point::Point,
+        rng::Rng,
+        transformation::rotation_y,
+        tuple::Tuple,
+        vector::{cross, Vector},
+    },
+    shapes::patterns::texture_map::{spherical_map, UvImage},
+    V,
+};
+
+/// DEFAULT_ENVIRONMENT_SAMPLES is how many directions the environment is looked at in to light a point.
+pub const DEFAULT_ENVIRONMENT_SAMPLES: usize = 16;
+
+/// Environment is an image wrapped around the whole scene, laid out with longitude across it and latitude up it
+/// (an equirectangular image). Rays that miss every shape see it as a backdrop, and it lights the shapes' diffuse
+/// color from every direction they aren't hidden from, which gives studio lighting without placing many lights.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Environment {
+    image: UvImage,
+    /// intensity scales the image, as the image formats that can be read only go up to 1.
+    intensity: f64,
+    /// yaw turns the environment around the y axis in radians, so the light and reflections can be pointed
+    /// somewhere else without changing the image.
+    yaw: f64,
+    /// samples is how many directions are looked at to light each point, fewer is faster but noisier.
+    samples: usize,
+}
+
+impl Environment {
+    pub fn new(image: UvImage) -> Self {
+        Self {
+            image,
+            intensity: 1.,
+            yaw: 0.,
+            samples: DEFAULT_ENVIRONMENT_SAMPLES,
+        }
+    }
+
+    pub fn intensity(&self) -> f64 {
+        self.intensity
+    }
+    pub fn set_intensity(&mut self, intensity: f64) {
+        self.intensity = intensity;
+    }
+    pub fn yaw(&self) -> f64 {
+        self.yaw
+    }
+    pub fn set_yaw(&mut self, yaw: f64) {
+        self.yaw = yaw;
+    }
+    pub fn samples(&self) -> usize {
+        self.samples
+    }
+    pub fn set_samples(&mut self, samples: usize) {
+        self.samples = samples;
+    }
+
+    /// color_in is the light arriving from far away along the direction, looking out from the middle of the scene.
+    pub fn color_in(&self, direction: Vector) -> Color {
+        // turning the environment one way is the same as turning the direction the other way.
+        let direction = rotation_y(-self.yaw) * direction;
+        let (u, v) = spherical_map(Point::new(direction.x(), direction.y(), direction.z()));
+        self.image.uv_color_at(u, v) * self.intensity
+    }
+
+    /// hemisphere is the directions to look at the environment in from a surface with the given normal.
+    /// They are spread so more of them are near the normal, in proportion to how much light from each direction
+    /// falls on the surface, so the average of the colors seen is the diffuse light without weighting each one.
+    pub fn hemisphere(&self, normal: Vector, rng: &mut Rng) -> impl Iterator<Item = Vector> + '_ {
+        let helper = if normal.x().abs() < 0.9 {
+            V![1., 0., 0.]
+        } else {
+            V![0., 1., 0.]
+        };
+        let u = cross(normal, helper).norm();
+        let v = cross(normal, u);
+        // one random turn for the point rather than a random direction per sample keeps the samples stratified.
+        let turn = rng.next_f64() * TAU;
+        let golden_angle = PI * (3. - 5.0_f64.sqrt());
+
+        (0..self.samples).map(move |k| {
+            let r2 = (k as f64 + 0.5) / self.samples as f64;
+            let r = r2.sqrt();
+            let theta = turn + k as f64 * golden_angle;
+            (normal * (1. - r2).sqrt() + u * (r * theta.cos()) + v * (r * theta.sin())).norm()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_environment {
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::{comparison::approx_eq, primatives::vector::dot, world::canvas::Canvas, C};
+
+    use super::*;
+
+    /// sides is an environment that is blue towards +x, red towards -x and black in front and behind.
+    fn sides() -> Environment {
+        // the columns are -z, +x, +z, -x and -z again.
+        let mut canvas = Canvas::new(5, 2);
+        for y in 0..2 {
+            canvas.write_pixel(1, y, C![0., 0., 1.]);
+            canvas.write_pixel(3, y, C![1., 0., 0.]);
+        }
+        Environment::new(UvImage::new(canvas))
+    }
+
+    #[test]
+    fn test_color_in() {
+        let mut environment = sides();
+        assert_eq!(C![0., 0., 1.], environment.color_in(V![1., 0., 0.]));
+        assert_eq!(C![1., 0., 0.], environment.color_in(V![-1., 0., 0.]));
+
+        environment.set_intensity(2.);
+        assert_eq!(C![0., 0., 2.], environment.color_in(V![1., 0., 0.]));
+    }
+
+    #[test]
+    fn test_yaw() {
+        let mut environment = sides();
+        // a quarter turn takes the blue side from +x round to -z.
+        environment.set_yaw(FRAC_PI_2);
+        assert_eq!(C![0., 0., 1.], environment.color_in(V![0., 0., -1.]));
+        assert_eq!(C![1., 0., 0.], environment.color_in(V![0., 0., 1.]));
+    }
+
+    #[test]
+    fn test_hemisphere() {
+        let environment = sides();
+        let normal = V![0., 1., 0.];
+        let directions: Vec<_> = environment.hemisphere(normal, &mut Rng::new(7)).collect();
+        assert_eq!(DEFAULT_ENVIRONMENT_SAMPLES, directions.len());
+        for direction in &directions {
+            assert!(approx_eq(1., direction.magnitude()));
+            assert!(dot(*direction, normal) > 0.);
+        }
+        // cosine weighted directions average to two thirds of the way up the normal.
+        let mean =
+            directions.iter().map(|d| dot(*d, normal)).sum::<f64>() / directions.len() as f64;
+        assert!((mean - 2. / 3.).abs() < 0.02);
+    }
+}
@@ -31,31 +31,59 @@ pub struct PrecomputedData {
     pub point: Point,
     /// over_point is the point shifted in the direction of the normal to avoid self shadow (shadow acne)
     pub over_point: Point,
+    /// under_point is the point shifted just beneath the surface, where rays passing into the object should start.
+    pub under_point: Point,
     pub eye_v: Vector,
     pub normal_v: Vector,
+    /// reflect_v is the eye ray reflected about the surface normal.
+    pub reflect_v: Vector,
     pub inside: bool,
+    /// n1 is the refractive index of the material the ray is leaving and n2 of the material it is entering.
+    pub n1: f64,
+    pub n2: f64,
 }
 
 impl PrecomputedData {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         t: f64,
         object: BoxedShape,
         point: Point,
         over_point: Point,
+        under_point: Point,
         eye_v: Vector,
         normal_v: Vector,
+        reflect_v: Vector,
         inside: bool,
+        n1: f64,
+        n2: f64,
     ) -> Self {
         Self {
             t,
             object,
             point,
             over_point,
+            under_point,
             eye_v,
             normal_v,
+            reflect_v,
             inside,
+            n1,
+            n2,
         }
     }
+
+    /// secondary_origin is where a new ray leaving this hit in the given direction should start.
+    /// Rays heading out of the surface start from the over point and rays heading into it from the under point,
+    /// and both are nudged along the direction by bias so that grazing rays don't land back on the surface.
+    pub fn secondary_origin(&self, direction: Vector, bias: f64) -> Point {
+        let start = if dot(direction, self.normal_v) >= 0. {
+            self.over_point
+        } else {
+            self.under_point
+        };
+        start + direction * bias
+    }
 }
 
 impl PartialEq for Intersection {
@@ -78,7 +106,18 @@ impl Intersection {
         self.object
     }
 
+    /// shape borrows the object that was hit.
+    pub fn shape(&self) -> &BoxedShape {
+        &self.object
+    }
+
     pub fn prepare_computations(&self, r: Ray) -> PrecomputedData {
+        self.prepare_computations_with_bias(r, EPSILON)
+    }
+
+    /// prepare_computations_with_bias works out the values needed to shade the hit,
+    /// moving the over and under points bias away from the surface.
+    pub fn prepare_computations_with_bias(&self, r: Ray, bias: f64) -> PrecomputedData {
         let point = r.at(self.t());
         let eye_v = -r.direction();
 
@@ -86,20 +125,76 @@ impl Intersection {
         let inside = dot(norm, eye_v) < 0.0;
 
         // if ray is inside the object then flip normal.
-        let normal_v = if inside { -norm } else { norm };
+        let flip = if inside { -1. } else { 1. };
+        let surface_v = norm * flip;
+        let normal_v = self.object.material().normal_at(&self.object, point, norm) * flip;
+
+        // add a tiny amount on (EPISLON)
+        // the points move off the real surface rather than the bumped one, so bumps can't push them inside it.
+        let over_point = self.object.shading_point(point) + surface_v * bias;
+        let under_point = point - surface_v * bias;
 
-        let over_point = point + normal_v * EPSILON; // add a tiny amount on (EPISLON)
+        let reflect_v = r.direction().reflect(normal_v);
 
         PrecomputedData {
             t: self.t,
             object: self.object.clone(),
             point,
             over_point,
+            under_point,
             eye_v,
             normal_v,
+            reflect_v,
             inside,
+            n1: 1.,
+            n2: 1.,
         }
     }
+
+    /// prepare_computations_with_intersections is prepare_computations_with_bias that also works out the refractive
+    /// indices either side of the hit, using every intersection along the ray to see which objects it is inside.
+    pub fn prepare_computations_with_intersections(
+        &self,
+        r: Ray,
+        xs: &Intersections,
+        bias: f64,
+    ) -> PrecomputedData {
+        let (n1, n2) = self.refractive_indices(xs);
+        PrecomputedData {
+            n1,
+            n2,
+            ..self.prepare_computations_with_bias(r, bias)
+        }
+    }
+
+    /// refractive_indices walks along the intersections up to this one, keeping a list of the objects the ray is inside.
+    /// Each intersection either enters an object, adding it to the list, or leaves one, removing it.
+    /// n1 is the refractive index of the innermost object before this hit and n2 after it, 1 when outside of everything.
+    pub fn refractive_indices(&self, xs: &Intersections) -> (f64, f64) {
+        let innermost = |containers: &Vec<&BoxedShape>| {
+            containers
+                .last()
+                .map_or(1., |object| object.material().refractive_index())
+        };
+
+        let mut containers: Vec<&BoxedShape> = vec![];
+        for i in xs.iter() {
+            let n1 = innermost(&containers);
+            match containers
+                .iter()
+                .position(|object| object.id() == i.object.id())
+            {
+                Some(index) => {
+                    containers.remove(index);
+                }
+                None => containers.push(&i.object),
+            }
+            if i == self {
+                return (n1, innermost(&containers));
+            }
+        }
+        (1., 1.)
+    }
 }
 
 impl Intersections {
@@ -115,16 +210,38 @@ impl Intersections {
         self.intersections.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.intersections.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Intersection> {
+        self.intersections.iter()
+    }
+
+    /// hit is the closest intersection in front of the ray. Anything closer than EPSILON is ignored
+    /// as it is almost always the ray hitting the surface it started from.
     pub fn hit(&mut self) -> Option<&Intersection> {
+        self.hit_after(EPSILON)
+    }
+
+    /// hit_after is the closest intersection at least min_t along the ray.
+    pub fn hit_after(&mut self, min_t: f64) -> Option<&Intersection> {
         self.intersections
             .sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap());
 
-        self.intersections.iter().find(|a| a.t().is_sign_positive())
+        self.intersections.iter().find(|a| a.t() >= min_t)
     }
 
-    pub fn extend(&mut self, i: Intersections) {
-        for xs in i.intersections {
-            self.intersections.push(xs);
+    /// extend adds the intersections in i, keeping them sorted. Adding nothing does nothing, and adding to an empty
+    /// collection takes over i's intersections, so gathering the hits along a ray only allocates when there are some.
+    pub fn extend(&mut self, mut i: Intersections) {
+        if i.is_empty() {
+            return;
+        }
+        if self.is_empty() {
+            self.intersections = i.intersections;
+        } else {
+            self.intersections.append(&mut i.intersections);
         }
         self.intersections
             .sort_by(|a, b| a.t().partial_cmp(&b.t()).unwrap())
@@ -144,8 +261,14 @@ mod test_intersection {
 
     use crate::{
         comparison::approx_eq,
-        primatives::{ray::Ray, transformation::translation, tuple::Tuple},
-        shapes::{sphere::Sphere, Shape},
+        primatives::{color::Color, ray::Ray, transformation::translation, tuple::Tuple},
+        shapes::{
+            material::Material,
+            patterns::{gradient::GraidentPattern, PatternBase},
+            plane::Plane,
+            sphere::Sphere,
+            Shape, ShapeBase,
+        },
         P, V,
     };
 
@@ -205,6 +328,43 @@ mod test_intersection {
         assert_eq!(hit, &i_4);
     }
 
+    #[test]
+    fn test_hit_ignores_near_zero() {
+        let s = Sphere::default_boxed();
+
+        // zero, positive or negative, is where the ray started and is not a hit.
+        let mut xs = Intersections::new(vec![
+            Intersection::new(0., s.clone()),
+            Intersection::new(-0., s.clone()),
+        ]);
+        assert_eq!(None, xs.hit());
+
+        let i = Intersection::new(EPSILON / 2., s.clone());
+        let mut xs = Intersections::new(vec![i.clone()]);
+        assert_eq!(None, xs.hit());
+        assert_eq!(Some(&i), xs.hit_after(0.));
+
+        let i_1 = Intersection::new(0.5, s.clone());
+        let i_2 = Intersection::new(1., s);
+        let mut xs = Intersections::new(vec![i_1, i_2.clone()]);
+        assert_eq!(Some(&i_2), xs.hit_after(0.75));
+    }
+
+    #[test]
+    fn test_hit_from_surface() {
+        // a ray starting on the surface of a sphere finds the far side rather than where it started.
+        let s = Sphere::default_boxed();
+        let r = Ray::new(P![0., 0., -1.], V![0., 0., 1.]);
+        let mut xs = s.intersect(r);
+        assert_eq!(2, xs.len());
+        assert!(approx_eq(2., xs.hit().unwrap().t()));
+
+        // leaving the surface of a plane there is nothing else to hit.
+        let p = Plane::default().box_clone();
+        let r = Ray::new(P![0., 0., 0.], V![0., 1., 0.]);
+        assert_eq!(None, p.intersect(r).hit());
+    }
+
     #[test]
     fn test_pre_compute() {
         // ray outside the object
@@ -246,4 +406,82 @@ mod test_intersection {
         assert!(comps.over_point.z() < -EPSILON / 2.);
         assert!(comps.point.z() > comps.over_point.z())
     }
+
+    #[test]
+    fn test_pre_compute_bump_map() {
+        // the bump map rises along x, so the shading normal leans back towards -x.
+        let bumps = GraidentPattern::new(Color::BLACK, Color::WHITE, None);
+        let material = Material::builder()
+            .bump_map(bumps.box_clone())
+            .bump_strength(0.5)
+            .build()
+            .unwrap();
+        let plane = Plane::new(None, Some(material)).box_clone();
+        let r = Ray::new(P![0.5, 1., 0.], V![0., -1., 0.]);
+        let comps = Intersection::new(1., plane).prepare_computations(r);
+
+        assert!(!comps.inside);
+        assert!(comps.normal_v.x() < 0. && comps.normal_v.y() > 0.);
+        assert!(approx_eq(1., comps.normal_v.magnitude()));
+        // the over point still sits straight above the real surface.
+        assert!(approx_eq(0.5, comps.over_point.x()));
+        assert!(comps.over_point.y() > 0.);
+    }
+
+    #[test]
+    fn test_pre_compute_under_point() {
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+        let mut s = Sphere::default();
+        s.set_transform(translation(0., 0., 1.));
+        let i = Intersection::new(5., s.box_clone());
+        let comps = i.prepare_computations(r);
+
+        assert!(comps.under_point.z() > EPSILON / 2.);
+        assert!(comps.point.z() < comps.under_point.z());
+
+        // a larger bias moves both points further from the surface.
+        let comps = i.prepare_computations_with_bias(r, 0.01);
+        assert!(approx_eq(-0.01, comps.over_point.z()));
+        assert!(approx_eq(0.01, comps.under_point.z()));
+    }
+
+    #[test]
+    fn test_secondary_origin() {
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+        let i = Intersection::new(4., Sphere::default_boxed());
+        let comps = i.prepare_computations_with_bias(r, 0.01);
+
+        // leaving the surface starts above it.
+        let out = comps.secondary_origin(comps.reflect_v, 0.01);
+        assert_eq!(P![0., 0., -1.02], out);
+
+        // entering the surface starts below it.
+        let into = comps.secondary_origin(V![0., 0., 1.], 0.01);
+        assert_eq!(P![0., 0., -0.98], into);
+    }
+
+    #[test]
+    fn test_secondary_origin_grazing() {
+        // a ray only just catching the edge of a sphere reflects almost along the surface.
+        let s = Sphere::default_boxed();
+        let r = Ray::new(P![0., 0.999, -5.], V![0., 0., 1.]);
+        let mut xs = s.intersect(r);
+        let comps = xs.hit().unwrap().prepare_computations(r);
+
+        let origin = comps.secondary_origin(comps.reflect_v, EPSILON);
+        let reflected = Ray::new(origin, comps.reflect_v);
+        assert_eq!(None, s.intersect(reflected).hit_after(0.));
+    }
+
+    #[test]
+    fn test_pre_compute_reflect_v() {
+        let s = Plane::default().box_clone();
+        let sqrt_2_2 = 2.0_f64.sqrt() / 2.0;
+        let r = Ray::new(P![0., 1., -1.], V![0., -sqrt_2_2, sqrt_2_2]);
+        let i = Intersection::new(2.0_f64.sqrt(), s);
+
+        let comps = i.prepare_computations(r);
+
+        assert_eq!(V![0., sqrt_2_2, sqrt_2_2], comps.reflect_v);
+    }
 }
@@ -0,0 +1,96 @@
+use super::{canvas::Canvas, World};
+use crate::{
+    comparison::EPSILON, primatives::color::Color, primatives::point::Point, primatives::rng::Rng,
+    primatives::tuple::Tuple, primatives::vector::dot, V,
+};
+
+/// irradiance_map is a debugging view of how the lights fall on a horizontal floor at height floor_y.
+/// It looks straight down on the square of floor from -extent to extent in x and z, with +z at the top of the image,
+/// and colors each pixel by the light arriving there. No camera or materials are involved so the map only shows
+/// the light's angle to the floor and the shadows cast by the objects in the world.
+pub fn irradiance_map(world: &World, floor_y: f64, extent: f64, size: usize) -> Canvas {
+    let mut canvas = Canvas::new(size, size);
+    let cell = 2. * extent / size as f64;
+
+    for y in 0..size {
+        for x in 0..size {
+            // sample the middle of each cell.
+            let world_x = -extent + (x as f64 + 0.5) * cell;
+            let world_z = extent - (y as f64 + 0.5) * cell;
+            let point = Point::new(world_x, floor_y, world_z);
+            canvas.write_pixel(x, y, irradiance_at(world, point));
+        }
+    }
+    canvas
+}
+
+/// irradiance_at is the light reaching a point on an upward facing floor, added up over every light that is on.
+fn irradiance_at(world: &World, point: Point) -> Color {
+    let up = V![0., 1., 0.];
+
+    // lift the point off the floor so a floor plane doesn't shadow itself.
+    let over_point = point + up * EPSILON;
+    world
+        .lights()
+        .map(|light| light.sample(over_point, &mut Rng::for_point(over_point)))
+        .filter(|sample| !world.is_shadowed_along(over_point, sample))
+        .map(|sample| {
+            let cos = dot(sample.direction, up);
+            if cos < 0. {
+                Color::BLACK
+            } else {
+                sample.intensity * cos
+            }
+        })
+        .fold(Color::BLACK, |total, color| total + color)
+}
+
+#[cfg(test)]
+mod test_irradiance {
+    use crate::{
+        comparison::approx_eq,
+        primatives::transformation::{scaling, translation},
+        shapes::{sphere::Sphere, ShapeBase},
+        world::light::{LightBase, PointLight},
+        P,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_no_light() {
+        let world = World::new(vec![], None);
+        let canvas = irradiance_map(&world, 0., 1., 3);
+        assert_eq!(Some(Color::BLACK), canvas.pixel_at(1, 1));
+    }
+
+    #[test]
+    fn test_falloff() {
+        let world = World::new(
+            vec![],
+            Some(PointLight::new(P![0., 1., 0.], Color::WHITE).box_clone()),
+        );
+        let canvas = irradiance_map(&world, 0., 3., 3);
+
+        // the light is straight above the middle of the map.
+        let middle = canvas.pixel_at(1, 1).unwrap();
+        assert!(approx_eq(1., middle.red()));
+
+        // a corner cell is at (-2, 0, 2), so the light arrives at an angle.
+        let corner = canvas.pixel_at(0, 0).unwrap();
+        assert!(approx_eq(1. / 3., corner.red()));
+    }
+
+    #[test]
+    fn test_shadow() {
+        let blocker = Sphere::new(Some(translation(0., 1., 0.) * scaling(0.5, 0.5, 0.5)), None);
+        let world = World::new(
+            vec![blocker.box_clone()],
+            Some(PointLight::new(P![0., 3., 0.], Color::WHITE).box_clone()),
+        );
+        let canvas = irradiance_map(&world, 0., 3., 3);
+
+        assert_eq!(Some(Color::BLACK), canvas.pixel_at(1, 1));
+        assert!(canvas.pixel_at(0, 0).unwrap().red() > 0.);
+    }
+}
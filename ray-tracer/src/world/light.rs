@@ -1,38 +1,150 @@
+use std::{
+    any::Any,
+    collections::HashSet,
+    f64::consts::{PI, TAU},
+    fmt,
+    sync::Arc,
+};
+
 use num_traits::Pow;
 
 use crate::{
+    comparison::EPSILON,
     primatives::color::Color,
     primatives::point::Point,
-    primatives::vector::{dot, Vector},
+    primatives::rng::Rng,
+    primatives::sampling::{bilinear_pdf, sample_bilinear},
+    primatives::tuple::Tuple,
+    primatives::vector::{cross, dot, Vector},
     shapes::{material::Material, BoxedShape},
 };
 
+/// LightSample is one way light can arrive at a point from a light.
+/// direction points from the point towards the light and distance is how far along it the light is,
+/// which is infinite for lights that are infinitely far away.
+/// intensity is the light arriving along the direction and pdf is the probability density, over solid angle,
+/// of the light choosing this sample. Lights that only ever give one sample, like point lights, have a pdf of 1.
+/// The Phong model uses the intensity as it is, while an integrator that needs an unbiased estimate divides by the pdf.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightSample {
+    pub direction: Vector,
+    pub distance: f64,
+    pub intensity: Color,
+    pub pdf: f64,
+}
+
+pub type BoxedLight = Arc<dyn Light>;
+
+/// LightBase is the part of a light that is the same for every kind of light, which every light that can be
+/// cloned and compared gets for free.
+pub trait LightBase {
+    fn box_clone(&self) -> BoxedLight;
+    fn box_eq(&self, other: &dyn Any) -> bool;
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<L: Light + Clone + PartialEq> LightBase for L {
+    fn box_clone(&self) -> BoxedLight {
+        Arc::new(self.clone())
+    }
+
+    fn box_eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<Self>().is_some_and(|a| self == a)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Light is anything that lights a scene. Shading only sees the samples a light gives,
+/// so a new type of light only has to say how it is sampled. Boxed lights of every kind can sit side by side in a
+/// world, like boxed shapes.
+pub trait Light: LightBase + Any + fmt::Debug + Send + Sync {
+    /// sample_at is the way light arrives at the point for a spot (u, v) in the unit square. Lights with an area use
+    /// it to choose where on themselves the light comes from, so a Sampler can spread samples evenly over the light.
+    /// Lights that always give the same sample ignore it.
+    fn sample_at(&self, point: Point, u: f64, v: f64) -> LightSample;
+
+    /// sample_facing is sample_at for a point on a surface facing along the normal. Lights with an area use the normal
+    /// to send more of their samples where the surface faces them squarely, as light arriving there counts for more.
+    fn sample_facing(&self, point: Point, _normal: Vector, u: f64, v: f64) -> LightSample {
+        self.sample_at(point, u, v)
+    }
+
+    /// sample picks one way light arrives at the point, using rng to pick the spot on a light that varies.
+    fn sample(&self, point: Point, rng: &mut Rng) -> LightSample {
+        if !self.varies() {
+            return self.sample_at(point, 0.5, 0.5);
+        }
+        let (u, v) = (rng.next_f64(), rng.next_f64());
+        self.sample_at(point, u, v)
+    }
+
+    /// varies is whether the light gives different samples for different spots, so that taking more than one sample
+    /// of it is worthwhile.
+    fn varies(&self) -> bool {
+        false
+    }
+
+    /// intensity is the color and brightness of the light as it was made.
+    fn intensity(&self) -> Color;
+
+    /// position is where the light shines from, the middle of a light with an area,
+    /// or None for a light infinitely far away.
+    fn position(&self) -> Option<Point>;
+
+    /// intensity_at is how much light reaches the point when nothing is in the way. It is the intensity for most
+    /// lights, but less or none outside a spot light's cone.
+    fn intensity_at(&self, point: Point) -> Color {
+        self.sample(point, &mut Rng::for_point(point)).intensity
+    }
+}
+
+impl PartialEq for dyn Light {
+    fn eq(&self, other: &dyn Light) -> bool {
+        self.box_eq(other.as_any())
+    }
+}
+
 /// lighting implements the *Phong reflection model* for lighting and simulates the interaction between three different types of lighting:
 /// 1. Ambient reflection or background lighting.
 /// 2. Diffuse reflection, the light reflected from matte surfaces (depeneds on the angle between the light and the surface normal).
 /// 3. Specular reflection, the light reflected from the light source itself (depends on the angle between the eye and the light).
+///
 /// Takes the material being hit, the light source, the point being illuminated, the vector of the eye to the point and the vector of the surface normal.
+/// The light is sampled once, with a generator seeded from the point.
 pub fn lighting(
-    material: Material,
+    material: &Material,
     object: BoxedShape,
-    light: PointLight,
+    light: impl Light,
+    point: Point,
+    eye_v: Vector,
+    normal_v: Vector,
+    in_shadow: bool,
+) -> Color {
+    let sample = light.sample(point, &mut Rng::for_point(point));
+    lighting_sample(material, object, &sample, point, eye_v, normal_v, in_shadow)
+}
+
+/// lighting_sample is the Phong model for light arriving as one sample of a light, see lighting.
+pub fn lighting_sample(
+    material: &Material,
+    object: BoxedShape,
+    sample: &LightSample,
     point: Point,
     eye_v: Vector,
     normal_v: Vector,
     in_shadow: bool,
 ) -> Color {
     // get color from pattern or material
-    let color = if material.pattern().is_some() {
-        material.pattern().as_ref().unwrap().at_shape(object, point)
-    } else {
-        material.color()
-    };
+    let color = material.color_at(object, point);
 
     // combine the surface color with the light's color/intensity
-    let effective_color = color * light.intensity();
+    let effective_color = color * sample.intensity;
 
     // get light direction
-    let light_v = (light.position - point).norm();
+    let light_v = sample.direction;
 
     let ambient = effective_color * material.ambient();
 
@@ -50,7 +162,7 @@ pub fn lighting(
             Color::BLACK
         } else {
             let factor = reflect_dot_eye.pow(material.shininess());
-            light.intensity * material.specular() * factor
+            sample.intensity * material.specular() * factor
         };
         (diffuse, specular)
     };
@@ -82,15 +194,413 @@ impl PointLight {
     }
 }
 
+impl Light for PointLight {
+    fn sample_at(&self, point: Point, _u: f64, _v: f64) -> LightSample {
+        let v = self.position - point;
+        LightSample {
+            direction: v.norm(),
+            distance: v.magnitude(),
+            intensity: self.intensity,
+            pdf: 1.,
+        }
+    }
+
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn position(&self) -> Option<Point> {
+        Some(self.position)
+    }
+}
+
+/// SpotLight is a point light that only shines in a cone around its direction.
+/// Points inside the inner angle get the full intensity, which fades smoothly to nothing at the outer angle.
+/// Both angles are measured from the direction to the edge of the cone, in radians.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpotLight {
+    intensity: Color,
+    position: Point,
+    direction: Vector,
+    cos_inner: f64,
+    cos_outer: f64,
+}
+
+impl SpotLight {
+    pub fn new(
+        position: Point,
+        direction: Vector,
+        inner_angle: f64,
+        outer_angle: f64,
+        intensity: Color,
+    ) -> Self {
+        assert!(
+            0. <= inner_angle && inner_angle <= outer_angle && outer_angle <= PI,
+            "spot light angles must satisfy 0 <= inner <= outer <= pi, got {} and {}",
+            inner_angle,
+            outer_angle
+        );
+        Self {
+            intensity,
+            position,
+            direction: direction.norm(),
+            cos_inner: inner_angle.cos(),
+            cos_outer: outer_angle.cos(),
+        }
+    }
+
+    /// falloff is how much of the light reaches a point whose direction from the light makes the given cosine
+    /// with the light's direction.
+    fn falloff(&self, cos: f64) -> f64 {
+        if cos >= self.cos_inner {
+            1.
+        } else if cos <= self.cos_outer {
+            0.
+        } else {
+            let t = (cos - self.cos_outer) / (self.cos_inner - self.cos_outer);
+            t * t * (3. - 2. * t)
+        }
+    }
+}
+
+impl Light for SpotLight {
+    fn sample_at(&self, point: Point, _u: f64, _v: f64) -> LightSample {
+        let v = self.position - point;
+        let direction = v.norm();
+        LightSample {
+            direction,
+            distance: v.magnitude(),
+            intensity: self.intensity * self.falloff(dot(-direction, self.direction)),
+            pdf: 1.,
+        }
+    }
+
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn position(&self) -> Option<Point> {
+        Some(self.position)
+    }
+}
+
+/// MIN_CORNER_WEIGHT is the least weight a corner of an area light is given when warping samples by the cosine.
+const MIN_CORNER_WEIGHT: f64 = 0.01;
+
+/// AreaLight is a light shining from the parallelogram with a corner at corner and sides u and v.
+/// It shines from both faces. Each sample comes from a random point on it, so averaging samples gives soft shadows.
+/// A rectangular light picks its points so that every direction it covers, as seen from the point being lit, is as
+/// likely as any other. Picking evenly over its area instead would bunch the samples up on the far part of a big
+/// light close to the point, which looks small from there, and leave the near part that gives most of the light
+/// noisy. When the surface being lit is known, directions it faces squarely are made more likely still, see
+/// sample_facing. Other parallelograms are sampled evenly over their area.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AreaLight {
+    intensity: Color,
+    corner: Point,
+    u: Vector,
+    v: Vector,
+}
+
+impl AreaLight {
+    pub fn new(corner: Point, u: Vector, v: Vector, intensity: Color) -> Self {
+        assert!(
+            cross(u, v).magnitude() > 0.,
+            "an area light's sides must not be parallel"
+        );
+        Self {
+            intensity,
+            corner,
+            u,
+            v,
+        }
+    }
+
+    pub fn area(&self) -> f64 {
+        cross(self.u, self.v).magnitude()
+    }
+
+    /// sample_rectangle picks the point on a rectangular light for the spot (s, t) so that points are spread evenly
+    /// over the solid angle the light covers as seen from the point, returning it along with that solid angle.
+    /// It follows Ureña, Fajardo and King's "An Area-Preserving Parametrization for Spherical Rectangles" (2013).
+    /// It is None when the light isn't a rectangle or the point is in the light's plane, where it covers no angle.
+    fn sample_rectangle(&self, point: Point, s: f64, t: f64) -> Option<(Point, f64)> {
+        let (width, height) = (self.u.magnitude(), self.v.magnitude());
+        if dot(self.u, self.v).abs() > EPSILON * width * height {
+            return None;
+        }
+
+        // work in a frame along the light's sides with the point at the origin and the light below it.
+        let x = self.u / width;
+        let y = self.v / height;
+        let mut z = cross(x, y);
+        let to_corner = self.corner - point;
+        let mut z0 = dot(to_corner, z);
+        if z0 > 0. {
+            z = -z;
+            z0 = -z0;
+        }
+        if z0 > -EPSILON {
+            return None;
+        }
+        let (x0, y0) = (dot(to_corner, x), dot(to_corner, y));
+        let (x1, y1) = (x0 + width, y0 + height);
+
+        // the rectangle seen from the origin is a spherical rectangle, bounded by the planes through its edges.
+        let v00 = Vector::new(x0, y0, z0);
+        let v01 = Vector::new(x0, y1, z0);
+        let v10 = Vector::new(x1, y0, z0);
+        let v11 = Vector::new(x1, y1, z0);
+        let n0 = cross(v00, v10).norm();
+        let n1 = cross(v10, v11).norm();
+        let n2 = cross(v11, v01).norm();
+        let n3 = cross(v01, v00).norm();
+        let angle = |a: Vector, b: Vector| dot(a, b).clamp(-1., 1.).acos();
+        let g0 = angle(-n0, n1);
+        let g1 = angle(-n1, n2);
+        let g2 = angle(-n2, n3);
+        let g3 = angle(-n3, n0);
+        let k = TAU - g2 - g3;
+        let solid_angle = g0 + g1 - k;
+        if solid_angle <= 0. {
+            return None;
+        }
+
+        // pick x so the strip of the rectangle left of it covers s of the solid angle.
+        let (b0, b1) = (n0.z(), n2.z());
+        let au = s * solid_angle + k;
+        let fu = (au.cos() * b0 - b1) / au.sin();
+        let cu = (1. / (fu * fu + b0 * b0).sqrt())
+            .copysign(fu)
+            .clamp(-1., 1.);
+        let xu = (-(cu * z0) / (1. - cu * cu).max(0.).sqrt()).clamp(x0, x1);
+
+        // then pick y so the part of that line of the rectangle below it covers t of its solid angle.
+        let d = (xu * xu + z0 * z0).sqrt();
+        let h0 = y0 / (d * d + y0 * y0).sqrt();
+        let h1 = y1 / (d * d + y1 * y1).sqrt();
+        let hv = h0 + t * (h1 - h0);
+        let yv = if hv * hv < 1. - EPSILON {
+            hv * d / (1. - hv * hv).sqrt()
+        } else {
+            y1
+        };
+
+        Some((point + x * xu + y * yv + z * z0, solid_angle))
+    }
+}
+
+impl Light for AreaLight {
+    fn sample_at(&self, point: Point, u: f64, v: f64) -> LightSample {
+        if let Some((on_light, solid_angle)) = self.sample_rectangle(point, u, v) {
+            let to_light = on_light - point;
+            return LightSample {
+                direction: to_light.norm(),
+                distance: to_light.magnitude(),
+                intensity: self.intensity,
+                pdf: 1. / solid_angle,
+            };
+        }
+
+        let on_light = self.corner + self.u * u + self.v * v;
+        let v = on_light - point;
+        let direction = v.norm();
+        let distance = v.magnitude();
+
+        // turn the even density over the light's area into a density over the directions seen from the point.
+        let normal = cross(self.u, self.v).norm();
+        let cos = dot(normal, direction).abs();
+        let (intensity, pdf) = if cos > 0. {
+            (self.intensity, distance * distance / (self.area() * cos))
+        } else {
+            // seen exactly edge on the light gives nothing.
+            (Color::BLACK, 0.)
+        };
+        LightSample {
+            direction,
+            distance,
+            intensity,
+            pdf,
+        }
+    }
+
+    /// sample_facing spreads samples by the solid angle the light covers weighted by the cosine at the surface, the
+    /// projected solid angle, following Hart, Pharr, Müller, Lopes, McGuire and Shirley's "Practical Product
+    /// Sampling by Fitting and Composing Warps" (2020). The cosine is taken at each corner of the light and the spot
+    /// (u, v) is warped by the bilinear function through them before it is turned into a point on the light.
+    fn sample_facing(&self, point: Point, normal: Vector, u: f64, v: f64) -> LightSample {
+        let corners = [
+            self.corner,
+            self.corner + self.u,
+            self.corner + self.v,
+            self.corner + self.u + self.v,
+        ];
+        // corners the surface faces edge on still get some samples, as the cosine varies between them.
+        let weights = corners.map(|corner| {
+            dot((corner - point).norm(), normal)
+                .abs()
+                .max(MIN_CORNER_WEIGHT)
+        });
+        let (s, t) = sample_bilinear(u, v, weights);
+        match self.sample_rectangle(point, s, t) {
+            Some((on_light, solid_angle)) => {
+                let to_light = on_light - point;
+                LightSample {
+                    direction: to_light.norm(),
+                    distance: to_light.magnitude(),
+                    intensity: self.intensity,
+                    pdf: bilinear_pdf(s, t, weights) / solid_angle,
+                }
+            }
+            None => self.sample_at(point, u, v),
+        }
+    }
+
+    fn varies(&self) -> bool {
+        true
+    }
+
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn position(&self) -> Option<Point> {
+        Some(self.corner + (self.u + self.v) * 0.5)
+    }
+}
+
+/// DomeLight is light arriving evenly from every direction, as if from a sky infinitely far away.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DomeLight {
+    intensity: Color,
+}
+
+impl DomeLight {
+    pub fn new(intensity: Color) -> Self {
+        Self { intensity }
+    }
+}
+
+impl Light for DomeLight {
+    fn sample_at(&self, _point: Point, u: f64, v: f64) -> LightSample {
+        // spread the square evenly over the sphere.
+        let z = 1. - 2. * u;
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = TAU * v;
+        LightSample {
+            direction: Vector::new(r * phi.cos(), r * phi.sin(), z),
+            distance: f64::INFINITY,
+            intensity: self.intensity,
+            pdf: 1. / (4. * PI),
+        }
+    }
+
+    fn varies(&self) -> bool {
+        true
+    }
+
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn position(&self) -> Option<Point> {
+        None
+    }
+}
+
+/// DirectionalLight is light travelling the same way everywhere from infinitely far away, like sunlight.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirectionalLight {
+    intensity: Color,
+    direction: Vector,
+}
+
+impl DirectionalLight {
+    /// new makes a light travelling in the direction, so `V![0., -1., 0.]` shines straight down.
+    pub fn new(direction: Vector, intensity: Color) -> Self {
+        assert!(
+            direction.magnitude() > 0.,
+            "a directional light needs a direction"
+        );
+        Self {
+            intensity,
+            direction: direction.norm(),
+        }
+    }
+
+    pub fn direction(&self) -> Vector {
+        self.direction
+    }
+}
+
+impl Light for DirectionalLight {
+    fn sample_at(&self, _point: Point, _u: f64, _v: f64) -> LightSample {
+        LightSample {
+            direction: -self.direction,
+            distance: f64::INFINITY,
+            intensity: self.intensity,
+            pdf: 1.,
+        }
+    }
+
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn position(&self) -> Option<Point> {
+        None
+    }
+}
+
+/// LightLinking is which shapes a light in the world shines on, so a light can be kept to the shapes it is meant for,
+/// such as a rim light that only outlines the subject. Shapes are picked by their ids.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LightLinking {
+    /// All shines on every shape.
+    #[default]
+    All,
+    /// Only shines on the shapes with the ids and nothing else.
+    Only(HashSet<u64>),
+    /// Except shines on every shape but those with the ids.
+    Except(HashSet<u64>),
+}
+
+impl LightLinking {
+    /// shines_on is whether the light reaches the shape with the id.
+    pub fn shines_on(&self, id: u64) -> bool {
+        match self {
+            LightLinking::All => true,
+            LightLinking::Only(ids) => ids.contains(&id),
+            LightLinking::Except(ids) => !ids.contains(&id),
+        }
+    }
+
+    /// map_ids is the same linking with its ids replaced, leaving All as it is.
+    pub(crate) fn map_ids(&self, f: impl FnOnce(&HashSet<u64>) -> HashSet<u64>) -> Self {
+        match self {
+            LightLinking::All => LightLinking::All,
+            LightLinking::Only(ids) => LightLinking::Only(f(ids)),
+            LightLinking::Except(ids) => LightLinking::Except(f(ids)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod test_lights {
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::comparison::approx_eq;
+    use crate::primatives::transformation::{rotation_x, translation, view_transformation};
     use crate::primatives::tuple::Tuple;
     use crate::primatives::vector::Vector;
     use crate::shapes::material::Material;
     use crate::shapes::patterns::striped::StripePattern;
-    use crate::shapes::patterns::Pattern;
+    use crate::shapes::patterns::PatternBase;
+    use crate::shapes::plane::Plane;
     use crate::shapes::sphere::Sphere;
-    use crate::shapes::Shape;
+    use crate::shapes::ShapeBase;
+    use crate::world::{camera::Camera, canvas::Canvas, World};
     use crate::{C, P, V};
 
     use super::*;
@@ -105,6 +615,275 @@ mod test_lights {
         assert_eq!(light.intensity(), intensity);
     }
 
+    #[test]
+    fn test_point_light_sample() {
+        let light = PointLight::new(P![0., 3., 4.], Color::WHITE);
+        let sample = light.sample(P![0., 0., 0.], &mut Rng::new(0));
+        assert_eq!(sample.direction, V![0., 0.6, 0.8]);
+        assert!(approx_eq(5., sample.distance));
+        assert_eq!(sample.intensity, Color::WHITE);
+        assert_eq!(sample.pdf, 1.);
+    }
+
+    #[test]
+    fn test_spot_light_sample() {
+        let light = SpotLight::new(
+            P![0., 10., 0.],
+            V![0., -1., 0.],
+            PI / 8.,
+            PI / 4.,
+            Color::WHITE,
+        );
+        let mut rng = Rng::new(0);
+        let mut intensity = |point| light.sample(point, &mut rng).intensity;
+
+        // straight below is inside the inner cone and outside the outer cone is dark.
+        assert_eq!(intensity(P![0., 0., 0.]), Color::WHITE);
+        assert_eq!(intensity(P![20., 0., 0.]), Color::BLACK);
+
+        // between the two cones the light fades.
+        let edge = intensity(P![6., 0., 0.]);
+        assert!(edge.red() > 0. && edge.red() < 1.);
+        assert!(intensity(P![7., 0., 0.]).red() < edge.red());
+    }
+
+    #[test]
+    fn test_area_light_sample() {
+        let light = AreaLight::new(
+            P![-1., 5., -1.],
+            V![2., 0., 0.],
+            V![0., 0., 2.],
+            Color::WHITE,
+        );
+        assert!(approx_eq(4., light.area()));
+
+        let mut rng = Rng::new(3);
+        let point = P![0., 0., 0.];
+        let first = light.sample(point, &mut rng);
+        for _ in 0..100 {
+            let sample = light.sample(point, &mut rng);
+            let on_light = point + sample.direction * sample.distance;
+            assert!(approx_eq(5., on_light.y()));
+            assert!(on_light.x().abs() <= 1. && on_light.z().abs() <= 1.);
+
+            // every direction the rectangle covers is as likely as any other.
+            assert!(approx_eq(first.pdf, sample.pdf));
+        }
+
+        // different samples come from different places on the light.
+        let a = light.sample(point, &mut rng);
+        let b = light.sample(point, &mut rng);
+        assert_ne!(a.direction, b.direction);
+
+        // a spot in the square picks the same place on the light.
+        let corner = light.sample_at(point, 0., 1.);
+        assert_eq!(P![-1., 5., 1.], point + corner.direction * corner.distance);
+        assert!(light.varies());
+        assert!(!PointLight::new(P![0., 5., 0.], Color::WHITE).varies());
+    }
+
+    #[test]
+    fn test_area_light_solid_angle() {
+        // a big light close to the point, off to one side.
+        let light = AreaLight::new(
+            P![-1., 1., -2.],
+            V![4., 0., 0.],
+            V![0., 0., 3.],
+            Color::WHITE,
+        );
+        let point = P![0.5, 0., 0.];
+
+        // the solid angle the light covers, added up over a fine grid on the light.
+        let n = 400;
+        let mut solid_angle = 0.;
+        for i in 0..n {
+            for j in 0..n {
+                let (u, v) = ((i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64);
+                let to_light = P![-1., 1., -2.] + V![4., 0., 0.] * u + V![0., 0., 3.] * v - point;
+                let distance = to_light.magnitude();
+                solid_angle +=
+                    12. / (n * n) as f64 * (to_light.y() / distance) / (distance * distance);
+            }
+        }
+        let sample = light.sample_at(point, 0.3, 0.8);
+        assert!((1. / sample.pdf - solid_angle).abs() < 1e-3);
+
+        // the corners of the square are the corners of the light.
+        let on_light = |sample: LightSample| point + sample.direction * sample.distance;
+        assert_eq!(P![-1., 1., -2.], on_light(light.sample_at(point, 0., 0.)));
+        assert_eq!(P![3., 1., 1.], on_light(light.sample_at(point, 1., 1.)));
+        // the middle of the square is nearer the point than the middle of the light, as the near part covers more.
+        let middle = on_light(light.sample_at(point, 0.5, 0.5));
+        assert!((middle - point).magnitude() < (P![1., 1., -0.5] - point).magnitude());
+
+        // the samples come from the same side whichever face of the light the point is under.
+        let above = light.sample_at(P![0.5, 2., 0.], 0., 0.);
+        assert_eq!(
+            P![-1., 1., -2.],
+            P![0.5, 2., 0.] + above.direction * above.distance
+        );
+    }
+
+    #[test]
+    fn test_area_light_projected_solid_angle() {
+        // a big light close to a point on a slope, which faces one end of the light far more squarely than the other.
+        let light = AreaLight::new(
+            P![-1., 1., -2.],
+            V![4., 0., 0.],
+            V![0., 0., 3.],
+            Color::WHITE,
+        );
+        let point = P![0.5, 0., 0.];
+        let normal = V![0.4, 1., 0.].norm();
+
+        // the cosine at the point added up over a fine grid on the light.
+        let n = 400;
+        let mut projected = 0.;
+        for i in 0..n {
+            for j in 0..n {
+                let (u, v) = ((i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64);
+                let to_light = P![-1., 1., -2.] + V![4., 0., 0.] * u + V![0., 0., 3.] * v - point;
+                let distance = to_light.magnitude();
+                let cos = dot(to_light.norm(), normal).abs();
+                projected +=
+                    12. / (n * n) as f64 * cos * (to_light.y() / distance) / (distance * distance);
+            }
+        }
+
+        // averaging the cosine over the pdf of the samples finds the same total, and varies less from sample to sample
+        // than when the samples ignore the normal.
+        let m = 32;
+        let estimates = |sample: &dyn Fn(f64, f64) -> LightSample| -> (f64, f64) {
+            let values: Vec<f64> = (0..m * m)
+                .map(|k| {
+                    let (u, v) = (
+                        ((k % m) as f64 + 0.5) / m as f64,
+                        ((k / m) as f64 + 0.5) / m as f64,
+                    );
+                    let sample = sample(u, v);
+                    dot(sample.direction, normal).abs() / sample.pdf
+                })
+                .collect();
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            let variance =
+                values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / values.len() as f64;
+            (mean, variance)
+        };
+        let (facing, facing_variance) = estimates(&|u, v| light.sample_facing(point, normal, u, v));
+        let (_, plain_variance) = estimates(&|u, v| light.sample_at(point, u, v));
+        assert!((facing - projected).abs() / projected < 1e-2);
+        assert!(facing_variance < plain_variance * 0.75);
+
+        // the samples still come from the light, with its corners at the corners of the square.
+        let sample = light.sample_facing(point, normal, 1., 1.);
+        assert_eq!(P![3., 1., 1.], point + sample.direction * sample.distance);
+    }
+
+    #[test]
+    fn test_skewed_area_light_sample() {
+        // a parallelogram that isn't a rectangle is sampled evenly over its area.
+        let light = AreaLight::new(
+            P![-1., 5., -1.],
+            V![2., 0., 0.],
+            V![1., 0., 2.],
+            Color::WHITE,
+        );
+        let point = P![0., 0., 0.];
+        let sample = light.sample_at(point, 0.25, 0.5);
+        let on_light = point + sample.direction * sample.distance;
+        assert_eq!(P![0., 5., 0.], on_light);
+        let cos = sample.direction.y();
+        assert!(approx_eq(
+            sample.distance * sample.distance / (4. * cos),
+            sample.pdf
+        ));
+    }
+
+    #[test]
+    fn test_dome_light_sample() {
+        let light = DomeLight::new(C![0.5, 0.5, 0.5]);
+        let mut rng = Rng::new(5);
+        let samples: Vec<LightSample> = (0..500)
+            .map(|_| light.sample(P![0., 0., 0.], &mut rng))
+            .collect();
+        assert!(samples
+            .iter()
+            .all(|s| approx_eq(1., s.direction.magnitude())
+                && s.distance == f64::INFINITY
+                && approx_eq(1. / (4. * PI), s.pdf)));
+
+        // the directions are spread over the whole sphere.
+        let up = samples.iter().filter(|s| s.direction.y() > 0.).count();
+        assert!(200 < up && up < 300);
+    }
+
+    #[test]
+    fn test_directional_light_sample() {
+        let light = DirectionalLight::new(V![0., -2., 0.], Color::WHITE);
+        let sample = light.sample(P![3., 0., -7.], &mut Rng::new(0));
+        assert_eq!(V![0., 1., 0.], sample.direction);
+        assert_eq!(f64::INFINITY, sample.distance);
+        assert_eq!(Color::WHITE, light.intensity_at(P![100., 0., 0.]));
+        assert_eq!(None, light.position());
+    }
+
+    #[test]
+    fn test_boxed_lights() {
+        let lights = [
+            PointLight::new(P![0., 5., 0.], Color::WHITE).box_clone(),
+            SpotLight::new(P![0., 5., 0.], V![0., -1., 0.], 0.2, 0.4, Color::WHITE).box_clone(),
+            AreaLight::new(
+                P![-1., 5., -1.],
+                V![2., 0., 0.],
+                V![0., 0., 2.],
+                Color::WHITE,
+            )
+            .box_clone(),
+            DirectionalLight::new(V![0., -1., 0.], Color::WHITE).box_clone(),
+        ];
+        assert_eq!(*lights[0], *lights[0].clone());
+        assert_ne!(*lights[0], *lights[1]);
+        assert_eq!(Some(P![0., 5., 0.]), lights[2].position());
+        // outside the spot light's cone.
+        assert_eq!(Color::BLACK, lights[1].intensity_at(P![5., 0., 0.]));
+
+        // every kind of light lights the floor under it side by side in one world.
+        let world = World::new(vec![Plane::default().box_clone()], None);
+        let mut lit = world.clone();
+        lights.iter().for_each(|light| {
+            lit.add_light(light.clone());
+        });
+        assert_eq!(4, lit.lights().count());
+        let r = crate::primatives::ray::Ray::new(P![0., 1., -1.], V![0., -1., 1.].norm());
+        let alone = |light: &BoxedLight| {
+            let mut world = world.clone();
+            world.set_light(light.clone());
+            world.color_at(r)
+        };
+        let total = lights
+            .iter()
+            .fold(Color::BLACK, |total, light| total + alone(light));
+        assert_eq!(total, lit.color_at(r));
+    }
+
+    #[test]
+    fn test_dome_light_is_shadowed_by_anything() {
+        let world = World::new(vec![Plane::default().box_clone()], None);
+        let point = P![0., -1., 0.];
+        let down = LightSample {
+            direction: V![0., -1., 0.],
+            distance: f64::INFINITY,
+            intensity: Color::WHITE,
+            pdf: 1.,
+        };
+        let up = LightSample {
+            direction: V![0., 1., 0.],
+            ..down
+        };
+        assert!(!world.is_shadowed_along(point, &down));
+        assert!(world.is_shadowed_along(point, &up));
+    }
+
     #[test]
     fn test_lighting() {
         let s = Sphere::default().box_clone();
@@ -119,7 +898,7 @@ mod test_lights {
         let normal_v = Vector::new(0., 0., -1.);
         let light = PointLight::new(P![0., 0., -10.], C![1., 1., 1.]);
 
-        let result = lighting(m, s.clone(), light, p, eye_v, normal_v, false);
+        let result = lighting(&m, s.clone(), light, p, eye_v, normal_v, false);
 
         assert_eq!(C![1.9, 1.9, 1.9], result);
 
@@ -130,7 +909,7 @@ mod test_lights {
         let normal_v = Vector::new(0., 0., -1.);
         let light = PointLight::new(P![0., 0., -10.], C![1., 1., 1.]);
 
-        let result = lighting(m, s.clone(), light, p, eye_v, normal_v, false);
+        let result = lighting(&m, s.clone(), light, p, eye_v, normal_v, false);
 
         assert_eq!(C![1.0, 1.0, 1.0], result);
 
@@ -142,7 +921,7 @@ mod test_lights {
         let normal_v = Vector::new(0., 0., -1.);
         let light = PointLight::new(P![0., 10., -10.], C![1., 1., 1.]);
 
-        let result = lighting(m, s.clone(), light, p, eye_v, normal_v, false);
+        let result = lighting(&m, s.clone(), light, p, eye_v, normal_v, false);
 
         assert_eq!(C![0.7364, 0.7364, 0.7364], result);
 
@@ -155,7 +934,7 @@ mod test_lights {
         let normal_v = Vector::new(0., 0., -1.);
         let light = PointLight::new(P![0., 10., -10.], C![1., 1., 1.]);
 
-        let result = lighting(m, s.clone(), light, p, eye_v, normal_v, false);
+        let result = lighting(&m, s.clone(), light, p, eye_v, normal_v, false);
 
         assert_eq!(C![1.6364, 1.6364, 1.6364], result);
 
@@ -167,7 +946,7 @@ mod test_lights {
         let normal_v = Vector::new(0., 0., -1.);
         let light = PointLight::new(P![0., 0., 10.], C![1., 1., 1.]);
 
-        let result = lighting(m, s.clone(), light, p, eye_v, normal_v, false);
+        let result = lighting(&m, s.clone(), light, p, eye_v, normal_v, false);
 
         assert_eq!(C![0.1, 0.1, 0.1], result);
 
@@ -179,7 +958,7 @@ mod test_lights {
         let light = PointLight::new(P![0., 0., -10.], C![1., 1., 1.]);
         let in_shadow = true;
 
-        let result = lighting(m, s, light, p, eye_v, normal_v, in_shadow);
+        let result = lighting(&m, s, light, p, eye_v, normal_v, in_shadow);
         assert_eq!(C![0.1, 0.1, 0.1], result);
     }
 
@@ -200,7 +979,7 @@ mod test_lights {
         let light = PointLight::new(P![0., 0., -10.], Color::WHITE);
 
         let c1 = lighting(
-            m.clone(),
+            &m,
             s.clone(),
             light,
             P![0.9, 0., 0.],
@@ -208,9 +987,88 @@ mod test_lights {
             normal_v,
             false,
         );
-        let c2 = lighting(m, s, light, P![1.1, 0., 0.], eye_v, normal_v, false);
+        let c2 = lighting(&m, s, light, P![1.1, 0., 0.], eye_v, normal_v, false);
 
         assert_eq!(Color::WHITE, c1);
         assert_eq!(Color::BLACK, c2);
     }
+
+    /// render_wall renders a tiny image of a matte wall facing the camera, with an optional sphere just in front of it,
+    /// lit by the light. The lighting is then checked through the statistics of the image rather than exact pixels.
+    fn render_wall(light: PointLight, with_sphere: bool) -> Canvas {
+        let matte = Material::builder()
+            .specular(0.)
+            .build()
+            .expect("every material field has a default");
+        let wall = Plane::new(Some(rotation_x(FRAC_PI_2)), Some(matte));
+        let mut objects = vec![wall.box_clone()];
+        if with_sphere {
+            let sphere = Sphere::new(Some(translation(0., 0., -1.5)), None);
+            objects.push(sphere.box_clone());
+        }
+        let world = World::new(objects, Some(light.box_clone()));
+
+        let mut camera = Camera::new(16, 16, FRAC_PI_2);
+        camera.set_transform(view_transformation(
+            P![0., 0., -5.],
+            P![0., 0., 0.],
+            V![0., 1., 0.],
+        ));
+        camera.render(world)
+    }
+
+    #[test]
+    fn test_lighting_falls_off_away_from_light() {
+        // the wall is brightest where it faces the light, so the brightness leans towards the light.
+        let (x, y) = render_wall(PointLight::new(P![-4., 0., -1.], Color::WHITE), false)
+            .luminance_centroid();
+        assert!(x < -1., "{} should lean left", x);
+        assert!(y.abs() < 0.1);
+
+        let (x, _) =
+            render_wall(PointLight::new(P![4., 0., -1.], Color::WHITE), false).luminance_centroid();
+        assert!(x > 1., "{} should lean right", x);
+
+        // image rows run downwards so a light above the wall pulls the brightness up.
+        let (x, y) =
+            render_wall(PointLight::new(P![0., 4., -1.], Color::WHITE), false).luminance_centroid();
+        assert!(x.abs() < 0.1);
+        assert!(y < -1., "{} should lean up", y);
+
+        // a light straight in front of the middle lights both sides evenly.
+        let (x, y) =
+            render_wall(PointLight::new(P![0., 0., -1.], Color::WHITE), false).luminance_centroid();
+        assert!(x.abs() < 0.1 && y.abs() < 0.1);
+    }
+
+    #[test]
+    fn test_lighting_scales_with_intensity() {
+        let bright = render_wall(PointLight::new(P![0., 0., -3.], Color::WHITE), false);
+        let dim = render_wall(PointLight::new(P![0., 0., -3.], C![0.5, 0.5, 0.5]), false);
+        assert!(bright.mean_luminance() > 0.);
+        assert!(approx_eq(
+            0.5,
+            dim.mean_luminance() / bright.mean_luminance()
+        ));
+
+        // a colored light only lights the channels it has.
+        let red = render_wall(PointLight::new(P![0., 0., -3.], C![1., 0., 0.]), false);
+        let pixel = red.pixel_at(8, 8).unwrap();
+        assert!(pixel.red() > 0. && pixel.green() == 0. && pixel.blue() == 0.);
+    }
+
+    #[test]
+    fn test_lighting_shadows_darken_image() {
+        let light = PointLight::new(P![-4., 0., -3.], Color::WHITE);
+        let lit = render_wall(light, false);
+        let shadowed = render_wall(light, true);
+        assert!(shadowed.mean_luminance() < lit.mean_luminance());
+
+        // the light is to the left so the sphere's shadow falls on the wall to its right.
+        let shadow = |c: &Canvas| c.pixel_at(13, 8).unwrap().luminance();
+        assert!(shadow(&shadowed) < shadow(&lit));
+        let (lit_x, _) = lit.luminance_centroid();
+        let (shadowed_x, _) = shadowed.luminance_centroid();
+        assert!(shadowed_x < lit_x);
+    }
 }
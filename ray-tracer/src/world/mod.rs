@@ -1,94 +1,751 @@
+pub mod animation;
 pub mod camera;
 pub mod canvas;
+pub mod depth;
+pub mod environment;
 pub mod intersection;
+pub mod irradiance;
 pub mod light;
+pub mod pick;
+pub mod scene;
+pub mod scenes;
+pub mod settings;
+
+use std::{
+    collections::HashSet,
+    f64::consts::{PI, TAU},
+    sync::Arc,
+};
 
 use crate::{
-    primatives::color::Color, primatives::point::Point, primatives::ray::Ray,
-    primatives::transformation::scaling, primatives::tuple::Tuple, shapes::material::Material,
-    shapes::sphere::Sphere, shapes::BoxedShape, C, P,
+    primatives::accelerator::{Acceleration, BoxedAccelerator},
+    primatives::color::Color,
+    primatives::point::Point,
+    primatives::ray::Ray,
+    primatives::rng::Rng,
+    primatives::sampling::{Halton, Sampler},
+    primatives::transformation::scaling,
+    primatives::tuple::Tuple,
+    primatives::vector::{cross, dot, Vector},
+    shapes::group::Group,
+    shapes::material::Material,
+    shapes::mesh::MeshFace,
+    shapes::quad::Quad,
+    shapes::sphere::Sphere,
+    shapes::triangle::TriangleIntersection,
+    shapes::{make_mut, BoxedShape, Shape},
+    C, P, V,
 };
 use {
-    intersection::{Intersections, PrecomputedData},
-    light::{lighting, PointLight},
+    camera::Camera,
+    environment::Environment,
+    intersection::{Intersection, Intersections, PrecomputedData},
+    light::{
+        lighting_sample, AreaLight, BoxedLight, Light, LightBase, LightLinking, LightSample,
+        PointLight,
+    },
+    pick::{FaceHit, Pick},
+    settings::{RenderSettings, SecondaryRays},
 };
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 pub struct World {
     objects: Vec<BoxedShape>,
-    light: Option<PointLight>,
+    /// primitives are the objects with every group replaced by the shapes inside it.
+    /// Children already hold their group's transformation so they can be intersected on their own.
+    primitives: Vec<BoxedShape>,
+    /// sources are the ids each primitive had before it was flattened, its own and those of the groups around it,
+    /// which stay the same however often the primitives are renewed.
+    sources: Vec<Vec<u64>>,
+    acceleration: Acceleration,
+    /// accelerator finds the primitives a ray might hit, rebuilt whenever the objects change.
+    accelerator: BoxedAccelerator,
+    lights: Vec<SceneLight>,
+    /// environment surrounds the scene, seen where rays miss and lighting the shapes from all around.
+    environment: Option<Environment>,
+    settings: RenderSettings,
+}
+
+/// SceneLight is a light in the world along with a switch and a dimmer, so the lighting of a scene can be changed
+/// between renders without building the world again.
+#[derive(Debug, Clone)]
+struct SceneLight {
+    light: BoxedLight,
+    enabled: bool,
+    /// dimmer scales the light's intensity, 1 is the light as it was made.
+    dimmer: f64,
+    /// links are the shapes the light was linked to, as they were given.
+    links: LightLinking,
+    /// linking is which primitives the light shines on, with any group it was given replaced by the shapes inside it.
+    linking: LightLinking,
+}
+
+impl SceneLight {
+    fn new(light: BoxedLight) -> Self {
+        Self {
+            light,
+            enabled: true,
+            dimmer: 1.,
+            links: LightLinking::All,
+            linking: LightLinking::All,
+        }
+    }
+
+    /// lit is the light as it shines with the dimmer applied, or nothing when it is switched off.
+    fn lit(&self) -> Option<BoxedLight> {
+        if !self.enabled {
+            None
+        } else if self.dimmer == 1. {
+            Some(self.light.clone())
+        } else {
+            Some(Arc::new(Dimmed {
+                light: self.light.clone(),
+                dimmer: self.dimmer,
+            }))
+        }
+    }
+
+    /// samples are samples of the light with the dimmer applied for a point on a surface facing along the normal, or
+    /// none when it is switched off. A light that varies is sampled count times, spread over it with a Halton sequence
+    /// shifted by rng, and other lights are sampled once.
+    fn samples(
+        &self,
+        point: Point,
+        normal: Vector,
+        count: usize,
+        rng: &mut Rng,
+    ) -> Vec<LightSample> {
+        if !self.enabled {
+            return vec![];
+        }
+        let mut samples = if !self.light.varies() {
+            vec![self.light.sample(point, rng)]
+        } else if count > 1 {
+            let sequence = Halton::scrambled(rng);
+            (0..count)
+                .map(|index| {
+                    let (u, v) = sequence.point(index);
+                    self.light.sample_facing(point, normal, u, v)
+                })
+                .collect()
+        } else {
+            let (u, v) = (rng.next_f64(), rng.next_f64());
+            vec![self.light.sample_facing(point, normal, u, v)]
+        };
+        samples
+            .iter_mut()
+            .for_each(|sample| sample.intensity = sample.intensity * self.dimmer);
+        samples
+    }
+}
+
+/// Dimmed is a light shining with its intensity scaled, as handed out for a light in the world with its dimmer turned.
+#[derive(Debug, Clone)]
+struct Dimmed {
+    light: BoxedLight,
+    dimmer: f64,
+}
+
+// comparing the lights behind the Arcs, as deriving PartialEq would try to move them out (rust-lang/rust#31740).
+impl PartialEq for SceneLight {
+    fn eq(&self, other: &Self) -> bool {
+        *self.light == *other.light
+            && self.enabled == other.enabled
+            && self.dimmer == other.dimmer
+            && self.links == other.links
+    }
+}
+
+impl PartialEq for Dimmed {
+    fn eq(&self, other: &Self) -> bool {
+        *self.light == *other.light && self.dimmer == other.dimmer
+    }
+}
+
+impl Light for Dimmed {
+    fn sample_at(&self, point: Point, u: f64, v: f64) -> LightSample {
+        let mut sample = self.light.sample_at(point, u, v);
+        sample.intensity = sample.intensity * self.dimmer;
+        sample
+    }
+
+    fn sample_facing(&self, point: Point, normal: Vector, u: f64, v: f64) -> LightSample {
+        let mut sample = self.light.sample_facing(point, normal, u, v);
+        sample.intensity = sample.intensity * self.dimmer;
+        sample
+    }
+
+    fn varies(&self) -> bool {
+        self.light.varies()
+    }
+
+    fn intensity(&self) -> Color {
+        self.light.intensity() * self.dimmer
+    }
+
+    fn position(&self) -> Option<Point> {
+        self.light.position()
+    }
 }
 
 impl World {
-    pub fn new(objects: Vec<BoxedShape>, light: Option<PointLight>) -> Self {
-        Self { objects, light }
+    pub fn new(objects: Vec<BoxedShape>, light: Option<BoxedLight>) -> Self {
+        let mut world = Self {
+            objects,
+            primitives: vec![],
+            sources: vec![],
+            acceleration: Acceleration::default(),
+            accelerator: Acceleration::default().build(vec![]),
+            lights: light.into_iter().map(SceneLight::new).collect(),
+            environment: None,
+            settings: RenderSettings::default(),
+        };
+        world.build_accelerator();
+        world
+    }
+
+    /// build_accelerator flattens the objects into primitives and builds the chosen accelerator over them.
+    /// A shape cloned into the world more than once would leave its copies sharing an id, so each copy after the
+    /// first is given an id of its own, otherwise a ray could never tell leaving one copy from entering another.
+    fn build_accelerator(&mut self) {
+        let (primitives, sources) = self.flattened();
+        self.accelerator = self
+            .acceleration
+            .build(primitives.iter().map(|p| p.world_bounds()).collect());
+        self.primitives = primitives;
+        self.sources = sources;
+        self.link_lights();
+    }
+
+    /// flattened is the objects with every group replaced by the shapes inside it, each with an id of its own and
+    /// a pattern transform for shading, along with the ids each of them came from.
+    fn flattened(&self) -> (Vec<BoxedShape>, Vec<Vec<u64>>) {
+        let mut primitives = vec![];
+        let mut sources = vec![];
+        self.objects
+            .iter()
+            .for_each(|object| flatten(object, &mut vec![], &mut primitives, &mut sources));
+        let mut ids = HashSet::new();
+        for primitive in primitives.iter_mut() {
+            while !ids.insert(primitive.id()) {
+                make_mut(primitive).tag_mut().renew();
+            }
+            if primitive.material().pattern().is_some()
+                && primitive.pattern_transformation().is_none()
+            {
+                make_mut(primitive).props_mut().combine_pattern_transform();
+            }
+        }
+        (primitives, sources)
+    }
+
+    /// restyle replaces the objects with copies that only differ in how they look, such as having new materials,
+    /// keeping the accelerator as the shapes haven't moved. This is much quicker than building the world again
+    /// for large scenes. It panics if the new objects aren't made of the same number of primitives.
+    pub fn restyle(&mut self, objects: Vec<BoxedShape>) {
+        self.objects = objects;
+        let (primitives, sources) = self.flattened();
+        assert_eq!(
+            self.primitives.len(),
+            primitives.len(),
+            "restyled objects must be made of the same primitives"
+        );
+        debug_assert!(
+            self.primitives
+                .iter()
+                .zip(&primitives)
+                .all(|(old, new)| old.world_bounds() == new.world_bounds()),
+            "restyled objects must not move"
+        );
+        self.primitives = primitives;
+        self.sources = sources;
+        self.link_lights();
+    }
+
+    /// add_object puts another object into the world, rebuilding the accelerator.
+    /// Its triangles are switched to the world's way of intersecting them if that isn't the default.
+    pub fn add_object(&mut self, mut object: BoxedShape) {
+        let intersection = self.settings.triangle_intersection();
+        if intersection != TriangleIntersection::default() {
+            make_mut(&mut object).set_triangle_intersection(intersection);
+        }
+        self.objects.push(object);
+        self.build_accelerator();
+    }
+
+    /// replace_material gives every object sharing the old material the new one instead, including shapes inside
+    /// groups and meshes, and returns the new material so it can be shared with more objects or replaced again.
+    pub fn replace_material(&mut self, old: &Arc<Material>, new: Material) -> Arc<Material> {
+        let new = Arc::new(new);
+        self.objects
+            .iter_mut()
+            .for_each(|object| make_mut(object).replace_material(old, &new));
+        self.build_accelerator();
+        new
+    }
+
+    pub fn acceleration(&self) -> Acceleration {
+        self.acceleration
+    }
+
+    /// set_acceleration switches how rays find the primitives they might hit, rebuilding the accelerator.
+    pub fn set_acceleration(&mut self, acceleration: Acceleration) {
+        self.acceleration = acceleration;
+        self.build_accelerator();
+    }
+
+    pub fn environment(&self) -> Option<&Environment> {
+        self.environment.as_ref()
+    }
+
+    pub fn set_environment(&mut self, environment: Option<Environment>) {
+        self.environment = environment;
+    }
+
+    pub fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    /// set_settings replaces the render settings, passing a change in how triangles are intersected on to the objects.
+    pub fn set_settings(&mut self, settings: RenderSettings) {
+        let intersection = settings.triangle_intersection();
+        let changed = intersection != self.settings.triangle_intersection();
+        self.settings = settings;
+        if changed {
+            self.set_triangle_intersection(intersection);
+        }
+    }
+
+    /// set_max_depth limits how many times a ray can be reflected, returning a warning for each setting that looks
+    /// like a mistake for the caller to show however it likes.
+    pub fn set_max_depth(&mut self, max_depth: usize) -> Vec<String> {
+        self.settings.set_max_depth(max_depth);
+        self.settings.warnings()
     }
 
-    pub fn set_light(&mut self, light: PointLight) {
-        self.light = Some(light);
+    pub fn set_min_hit_distance(&mut self, min_hit_distance: f64) {
+        self.settings.set_min_hit_distance(min_hit_distance);
     }
 
-    pub fn light(&self) -> &Option<PointLight> {
-        &self.light
+    pub fn set_bias(&mut self, bias: f64) {
+        self.settings.set_bias(bias);
+    }
+
+    /// set_triangle_intersection switches how every triangle and mesh face in the world is intersected,
+    /// rebuilding the primitives so they use it too.
+    pub fn set_triangle_intersection(&mut self, intersection: TriangleIntersection) {
+        self.settings.set_triangle_intersection(intersection);
+        self.objects
+            .iter_mut()
+            .for_each(|object| make_mut(object).set_triangle_intersection(intersection));
+        self.build_accelerator();
+    }
+
+    /// set_light makes the given light the only light in the world.
+    pub fn set_light(&mut self, light: BoxedLight) {
+        self.lights = vec![SceneLight::new(light)];
+    }
+
+    /// set_lights replaces every light in the world, switched on, at full strength and shining on everything.
+    pub fn set_lights(&mut self, lights: Vec<BoxedLight>) {
+        self.lights = lights.into_iter().map(SceneLight::new).collect();
+    }
+
+    /// add_light puts another light into the world, returning the index used to switch or dim it.
+    pub fn add_light(&mut self, light: BoxedLight) -> usize {
+        self.lights.push(SceneLight::new(light));
+        self.lights.len() - 1
+    }
+
+    /// add_emissive_lights makes every quad with an emissive material into an area light of the same size and color,
+    /// so a glowing panel lights the shapes around it as well as looking bright itself. Other emissive shapes can't
+    /// be made into lights and only glow. It returns how many lights were added.
+    pub fn add_emissive_lights(&mut self) -> usize {
+        let panels: Vec<BoxedLight> = self
+            .primitives
+            .iter()
+            .filter(|p| p.material().emissive() != Color::BLACK && p.as_any().is::<Quad>())
+            .map(|quad| {
+                let m = quad.transformation();
+                let corner = m * P![-1., 0., -1.];
+                let (u, v) = (m * P![1., 0., -1.] - corner, m * P![-1., 0., 1.] - corner);
+                AreaLight::new(corner, u, v, quad.material().emissive()).box_clone()
+            })
+            .collect();
+        let added = panels.len();
+        self.lights.extend(panels.into_iter().map(SceneLight::new));
+        added
+    }
+
+    /// light is the first light that is switched on, as it shines with its dimmer applied.
+    pub fn light(&self) -> Option<BoxedLight> {
+        self.lights().next()
+    }
+
+    /// lights are the lights that are switched on, as they shine with their dimmers applied.
+    pub fn lights(&self) -> impl Iterator<Item = BoxedLight> + '_ {
+        self.lights.iter().filter_map(SceneLight::lit)
+    }
+
+    /// set_light_enabled switches the light at the index on or off. It panics if there is no such light.
+    pub fn set_light_enabled(&mut self, index: usize, enabled: bool) {
+        self.lights[index].enabled = enabled;
+    }
+
+    pub fn light_enabled(&self, index: usize) -> bool {
+        self.lights[index].enabled
+    }
+
+    /// set_light_dimmer scales the intensity of the light at the index, 0 is dark and 1 is the light as it was made.
+    /// Values above 1 brighten it. It panics if there is no such light or the dimmer is negative.
+    pub fn set_light_dimmer(&mut self, index: usize, dimmer: f64) {
+        assert!(dimmer >= 0., "a light's dimmer can't be negative");
+        self.lights[index].dimmer = dimmer;
+    }
+
+    pub fn light_dimmer(&self, index: usize) -> f64 {
+        self.lights[index].dimmer
+    }
+
+    /// set_light_linking picks the shapes the light at the index shines on. The id of a group stands for every shape
+    /// in it, including shapes added to it later. It panics if there is no such light.
+    pub fn set_light_linking(&mut self, index: usize, linking: LightLinking) {
+        self.lights[index].links = linking;
+        self.link_lights();
+    }
+
+    /// link_lights finds the primitives each light shines on from the shapes it was linked to. The primitives are
+    /// matched by the ids they came from, as copies of a shape are given new ids each time the world is flattened.
+    fn link_lights(&mut self) {
+        for light in self.lights.iter_mut() {
+            light.linking = light.links.map_ids(|ids| {
+                let mut within = ids.clone();
+                within.extend(
+                    self.primitives
+                        .iter()
+                        .zip(&self.sources)
+                        .filter(|(_, sources)| sources.iter().any(|id| ids.contains(id)))
+                        .map(|(primitive, _)| primitive.id()),
+                );
+                within
+            });
+        }
+    }
+
+    /// light_linking is the shapes the light at the index shines on, with groups replaced by the shapes inside them.
+    pub fn light_linking(&self, index: usize) -> &LightLinking {
+        &self.lights[index].linking
     }
 
     pub fn objects(&self) -> &Vec<BoxedShape> {
         &self.objects
     }
 
+    /// update_object lets the object at the index be changed, for example moved with set_transform or given a new
+    /// material, rebuilding the accelerator afterwards so rays see the change. It panics if there is no such object.
+    pub fn update_object(&mut self, index: usize, update: impl FnOnce(&mut dyn Shape)) {
+        update(make_mut(&mut self.objects[index]));
+        self.build_accelerator();
+    }
+
+    /// intersect finds everywhere the ray hits an object, only testing the primitives the accelerator finds.
     pub fn intersect(&self, r: Ray) -> Intersections {
-        let mut intersections = Intersections::new(vec![]);
-        self.objects()
-            .iter()
-            .for_each(|o| intersections.extend(o.intersect(r)));
+        let mut intersections = Intersections::EMPTY;
+        self.accelerator.visit(r, &mut |i| {
+            intersections.extend(self.primitives[i].intersect(r))
+        });
         intersections
     }
 
+    /// primitives are the shapes the world is made of, with every group replaced by the shapes inside it.
+    pub fn primitives(&self) -> &Vec<BoxedShape> {
+        &self.primitives
+    }
+
+    /// primitive_hit is the closest hit in front of the ray along with the index of the primitive that was hit.
+    pub fn primitive_hit(&self, r: Ray) -> Option<(usize, Intersection)> {
+        let mut closest: Option<(usize, Intersection)> = None;
+        self.accelerator.visit(r, &mut |i| {
+            let mut xs = self.primitives[i].intersect(r);
+            if let Some(hit) = xs.hit_after(self.settings.min_hit_distance()) {
+                if closest.as_ref().is_none_or(|(_, c)| hit.t() < c.t()) {
+                    closest = Some((i, hit.clone()));
+                }
+            }
+        });
+        closest
+    }
+
+    /// pick finds what the camera sees through the pixel, so a preview can select objects by clicking on them.
+    /// Hits on meshes say which face was hit and where on it.
+    pub fn pick(&self, x: usize, y: usize, camera: &Camera) -> Option<Pick> {
+        let ray = camera.ray_for_pixel(x, y);
+        let (primitive, hit) = self.primitive_hit(ray)?;
+        let point = ray.at(hit.t());
+        let face = hit.shape().as_any().downcast_ref::<MeshFace>().map(|face| {
+            let (u, v) = face.barycentric(point);
+            FaceHit {
+                index: face.face(),
+                u,
+                v,
+            }
+        });
+        Some(Pick {
+            primitive,
+            object: self.primitives[primitive].clone(),
+            t: hit.t(),
+            point,
+            face,
+        })
+    }
+
+    /// is_shadowed is whether the point is hidden from the first light that is switched on.
     pub fn is_shadowed(&self, p: Point) -> bool {
-        if self.light.is_none() {
-            return true; // no lights -> all shadow
+        match self.light() {
+            Some(light) => self.is_shadowed_from(p, light.as_ref()),
+            None => true, // no lights -> all shadow
         }
-        let v = self.light.unwrap().position() - p;
-        let direction = v.norm();
-        let distance = v.magnitude();
-        let ray_to_light = Ray::new(p, direction);
+    }
+
+    /// is_shadowed_from is whether something that casts shadows is between the point and the light.
+    pub fn is_shadowed_from(&self, p: Point, light: &(impl Light + ?Sized)) -> bool {
+        let sample = light.sample(p, &mut Rng::for_point(p));
+        self.is_shadowed_along(p, &sample)
+    }
+
+    /// is_shadowed_along is whether something that casts shadows is between the point and where the sample's light
+    /// comes from.
+    pub fn is_shadowed_along(&self, p: Point, sample: &LightSample) -> bool {
+        let ray_to_light = Ray::new(p, sample.direction);
 
         // check if intersections between point and light source.
-        // ignore any over distance between the two
-        let mut intersections = self.intersect(ray_to_light);
-        let h = intersections.hit();
-        h.is_some() && h.unwrap().t() < distance
+        // ignore any over distance between the two, and any objects that don't cast shadows. A hit right at the
+        // light is the surface it shines from, such as an emissive quad, so it doesn't count either.
+        let min_t = self.settings.min_hit_distance();
+        self.intersect(ray_to_light).iter().any(|i| {
+            i.t() >= min_t && i.t() < sample.distance - min_t && i.shape().material().casts_shadow()
+        })
     }
 
+    /// shade_hit finds the color at a prepared intersection, following reflections up to the max depth.
     pub fn shade_hit(&self, prepared: PrecomputedData) -> Color {
-        let is_shadowed = self.is_shadowed(prepared.over_point);
-        lighting(
-            prepared.object.material().clone(),
-            prepared.object,
-            self.light.expect("trying to shade a hit without a light"),
-            prepared.over_point,
-            prepared.eye_v,
-            prepared.normal_v,
-            is_shadowed,
-        )
+        self.shade_hit_with_depth(prepared, self.settings.max_depth())
+    }
+
+    /// shade_hit_with_depth adds up the light from every light that is switched on and from the environment, so a
+    /// surface lit by neither is black apart from what it reflects and lets through.
+    fn shade_hit_with_depth(&self, prepared: PrecomputedData, remaining: usize) -> Color {
+        let reflected = self.reflected_color(&prepared, remaining);
+        let refracted = self.refracted_color(&prepared, remaining);
+        let material = prepared.object.shared_material().clone();
+        let mut rng = Rng::for_point(prepared.over_point);
+        let surface = self
+            .lights
+            .iter()
+            .filter(|light| light.linking.shines_on(prepared.object.id()))
+            .map(|light| {
+                let samples = light.samples(
+                    prepared.over_point,
+                    prepared.normal_v,
+                    self.settings.light_samples(),
+                    &mut rng,
+                );
+                let total = samples
+                    .iter()
+                    .map(|sample| {
+                        lighting_sample(
+                            &material,
+                            prepared.object.clone(),
+                            sample,
+                            prepared.over_point,
+                            prepared.eye_v,
+                            prepared.normal_v,
+                            self.is_shadowed_along(prepared.over_point, sample),
+                        )
+                    })
+                    .fold(Color::BLACK, |total, color| total + color);
+                total * (1. / samples.len().max(1) as f64)
+            })
+            .fold(Color::BLACK, |total, color| total + color);
+        let environment = self.environment_light(&prepared, &material, &mut rng);
+        surface + environment + reflected + refracted + material.emissive()
+    }
+
+    /// environment_light is the environment's light on the surface's diffuse color, found by looking at the
+    /// environment in directions around the normal and leaving out those where something casts a shadow.
+    fn environment_light(
+        &self,
+        prepared: &PrecomputedData,
+        material: &Material,
+        rng: &mut Rng,
+    ) -> Color {
+        let Some(environment) = &self.environment else {
+            return Color::BLACK;
+        };
+        if material.diffuse() == 0. || environment.samples() == 0 {
+            return Color::BLACK;
+        }
+        let total = environment
+            .hemisphere(prepared.normal_v, rng)
+            .filter(|&direction| {
+                let sample = LightSample {
+                    direction,
+                    distance: f64::INFINITY,
+                    intensity: Color::BLACK,
+                    pdf: 1.,
+                };
+                !self.is_shadowed_along(prepared.over_point, &sample)
+            })
+            .map(|direction| environment.color_in(direction))
+            .fold(Color::BLACK, |total, color| total + color);
+        let color = material.color_at(prepared.object.clone(), prepared.point);
+        color * total * (material.diffuse() / environment.samples() as f64)
+    }
+
+    /// secondary_color is what a surface shows in place of a reflection or refraction when the settings say not to
+    /// trace it, or None when it should be traced.
+    fn secondary_color(&self, prepared: &PrecomputedData, rays: SecondaryRays) -> Option<Color> {
+        match rays {
+            SecondaryRays::Traced => None,
+            SecondaryRays::Black => Some(Color::BLACK),
+            SecondaryRays::SurfaceColor => Some(
+                prepared
+                    .object
+                    .material()
+                    .color_at(prepared.object.clone(), prepared.point),
+            ),
+        }
+    }
+
+    /// reflected_color casts a ray in the reflected direction and scales the color it finds by how reflective the surface is.
+    /// remaining is how many more reflections are allowed, so that two facing mirrors don't recurse forever.
+    pub fn reflected_color(&self, prepared: &PrecomputedData, remaining: usize) -> Color {
+        let reflective = prepared.object.material().reflective();
+        if reflective == 0. {
+            return Color::BLACK;
+        }
+        if let Some(color) = self.secondary_color(prepared, self.settings.reflections()) {
+            return color * reflective;
+        }
+        if remaining == 0 {
+            return Color::BLACK;
+        }
+
+        let blur = prepared.object.material().reflection_blur();
+        if blur == 0. || !prepared.object.is_planar() {
+            let origin = prepared.secondary_origin(prepared.reflect_v, self.settings.bias());
+            let reflect_ray = Ray::new(origin, prepared.reflect_v);
+            return self.color_at_with_depth(reflect_ray, remaining - 1) * reflective;
+        }
+
+        let samples = self.settings.reflection_blur_samples();
+        let total = blurred_reflections(prepared, blur, samples)
+            .map(|direction| {
+                let origin = prepared.secondary_origin(direction, self.settings.bias());
+                self.color_at_with_depth(Ray::new(origin, direction), remaining - 1)
+            })
+            .fold(Color::BLACK, |total, color| total + color);
+        total * (reflective / samples as f64)
+    }
+
+    /// refracted_color bends a ray into the surface by Snell's law and scales the color it finds by how transparent
+    /// the surface is. Rays that would leave a denser material at too shallow an angle are reflected back inside
+    /// instead (total internal reflection), so nothing comes through.
+    pub fn refracted_color(&self, prepared: &PrecomputedData, remaining: usize) -> Color {
+        let transparency = prepared.object.material().transparency();
+        if transparency == 0. {
+            return Color::BLACK;
+        }
+        if let Some(color) = self.secondary_color(prepared, self.settings.refractions()) {
+            return color * transparency;
+        }
+        if remaining == 0 {
+            return Color::BLACK;
+        }
+
+        let n_ratio = prepared.n1 / prepared.n2;
+        let cos_i = dot(prepared.eye_v, prepared.normal_v);
+        let sin2_t = n_ratio.powi(2) * (1. - cos_i.powi(2));
+        if sin2_t > 1. {
+            return Color::BLACK;
+        }
+
+        let cos_t = (1. - sin2_t).sqrt();
+        let direction = prepared.normal_v * (n_ratio * cos_i - cos_t) - prepared.eye_v * n_ratio;
+        let origin = prepared.secondary_origin(direction, self.settings.bias());
+        self.color_at_with_depth(Ray::new(origin, direction), remaining - 1) * transparency
     }
 
     pub fn color_at(&self, r: Ray) -> Color {
+        self.color_at_with_depth(r, self.settings.max_depth())
+    }
+
+    fn color_at_with_depth(&self, r: Ray, remaining: usize) -> Color {
         let mut xs = self.intersect(r);
-        let hit = xs.hit();
+        let hit = xs.hit_after(self.settings.min_hit_distance()).cloned();
 
         if let Some(hit) = hit {
-            let prepared = hit.prepare_computations(r);
-            self.shade_hit(prepared)
+            let prepared =
+                hit.prepare_computations_with_intersections(r, &xs, self.settings.bias());
+            self.shade_hit_with_depth(prepared, remaining)
+        } else {
+            self.environment
+                .as_ref()
+                .map_or(Color::BLACK, |environment| {
+                    environment.color_in(r.direction)
+                })
+        }
+    }
+}
+
+/// blurred_reflections spreads the given number of directions over a disc of the given radius around the mirror
+/// direction. The samples follow a golden angle spiral so that even a handful cover the disc evenly, and the spiral
+/// is turned by an amount taken from the hit point, which trades banding for fine noise that is the same every render.
+/// Directions that would dip below the surface are replaced by the mirror direction.
+fn blurred_reflections(
+    prepared: &PrecomputedData,
+    radius: f64,
+    samples: usize,
+) -> impl Iterator<Item = Vector> + '_ {
+    let reflect_v = prepared.reflect_v;
+    let helper = if reflect_v.x().abs() < 0.9 {
+        V![1., 0., 0.]
+    } else {
+        V![0., 1., 0.]
+    };
+    let u = cross(reflect_v, helper).norm();
+    let v = cross(reflect_v, u);
+    let p = prepared.point;
+    let hash = (p.x() * 12.9898 + p.y() * 78.233 + p.z() * 37.719).sin() * 43758.5453;
+    let turn = hash.fract() * TAU;
+    let golden_angle = PI * (3. - 5.0_f64.sqrt());
+
+    (0..samples).map(move |k| {
+        let r = radius * ((k as f64 + 0.5) / samples as f64).sqrt();
+        let theta = turn + k as f64 * golden_angle;
+        let direction = (reflect_v + u * (r * theta.cos()) + v * (r * theta.sin())).norm();
+        if dot(direction, prepared.normal_v) > 0. {
+            direction
         } else {
-            Color::BLACK
+            reflect_v
         }
+    })
+}
+
+/// Worlds are equal when they are made of the same objects and lights, as everything else is built from them.
+impl PartialEq for World {
+    fn eq(&self, other: &Self) -> bool {
+        self.objects == other.objects
+            && self.acceleration == other.acceleration
+            && self.lights == other.lights
+            && self.environment == other.environment
+            && self.settings == other.settings
     }
 }
 
 impl Default for World {
     fn default() -> Self {
-        let s1 = Box::new(Sphere::new(
+        let s1 = Arc::new(Sphere::new(
             None,
             Some(
                 Material::builder()
@@ -101,27 +758,62 @@ impl Default for World {
                     .unwrap(),
             ),
         ));
-        let s2 = Box::new(Sphere::new(Some(scaling(0.5, 0.5, 0.5)), None));
-        Self {
-            objects: vec![s1, s2],
-            light: Some(PointLight::new(P![-10., 10., -10.], Color::WHITE)),
+        let s2 = Arc::new(Sphere::new(Some(scaling(0.5, 0.5, 0.5)), None));
+        Self::new(
+            vec![s1, s2],
+            Some(PointLight::new(P![-10., 10., -10.], Color::WHITE).box_clone()),
+        )
+    }
+}
+
+/// flatten adds the shape to primitives, or if it is a group, the shapes inside it.
+/// path is the ids of the groups around the shape, which are added to sources along with the shape's own id.
+fn flatten(
+    shape: &BoxedShape,
+    path: &mut Vec<u64>,
+    primitives: &mut Vec<BoxedShape>,
+    sources: &mut Vec<Vec<u64>>,
+) {
+    path.push(shape.id());
+    match shape.as_any().downcast_ref::<Group>() {
+        Some(group) => group
+            .children()
+            .iter()
+            .for_each(|child| flatten(child, path, primitives, sources)),
+        None => {
+            primitives.push(shape.clone());
+            sources.push(path.clone());
         }
     }
+    path.pop();
 }
 
 #[cfg(test)]
 mod test_world {
+    use std::{collections::HashSet, sync::Arc};
+
     use crate::{
+        comparison::{approx_eq, EPSILON},
+        primatives::accelerator::Acceleration,
         primatives::color::Color,
         primatives::point::Point,
         primatives::ray::Ray,
         primatives::transformation::{scaling, translation},
         primatives::tuple::Tuple,
+        shapes::group::Group,
         shapes::material::Material,
+        shapes::mesh::Mesh,
+        shapes::patterns::texture_map::UvImage,
+        shapes::plane::Plane,
+        shapes::quad::Quad,
         shapes::sphere::Sphere,
-        shapes::Shape,
+        shapes::triangle::TriangleIntersection,
+        shapes::{Shape, ShapeBase},
+        world::canvas::Canvas,
+        world::environment::Environment,
         world::intersection::Intersection,
-        world::light::PointLight,
+        world::light::{AreaLight, LightBase, LightLinking, PointLight},
+        world::settings::{SecondaryRays, DEFAULT_MAX_DEPTH},
         world::World,
         C, P, V,
     };
@@ -145,7 +837,7 @@ mod test_world {
         let s2 = Sphere::new(Some(scaling(0.5, 0.5, 0.5)), None);
         let w = World::default();
 
-        assert_eq!(w.light().unwrap(), light);
+        assert_eq!(*w.light().unwrap(), *light.box_clone());
         assert!(w.objects().contains(&s1.box_clone()));
         assert!(w.objects().contains(&s2.box_clone()));
     }
@@ -165,6 +857,171 @@ mod test_world {
         assert_eq!(xs[3].t(), 6.);
     }
 
+    #[test]
+    fn test_replace_material() {
+        // a material shared by a loose sphere and one inside a group is changed on both at once.
+        let shared = Arc::new(Material::builder().color(C![1., 0., 0.]).build().unwrap());
+        let mut loose = Sphere::new(Some(translation(-3., 0., 0.)), None);
+        loose.set_shared_material(shared.clone());
+        let mut inside = Sphere::new(Some(translation(3., 0., 0.)), None);
+        inside.set_shared_material(shared.clone());
+        let mut g = Group::new(None, None);
+        g.add_child(inside.box_clone());
+        let other = Sphere::new(None, None);
+        let mut w = World::new(
+            vec![loose.box_clone(), g.box_clone(), other.box_clone()],
+            None,
+        );
+
+        let blue = w.replace_material(
+            &shared,
+            Material::builder().color(C![0., 0., 1.]).build().unwrap(),
+        );
+        let material_at = |w: &World, x: f64| {
+            let r = Ray::new(P![x, 0., -5.], V![0., 0., 1.]);
+            w.intersect(r)[0].clone().object().shared_material().clone()
+        };
+        assert!(Arc::ptr_eq(&blue, &material_at(&w, -3.)));
+        assert!(Arc::ptr_eq(&blue, &material_at(&w, 3.)));
+        assert_eq!(&Material::default(), material_at(&w, 0.).as_ref());
+        assert_eq!(C![0., 0., 1.], w.objects()[0].material().color());
+    }
+
+    #[test]
+    fn test_repeated_shapes_get_their_own_ids() {
+        let mut glass = Sphere::glass(None, 1.5);
+        glass.set_name("glass");
+        let mut other = glass.clone();
+        other.set_transform(translation(0., 0., 3.));
+        let w = World::new(vec![glass.box_clone(), other.box_clone()], None);
+
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+        let xs = w.intersect(r);
+        let ids: Vec<u64> = xs.iter().map(|i| i.clone().object().id()).collect();
+        assert_eq!(4, ids.len());
+        assert_eq!(glass.id(), ids[0]);
+        assert_eq!(ids[0], ids[1]);
+        assert_eq!(ids[2], ids[3]);
+        assert_ne!(ids[0], ids[2]);
+        assert_eq!(Some("glass"), xs[2].clone().object().name());
+
+        // leaving the first sphere and entering the second are told apart, so the ray is back in air between them.
+        assert_eq!((1.5, 1.), xs[1].refractive_indices(&xs));
+        assert_eq!((1., 1.5), xs[2].refractive_indices(&xs));
+    }
+
+    #[test]
+    fn test_update_object() {
+        let mut w = World::new(vec![Sphere::default_boxed()], None);
+        w.update_object(0, |s| {
+            s.set_transform(translation(0., 0., 10.));
+            s.material_mut().set_color(C![0., 1., 0.]);
+        });
+
+        // rays find the sphere where it was moved to rather than where it was built.
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+        let xs = w.intersect(r);
+        assert_eq!(2, xs.len());
+        assert_eq!(14., xs[0].t());
+        assert_eq!(C![0., 1., 0.], xs[0].clone().object().material().color());
+    }
+
+    #[test]
+    fn test_intersect_groups() {
+        // shapes inside groups are found through the tree of boxes like any other object.
+        let mut g = Group::new(Some(translation(0., 0., 10.)), None);
+        (0..10).for_each(|i| {
+            g.add_child(Sphere::new(Some(translation(3. * i as f64, 0., 0.)), None).box_clone())
+        });
+        let w = World::new(vec![g.box_clone()], None);
+        assert_eq!(1, w.objects().len());
+
+        let r = Ray::new(P![6., 0., 0.], V![0., 0., 1.]);
+        let xs = w.intersect(r);
+        assert_eq!(2, xs.len());
+        assert!(approx_eq(9., xs[0].t()));
+        assert_eq!(
+            &(translation(0., 0., 10.) * translation(6., 0., 0.)),
+            xs[0].clone().object().transformation()
+        );
+
+        let r = Ray::new(P![7.5, 0., 0.], V![0., 0., 1.]);
+        assert_eq!(0, w.intersect(r).len());
+    }
+
+    #[test]
+    fn test_set_acceleration() {
+        // every accelerator finds the same hits as the tree of boxes, including planes which can't be split up.
+        let mut shapes = vec![Plane::new(Some(translation(0., -2., 0.)), None).box_clone()];
+        for x in 0..5 {
+            for z in 0..5 {
+                let transform = translation(3. * x as f64, 0., 3. * z as f64);
+                shapes.push(Sphere::new(Some(transform), None).box_clone());
+            }
+        }
+        let bvh = World::new(shapes, None);
+        assert_eq!(Acceleration::Bvh, bvh.acceleration());
+
+        let rays = [
+            Ray::new(P![-5., 0., -5.], V![1., 0., 1.].norm()),
+            Ray::new(P![6., 10., 6.], V![0., -1., 0.]),
+            Ray::new(P![-5., 0.5, 3.], V![1., 0., 0.]),
+            Ray::new(P![1.5, 10., 1.5], V![0., -1., 0.]),
+            Ray::new(P![20., 3., 20.], V![-1., -0.2, -0.9].norm()),
+        ];
+        let ts = |w: &World, r| w.intersect(r).iter().map(|i| i.t()).collect::<Vec<_>>();
+        for acceleration in [
+            Acceleration::Naive,
+            Acceleration::Grid,
+            Acceleration::KdTree,
+        ] {
+            let mut w = bvh.clone();
+            w.set_acceleration(acceleration);
+            assert_eq!(acceleration, w.acceleration());
+            for r in rays {
+                assert_eq!(ts(&bvh, r), ts(&w, r), "{:?} {:?}", acceleration, r);
+            }
+        }
+    }
+
+    #[test]
+    fn test_set_triangle_intersection() {
+        // two faces sharing an edge, with a ray aimed at the edge that Möller–Trumbore lets through.
+        let (a, b) = (P![1000.3, 20.7, 500.1], P![1003.9, 27.1, 499.3]);
+        let (c, d) = (P![1000., 29., 498.], P![1004., 19., 501.]);
+        let mesh = Mesh::new(vec![a, b, c, d], vec![[0, 1, 2], [1, 0, 3]], None, None);
+        let origin = P![995.1, 21.3, 480.7];
+        let r = Ray::new(origin, a + (b - a) * 0.00289 - origin);
+
+        let mut g = Group::new(None, None);
+        g.add_child(mesh.box_clone());
+        let mut w = World::new(vec![g.box_clone()], None);
+        assert_eq!(0, w.intersect(r).len());
+
+        // the setting reaches meshes inside groups, and objects added afterwards.
+        let mut settings = w.settings().clone();
+        settings.set_triangle_intersection(TriangleIntersection::Watertight);
+        w.set_settings(settings);
+        assert!(!w.intersect(r).is_empty());
+
+        let mut w = World::new(vec![], None);
+        w.set_triangle_intersection(TriangleIntersection::Watertight);
+        w.add_object(mesh.box_clone());
+        assert!(!w.intersect(r).is_empty());
+    }
+
+    #[test]
+    fn test_intersect_skips_missed_bounds() {
+        let w = World::default();
+
+        // both spheres are behind the ray so their boxes are missed and they aren't tested.
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., -1.]);
+        assert_eq!(0, w.intersect(r).len());
+
+        let r = Ray::new(P![0., 2., -5.], V![0., 0., 1.]);
+        assert_eq!(0, w.intersect(r).len());
+    }
+
     #[test]
     fn test_shade_hit() {
         // normal intersection
@@ -181,7 +1038,7 @@ mod test_world {
 
         // shading and intersection from the inside
         let mut w = World::default();
-        w.set_light(PointLight::new(P![0., 0.25, 0.], Color::WHITE));
+        w.set_light(PointLight::new(P![0., 0.25, 0.], Color::WHITE).box_clone());
         let r = Ray::new(P![0., 0., 0.], V![0., 0., 1.]);
         let shape = w.objects()[1].clone();
         let i = Intersection::new(0.5, shape);
@@ -195,7 +1052,10 @@ mod test_world {
         let s1 = Sphere::default();
         let mut s2 = Sphere::default();
         s2.set_transform(translation(0., 0., 10.));
-        let w = World::new(vec![s1.box_clone(), s2.box_clone()], Some(light));
+        let w = World::new(
+            vec![s1.box_clone(), s2.box_clone()],
+            Some(light.box_clone()),
+        );
         let ray = Ray::new(P![0., 0., 5.], V![0., 0., 1.]);
         let i = Intersection::new(4., s2.box_clone());
         let comps = i.prepare_computations(ray);
@@ -229,8 +1089,8 @@ mod test_world {
         let color = Color::WHITE;
         let m2 = Material::new(color, 1., 9.9, 0.9, 200.0, None);
         let s2 = Sphere::new(Some(tr), Some(m2));
-        let light = Some(PointLight::new(P!(-10., 10., -10.), Color::WHITE));
-        let w = World::new(vec![Box::new(s1), Box::new(s2)], light);
+        let light = Some(PointLight::new(P!(-10., 10., -10.), Color::WHITE).box_clone());
+        let w = World::new(vec![Arc::new(s1), Arc::new(s2)], light);
         let r = Ray::new(P!(0., 0., 0.75), V!(0., 0., -1.));
         let c = w.color_at(r);
 
@@ -261,4 +1121,460 @@ mod test_world {
         let p = Point::new(-2., 2., -2.);
         assert!(!w.is_shadowed(p));
     }
+
+    #[test]
+    fn test_emissive() {
+        // an emissive surface is bright even with no lights.
+        let mut panel = Quad::default();
+        panel.material_mut().set_emissive(C![1., 0.5, 0.]);
+        let w = World::new(vec![panel.box_clone()], None);
+        let r = Ray::new(P![0.5, 1., 0.], V![0., -1., 0.]);
+        assert_eq!(C![1., 0.5, 0.], w.color_at(r));
+    }
+
+    #[test]
+    fn test_add_emissive_lights() {
+        let mut panel = Quad::new(Some(translation(0., 4., 0.) * scaling(2., 1., 3.)), None);
+        panel.material_mut().set_emissive(Color::WHITE);
+        let floor = Plane::default();
+        let mut w = World::new(vec![panel.box_clone(), floor.box_clone()], None);
+
+        assert_eq!(1, w.add_emissive_lights());
+        let light = w.light().unwrap();
+        assert_eq!(Some(P![0., 4., 0.]), light.position());
+        assert_eq!(
+            AreaLight::new(
+                P![-2., 4., -3.],
+                V![4., 0., 0.],
+                V![0., 0., 6.],
+                Color::WHITE
+            )
+            .box_clone()
+            .as_ref(),
+            light.as_ref()
+        );
+
+        // the panel lights the floor under it without shadowing itself.
+        let r = Ray::new(P![0., 1., -1.], V![0., -1., 1.].norm());
+        assert!(w.color_at(r).luminance() > 0.1);
+    }
+
+    #[test]
+    fn test_light_samples() {
+        let light = AreaLight::new(
+            P![-1., 4., -1.],
+            V![2., 0., 0.],
+            V![0., 0., 2.],
+            Color::WHITE,
+        );
+        let floor = Plane::default();
+        let mut w = World::new(vec![floor.box_clone()], Some(light.box_clone()));
+        let r = Ray::new(P![0., 1., -1.], V![0., -1., 1.].norm());
+        let lit = w.color_at(r).luminance();
+
+        // a roof over x < 0 hides the half of the light on that side from the middle of the floor.
+        let roof = Quad::new(Some(translation(-1.5, 2., 0.) * scaling(1.5, 1., 2.)), None);
+        w.add_object(roof.box_clone());
+        let mut settings = w.settings().clone();
+        settings.set_light_samples(64);
+        w.set_settings(settings);
+        let ambient = Material::default().ambient();
+        let half = w.color_at(r).luminance();
+        assert!((half - (lit + ambient) / 2.).abs() < 0.05);
+    }
+
+    /// white_environment is an environment giving the same white light from every direction.
+    fn white_environment() -> Environment {
+        let mut canvas = Canvas::new(2, 2);
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            canvas.write_pixel(x, y, Color::WHITE);
+        }
+        Environment::new(UvImage::new(canvas))
+    }
+
+    #[test]
+    fn test_environment_backdrop() {
+        let mut w = World::new(vec![], None);
+        let r = Ray::new(P![0., 0., 0.], V![0., 0., 1.]);
+        assert_eq!(Color::BLACK, w.color_at(r));
+        w.set_environment(Some(white_environment()));
+        assert_eq!(Color::WHITE, w.color_at(r));
+    }
+
+    #[test]
+    fn test_environment_lighting() {
+        let floor = Plane::new(None, Some(Material::builder().ambient(0.).build().unwrap()));
+        let mut w = World::new(vec![floor.box_clone()], None);
+        w.set_environment(Some(white_environment()));
+
+        // with nothing in the way the floor gets all of the environment's light on its diffuse color.
+        let r = Ray::new(P![0., 1., 0.], V![0., -1., 0.]);
+        let lit = w.color_at(r);
+        assert!(approx_eq(Material::default().diffuse(), lit.red()));
+
+        // a sphere just above the floor hides some of the environment from the point under it.
+        let sphere = Sphere::new(Some(translation(0., 1.5, 0.)), None);
+        w.add_object(sphere.box_clone());
+        let shaded = w.color_at(Ray::new(P![0., 0.4, -1.], V![0., -0.4, 1.].norm()));
+        assert!(shaded.red() > 0. && shaded.red() < lit.red());
+    }
+
+    #[test]
+    fn test_light_linking() {
+        let left = Sphere::new(Some(translation(-2., 0., 0.)), None);
+        let right = Sphere::new(Some(translation(2., 0., 0.)), None);
+        let mut w = World::new(
+            vec![left.box_clone(), right.box_clone()],
+            Some(PointLight::new(P![0., 0., -10.], Color::WHITE).box_clone()),
+        );
+        let at_left = Ray::new(P![-2., 0., -5.], V![0., 0., 1.]);
+        let at_right = Ray::new(P![2., 0., -5.], V![0., 0., 1.]);
+        let lit = w.color_at(at_left);
+
+        w.set_light_linking(0, LightLinking::Only(HashSet::from([left.id()])));
+        assert_eq!(lit, w.color_at(at_left));
+        assert_eq!(Color::BLACK, w.color_at(at_right));
+
+        w.set_light_linking(0, LightLinking::Except(HashSet::from([left.id()])));
+        assert_eq!(Color::BLACK, w.color_at(at_left));
+        assert_eq!(lit, w.color_at(at_right));
+    }
+
+    #[test]
+    fn test_light_linking_group() {
+        let mut group = Group::new(Some(translation(-2., 0., 0.)), None);
+        group.add_child(Sphere::default().box_clone());
+        let mut w = World::new(
+            vec![
+                group.box_clone(),
+                Sphere::new(Some(translation(2., 0., 0.)), None).box_clone(),
+            ],
+            Some(PointLight::new(P![0., 0., -10.], Color::WHITE).box_clone()),
+        );
+
+        w.set_light_linking(0, LightLinking::Only(HashSet::from([group.id()])));
+        let child = w.primitives()[0].id();
+        assert_eq!(
+            &LightLinking::Only(HashSet::from([group.id(), child])),
+            w.light_linking(0)
+        );
+        assert_ne!(
+            Color::BLACK,
+            w.color_at(Ray::new(P![-2., 0., -5.], V![0., 0., 1.]))
+        );
+        assert_eq!(
+            Color::BLACK,
+            w.color_at(Ray::new(P![2., 0., -5.], V![0., 0., 1.]))
+        );
+    }
+
+    #[test]
+    fn test_light_linking_copies() {
+        // the same sphere in a group twice, so flattening gives the second copy a new id.
+        let sphere = Sphere::default();
+        let mut group = Group::new(None, None);
+        group.add_child(Sphere::new(Some(translation(-2., 0., 0.)), None).box_clone());
+        let mut w = World::new(
+            vec![
+                sphere.box_clone(),
+                group.box_clone(),
+                Sphere::new(Some(translation(2., 0., 0.)), None).box_clone(),
+            ],
+            Some(PointLight::new(P![0., 0., -10.], Color::WHITE).box_clone()),
+        );
+        let mut copy = sphere.clone();
+        copy.set_transform(translation(0., 3., 0.));
+        w.add_object(copy.box_clone());
+        let at_copy = Ray::new(P![0., 3., -5.], V![0., 0., 1.]);
+        let at_group = Ray::new(P![-2., 0., -5.], V![0., 0., 1.]);
+        let at_other = Ray::new(P![2., 0., -5.], V![0., 0., 1.]);
+        assert_ne!(sphere.id(), w.primitives()[3].id());
+
+        w.set_light_linking(
+            0,
+            LightLinking::Only(HashSet::from([sphere.id(), group.id()])),
+        );
+        let linked = w.light_linking(0).clone();
+        assert_ne!(Color::BLACK, w.color_at(at_copy));
+        assert_ne!(Color::BLACK, w.color_at(at_group));
+        assert_eq!(Color::BLACK, w.color_at(at_other));
+
+        // restyling renews the copy's id again, and the linking follows it.
+        let objects = w.objects().clone();
+        w.restyle(objects);
+        assert_ne!(&linked, w.light_linking(0));
+        assert_ne!(Color::BLACK, w.color_at(at_copy));
+        assert_ne!(Color::BLACK, w.color_at(at_group));
+        assert_eq!(Color::BLACK, w.color_at(at_other));
+    }
+
+    #[test]
+    fn test_switching_lights() {
+        let mut w = World::default();
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+        let day = w.color_at(r);
+
+        // a second light in the same place doubles the light on the sphere.
+        let second = w.add_light(PointLight::new(P![-10., 10., -10.], Color::WHITE).box_clone());
+        assert_eq!(1, second);
+        assert_eq!(day * 2., w.color_at(r));
+
+        // dimming both lights to half gives the first light alone.
+        w.set_light_dimmer(0, 0.5);
+        w.set_light_dimmer(second, 0.5);
+        assert_eq!(day, w.color_at(r));
+        assert_eq!(
+            Some(Color::new(0.5, 0.5, 0.5)),
+            w.light().map(|l| l.intensity())
+        );
+
+        // with every light off the scene is dark but the lights are kept.
+        w.set_light_enabled(0, false);
+        w.set_light_enabled(second, false);
+        assert_eq!(Color::BLACK, w.color_at(r));
+        assert_eq!(None, w.light());
+        assert!(!w.light_enabled(0));
+
+        w.set_light_enabled(0, true);
+        w.set_light_dimmer(0, 1.);
+        assert_eq!(day, w.color_at(r));
+    }
+
+    #[test]
+    #[should_panic(expected = "can't be negative")]
+    fn test_negative_dimmer() {
+        World::default().set_light_dimmer(0, -1.);
+    }
+
+    #[test]
+    fn test_objects_without_shadows() {
+        // the outer sphere of the default world is the only thing between this point and the light.
+        let mut w = World::default();
+        let p = Point::new(10., -10., 10.);
+        w.update_object(0, |s| s.material_mut().set_casts_shadow(false));
+        assert!(w.is_shadowed(p));
+
+        // with neither sphere casting shadows the light reaches the point.
+        w.update_object(1, |s| s.material_mut().set_casts_shadow(false));
+        assert!(!w.is_shadowed(p));
+    }
+
+    /// reflective_plane returns the default world with a half reflective plane below the spheres.
+    fn reflective_plane() -> (World, Plane) {
+        let mut w = World::default();
+        let plane = Plane::new(
+            Some(translation(0., -1., 0.)),
+            Some(Material::builder().reflective(0.5).build().unwrap()),
+        );
+        w.add_object(plane.box_clone());
+        (w, plane)
+    }
+
+    /// glass_floor is the default world over a half transparent floor, with a red ball beneath the floor.
+    fn glass_floor() -> World {
+        let mut w = World::default();
+        let floor = Plane::new(
+            Some(translation(0., -1., 0.)),
+            Some(
+                Material::builder()
+                    .transparency(0.5)
+                    .refractive_index(1.5)
+                    .build()
+                    .unwrap(),
+            ),
+        );
+        let ball = Sphere::new(
+            Some(translation(0., -3.5, -0.5)),
+            Some(
+                Material::builder()
+                    .color(C![1., 0., 0.])
+                    .ambient(0.5)
+                    .build()
+                    .unwrap(),
+            ),
+        );
+        w.add_object(floor.box_clone());
+        w.add_object(ball.box_clone());
+        w
+    }
+
+    #[test]
+    fn test_refracted_color() {
+        let sqrt_2_2 = 2.0_f64.sqrt() / 2.0;
+
+        // an opaque surface lets nothing through.
+        let w = World::default();
+        let r = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+        let xs = w.intersect(r);
+        let comps = xs[0].prepare_computations_with_intersections(r, &xs, EPSILON);
+        assert_eq!(Color::BLACK, w.refracted_color(&comps, DEFAULT_MAX_DEPTH));
+
+        // total internal reflection, looking up at the surface of a glass sphere from inside it.
+        let mut w = World::default();
+        w.update_object(0, |object| {
+            object.set_material(
+                Material::builder()
+                    .transparency(1.)
+                    .refractive_index(1.5)
+                    .build()
+                    .unwrap(),
+            )
+        });
+        let r = Ray::new(P![0., 0., sqrt_2_2], V![0., 1., 0.]);
+        let xs = w.intersect(r);
+        let hit = xs.iter().find(|i| approx_eq(i.t(), sqrt_2_2)).unwrap();
+        let comps = hit.prepare_computations_with_intersections(r, &xs, EPSILON);
+        assert_eq!(Color::BLACK, w.refracted_color(&comps, DEFAULT_MAX_DEPTH));
+
+        // the ball is seen through the floor.
+        let w = glass_floor();
+        let r = Ray::new(P![0., 0., -3.], V![0., -sqrt_2_2, sqrt_2_2]);
+        assert_eq!(C![0.93642, 0.68642, 0.68642], w.color_at(r));
+    }
+
+    #[test]
+    fn test_secondary_rays() {
+        let sqrt_2_2 = 2.0_f64.sqrt() / 2.0;
+        let r = Ray::new(P![0., 0., -3.], V![0., -sqrt_2_2, sqrt_2_2]);
+        let with = |mut w: World, reflections, refractions| {
+            let mut settings = w.settings().clone();
+            settings.set_reflections(reflections);
+            settings.set_refractions(refractions);
+            w.set_settings(settings);
+            w.color_at(r)
+        };
+
+        // without refraction the ball under the glass floor disappears, leaving the lit floor.
+        let lit_floor = with(glass_floor(), SecondaryRays::Traced, SecondaryRays::Black);
+        assert_eq!(C![0.68642, 0.68642, 0.68642], lit_floor);
+        // or the floor shows its own white color where it is transparent.
+        assert_eq!(
+            lit_floor + C![0.5, 0.5, 0.5],
+            with(
+                glass_floor(),
+                SecondaryRays::Traced,
+                SecondaryRays::SurfaceColor
+            )
+        );
+
+        // reflections are switched off the same way.
+        let (w, _) = reflective_plane();
+        let reflected = with(w.clone(), SecondaryRays::Traced, SecondaryRays::Traced);
+        let unreflected = with(w.clone(), SecondaryRays::Black, SecondaryRays::Traced);
+        assert_eq!(C![0.19033, 0.23792, 0.14275], reflected - unreflected);
+        assert_eq!(
+            unreflected + C![0.5, 0.5, 0.5],
+            with(w, SecondaryRays::SurfaceColor, SecondaryRays::Traced)
+        );
+    }
+
+    #[test]
+    fn test_default_max_depth() {
+        let mut w = World::default();
+        assert_eq!(DEFAULT_MAX_DEPTH, w.settings().max_depth());
+
+        w.set_max_depth(2);
+        assert_eq!(2, w.settings().max_depth());
+    }
+
+    #[test]
+    fn test_reflected_color() {
+        // a non reflective material
+        let w = World::default();
+        let r = Ray::new(P![0., 0., 0.], V![0., 0., 1.]);
+        let shape = w.objects()[1].clone();
+        let i = Intersection::new(1., shape);
+        let comps = i.prepare_computations(r);
+
+        assert_eq!(Color::BLACK, w.reflected_color(&comps, DEFAULT_MAX_DEPTH));
+
+        // a reflective material
+        let (w, plane) = reflective_plane();
+        let sqrt_2_2 = 2.0_f64.sqrt() / 2.0;
+        let r = Ray::new(P![0., 0., -3.], V![0., -sqrt_2_2, sqrt_2_2]);
+        let i = Intersection::new(2.0_f64.sqrt(), plane.box_clone());
+        let comps = i.prepare_computations(r);
+
+        assert_eq!(
+            C![0.19033, 0.23792, 0.14275],
+            w.reflected_color(&comps, DEFAULT_MAX_DEPTH)
+        );
+
+        // no reflections are left
+        let comps = i.prepare_computations(r);
+        assert_eq!(Color::BLACK, w.reflected_color(&comps, 0));
+    }
+
+    #[test]
+    fn test_reflection_blur() {
+        let blurred = Material::builder()
+            .reflective(0.5)
+            .reflection_blur(0.3)
+            .build()
+            .unwrap();
+        let sqrt_2_2 = 2.0_f64.sqrt() / 2.0;
+        let r = Ray::new(P![0., 0., -3.], V![0., -sqrt_2_2, sqrt_2_2]);
+        let (w, plane) = reflective_plane();
+        let sharp = w.reflected_color(
+            &Intersection::new(2.0_f64.sqrt(), plane.box_clone()).prepare_computations(r),
+            DEFAULT_MAX_DEPTH,
+        );
+
+        // a blurred floor averages rays around the mirror direction, the same way every time.
+        let plane = Plane::new(Some(translation(0., -1., 0.)), Some(blurred.clone()));
+        let comps = Intersection::new(2.0_f64.sqrt(), plane.box_clone()).prepare_computations(r);
+        let color = w.reflected_color(&comps, DEFAULT_MAX_DEPTH);
+        assert_ne!(sharp, color);
+        assert_eq!(color, w.reflected_color(&comps, DEFAULT_MAX_DEPTH));
+
+        // the blur is only used on planar shapes.
+        let mut w = World::default();
+        let sphere = Sphere::new(Some(translation(0., 0., 5.)), Some(blurred));
+        w.add_object(sphere.box_clone());
+        let r = Ray::new(P![0., 0., 0.], V![0., 0., 1.]);
+        let comps = Intersection::new(4., sphere.box_clone()).prepare_computations(r);
+        let mut sharp_sphere = sphere;
+        sharp_sphere.set_material(Material::builder().reflective(0.5).build().unwrap());
+        let sharp_comps = Intersection::new(4., sharp_sphere.box_clone()).prepare_computations(r);
+        assert_eq!(
+            w.reflected_color(&sharp_comps, DEFAULT_MAX_DEPTH),
+            w.reflected_color(&comps, DEFAULT_MAX_DEPTH)
+        );
+    }
+
+    #[test]
+    fn test_shade_hit_reflective() {
+        let (w, plane) = reflective_plane();
+        let sqrt_2_2 = 2.0_f64.sqrt() / 2.0;
+        let r = Ray::new(P![0., 0., -3.], V![0., -sqrt_2_2, sqrt_2_2]);
+        let i = Intersection::new(2.0_f64.sqrt(), plane.box_clone());
+        let comps = i.prepare_computations(r);
+
+        assert_eq!(C![0.87676, 0.92434, 0.82917], w.shade_hit(comps));
+    }
+
+    #[test]
+    fn test_color_at_mutually_reflective() {
+        // two parallel mirrors should not recurse forever
+        let mirror = Material::builder().reflective(1.).build().unwrap();
+        let lower = Plane::new(Some(translation(0., -1., 0.)), Some(mirror.clone()));
+        let upper = Plane::new(Some(translation(0., 1., 0.)), Some(mirror));
+        let light = PointLight::new(P![0., 0., 0.], Color::WHITE);
+        let w = World::new(
+            vec![lower.box_clone(), upper.box_clone()],
+            Some(light.box_clone()),
+        );
+        let r = Ray::new(P![0., 0., 0.], V![0., 1., 0.]);
+
+        let c = w.color_at(r);
+        assert!(c.red() > 0.);
+
+        // with no reflections allowed only the surface itself is lit, which is warned about.
+        let mut w = w;
+        assert_eq!(
+            vec!["max depth is 0 so reflective surfaces will not show any reflections".to_string()],
+            w.set_max_depth(0)
+        );
+        assert_eq!(C![1.9, 1.9, 1.9], w.color_at(r));
+    }
 }
@@ -0,0 +1,106 @@
+use crate::{primatives::point::Point, shapes::BoxedShape};
+
+/// Pick is what is under a pixel of the camera, for selecting objects in a preview by clicking on them.
+#[derive(Debug, Clone)]
+pub struct Pick {
+    /// primitive is the index into the world's primitives of the shape that was hit.
+    pub primitive: usize,
+    /// object is the primitive that was hit. Shapes inside groups are picked on their own, and a hit on one face
+    /// of a mesh picks the whole mesh.
+    pub object: BoxedShape,
+    pub t: f64,
+    pub point: Point,
+    /// face is the triangle that was hit when the object is a mesh, as every model the importers load is.
+    pub face: Option<FaceHit>,
+}
+
+/// FaceHit is where on a mesh a ray hits: the index of the face and the barycentric coordinates of the hit,
+/// running from the face's first corner towards its second (u) and third (v).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FaceHit {
+    pub index: usize,
+    pub u: f64,
+    pub v: f64,
+}
+
+#[cfg(test)]
+mod test_pick {
+    use crate::{
+        comparison::approx_eq,
+        importers::obj::ObjImport,
+        primatives::{
+            transformation::{translation, view_transformation},
+            tuple::Tuple,
+        },
+        shapes::{group::Group, mesh::Mesh, sphere::Sphere, Shape, ShapeBase},
+        world::{camera::Camera, World},
+        P, V,
+    };
+
+    fn camera() -> Camera {
+        let mut c = Camera::new(11, 11, 0.5);
+        c.set_transform(view_transformation(
+            P![0., 0., -5.],
+            P![0., 0., 0.],
+            V![0., 1., 0.],
+        ));
+        c
+    }
+
+    #[test]
+    fn test_pick_mesh_face() {
+        // two faces making a square, moved so the middle of the view is inside the first face.
+        let mesh = Mesh::new(
+            vec![
+                P![-1., -1., 0.],
+                P![-1., 1., 0.],
+                P![1., 1., 0.],
+                P![1., -1., 0.],
+            ],
+            vec![[0, 2, 1], [0, 3, 2]],
+            Some(translation(0.5, -0.5, 0.)),
+            None,
+        );
+        let id = mesh.id();
+        let w = World::new(vec![mesh.box_clone()], None);
+
+        let pick = w.pick(5, 5, &camera()).unwrap();
+        assert_eq!(id, pick.object.id());
+        assert!(approx_eq(5., pick.t));
+        assert_eq!(P![0., 0., 0.], pick.point);
+        let face = pick.face.unwrap();
+        assert_eq!(0, face.index);
+        assert!(approx_eq(0.25, face.u) && approx_eq(0.5, face.v));
+
+        // pixels off the square miss it.
+        assert!(w.pick(0, 0, &camera()).is_none());
+    }
+
+    #[test]
+    fn test_pick_imported_model() {
+        // a square face is split into two triangles of one mesh, so picking it says which half was hit.
+        let import = ObjImport::parse("v -1 -1 0\nv 1 -1 0\nv 1 1 0\nv -1 1 0\nf 1 2 3 4").unwrap();
+        let w = World::new(vec![import.to_group().box_clone()], None);
+
+        let pick = w.pick(7, 7, &camera()).unwrap();
+        assert!(pick.object.as_any().is::<Mesh>());
+        assert_eq!(0, pick.face.unwrap().index);
+        let pick = w.pick(3, 3, &camera()).unwrap();
+        assert_eq!(1, pick.face.unwrap().index);
+    }
+
+    #[test]
+    fn test_pick_shape_in_group() {
+        let mut g = Group::new(Some(translation(0., 0., 1.)), None);
+        let mut sphere = Sphere::default();
+        sphere.set_name("ball");
+        g.add_child(sphere.box_clone());
+        let w = World::new(vec![g.box_clone()], None);
+
+        let pick = w.pick(5, 5, &camera()).unwrap();
+        assert_eq!(Some("ball"), pick.object.name());
+        assert_eq!(0, pick.primitive);
+        assert!(approx_eq(5., pick.t));
+        assert!(pick.face.is_none());
+    }
+}
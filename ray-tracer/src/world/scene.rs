@@ -0,0 +1,241 @@
+use std::{io, io::Write, time::Duration};
+
+use super::{
+    camera::Camera,
+    canvas::{Canvas, CanvasError, SaveOptions},
+    depth::DepthMap,
+    settings::RenderSettings,
+    World,
+};
+
+/// SceneMetadata describes a scene for people rather than for the renderer.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SceneMetadata {
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// Scene is everything needed to render an image: the world, the cameras looking at it,
+/// the settings to shade it with and a description of what it is.
+/// The settings are the world's own, so changing them through the scene or through its world is the same thing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    world: World,
+    cameras: Vec<Camera>,
+    metadata: SceneMetadata,
+}
+
+impl Scene {
+    /// new makes a scene with a single camera.
+    pub fn new(world: World, camera: Camera) -> Self {
+        Self {
+            world,
+            cameras: vec![camera],
+            metadata: SceneMetadata::default(),
+        }
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn cameras(&self) -> &Vec<Camera> {
+        &self.cameras
+    }
+
+    pub fn camera(&self, index: usize) -> Option<&Camera> {
+        self.cameras.get(index)
+    }
+
+    pub fn add_camera(&mut self, camera: Camera) {
+        self.cameras.push(camera);
+    }
+
+    pub fn settings(&self) -> &RenderSettings {
+        self.world.settings()
+    }
+
+    /// set_settings replaces the world's render settings, see World::set_settings.
+    pub fn set_settings(&mut self, settings: RenderSettings) {
+        self.world.set_settings(settings);
+    }
+
+    pub fn metadata(&self) -> &SceneMetadata {
+        &self.metadata
+    }
+
+    pub fn set_metadata(&mut self, metadata: SceneMetadata) {
+        self.metadata = metadata;
+    }
+
+    /// render draws the scene through the camera at the given index.
+    pub fn render(&self, camera: usize) -> Canvas {
+        self.expect_camera(camera).render(self.world.clone())
+    }
+
+    /// render_parallel draws the scene through the camera at the given index using several threads.
+    pub fn render_parallel(&self, camera: usize, threads: usize) -> Canvas {
+        self.expect_camera(camera)
+            .render_parallel(self.world.clone(), threads)
+    }
+
+    /// render_to_with_options draws the scene through the camera at the given index and writes it to out as a ppm
+    /// file, see Camera::render_to_with_options.
+    pub fn render_to_with_options(
+        &self,
+        camera: usize,
+        threads: usize,
+        out: &mut dyn Write,
+        options: &SaveOptions,
+    ) -> io::Result<()> {
+        self.expect_camera(camera)
+            .render_to_with_options(&self.world, threads, out, options)
+    }
+
+    /// render_progressive draws the best image of the scene it can within the budget, see Camera::render_progressive.
+    pub fn render_progressive(
+        &self,
+        camera: usize,
+        threads: usize,
+        budget: Duration,
+    ) -> Result<Canvas, CanvasError> {
+        self.expect_camera(camera)
+            .render_progressive(&self.world, threads, budget)
+    }
+
+    /// render_adaptive draws the scene with extra samples along edges, see Camera::render_adaptive.
+    pub fn render_adaptive(
+        &self,
+        camera: usize,
+        threads: usize,
+        threshold: f64,
+    ) -> Result<Canvas, CanvasError> {
+        self.expect_camera(camera)
+            .render_adaptive(&self.world, threads, threshold)
+    }
+
+    /// render_depth finds the depth of the scene seen through the camera at the given index, see Camera::render_depth.
+    pub fn render_depth(&self, camera: usize) -> Result<DepthMap, CanvasError> {
+        self.expect_camera(camera).render_depth(&self.world)
+    }
+
+    /// into_parts takes the scene apart into its world, which keeps the settings, and its cameras.
+    pub fn into_parts(self) -> (World, Vec<Camera>) {
+        (self.world, self.cameras)
+    }
+
+    fn expect_camera(&self, camera: usize) -> &Camera {
+        self.camera(camera).unwrap_or_else(|| {
+            panic!(
+                "trying to render with camera {} but the scene only has {}",
+                camera,
+                self.cameras.len()
+            )
+        })
+    }
+}
+
+impl From<(World, Camera)> for Scene {
+    fn from((world, camera): (World, Camera)) -> Self {
+        Self::new(world, camera)
+    }
+}
+
+#[cfg(test)]
+mod test_scene {
+    use std::f64::consts::PI;
+
+    use crate::{
+        primatives::{
+            transformation::{scaling, view_transformation},
+            tuple::Tuple,
+        },
+        P, V,
+    };
+
+    use super::*;
+
+    fn test_camera() -> Camera {
+        let mut c = Camera::new(11, 11, PI / 2.);
+        c.set_transform(view_transformation(
+            P![0., 0., -5.],
+            P![0., 0., 0.],
+            V![0., 1., 0.],
+        ));
+        c
+    }
+
+    #[test]
+    fn test_new() {
+        let mut world = World::default();
+        world.set_max_depth(2);
+        let scene = Scene::new(world.clone(), test_camera());
+
+        assert_eq!(&world, scene.world());
+        assert_eq!(1, scene.cameras().len());
+        assert_eq!(2, scene.settings().max_depth());
+        assert_eq!(&SceneMetadata::default(), scene.metadata());
+    }
+
+    #[test]
+    fn test_from_tuple() {
+        let scene: Scene = (World::default(), test_camera()).into();
+        assert_eq!(Some(&test_camera()), scene.camera(0));
+        assert_eq!(None, scene.camera(1));
+    }
+
+    #[test]
+    fn test_render() {
+        let mut scene = Scene::new(World::default(), test_camera());
+        scene.add_camera(Camera::new(3, 2, PI / 2.));
+
+        let expected = test_camera().render(World::default());
+        assert_eq!(expected, scene.render(0));
+        assert_eq!(expected, scene.render_parallel(0, 2));
+        assert_eq!(3, scene.render(1).width());
+        assert_eq!(
+            test_camera().render_depth(&World::default()).unwrap(),
+            scene.render_depth(0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_settings_apply_to_world() {
+        let mut scene = Scene::new(World::default(), test_camera());
+        let mut settings = scene.settings().clone();
+        settings.set_max_depth(0);
+        scene.set_settings(settings);
+        assert_eq!(0, scene.world().settings().max_depth());
+
+        // the scene has no settings of its own to hide a change made to its world.
+        scene.world_mut().set_max_depth(3);
+        assert_eq!(3, scene.settings().max_depth());
+
+        let (world, cameras) = scene.into_parts();
+        assert_eq!(3, world.settings().max_depth());
+        assert_eq!(1, cameras.len());
+    }
+
+    #[test]
+    fn test_scene_macro() {
+        // the default world written out with scene!.
+        let scene = crate::scene! {
+            camera { width: 11, height: 11, field_of_view: PI / 2., from: (0, 0, -5), to: (0, 0, 0) }
+            light { at: (-10, 10, -10), intensity: (1, 1, 1) }
+            sphere { material: { color: (0.8, 1, 0.6), diffuse: 0.7, specular: 0.2 } }
+            sphere { transform: scaling(0.5, 0.5, 0.5) }
+        };
+
+        assert_eq!(Scene::new(World::default(), test_camera()), scene);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_render_missing_camera() {
+        Scene::new(World::default(), test_camera()).render(1);
+    }
+}
@@ -0,0 +1,218 @@
+use std::f64::consts::PI;
+
+use super::{
+    camera::Camera,
+    light::{AreaLight, LightBase},
+    scene::{Scene, SceneMetadata},
+    World,
+};
+use crate::{
+    primatives::{
+        color::Color,
+        transformation::{rotation_x, rotation_z, scaling, translation, view_transformation},
+        tuple::Tuple,
+    },
+    shapes::{material::Material, quad::Quad, sphere::Sphere, ShapeBase},
+    C, P, V,
+};
+
+/// the reflectance of the walls in the original Cornell box measurements.
+const CORNELL_WHITE: Color = Color::new(0.725, 0.71, 0.68);
+const CORNELL_RED: Color = Color::new(0.63, 0.065, 0.05);
+const CORNELL_GREEN: Color = Color::new(0.14, 0.45, 0.091);
+
+fn wall(color: Color) -> Material {
+    Material::builder()
+        .color(color)
+        .ambient(0.1)
+        .diffuse(0.9)
+        .specular(0.)
+        .build()
+        .unwrap()
+}
+
+/// cornell_box builds the standard test scene: a white room two units wide, tall and deep with a red wall
+/// on the left, a green wall on the right and a square area light just under the ceiling, so shadows are soft.
+/// The walls are quads, so the front of the box is open and the camera looks in through it.
+/// A mirrored and a matte sphere stand in for the usual blocks.
+pub fn cornell_box(hsize: usize, vsize: usize) -> Scene {
+    let floor = Quad::new(None, Some(wall(CORNELL_WHITE)));
+    let ceiling = Quad::new(Some(translation(0., 2., 0.)), Some(wall(CORNELL_WHITE)));
+    let back = Quad::new(
+        Some(translation(0., 1., 1.) * rotation_x(PI / 2.)),
+        Some(wall(CORNELL_WHITE)),
+    );
+    let left = Quad::new(
+        Some(translation(-1., 1., 0.) * rotation_z(PI / 2.)),
+        Some(wall(CORNELL_RED)),
+    );
+    let right = Quad::new(
+        Some(translation(1., 1., 0.) * rotation_z(PI / 2.)),
+        Some(wall(CORNELL_GREEN)),
+    );
+
+    let mirror = Sphere::new(
+        Some(translation(-0.4, 0.35, 0.3) * scaling(0.35, 0.35, 0.35)),
+        Some(
+            Material::builder()
+                .color(C![0.1, 0.1, 0.1])
+                .diffuse(0.1)
+                .specular(0.9)
+                .shininess(300.)
+                .reflective(0.9)
+                .build()
+                .unwrap(),
+        ),
+    );
+    let matte = Sphere::new(
+        Some(translation(0.45, 0.3, -0.2) * scaling(0.3, 0.3, 0.3)),
+        Some(wall(CORNELL_WHITE)),
+    );
+
+    let light = AreaLight::new(
+        P![-0.25, 1.99, -0.25],
+        V![0.5, 0., 0.],
+        V![0., 0., 0.5],
+        Color::WHITE,
+    );
+
+    let world = World::new(
+        vec![
+            floor.box_clone(),
+            ceiling.box_clone(),
+            back.box_clone(),
+            left.box_clone(),
+            right.box_clone(),
+            mirror.box_clone(),
+            matte.box_clone(),
+        ],
+        Some(light.box_clone()),
+    );
+
+    let mut camera = Camera::new(hsize, vsize, PI / 3.);
+    camera.set_transform(view_transformation(
+        P![0., 1., -2.7],
+        P![0., 1., 0.],
+        V![0., 1., 0.],
+    ));
+
+    let mut scene = Scene::new(world, camera);
+    scene.set_metadata(SceneMetadata {
+        name: "Cornell box".to_string(),
+        description: Some("the standard test room with a mirrored and a matte sphere".to_string()),
+    });
+    scene
+}
+
+/// russian_doll is three glass spheres inside each other: a large sphere of glass holding two smaller ones of denser
+/// material that overlap in the middle. A ray along the z axis passes through every change in refractive index.
+pub fn russian_doll() -> World {
+    let a = Sphere::glass(Some(scaling(2., 2., 2.)), 1.5);
+    let b = Sphere::glass(Some(translation(0., 0., -0.25)), 2.);
+    let c = Sphere::glass(Some(translation(0., 0., 0.25)), 2.5);
+    World::new(vec![a.box_clone(), b.box_clone(), c.box_clone()], None)
+}
+
+/// air_gap is a glass shell of radius 2 holding a pocket of air of radius 1.5, with a solid glass core of radius 1 in
+/// the middle of the air. A ray through the middle goes from air to glass and back three times.
+pub fn air_gap() -> World {
+    let shell = Sphere::glass(Some(scaling(2., 2., 2.)), 1.5);
+    let air = Sphere::glass(Some(scaling(1.5, 1.5, 1.5)), 1.);
+    let core = Sphere::glass(None, 1.5);
+    World::new(
+        vec![shell.box_clone(), air.box_clone(), core.box_clone()],
+        None,
+    )
+}
+
+#[cfg(test)]
+mod test_scenes {
+    use crate::{
+        comparison::{approx_eq, EPSILON},
+        primatives::ray::Ray,
+    };
+
+    use super::*;
+
+    /// refractive_indices are the (n1, n2) pairs at every intersection of a ray along the z axis.
+    fn refractive_indices(world: &World) -> Vec<(f64, f64)> {
+        let r = Ray::new(P![0., 0., -4.], V![0., 0., 1.]);
+        let xs = world.intersect(r);
+        xs.iter().map(|i| i.refractive_indices(&xs)).collect()
+    }
+
+    fn assert_indices(expected: &[(f64, f64)], actual: &[(f64, f64)]) {
+        assert_eq!(expected.len(), actual.len());
+        for (e, a) in expected.iter().zip(actual) {
+            assert!(
+                approx_eq(e.0, a.0) && approx_eq(e.1, a.1),
+                "expected {:?} but got {:?}",
+                expected,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn test_russian_doll() {
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+        assert_indices(&expected, &refractive_indices(&russian_doll()));
+    }
+
+    #[test]
+    fn test_air_gap() {
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 1.0),
+            (1.0, 1.5),
+            (1.5, 1.0),
+            (1.0, 1.5),
+            (1.5, 1.0),
+        ];
+        assert_indices(&expected, &refractive_indices(&air_gap()));
+
+        // the ray is shaded with the indices of the hit it sees first.
+        let world = air_gap();
+        let r = Ray::new(P![0., 0., -4.], V![0., 0., 1.]);
+        let mut xs = world.intersect(r);
+        let hit = xs.hit().cloned().unwrap();
+        let comps = hit.prepare_computations_with_intersections(r, &xs, EPSILON);
+        assert!(approx_eq(2., comps.t));
+        assert!(approx_eq(1., comps.n1));
+        assert!(approx_eq(1.5, comps.n2));
+    }
+
+    #[test]
+    fn test_cornell_box() {
+        let scene = cornell_box(21, 21);
+        assert_eq!(7, scene.world().objects().len());
+        assert_eq!("Cornell box", scene.metadata().name);
+        let light = scene.world().light().unwrap();
+        assert!(light.as_any().is::<AreaLight>());
+        assert!(scene
+            .world()
+            .objects()
+            .iter()
+            .take(5)
+            .all(|wall| wall.as_any().is::<Quad>()));
+
+        let canvas = scene.render(0);
+
+        // the left wall is red and the right wall is green.
+        let left = canvas.pixel_at(0, 10).unwrap();
+        let right = canvas.pixel_at(20, 10).unwrap();
+        assert!(left.red() > left.green());
+        assert!(right.green() > right.red());
+
+        // the floor is lit by the light above it.
+        let floor = canvas.pixel_at(10, 20).unwrap();
+        assert!(floor.red() > 0.1);
+    }
+}
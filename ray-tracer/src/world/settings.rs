@@ -0,0 +1,201 @@
+use crate::{comparison::EPSILON, shapes::triangle::TriangleIntersection};
+
+/// DEFAULT_MAX_DEPTH is how many times a ray may bounce between reflective surfaces before giving up.
+pub const DEFAULT_MAX_DEPTH: usize = 5;
+
+/// depths above this are allowed but rarely change the image and can be very slow to render.
+const LARGE_MAX_DEPTH: usize = 50;
+
+/// DEFAULT_MIN_HIT_DISTANCE is how far along a ray an intersection must be before it counts as a hit.
+pub const DEFAULT_MIN_HIT_DISTANCE: f64 = EPSILON;
+
+/// DEFAULT_BIAS is how far secondary rays are moved off a surface before they are cast.
+pub const DEFAULT_BIAS: f64 = EPSILON;
+
+/// DEFAULT_REFLECTION_BLUR_SAMPLES is how many reflection rays are averaged on surfaces with a blurred reflection.
+pub const DEFAULT_REFLECTION_BLUR_SAMPLES: usize = 4;
+
+/// DEFAULT_LIGHT_SAMPLES is how many times lights with an area are sampled at each point that is shaded.
+pub const DEFAULT_LIGHT_SAMPLES: usize = 1;
+
+/// SecondaryRays is what a surface shows in place of what it reflects or lets through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SecondaryRays {
+    /// Traced follows the rays into the scene.
+    #[default]
+    Traced,
+    /// Black shows nothing, leaving only the light on the surface itself.
+    Black,
+    /// SurfaceColor shows the surface's own color, so mirrors and glass still stand out in a quick draft.
+    SurfaceColor,
+}
+
+/// RenderSettings holds the options that change how a world is shaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderSettings {
+    max_depth: usize,
+    min_hit_distance: f64,
+    bias: f64,
+    reflection_blur_samples: usize,
+    light_samples: usize,
+    triangle_intersection: TriangleIntersection,
+    reflections: SecondaryRays,
+    refractions: SecondaryRays,
+}
+
+impl RenderSettings {
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn min_hit_distance(&self) -> f64 {
+        self.min_hit_distance
+    }
+
+    /// set_min_hit_distance changes how close to its origin a ray ignores intersections.
+    /// Larger values hide acne on badly scaled scenes at the cost of missing thin gaps.
+    pub fn set_min_hit_distance(&mut self, min_hit_distance: f64) {
+        assert!(
+            min_hit_distance >= 0.,
+            "the minimum hit distance cannot be negative"
+        );
+        self.min_hit_distance = min_hit_distance;
+    }
+
+    pub fn bias(&self) -> f64 {
+        self.bias
+    }
+
+    /// set_bias changes how far reflected rays start from the surface that spawned them.
+    /// Raise it if curved mirrors show acne, lower it if reflections visibly float above contact points.
+    pub fn set_bias(&mut self, bias: f64) {
+        assert!(bias >= 0., "the bias cannot be negative");
+        self.bias = bias;
+    }
+
+    pub fn reflection_blur_samples(&self) -> usize {
+        self.reflection_blur_samples
+    }
+
+    /// set_reflection_blur_samples changes how many rays are averaged for a blurred reflection.
+    /// More samples give a smoother floor but each one costs as much as a sharp reflection.
+    pub fn set_reflection_blur_samples(&mut self, samples: usize) {
+        assert!(
+            samples > 0,
+            "a blurred reflection needs at least one sample"
+        );
+        self.reflection_blur_samples = samples;
+    }
+
+    pub fn light_samples(&self) -> usize {
+        self.light_samples
+    }
+
+    /// set_light_samples changes how many times lights with an area, such as area and dome lights, are sampled at
+    /// each point. The samples are spread evenly over the light so soft shadows come out smooth with fewer of them
+    /// than random samples would need, but each one still costs a shadow ray.
+    pub fn set_light_samples(&mut self, samples: usize) {
+        assert!(samples > 0, "a light needs at least one sample");
+        self.light_samples = samples;
+    }
+
+    pub fn triangle_intersection(&self) -> TriangleIntersection {
+        self.triangle_intersection
+    }
+
+    /// set_triangle_intersection chooses how rays are tested against triangles and mesh faces.
+    /// Watertight is a little slower but stops rays slipping through the edges between faces of large meshes.
+    pub fn set_triangle_intersection(&mut self, intersection: TriangleIntersection) {
+        self.triangle_intersection = intersection;
+    }
+
+    pub fn reflections(&self) -> SecondaryRays {
+        self.reflections
+    }
+
+    /// set_reflections chooses whether reflective surfaces trace reflections. Turning them off speeds up drafts and
+    /// shows which artifacts come from reflections without editing any materials.
+    pub fn set_reflections(&mut self, reflections: SecondaryRays) {
+        self.reflections = reflections;
+    }
+
+    pub fn refractions(&self) -> SecondaryRays {
+        self.refractions
+    }
+
+    /// set_refractions chooses whether transparent surfaces trace the rays passing through them.
+    pub fn set_refractions(&mut self, refractions: SecondaryRays) {
+        self.refractions = refractions;
+    }
+
+    /// warnings returns a description of every setting that is allowed but probably a mistake.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = vec![];
+        if self.max_depth == 0 {
+            warnings.push(
+                "max depth is 0 so reflective surfaces will not show any reflections".to_string(),
+            );
+        } else if self.max_depth > LARGE_MAX_DEPTH {
+            warnings.push(format!(
+                "max depth of {} is very large and may make rendering slow",
+                self.max_depth
+            ));
+        }
+        warnings
+    }
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_DEPTH,
+            min_hit_distance: DEFAULT_MIN_HIT_DISTANCE,
+            bias: DEFAULT_BIAS,
+            reflection_blur_samples: DEFAULT_REFLECTION_BLUR_SAMPLES,
+            light_samples: DEFAULT_LIGHT_SAMPLES,
+            triangle_intersection: TriangleIntersection::default(),
+            reflections: SecondaryRays::default(),
+            refractions: SecondaryRays::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_settings {
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        let s = RenderSettings::default();
+        assert_eq!(DEFAULT_MAX_DEPTH, s.max_depth());
+        assert_eq!(DEFAULT_MIN_HIT_DISTANCE, s.min_hit_distance());
+        assert_eq!(DEFAULT_BIAS, s.bias());
+        assert_eq!(DEFAULT_REFLECTION_BLUR_SAMPLES, s.reflection_blur_samples());
+        assert_eq!(DEFAULT_LIGHT_SAMPLES, s.light_samples());
+        assert_eq!(
+            TriangleIntersection::MollerTrumbore,
+            s.triangle_intersection()
+        );
+        assert_eq!(SecondaryRays::Traced, s.reflections());
+        assert_eq!(SecondaryRays::Traced, s.refractions());
+        assert!(s.warnings().is_empty());
+    }
+
+    #[test]
+    fn test_warnings() {
+        let mut s = RenderSettings::default();
+
+        s.set_max_depth(0);
+        assert_eq!(1, s.warnings().len());
+
+        s.set_max_depth(LARGE_MAX_DEPTH + 1);
+        assert_eq!(1, s.warnings().len());
+
+        s.set_max_depth(1);
+        assert!(s.warnings().is_empty());
+    }
+}
@@ -0,0 +1,90 @@
+//! Rays that miss everything are by far the most common in open scenes, so intersecting them should not touch the
+//! heap at all. This counts every allocation made while tracing misses through each accelerator.
+//! It lives in its own test binary because it replaces the global allocator.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use ray_tracer::{
+    primatives::{accelerator::Acceleration, ray::Ray, transformation::translation, tuple::Tuple},
+    shapes::{cylinder::Cylinder, group::Group, sphere::Sphere, BoxedShape, ShapeBase},
+    world::World,
+    P, V,
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// allocations is how many allocations f makes.
+fn allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// row is a line of spheres along x with a group of cylinders at the end, spaced so rays can pass between them.
+fn row() -> Vec<BoxedShape> {
+    let mut objects: Vec<BoxedShape> = (0..40)
+        .map(|i| Sphere::new(Some(translation(3. * i as f64, 0., 0.)), None).box_clone())
+        .collect();
+    let mut group = Group::default();
+    for i in 0..4 {
+        group.add_child(
+            Cylinder::new(Some(translation(130. + 3. * i as f64, 0., 0.)), None).box_clone(),
+        );
+    }
+    objects.push(group.box_clone());
+    objects
+}
+
+// a single test, as tests running alongside on other threads would add to the count.
+#[test]
+fn test_misses_do_not_allocate() {
+    let misses = [
+        // away from everything.
+        Ray::new(P![0., 0., -5.], V![0., 0., -1.]),
+        // alongside the row.
+        Ray::new(P![-5., 0., 5.], V![1., 0., 0.]),
+        // through the bounds of the whole row, between two spheres.
+        Ray::new(P![1.5, 0., -5.], V![0., 0., 1.]),
+    ];
+    let hit = Ray::new(P![0., 0., -5.], V![0., 0., 1.]);
+
+    for acceleration in [
+        Acceleration::Naive,
+        Acceleration::Bvh,
+        Acceleration::Grid,
+        Acceleration::KdTree,
+    ] {
+        let mut world = World::new(row(), None);
+        world.set_acceleration(acceleration);
+
+        for r in misses {
+            let count = allocations(|| {
+                assert!(world.intersect(r).is_empty());
+                assert!(world.primitive_hit(r).is_none());
+            });
+            assert_eq!(0, count, "{:?} allocated for a miss {:?}", acceleration, r);
+        }
+
+        // hits still allocate for what they find.
+        assert!(allocations(|| assert_eq!(2, world.intersect(hit).len())) > 0);
+    }
+}
@@ -0,0 +1,497 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, quote_spanned};
+use syn::{
+    braced, bracketed,
+    parse::{Parse, ParseStream},
+    parse_macro_input,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    token, Expr, ExprLit, ExprUnary, Ident, Lit, LitFloat, Token, UnOp,
+};
+
+/// SHAPES are the kinds of shape a scene can hold, with the fields each has on top of `transform`, `material` and
+/// `name`.
+const SHAPES: [(&str, &[&str]); 8] = [
+    ("sphere", &[]),
+    ("plane", &[]),
+    ("quad", &[]),
+    ("cylinder", &["minimum", "maximum", "closed"]),
+    ("cone", &["minimum", "maximum", "closed"]),
+    ("torus", &["major_radius", "minor_radius"]),
+    ("disc", &["inner_radius"]),
+    ("group", &["children"]),
+];
+
+/// MATERIAL_FIELDS are the fields of `Material` that can be set, each set with the material builder.
+const MATERIAL_FIELDS: [&str; 11] = [
+    "color",
+    "ambient",
+    "diffuse",
+    "specular",
+    "shininess",
+    "pattern",
+    "reflective",
+    "reflection_blur",
+    "transparency",
+    "refractive_index",
+    "casts_shadow",
+];
+
+/// scene! builds a `Scene` from a list of cameras, lights and shapes written in Rust, as an alternative to a YAML scene
+/// file that is checked when it is compiled. Each item is its kind followed by its fields in braces:
+///
+/// ```
+/// use ray_tracer::scene;
+/// use ray_tracer::primatives::transformation::translation;
+///
+/// let scene = scene! {
+///     camera { width: 100, height: 50, field_of_view: 1.047, from: (0, 1.5, -5), to: (0, 1, 0) }
+///     light { at: (-10, 10, -10), intensity: (1, 1, 1) }
+///     plane { material: { color: (1, 0.9, 0.9), specular: 0 } }
+///     sphere {
+///         name: "middle",
+///         transform: translation(-0.5, 1., 0.5),
+///         material: { color: (0.1, 1, 0.5), diffuse: 0.7, specular: 0.3 },
+///     }
+/// };
+/// assert_eq!(2, scene.world().objects().len());
+/// ```
+///
+/// Points, vectors and colors can be written as a tuple of three numbers, and numbers can be integers. Any other
+/// Rust expression can be used as a value too, such as a `Material` for `material` or a `Matrix` for `transform`.
+/// A camera looks along z unless it has `from` and `to`, with `up` being y unless it is given. Cylinders and cones
+/// take `minimum`, `maximum` and `closed`, a torus `major_radius` and `minor_radius`, a disc `inner_radius` and
+/// a group a list of shapes as its `children`.
+///
+/// Unknown kinds and fields, fields given twice, missing fields and a scene without a camera are all compile errors.
+/// The first camera and light are the scene's and world's own, the others are added after them.
+#[proc_macro]
+pub fn scene(input: TokenStream) -> TokenStream {
+    let items = parse_macro_input!(input as Items);
+    match expand(&items.0) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+/// Items are the items of a scene or the children of a group, optionally separated by commas.
+struct Items(Vec<Item>);
+
+impl Parse for Items {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut items = vec![];
+        while !input.is_empty() {
+            items.push(input.parse()?);
+            if input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        Ok(Items(items))
+    }
+}
+
+/// Item is one camera, light or shape, such as `sphere { transform: scaling(2., 2., 2.) }`.
+struct Item {
+    kind: Ident,
+    fields: Vec<Field>,
+}
+
+impl Parse for Item {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let kind = input.parse()?;
+        let content;
+        braced!(content in input);
+        Ok(Item {
+            kind,
+            fields: fields(&content)?,
+        })
+    }
+}
+
+struct Field {
+    name: Ident,
+    value: Value,
+}
+
+enum Value {
+    Expr(Box<Expr>),
+    /// Material is a material written in place as its fields in braces.
+    Material(Vec<Field>),
+    /// Children are the shapes of a group, in square brackets.
+    Children(Vec<Item>),
+}
+
+impl Parse for Field {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name: Ident = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let value = if name == "material" && input.peek(token::Brace) {
+            let content;
+            braced!(content in input);
+            Value::Material(fields(&content)?)
+        } else if name == "children" && input.peek(token::Bracket) {
+            let content;
+            bracketed!(content in input);
+            Value::Children(content.parse::<Items>()?.0)
+        } else {
+            Value::Expr(Box::new(input.parse()?))
+        };
+        Ok(Field { name, value })
+    }
+}
+
+fn fields(input: ParseStream) -> syn::Result<Vec<Field>> {
+    Ok(Punctuated::<Field, Token![,]>::parse_terminated(input)?
+        .into_iter()
+        .collect())
+}
+
+/// Fields are the fields of an item, checked against the ones that kind of item can have.
+struct Fields<'a> {
+    kind: &'a Ident,
+    fields: &'a [Field],
+}
+
+impl<'a> Fields<'a> {
+    fn new(kind: &'a Ident, fields: &'a [Field], allowed: &[&str]) -> syn::Result<Self> {
+        for (i, field) in fields.iter().enumerate() {
+            let name = field.name.to_string();
+            if !allowed.contains(&name.as_str()) {
+                return Err(syn::Error::new(
+                    field.name.span(),
+                    format!(
+                        "a {} has no `{}`, expected one of {}",
+                        kind,
+                        name,
+                        allowed.join(", ")
+                    ),
+                ));
+            }
+            if fields[..i].iter().any(|other| other.name == field.name) {
+                return Err(syn::Error::new(
+                    field.name.span(),
+                    format!("`{}` is given more than once", name),
+                ));
+            }
+        }
+        Ok(Self { kind, fields })
+    }
+
+    fn get(&self, name: &str) -> Option<&'a Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    fn expr(&self, name: &str) -> syn::Result<Option<&'a Expr>> {
+        match self.get(name) {
+            None => Ok(None),
+            Some(Field {
+                value: Value::Expr(expr),
+                ..
+            }) => Ok(Some(expr.as_ref())),
+            Some(field) => Err(syn::Error::new(
+                field.name.span(),
+                format!("`{}` should be a value", name),
+            )),
+        }
+    }
+
+    fn require(&self, name: &str) -> syn::Result<&'a Expr> {
+        self.expr(name)?.ok_or_else(|| {
+            syn::Error::new(
+                self.kind.span(),
+                format!("a {} needs a `{}`", self.kind, name),
+            )
+        })
+    }
+}
+
+fn expand(items: &[Item]) -> syn::Result<TokenStream2> {
+    let (mut cameras, mut lights, mut objects) = (vec![], vec![], vec![]);
+    for item in items {
+        match item.kind.to_string().as_str() {
+            "camera" => cameras.push(camera(item)?),
+            "light" => lights.push(light(item)?),
+            _ => objects.push(shape(item)?),
+        }
+    }
+    if cameras.is_empty() {
+        return Err(syn::Error::new(Span::call_site(), "a scene needs a camera"));
+    }
+
+    Ok(quote! {
+        {
+            let mut __lights = ::std::vec![#(#lights),*].into_iter();
+            #[allow(unused_mut)]
+            let mut __world = ::ray_tracer::world::World::new(
+                ::std::vec![#(#objects),*],
+                __lights.next(),
+            );
+            __lights.for_each(|light| {
+                __world.add_light(light);
+            });
+            let mut __cameras = ::std::vec![#(#cameras),*].into_iter();
+            #[allow(unused_mut)]
+            let mut __scene = ::ray_tracer::world::scene::Scene::new(
+                __world,
+                __cameras.next().unwrap(),
+            );
+            __cameras.for_each(|camera| __scene.add_camera(camera));
+            __scene
+        }
+    })
+}
+
+fn camera(item: &Item) -> syn::Result<TokenStream2> {
+    let fields = Fields::new(
+        &item.kind,
+        &item.fields,
+        &["width", "height", "field_of_view", "from", "to", "up"],
+    )?;
+    let width = fields.require("width")?;
+    let height = fields.require("height")?;
+    let field_of_view = number(fields.require("field_of_view")?);
+
+    let view = if ["from", "to", "up"]
+        .iter()
+        .any(|name| fields.get(name).is_some())
+    {
+        let from = tuple(fields.require("from")?, point())?;
+        let to = tuple(fields.require("to")?, point())?;
+        let up = match fields.expr("up")? {
+            Some(up) => tuple(up, vector())?,
+            None => {
+                let vector = vector();
+                quote! { #vector(0., 1., 0.) }
+            }
+        };
+        quote! {
+            __camera.set_transform(
+                ::ray_tracer::primatives::transformation::view_transformation(#from, #to, #up)
+            );
+        }
+    } else {
+        quote! {}
+    };
+
+    Ok(quote! {
+        {
+            #[allow(unused_mut)]
+            let mut __camera = ::ray_tracer::world::camera::Camera::new(#width, #height, #field_of_view);
+            #view
+            __camera
+        }
+    })
+}
+
+fn light(item: &Item) -> syn::Result<TokenStream2> {
+    let fields = Fields::new(&item.kind, &item.fields, &["at", "intensity"])?;
+    let at = tuple(fields.require("at")?, point())?;
+    let intensity = tuple(fields.require("intensity")?, color())?;
+    Ok(quote! {
+        ::ray_tracer::world::light::LightBase::box_clone(
+            &::ray_tracer::world::light::PointLight::new(#at, #intensity),
+        )
+    })
+}
+
+fn shape(item: &Item) -> syn::Result<TokenStream2> {
+    let kind = item.kind.to_string();
+    let Some((_, extra)) = SHAPES.iter().find(|(name, _)| *name == kind) else {
+        let kinds: Vec<&str> = ["camera", "light"]
+            .into_iter()
+            .chain(SHAPES.iter().map(|(name, _)| *name))
+            .collect();
+        return Err(syn::Error::new(
+            item.kind.span(),
+            format!(
+                "`{}` is not something a scene can hold, expected one of {}",
+                kind,
+                kinds.join(", ")
+            ),
+        ));
+    };
+    let mut allowed = vec!["transform", "material", "name"];
+    allowed.extend(extra.iter());
+    let fields = Fields::new(&item.kind, &item.fields, &allowed)?;
+
+    let transform = match fields.expr("transform")? {
+        Some(transform) => quote! { ::std::option::Option::Some(#transform) },
+        None => quote! { ::std::option::Option::None },
+    };
+    let material = match fields.get("material").map(|field| &field.value) {
+        Some(Value::Material(material)) => {
+            let material = self::material(material)?;
+            quote! { ::std::option::Option::Some(#material) }
+        }
+        Some(_) => {
+            let material = fields.require("material")?;
+            quote! { ::std::option::Option::Some(#material) }
+        }
+        None => quote! { ::std::option::Option::None },
+    };
+
+    let mut setup = vec![];
+    match kind.as_str() {
+        "cylinder" | "cone" => {
+            if fields.get("minimum").is_some() || fields.get("maximum").is_some() {
+                let minimum = fields
+                    .expr("minimum")?
+                    .map(number)
+                    .unwrap_or(quote! { f64::NEG_INFINITY });
+                let maximum = fields
+                    .expr("maximum")?
+                    .map(number)
+                    .unwrap_or(quote! { f64::INFINITY });
+                setup.push(quote! { __shape.set_limits(#minimum, #maximum); });
+            }
+            if let Some(closed) = fields.expr("closed")? {
+                setup.push(quote! { __shape.set_closed(#closed); });
+            }
+        }
+        "torus" if fields.get("major_radius").is_some() || fields.get("minor_radius").is_some() => {
+            let major = fields
+                .expr("major_radius")?
+                .map(number)
+                .unwrap_or(quote! { __shape.major_radius() });
+            let minor = fields
+                .expr("minor_radius")?
+                .map(number)
+                .unwrap_or(quote! { __shape.minor_radius() });
+            setup.push(quote! { __shape.set_radii(#major, #minor); });
+        }
+        "disc" => {
+            if let Some(inner) = fields.expr("inner_radius")? {
+                let inner = number(inner);
+                setup.push(quote! { __shape.set_inner_radius(#inner); });
+            }
+        }
+        "group" => {
+            let children = match fields.get("children") {
+                Some(Field {
+                    value: Value::Children(children),
+                    ..
+                }) => children.as_slice(),
+                Some(field) => {
+                    return Err(syn::Error::new(
+                        field.name.span(),
+                        "`children` should be a list of shapes in square brackets",
+                    ))
+                }
+                None => &[],
+            };
+            for child in children {
+                if child.kind == "camera" || child.kind == "light" {
+                    return Err(syn::Error::new(
+                        child.kind.span(),
+                        format!("a {} can't be a group's child", child.kind),
+                    ));
+                }
+                let child = shape(child)?;
+                setup.push(quote! { __shape.add_child(#child); });
+            }
+        }
+        _ => {}
+    }
+    if let Some(name) = fields.expr("name")? {
+        setup.push(quote! { ::ray_tracer::shapes::Shape::set_name(&mut __shape, #name); });
+    }
+
+    let ty = Ident::new(&shape_type(&kind), item.kind.span());
+    let module = Ident::new(&kind, item.kind.span());
+    Ok(quote! {
+        {
+            #[allow(unused_mut)]
+            let mut __shape = ::ray_tracer::shapes::#module::#ty::new(#transform, #material);
+            #(#setup)*
+            ::ray_tracer::shapes::ShapeBase::box_clone(&__shape)
+        }
+    })
+}
+
+/// shape_type is the name of the type for a kind of shape, which is the kind with a capital letter.
+fn shape_type(kind: &str) -> String {
+    let mut chars = kind.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// material builds a material with the material builder, leaving every field it doesn't set at its default.
+fn material(fields: &[Field]) -> syn::Result<TokenStream2> {
+    let kind = Ident::new("material", Span::call_site());
+    let checked = Fields::new(&kind, fields, &MATERIAL_FIELDS)?;
+    let mut setters = vec![];
+    for field in fields {
+        let name = &field.name;
+        let value = checked.require(&name.to_string())?;
+        let value = match name.to_string().as_str() {
+            "color" => tuple(value, color())?,
+            "pattern" | "casts_shadow" => quote! { #value },
+            _ => number(value),
+        };
+        setters.push(quote! { __material.#name(#value); });
+    }
+    Ok(quote! {
+        {
+            #[allow(unused_mut)]
+            let mut __material = ::ray_tracer::shapes::material::Material::builder();
+            #(#setters)*
+            __material
+                .build_owned()
+                .expect("a material with defaults always builds")
+        }
+    })
+}
+
+/// number lets a number be written as an integer as well as a float, by writing integers as floats.
+fn number(expr: &Expr) -> TokenStream2 {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(int), ..
+        }) => {
+            let float = LitFloat::new(&format!("{}f64", int.base10_digits()), int.span());
+            quote! { #float }
+        }
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr: inner,
+            ..
+        }) => {
+            let inner = number(inner);
+            quote_spanned! {expr.span()=> -#inner }
+        }
+        _ => quote! { #expr },
+    }
+}
+
+/// tuple makes a point, vector or color from a tuple of three numbers with the given constructor, and leaves any
+/// other expression as it is.
+fn tuple(expr: &Expr, new: TokenStream2) -> syn::Result<TokenStream2> {
+    match expr {
+        Expr::Tuple(tuple) if tuple.elems.len() == 3 => {
+            let values = tuple.elems.iter().map(number);
+            Ok(quote! { #new(#(#values),*) })
+        }
+        Expr::Tuple(tuple) => Err(syn::Error::new(
+            tuple.span(),
+            format!("expected three values but there are {}", tuple.elems.len()),
+        )),
+        _ => Ok(quote! { #expr }),
+    }
+}
+
+fn point() -> TokenStream2 {
+    quote! { <::ray_tracer::primatives::point::Point as ::ray_tracer::primatives::tuple::Tuple>::new }
+}
+
+fn vector() -> TokenStream2 {
+    quote! { <::ray_tracer::primatives::vector::Vector as ::ray_tracer::primatives::tuple::Tuple>::new }
+}
+
+fn color() -> TokenStream2 {
+    quote! { ::ray_tracer::primatives::color::Color::new }
+}
@@ -0,0 +1,34 @@
+use ray_tracer::primatives::transformation::{scaling, translation};
+use ray_tracer::scene;
+use ray_tracer::shapes::material::Material;
+
+fn main() {
+    let glass = Material::builder()
+        .transparency(1.)
+        .refractive_index(1.5)
+        .build()
+        .unwrap();
+
+    let scene = scene! {
+        camera { width: 40, height: 20, field_of_view: 1.047, from: (0, 1.5, -5), to: (0, 1, 0) }
+        camera { width: 10, height: 10, field_of_view: 0.5 }
+        light { at: (-10, 10, -10), intensity: (1, 1, 1) }
+        light { at: (10, 10, -10), intensity: (0.2, 0.2, 0.2) }
+        plane { material: { color: (1, 0.9, 0.9), specular: 0, reflective: 0.2 } }
+        sphere { name: "glass", transform: translation(-0.5, 1., 0.5), material: glass }
+        cylinder { minimum: 0, maximum: 1, closed: true }
+        group {
+            transform: scaling(0.5, 0.5, 0.5),
+            children: [
+                cone { maximum: 0 },
+                torus { major_radius: 2, minor_radius: 0.5 },
+                disc { inner_radius: 0.5 },
+            ],
+        }
+    };
+
+    assert_eq!(2, scene.cameras().len());
+    assert_eq!(2, scene.world().lights().count());
+    assert_eq!(4, scene.world().objects().len());
+    assert_eq!(Some("glass"), scene.world().objects()[1].name());
+}
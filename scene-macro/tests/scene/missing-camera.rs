@@ -0,0 +1,8 @@
+use ray_tracer::scene;
+
+fn main() {
+    let _scene = scene! {
+        light { at: (-10, 10, -10), intensity: (1, 1, 1) }
+        sphere {}
+    };
+}
@@ -0,0 +1,7 @@
+use ray_tracer::scene;
+
+fn main() {
+    let _scene = scene! {
+        camera { width: 10, field_of_view: 1 }
+    };
+}
@@ -0,0 +1,8 @@
+use ray_tracer::scene;
+
+fn main() {
+    let _scene = scene! {
+        camera { width: 10, height: 10, field_of_view: 1 }
+        sphere { material: { colour: (1, 0, 0) } }
+    };
+}
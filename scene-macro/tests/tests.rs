@@ -0,0 +1,8 @@
+#[test]
+fn scene() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/scene/happy_path.rs");
+    t.compile_fail("tests/scene/unknown-field.rs");
+    t.compile_fail("tests/scene/missing-camera.rs");
+    t.compile_fail("tests/scene/missing-field.rs");
+}
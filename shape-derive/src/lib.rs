@@ -0,0 +1,150 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, DeriveInput};
+
+/// ShapeDerive implements `ShapeBase` for a shape in the ray tracer: `box_clone`, `box_eq`, `as_any`, `props` and
+/// `props_mut`, which are the same for every shape. The shape's transformation, material and tag all come from its
+/// `ShapeProps` field, so only what makes the shape different is left to `Shape`.
+///
+/// A struct level `#[shape(new)]` also generates `new(transform: Option<Matrix>, material: Option<Material>)`,
+/// filling every other field from its `Default` implementation.
+///
+/// The shape must be `Clone` and `PartialEq`, and have exactly one field of type `ShapeProps`.
+#[proc_macro_derive(ShapeDerive, attributes(shape))]
+pub fn derive(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    let name = &ast.ident;
+
+    let fields = if let syn::Data::Struct(syn::DataStruct {
+        fields: syn::Fields::Named(syn::FieldsNamed { ref named, .. }),
+        ..
+    }) = ast.data
+    {
+        named
+    } else {
+        return syn::Error::new_spanned(
+            &ast.ident,
+            "ShapeDerive only works on structs with named fields",
+        )
+        .into_compile_error()
+        .into();
+    };
+
+    let props = match props_field(fields) {
+        std::result::Result::Ok(props) => props,
+        std::result::Result::Err(e) => {
+            return syn::Error::new_spanned(&ast.ident, e)
+                .into_compile_error()
+                .into()
+        }
+    };
+
+    let constructor = match struct_new(&ast.attrs) {
+        std::result::Result::Ok(false) => quote! {},
+        std::result::Result::Ok(true) => {
+            let others = fields
+                .iter()
+                .filter(|f| f.ident.as_ref() != std::option::Option::Some(props))
+                .map(|f| {
+                    let name = &f.ident;
+                    quote! { #name: std::default::Default::default() }
+                });
+            quote! {
+                impl #name {
+                    pub fn new(
+                        transform: std::option::Option<crate::primatives::matrix::Matrix>,
+                        material: std::option::Option<crate::shapes::material::Material>,
+                    ) -> Self {
+                        Self {
+                            #props: crate::shapes::ShapeProps::new(transform, material),
+                            #(#others,)*
+                        }
+                    }
+                }
+            }
+        }
+        std::result::Result::Err(e) => return e.into_compile_error().into(),
+    };
+
+    let expanded = quote! {
+        impl crate::shapes::ShapeBase for #name {
+            fn box_clone(&self) -> crate::shapes::BoxedShape {
+                std::sync::Arc::new(std::clone::Clone::clone(self))
+            }
+
+            fn box_eq(&self, other: &dyn std::any::Any) -> bool {
+                other.downcast_ref::<Self>().is_some_and(|a| self == a)
+            }
+
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+
+            fn props(&self) -> &crate::shapes::ShapeProps {
+                &self.#props
+            }
+
+            fn props_mut(&mut self) -> &mut crate::shapes::ShapeProps {
+                &mut self.#props
+            }
+        }
+
+        #constructor
+    };
+
+    expanded.into()
+}
+
+/// props_field finds the name of the one field holding the shape's `ShapeProps`.
+fn props_field(
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+) -> Result<&syn::Ident, &'static str> {
+    let mut props = fields.iter().filter(|f| match &f.ty {
+        syn::Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .is_some_and(|s| s.ident == "ShapeProps"),
+        _ => false,
+    });
+    match (props.next(), props.next()) {
+        (std::option::Option::Some(f), std::option::Option::None) => {
+            std::result::Result::Ok(f.ident.as_ref().unwrap())
+        }
+        (std::option::Option::None, _) => {
+            std::result::Result::Err("a shape needs a field of type `ShapeProps`")
+        }
+        _ => std::result::Result::Err("a shape can only have one field of type `ShapeProps`"),
+    }
+}
+
+fn struct_new(attrs: &[syn::Attribute]) -> syn::Result<bool> {
+    for attr in attrs {
+        let seg = &attr.path.segments;
+        if seg.len() != 1 || seg[0].ident != "shape" {
+            continue;
+        }
+        match attr.parse_meta()? {
+            syn::Meta::List(nvs) if nvs.nested.len() == 1 => {
+                if let syn::NestedMeta::Meta(syn::Meta::Path(ref path)) = nvs.nested[0] {
+                    if path.is_ident("new") {
+                        return std::result::Result::Ok(true);
+                    }
+                }
+                return std::result::Result::Err(syn::Error::new_spanned(
+                    nvs,
+                    "expected `shape(new)`",
+                ));
+            }
+            meta => {
+                return std::result::Result::Err(syn::Error::new_spanned(
+                    meta,
+                    "expected `shape(new)`",
+                ))
+            }
+        }
+    }
+    std::result::Result::Ok(false)
+}